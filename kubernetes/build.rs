@@ -0,0 +1,11 @@
+//! Generates the messages of the Container Runtime Interface from the vendored `api.proto`, parsed with protox so that `protoc` is not needed.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+    let files = protox::compile(["runtime/v1/api.proto"], ["proto"])?;
+    tonic_prost_build::configure()
+        .build_client(false)
+        .build_server(false)
+        .compile_fds(files)?;
+    Ok(())
+}
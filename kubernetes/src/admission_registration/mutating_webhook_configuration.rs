@@ -2,8 +2,9 @@
 
 use std::path::PathBuf;
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
+use crate::decode::Fields;
 use crate::meta::{
     label_selector::Selector,
     metadata::Metadata,
@@ -12,42 +13,92 @@ use crate::meta::{
 /// <https://kubernetes.io/docs/reference/kubernetes-api/extend-resources/mutating-webhook-configuration-v1/>
 #[derive(Debug, Decode)]
 pub struct MutatingWebhookConfiguration {
-    metadata: Metadata,
+    #[kfl(child)]
+    pub metadata: Metadata,
     #[kfl(children)]
-    webhooks: Vec<MutatingWebhook>
+    pub webhooks: Vec<MutatingWebhook>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct MutatingWebhook {
-    admission_review_versions: Vec<String>,
-    client_config: WebhookClientConfig,
-    name: String,
-    side_effects: String,
-    failure_policy: Option<String>,
-    match_policy: Option<String>,
-    namespace_selector: Option<Selector>,
-    object_selector: Option<Selector>,
-    reinvocation_policy: Option<String>,
-    rules: Vec<RuleWithOperations>,
-    timeout_seconds: Option<i32>
+    pub admission_review_versions: Vec<String>,
+    pub client_config: WebhookClientConfig,
+    pub name: String,
+    pub side_effects: String,
+    pub failure_policy: Option<String>,
+    pub match_policy: Option<String>,
+    pub namespace_selector: Option<Selector>,
+    pub object_selector: Option<Selector>,
+    pub reinvocation_policy: Option<String>,
+    pub rules: Vec<RuleWithOperations>,
+    pub timeout_seconds: Option<i32>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for MutatingWebhook {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let mutating_webhook = Self {
+            admission_review_versions: fields.values("admission-review-versions")?,
+            client_config: fields.child("client-config")?.ok_or_else(|| fields.missing("client-config"))?,
+            name: fields.property("name")?.unwrap_or_default(),
+            side_effects: fields.property("side-effects")?.unwrap_or_default(),
+            failure_policy: fields.property("failure-policy")?,
+            match_policy: fields.property("match-policy")?,
+            namespace_selector: fields.child("namespace-selector")?,
+            object_selector: fields.child("object-selector")?,
+            reinvocation_policy: fields.property("reinvocation-policy")?,
+            rules: fields.children("rule-with-operations")?,
+            timeout_seconds: fields.property("timeout-seconds")?
+        };
+        fields.finish()?;
+        Ok(mutating_webhook)
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct WebhookClientConfig {
-    ca_bundle: Vec<u8>,
-    service: Option<ServiceReference>,
-    url: Option<String>
+    pub ca_bundle: Vec<u8>,
+    pub service: Option<ServiceReference>,
+    pub url: Option<String>
+}
+
+impl Decode for WebhookClientConfig {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let webhook_client_config = Self {
+            ca_bundle: fields.property("ca-bundle")?.unwrap_or_default(),
+            service: fields.child("service")?,
+            url: fields.property("url")?
+        };
+        fields.finish()?;
+        Ok(webhook_client_config)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct ServiceReference {
-    name: String,
-    namespace: String,
-    path: Option<PathBuf>,
-    port: Option<i32>
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub namespace: String,
+    #[kfl(property, default)]
+    pub path: Option<PathBuf>,
+    #[kfl(property, default)]
+    pub port: Option<i32>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct RuleWithOperations {
-    api_groups: Vec<String>
+    pub api_groups: Vec<String>
+}
+
+impl Decode for RuleWithOperations {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let rule_with_operations = Self {
+            api_groups: fields.values("api-groups")?
+        };
+        fields.finish()?;
+        Ok(rule_with_operations)
+    }
 }
@@ -7,5 +7,6 @@ use crate::meta::metadata::Metadata;
 /// <https://kubernetes.io/docs/reference/kubernetes-api/extend-resources/validating-webhook-configuration-v1/#ValidatingWebhookConfiguration>
 #[derive(Debug, Decode)]
 pub struct ValidatingWebhookConfiguration {
-    metadata: Metadata
+    #[kfl(child)]
+    pub metadata: Metadata
 }
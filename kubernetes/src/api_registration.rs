@@ -1,7 +1,5 @@
 pub mod api_service;
 
-use kfl::Decode;
-
 pub use api_service::ApiService;
 
 // #[derive(Debug, Decode)]
@@ -2,43 +2,77 @@
 Reference <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/api-service-v1/>
 */
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
+use crate::decode::Fields;
 use crate::meta::{
     condition::Condition,
     metadata::Metadata,
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/api-service-v1/#ApiService>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct ApiService {
-    metadata: Option<Metadata>,
-    spec: ApiServiceSpec,
-    status: Option<ApiServiceStatus>
+    pub metadata: Option<Metadata>,
+    pub spec: ApiServiceSpec,
+    pub status: Option<ApiServiceStatus>
+}
+
+impl Decode for ApiService {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let api_service = Self {
+            metadata: fields.child("metadata")?,
+            spec: fields.child("spec")?.ok_or_else(|| fields.missing("spec"))?,
+            status: fields.child("status")?
+        };
+        fields.finish()?;
+        Ok(api_service)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/api-service-v1/#ApiServiceSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct ApiServiceSpec {
-    group_priority_minimum: i32,
-    version_priority: i32,
-    ca_bundle: Vec<u8>,
-    group: Option<String>,
-    insecure_skip_tls_verify: Option<bool>,
-    service: Option<ServiceReference>,
-    version: Option<String>
+    pub group_priority_minimum: i32,
+    pub version_priority: i32,
+    pub ca_bundle: Vec<u8>,
+    pub group: Option<String>,
+    pub insecure_skip_tls_verify: Option<bool>,
+    pub service: Option<ServiceReference>,
+    pub version: Option<String>
+}
+
+impl Decode for ApiServiceSpec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let api_service_spec = Self {
+            group_priority_minimum: fields.property("group-priority-minimum")?.unwrap_or_default(),
+            version_priority: fields.property("version-priority")?.unwrap_or_default(),
+            ca_bundle: fields.property("ca-bundle")?.unwrap_or_default(),
+            group: fields.property("group")?,
+            insecure_skip_tls_verify: fields.property("insecure-skip-tls-verify")?,
+            service: fields.child("service")?,
+            version: fields.property("version")?
+        };
+        fields.finish()?;
+        Ok(api_service_spec)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct ServiceReference {
-    name: Option<String>,
-    namespace: Option<String>,
-    port: Option<i32>,
+    #[kfl(property, default)]
+    pub name: Option<String>,
+    #[kfl(property, default)]
+    pub namespace: Option<String>,
+    #[kfl(property, default)]
+    pub port: Option<i32>,
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/api-service-v1/#ApiServiceStatus>
 #[derive(Debug, Decode)]
 pub struct ApiServiceStatus {
     #[kfl(children)]
-    conditions: Vec<Condition>
+    pub conditions: Vec<Condition>
 }
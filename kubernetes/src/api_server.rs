@@ -1 +1,2 @@
 pub mod flow_control;
+pub mod fake;
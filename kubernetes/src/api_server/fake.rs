@@ -0,0 +1,1096 @@
+/*!
+An in-process API server for testing clients and controllers, served over HTTP on a local port.
+
+Objects are kept unstructured, as JSON, for the resources `kubectl` knows, leases and any registered with [`FakeApiServer::register`]. The server does what clients rely on:
+
+- assigns `uid`, `resourceVersion`, `generation` and `creationTimestamp`, and names from `generateName`;
+- lists with label and field selectors, in pages with `continue` tokens;
+- watches from a resource version, replaying what happened since, with bookmarks;
+- rejects writes with a stale `resourceVersion` with `409 Conflict`, and watches from a compacted one with `410 Gone`;
+- takes status only through the `status` subresource, and everything else only through the object;
+- keeps objects with finalisers until they are removed, and collects the dependents of deleted owners, or orphans them, through the owner graph of [`garbage_collection`].
+
+Resource versions are one counter across resources, as etcd revisions are. Garbage is collected at once, within the request that makes it. Pages after the first show the objects as they are when the page is asked for, not as they were at the first. There is no admission, validation, authentication, defaulting or namespace lifecycle.
+
+- Concepts <https://kubernetes.io/docs/reference/using-api/api-concepts/>
+- Source <https://github.com/kubernetes/apiserver/blob/master/pkg/registry/generic/registry/store.go>
+*/
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    convert::Infallible,
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration
+};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use bytes::Bytes;
+use http::{header, Method, Request, Response, StatusCode};
+use http_body_util::{combinators::BoxBody, BodyExt, Full, StreamBody};
+use hyper::{body::{Frame, Incoming}, server::conn::http1, service::service_fn};
+use hyper_util::rt::TokioIo;
+use serde_json::{json, Map, Value};
+use tokio::{
+    net::TcpListener,
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+    time::{self, Instant}
+};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+
+use crate::{
+    client::{self, ApiResource, Client, Resource as _, TcpConnector},
+    coordination::Lease,
+    kubectl::{resource::RESOURCES, FieldSelector},
+    meta::{
+        garbage_collection::{self, Graph, PropagationPolicy},
+        patch::{json_patch, merge_patch},
+        status::{self, Status, StatusCause},
+        Selector
+    },
+    time::Time
+};
+
+/// Changes kept for watches to resume from; watches from before the oldest are gone.
+pub const HISTORY: usize = 10_000;
+
+type Body = BoxBody<Bytes, Infallible>;
+
+/// A running fake API server, stopped when dropped.
+pub struct FakeApiServer {
+    address: SocketAddr,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>
+}
+
+impl FakeApiServer {
+    /// Serves on a free port of the loopback interface. Must be called within a Tokio runtime.
+    pub async fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::new()));
+        let shared = state.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = shared.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |request| {
+                        let state = state.clone();
+                        async move { Ok::<_, Infallible>(serve(&state, request).await) }
+                    });
+                    let _ = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await;
+                });
+            }
+        });
+        Ok(Self { address, state, task })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// `http://127.0.0.1:<port>`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// A client of the server.
+    pub fn client(&self) -> Client {
+        Client::new(&self.url(), TcpConnector).expect("the URL of the server is valid")
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        lock(&self.state)
+    }
+
+    /// Serves `resource` too, as a custom resource definition would.
+    pub fn register(&self, resource: ApiResource) {
+        let mut state = self.lock();
+        state.resources.retain(|served| !(served.group == resource.group
+            && served.plural == resource.plural));
+        state.resources.push(resource);
+    }
+
+    /// Creates `object` as if it were posted, in the namespace of its metadata.
+    pub fn create(&self, object: Value) -> Result<Value, Box<Status>> {
+        let mut state = self.lock();
+        let resource = state.resource_of(&object)?;
+        let namespace = object.pointer("/metadata/namespace").and_then(Value::as_str)
+            .map(str::to_owned);
+        let namespace = namespace.as_deref()
+            .or(resource.namespaced.then_some(crate::kubectl::DEFAULT_NAMESPACE));
+        state.create(&resource, namespace, object, false)
+    }
+
+    /// The object `name` of `resource`, in `namespace` if namespaced.
+    pub fn get(&self, resource: &ApiResource, namespace: Option<&str>, name: &str) -> Option<Value> {
+        self.lock().objects.get(&Key::new(resource, namespace, name)).cloned()
+    }
+
+    /// The objects of `resource`, in `namespace` or in all of them, by namespace then name.
+    pub fn list(&self, resource: &ApiResource, namespace: Option<&str>) -> Vec<Value> {
+        let state = self.lock();
+        state.objects.iter()
+            .filter(|(key, _)| key.is(resource)
+                && namespace.is_none_or(|namespace| key.namespace == namespace))
+            .map(|(_, object)| object.clone())
+            .collect()
+    }
+
+    /// The resource version of the last change.
+    pub fn resource_version(&self) -> String {
+        self.lock().revision.to_string()
+    }
+
+    /// Sends a bookmark at the current resource version to the watches that allow them.
+    pub fn bookmark(&self) {
+        let state = self.lock();
+        let revision = state.revision;
+        let _ = state.events.send(Arc::new(Change {
+            revision,
+            r#type: EventType::Bookmark,
+            key: None,
+            old: None,
+            object: Value::Null
+        }));
+    }
+
+    /// Forgets the changes so far: watches from before now fail with `410 Gone`, as do `continue` tokens.
+    pub fn compact(&self) {
+        let mut state = self.lock();
+        state.history.clear();
+        state.oldest = state.revision;
+    }
+}
+
+impl Drop for FakeApiServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+/// Where an object is stored.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Key {
+    group: String,
+    resource: String,
+    /// Empty for cluster-scoped objects.
+    namespace: String,
+    name: String
+}
+
+impl Key {
+    fn new(resource: &ApiResource, namespace: Option<&str>, name: &str) -> Self {
+        Self {
+            group: resource.group.clone(),
+            resource: resource.plural.clone(),
+            namespace: namespace.filter(|_| resource.namespaced).unwrap_or_default().to_owned(),
+            name: name.to_owned()
+        }
+    }
+
+    fn is(&self, resource: &ApiResource) -> bool {
+        self.group == resource.group && self.resource == resource.plural
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventType {
+    Added,
+    Modified,
+    Deleted,
+    Bookmark
+}
+
+impl EventType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Added => "ADDED",
+            Self::Modified => "MODIFIED",
+            Self::Deleted => "DELETED",
+            Self::Bookmark => "BOOKMARK"
+        }
+    }
+}
+
+/// A change to an object, or a bookmark.
+#[derive(Debug)]
+struct Change {
+    revision: u64,
+    r#type: EventType,
+    /// `None` for bookmarks.
+    key: Option<Key>,
+    /// The object before a modification, to tell watches with selectors whether it left or entered them.
+    old: Option<Value>,
+    object: Value
+}
+
+struct State {
+    resources: Vec<ApiResource>,
+    objects: BTreeMap<Key, Value>,
+    /// Resource version of the last change.
+    revision: u64,
+    history: VecDeque<Arc<Change>>,
+    /// The oldest resource version watches can start from.
+    oldest: u64,
+    events: broadcast::Sender<Arc<Change>>,
+    /// Objects created so far, for UIDs and generated names.
+    created: u64
+}
+
+impl State {
+    fn new() -> Self {
+        let mut resources: Vec<ApiResource> = RESOURCES.iter().map(ApiResource::from).collect();
+        resources.push(Lease::api_resource());
+        Self {
+            resources,
+            objects: BTreeMap::new(),
+            revision: 0,
+            history: VecDeque::new(),
+            oldest: 0,
+            events: broadcast::channel(1024).0,
+            created: 0
+        }
+    }
+
+    fn resource_of(&self, object: &Value) -> Result<ApiResource, Box<Status>> {
+        let api_version = object.get("apiVersion").and_then(Value::as_str).unwrap_or_default();
+        let kind = object.get("kind").and_then(Value::as_str).unwrap_or_default();
+        let (group, version) = api_version.rsplit_once('/').unwrap_or(("", api_version));
+        self.resources.iter()
+            .find(|resource| resource.group == group && resource.version == version
+                && resource.kind == kind)
+            .cloned()
+            .ok_or_else(|| Box::new(Status::bad_request(format!(
+                "no kind {:?} is registered for version {:?}", kind, api_version))))
+    }
+
+    /// Stores `object` at a new resource version, or removes it for [`EventType::Deleted`], and tells watches.
+    fn commit(&mut self, key: Key, r#type: EventType, old: Option<Value>, mut object: Value) -> Value {
+        self.revision += 1;
+        object["metadata"]["resourceVersion"] = self.revision.to_string().into();
+        if r#type == EventType::Deleted {
+            self.objects.remove(&key);
+        } else {
+            self.objects.insert(key.clone(), object.clone());
+        }
+        let change = Arc::new(Change {
+            revision: self.revision,
+            r#type,
+            key: Some(key),
+            old,
+            object: object.clone()
+        });
+        if self.history.len() == HISTORY {
+            if let Some(oldest) = self.history.pop_front() {
+                self.oldest = oldest.revision;
+            }
+        }
+        self.history.push_back(change.clone());
+        let _ = self.events.send(change);
+        object
+    }
+
+    fn create(&mut self, resource: &ApiResource, namespace: Option<&str>, mut object: Value,
+        dry_run: bool) -> Result<Value, Box<Status>>
+    {
+        kind(resource, &mut object)?;
+        let metadata = metadata(&mut object)?;
+        if resource.namespaced {
+            let namespace = namespace.ok_or_else(|| Box::new(Status::failure(405, status::METHOD_NOT_ALLOWED,
+                format!("{} must be created in a namespace", resource.plural))))?;
+            match metadata.get("namespace").and_then(Value::as_str) {
+                Some(own) if !own.is_empty() && own != namespace => return Err(Status::bad_request(
+                    "the namespace of the provided object does not match the namespace sent on the \
+                    request".to_owned()).into()),
+                _ => metadata.insert("namespace".to_owned(), namespace.into())
+            };
+        } else {
+            metadata.remove("namespace");
+        }
+        self.created += 1;
+        let created = self.created;
+        let name = match metadata.get("name").and_then(Value::as_str).filter(|name| !name.is_empty()) {
+            Some(name) => name.to_owned(),
+            None => match metadata.get("generateName").and_then(Value::as_str)
+                .filter(|prefix| !prefix.is_empty())
+            {
+                Some(prefix) => format!("{}{}", prefix, suffix(created)),
+                None => return Err(Status::invalid(&resource.group, &resource.kind, "", vec![StatusCause {
+                    field: Some("metadata.name".to_owned()),
+                    message: Some("Required value: name or generateName is required".to_owned()),
+                    reason: Some("FieldValueRequired".to_owned())
+                }]).into())
+            }
+        };
+        metadata.insert("name".to_owned(), name.clone().into());
+        metadata.insert("uid".to_owned(), uid(created).into());
+        metadata.insert("creationTimestamp".to_owned(), Time::now().to_string().into());
+        metadata.insert("generation".to_owned(), 1.into());
+        for field in ["resourceVersion", "deletionTimestamp", "deletionGracePeriodSeconds", "selfLink"] {
+            metadata.remove(field);
+        }
+        let key = Key::new(resource, namespace, &name);
+        if self.objects.contains_key(&key) {
+            return Err(Status::already_exists(&resource.group, &resource.plural, &name).into())
+        }
+        if dry_run {
+            return Ok(object)
+        }
+        Ok(self.commit(key, EventType::Added, None, object))
+    }
+
+    /**
+    Replaces the object, or only its status for the `status` subresource, unless `object` has a resource version other than the stored one. Fields the server sets are kept, and the generation is increased when anything but the metadata and status changes.
+    */
+    fn update(&mut self, resource: &ApiResource, namespace: Option<&str>, name: &str,
+        mut object: Value, subresource: Option<&str>, dry_run: bool) -> Result<Value, Box<Status>>
+    {
+        let key = Key::new(resource, namespace, name);
+        let current = self.objects.get(&key).cloned()
+            .ok_or_else(|| Box::new(Status::not_found(&resource.group, &resource.plural, name)))?;
+        kind(resource, &mut object)?;
+        match object.pointer("/metadata/name").and_then(Value::as_str) {
+            Some(own) if own != name => return Err(Status::bad_request(format!(
+                "the name of the object ({}) does not match the name on the URL ({})", own, name)).into()),
+            _ => {}
+        }
+        let version = object.pointer("/metadata/resourceVersion").and_then(Value::as_str)
+            .filter(|version| !version.is_empty());
+        if version.is_some_and(|version| Some(version) != resource_version(&current)) {
+            return Err(Status::conflict(&resource.group, &resource.plural, name).into())
+        }
+        let mut new = match subresource {
+            Some("status") => {
+                let mut new = current.clone();
+                set(&mut new, "status", object.get("status").cloned());
+                new
+            }
+            Some(subresource) => return Err(Status::not_found(&resource.group,
+                &format!("{}/{}", resource.plural, subresource), name).into()),
+            None => {
+                set(&mut object, "status", current.get("status").cloned());
+                object
+            }
+        };
+        let metadata = metadata(&mut new)?;
+        for field in ["name", "namespace", "uid", "creationTimestamp", "deletionTimestamp",
+            "deletionGracePeriodSeconds", "generation", "resourceVersion"]
+        {
+            match current["metadata"].get(field) {
+                Some(value) => metadata.insert(field.to_owned(), value.clone()),
+                None => metadata.remove(field)
+            };
+        }
+        if strip(&new) != strip(&current) {
+            let generation = current.pointer("/metadata/generation").and_then(Value::as_i64)
+                .unwrap_or_default();
+            new["metadata"]["generation"] = (generation + 1).into();
+        }
+        if new == current || dry_run {
+            return Ok(new)
+        }
+        if is_being_deleted(&new) && finalisers(&new).is_empty() {
+            let deleted = self.commit(key, EventType::Deleted, Some(current), new);
+            self.collect_garbage(None);
+            return Ok(deleted)
+        }
+        let updated = self.commit(key, EventType::Modified, Some(current), new);
+        // Removing a finaliser or owner reference may free or orphan other objects.
+        self.collect_garbage(None);
+        Ok(updated)
+    }
+
+    /// Patches the object, creating it for server-side apply if it does not exist.
+    fn patch(&mut self, route: &Route, name: &str, content_type: &str, patch: Value, query: &Query)
+        -> Result<(StatusCode, Value), Box<Status>>
+    {
+        let (resource, namespace) = (&route.resource, route.namespace.as_deref());
+        let subresource = route.subresource.as_deref();
+        let apply = content_type == "application/apply-patch+yaml";
+        if apply && query.get("fieldManager").is_none_or(str::is_empty) {
+            return Err(Status::bad_request(
+                "PATCH requests with an apply patch must have a field manager".to_owned()).into())
+        }
+        let key = Key::new(resource, namespace, name);
+        let Some(current) = self.objects.get(&key).cloned() else {
+            if apply && subresource.is_none() {
+                let mut object = patch;
+                metadata(&mut object)?.insert("name".to_owned(), name.into());
+                return self.create(resource, namespace, object, query.dry_run())
+                    .map(|object| (StatusCode::CREATED, object))
+            }
+            return Err(Status::not_found(&resource.group, &resource.plural, name).into())
+        };
+        let mut patched = current;
+        match content_type {
+            "application/json-patch+json" => json_patch(&mut patched, &patch)
+                .map_err(|message| Status::failure(422, status::INVALID, message))?,
+            "application/merge-patch+json" | "application/strategic-merge-patch+json" =>
+                merge_patch(&mut patched, &patch),
+            _ if apply => merge_patch(&mut patched, &patch),
+            content_type => return Err(Status::failure(415, status::UNSUPPORTED_MEDIA_TYPE,
+                format!("the body of the request was in an unknown format: {}", content_type)).into())
+        }
+        self.update(resource, namespace, name, patched, subresource, query.dry_run())
+            .map(|object| (StatusCode::OK, object))
+    }
+
+    /**
+    Deletes the object unless `options` has preconditions it fails. Objects kept by finalisers, those of the propagation policy included, are only marked as being deleted, and returned; others are removed, and a success status returned.
+
+    Dependents are deleted after their owner by default, before it in the foreground, or orphaned, by [`collect_garbage`][Self::collect_garbage].
+    */
+    fn delete(&mut self, resource: &ApiResource, namespace: Option<&str>, name: &str,
+        options: &Value, dry_run: bool) -> Result<Value, Box<Status>>
+    {
+        let key = Key::new(resource, namespace, name);
+        let current = self.objects.get(&key).cloned()
+            .ok_or_else(|| Box::new(Status::not_found(&resource.group, &resource.plural, name)))?;
+        let uid = current.pointer("/metadata/uid").and_then(Value::as_str).unwrap_or_default()
+            .to_owned();
+        let precondition = |field: &str| options.get("preconditions")
+            .and_then(|preconditions| preconditions.get(field)).and_then(Value::as_str);
+        if precondition("uid").is_some_and(|own| own != uid)
+            || precondition("resourceVersion").is_some_and(|own| Some(own) != resource_version(&current))
+        {
+            return Err(Status::conflict(&resource.group, &resource.plural, name).into())
+        }
+        let policy = match options.get("propagationPolicy").and_then(Value::as_str) {
+            Some(policy) => policy.parse().map_err(|_| Box::new(Status::bad_request(format!(
+                "unknown propagation policy {:?}", policy))))?,
+            None => PropagationPolicy::Background
+        };
+        let dry_run = dry_run || options.get("dryRun").and_then(Value::as_array)
+            .is_some_and(|dry_run| dry_run.iter().any(|value| value == "All"));
+        if dry_run {
+            let mut marked = garbage_collection::Object::from_json(&current);
+            marked.mark_deleted(policy, Time::now());
+            if marked.metadata.finalisers.is_empty() {
+                return Ok(deleted(resource, name, &uid))
+            }
+            let mut object = current;
+            mark(&mut object, &marked);
+            return Ok(object)
+        }
+        self.collect_garbage(Some((&uid, policy)));
+        match self.objects.get(&key) {
+            Some(object) if object.pointer("/metadata/uid").and_then(Value::as_str) == Some(&uid) =>
+                Ok(object.clone()),
+            _ => Ok(deleted(resource, name, &uid))
+        }
+    }
+
+    /**
+    Deletes the object with the UID of `deleted`, if any, with its propagation policy, then runs the garbage collector over the owner graph of all objects until nothing changes, and stores what it changed: finalisers and owner references dropped, dependents marked as being deleted, and objects removed once nothing keeps them.
+    */
+    fn collect_garbage(&mut self, deleted: Option<(&str, PropagationPolicy)>) {
+        let keys: HashMap<String, Key> = self.objects.iter()
+            .filter_map(|(key, object)| Some((object.pointer("/metadata/uid")?.as_str()?.to_owned(),
+                key.clone())))
+            .collect();
+        let mut graph = Graph::new(self.objects.values().map(garbage_collection::Object::from_json))
+            .expect("objects have unique UIDs");
+        let now = Time::now();
+        let deletion = match deleted {
+            Some((uid, policy)) => graph.delete(uid, policy, now).unwrap_or_default(),
+            None => graph.collect(now)
+        };
+        for object in graph.objects() {
+            let key = &keys[object.uid()];
+            let current = &self.objects[key];
+            let mut updated = current.clone();
+            mark(&mut updated, object);
+            if &updated != current {
+                let current = current.clone();
+                self.commit(key.clone(), EventType::Modified, Some(current), updated);
+            }
+        }
+        for uid in deletion.removed {
+            let key = keys[&uid].clone();
+            let current = self.objects[&key].clone();
+            self.commit(key, EventType::Deleted, Some(current.clone()), current);
+        }
+    }
+
+    fn list(&self, resource: &ApiResource, namespace: Option<&str>, filter: &Filter, query: &Query)
+        -> Result<Value, Box<Status>>
+    {
+        let limit = query.get("limit").and_then(|limit| limit.parse::<usize>().ok())
+            .filter(|limit| *limit > 0);
+        let (revision, start) = match query.get("continue").filter(|token| !token.is_empty()) {
+            Some(token) => {
+                let (revision, start) = decode_continue(token).ok_or_else(|| Box::new(Status::bad_request(
+                    format!("invalid continue token {:?}", token))))?;
+                if revision < self.oldest {
+                    return Err(Status::expired(
+                        "The provided continue parameter is too old to display a consistent list \
+                        result. You can start a new list without the continue parameter.".to_owned()).into())
+                }
+                (revision, Some(start))
+            }
+            None => (self.revision, None)
+        };
+        let mut items: Vec<(&Key, &Value)> = self.objects.iter()
+            .filter(|(key, object)| key.is(resource)
+                && namespace.is_none_or(|namespace| key.namespace == namespace)
+                && filter.matches(object))
+            .filter(|(key, _)| start.as_ref()
+                .is_none_or(|start| (&key.namespace, &key.name) > (&start.0, &start.1)))
+            .collect();
+        let mut metadata = json!({ "resourceVersion": revision.to_string() });
+        if let Some(limit) = limit.filter(|limit| *limit < items.len()) {
+            let remaining = items.len() - limit;
+            items.truncate(limit);
+            let last = items[limit - 1].0;
+            metadata["continue"] = encode_continue(revision, &last.namespace, &last.name).into();
+            metadata["remainingItemCount"] = remaining.into();
+        }
+        Ok(json!({
+            "apiVersion": resource.api_version(),
+            "kind": format!("{}List", resource.kind),
+            "metadata": metadata,
+            "items": items.into_iter().map(|(_, object)| object.clone()).collect::<Vec<_>>()
+        }))
+    }
+
+    /**
+    The events a watch from `resource_version` starts with, the current objects as added if it is empty or `0`, else what changed since, and the receiver of the changes to come; a `410` status if the version is too old.
+    */
+    fn watch(&self, filter: &Filter, resource_version: &str)
+        -> Result<Watching, Box<Status>>
+    {
+        let receiver = self.events.subscribe();
+        let events = if resource_version.is_empty() || resource_version == "0" {
+            self.objects.iter()
+                .filter(|(key, object)| filter.selects(key) && filter.matches(object))
+                .map(|(_, object)| event(EventType::Added, object.clone()))
+                .collect()
+        } else {
+            let revision: u64 = resource_version.parse().map_err(|_| Box::new(Status::bad_request(
+                format!("invalid resource version {:?}", resource_version))))?;
+            if revision < self.oldest {
+                return Err(Status::expired(format!("too old resource version: {} ({})",
+                    revision, self.oldest)).into())
+            }
+            self.history.iter()
+                .filter(|change| change.revision > revision)
+                .filter_map(|change| filter.event(change))
+                .collect()
+        };
+        Ok((events, receiver))
+    }
+}
+
+/// The events a watch starts with, and the receiver of the changes after them.
+type Watching = (Vec<Value>, broadcast::Receiver<Arc<Change>>);
+
+/// What a list or watch selects.
+struct Filter {
+    resource: ApiResource,
+    namespace: Option<String>,
+    labels: Option<Selector>,
+    fields: Option<FieldSelector>,
+    bookmarks: bool
+}
+
+impl Filter {
+    fn new(resource: &ApiResource, namespace: Option<&str>, query: &Query)
+        -> Result<Self, Box<Status>>
+    {
+        Ok(Self {
+            resource: resource.clone(),
+            namespace: namespace.map(str::to_owned),
+            labels: query.get("labelSelector").filter(|selector| !selector.is_empty())
+                .map(|selector| selector.parse().map_err(|error| Box::new(Status::bad_request(
+                    format!("unable to parse requirement: {}", error)))))
+                .transpose()?,
+            fields: query.get("fieldSelector").filter(|selector| !selector.is_empty())
+                .map(|selector| selector.parse().map_err(|error| Box::new(Status::bad_request(
+                    format!("{}", error)))))
+                .transpose()?,
+            bookmarks: query.get("allowWatchBookmarks") == Some("true")
+        })
+    }
+
+    fn selects(&self, key: &Key) -> bool {
+        key.is(&self.resource)
+            && self.namespace.as_ref().is_none_or(|namespace| key.namespace == *namespace)
+    }
+
+    fn matches(&self, object: &Value) -> bool {
+        let labels = object.pointer("/metadata/labels").and_then(Value::as_object)
+            .map(|labels| labels.iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_owned())))
+                .collect())
+            .unwrap_or_default();
+        self.labels.as_ref().is_none_or(|selector| selector.matches(&labels))
+            && self.fields.as_ref().is_none_or(|selector| selector.matches(object))
+    }
+
+    /// The event `change` is to a watch with this filter: objects leaving the selection are deleted from it, and objects entering it added.
+    fn event(&self, change: &Change) -> Option<Value> {
+        let Some(key) = &change.key else {
+            return self.bookmarks.then(|| event(EventType::Bookmark, json!({
+                "apiVersion": self.resource.api_version(),
+                "kind": self.resource.kind,
+                "metadata": { "resourceVersion": change.revision.to_string() }
+            })))
+        };
+        if !self.selects(key) {
+            return None
+        }
+        let now = self.matches(&change.object);
+        let r#type = match (change.r#type, change.old.as_ref().map(|old| self.matches(old))) {
+            (EventType::Modified, Some(true)) if !now => EventType::Deleted,
+            (EventType::Modified, Some(false)) if now => EventType::Added,
+            (EventType::Modified, Some(false)) => return None,
+            (r#type, _) if now => r#type,
+            _ => return None
+        };
+        Some(event(r#type, change.object.clone()))
+    }
+}
+
+fn event(r#type: EventType, object: Value) -> Value {
+    json!({ "type": r#type.as_str(), "object": object })
+}
+
+/// Query parameters, decoded.
+struct Query(HashMap<String, String>);
+
+impl Query {
+    fn parse(query: Option<&str>) -> Self {
+        Self(query.unwrap_or_default().split('&').filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (decode(key), decode(value))
+            })
+            .collect())
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    fn dry_run(&self) -> bool {
+        self.get("dryRun") == Some("All")
+    }
+}
+
+/// Percent-decodes a query component, with `+` for spaces.
+fn decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' if index + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[index + 1..index + 3]).ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        index += 3;
+                        continue
+                    }
+                    None => decoded.push(b'%')
+                }
+            }
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte)
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The object a request is for.
+struct Route {
+    resource: ApiResource,
+    namespace: Option<String>,
+    name: Option<String>,
+    subresource: Option<String>
+}
+
+/// Routes `/api/<version>/...` and `/apis/<group>/<version>/...` paths, with `namespaces/<namespace>/` before namespaced resources.
+fn route(state: &State, path: &str) -> Result<Route, Box<Status>> {
+    let not_found = || Box::new(Status::failure(404, status::NOT_FOUND,
+        "the server could not find the requested resource".to_owned()));
+    let segments: Vec<String> = path.trim_matches('/').split('/').map(decode).collect();
+    let (group, rest) = match segments.as_slice() {
+        [api, rest @ ..] if api == "api" => ("", rest),
+        [apis, group, rest @ ..] if apis == "apis" => (group.as_str(), rest),
+        _ => return Err(not_found())
+    };
+    let [version, rest @ ..] = rest else { return Err(not_found()) };
+    let find = |plural: &str| state.resources.iter()
+        .find(|resource| resource.group == group && resource.version == *version
+            && resource.plural == plural);
+    let (namespace, rest) = match rest {
+        [namespaces, namespace, plural, rest @ ..] if namespaces == "namespaces"
+            && find(plural).is_some_and(|resource| resource.namespaced) =>
+            (Some(namespace.clone()), &rest_with(plural, rest)[..]),
+        rest => (None, rest)
+    };
+    match rest {
+        [plural, rest @ ..] if rest.len() <= 2 => Ok(Route {
+            resource: find(plural).cloned().ok_or_else(not_found)?,
+            namespace,
+            name: rest.first().cloned(),
+            subresource: rest.get(1).cloned()
+        }),
+        _ => Err(not_found())
+    }
+}
+
+fn rest_with(first: &str, rest: &[String]) -> Vec<String> {
+    let mut segments = vec![first.to_owned()];
+    segments.extend_from_slice(rest);
+    segments
+}
+
+async fn serve(state: &Mutex<State>, request: Request<Incoming>) -> Response<Body> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_owned();
+    let query = Query::parse(request.uri().query());
+    let content_type = request.headers().get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(';').next().unwrap_or_default().trim().to_owned())
+        .unwrap_or_default();
+    let body = match request.into_body().collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(error) => return respond(Err(Status::bad_request(error.to_string()).into()))
+    };
+    let body = if body.is_empty() {
+        Value::Null
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(body) => body,
+            Err(error) => return respond(Err(Status::bad_request(format!(
+                "the body of the request could not be decoded: {}", error)).into()))
+        }
+    };
+    let mut locked = lock(state);
+    let route = match route(&locked, &path) {
+        Ok(route) => route,
+        Err(status) => return respond(Err(status))
+    };
+    let resource = &route.resource;
+    let namespace = route.namespace.as_deref();
+    let name = route.name.as_deref();
+    let subresource = route.subresource.as_deref();
+    let result = match (&method, name) {
+        (&Method::GET, None) if query.get("watch").is_some_and(|watch| watch == "true" || watch == "1") => {
+            let filter = match Filter::new(resource, namespace, &query) {
+                Ok(filter) => filter,
+                Err(status) => return respond(Err(status))
+            };
+            let timeout = query.get("timeoutSeconds").and_then(|seconds| seconds.parse().ok())
+                .map(Duration::from_secs);
+            let started = locked.watch(&filter, query.get("resourceVersion").unwrap_or_default());
+            drop(locked);
+            return stream(filter, started, timeout)
+        }
+        (&Method::GET, None) => Filter::new(resource, namespace, &query)
+            .and_then(|filter| locked.list(resource, namespace, &filter, &query))
+            .map(|list| (StatusCode::OK, list)),
+        (&Method::GET, Some(name)) => locked.objects.get(&Key::new(resource, namespace, name))
+            .filter(|_| subresource.is_none_or(|subresource| subresource == "status"))
+            .cloned()
+            .map(|object| (StatusCode::OK, object))
+            .ok_or_else(|| Box::new(Status::not_found(&resource.group, &resource.plural, name))),
+        (&Method::POST, None) => locked.create(resource, namespace, body, query.dry_run())
+            .map(|object| (StatusCode::CREATED, object)),
+        (&Method::PUT, Some(name)) => locked.update(resource, namespace, name, body, subresource,
+            query.dry_run()).map(|object| (StatusCode::OK, object)),
+        (&Method::PATCH, Some(name)) => locked.patch(&route, name, &content_type, body, &query),
+        (&Method::DELETE, Some(name)) if subresource.is_none() => locked.delete(resource, namespace,
+            name, &body, query.dry_run()).map(|object| (StatusCode::OK, object)),
+        (method, _) => Err(Status::failure(405, status::METHOD_NOT_ALLOWED,
+            format!("the server does not allow this method on the requested resource: {}", method)).into())
+    };
+    respond(result)
+}
+
+fn respond(result: Result<(StatusCode, Value), Box<Status>>) -> Response<Body> {
+    let (code, body) = match result {
+        Ok((code, body)) => (code, body),
+        Err(status) => (
+            status.code.and_then(|code| u16::try_from(code).ok())
+                .and_then(|code| StatusCode::from_u16(code).ok())
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            status.to_json()
+        )
+    };
+    let mut response = Response::new(Full::new(Bytes::from(body.to_string())).boxed());
+    *response.status_mut() = code;
+    response.headers_mut().insert(header::CONTENT_TYPE, header::HeaderValue::from_static(client::JSON));
+    response
+}
+
+/// Streams the events of a watch, one JSON line each, until the client goes, the timeout passes, or the watch falls too far behind.
+fn stream(filter: Filter, started: Result<Watching, Box<Status>>, timeout: Option<Duration>)
+    -> Response<Body>
+{
+    let (sender, receiver) = mpsc::channel::<Bytes>(64);
+    let line = |event: Value| Bytes::from(format!("{}\n", event));
+    tokio::spawn(async move {
+        let (events, mut changes) = match started {
+            Ok(started) => started,
+            Err(status) => {
+                let _ = sender.send(line(json!({ "type": "ERROR", "object": status.to_json() }))).await;
+                return
+            }
+        };
+        for event in events {
+            if sender.send(line(event)).await.is_err() {
+                return
+            }
+        }
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let change = tokio::select! {
+                change = changes.recv() => match change {
+                    Ok(change) => change,
+                    Err(_) => return
+                },
+                _ = sender.closed() => return,
+                _ = until(deadline) => return
+            };
+            if let Some(event) = filter.event(&change) {
+                if sender.send(line(event)).await.is_err() {
+                    return
+                }
+            }
+        }
+    });
+    let body = StreamBody::new(ReceiverStream::new(receiver)
+        .map(|bytes| Ok::<_, Infallible>(Frame::data(bytes))));
+    let mut response = Response::new(BodyExt::boxed(body));
+    response.headers_mut().insert(header::CONTENT_TYPE, header::HeaderValue::from_static(client::JSON));
+    response
+}
+
+async fn until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => time::sleep_until(deadline).await,
+        None => std::future::pending().await
+    }
+}
+
+/// Checks the kind and API version of `object` are those of `resource`, setting them if missing.
+fn kind(resource: &ApiResource, object: &mut Value) -> Result<(), Box<Status>> {
+    let Value::Object(fields) = object else {
+        return Err(Status::bad_request("the body of the request must be an object".to_owned()).into())
+    };
+    for (field, expected) in [("kind", resource.kind.clone()), ("apiVersion", resource.api_version())] {
+        match fields.get(field).and_then(Value::as_str) {
+            Some(own) if !own.is_empty() && own != expected => return Err(Status::bad_request(
+                format!("the {} of the object ({}) does not match the {} of the resource ({})",
+                    field, own, field, expected)).into()),
+            _ => fields.insert(field.to_owned(), expected.into())
+        };
+    }
+    Ok(())
+}
+
+fn metadata(object: &mut Value) -> Result<&mut Map<String, Value>, Box<Status>> {
+    let fields = object.as_object_mut()
+        .ok_or_else(|| Status::bad_request("the body of the request must be an object".to_owned()))?;
+    fields.entry("metadata").or_insert_with(|| json!({})).as_object_mut()
+        .ok_or_else(|| Box::new(Status::bad_request("metadata must be an object".to_owned())))
+}
+
+/// Sets or, if `None`, removes the top-level `field`.
+fn set(object: &mut Value, field: &str, value: Option<Value>) {
+    if let Some(fields) = object.as_object_mut() {
+        match value {
+            Some(value) => fields.insert(field.to_owned(), value),
+            None => fields.remove(field)
+        };
+    }
+}
+
+/// The object without metadata and status, what its generation counts changes of.
+fn strip(object: &Value) -> Value {
+    let mut stripped = object.clone();
+    set(&mut stripped, "metadata", None);
+    set(&mut stripped, "status", None);
+    stripped
+}
+
+/// The success status of deleting the object `name` with `uid`.
+fn deleted(resource: &ApiResource, name: &str, uid: &str) -> Value {
+    let mut status = Status::success();
+    status.details = Some(status::StatusDetails {
+        name: Some(name.to_owned()),
+        group: Some(resource.group.clone()),
+        kind: Some(resource.plural.clone()),
+        uid: Some(uid.to_owned()),
+        ..Default::default()
+    });
+    status.to_json()
+}
+
+/// Writes what the garbage collector changed of `object` to its unstructured form, with no grace period for objects it marked as being deleted.
+fn mark(object: &mut Value, marked: &garbage_collection::Object) {
+    let deleting = is_being_deleted(object);
+    marked.write_json(object);
+    if !deleting && is_being_deleted(object) {
+        object["metadata"]["deletionGracePeriodSeconds"] = 0.into();
+    }
+}
+
+fn resource_version(object: &Value) -> Option<&str> {
+    object.pointer("/metadata/resourceVersion").and_then(Value::as_str)
+}
+
+fn finalisers(object: &Value) -> Vec<&str> {
+    object.pointer("/metadata/finalizers").and_then(Value::as_array).into_iter().flatten()
+        .filter_map(Value::as_str)
+        .collect()
+}
+
+fn is_being_deleted(object: &Value) -> bool {
+    object.pointer("/metadata/deletionTimestamp").is_some_and(|timestamp| !timestamp.is_null())
+}
+
+/// A UID unique to the `count`th object created.
+fn uid(count: u64) -> String {
+    format!("{:08x}-0000-4000-8000-{:012x}", count >> 32, count & 0xffff_ffff_ffff)
+}
+
+/// Five characters unique to the `count`th object created, of those the API server appends to `generateName`, which make no words.
+fn suffix(count: u64) -> String {
+    const ALPHABET: &[u8] = b"bcdfghjklmnpqrstvwxz2456789";
+    let base = ALPHABET.len() as u64;
+    // Scrambled by a multiplier coprime with the number of suffixes, so that consecutive ones differ at the front.
+    let mut value = count.wrapping_mul(7_368_787) % base.pow(5);
+    (0..5).map(|_| {
+        let character = ALPHABET[(value % base) as usize] as char;
+        value /= base;
+        character
+    }).collect()
+}
+
+fn encode_continue(revision: u64, namespace: &str, name: &str) -> String {
+    URL_SAFE_NO_PAD.encode(json!({ "rv": revision, "start": [namespace, name] }).to_string())
+}
+
+fn decode_continue(token: &str) -> Option<(u64, (String, String))> {
+    let token: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(token).ok()?).ok()?;
+    let start = token.get("start")?.as_array()?;
+    Some((token.get("rv")?.as_u64()?,
+        (start.first()?.as_str()?.to_owned(), start.get(1)?.as_str()?.to_owned())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::{Api, DeleteParams, Deleted, Patch, PatchParams};
+    use super::*;
+
+    fn pods() -> ApiResource {
+        ApiResource::new("", "v1", "Pod", "pods", true)
+    }
+
+    fn replica_sets() -> ApiResource {
+        ApiResource::new("apps", "v1", "ReplicaSet", "replicasets", true)
+    }
+
+    /// Creates the replica set `name`, and returns its UID.
+    fn replica_set(server: &FakeApiServer, name: &str) -> String {
+        let created = server.create(json!({
+            "apiVersion": "apps/v1",
+            "kind": "ReplicaSet",
+            "metadata": {"name": name}
+        })).unwrap();
+        created["metadata"]["uid"].as_str().unwrap().to_owned()
+    }
+
+    fn pod(server: &FakeApiServer, name: &str, owner: &str, block: bool, finalisers: &[&str]) {
+        server.create(json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {
+                "name": name,
+                "finalizers": finalisers,
+                "ownerReferences": [{
+                    "apiVersion": "apps/v1",
+                    "kind": "ReplicaSet",
+                    "name": "web",
+                    "uid": owner,
+                    "blockOwnerDeletion": block
+                }]
+            }
+        })).unwrap();
+    }
+
+    fn names(objects: Vec<Value>) -> Vec<String> {
+        objects.iter().map(|object| object["metadata"]["name"].as_str().unwrap().to_owned()).collect()
+    }
+
+    #[tokio::test]
+    async fn propagates_deletion_to_dependents() {
+        let server = FakeApiServer::start().await.unwrap();
+        let (pods, replica_sets) = (pods(), replica_sets());
+        let rs = Api::<Value>::new(server.client(), replica_sets.clone(), Some("default"));
+        let pod_api = Api::<Value>::new(server.client(), pods.clone(), Some("default"));
+
+        // In the foreground, the owner waits for the dependents blocking it.
+        let web = replica_set(&server, "web");
+        pod(&server, "kept", &web, true, &["example.com/keep"]);
+        pod(&server, "blocking", &web, true, &[]);
+        pod(&server, "free", &web, false, &["example.com/keep"]);
+        let dry_run = DeleteParams {
+            dry_run: true,
+            propagation_policy: Some(PropagationPolicy::Foreground),
+            ..DeleteParams::default()
+        };
+        let Deleted::Object(marked) = rs.delete("web", &dry_run).await.unwrap() else {
+            panic!("a dry run of a deletion in the foreground returns the object")
+        };
+        assert_eq!(marked["metadata"]["finalizers"], json!([garbage_collection::FOREGROUND_DELETION]));
+        assert_eq!(server.list(&pods, None).len(), 3);
+        let foreground = DeleteParams { dry_run: false, ..dry_run };
+        let Deleted::Object(marked) = rs.delete("web", &foreground).await.unwrap() else {
+            panic!("an owner with blocking dependents is kept")
+        };
+        assert!(is_being_deleted(&marked));
+        assert_eq!(names(server.list(&pods, None)), ["free", "kept"]);
+        assert!(server.list(&pods, None).iter().all(is_being_deleted));
+        pod_api.patch("free", &Patch::Json(json!([{"op": "remove", "path": "/metadata/finalizers"}])),
+            &PatchParams::default()).await.unwrap();
+        assert!(server.get(&replica_sets, Some("default"), "web").is_some());
+        pod_api.patch("kept", &Patch::Json(json!([{"op": "remove", "path": "/metadata/finalizers"}])),
+            &PatchParams::default()).await.unwrap();
+        assert!(server.list(&replica_sets, None).is_empty());
+        assert!(server.list(&pods, None).is_empty());
+
+        // Orphaned dependents lose their references and are kept.
+        let web = replica_set(&server, "web");
+        pod(&server, "orphan", &web, true, &[]);
+        let orphan = DeleteParams {
+            propagation_policy: Some(PropagationPolicy::Orphan),
+            ..DeleteParams::default()
+        };
+        assert!(matches!(rs.delete("web", &orphan).await.unwrap(), Deleted::Status(_)));
+        let orphan = server.get(&pods, Some("default"), "orphan").unwrap();
+        assert_eq!(orphan["metadata"].get("ownerReferences"), None);
+
+        // In the background, the owner goes at once and its dependents after it.
+        let web = replica_set(&server, "web");
+        pod(&server, "dependent", &web, true, &[]);
+        let dry_run = DeleteParams { dry_run: true, ..DeleteParams::default() };
+        assert!(matches!(rs.delete("web", &dry_run).await.unwrap(), Deleted::Status(_)));
+        assert_eq!(server.list(&pods, None).len(), 2);
+        assert!(matches!(rs.delete("web", &DeleteParams::default()).await.unwrap(), Deleted::Status(_)));
+        assert_eq!(names(server.list(&pods, None)), ["orphan"]);
+    }
+}
@@ -2,75 +2,127 @@
 Reference <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/flow-schema-v1beta2/>
 */
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
 
+use crate::decode::Fields;
 use crate::meta::{
     condition::Condition,
     metadata::Metadata
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/flow-schema-v1beta2/#FlowSchema>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct FlowSchema {
-    metadata: Metadata,
-    spec: FlowSchemaSpec,
-    status: Option<FlowSchemaStatus>
+    pub metadata: Metadata,
+    pub spec: FlowSchemaSpec,
+    pub status: Option<FlowSchemaStatus>
+}
+
+impl Decode for FlowSchema {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let flow_schema = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            spec: fields.child("spec")?.ok_or_else(|| fields.missing("spec"))?,
+            status: fields.child("status")?
+        };
+        fields.finish()?;
+        Ok(flow_schema)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/flow-schema-v1beta2/#FlowSchemaSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct FlowSchemaSpec {
-    priority_level_configuration: PriorityLevelConfigurationReference,
-    distinguisher_method: Option<FlowDistinguisherMethod>,
-    matching_precedence: Option<i32>,
-    rules: Vec<PolicyRulesWithSubjects>
+    pub priority_level_configuration: PriorityLevelConfigurationReference,
+    pub distinguisher_method: Option<FlowDistinguisherMethod>,
+    pub matching_precedence: Option<i32>,
+    pub rules: Vec<PolicyRulesWithSubjects>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for FlowSchemaSpec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let flow_schema_spec = Self {
+            priority_level_configuration: fields.child("priority-level-configuration")?.unwrap_or_default(),
+            distinguisher_method: fields.child("distinguisher-method")?,
+            matching_precedence: fields.property("matching-precedence")?,
+            rules: fields.children("policy-rules-with-subjects")?
+        };
+        fields.finish()?;
+        Ok(flow_schema_spec)
+    }
+}
+
+#[derive(Debug, Decode, Default)]
 pub struct PriorityLevelConfigurationReference {
-    name: String
+    #[kfl(property, default)]
+    pub name: String
 }
 
 #[derive(Debug, Decode)]
 pub struct FlowDistinguisherMethod {
-    r#type: FlowDistinguisherMethodType
+    #[kfl(property, default)]
+    pub r#type: FlowDistinguisherMethodType
 }
 
-#[derive(Debug, DecodeScalar)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum FlowDistinguisherMethodType {
+    #[default]
     ByUser,
     ByNamespace
 }
 
 #[derive(Debug, Decode)]
 pub struct PolicyRulesWithSubjects {
-    subjects: Vec<Subject>,
-    non_resource_rules: Vec<NonResourcePolicyRule>,
-    resource_rules: Vec<ResourcePolicyRule>
+    #[kfl(children)]
+    pub subjects: Vec<Subject>,
+    #[kfl(children)]
+    pub non_resource_rules: Vec<NonResourcePolicyRule>,
+    #[kfl(children)]
+    pub resource_rules: Vec<ResourcePolicyRule>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Subject {
-    kind: String,
-    group: Option<GroupSubject>,
-    service_account: Option<ServiceAccountSubject>,
-    user: Option<UserSubject>
+    pub kind: String,
+    pub group: Option<GroupSubject>,
+    pub service_account: Option<ServiceAccountSubject>,
+    pub user: Option<UserSubject>
+}
+
+impl Decode for Subject {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let subject = Self {
+            kind: fields.property("kind")?.unwrap_or_default(),
+            group: fields.child("group")?,
+            service_account: fields.child("service-account")?,
+            user: fields.child("user")?
+        };
+        fields.finish()?;
+        Ok(subject)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct GroupSubject {
-    name: String
+    #[kfl(property, default)]
+    pub name: String
 }
 
 #[derive(Debug, Decode)]
 pub struct ServiceAccountSubject {
-    name: String,
-    namespace: String
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub namespace: String
 }
 
 #[derive(Debug, Decode)]
 pub struct UserSubject {
-    name: String
+    #[kfl(property, default)]
+    pub name: String
 }
 
 #[derive(Debug, Decode)]
@@ -86,5 +138,6 @@ pub struct ResourcePolicyRule {
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/flow-schema-v1beta2/#FlowSchemaStatus>
 #[derive(Debug, Decode)]
 pub struct FlowSchemaStatus {
-    conditions: Vec<Condition>,
+    #[kfl(children)]
+    pub conditions: Vec<Condition>,
 }
@@ -1,46 +1,56 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/priority-level-configuration-v1beta2/>
 
-use kfl::Decode;
+use kfl::{Decode, DecodeScalar};
 
 use crate::meta::{condition::Condition, metadata::Metadata};
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/priority-level-configuration-v1beta2/#PriorityLevelConfiguration>
 #[derive(Debug, Decode)]
 pub struct PriorityLevelConfiguration {
-    metadata: Metadata,
-    spec: Spec,
-    status: Option<Status>
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child)]
+    pub spec: Spec,
+    #[kfl(child, default)]
+    pub status: Option<Status>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/priority-level-configuration-v1beta2/#PriorityLevelConfigurationSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Spec {
-    r#type: Type,
-    limited: Option<Limited>
+    #[kfl(property, default)]
+    pub r#type: Type,
+    #[kfl(child, default)]
+    pub limited: Option<Limited>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, DecodeScalar, Default)]
 pub enum Type {
     Exempt,
+    #[default]
     Limited
 }
 
 #[derive(Debug, Decode)]
 pub struct Limited {
-    assured_concurrency_shares: Option<i32>,
-    limit_response: Option<LimitResponse>,   
+    #[kfl(property, default)]
+    pub assured_concurrency_shares: Option<i32>,
+    #[kfl(child, default)]
+    pub limit_response: Option<LimitResponse>,   
 }
 
 #[derive(Debug, Decode)]
 pub struct LimitResponse {
-    r#type: limit_response::Type,
-    queuing: Option<Queuing>
+    #[kfl(property)]
+    pub r#type: limit_response::Type,
+    #[kfl(child, default)]
+    pub queuing: Option<Queuing>
 }
 
 pub mod limit_response {
     use kfl::DecodeScalar;
 
-    #[derive(Debug, DecodeScalar)]
+    #[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
     pub enum Type {
         Queue,
         Reject
@@ -49,13 +59,17 @@ pub mod limit_response {
 
 #[derive(Debug, Decode)]
 pub struct Queuing {
-    hand_size: Option<i32>,
-    queue_length_limit: Option<i32>,
-    queues: Option<i32>
+    #[kfl(property, default)]
+    pub hand_size: Option<i32>,
+    #[kfl(property, default)]
+    pub queue_length_limit: Option<i32>,
+    #[kfl(property, default)]
+    pub queues: Option<i32>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/priority-level-configuration-v1beta2/#PriorityLevelConfigurationStatus>
 #[derive(Debug, Decode)]
 pub struct Status {
-    conditions: Vec<Condition>
+    #[kfl(children)]
+    pub conditions: Vec<Condition>
 }
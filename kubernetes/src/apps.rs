@@ -1,7 +1,9 @@
 pub mod daemon_set;
+pub mod daemon_set_rollout;
 pub mod deployment;
 pub mod replica_set;
 pub mod stateful_set;
+pub mod stateful_set_rollout;
 
 // use kfl::Decode;
 
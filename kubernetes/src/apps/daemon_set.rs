@@ -1,10 +1,11 @@
 //! - Concepts <https://kubernetes.io/docs/concepts/workloads/controllers/daemonset/>
 //! - Reference <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/daemon-set-v1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
     core::pod_template::PodTemplateSpec,
+    decode::Fields,
     meta::{
         condition::Condition,
         label_selector::Selector,
@@ -18,66 +19,110 @@ use crate::{
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/daemon-set-v1/#DaemonSet>
 #[derive(Debug, Decode)]
 pub struct DaemonSet {
-    metadata: Option<Metadata>,
-    spec: Spec,
+    #[kfl(child, default)]
+    pub metadata: Option<Metadata>,
     #[kfl(child)]
-    status: Option<DaemonSetStatus>
+    pub spec: Spec,
+    #[kfl(child)]
+    pub status: Option<DaemonSetStatus>
 }
 
 /// Spec is the specification of a daemon set.
 ///
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/daemon-set-v1/#DaemonSetSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Spec {
     /// A label query over pods that are managed by the daemon set. Must match in order to be controlled. It must match the pod template's labels. More info: <https://kubernetes.io/docs/concepts/overview/working-with-objects/labels/#label-selectors>.
-    selector: Selector,
+    pub selector: Selector,
     /// An object that describes the pod that will be created. The DaemonSet will create exactly one copy of this pod on every node that matches the template's node selector (or on every node if no node selector is specified). More info: <https://kubernetes.io/docs/concepts/workloads/controllers/replicationcontroller#pod-template>.
-    template: PodTemplateSpec,
+    pub template: PodTemplateSpec,
     /// Minimum number of seconds for which a newly created DaemonSet pod should be ready without any of its container crashing, for it to be considered available. Defaults to 0 (pod will be considered available as soon as it is ready).
-    min_ready_seconds: Option<i32>,
+    pub min_ready_seconds: Option<i32>,
     /// Update strategy to replace existing DaemonSet pods with new pods.
-    update_strategy: Option<UpdateStrategy>,
+    pub update_strategy: Option<UpdateStrategy>,
     /// Number of old history to retain to allow rollback. This is a pointer to distinguish between explicit zero and not specified. Defaults to 10.
-    #[kfl(default = 10)]
-    revision_history_limit: u16
+    pub revision_history_limit: u16
+}
+
+impl Decode for Spec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            selector: fields.child("selector")?.unwrap_or_default(),
+            template: fields.child("template")?.unwrap_or_default(),
+            min_ready_seconds: fields.property("min-ready-seconds")?,
+            update_strategy: fields.child("update-strategy")?,
+            revision_history_limit: fields.property("revision-history-limit")?.unwrap_or(10)
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
 }
 
 /// UpdateStrategy is a struct used to control the update strategy for a DaemonSet.
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct UpdateStrategy {
-    #[kfl(default)]
-    r#type: update_strategy::Type,
-    rolling_update: Option<RollingUpdateDaemonSet>
+    pub r#type: update_strategy::Type,
+    pub rolling_update: Option<RollingUpdateDaemonSet>
+}
+
+impl Decode for UpdateStrategy {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let update_strategy = Self {
+            r#type: fields.property("type")?.unwrap_or_default(),
+            rolling_update: fields.child("rolling-update")?
+        };
+        fields.finish()?;
+        Ok(update_strategy)
+    }
 }
 
 pub mod update_strategy {
     use kfl::DecodeScalar;
 
-    #[derive(Debug, DecodeScalar, Default)]
+    #[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
     pub enum Type {
+        /// Old pods are replaced as the rolling update of the daemon set says.
         #[default]
         RollingUpdate,
+        /// Old pods are replaced only once deleted by hand.
         OnDelete
     }
 }
 
+/// <https://kubernetes.io/docs/tasks/manage-daemon/update-daemon-set/>
 #[derive(Debug, Decode)]
 pub struct RollingUpdateDaemonSet {
-    max_surge: Option<IntOrString>,
-    max_unavailable: Option<IntOrString>
+    /// The most nodes, a number or a percentage of those that should run the pod rounded up, that can run an updated pod alongside an old one. Defaults to `0`; must be `0` if `max_unavailable` is not.
+    #[kfl(property, default)]
+    pub max_surge: Option<IntOrString>,
+    /// The most nodes, a number or a percentage of those that should run the pod rounded up, whose pod can be unavailable during the update. Defaults to `1`.
+    #[kfl(property, default)]
+    pub max_unavailable: Option<IntOrString>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/daemon-set-v1/#DaemonSetStatus>
 #[derive(Debug, Decode)]
 pub struct DaemonSetStatus {
-    number_ready: i32,
-    number_available: Option<i32>,
-    number_unavailable: Option<i32>,
-    number_misscheduled: i32,
-    desired_number_scheduled: i32,
-    current_number_scheduled: i32,
-    updated_number_scheduled: Option<i32>,
-    collision_count: Option<i32>,
-    conditions: Vec<Condition>,
-    observed_generation: i64
+    #[kfl(property, default)]
+    pub number_ready: i32,
+    #[kfl(property, default)]
+    pub number_available: Option<i32>,
+    #[kfl(property, default)]
+    pub number_unavailable: Option<i32>,
+    #[kfl(property, default)]
+    pub number_misscheduled: i32,
+    #[kfl(property, default)]
+    pub desired_number_scheduled: i32,
+    #[kfl(property, default)]
+    pub current_number_scheduled: i32,
+    #[kfl(property, default)]
+    pub updated_number_scheduled: Option<i32>,
+    #[kfl(property, default)]
+    pub collision_count: Option<i32>,
+    #[kfl(children)]
+    pub conditions: Vec<Condition>,
+    #[kfl(property, default)]
+    pub observed_generation: i64
 }
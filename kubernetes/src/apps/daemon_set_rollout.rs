@@ -0,0 +1,284 @@
+/*!
+What the daemon set controller does next to the pods of a daemon set, evaluated offline: which nodes should run a pod, where to create and delete pods, and how to replace old pods in a rolling update.
+
+A node should run the pod if it matches the node selector and the required node affinity of the template and has no taint of effect `NoSchedule` or `NoExecute` the pod does not tolerate; a node with only untolerated `NoSchedule` taints keeps the pod it already runs. Daemon pods tolerate the taints of unschedulable and pressured nodes, as the controller adds those tolerations itself. The revision of a pod is its `controller-revision-hash` label.
+
+- Concepts <https://kubernetes.io/docs/concepts/workloads/controllers/daemonset/#how-daemon-pods-are-scheduled>
+- Concepts <https://kubernetes.io/docs/tasks/manage-daemon/update-daemon-set/>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/daemon/daemon_controller.go>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/daemon/update.go>
+*/
+
+use std::collections::BTreeMap;
+
+use crate::{
+    apps::daemon_set::{update_strategy, DaemonSet},
+    core::{
+        node::{Node, Taint},
+        pod::{self, Pod, TaintEffect}
+    },
+    time::Time
+};
+
+pub use crate::apps::stateful_set_rollout::REVISION_LABEL;
+
+/// Taints of nodes every daemon pod tolerates, with the effects tolerated.
+const TOLERATED: [(&str, TaintEffect); 6] = [
+    ("node.kubernetes.io/not-ready", TaintEffect::NoExecute),
+    ("node.kubernetes.io/unreachable", TaintEffect::NoExecute),
+    ("node.kubernetes.io/disk-pressure", TaintEffect::NoSchedule),
+    ("node.kubernetes.io/memory-pressure", TaintEffect::NoSchedule),
+    ("node.kubernetes.io/pid-pressure", TaintEffect::NoSchedule),
+    ("node.kubernetes.io/unschedulable", TaintEffect::NoSchedule)
+];
+/// Tolerated by daemon pods on the host network too.
+const NETWORK_UNAVAILABLE: &str = "node.kubernetes.io/network-unavailable";
+
+/// Whether a node should run the pod of a daemon set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    /// A pod should be created on the node if there is none.
+    pub should_run: bool,
+    /// The pod on the node, if any, should be kept.
+    pub should_continue_running: bool
+}
+
+/// Whether `node` should run a pod of `template`.
+pub fn placement(template: &pod::Spec, node: &Node) -> Placement {
+    let labels = &node.metadata.labels;
+    let selected = template.node_selector.iter().all(|(key, value)| labels.get(key) == Some(value))
+        && template.affinity.as_ref()
+            .and_then(|affinity| affinity.node_affinity.as_ref())
+            .and_then(|affinity| affinity.required_during_scheduling_ignored_during_execution.as_ref())
+            .is_none_or(|selector| selector.matches(labels,
+                node.metadata.name.as_deref().unwrap_or_default()));
+    if !selected {
+        return Placement { should_run: false, should_continue_running: false }
+    }
+    let untolerated = |effect: TaintEffect| node.spec.taints.iter()
+        .filter(|taint| taint.effect == effect)
+        .any(|taint| !tolerates(template, taint));
+    let evicted = untolerated(TaintEffect::NoExecute);
+    Placement {
+        should_run: !evicted && !untolerated(TaintEffect::NoSchedule),
+        should_continue_running: !evicted
+    }
+}
+
+fn tolerates(template: &pod::Spec, taint: &Taint) -> bool {
+    TOLERATED.iter().any(|(key, effect)| taint.key == *key && taint.effect == *effect)
+        || template.host_network && taint.key == NETWORK_UNAVAILABLE
+            && taint.effect == TaintEffect::NoSchedule
+        || template.tolerations.iter().any(|toleration| toleration.tolerates(taint))
+}
+
+/// The node `pod` is bound to, or that its required node affinity names, as daemon pods are scheduled.
+pub fn node_name(pod: &Pod) -> Option<&str> {
+    pod.spec.node_name.as_deref().filter(|name| !name.is_empty()).or_else(||
+        pod.spec.affinity.as_ref()?
+            .node_affinity.as_ref()?
+            .required_during_scheduling_ignored_during_execution.as_ref()?
+            .node_selector_terms.iter()
+            .flat_map(|term| &term.match_fields)
+            .find(|requirement| requirement.key == "metadata.name" && requirement.operator == "In")?
+            .values.first().map(String::as_str))
+}
+
+/// The revision of `pod`, empty if it has none.
+pub fn revision(pod: &Pod) -> &str {
+    pod.metadata.labels.get(REVISION_LABEL).map_or("", String::as_str)
+}
+
+/// An action on the pods of the set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Create a pod on `node`, from the template of `revision`.
+    Create { node: String, revision: String },
+    /// Delete the pod `name` on `node`.
+    Delete { node: String, name: String }
+}
+
+/**
+The next actions of the controller on the `pods` of `set` over `nodes`, at `now`, for every node that should run the pod to run one at `update_revision`.
+
+Pods are created on nodes that should run the pod and have none, and deleted from nodes that should not; failed pods are deleted, and created again by the next call, and of several pods of the same revision on a node only the oldest is kept.
+
+With the [`RollingUpdate`][update_strategy::Type::RollingUpdate] strategy, old pods are replaced as the rolling update says: without `max_surge`, old pods are deleted while no more than `max_unavailable` nodes are without an available pod, unavailable old pods first; with it, updated pods are created next to old ones on up to `max_surge` nodes at once, and the old pods deleted once the updated ones are available.
+*/
+pub fn next_actions(set: &DaemonSet, nodes: &[&Node], pods: &[&Pod], update_revision: &str, now: Time)
+    -> Vec<Action>
+{
+    let min_ready_seconds = set.spec.min_ready_seconds
+        .map_or(0, |seconds| u32::try_from(seconds).unwrap_or_default());
+    let available = |pod: &Pod| pod.is_available(min_ready_seconds, now);
+    let mut by_node: BTreeMap<&str, Vec<&Pod>> = BTreeMap::new();
+    for pod in pods {
+        if let Some(node) = node_name(pod).filter(|_| !pod.is_terminating()) {
+            by_node.entry(node).or_default().push(pod);
+        }
+    }
+
+    let mut actions = Vec::new();
+    let delete = |node: &str, pod: &Pod| Action::Delete {
+        node: node.to_owned(),
+        name: pod.metadata.name.clone().unwrap_or_default()
+    };
+    // The oldest old and updated pods of the nodes that should run the pod.
+    let mut running: Vec<(&str, Option<&Pod>, Option<&Pod>)> = Vec::new();
+    let mut desired = 0;
+    for node in nodes {
+        let name = node.metadata.name.as_deref().unwrap_or_default();
+        let placement = placement(&set.spec.template.spec, node);
+        let mut node_pods = by_node.remove(name).unwrap_or_default();
+        if placement.should_run {
+            desired += 1;
+        }
+        if !placement.should_run && (!placement.should_continue_running || node_pods.is_empty()) {
+            actions.extend(node_pods.iter().map(|pod| delete(name, pod)));
+            continue
+        }
+        let failed = node_pods.iter().any(|pod| is_failed(pod));
+        actions.extend(node_pods.iter().filter(|pod| is_failed(pod)).map(|pod| delete(name, pod)));
+        node_pods.retain(|pod| !is_failed(pod));
+        if node_pods.is_empty() {
+            if placement.should_run && !failed {
+                actions.push(Action::Create { node: name.to_owned(), revision: update_revision.to_owned() });
+            }
+            continue
+        }
+        node_pods.sort_by_key(|pod| (pod.metadata.creation_timestamp, pod.metadata.name.clone()));
+        let (updated, old): (Vec<&Pod>, Vec<&Pod>) = node_pods.into_iter()
+            .partition(|pod| revision(pod) == update_revision);
+        actions.extend(updated.iter().skip(1).chain(old.iter().skip(1)).map(|pod| delete(name, pod)));
+        if placement.should_run {
+            running.push((name, old.first().copied(), updated.first().copied()));
+        }
+    }
+
+    let strategy = set.spec.update_strategy.as_ref();
+    if strategy.is_some_and(|strategy| strategy.r#type == update_strategy::Type::OnDelete) {
+        return actions
+    }
+    let rolling_update = strategy.and_then(|strategy| strategy.rolling_update.as_ref());
+    let max_surge = rolling_update.and_then(|rolling_update| rolling_update.max_surge.as_ref())
+        .and_then(|max_surge| max_surge.scaled_value(desired, true))
+        .unwrap_or_default();
+    let max_unavailable = rolling_update
+        .and_then(|rolling_update| rolling_update.max_unavailable.as_ref())
+        .and_then(|max_unavailable| max_unavailable.scaled_value(desired, true))
+        .unwrap_or(1);
+    let max_unavailable = if max_surge == 0 && max_unavailable == 0 { 1 } else { max_unavailable };
+
+    // Nodes with an updated pod only keep it once it is available.
+    for (name, old, updated) in &running {
+        if let (Some(old), Some(updated)) = (old, updated) {
+            if available(updated) {
+                actions.push(delete(name, old));
+            }
+        }
+    }
+    let outdated = running.iter().filter_map(|(name, old, updated)|
+        old.filter(|_| updated.is_none()).map(|old| (*name, old)));
+    if max_surge == 0 {
+        // A node with an updated pod is as available as it, whatever its old pod.
+        let unavailable = desired - i32::try_from(running.iter()
+            .filter(|(_, old, updated)| updated.or(*old).is_some_and(available))
+            .count()).unwrap_or(i32::MAX);
+        let (ready, unready): (Vec<_>, Vec<_>) = outdated.partition(|(_, old)| available(old));
+        actions.extend(unready.into_iter().map(|(name, old)| delete(name, old)));
+        let budget = usize::try_from(max_unavailable - unavailable).unwrap_or_default();
+        actions.extend(ready.into_iter().take(budget).map(|(name, old)| delete(name, old)));
+    } else {
+        let mut surging = i32::try_from(running.iter()
+            .filter(|(_, old, updated)| old.is_some() && updated.is_some_and(|updated| !available(updated)))
+            .count()).unwrap_or(i32::MAX);
+        for (name, old) in outdated {
+            // Unavailable old pods are replaced at once, outside the surge.
+            if available(old) {
+                if surging >= max_surge {
+                    continue
+                }
+                surging += 1;
+            }
+            actions.push(Action::Create { node: name.to_owned(), revision: update_revision.to_owned() });
+        }
+    }
+    actions
+}
+
+fn is_failed(pod: &Pod) -> bool {
+    pod.status.as_ref().is_some_and(|status| status.phase == "Failed")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        apps::daemon_set::{RollingUpdateDaemonSet, Spec, UpdateStrategy},
+        client::Object,
+        core::node,
+        meta::Metadata,
+        IntOrString
+    };
+    use super::*;
+
+    fn node(name: &str) -> Node {
+        Node {
+            metadata: Metadata { name: Some(name.to_owned()), ..Metadata::default() },
+            spec: node::Spec::default(),
+            status: None
+        }
+    }
+
+    fn pod(name: &str, node: &str, revision: &str, ready: bool) -> Pod {
+        Pod::from_json(json!({
+            "metadata": { "name": name, "labels": { REVISION_LABEL: revision } },
+            "spec": { "nodeName": node, "containers": [{ "name": "agent" }] },
+            "status": {
+                "phase": "Running",
+                "conditions": [{
+                    "type": "Ready",
+                    "status": if ready { "True" } else { "False" },
+                    "lastTransitionTime": "2024-01-01T00:00:00Z"
+                }]
+            }
+        })).unwrap()
+    }
+
+    #[test]
+    fn counts_unavailable_updated_pods_against_max_unavailable() {
+        let set = DaemonSet {
+            metadata: None,
+            spec: Spec {
+                update_strategy: Some(UpdateStrategy {
+                    r#type: update_strategy::Type::RollingUpdate,
+                    rolling_update: Some(RollingUpdateDaemonSet {
+                        max_surge: Some(IntOrString::Int(0)),
+                        max_unavailable: Some(IntOrString::Int(1))
+                    })
+                }),
+                ..Spec::default()
+            },
+            status: None
+        };
+        let nodes = [node("a"), node("b"), node("c")];
+        let nodes: Vec<&Node> = nodes.iter().collect();
+        let old = [pod("a-old", "a", "1", true), pod("b-old", "b", "1", true),
+            pod("c-old", "c", "1", true)];
+        let delete = |node: &str, name: &str| Action::Delete {
+            node: node.to_owned(),
+            name: name.to_owned()
+        };
+
+        // The updated pod on `a` is not available yet, so `a` already uses up the budget.
+        let updating = pod("a-new", "a", "2", false);
+        let pods: Vec<&Pod> = old.iter().chain([&updating]).collect();
+        assert_eq!(next_actions(&set, &nodes, &pods, "2", Time::now()), []);
+
+        let updated = pod("a-new", "a", "2", true);
+        let pods: Vec<&Pod> = old.iter().chain([&updated]).collect();
+        assert_eq!(next_actions(&set, &nodes, &pods, "2", Time::now()),
+            [delete("a", "a-old"), delete("b", "b-old")]);
+    }
+}
@@ -1,17 +1,19 @@
 /*!
 - Concepts <https://kubernetes.io/docs/concepts/workloads/controllers/deployment/>
 - Reference <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/deployment-v1/>
-```toml
+```kdl
 */
-#![doc = include_str!("../../examples/controllers/nginx-deployment.toml")]
+#![doc = include_str!("../../examples/controllers/nginx-deployment.kdl")]
 /*!
 ```
 */
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
+use serde_json::{json, Map, Value};
 
 use crate::{
     core::pod_template::PodTemplateSpec,
+    decode::{decode_as, Fields},
     meta::{Condition, Selector, Metadata},
     IntOrString
 };
@@ -19,15 +21,18 @@ use crate::{
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/deployment-v1/#Deployment>
 #[derive(Debug, Decode)]
 pub struct Deployment {
-    metadata: Option<Metadata>,
-    spec: Option<Spec>,
-    status: Option<Status>
+    #[kfl(child, default)]
+    pub metadata: Option<Metadata>,
+    #[kfl(child, default)]
+    pub spec: Option<Spec>,
+    #[kfl(child, default)]
+    pub status: Option<Status>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/deployment-v1/#DeploymentSpec>
 ///
 /// Spec is the specification of the desired behaviour of the Deployment.
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Spec {
     /// Label selector for pods. Existing ReplicaSets whose pods are selected by this will be the ones affected by this deployment. It must match the pod template's labels.
     ///
@@ -46,7 +51,7 @@ pub struct Spec {
     /// > **Note**: You should not create other Pods whose labels match this selector, either directly, by creating another Deployment, or by creating another controller such as a ReplicaSet or a ReplicationController. If you do so, the first Deployment thinks that it created these other Pods. Kubernetes does not stop you from doing this.
     ///
     /// If you have multiple controllers that have overlapping selectors, the controllers will fight with each other and won't behave correctly.
-    selector: Selector,
+    pub selector: Selector,
 
     /// `template` describes the pods that will be created.
     ///
@@ -61,7 +66,7 @@ pub struct Spec {
     /// In addition to required fields for a Pod, a Pod template in a Deployment must specify appropriate labels and an appropriate restart policy. For labels, make sure not to overlap with other controllers. See [`selector`][Self::selector].
     ///
     /// Only a [`template.spec.restart_policy`][crate::core::pod::PodSpec::restart_policy] equal to `Always` is allowed, which is the default if not specified.
-    template: PodTemplateSpec,
+    pub template: PodTemplateSpec,
 
     /// Number of desired pods. This is a pointer to distinguish between explicit zero and not specified. Defaults to `1`.
     ///
@@ -76,7 +81,7 @@ pub struct Spec {
     /// If a [HorizontalPodAutoscaler](https://kubernetes.io/docs/tasks/run-application/horizontal-pod-autoscale/) (or any similar API for horizontal scaling) is managing scaling for a Deployment, don't set [`replicas`][Self::replicas].
     ///
     /// Instead, allow the Kubernetes control plane to manage the `replicas` field automatically.
-    replicas: Option<u16>,
+    pub replicas: Option<u16>,
 
     /// Minimum number of seconds for which a newly created pod should be ready without any of its container crashing, for it to be considered available. Defaults to `0` (pod will be considered available as soon as it is ready).
     ///
@@ -85,7 +90,7 @@ pub struct Spec {
     /// <https://kubernetes.io/docs/concepts/workloads/controllers/deployment/#min-ready-seconds>
     ///
     /// `min_ready_seconds` is an optional field that specifies the minimum number of seconds for which a newly created Pod should be ready without any of its containers crashing, for it to be considered available. This defaults to `0` (the Pod will be considered available as soon as it is ready). To learn more about when a Pod is considered ready, see [Container Probes](https://kubernetes.io/docs/concepts/workloads/pods/pod-lifecycle/#container-probes).
-    min_ready_seconds: Option<u16>,
+    pub min_ready_seconds: Option<u16>,
 
     /// The deployment strategy to use to replace existing pods with new ones.
     ///
@@ -100,7 +105,7 @@ pub struct Spec {
     /// > **Note**: This will only guarantee Pod termination previous to creation for upgrades. If you upgrade a Deployment, all Pods of the old revision will be terminated immediately. Successful removal is awaited before any Pod of the new revision is created. If you manually delete a Pod, the lifecycle is controlled by the ReplicaSet and the replacement will be created immediately (even if the old Pod is still in a Terminating state). If you need an 'at most' guarantee for your Pods, you should consider using a [StatefulSet](https://kubernetes.io/docs/concepts/workloads/controllers/statefulset/).
     ///
     /// - The Deployment updates Pods in a rolling update fashion when `strategy.type` = `RollingUpdate`. You can specify `max_unavailable` and `max_surge` to control the rolling update process.
-    strategy: Option<DeploymentStrategy>,
+    pub strategy: Option<DeploymentStrategy>,
 
     /// The number of old ReplicaSets to retain to allow rollback. This is a pointer to distinguish between explicit zero and not specified. Defaults to `10`.
     ///
@@ -113,7 +118,7 @@ pub struct Spec {
     /// `revision_history_limit` is an optional field that specifies the number of old ReplicaSets to retain to allow rollback. These old ReplicaSets consume resources in `etcd` and crowd the output of **kubectl get replicasets**. The configuration of each Deployment revision is stored in its ReplicaSets; therefore, once an old ReplicaSet is deleted, you lose the ability to rollback to that revision of Deployment. By default, `10` old ReplicaSets will be kept, however its ideal value depends on the frequency and stability of new Deployments.
     ///
     /// More specifically, setting this field to `0` means that all old ReplicaSets with 0 replicas will be cleaned up. In this case, a new Deployment rollout cannot be undone, since its revision history is cleaned up.
-    revision_history_limit: Option<u16>,
+    pub revision_history_limit: Option<u16>,
 
     /// The maximum time in seconds for a deployment to make progress before it is considered to be failed. The deployment controller will continue to process failed deployments and a condition with a `ProgressDeadlineExceeded` reason will be surfaced in the deployment status. Note that progress will not be estimated during the time a deployment is paused. Defaults to `600`.
     ///
@@ -124,7 +129,7 @@ pub struct Spec {
     /// `progress_deadline_seconds` is an optional field that specifies the number of seconds you want to wait for your Deployment to progress before the system reports back that the Deployment has [failed progressing](https://kubernetes.io/docs/concepts/workloads/controllers/deployment/#failed-deployment) - surfaced as a condition with `type`: `Progressing`, `status`: `False`. and `reason`: `ProgressDeadlineExceeded` in the status of the resource. The Deployment controller will keep retrying the Deployment. This defaults to 600. In the future, once automatic rollback will be implemented, the Deployment controller will roll back a Deployment as soon as it observes such a condition.
     ///
     /// If specified, this field needs to be greater than [`min_ready_seconds`][Self::min_ready_seconds].
-    progress_deadline_seconds: Option<u16>,
+    pub progress_deadline_seconds: Option<u16>,
     /// Indicates that the deployment is paused.
     ///
     /// # Concepts
@@ -132,29 +137,122 @@ pub struct Spec {
     /// <https://kubernetes.io/docs/concepts/workloads/controllers/deployment/#paused>
     ///
     /// `paused` is an optional boolean field for pausing and resuming a Deployment. The only difference between a paused Deployment and one that is not paused, is that any changes into the PodTemplateSpec of the paused Deployment will not trigger new rollouts as long as it is paused. A Deployment is not paused by default when it is created.
-    paused: Option<bool>
+    pub paused: Option<bool>
+}
+
+impl Decode for Spec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            selector: fields.child("selector")?.unwrap_or_default(),
+            template: fields.child("template")?.unwrap_or_default(),
+            replicas: fields.property("replicas")?,
+            min_ready_seconds: fields.property("min-ready-seconds")?,
+            strategy: fields.variant()?,
+            revision_history_limit: fields.property("revision-history-limit")?,
+            progress_deadline_seconds: fields.property("progress-deadline-seconds")?,
+            paused: fields.property("paused")?
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
+}
+
+impl Spec {
+    pub fn from_json(value: &Value) -> Self {
+        let int = |key: &str| value.get(key).and_then(Value::as_u64)
+            .and_then(|value| u16::try_from(value).ok());
+        let strategy = value.get("strategy");
+        Self {
+            selector: value.get("selector").map(Selector::from_json).unwrap_or_default(),
+            template: value.get("template").map(PodTemplateSpec::from_json).unwrap_or_default(),
+            replicas: int("replicas"),
+            min_ready_seconds: int("minReadySeconds"),
+            strategy: match strategy.and_then(|strategy| strategy.get("type")?.as_str()) {
+                Some("Recreate") => Some(DeploymentStrategy::Recreate),
+                Some("RollingUpdate") => {
+                    let rolling_update = strategy.and_then(|strategy| strategy.get("rollingUpdate"));
+                    let int_or_string = |key: &str| rolling_update
+                        .and_then(|rolling_update| rolling_update.get(key))
+                        .and_then(IntOrString::from_json);
+                    Some(DeploymentStrategy::RollingUpdate(RollingUpdateDeployment {
+                        max_surge: int_or_string("maxSurge"),
+                        max_unavailable: int_or_string("maxUnavailable")
+                    }))
+                }
+                _ => None
+            },
+            revision_history_limit: int("revisionHistoryLimit"),
+            progress_deadline_seconds: int("progressDeadlineSeconds"),
+            paused: value.get("paused").and_then(Value::as_bool)
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        object.insert("selector".to_owned(), self.selector.to_json());
+        object.insert("template".to_owned(), self.template.to_json());
+        let mut insert = |key: &str, value: Option<Value>| if let Some(value) = value {
+            object.insert(key.to_owned(), value);
+        };
+        insert("replicas", self.replicas.map(Value::from));
+        insert("minReadySeconds", self.min_ready_seconds.map(Value::from));
+        insert("strategy", self.strategy.as_ref().map(|strategy| match strategy {
+            DeploymentStrategy::Recreate => json!({ "type": "Recreate" }),
+            DeploymentStrategy::RollingUpdate(rolling_update) => {
+                let mut rolling = Map::new();
+                for (key, value) in [("maxSurge", &rolling_update.max_surge),
+                    ("maxUnavailable", &rolling_update.max_unavailable)]
+                {
+                    if let Some(value) = value {
+                        rolling.insert(key.to_owned(), value.to_json());
+                    }
+                }
+                json!({ "type": "RollingUpdate", "rollingUpdate": rolling })
+            }
+        }));
+        insert("revisionHistoryLimit", self.revision_history_limit.map(Value::from));
+        insert("progressDeadlineSeconds", self.progress_deadline_seconds.map(Value::from));
+        insert("paused", self.paused.map(Value::from));
+        Value::Object(object)
+    }
 }
 
 /// DeploymentStrategy describes how to replace existing pods with new ones.
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub enum DeploymentStrategy {
     Recreate,
     // #[default]
     RollingUpdate(RollingUpdateDeployment)
 }
 
+impl Decode for DeploymentStrategy {
+    fn decode(node: &kfl::ast::Node, ctx: &mut kfl::context::Context)
+        -> Result<Self, kfl::errors::DecodeError>
+    {
+        match node.node_name.as_ref() {
+            "recreate" => Ok(Self::Recreate),
+            "rolling-update" => decode_as(node, ctx).map(Self::RollingUpdate),
+            _ => Err(kfl::errors::DecodeError::conversion(ctx.span(&node),
+                "expected one of `recreate`, `rolling-update`"))
+        }
+    }
+}
+
 /// Spec to control the desired behaviour of rolling update.
 #[derive(Debug, Decode)]
 pub struct RollingUpdateDeployment {
     /**
     The maximum number of pods that can be scheduled above the desired number of pods. Value can be an absolute number (ex: `5`) or a percentage of desired pods (ex: `10%`). This can not be `0` if [`max_unavailable`][Self::max_unavailable] is `0`. Absolute number is calculated from percentage by rounding up. Defaults to `25%`. Example: when this is set to `30%`, the new ReplicaSet can be scaled up immediately when the rolling update starts, such that the total number of old and new pods do not exceed 130% of desired pods. Once old pods have been killed, new ReplicaSet can be scaled up further, ensuring that total number of pods running at any time during the update is at most 130% of desired pods.
     */
-    max_surge: Option<IntOrString>,
+    #[kfl(property, default)]
+    pub max_surge: Option<IntOrString>,
     
     /**
     The maximum number of pods that can be unavailable during the update. Value can be an absolute number (ex: `5`) or a percentage of desired pods (ex: `10%`). Absolute number is calculated from percentage by rounding down. This can not be `0` if [`max_surge`][Self::max_surge] is `0`. Defaults to `25%`. Example: when this is set to `30%`, the old ReplicaSet can be scaled down to 70% of desired pods immediately when the rolling update starts. Once new pods are ready, old ReplicaSet can be scaled down further, followed by scaling up the new ReplicaSet, ensuring that the total number of pods available at all times during the update is at least 70% of desired pods.
     */
-    max_unavailable: Option<IntOrString>
+    #[kfl(property, default)]
+    pub max_unavailable: Option<IntOrString>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/deployment-v1/#DeploymentStatus>
@@ -167,21 +265,66 @@ pub struct RollingUpdateDeployment {
 #[derive(Debug, Decode)]
 pub struct Status {
     /// Total number of non-terminated pods targeted by this deployment (their labels match the selector).
-    replicas: Option<u16>,
+    #[kfl(property, default)]
+    pub replicas: Option<u16>,
     /// Total number of available pods (ready for at least [`min_ready_seconds`][Spec::min_ready_seconds]) targeted by this deployment.
-    available_replicas: Option<u16>,
+    #[kfl(property, default)]
+    pub available_replicas: Option<u16>,
     /// `ready_replicas` is the number of pods targeted by this Deployment with a `Ready` Condition.
-    ready_replicas: Option<u16>,
+    #[kfl(property, default)]
+    pub ready_replicas: Option<u16>,
     /// Total number of unavailable pods targeted by this deployment. This is the total number of pods that are still required for the deployment to have 100% available capacity. They may either be pods that are running but not yet available or pods that still have not been created.
-    unavailable_replicas: Option<u16>,
+    #[kfl(property, default)]
+    pub unavailable_replicas: Option<u16>,
     /// Total number of non-terminated pods targeted by this deployment that have the desired template spec.
-    updated_replicas: Option<u16>,
+    #[kfl(property, default)]
+    pub updated_replicas: Option<u16>,
     /// Count of hash collisions for the Deployment. The Deployment controller uses this field as a collision avoidance mechanism when it needs to create the name for the newest ReplicaSet.
-    collision_count: Option<u16>,
+    #[kfl(property, default)]
+    pub collision_count: Option<u16>,
     /// Represents the latest available observations of a deployment's current state.
-    conditions: Vec<Condition>,
+    #[kfl(children)]
+    pub conditions: Vec<Condition>,
     /// The generation observed by the deployment controller.
-    observed_generation: Option<u32>,
+    #[kfl(property, default)]
+    pub observed_generation: Option<u32>,
+}
+
+impl Status {
+    pub fn from_json(value: &Value) -> Self {
+        let int = |key: &str| value.get(key).and_then(Value::as_u64)
+            .and_then(|value| u16::try_from(value).ok());
+        Self {
+            replicas: int("replicas"),
+            available_replicas: int("availableReplicas"),
+            ready_replicas: int("readyReplicas"),
+            unavailable_replicas: int("unavailableReplicas"),
+            updated_replicas: int("updatedReplicas"),
+            collision_count: int("collisionCount"),
+            conditions: value.get("conditions").and_then(Value::as_array).into_iter().flatten()
+                .filter_map(Condition::from_json)
+                .collect(),
+            observed_generation: value.get("observedGeneration").and_then(Value::as_u64)
+                .and_then(|generation| u32::try_from(generation).ok())
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        let mut insert = |key: &str, value: Option<Value>| if let Some(value) = value {
+            object.insert(key.to_owned(), value);
+        };
+        insert("replicas", self.replicas.map(Value::from));
+        insert("availableReplicas", self.available_replicas.map(Value::from));
+        insert("readyReplicas", self.ready_replicas.map(Value::from));
+        insert("unavailableReplicas", self.unavailable_replicas.map(Value::from));
+        insert("updatedReplicas", self.updated_replicas.map(Value::from));
+        insert("collisionCount", self.collision_count.map(Value::from));
+        insert("conditions", (!self.conditions.is_empty())
+            .then(|| self.conditions.iter().map(Condition::to_json).collect()));
+        insert("observedGeneration", self.observed_generation.map(Value::from));
+        Value::Object(object)
+    }
 }
 
 pub enum DeploymentConditionType {
@@ -197,3 +340,55 @@ pub enum DeploymentConditionReason {
     FailedCreate,
     NewReplicaSetAvailable
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::decode::decode;
+    use super::*;
+
+    #[test]
+    fn decodes_spec() {
+        let deployment: Deployment = decode("deployment.kdl", r#"
+            deployment {
+                metadata "web"
+                spec replicas=3 revision-history-limit=5 {
+                    selector {
+                        match-labels app="web"
+                        label-selector-requirement key="tier" operator="in" {
+                            values "frontend" "edge"
+                        }
+                    }
+                    template {
+                        metadata {
+                            labels app="web" tier="frontend"
+                        }
+                        spec {
+                            container "nginx" image="nginx:1.25"
+                        }
+                    }
+                    rolling-update max-surge=1 max-unavailable="10%"
+                }
+            }
+        "#).unwrap();
+        let spec = deployment.spec.unwrap();
+        assert_eq!(spec.replicas, Some(3));
+        assert_eq!(spec.revision_history_limit, Some(5));
+        let labels = &spec.template.metadata.as_ref().unwrap().labels;
+        assert!(spec.selector.matches(labels));
+        assert_eq!(spec.template.spec.containers[0].image.as_deref(), Some("nginx:1.25"));
+        let Some(DeploymentStrategy::RollingUpdate(rolling_update)) = spec.strategy else {
+            panic!("expected a rolling update, got {:?}", spec.strategy)
+        };
+        assert!(matches!(rolling_update.max_surge, Some(IntOrString::Int(1))));
+        assert!(matches!(rolling_update.max_unavailable, Some(IntOrString::String(ref value)) if value == "10%"));
+
+        let deployment: Deployment = decode("deployment.kdl", r#"
+            deployment {
+                spec {
+                    recreate
+                }
+            }
+        "#).unwrap();
+        assert!(matches!(deployment.spec.unwrap().strategy, Some(DeploymentStrategy::Recreate)));
+    }
+}
@@ -3,10 +3,11 @@
 - Reference <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/replica-set-v1/>
 */
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
     core::pod_template::PodTemplateSpec,
+    decode::Fields,
     meta::{
         condition::Condition,
         label_selector::Selector,
@@ -14,29 +15,62 @@ use crate::{
     },
 };
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct ReplicaSet {
-    metadata: Metadata,
-    spec: ReplicaSetSpec,
-    status: Option<ReplicaSetStatus>
+    pub metadata: Metadata,
+    pub spec: ReplicaSetSpec,
+    pub status: Option<ReplicaSetStatus>
+}
+
+impl Decode for ReplicaSet {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let replica_set = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            spec: fields.child("spec")?.ok_or_else(|| fields.missing("spec"))?,
+            status: fields.child("status")?
+        };
+        fields.finish()?;
+        Ok(replica_set)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/replica-set-v1/#ReplicaSetSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct ReplicaSetSpec {
-    selector: Selector,
-    template: Option<PodTemplateSpec>,
-    replicas: Option<i32>,
-    min_ready_seconds: Option<i32>,
+    pub selector: Selector,
+    pub template: Option<PodTemplateSpec>,
+    pub replicas: Option<i32>,
+    pub min_ready_seconds: Option<i32>,
+}
+
+impl Decode for ReplicaSetSpec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let replica_set_spec = Self {
+            selector: fields.child("selector")?.unwrap_or_default(),
+            template: fields.child("template")?,
+            replicas: fields.property("replicas")?,
+            min_ready_seconds: fields.property("min-ready-seconds")?
+        };
+        fields.finish()?;
+        Ok(replica_set_spec)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/replica-set-v1/#ReplicaSetStatus>
 #[derive(Debug, Decode)]
 pub struct ReplicaSetStatus {
-    replicas: i32,
-    available_replicas: Option<i32>,
-    ready_replicas: Option<i32>,
-    fully_labeled_replicas: Option<i32>,
-    conditions: Vec<Condition>,
-    observed_generation: Option<i64>
+    #[kfl(property, default)]
+    pub replicas: i32,
+    #[kfl(property, default)]
+    pub available_replicas: Option<i32>,
+    #[kfl(property, default)]
+    pub ready_replicas: Option<i32>,
+    #[kfl(property, default)]
+    pub fully_labeled_replicas: Option<i32>,
+    #[kfl(children)]
+    pub conditions: Vec<Condition>,
+    #[kfl(property, default)]
+    pub observed_generation: Option<i64>
 }
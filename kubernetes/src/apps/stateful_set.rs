@@ -3,95 +3,178 @@
 - Reference <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/stateful-set-v1/>
 */
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
 
 use crate::{
     core::{
         persistent_volume_claim::PersistentVolumeClaim,
         pod_template::PodTemplateSpec
     },
+    decode::Fields,
     meta::{
         condition::Condition,
         label_selector::Selector,
         metadata::Metadata,
     },
+    IntOrString
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/stateful-set-v1/#StatefulSet>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct StatefulSet {
-    metadata: Metadata,
-    spec: StatefulSetSpec,
-    status: Option<StatefulSetStatus>
+    pub metadata: Metadata,
+    pub spec: StatefulSetSpec,
+    pub status: Option<StatefulSetStatus>
+}
+
+impl Decode for StatefulSet {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let stateful_set = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            spec: fields.child("spec")?.ok_or_else(|| fields.missing("spec"))?,
+            status: fields.child("status")?
+        };
+        fields.finish()?;
+        Ok(stateful_set)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/stateful-set-v1/#StatefulSetSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct StatefulSetSpec {
     /// `service_name` is the name of the service that governs this StatefulSet. This service must exist before the StatefulSet, and is responsible for the network identity of the set. Pods get DNS/hostnames that follow the pattern: `pod-specific-string.serviceName.default.svc.cluster.local` where `"pod-specific-string"` is managed by the StatefulSet controller.
-    service_name: String,
+    pub service_name: String,
 
     /// `selector` is a label query over pods that should match the replica count. It must match the pod template's labels.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/overview/working-with-objects/labels/#label-selectors>
-    selector: Selector,
+    pub selector: Selector,
     /// `template` is the object that describes the pod that will be created if insufficient replicas are detected. Each pod stamped out by the StatefulSet will fulfill this Template, but have a unique identity from the rest of the StatefulSet.
-    template: PodTemplateSpec,
+    pub template: PodTemplateSpec,
     /// `replicas` is the desired number of replicas of the given Template. These are replicas in the sense that they are instantiations of the same Template, but individual replicas also have a consistent identity. If unspecified, defaults to `1`.
-    replicas: Option<u32>,
+    pub replicas: Option<u32>,
     /// `update_strategy` indicates the `StatefulSetUpdateStrategy` that will be employed to update Pods in the StatefulSet when a revision is made to Template.
-    update_strategy: Option<StatefulSetUpdateStrategy>,
+    pub update_strategy: Option<StatefulSetUpdateStrategy>,
     /// `pod_management_policy` controls how pods are created during initial scale up, when replacing pods on nodes, or when scaling down. The default policy is `OrderedReady`, where pods are created in increasing order (pod-0, then pod-1, etc) and the controller will wait until each pod is ready before continuing. When scaling down, the pods are removed in the opposite order. The alternative policy is `Parallel` which will create pods in parallel to match the desired scale without waiting, and on scale down will delete all pods at once.
-    pod_management_policy: Option<PodManagementPolicy>,
+    pub pod_management_policy: Option<PodManagementPolicy>,
     /// Maximum number of revisions that will be maintained in the StatefulSet's revision history. The revision history consists of all revisions not represented by a currently applied StatefulSetSpec version. The default value is `10`.
-    revision_history_limit: Option<u32>,
+    pub revision_history_limit: Option<u32>,
     /// List of claims that pods are allowed to reference. The StatefulSet controller is responsible for mapping network identities to claims in a way that maintains the identity of a pod. Every claim in this list must have at least one matching (by name) `volume_mount` in one container in the template. A claim in this list takes precedence over any volumes in the template, with the same name.
-    volume_claim_templates: Vec<PersistentVolumeClaim>,
+    pub volume_claim_templates: Vec<PersistentVolumeClaim>,
     /// Minimum number of seconds for which a newly created pod should be ready without any of its container crashing for it to be considered available. Defaults to `0` (pod will be considered available as soon as it is ready)
-    min_ready_seconds: Option<u32>,
+    pub min_ready_seconds: Option<u32>,
     /// `persistent_volume_claim_retention_policy` describes the lifecycle of persistent volume claims created from [`volume_claim_templates`][Self::volume_claim_templates]. By default, all persistent volume claims are created as needed and retained until manually deleted. This policy allows the lifecycle to be altered, for example by deleting persistent volume claims when their stateful set is deleted, or when their pod is scaled down. This requires the `StatefulSetAutoDeletePVC` feature gate to be enabled, which is alpha. +optional
-    persistent_volume_claim_retention_policy:
-        Option<StatefulSetPersistentVolumeClaimRetentionPolicy>,
+    pub persistent_volume_claim_retention_policy:
+        Option<StatefulSetPersistentVolumeClaimRetentionPolicy>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for StatefulSetSpec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let stateful_set_spec = Self {
+            service_name: fields.property("service-name")?.unwrap_or_default(),
+            selector: fields.child("selector")?.unwrap_or_default(),
+            template: fields.child("template")?.unwrap_or_default(),
+            replicas: fields.property("replicas")?,
+            update_strategy: fields.child("update-strategy")?,
+            pod_management_policy: fields.property("pod-management-policy")?,
+            revision_history_limit: fields.property("revision-history-limit")?,
+            volume_claim_templates: fields.children("persistent-volume-claim")?,
+            min_ready_seconds: fields.property("min-ready-seconds")?,
+            persistent_volume_claim_retention_policy: fields.child("persistent-volume-claim-retention-policy")?
+        };
+        fields.finish()?;
+        Ok(stateful_set_spec)
+    }
+}
+
+/// <https://kubernetes.io/docs/concepts/workloads/controllers/statefulset/#update-strategies>
+#[derive(Debug)]
 pub struct StatefulSetUpdateStrategy {
-    r#type: StatefulSetUpdateStrategyType,
-    rolling_update: RollingUpdateStatefulSetStrategy,
+    pub r#type: StatefulSetUpdateStrategyType,
+    /// Used only with [`RollingUpdate`][StatefulSetUpdateStrategyType::RollingUpdate].
+    pub rolling_update: Option<RollingUpdateStatefulSetStrategy>,
+}
+
+impl Decode for StatefulSetUpdateStrategy {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let stateful_set_update_strategy = Self {
+            r#type: fields.property("type")?.unwrap_or_default(),
+            rolling_update: fields.child("rolling-update")?
+        };
+        fields.finish()?;
+        Ok(stateful_set_update_strategy)
+    }
 }
 
-#[derive(Debug, Decode, Default)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum StatefulSetUpdateStrategyType {
+    /// Pods are replaced in decreasing ordinal order when the template changes.
     #[default]
     RollingUpdate,
+    /// Pods are replaced only once deleted by hand.
+    OnDelete
 }
 
 #[derive(Debug, Decode)]
 pub struct RollingUpdateStatefulSetStrategy {
-    max_unavailable: i32,
-    partition: i32,
+    /// The most pods, a number or a percentage of `replicas` rounded down to at least `1`, that can be unavailable during the update. Defaults to `1`.
+    #[kfl(property, default)]
+    pub max_unavailable: Option<IntOrString>,
+    /// The ordinal from which pods are updated; those below keep the current revision, even when recreated. Defaults to `0`.
+    #[kfl(property, default)]
+    pub partition: Option<u32>,
 }
 
-#[derive(Debug, Decode, Default)]
+/// <https://kubernetes.io/docs/concepts/workloads/controllers/statefulset/#pod-management-policies>
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum PodManagementPolicy {
+    /// One pod at a time, in increasing ordinal order up and decreasing down, each waiting for the one before to be running and ready.
     #[default]
     OrderedReady,
+    /// All pods at once.
     Parallel
 }
 
+/// <https://kubernetes.io/docs/concepts/workloads/controllers/statefulset/#persistentvolumeclaim-retention>
 #[derive(Debug, Decode)]
 pub struct StatefulSetPersistentVolumeClaimRetentionPolicy {
-    when_deleted: String,
-    when_scaled: String
+    /// What happens to the claims when the stateful set is deleted.
+    #[kfl(property, default)]
+    pub when_deleted: PersistentVolumeClaimRetentionPolicyType,
+    /// What happens to the claims of pods removed by scaling down.
+    #[kfl(property, default)]
+    pub when_scaled: PersistentVolumeClaimRetentionPolicyType
+}
+
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PersistentVolumeClaimRetentionPolicyType {
+    #[default]
+    Retain,
+    Delete
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/replication-controller-v1/#ReplicationControllerStatus>
 #[derive(Debug, Decode)]
 pub struct StatefulSetStatus {
-    replicas: i32,
-    available_replicas: Option<i32>,
-    ready_replicas: Option<i32>,
-    fully_labeled_replicas: Option<i32>,
-    conditions: Vec<Condition>,
-    observed_generation: Option<i64>
+    #[kfl(property, default)]
+    pub replicas: i32,
+    #[kfl(property, default)]
+    pub available_replicas: Option<i32>,
+    #[kfl(property, default)]
+    pub ready_replicas: Option<i32>,
+    #[kfl(property, default)]
+    pub fully_labeled_replicas: Option<i32>,
+    #[kfl(children)]
+    pub conditions: Vec<Condition>,
+    #[kfl(property, default)]
+    pub observed_generation: Option<i64>,
+    /// The revision of the pods below the partition.
+    #[kfl(property, default)]
+    pub current_revision: Option<String>,
+    /// The revision the pods are updated to.
+    #[kfl(property, default)]
+    pub update_revision: Option<String>
 }
@@ -0,0 +1,338 @@
+/*!
+What the stateful set controller does next to the pods of a stateful set, evaluated offline: which ordinals to create, which to delete and in what order, for scaling and for rolling updates.
+
+Pods are named `<set>-<ordinal>`, ordinals from `0` to `replicas - 1`, and claims from the `volume_claim_templates` of the set `<template>-<set>-<ordinal>`. The revision of a pod is its `controller-revision-hash` label.
+
+- Concepts <https://kubernetes.io/docs/concepts/workloads/controllers/statefulset/#deployment-and-scaling-guarantees>
+- Concepts <https://kubernetes.io/docs/concepts/workloads/controllers/statefulset/#rolling-updates>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/statefulset/stateful_set_control.go>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/statefulset/stateful_set_utils.go>
+*/
+
+use std::collections::BTreeMap;
+
+use crate::{
+    apps::stateful_set::{
+        PersistentVolumeClaimRetentionPolicyType, PodManagementPolicy, StatefulSet,
+        StatefulSetUpdateStrategyType
+    },
+    core::pod::Pod,
+    time::Time
+};
+
+/// Label of pods, the revision of the set they were created from.
+pub const REVISION_LABEL: &str = "controller-revision-hash";
+/// Label of pods, their own name, for services to select a single pod.
+pub const POD_NAME_LABEL: &str = "statefulset.kubernetes.io/pod-name";
+/// Label of pods, their ordinal.
+pub const POD_INDEX_LABEL: &str = "apps.kubernetes.io/pod-index";
+
+/// An action on a pod of the set, in the order to take them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Create the pod of `ordinal`, from the template of `revision` labelled with [`REVISION_LABEL`], [`POD_NAME_LABEL`] and [`POD_INDEX_LABEL`], after those of its `claims` that do not exist yet.
+    Create { ordinal: u32, name: String, revision: String, claims: Vec<String> },
+    /// Delete the pod of `ordinal`, failed or outdated, to create it again; its claims are kept.
+    Replace { ordinal: u32, name: String },
+    /// Delete the pod of `ordinal` beyond `replicas`, and `claims` if the retention policy deletes them when scaled down.
+    Condemn { ordinal: u32, name: String, claims: Vec<String> }
+}
+
+/// The revisions of the set: that of pods below the partition, and that pods are updated to.
+#[derive(Debug, Clone, Copy)]
+pub struct Revisions<'a> {
+    pub current: &'a str,
+    pub update: &'a str
+}
+
+/// The name of the pod of `ordinal` of `set`.
+pub fn pod_name(set: &StatefulSet, ordinal: u32) -> String {
+    format!("{}-{}", set.metadata.name.as_deref().unwrap_or_default(), ordinal)
+}
+
+/// The names of the claims of the pod of `ordinal` of `set`, one per volume claim template.
+pub fn claim_names(set: &StatefulSet, ordinal: u32) -> Vec<String> {
+    set.spec.volume_claim_templates.iter()
+        .map(|template| format!("{}-{}", template.metadata.name.as_deref().unwrap_or_default(),
+            pod_name(set, ordinal)))
+        .collect()
+}
+
+/// The ordinal of `pod` in `set`, if it is named as a pod of it.
+pub fn ordinal(set: &StatefulSet, pod: &Pod) -> Option<u32> {
+    pod.metadata.name.as_deref()?
+        .strip_prefix(set.metadata.name.as_deref()?)?
+        .strip_prefix('-')?
+        .parse().ok()
+}
+
+/// The revision of `pod`, empty if it has none.
+pub fn revision(pod: &Pod) -> &str {
+    pod.metadata.labels.get(REVISION_LABEL).map_or("", String::as_str)
+}
+
+/**
+The next actions of the controller on the `pods` of `set`, at `now`, for the pods to be `replicas` pods at the revisions they should be.
+
+With [`OrderedReady`][PodManagementPolicy::OrderedReady], a single pod is created or condemned at a time, and nothing more is done while any pod is not running and available: pods are created in increasing ordinal order and condemned in decreasing order. With [`Parallel`][PodManagementPolicy::Parallel], all of them are at once.
+
+Pods below the partition of a rolling update are created at the current revision, the others at the update revision. Once all pods exist, outdated pods from the highest ordinal down to the partition are replaced, as many at once as `max_unavailable` leaves unavailable; the replacements are created by the next call.
+*/
+pub fn next_actions(set: &StatefulSet, pods: &[&Pod], revisions: Revisions<'_>, now: Time)
+    -> Vec<Action>
+{
+    let replicas = set.spec.replicas.unwrap_or(1);
+    let ordered = set.spec.pod_management_policy.unwrap_or_default()
+        == PodManagementPolicy::OrderedReady;
+    let min_ready_seconds = set.spec.min_ready_seconds.unwrap_or_default();
+    let healthy = |pod: &Pod| !pod.is_terminating() && is_running(pod)
+        && pod.is_available(min_ready_seconds, now);
+    let strategy = set.spec.update_strategy.as_ref();
+    let rolling = strategy.is_none_or(|strategy|
+        strategy.r#type == StatefulSetUpdateStrategyType::RollingUpdate);
+    let partition = strategy.and_then(|strategy| strategy.rolling_update.as_ref())
+        .and_then(|rolling_update| rolling_update.partition)
+        .filter(|_| rolling)
+        .unwrap_or_default();
+
+    let mut members: BTreeMap<u32, &Pod> = BTreeMap::new();
+    let mut condemned: BTreeMap<u32, &Pod> = BTreeMap::new();
+    for pod in pods {
+        let Some(ordinal) = ordinal(set, pod) else { continue };
+        if ordinal < replicas {
+            members.insert(ordinal, pod);
+        } else {
+            condemned.insert(ordinal, pod);
+        }
+    }
+
+    let mut actions = Vec::new();
+    let create = |ordinal: u32| Action::Create {
+        ordinal,
+        name: pod_name(set, ordinal),
+        revision: if ordinal < partition { revisions.current } else { revisions.update }.to_owned(),
+        claims: claim_names(set, ordinal)
+    };
+    for ordinal in 0..replicas {
+        let Some(pod) = members.get(&ordinal) else {
+            actions.push(create(ordinal));
+            if ordered {
+                return actions
+            }
+            continue
+        };
+        if is_finished(pod) && !pod.is_terminating() {
+            actions.push(Action::Replace { ordinal, name: pod_name(set, ordinal) });
+            actions.push(create(ordinal));
+            if ordered {
+                return actions
+            }
+            continue
+        }
+        if ordered && !healthy(pod) {
+            return actions
+        }
+    }
+
+    let retention = set.spec.persistent_volume_claim_retention_policy.as_ref()
+        .map(|policy| policy.when_scaled).unwrap_or_default();
+    for (&ordinal, pod) in condemned.iter().rev() {
+        if pod.is_terminating() {
+            if ordered {
+                return actions
+            }
+            continue
+        }
+        actions.push(Action::Condemn {
+            ordinal,
+            name: pod_name(set, ordinal),
+            claims: match retention {
+                PersistentVolumeClaimRetentionPolicyType::Retain => Vec::new(),
+                PersistentVolumeClaimRetentionPolicyType::Delete => claim_names(set, ordinal)
+            }
+        });
+        if ordered {
+            return actions
+        }
+    }
+    if !actions.is_empty() || !rolling {
+        return actions
+    }
+
+    let max_unavailable = strategy.and_then(|strategy| strategy.rolling_update.as_ref())
+        .and_then(|rolling_update| rolling_update.max_unavailable.as_ref())
+        .and_then(|max_unavailable| max_unavailable.scaled_value(
+            i32::try_from(replicas).unwrap_or(i32::MAX), false))
+        .unwrap_or(1)
+        .max(1);
+    let updating: Vec<(u32, &Pod)> = members.range(partition..).rev()
+        .map(|(&ordinal, &pod)| (ordinal, pod))
+        .collect();
+    let mut unavailable = i32::try_from(updating.iter().filter(|(_, pod)| !healthy(pod)).count())
+        .unwrap_or(i32::MAX);
+    for (ordinal, pod) in updating {
+        if unavailable >= max_unavailable {
+            break
+        }
+        if revision(pod) != revisions.update && !pod.is_terminating() {
+            actions.push(Action::Replace { ordinal, name: pod_name(set, ordinal) });
+            if healthy(pod) {
+                unavailable += 1;
+            }
+        } else if ordered && !healthy(pod) {
+            // Ordered updates wait for each replacement to become available.
+            break
+        }
+    }
+    actions
+}
+
+fn is_running(pod: &Pod) -> bool {
+    pod.status.as_ref().is_some_and(|status| status.phase == "Running")
+}
+
+fn is_finished(pod: &Pod) -> bool {
+    pod.status.as_ref().is_some_and(|status| status.phase == "Failed" || status.phase == "Succeeded")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{client::Object, decode::decode};
+    use super::*;
+
+    fn stateful_set(spec: &str) -> StatefulSet {
+        decode("stateful-set.kdl", &format!(r#"
+            stateful-set {{
+                metadata "db"
+                {}
+            }}
+        "#, spec)).unwrap()
+    }
+
+    fn pod(ordinal: u32, revision: &str, ready: bool) -> Pod {
+        Pod::from_json(json!({
+            "metadata": {
+                "name": format!("db-{}", ordinal),
+                "labels": { REVISION_LABEL: revision }
+            },
+            "spec": { "containers": [{ "name": "db" }] },
+            "status": {
+                "phase": "Running",
+                "conditions": [{
+                    "type": "Ready",
+                    "status": if ready { "True" } else { "False" },
+                    "lastTransitionTime": "2024-01-01T00:00:00Z"
+                }]
+            }
+        })).unwrap()
+    }
+
+    fn create(ordinal: u32, revision: &str) -> Action {
+        Action::Create {
+            ordinal,
+            name: format!("db-{}", ordinal),
+            revision: revision.to_owned(),
+            claims: vec![format!("data-db-{}", ordinal)]
+        }
+    }
+
+    fn replace(ordinal: u32) -> Action {
+        Action::Replace { ordinal, name: format!("db-{}", ordinal) }
+    }
+
+    const REVISIONS: Revisions<'static> = Revisions { current: "1", update: "2" };
+
+    #[test]
+    fn creates_pods_in_order() {
+        let set = stateful_set(r#"
+            spec replicas=3 {
+                persistent-volume-claim {
+                    metadata "data"
+                }
+            }
+        "#);
+        let now = Time::now();
+        assert_eq!(next_actions(&set, &[], REVISIONS, now), [create(0, "2")]);
+        let pods = [pod(0, "2", true), pod(1, "2", false)];
+        let pods: Vec<&Pod> = pods.iter().collect();
+        // `db-1` is not ready, so `db-2` waits.
+        assert_eq!(next_actions(&set, &pods, REVISIONS, now), []);
+
+        let set = stateful_set(r#"
+            spec replicas=3 pod-management-policy="parallel" {
+                persistent-volume-claim {
+                    metadata "data"
+                }
+            }
+        "#);
+        assert_eq!(next_actions(&set, &pods, REVISIONS, now), [create(2, "2")]);
+        assert_eq!(claim_names(&set, 2), ["data-db-2"]);
+        assert_eq!(ordinal(&set, pods[1]), Some(1));
+    }
+
+    #[test]
+    fn condemns_pods_from_the_highest_ordinal() {
+        let pods = [pod(0, "1", true), pod(1, "1", true), pod(2, "1", true)];
+        let pods: Vec<&Pod> = pods.iter().collect();
+        let now = Time::now();
+        let set = stateful_set(r#"
+            spec replicas=1 {
+                persistent-volume-claim {
+                    metadata "data"
+                }
+                persistent-volume-claim-retention-policy when-scaled="delete"
+            }
+        "#);
+        assert_eq!(next_actions(&set, &pods, REVISIONS, now), [Action::Condemn {
+            ordinal: 2,
+            name: "db-2".to_owned(),
+            claims: vec!["data-db-2".to_owned()]
+        }]);
+
+        let set = stateful_set(r#"spec replicas=1 pod-management-policy="parallel""#);
+        let condemn = |ordinal: u32| Action::Condemn {
+            ordinal,
+            name: format!("db-{}", ordinal),
+            claims: Vec::new()
+        };
+        assert_eq!(next_actions(&set, &pods, REVISIONS, now), [condemn(2), condemn(1)]);
+    }
+
+    #[test]
+    fn updates_pods_down_to_the_partition() {
+        let now = Time::now();
+        let set = stateful_set(r#"
+            spec replicas=3 {
+                update-strategy {
+                    rolling-update partition=1
+                }
+            }
+        "#);
+        let old = [pod(0, "1", true), pod(1, "1", true), pod(2, "1", true)];
+        let pods: Vec<&Pod> = old.iter().collect();
+        assert_eq!(next_actions(&set, &pods, REVISIONS, now), [replace(2)]);
+
+        let updated = pod(2, "2", true);
+        let pods = [&old[0], &old[1], &updated];
+        assert_eq!(next_actions(&set, &pods, REVISIONS, now), [replace(1)]);
+
+        // Below the partition pods are created again at the current revision.
+        let pods = [&old[1], &updated];
+        assert_eq!(next_actions(&set, &pods, REVISIONS, now), [Action::Create {
+            ordinal: 0,
+            name: "db-0".to_owned(),
+            revision: "1".to_owned(),
+            claims: Vec::new()
+        }]);
+
+        let set = stateful_set(r#"
+            spec replicas=3 {
+                update-strategy type="on-delete"
+            }
+        "#);
+        let pods: Vec<&Pod> = old.iter().collect();
+        assert_eq!(next_actions(&set, &pods, REVISIONS, now), []);
+    }
+}
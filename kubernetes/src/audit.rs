@@ -4,9 +4,10 @@ Reference <https://kubernetes.io/docs/reference/config-api/apiserver-audit.v1/>
 
 use std::collections::HashMap;
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
+    decode::Fields,
     meta::{
         metadata::Metadata,
         status::Status
@@ -21,27 +22,53 @@ use crate::{
 // }
 
 /// <https://kubernetes.io/docs/reference/config-api/apiserver-audit.v1/#audit-k8s-io-v1-Event>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Event {
-    level: Level,
+    pub level: Level,
     // #[kfl(rename(serialize = "auditID"))]
-    audit_id: String,
-    stage: Stage,
+    pub audit_id: String,
+    pub stage: Stage,
     // #[kfl(rename(serialize = "requestURI"))]
-    request_uri: String,
-    verb: String,
-    user: UserInfo,
-    impersonated_user: UserInfo,
+    pub request_uri: String,
+    pub verb: String,
+    pub user: UserInfo,
+    pub impersonated_user: UserInfo,
     // #[kfl(rename(serialize = "sourceIPs"))]
-    source_ips: Vec<String>,
-    user_agent: Option<String>,
-    object_ref: Option<ObjectReference>,
-    response_status: Option<Status>,
-    request_object: Option<String>,
-    response_pbject: Option<String>,
-    request_received_timestamp: Option<MicroTime>,
-    stage_timestamp: Option<MicroTime>,
-    annotations: HashMap<String, String>,
+    pub source_ips: Vec<String>,
+    pub user_agent: Option<String>,
+    pub object_ref: Option<ObjectReference>,
+    pub response_status: Option<Status>,
+    pub request_object: Option<String>,
+    pub response_pbject: Option<String>,
+    pub request_received_timestamp: Option<MicroTime>,
+    pub stage_timestamp: Option<MicroTime>,
+    pub annotations: HashMap<String, String>,
+}
+
+impl Decode for Event {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let event = Self {
+            level: fields.property("level")?.unwrap_or_default(),
+            audit_id: fields.property("audit-id")?.unwrap_or_default(),
+            stage: fields.property("stage")?.unwrap_or_default(),
+            request_uri: fields.property("request-uri")?.unwrap_or_default(),
+            verb: fields.property("verb")?.unwrap_or_default(),
+            user: fields.child("user")?.ok_or_else(|| fields.missing("user"))?,
+            impersonated_user: fields.child("impersonated-user")?.ok_or_else(|| fields.missing("impersonated-user"))?,
+            source_ips: fields.values("source-ips")?,
+            user_agent: fields.property("user-agent")?,
+            object_ref: fields.child("object-ref")?,
+            response_status: fields.child("response-status")?,
+            request_object: fields.property("request-object")?,
+            response_pbject: fields.property("response-pbject")?,
+            request_received_timestamp: fields.property("request-received-timestamp")?,
+            stage_timestamp: fields.property("stage-timestamp")?,
+            annotations: fields.entries("annotations")?
+        };
+        fields.finish()?;
+        Ok(event)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/apiserver-audit.v1/#audit-k8s-io-v1-Level>
@@ -53,49 +80,103 @@ pub type Stage = String;
 /// <https://kubernetes.io/docs/reference/config-api/apiserver-audit.v1/#audit-k8s-io-v1-ObjectReference>
 #[derive(Debug, Decode)]
 pub struct ObjectReference {
-    resource: Option<String>,
-    namespace: Option<String>,
-    name: Option<String>,
-    uid: Option<String>,
-    api_group: Option<String>,
-    api_version: Option<String>,
-    resource_version: Option<String>,
-    subresource: Option<String>,
+    #[kfl(property, default)]
+    pub resource: Option<String>,
+    #[kfl(property, default)]
+    pub namespace: Option<String>,
+    #[kfl(property, default)]
+    pub name: Option<String>,
+    #[kfl(property, default)]
+    pub uid: Option<String>,
+    #[kfl(property, default)]
+    pub api_group: Option<String>,
+    #[kfl(property, default)]
+    pub api_version: Option<String>,
+    #[kfl(property, default)]
+    pub resource_version: Option<String>,
+    #[kfl(property, default)]
+    pub subresource: Option<String>,
 }
 
 /// <https://kubernetes.io/docs/reference/generated/kubernetes-api/v1.25/#userinfo-v1-authentication-k8s-io>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct UserInfo {
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/apiserver-audit.v1/#audit-k8s-io-v1-Policy>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Policy {
-    metadata: Metadata,
-    rules: Vec<PolicyRule>,
-    omit_stages: Vec<Stage>,
-    omit_managed_fields: Option<bool>
+    pub metadata: Metadata,
+    pub rules: Vec<PolicyRule>,
+    pub omit_stages: Vec<Stage>,
+    pub omit_managed_fields: Option<bool>
+}
+
+impl Decode for Policy {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let policy = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            rules: fields.children("policy-rule")?,
+            omit_stages: fields.values("omit-stages")?,
+            omit_managed_fields: fields.property("omit-managed-fields")?
+        };
+        fields.finish()?;
+        Ok(policy)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/apiserver-audit.v1/#audit-k8s-io-v1-PolicyRule>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct PolicyRule {
-    level: Level,
-    users: Vec<String>,
-    user_groups: Vec<String>,
-    verbs: Vec<String>,
-    resources: Vec<GroupResources>,
-    namespaces: Vec<String>,
-    #[kfl(rename(serialize = "nonResourceURLs"))]
-    non_resource_urls: Vec<String>,
-    omit_stages: Vec<Stage>,
-    omit_managed_fields: Option<bool>
+    pub level: Level,
+    pub users: Vec<String>,
+    pub user_groups: Vec<String>,
+    pub verbs: Vec<String>,
+    pub resources: Vec<GroupResources>,
+    pub namespaces: Vec<String>,
+    // #[kfl(rename(serialize = "nonResourceURLs"))]
+    pub non_resource_urls: Vec<String>,
+    pub omit_stages: Vec<Stage>,
+    pub omit_managed_fields: Option<bool>
+}
+
+impl Decode for PolicyRule {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let policy_rule = Self {
+            level: fields.property("level")?.unwrap_or_default(),
+            users: fields.values("users")?,
+            user_groups: fields.values("user-groups")?,
+            verbs: fields.values("verbs")?,
+            resources: fields.children("group-resources")?,
+            namespaces: fields.values("namespaces")?,
+            non_resource_urls: fields.values("non-resource-urls")?,
+            omit_stages: fields.values("omit-stages")?,
+            omit_managed_fields: fields.property("omit-managed-fields")?
+        };
+        fields.finish()?;
+        Ok(policy_rule)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/apiserver-audit.v1/#audit-k8s-io-v1-GroupResources>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct GroupResources {
-    group: String,
-    resources: Vec<String>,
-    resource_names: Vec<String>
+    pub group: String,
+    pub resources: Vec<String>,
+    pub resource_names: Vec<String>
+}
+
+impl Decode for GroupResources {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let group_resources = Self {
+            group: fields.property("group")?.unwrap_or_default(),
+            resources: fields.values("resources")?,
+            resource_names: fields.values("resource-names")?
+        };
+        fields.finish()?;
+        Ok(group_resources)
+    }
 }
@@ -1,10 +1,8 @@
 pub mod client;
+pub mod exec;
 pub mod token_request;
 pub mod token_review;
 
-use kfl::Decode;
-
-pub use client::Client;
 pub use token_request::TokenRequest;
 pub use token_review::TokenReview;
 
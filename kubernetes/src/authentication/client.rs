@@ -1,41 +1,280 @@
-//! Reference <https://kubernetes.io/docs/reference/config-api/client-authentication.v1/>
+//! - Reference <https://kubernetes.io/docs/reference/config-api/client-authentication.v1/>
+//! - Concepts <https://kubernetes.io/docs/reference/access-authn-authz/authentication/#client-go-credential-plugins>
+//! - Source <https://github.com/kubernetes/client-go/blob/master/plugin/pkg/client/auth/exec/exec.go>
 
-use kfl::Decode;
+use std::fmt;
 
-use crate::time::Time;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
+use serde_json::{json, Map, Value};
+
+use crate::{decode::Fields, time::Time};
 
 // #[derive(Debug, Decode)]
 // pub enum Client {
 //     ExecCredential(ExecCredential)
 // }
 
+/// API versions of the exec protocol, newest first.
+pub const API_VERSIONS: [&str; 2] = [
+    "client.authentication.k8s.io/v1",
+    "client.authentication.k8s.io/v1beta1"
+];
+
 /// <https://kubernetes.io/docs/reference/config-api/client-authentication.v1/#client-authentication-k8s-io-v1-ExecCredential>
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct ExecCredential {
-    spec: ExecCredentialSpec,
-    status: Option<ExecCredentialStatus>
+    /// Written by the client to the plugin's `KUBERNETES_EXEC_INFO` environment variable.
+    pub spec: ExecCredentialSpec,
+    /// Written by the plugin to its stdout.
+    pub status: Option<ExecCredentialStatus>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for ExecCredential {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let exec_credential = Self {
+            spec: fields.child("spec")?.unwrap_or_default(),
+            status: fields.child("status")?
+        };
+        fields.finish()?;
+        Ok(exec_credential)
+    }
+}
+
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
 pub struct ExecCredentialSpec {
-    cluster: Option<Cluster>,
-    interactive: bool
+    /// The cluster the credentials are for, only given if [`provide_cluster_info`][crate::kubectl::config::ExecConfig::provide_cluster_info] is set.
+    #[kfl(child, default)]
+    pub cluster: Option<Cluster>,
+    /// Whether the plugin may read from the standard input of the client, to prompt the user.
+    #[kfl(property, default)]
+    pub interactive: bool
 }
 
-#[derive(Debug, Decode)]
+/// <https://kubernetes.io/docs/reference/config-api/client-authentication.v1/#client-authentication-k8s-io-v1-Cluster>
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
 pub struct Cluster {
-    server: String,
-    tls_server_name: Option<String>,
-    insecure_skip_tls_verify: Option<bool>,
-    certificate_authority_data: Vec<u8>,
-    proxy_url: Option<String>,
-    config: Option<String>
+    #[kfl(property, default)]
+    pub server: String,
+    #[kfl(property, default)]
+    pub tls_server_name: Option<String>,
+    #[kfl(property, default)]
+    pub insecure_skip_tls_verify: Option<bool>,
+    /// PEM-encoded certificate authorities, base64 encoded on the wire.
+    #[kfl(property, default)]
+    pub certificate_authority_data: Vec<u8>,
+    #[kfl(property, default)]
+    pub proxy_url: Option<String>,
+    /// The `client.authentication.k8s.io/exec` extension of the cluster in the kubeconfig, as JSON.
+    #[kfl(property, default)]
+    pub config: Option<String>
 }
 
-#[derive(Debug, Decode)]
+/// <https://kubernetes.io/docs/reference/config-api/client-authentication.v1/#client-authentication-k8s-io-v1-ExecCredentialStatus>
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
 pub struct ExecCredentialStatus {
-    expiration_timestamp: Option<Time>,
-    token: String,
-    client_certificate_data: String,
-    client_key_data: String
+    /// When the credentials expire; they are cached until then, or for as long as the client runs if unset.
+    #[kfl(property, default)]
+    pub expiration_timestamp: Option<Time>,
+    /// Bearer token, empty if the plugin returned a client certificate.
+    #[kfl(property, default)]
+    pub token: String,
+    /// PEM-encoded client certificate, empty if the plugin returned a token.
+    #[kfl(property, default)]
+    pub client_certificate_data: String,
+    /// PEM-encoded private key of the client certificate.
+    #[kfl(property, default)]
+    pub client_key_data: String
+}
+
+impl ExecCredential {
+    /// Serialises the credential as it is written to `KUBERNETES_EXEC_INFO`.
+    pub fn to_json(&self, api_version: &str) -> String {
+        let mut spec = Map::new();
+        if let Some(cluster) = &self.spec.cluster {
+            spec.insert("cluster".to_owned(), cluster.to_json());
+        }
+        spec.insert("interactive".to_owned(), self.spec.interactive.into());
+        let mut object = Map::new();
+        object.insert("apiVersion".to_owned(), api_version.into());
+        object.insert("kind".to_owned(), "ExecCredential".into());
+        object.insert("spec".to_owned(), Value::Object(spec));
+        if let Some(status) = &self.status {
+            object.insert("status".to_owned(), status.to_json());
+        }
+        Value::Object(object).to_string()
+    }
+
+    /// Parses a credential as a plugin writes it to stdout, and returns it along with the API version it was written in.
+    pub fn from_json(input: &[u8]) -> Result<(Self, String), Error> {
+        let object = match serde_json::from_slice(input) {
+            Ok(Value::Object(object)) => object,
+            Ok(_) => return Err(Error::InvalidResponse("expected a JSON object".to_owned())),
+            Err(error) => return Err(Error::InvalidResponse(error.to_string()))
+        };
+        match object.get("kind").and_then(Value::as_str) {
+            Some("ExecCredential") => {}
+            kind => return Err(Error::InvalidResponse(format!(
+                "expected kind ExecCredential, got {:?}", kind.unwrap_or_default())))
+        }
+        let api_version = match object.get("apiVersion").and_then(Value::as_str) {
+            Some(version) if API_VERSIONS.contains(&version) => version.to_owned(),
+            version => return Err(Error::UnsupportedVersion(version.unwrap_or_default().to_owned()))
+        };
+        let spec = object.get("spec");
+        let credential = Self {
+            spec: ExecCredentialSpec {
+                cluster: spec.and_then(|spec| spec.get("cluster"))
+                    .filter(|cluster| !cluster.is_null())
+                    .map(Cluster::from_json)
+                    .transpose()?,
+                interactive: spec.and_then(|spec| spec.get("interactive"))
+                    .and_then(Value::as_bool)
+                    .unwrap_or_default()
+            },
+            status: object.get("status").filter(|status| !status.is_null())
+                .map(ExecCredentialStatus::from_json)
+                .transpose()?
+        };
+        Ok((credential, api_version))
+    }
+}
+
+impl Cluster {
+    fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        object.insert("server".to_owned(), self.server.clone().into());
+        if let Some(name) = &self.tls_server_name {
+            object.insert("tls-server-name".to_owned(), name.clone().into());
+        }
+        if let Some(insecure) = self.insecure_skip_tls_verify {
+            object.insert("insecure-skip-tls-verify".to_owned(), insecure.into());
+        }
+        if !self.certificate_authority_data.is_empty() {
+            object.insert("certificate-authority-data".to_owned(),
+                STANDARD.encode(&self.certificate_authority_data).into());
+        }
+        if let Some(proxy_url) = &self.proxy_url {
+            object.insert("proxy-url".to_owned(), proxy_url.clone().into());
+        }
+        if let Some(config) = &self.config {
+            object.insert("config".to_owned(),
+                serde_json::from_str(config).unwrap_or_else(|_| config.clone().into()));
+        }
+        Value::Object(object)
+    }
+
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        let string = |name: &str| value.get(name).and_then(Value::as_str).map(str::to_owned);
+        let certificate_authority_data = match string("certificate-authority-data") {
+            Some(data) => STANDARD.decode(data).map_err(|_| Error::InvalidResponse(
+                "certificate-authority-data is not base64".to_owned()))?,
+            None => Vec::new()
+        };
+        Ok(Self {
+            server: string("server").unwrap_or_default(),
+            tls_server_name: string("tls-server-name"),
+            insecure_skip_tls_verify: value.get("insecure-skip-tls-verify").and_then(Value::as_bool),
+            certificate_authority_data,
+            proxy_url: string("proxy-url"),
+            config: value.get("config").filter(|config| !config.is_null()).map(Value::to_string)
+        })
+    }
 }
+
+impl ExecCredentialStatus {
+    fn to_json(&self) -> Value {
+        let mut object = json!({
+            "token": self.token,
+            "clientCertificateData": self.client_certificate_data,
+            "clientKeyData": self.client_key_data
+        });
+        if let Some(expiration) = self.expiration_timestamp {
+            object["expirationTimestamp"] = expiration.to_string().into();
+        }
+        object
+    }
+
+    fn from_json(value: &Value) -> Result<Self, Error> {
+        let string = |name: &str| value.get(name).and_then(Value::as_str)
+            .unwrap_or_default().to_owned();
+        let expiration_timestamp = match value.get("expirationTimestamp") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(time)) => Some(time.parse().map_err(|error| Error::InvalidResponse(
+                format!("invalid expirationTimestamp {:?}: {}", time, error)))?),
+            Some(time) => return Err(Error::InvalidResponse(format!(
+                "invalid expirationTimestamp {}", time)))
+        };
+        Ok(Self {
+            expiration_timestamp,
+            token: string("token"),
+            client_certificate_data: string("clientCertificateData"),
+            client_key_data: string("clientKeyData")
+        })
+    }
+
+    /// Checks that the status holds a token or a client certificate with its key, as the client requires of plugins.
+    pub fn validate(&self) -> Result<(), Error> {
+        match (self.client_certificate_data.is_empty(), self.client_key_data.is_empty()) {
+            (false, true) => Err(Error::InvalidResponse(
+                "exec plugin: invalid output: client certificate without key".to_owned())),
+            (true, false) => Err(Error::InvalidResponse(
+                "exec plugin: invalid output: client key without certificate".to_owned())),
+            (true, true) if self.token.is_empty() => Err(Error::InvalidResponse(
+                "exec plugin didn't return a token or cert/key pair".to_owned())),
+            _ => Ok(())
+        }
+    }
+
+    /// Whether the credentials have expired at `now`.
+    pub fn is_expired(&self, now: Time) -> bool {
+        self.expiration_timestamp.is_some_and(|expiration| expiration <= now)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Running the plugin or talking to it failed.
+    Io(String),
+    /// The plugin exited unsuccessfully.
+    Exec {
+        command: String,
+        status: Option<i32>,
+        stderr: String,
+        /// The `installHint` of the exec configuration, shown when the command is not found.
+        install_hint: Option<String>
+    },
+    /// The plugin needs the standard input of the client, which is not available.
+    Interactive(String),
+    /// The plugin's output is not a valid `ExecCredential`.
+    InvalidResponse(String),
+    /// The API version is not one of [`API_VERSIONS`], or the plugin did not answer in the version it was asked in.
+    UnsupportedVersion(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(message) | Self::InvalidResponse(message) => f.write_str(message),
+            Self::Exec { command, status, stderr, install_hint } => {
+                write!(f, "exec: executable {} failed with exit code {}", command,
+                    status.map_or("unknown".to_owned(), |status| status.to_string()))?;
+                if !stderr.is_empty() {
+                    write!(f, ": {}", stderr.trim_end())?;
+                }
+                match install_hint {
+                    Some(hint) => write!(f, "\n\n{}", hint),
+                    None => Ok(())
+                }
+            }
+            Self::Interactive(command) => write!(f,
+                "exec plugin {} is configured to use interactive mode but no standard input is \
+                available", command),
+            Self::UnsupportedVersion(version) =>
+                write!(f, "exec plugin: unsupported API version {:?}", version)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
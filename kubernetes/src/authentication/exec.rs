@@ -0,0 +1,160 @@
+/*!
+Running credential plugins, the exec plugins of kubeconfigs and the kubelet's image credential providers: a command given a request on its standard input or in its environment, answering on its standard output.
+
+- Source <https://github.com/kubernetes/client-go/blob/master/plugin/pkg/client/auth/exec/exec.go>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/credentialprovider/plugin/plugin.go>
+*/
+
+use std::{
+    ffi::OsString,
+    io::{self, ErrorKind, Read, Write},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant}
+};
+
+/// How often a plugin with a timeout is checked for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// What the standard input of a plugin is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Stdin {
+    #[default]
+    Null,
+    /// That of this process, for plugins prompting the user.
+    Inherit,
+    /// The request, written in full and closed.
+    Bytes(Vec<u8>)
+}
+
+/// A plugin command and how it is run.
+#[derive(Debug, Clone, Default)]
+pub struct Runner {
+    pub command: OsString,
+    pub args: Vec<String>,
+    /// Added to the environment of this process.
+    pub env: Vec<(String, String)>,
+    pub stdin: Stdin,
+    /// Whether the standard error of the plugin goes to that of this process, so the user sees its prompts and messages, rather than into [`Output::stderr`].
+    pub inherit_stderr: bool,
+    /// How long the plugin may run before it is killed, without limit if `None`.
+    pub timeout: Option<Duration>
+}
+
+impl Runner {
+    pub fn new(command: impl Into<OsString>) -> Self {
+        Self { command: command.into(), ..Self::default() }
+    }
+
+    /**
+    Runs the plugin to completion and returns what it wrote to its standard output, and to its standard error unless that is inherited.
+
+    Fails with the error of spawning it, [`ErrorKind::NotFound`] if the command does not exist, or with [`ErrorKind::TimedOut`] once it has run for longer than the [`timeout`][Self::timeout].
+    */
+    pub fn run(&self) -> io::Result<Output> {
+        let mut child = Command::new(&self.command)
+            .args(&self.args)
+            .envs(self.env.iter().map(|(name, value)| (name, value)))
+            .stdin(match self.stdin {
+                Stdin::Null => Stdio::null(),
+                Stdin::Inherit => Stdio::inherit(),
+                Stdin::Bytes(_) => Stdio::piped()
+            })
+            .stdout(Stdio::piped())
+            .stderr(if self.inherit_stderr { Stdio::inherit() } else { Stdio::piped() })
+            .spawn()?;
+        // The streams are served on threads of their own, so that a plugin filling one pipe while the other is waited on does not deadlock.
+        let writer = match (&self.stdin, child.stdin.take()) {
+            (Stdin::Bytes(bytes), Some(mut stdin)) => {
+                let bytes = bytes.clone();
+                Some(thread::spawn(move || stdin.write_all(&bytes)))
+            }
+            _ => None
+        };
+        let stdout = child.stdout.take().map(read_to_end);
+        let stderr = child.stderr.take().map(read_to_end);
+        let status = match self.timeout {
+            Some(timeout) => wait_timeout(&mut child, timeout),
+            None => child.wait()
+        }?;
+        let join = |reader: Option<JoinHandle<io::Result<Vec<u8>>>>| reader
+            .map_or(Ok(Vec::new()), |reader| reader.join().unwrap_or_else(|_| Ok(Vec::new())));
+        let output = Output { status, stdout: join(stdout)?, stderr: join(stderr)? };
+        if let Some(writer) = writer {
+            match writer.join() {
+                // A plugin may exit without reading its input.
+                Ok(Err(error)) if error.kind() != ErrorKind::BrokenPipe => return Err(error),
+                _ => {}
+            }
+        }
+        Ok(output)
+    }
+}
+
+fn read_to_end(mut stream: impl Read + Send + 'static) -> JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        stream.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    })
+}
+
+/// Waits for `child` to exit, killing it once `timeout` has passed.
+fn wait_timeout(child: &mut Child, timeout: Duration) -> io::Result<ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status)
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(ErrorKind::TimedOut,
+                format!("timed out after {:?}", timeout)))
+        }
+        thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> Runner {
+        Runner {
+            args: vec!["-c".to_owned(), script.to_owned()],
+            ..Runner::new("sh")
+        }
+    }
+
+    #[test]
+    fn writes_the_request_and_reads_the_response() {
+        let runner = Runner {
+            stdin: Stdin::Bytes(b"request".to_vec()),
+            env: vec![("SUFFIX".to_owned(), "!".to_owned())],
+            ..shell("cat; printf \"$SUFFIX\"; echo oops >&2; exit 3")
+        };
+        let output = runner.run().unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stdout, b"request!");
+        assert_eq!(output.stderr, b"oops\n");
+    }
+
+    #[test]
+    fn kills_plugins_running_past_the_timeout() {
+        let runner = Runner {
+            timeout: Some(Duration::from_millis(100)),
+            ..shell("sleep 10")
+        };
+        let start = Instant::now();
+        assert_eq!(runner.run().unwrap_err().kind(), ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn missing_commands_are_not_found() {
+        let error = Runner::new("/nonexistent/plugin").run().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+}
@@ -1,43 +1,63 @@
 //! References <https://kubernetes.io/docs/reference/kubernetes-api/authentication-resources/token-request-v1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
+    decode::Fields,
     meta::metadata::Metadata,
     time::Time
 };
 
 #[derive(Debug, Decode)]
 pub struct TokenRequest {
-    metadata: Metadata,
-    spec: Spec,
-    status: Option<Status>
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child)]
+    pub spec: Spec,
+    #[kfl(child, default)]
+    pub status: Option<Status>
 }
 
 /// TokenRequestSpec contains client provided parameters of a token request.
 /// 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/authentication-resources/token-request-v1/#TokenRequestSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Spec {
     /// Intendend audiences of the token. A recipient of a token must identify themself with an identifier in the list of audiences of the token, and otherwise should reject the token. A token issued for multiple audiences may be used to authenticate against any of the audiences listed but implies a high degree of trust between the target audiences.
-    audiences: Vec<String>,
+    pub audiences: Vec<String>,
     /// Reference to an object that the token will be bound to. The token will only be valid for as long as the bound object exists. NOTE: The API server's TokenReview endpoint will validate the BoundObjectRef, but other audiences may not. Keep ExpirationSeconds small if you want prompt revocation.
-    bound_object_ref: Option<BoundObjectReference>,
+    pub bound_object_ref: Option<BoundObjectReference>,
     /// Requested duration of validity of the request. The token issuer may return a token with a different validity duration so a client needs to check the `expiration` field in a response.
-    expiration_seconds: u32
+    pub expiration_seconds: u32
+}
+
+impl Decode for Spec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            audiences: fields.values("audiences")?,
+            bound_object_ref: fields.child("bound-object-ref")?,
+            expiration_seconds: fields.property("expiration-seconds")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct BoundObjectReference {
-    kind: Option<bound_object_ref::Kind>,
-    name: Option<String>,
-    uid: Option<String>
+    #[kfl(property, default)]
+    pub kind: Option<bound_object_ref::Kind>,
+    #[kfl(property, default)]
+    pub name: Option<String>,
+    #[kfl(property, default)]
+    pub uid: Option<String>
 }
 
 pub mod bound_object_ref {
     use kfl::DecodeScalar;
 
-    #[derive(Debug, DecodeScalar)]
+    #[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
     pub enum Kind {
         Pop,
         Secret
@@ -48,7 +68,9 @@ pub mod bound_object_ref {
 #[derive(Debug, Decode)]
 pub struct Status {
     /// Time of expiration of the returned token.
-    expiration_timestamp: Time,
+    #[kfl(property, default)]
+    pub expiration_timestamp: Time,
     /// Token is the opaque bearer token.
-    token: String
+    #[kfl(property, default)]
+    pub token: String
 }
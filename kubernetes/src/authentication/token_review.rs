@@ -2,18 +2,28 @@
 References <https://kubernetes.io/docs/reference/kubernetes-api/authentication-resources/token-review-v1/>
 */
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
-use crate::{
-    meta::metadata::Metadata,
-    time::Time
-};
+use crate::{decode::Fields, meta::metadata::Metadata};
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct TokenReview {
-    metadata: Metadata,
-    spec: TokenReviewSpec,
-    status: Option<TokenReviewStatus>
+    pub metadata: Metadata,
+    pub spec: TokenReviewSpec,
+    pub status: Option<TokenReviewStatus>
+}
+
+impl Decode for TokenReview {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let token_review = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            spec: fields.child("spec")?.ok_or_else(|| fields.missing("spec"))?,
+            status: fields.child("status")?
+        };
+        fields.finish()?;
+        Ok(token_review)
+    }
 }
 
 /**
@@ -21,7 +31,7 @@ pub struct TokenReview {
 
 TokenReviewSpec is a description of the token authentication request.
 */
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct TokenReviewSpec {
 }
 
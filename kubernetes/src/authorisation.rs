@@ -1,9 +1,5 @@
 pub mod rbac;
 
-use kfl::Decode;
-
-pub use rbac::Rbac;
-
 // #[derive(Debug, Decode)]
 // pub enum Authorisation {
 //     #[kfl(rename = "")]
@@ -19,14 +19,17 @@ pub use role_binding::RoleBinding;
 // }
 
 /// RoleRef contains information that points to the role being used.
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct RoleRef {
     /// Group for the resource being referenced.
-    api_group: String,
+    #[kfl(property, default)]
+    pub api_group: String,
     /// Kind of resource being referenced.
-    kind: String,
+    #[kfl(property, default)]
+    pub kind: String,
     /// Name of resource being referenced.
-    name: String
+    #[kfl(property, default)]
+    pub name: String
 }
 
 /// Subject contains a reference to the object or user identities a role binding applies to. This can either hold a direct API object reference, or a value for non-objects such as user and group names.
@@ -3,19 +3,23 @@
 use kfl::Decode;
 
 use crate::{
-    authorisation::rbac::role::PolicyRule,
+    authorisation::rbac::role::Rule as PolicyRule,
     meta::{Selector, Metadata},
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/authorization-resources/cluster-role-v1/#ClusterRole>
 #[derive(Debug, Decode)]
 pub struct ClusterRole {
-    metadata: Metadata,
-    aggregation_rule: Option<AggregationRule>,
-    rules: Vec<PolicyRule>
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child, default)]
+    pub aggregation_rule: Option<AggregationRule>,
+    #[kfl(children)]
+    pub rules: Vec<PolicyRule>
 }
 
 #[derive(Debug, Decode)]
 pub struct AggregationRule {
-    cluster_role_selectors: Vec<Selector>,
+    #[kfl(children)]
+    pub cluster_role_selectors: Vec<Selector>,
 }
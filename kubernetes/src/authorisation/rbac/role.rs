@@ -1,21 +1,38 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/authorization-resources/role-v1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
-use crate::meta::metadata::Metadata;
+use crate::{decode::Fields, meta::metadata::Metadata};
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/authorization-resources/role-v1/#Role>
 #[derive(Debug, Decode)]
 pub struct Role {
-    metadata: Metadata,
-    rules: Vec<Rule>
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(children)]
+    pub rules: Vec<Rule>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Rule {
-    api_groups: Vec<String>,
-    resources: Vec<String>,
-    verbs: Vec<String>,
-    resource_names: Vec<String>,
-    non_resource_urls: Vec<String>,
+    pub api_groups: Vec<String>,
+    pub resources: Vec<String>,
+    pub verbs: Vec<String>,
+    pub resource_names: Vec<String>,
+    pub non_resource_urls: Vec<String>,
+}
+
+impl Decode for Rule {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let rule = Self {
+            api_groups: fields.values("api-groups")?,
+            resources: fields.values("resources")?,
+            verbs: fields.values("verbs")?,
+            resource_names: fields.values("resource-names")?,
+            non_resource_urls: fields.values("non-resource-urls")?
+        };
+        fields.finish()?;
+        Ok(rule)
+    }
 }
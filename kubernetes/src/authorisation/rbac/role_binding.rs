@@ -8,8 +8,11 @@ use super::{RoleRef, Subject};
 /// <https://kubernetes.io/docs/reference/kubernetes-api/authorization-resources/role-binding-v1/#RoleBinding>
 #[derive(Debug, Decode)]
 pub struct RoleBinding {
-    metadata: Metadata,
-    role_ref: RoleRef,
-    subjects: Vec<Subject>,
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child)]
+    pub role_ref: RoleRef,
+    #[kfl(children)]
+    pub subjects: Vec<Subject>,
 
 }
@@ -1,7 +1,5 @@
 pub mod horizontal_pod_autoscaler;
 
-use kfl::Decode;
-
 pub use horizontal_pod_autoscaler::HorizontalPodAutoscaler;
 
 // #[derive(Debug, Decode)]
@@ -3,9 +3,10 @@
 //!   - <https://kubernetes.io/docs/tasks/run-application/horizontal-pod-autoscale/>
 //!   - <https://kubernetes.io/docs/tasks/run-application/horizontal-pod-autoscale-walkthrough/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
+    decode::Fields,
     meta::{condition::Condition, metadata::Metadata},
     time::Time
 };
@@ -13,23 +14,41 @@ use crate::{
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/horizontal-pod-autoscaler-v2/#HorizontalPodAutoscaler>
 #[derive(Debug, Decode)]
 pub struct HorizontalPodAutoscaler {
-    metadata: Metadata,
-    spec: Spec,
-    status: Option<Status>
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child)]
+    pub spec: Spec,
+    #[kfl(child, default)]
+    pub status: Option<Status>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/horizontal-pod-autoscaler-v2/#HorizontalPodAutoscalerSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Spec {
-    max_replicas: i32,
-    scale_target_ref: CrossVersionObjectReference,
-    min_replicas: Option<i32>,
-    behaviour: Option<Behaviour>,
-    metrics: Vec<Metric>,
+    pub max_replicas: i32,
+    pub scale_target_ref: CrossVersionObjectReference,
+    pub min_replicas: Option<i32>,
+    pub behaviour: Option<Behaviour>,
+    pub metrics: Vec<Metric>,
 
 }
 
-#[derive(Debug, Decode)]
+impl Decode for Spec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            max_replicas: fields.property("max-replicas")?.unwrap_or_default(),
+            scale_target_ref: fields.child("scale-target-ref")?.unwrap_or_default(),
+            min_replicas: fields.property("min-replicas")?,
+            behaviour: fields.child("behaviour")?,
+            metrics: fields.children("metric")?
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
+}
+
+#[derive(Debug, Decode, Default)]
 pub struct CrossVersionObjectReference {
 }
 
@@ -44,12 +63,18 @@ pub struct Metric {
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/horizontal-pod-autoscaler-v2/#StatHorizontalPodAutoscalerStatusus>
 #[derive(Debug, Decode)]
 pub struct Status {
-    desired_replicas: i32,
-    conditions: Vec<Condition>,
-    current_metrics: Vec<status::Metric>,
-    current_replicas: Option<i32>,
-    last_scale_time: Option<Time>,
-    observed_generation: Option<i64>
+    #[kfl(property, default)]
+    pub desired_replicas: i32,
+    #[kfl(children)]
+    pub conditions: Vec<Condition>,
+    #[kfl(children)]
+    pub current_metrics: Vec<status::Metric>,
+    #[kfl(property, default)]
+    pub current_replicas: Option<i32>,
+    #[kfl(property, default)]
+    pub last_scale_time: Option<Time>,
+    #[kfl(property, default)]
+    pub observed_generation: Option<i64>
 }
 
 pub mod status {
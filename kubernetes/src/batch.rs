@@ -1,7 +1,6 @@
 pub mod cron_job;
 pub mod job;
-
-use kfl::Decode;
+pub mod job_state;
 
 pub use cron_job::CronJob;
 pub use job::Job;
@@ -0,0 +1,370 @@
+/*!
+The job controller as a state machine, evaluated offline: from the pods of a job, the pods to create and delete, and the status of the job, its conditions included.
+
+Failed pods are matched against the rules of the pod failure policy in order: `FailJob` fails the job at once, `Ignore` does not count the failure, and `Count`, like failures no rule matches, counts it towards the backoff limit. Each failure is counted once, while the pod has the [`TRACKING_FINALISER`] it is created with, which is then removed; the count survives the pod. Pods are created again after failures with exponential backoff, from 10 seconds doubling up to 6 minutes. Pods are kept until the job finishes, so that they can be counted.
+
+- Concepts <https://kubernetes.io/docs/concepts/workloads/controllers/job/#handling-pod-and-container-failures>
+- Concepts <https://kubernetes.io/docs/concepts/workloads/controllers/job/#completion-mode>
+- Concepts <https://kubernetes.io/docs/concepts/workloads/controllers/job/#pod-failure-policy>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/job/job_controller.go>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/job/indexed_job_utils.go>
+*/
+
+use std::{
+    cmp::Reverse,
+    collections::BTreeSet,
+    fmt,
+    time::Duration
+};
+
+use crate::{
+    batch::job::{Action, CompletionMode, ConditionType, Job, Status},
+    core::pod::Pod,
+    meta::condition::{self, Condition, ConditionStatus},
+    time::Time
+};
+
+/// Annotation of the pods of `Indexed` jobs, their completion index.
+pub const COMPLETION_INDEX: &str = "batch.kubernetes.io/job-completion-index";
+/// Finaliser of the pods of jobs, created with it, until the job has counted them.
+pub const TRACKING_FINALISER: &str = "batch.kubernetes.io/job-tracking";
+
+/// Wait before creating pods again after the first failure, doubled after each other.
+const BACKOFF_BASE: Duration = Duration::from_secs(10);
+const BACKOFF_MAX: Duration = Duration::from_secs(360);
+
+/// A set of completion indexes, written as ordered ranges such as `1,3-5,7`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Indexes(pub BTreeSet<u32>);
+
+impl Indexes {
+    /// Parses the ranges `s` of a job of `completions`, dropping the indexes from `completions` on, which it does not have.
+    pub fn parse(s: &str, completions: u32) -> Result<Self, Error> {
+        let mut indexes = BTreeSet::new();
+        for range in s.split(',').filter(|range| !range.is_empty()) {
+            let invalid = || Error::InvalidIndexes(s.to_owned());
+            let (first, last): (u32, u32) = match range.split_once('-') {
+                Some((first, last)) => (first.parse().map_err(|_| invalid())?,
+                    last.parse().map_err(|_| invalid())?),
+                None => {
+                    let index = range.parse().map_err(|_| invalid())?;
+                    (index, index)
+                }
+            };
+            if first > last {
+                return Err(invalid())
+            }
+            if first < completions {
+                indexes.extend(first..=last.min(completions - 1));
+            }
+        }
+        Ok(Self(indexes))
+    }
+}
+
+impl fmt::Display for Indexes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut indexes = self.0.iter().copied().peekable();
+        let mut first = true;
+        while let Some(start) = indexes.next() {
+            let mut end = start;
+            while indexes.peek() == Some(&(end + 1)) {
+                end += 1;
+                indexes.next();
+            }
+            if !first {
+                f.write_str(",")?;
+            }
+            first = false;
+            if start == end {
+                write!(f, "{}", start)?;
+            } else {
+                write!(f, "{}-{}", start, end)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    InvalidIndexes(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidIndexes(indexes) => write!(f, "invalid completion indexes `{}`", indexes)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The completion index of `pod`, if it is a pod of an `Indexed` job.
+pub fn completion_index(pod: &Pod) -> Option<u32> {
+    pod.metadata.annotations.get(COMPLETION_INDEX)?.parse().ok()
+}
+
+/// How long pods wait to be created again after `failures` failures.
+pub fn backoff(failures: u32) -> Duration {
+    match failures {
+        0 => Duration::ZERO,
+        failures => 2u32.checked_pow(failures - 1)
+            .and_then(|factor| BACKOFF_BASE.checked_mul(factor))
+            .map_or(BACKOFF_MAX, |wait| wait.min(BACKOFF_MAX))
+    }
+}
+
+/// What [`sync`] decided.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// One per pod to create, with its completion index for `Indexed` jobs.
+    pub create: Vec<Option<u32>>,
+    /// The names of the pods to delete.
+    pub delete: Vec<String>,
+    /// The names of the failed pods counted, whose [`TRACKING_FINALISER`] to remove.
+    pub untrack: Vec<String>,
+    /// The status of the job once the pods are created and deleted.
+    pub status: Status,
+    /// When to sync again, to create pods after backoff or to fail the job on its deadline.
+    pub requeue_after: Option<Duration>
+}
+
+/**
+Syncs `job` with its `pods` at `now`, as the controller does on each change: counts the succeeded and failed pods, completes or fails the job, and otherwise creates or deletes pods for `parallelism` of them to run, no more than the completions left.
+
+The job fails when a failure matches a `FailJob` rule, when more than `backoff_limit` failures count, or when it has been active longer than `active_deadline_seconds`; its active pods are deleted. It completes when `completions` pods succeeded, or, without `completions`, when any pod succeeded and none is active. Suspended jobs have their active pods deleted, and their start time reset.
+
+Pods of `Indexed` jobs are created for the lowest indexes neither completed nor running; pods of indexes completed or run twice are deleted.
+*/
+pub fn sync(job: &Job, pods: &[&Pod], now: Time) -> Outcome {
+    let spec = &job.spec;
+    let mut status = job.status.clone().unwrap_or_default();
+    let mut outcome = Outcome { create: Vec::new(), delete: Vec::new(), untrack: Vec::new(),
+        status: Status::default(), requeue_after: None };
+    if condition::is_true(&status.conditions, &ConditionType::Complete)
+        || condition::is_true(&status.conditions, &ConditionType::Failed)
+    {
+        outcome.status = status;
+        return outcome
+    }
+    let indexed = spec.completion_mode.unwrap_or_default() == CompletionMode::Indexed;
+    let name = |pod: &Pod| pod.metadata.name.clone().unwrap_or_default();
+
+    let mut active: Vec<&Pod> = pods.iter().copied()
+        .filter(|pod| !matches!(phase(pod), "Succeeded" | "Failed") && !pod.is_terminating())
+        .collect();
+    active.sort_by_key(|pod| (pod.metadata.creation_timestamp, name(pod)));
+
+    let mut failed = status.failed;
+    let mut last_failure = None;
+    let mut fail_job = None;
+    for pod in pods.iter().filter(|pod| phase(pod) == "Failed") {
+        let rule = spec.pod_failure_policy.as_ref()
+            .and_then(|policy| policy.rules.iter().position(|rule| rule.matches(pod))
+                .map(|index| (index, policy.rules[index].action)));
+        let counted = !pod.metadata.has_finaliser(TRACKING_FINALISER);
+        if !counted {
+            outcome.untrack.push(name(pod));
+        }
+        match rule {
+            Some((_, Action::Ignore)) => continue,
+            Some((index, Action::FailJob)) if !counted => {
+                fail_job.get_or_insert_with(|| format!(
+                    "Pod {}/{} failed matching FailJob rule at index {}",
+                    pod.metadata.namespace.as_deref().unwrap_or("default"), name(pod), index));
+            }
+            Some((_, Action::FailJob | Action::Count)) | None => {}
+        }
+        last_failure = last_failure.max(finished_at(pod));
+        if !counted {
+            failed = failed.saturating_add(1);
+        }
+    }
+
+    let succeeded_pods = pods.iter().filter(|pod| phase(pod) == "Succeeded");
+    let mut completed = Indexes::default();
+    let succeeded = if indexed {
+        completed = Indexes::parse(&status.completed_indexes, spec.completions.unwrap_or_default())
+            .unwrap_or_default();
+        completed.0.extend(succeeded_pods.filter_map(|pod| completion_index(pod))
+            .filter(|index| spec.completions.is_none_or(|completions| *index < completions)));
+        completed.0.len()
+    } else {
+        succeeded_pods.count()
+    };
+    status.succeeded = u32::try_from(succeeded).unwrap_or(u32::MAX);
+    status.failed = failed;
+    if indexed {
+        status.completed_indexes = completed.to_string();
+    }
+
+    let deadline = spec.active_deadline_seconds.zip(status.start_time)
+        .map(|(seconds, start)| start.0 + Duration::from_secs(seconds));
+    let failure = if let Some(message) = fail_job {
+        Some(("PodFailurePolicy", message))
+    } else if failed > spec.backoff_limit {
+        Some(("BackoffLimitExceeded", "Job has reached the specified backoff limit".to_owned()))
+    } else if deadline.is_some_and(|deadline| deadline <= now.0) {
+        Some(("DeadlineExceeded", "Job was active longer than specified deadline".to_owned()))
+    } else {
+        None
+    };
+    let complete = match spec.completions {
+        Some(completions) => status.succeeded >= completions,
+        None => status.succeeded > 0 && active.is_empty()
+    };
+    let set = |status: &mut Status, r#type: ConditionType, value: ConditionStatus, reason: &str,
+        message: &str| condition::set(&mut status.conditions,
+            Condition::new(r#type, value, reason, message, now));
+
+    if let Some((reason, message)) = failure {
+        outcome.delete = active.iter().map(|pod| name(pod)).collect();
+        set(&mut status, ConditionType::FailureTarget, ConditionStatus::True, reason, &message);
+        set(&mut status, ConditionType::Failed, ConditionStatus::True, reason, &message);
+        status.active = 0;
+        status.ready = 0;
+        outcome.status = status;
+        return outcome
+    }
+    if complete {
+        outcome.delete = active.iter().map(|pod| name(pod)).collect();
+        set(&mut status, ConditionType::Complete, ConditionStatus::True, "CompletionsReached",
+            "Reached expected number of succeeded pods");
+        status.completion_time = Some(now);
+        status.active = 0;
+        status.ready = 0;
+        outcome.status = status;
+        return outcome
+    }
+    if spec.suspend == Some(true) {
+        outcome.delete = active.iter().map(|pod| name(pod)).collect();
+        set(&mut status, ConditionType::Suspended, ConditionStatus::True, "JobSuspended",
+            "Job suspended");
+        status.start_time = None;
+        status.active = 0;
+        status.ready = 0;
+        outcome.status = status;
+        return outcome
+    }
+    if condition::is_true(&status.conditions, &ConditionType::Suspended) {
+        set(&mut status, ConditionType::Suspended, ConditionStatus::False, "JobResumed",
+            "Job resumed");
+    }
+    let start = *status.start_time.get_or_insert(now);
+    if let Some(seconds) = spec.active_deadline_seconds {
+        let deadline = start.0 + Duration::from_secs(seconds);
+        outcome.requeue_after = Some(deadline.duration_since(now.0).unwrap_or_default());
+    }
+
+    if indexed {
+        let mut running = BTreeSet::new();
+        active.retain(|pod| match completion_index(pod) {
+            Some(index) if spec.completions.is_some_and(|completions| index < completions)
+                && !completed.0.contains(&index) && running.insert(index) => true,
+            _ => {
+                outcome.delete.push(name(pod));
+                false
+            }
+        });
+    }
+    let parallelism = spec.parallelism.unwrap_or(1);
+    let current = u32::try_from(active.len()).unwrap_or(u32::MAX);
+    let wanted = match spec.completions {
+        None if status.succeeded > 0 => current,
+        None => parallelism,
+        Some(completions) => parallelism.min(completions.saturating_sub(status.succeeded))
+    };
+    if current > wanted {
+        // Pods not ready, then the newest, are the cheapest to lose.
+        active.sort_by_key(|pod| (pod.is_ready(), Reverse(pod.metadata.creation_timestamp)));
+        let excess = (current - wanted) as usize;
+        outcome.delete.extend(active.drain(..excess).map(name));
+    } else if current < wanted {
+        let ready_at = last_failure.map(|last: Time| last.0 + backoff(failed));
+        match ready_at.and_then(|ready_at| ready_at.duration_since(now.0).ok())
+            .filter(|wait| !wait.is_zero())
+        {
+            Some(wait) => outcome.requeue_after = Some(outcome.requeue_after
+                .map_or(wait, |requeue_after| requeue_after.min(wait))),
+            None if indexed => {
+                let taken: BTreeSet<u32> = active.iter().filter_map(|pod| completion_index(pod))
+                    .collect();
+                outcome.create = (0..spec.completions.unwrap_or_default())
+                    .filter(|index| !completed.0.contains(index) && !taken.contains(index))
+                    .take((wanted - current) as usize)
+                    .map(Some)
+                    .collect();
+            }
+            None => outcome.create = vec![None; (wanted - current) as usize]
+        }
+    }
+    status.active = u32::try_from(active.len()).unwrap_or(u32::MAX);
+    status.ready = u32::try_from(active.iter().filter(|pod| pod.is_ready()).count())
+        .unwrap_or(u32::MAX);
+    outcome.status = status;
+    outcome
+}
+
+/// When the last container of `pod` terminated.
+fn finished_at(pod: &Pod) -> Option<Time> {
+    let status = pod.status.as_ref()?;
+    status.init_container_statuses.iter().chain(&status.container_statuses)
+        .filter_map(|container| container.state.as_ref()?.terminated.as_ref()?.finished_at)
+        .max()
+}
+
+fn phase(pod: &Pod) -> &str {
+    pod.status.as_ref().map_or("", |status| status.phase.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        batch::job::Spec,
+        core::pod,
+        meta::Metadata
+    };
+    use super::*;
+
+    #[test]
+    fn indexes() {
+        let indexes = Indexes::parse("1,3-5,7", 10).unwrap();
+        assert_eq!(indexes.0, BTreeSet::from([1, 3, 4, 5, 7]));
+        assert_eq!(indexes.to_string(), "1,3-5,7");
+        assert_eq!(Indexes::parse("", 10).unwrap(), Indexes::default());
+        assert_eq!(Indexes::parse("2-4294967295,4000000000", 4).unwrap().to_string(), "2-3");
+        assert!(Indexes::parse("5-3", 10).is_err());
+        assert!(Indexes::parse("1,,x", 10).is_err());
+    }
+
+    fn failed(name: &str, tracked: bool) -> Pod {
+        Pod {
+            metadata: Metadata {
+                name: Some(name.to_owned()),
+                finalisers: tracked.then(|| TRACKING_FINALISER.to_owned()).into_iter().collect(),
+                ..Metadata::default()
+            },
+            status: Some(pod::Status { phase: "Failed".to_owned(), ..pod::Status::default() }),
+            ..Pod::default()
+        }
+    }
+
+    #[test]
+    fn failures_are_counted_once() {
+        let mut job = Job {
+            metadata: Metadata::default(),
+            spec: Spec { backoff_limit: 6, ..Spec::default() },
+            status: None
+        };
+        let mut sync = |pods: &[Pod]| {
+            let outcome = sync(&job, &pods.iter().collect::<Vec<_>>(), Time::now());
+            job.status = Some(outcome.status.clone());
+            (outcome.status.failed, outcome.untrack)
+        };
+        assert_eq!(sync(&[failed("a", true)]), (1, vec!["a".to_owned()]));
+        assert_eq!(sync(&[failed("a", false), failed("b", true)]), (2, vec!["b".to_owned()]));
+        assert_eq!(sync(&[]), (2, Vec::new()));
+    }
+}
@@ -1,4 +1,6 @@
+pub mod approver;
 pub mod certificate_signing_request;
+pub mod signer;
 
 // use kfl::Decode;
 
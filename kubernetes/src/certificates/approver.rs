@@ -0,0 +1,168 @@
+/*!
+Approving and denying certificate signing requests, by hand as `kubectl certificate approve` does, or automatically for the client certificates of kubelets as kube-controller-manager's `csrapproving` controller does.
+
+- Concepts <https://kubernetes.io/docs/reference/access-authn-authz/certificate-signing-requests/#approval-rejection>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/certificates/approver/sarapprove.go>
+*/
+
+use std::fmt;
+
+use crate::time::Time;
+
+use super::{
+    certificate_signing_request::{CertificateConditionType, CertificateSigningRequest, Spec},
+    signer::{self, Request}
+};
+
+/// Reason `kubectl certificate approve` approves with.
+pub const KUBECTL_APPROVE: &str = "KubectlApprove";
+/// Reason `kubectl certificate deny` denies with.
+pub const KUBECTL_DENY: &str = "KubectlDeny";
+/// Reason requests are approved automatically with.
+pub const AUTO_APPROVED: &str = "AutoApproved";
+
+/// Approves `csr` so its signer issues the certificate, unless it was denied.
+pub fn approve(csr: &mut CertificateSigningRequest, reason: &str, message: &str, now: Time)
+    -> Result<(), Error>
+{
+    if csr.set_condition(CertificateConditionType::Approved, reason, message, now) {
+        Ok(())
+    } else {
+        Err(Error::Denied)
+    }
+}
+
+/// Denies `csr` so its signer never issues the certificate, unless it was approved.
+pub fn deny(csr: &mut CertificateSigningRequest, reason: &str, message: &str, now: Time)
+    -> Result<(), Error>
+{
+    if csr.set_condition(CertificateConditionType::Denied, reason, message, now) {
+        Ok(())
+    } else {
+        Err(Error::Approved)
+    }
+}
+
+/**
+Approves a pending request for a kubelet client certificate when its requester is allowed to create the `certificatesigningrequests` subresource checked by `allowed`, `selfnodeclient` for renewing its own certificate, the common name being its user name, or else `nodeclient` for bootstrapping one, returning whether it did.
+
+Requests for other signers or breaking the rules of [`KUBE_APISERVER_CLIENT_KUBELET`][signer::KUBE_APISERVER_CLIENT_KUBELET] are left for someone else to approve or deny; kubelet serving certificates in particular are never approved automatically.
+*/
+pub fn auto_approve(
+    csr: &mut CertificateSigningRequest,
+    allowed: impl Fn(&Spec, &str) -> bool,
+    now: Time
+) -> Result<bool, Error> {
+    if !csr.is_pending() || csr.spec.signer_name != signer::KUBE_APISERVER_CLIENT_KUBELET {
+        return Ok(false)
+    }
+    let request = Request::parse(&csr.spec.request)?;
+    let Ok(usages) = signer::parse_usages(&csr.spec.usages) else {
+        return Ok(false)
+    };
+    if signer::validate(&csr.spec.signer_name, &request, &usages).is_err() {
+        return Ok(false)
+    }
+    let message = if request.common_name == csr.spec.username
+        && allowed(&csr.spec, "selfnodeclient")
+    {
+        "Auto approving self kubelet client certificate after SubjectAccessReview."
+    } else if allowed(&csr.spec, "nodeclient") {
+        "Auto approving kubelet client certificate after SubjectAccessReview."
+    } else {
+        return Ok(false)
+    };
+    approve(csr, AUTO_APPROVED, message, now)?;
+    Ok(true)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request was already approved, so cannot be denied.
+    Approved,
+    /// The request was already denied, so cannot be approved.
+    Denied,
+    Request(signer::Error)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Approved => f.write_str("certificate signing request is already approved"),
+            Self::Denied => f.write_str("certificate signing request is already denied"),
+            Self::Request(error) => error.fmt(f)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<signer::Error> for Error {
+    fn from(error: signer::Error) -> Self {
+        Self::Request(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::{CertificateParams, DistinguishedName, DnType, KeyPair};
+
+    use crate::meta::Metadata;
+    use super::*;
+
+    /// A request for the client certificate of the kubelet of `node`, by `username`.
+    fn kubelet_request(node: &str, username: &str) -> CertificateSigningRequest {
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.distinguished_name = DistinguishedName::new();
+        params.distinguished_name.push(DnType::CommonName, format!("system:node:{}", node));
+        params.distinguished_name.push(DnType::OrganizationName, signer::NODES_GROUP);
+        let key = KeyPair::generate().unwrap();
+        CertificateSigningRequest {
+            metadata: Metadata::default(),
+            spec: Spec {
+                request: params.serialize_request(&key).unwrap().pem().unwrap().into_bytes(),
+                signer_name: signer::KUBE_APISERVER_CLIENT_KUBELET.to_owned(),
+                usages: vec!["digital signature".to_owned(), "client auth".to_owned()],
+                username: Some(username.to_owned()),
+                ..Spec::default()
+            },
+            status: None
+        }
+    }
+
+    #[test]
+    fn approves_kubelet_client_certificates() {
+        let now = Time::now();
+        // Renewing its own certificate needs `selfnodeclient`.
+        let mut csr = kubelet_request("worker-1", "system:node:worker-1");
+        assert!(auto_approve(&mut csr, |_, subresource| subresource == "selfnodeclient", now)
+            .unwrap());
+        assert!(csr.is_approved());
+        let condition = csr.condition(CertificateConditionType::Approved).unwrap();
+        assert_eq!(condition.reason.as_deref(), Some(AUTO_APPROVED));
+
+        // Bootstrapping one, with the token of another user, needs `nodeclient`.
+        let mut csr = kubelet_request("worker-1", "system:bootstrap:abcdef");
+        assert!(!auto_approve(&mut csr, |_, subresource| subresource == "selfnodeclient", now)
+            .unwrap());
+        assert!(csr.is_pending());
+        assert!(auto_approve(&mut csr, |_, subresource| subresource == "nodeclient", now)
+            .unwrap());
+
+        // Other signers are left alone.
+        let mut csr = kubelet_request("worker-1", "system:node:worker-1");
+        csr.spec.signer_name = signer::KUBELET_SERVING.to_owned();
+        assert!(!auto_approve(&mut csr, |_, _| true, now).unwrap());
+        assert!(csr.is_pending());
+    }
+
+    #[test]
+    fn approval_and_denial_exclude_each_other() {
+        let now = Time::now();
+        let mut csr = kubelet_request("worker-1", "system:node:worker-1");
+        deny(&mut csr, KUBECTL_DENY, "", now).unwrap();
+        assert!(matches!(approve(&mut csr, KUBECTL_APPROVE, "", now), Err(Error::Denied)));
+        assert!(!auto_approve(&mut csr, |_, _| true, now).unwrap());
+        assert!(csr.is_denied() && !csr.is_approved());
+    }
+}
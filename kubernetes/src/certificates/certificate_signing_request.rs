@@ -2,11 +2,12 @@
 
 use std::collections::HashMap;
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
 
-use crate::meta::{
-    condition::Condition,
-    metadata::Metadata
+use crate::{
+    decode::Fields,
+    meta::{condition::ConditionStatus, metadata::Metadata},
+    time::Time
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/authentication-resources/certificate-signing-request-v1/#CertificateSigningRequest>
@@ -23,9 +24,93 @@ use crate::meta::{
 /// This API can be used to request client certificates to authenticate to kube-apiserver (with the "kubernetes.io/kube-apiserver-client" [`signer_name`][Spec::signer_name]), or to obtain certificates from custom non-Kubernetes signers.
 #[derive(Debug, Decode)]
 pub struct CertificateSigningRequest {
-    metadata: Metadata,
-    spec: Spec,
-    status: Option<Status>
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child)]
+    pub spec: Spec,
+    #[kfl(child, default)]
+    pub status: Option<Status>
+}
+
+impl CertificateSigningRequest {
+    /// The condition of type `type`, if any; there is at most one of each.
+    pub fn condition(&self, r#type: CertificateConditionType) -> Option<&Condition> {
+        self.status.as_ref()?.conditions.iter()
+            .find(|condition| condition.r#type == r#type)
+    }
+
+    fn has_condition(&self, r#type: CertificateConditionType) -> bool {
+        self.condition(r#type)
+            .is_some_and(|condition| condition.status == ConditionStatus::True)
+    }
+
+    pub fn is_approved(&self) -> bool {
+        self.has_condition(CertificateConditionType::Approved)
+    }
+
+    pub fn is_denied(&self) -> bool {
+        self.has_condition(CertificateConditionType::Denied)
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.has_condition(CertificateConditionType::Failed)
+    }
+
+    /// Whether the certificate was issued.
+    pub fn is_issued(&self) -> bool {
+        self.status.as_ref().is_some_and(|status| !status.certificate.is_empty())
+    }
+
+    /// Whether the request is neither approved nor denied yet.
+    pub fn is_pending(&self) -> bool {
+        !self.is_approved() && !self.is_denied()
+    }
+
+    /**
+    Adds the condition of type `type`, or updates the existing one, setting its last transition time only when its status changes.
+
+    Conditions cannot be removed, and `Approved` and `Denied` exclude each other: neither is set if the other already is, which this returns false for.
+    */
+    pub fn set_condition(
+        &mut self,
+        r#type: CertificateConditionType,
+        reason: &str,
+        message: &str,
+        now: Time
+    ) -> bool {
+        let excluded = match r#type {
+            CertificateConditionType::Approved => Some(CertificateConditionType::Denied),
+            CertificateConditionType::Denied => Some(CertificateConditionType::Approved),
+            CertificateConditionType::Failed => None
+        };
+        if excluded.is_some_and(|excluded| self.condition(excluded).is_some()) {
+            return false
+        }
+        let conditions = &mut self.status.get_or_insert_with(Default::default).conditions;
+        let index = match conditions.iter().position(|c| c.r#type == r#type) {
+            Some(index) => index,
+            None => {
+                conditions.push(Condition {
+                    r#type,
+                    status: ConditionStatus::True,
+                    reason: None,
+                    message: None,
+                    last_update_time: None,
+                    last_transition_time: Some(now)
+                });
+                conditions.len() - 1
+            }
+        };
+        let condition = &mut conditions[index];
+        if condition.status != ConditionStatus::True {
+            condition.status = ConditionStatus::True;
+            condition.last_transition_time = Some(now);
+        }
+        condition.reason = Some(reason.to_owned());
+        condition.message = Some(message.to_owned());
+        condition.last_update_time = Some(now);
+        true
+    }
 }
 
 /**
@@ -33,25 +118,70 @@ pub struct CertificateSigningRequest {
 
 Spec contains the certificate request.
 */
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Spec {
-    request: Vec<u8>,
-    signer_name: String,
-    expiration_seconds: Option<u16>,
-    extra: HashMap<String, String>,
-    groups: Vec<String>,
-    uid: Option<String>,
-    usages: Vec<String>,
-    username: Option<String>
+    /// PEM encoded PKCS#10 certificate request.
+    pub request: Vec<u8>,
+    /// Signer the request is for, such as one of [`signer`][super::signer]'s built-in ones.
+    pub signer_name: String,
+    /// Requested duration of validity of the certificate, at least 600; the signer may issue a shorter one.
+    pub expiration_seconds: Option<u32>,
+    pub extra: HashMap<String, String>,
+    pub groups: Vec<String>,
+    pub uid: Option<String>,
+    /// Requested key usages and extended key usages, such as `digital signature` and `client auth`.
+    pub usages: Vec<String>,
+    pub username: Option<String>
+}
+
+impl Decode for Spec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            request: fields.property("request")?.unwrap_or_default(),
+            signer_name: fields.property("signer-name")?.unwrap_or_default(),
+            expiration_seconds: fields.property("expiration-seconds")?,
+            extra: fields.entries("extra")?,
+            groups: fields.values("groups")?,
+            uid: fields.property("uid")?,
+            usages: fields.values("usages")?,
+            username: fields.property("username")?
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/authentication-resources/certificate-signing-request-v1/#CertificateSigningRequestStatus>
 ///
 /// Status contains conditions used to indicate approved/denied/failed status of the request, and the issued certificate.
-#[derive(Debug, Decode)]
+#[derive(Debug, Default, Decode)]
 pub struct Status {
-    certificate: Vec<u8>,
-    conditions: Option<Vec<Condition<CertificateConditionType>>>
+    /// PEM encoded certificate issued by the signer, once approved.
+    #[kfl(property, default)]
+    pub certificate: Vec<u8>,
+    #[kfl(children)]
+    pub conditions: Vec<Condition>
+}
+
+/// <https://kubernetes.io/docs/reference/kubernetes-api/authentication-resources/certificate-signing-request-v1/#CertificateSigningRequestStatus>
+#[derive(Debug, Decode)]
+pub struct Condition {
+    #[kfl(property, default)]
+    pub r#type: CertificateConditionType,
+    #[kfl(property, default)]
+    pub status: ConditionStatus,
+    /// Brief reason for the request state.
+    #[kfl(property, default)]
+    pub reason: Option<String>,
+    /// Human readable message with details about the request state.
+    #[kfl(property, default)]
+    pub message: Option<String>,
+    #[kfl(property, default)]
+    pub last_update_time: Option<Time>,
+    /// Last time the condition transitioned from one status to another.
+    #[kfl(property, default)]
+    pub last_transition_time: Option<Time>
 }
 
 /**
@@ -67,8 +197,9 @@ Type of the condition. Known conditions are `Approved`, `Denied`, and `Failed`.
 
 Only one condition of a given type is allowed.
 */
-#[derive(Debug, Decode)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum CertificateConditionType {
+    #[default]
     Approved,
     Denied,
     Failed
@@ -0,0 +1,705 @@
+/*!
+An async client of the API server: typed requests on the objects of a resource, paginated lists and watches, authenticated with the credentials of a kubeconfig.
+
+Requests are sent over HTTP/1.1, one connection each. Plain `http://` servers are reached with [`TcpConnector`]; `https://` ones with [`RustlsConnector`], or another [`Connector`] that speaks TLS with the [`Tls`] settings it is given.
+
+- Concepts <https://kubernetes.io/docs/reference/using-api/api-concepts/>
+- Source <https://github.com/kubernetes/client-go/blob/master/rest/request.go>
+*/
+
+pub mod cache;
+pub mod controller;
+pub mod resource;
+pub mod tls;
+pub mod watch;
+
+use std::{
+    fmt,
+    future::Future,
+    io,
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use bytes::Bytes;
+use http::{header, Method, Request, Response, StatusCode, Uri};
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper_util::rt::TokioIo;
+use serde_json::{json, Map, Value};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream
+};
+
+use crate::{
+    kubectl::config::{self, Authenticator, Credentials, Resolved},
+    meta::{
+        garbage_collection::PropagationPolicy,
+        list_metadata::ListMeta,
+        status::{self, Status}
+    },
+    time::Time
+};
+
+pub use resource::{ApiResource, Meta, Object, Resource};
+pub use tls::RustlsConnector;
+pub use watch::{Watch, WatchEvent, Watcher};
+
+/// Content type of JSON bodies.
+pub const JSON: &str = "application/json";
+
+pub(crate) type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A connection to the server.
+pub trait Io: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Io for T {}
+
+/// What a TLS connection to the server is set up with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tls {
+    /// Name the server certificate must be valid for.
+    pub server_name: String,
+    /// PEM certificate authorities to trust; the system's if empty.
+    pub certificate_authorities: Vec<u8>,
+    /// PEM client certificate and key to present.
+    pub client_certificate: Option<(Vec<u8>, Vec<u8>)>,
+    pub insecure_skip_verify: bool
+}
+
+/// Opens connections to the server.
+pub trait Connector: Send + Sync {
+    /// Connects to `host` on `port`, over TLS set up as `tls` for `https://` servers.
+    fn connect<'a>(&'a self, host: &'a str, port: u16, tls: Option<&'a Tls>)
+        -> BoxFuture<'a, io::Result<Box<dyn Io>>>;
+}
+
+/// Plain TCP connections, for `http://` servers only.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TcpConnector;
+
+impl Connector for TcpConnector {
+    fn connect<'a>(&'a self, host: &'a str, port: u16, tls: Option<&'a Tls>)
+        -> BoxFuture<'a, io::Result<Box<dyn Io>>>
+    {
+        Box::pin(async move {
+            if tls.is_some() {
+                return Err(io::Error::new(io::ErrorKind::Unsupported,
+                    "https servers need a connector speaking TLS"))
+            }
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            let stream = TcpStream::connect((host, port)).await?;
+            Ok(Box::new(stream) as Box<dyn Io>)
+        })
+    }
+}
+
+/// A client of one API server, cheap to clone.
+#[derive(Clone)]
+pub struct Client {
+    inner: Arc<Inner>
+}
+
+struct Inner {
+    host: String,
+    port: u16,
+    /// Path the API is served under, without a trailing slash.
+    base: String,
+    /// `None` for `http://` servers.
+    tls: Option<Tls>,
+    default_namespace: String,
+    authenticator: Option<Authenticator>,
+    headers: Vec<(String, String)>,
+    connector: Box<dyn Connector>
+}
+
+impl Client {
+    /// An unauthenticated client of `server`, as `http://127.0.0.1:8080`.
+    pub fn new(server: &str, connector: impl Connector + 'static) -> Result<Self, Error> {
+        let uri: Uri = server.parse().map_err(|_| Error::Url(server.to_owned()))?;
+        let https = match uri.scheme_str() {
+            Some("https") => true,
+            Some("http") => false,
+            _ => return Err(Error::Url(server.to_owned()))
+        };
+        let host = uri.host().ok_or_else(|| Error::Url(server.to_owned()))?.to_owned();
+        Ok(Self {
+            inner: Arc::new(Inner {
+                port: uri.port_u16().unwrap_or(if https { 443 } else { 80 }),
+                base: uri.path().trim_end_matches('/').to_owned(),
+                tls: https.then(|| Tls { server_name: host.clone(), ..Default::default() }),
+                host,
+                default_namespace: crate::kubectl::DEFAULT_NAMESPACE.to_owned(),
+                authenticator: None,
+                headers: Vec::new(),
+                connector: Box::new(connector)
+            })
+        })
+    }
+
+    /**
+    A client of the cluster of `resolved`, authenticated as its user and impersonating whom it says, with its namespace as the default.
+
+    Exec plugins run interactively only if their configuration allows and `stdin_available`.
+    */
+    pub fn from_config(resolved: &Resolved, stdin_available: bool, connector: impl Connector + 'static)
+        -> Result<Self, Error>
+    {
+        let mut client = Self::new(&resolved.cluster.server, connector)?;
+        let inner = Arc::get_mut(&mut client.inner).unwrap();
+        if let Some(tls) = inner.tls.as_mut() {
+            if let Some(name) = &resolved.cluster.tls_server_name {
+                tls.server_name = name.clone();
+            }
+            tls.certificate_authorities = resolved.cluster.certificate_authorities()?;
+            tls.insecure_skip_verify = resolved.cluster.insecure_skip_tls_verify;
+        }
+        inner.default_namespace = resolved.namespace.clone();
+        inner.authenticator = Some(resolved.authenticator(stdin_available)?);
+        let user = &resolved.user;
+        if let Some(impersonate) = &user.impersonate {
+            inner.headers.push(("Impersonate-User".to_owned(), impersonate.clone()));
+        }
+        if let Some(uid) = &user.impersonate_uid {
+            inner.headers.push(("Impersonate-Uid".to_owned(), uid.clone()));
+        }
+        for group in &user.impersonate_groups {
+            inner.headers.push(("Impersonate-Group".to_owned(), group.clone()));
+        }
+        for (key, values) in &user.impersonate_user_extra {
+            for value in values {
+                inner.headers.push((format!("Impersonate-Extra-{}", encode(key)), value.clone()));
+            }
+        }
+        Ok(client)
+    }
+
+    /// The namespace of the context, for [`Api::default_namespaced`].
+    pub fn default_namespace(&self) -> &str {
+        &self.inner.default_namespace
+    }
+
+    /**
+    Sends a request for `path_and_query`, below the path of the server, and returns the response if it is successful, else the [`Status`] the server answered with.
+
+    A request rejected as unauthorized is retried once with fresh credentials from the exec plugin, if the user has one.
+    */
+    pub async fn send(&self, method: Method, path_and_query: &str, body: Option<(&str, Vec<u8>)>)
+        -> Result<Response<Incoming>, Error>
+    {
+        let retry = self.inner.authenticator.as_ref()
+            .is_some_and(|authenticator| authenticator.user.exec.is_some());
+        let response = self.send_once(method.clone(), path_and_query, body.clone()).await?;
+        let response = match (response.status(), &self.inner.authenticator) {
+            (StatusCode::UNAUTHORIZED, Some(authenticator)) if retry => {
+                authenticator.invalidate().await;
+                self.send_once(method, path_and_query, body).await?
+            }
+            _ => response
+        };
+        if response.status().is_success() {
+            return Ok(response)
+        }
+        let code = response.status();
+        let bytes = response.into_body().collect().await
+            .map_err(|error| Error::Http(error.to_string()))?
+            .to_bytes();
+        Err(Error::Api(Box::new(error_status(code, &bytes))))
+    }
+
+    async fn send_once(&self, method: Method, path_and_query: &str, body: Option<(&str, Vec<u8>)>)
+        -> Result<Response<Incoming>, Error>
+    {
+        let inner = &self.inner;
+        let credentials = match &inner.authenticator {
+            Some(authenticator) => authenticator.credentials(Time::now()).await?,
+            None => Credentials::default()
+        };
+        let tls = inner.tls.clone().map(|mut tls| {
+            tls.client_certificate = credentials.client_certificate.clone();
+            tls
+        });
+        let authority = if inner.host.contains(':') && !inner.host.starts_with('[') {
+            format!("[{}]:{}", inner.host, inner.port)
+        } else {
+            format!("{}:{}", inner.host, inner.port)
+        };
+        let mut request = Request::builder()
+            .method(method)
+            .uri(format!("{}{}", inner.base, path_and_query))
+            .header(header::HOST, authority)
+            .header(header::ACCEPT, JSON)
+            .header(header::USER_AGENT, concat!("kfl-kubernetes/", env!("CARGO_PKG_VERSION")));
+        if let Some(token) = &credentials.token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        } else if let Some((username, password)) = &credentials.basic {
+            request = request.header(header::AUTHORIZATION,
+                format!("Basic {}", STANDARD.encode(format!("{}:{}", username, password))));
+        }
+        for (name, value) in &inner.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let request = match body {
+            Some((content_type, body)) => request.header(header::CONTENT_TYPE, content_type)
+                .body(Full::new(Bytes::from(body))),
+            None => request.body(Full::new(Bytes::new()))
+        }.map_err(|error| Error::Http(error.to_string()))?;
+        let io = inner.connector.connect(&inner.host, inner.port, tls.as_ref()).await
+            .map_err(Error::Connect)?;
+        let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(io)).await
+            .map_err(|error| Error::Http(error.to_string()))?;
+        tokio::spawn(connection);
+        sender.send_request(request).await.map_err(|error| Error::Http(error.to_string()))
+    }
+
+    /// Sends a request and decodes the JSON of the response.
+    pub async fn request(&self, method: Method, path_and_query: &str, body: Option<(&str, Value)>)
+        -> Result<Value, Error>
+    {
+        let body = body.map(|(content_type, body)| (content_type, body.to_string().into_bytes()));
+        let response = self.send(method, path_and_query, body).await?;
+        let bytes = response.into_body().collect().await
+            .map_err(|error| Error::Http(error.to_string()))?
+            .to_bytes();
+        serde_json::from_slice(&bytes).map_err(|error| Error::Decode(error.to_string()))
+    }
+}
+
+/// The [`Status`] of an unsuccessful response, made up from the code if the body is not one.
+fn error_status(code: StatusCode, body: &[u8]) -> Status {
+    if let Ok(value) = serde_json::from_slice::<Value>(body) {
+        if value.get("kind").and_then(Value::as_str) == Some("Status") {
+            let mut status = Status::from_json(&value);
+            status.code.get_or_insert(i32::from(code.as_u16()));
+            return status
+        }
+    }
+    let reason = match code {
+        StatusCode::UNAUTHORIZED => status::UNAUTHORIZED,
+        StatusCode::FORBIDDEN => status::FORBIDDEN,
+        StatusCode::NOT_FOUND => status::NOT_FOUND,
+        StatusCode::CONFLICT => status::CONFLICT,
+        StatusCode::GONE => status::GONE,
+        StatusCode::UNPROCESSABLE_ENTITY => status::INVALID,
+        StatusCode::METHOD_NOT_ALLOWED => status::METHOD_NOT_ALLOWED,
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => status::UNSUPPORTED_MEDIA_TYPE,
+        StatusCode::TOO_MANY_REQUESTS => status::TOO_MANY_REQUESTS,
+        StatusCode::SERVICE_UNAVAILABLE => status::SERVICE_UNAVAILABLE,
+        code if code.is_server_error() => status::INTERNAL_ERROR,
+        _ => status::BAD_REQUEST
+    };
+    let body = String::from_utf8_lossy(body);
+    let message = if body.trim().is_empty() {
+        format!("the server responded with the status code {}", code.as_u16())
+    } else {
+        body.trim().to_owned()
+    };
+    Status::failure(i32::from(code.as_u16()), reason, message)
+}
+
+/// Options of lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListParams {
+    /// In the string form of [`Selector`][crate::meta::Selector], as `app=web,tier!=cache`.
+    pub label_selector: Option<String>,
+    /// As `metadata.name=web,status.phase!=Running`.
+    pub field_selector: Option<String>,
+    /// Most items to return, with a `continue` token for the rest.
+    pub limit: Option<u32>,
+    /// Token of the previous page.
+    pub continue_token: Option<String>,
+    /// `0` to accept any version, as from a cache; the latest if unset.
+    pub resource_version: Option<String>
+}
+
+impl ListParams {
+    fn query(&self) -> String {
+        let mut query = Query::default();
+        query.push("labelSelector", self.label_selector.as_deref());
+        query.push("fieldSelector", self.field_selector.as_deref());
+        query.push("limit", self.limit.map(|limit| limit.to_string()).as_deref());
+        query.push("continue", self.continue_token.as_deref());
+        query.push("resourceVersion", self.resource_version.as_deref());
+        query.0
+    }
+}
+
+/// Options of watches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchParams {
+    pub label_selector: Option<String>,
+    pub field_selector: Option<String>,
+    /// How long the server keeps the watch open.
+    pub timeout_seconds: Option<u32>,
+    /// Asks for [`WatchEvent::Bookmark`]s, so the watch can resume from recent versions even when nothing it selects changes.
+    pub allow_bookmarks: bool
+}
+
+impl WatchParams {
+    fn query(&self, resource_version: &str) -> String {
+        let mut query = Query::default();
+        query.push("watch", Some("true"));
+        query.push("labelSelector", self.label_selector.as_deref());
+        query.push("fieldSelector", self.field_selector.as_deref());
+        query.push("timeoutSeconds", self.timeout_seconds.map(|seconds| seconds.to_string()).as_deref());
+        query.push("allowWatchBookmarks", self.allow_bookmarks.then_some("true"));
+        query.push("resourceVersion", (!resource_version.is_empty()).then_some(resource_version));
+        query.0
+    }
+}
+
+/// Options of creations and replacements.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PostParams {
+    /// Validates and admits without persisting.
+    pub dry_run: bool,
+    pub field_manager: Option<String>
+}
+
+impl PostParams {
+    fn query(&self) -> String {
+        let mut query = Query::default();
+        query.push("dryRun", self.dry_run.then_some("All"));
+        query.push("fieldManager", self.field_manager.as_deref());
+        query.0
+    }
+}
+
+/// A patch, by how it is merged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Patch {
+    /// RFC 6902 operations.
+    Json(Value),
+    /// RFC 7386 merge patch, where `null` deletes.
+    Merge(Value),
+    /// Merge patch where lists are merged by the keys the built-in types declare.
+    Strategic(Value),
+    /// Server-side apply of a full configuration, which needs a field manager.
+    Apply(Value)
+}
+
+impl Patch {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json(_) => "application/json-patch+json",
+            Self::Merge(_) => "application/merge-patch+json",
+            Self::Strategic(_) => "application/strategic-merge-patch+json",
+            Self::Apply(_) => "application/apply-patch+yaml"
+        }
+    }
+
+    pub fn body(&self) -> &Value {
+        match self {
+            Self::Json(body) | Self::Merge(body) | Self::Strategic(body) | Self::Apply(body) => body
+        }
+    }
+}
+
+/// Options of patches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatchParams {
+    pub dry_run: bool,
+    /// Required by [`Patch::Apply`].
+    pub field_manager: Option<String>,
+    /// Takes the fields of other managers over on conflicts, for [`Patch::Apply`] only.
+    pub force: bool
+}
+
+impl PatchParams {
+    fn query(&self) -> String {
+        let mut query = Query::default();
+        query.push("dryRun", self.dry_run.then_some("All"));
+        query.push("fieldManager", self.field_manager.as_deref());
+        query.push("force", self.force.then_some("true"));
+        query.0
+    }
+}
+
+/// <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/delete-options/>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeleteParams {
+    pub dry_run: bool,
+    /// Seconds the object may take to terminate, `0` to delete at once.
+    pub grace_period_seconds: Option<i64>,
+    pub propagation_policy: Option<PropagationPolicy>,
+    /// Only deletes the object with this UID.
+    pub uid: Option<String>,
+    /// Only deletes the object at this version.
+    pub resource_version: Option<String>
+}
+
+impl DeleteParams {
+    fn body(&self) -> Value {
+        let mut body = json!({
+            "apiVersion": "v1",
+            "kind": "DeleteOptions"
+        });
+        if self.dry_run {
+            body["dryRun"] = json!(["All"]);
+        }
+        if let Some(seconds) = self.grace_period_seconds {
+            body["gracePeriodSeconds"] = seconds.into();
+        }
+        if let Some(policy) = self.propagation_policy {
+            body["propagationPolicy"] = policy.as_str().into();
+        }
+        let mut preconditions = Map::new();
+        if let Some(uid) = &self.uid {
+            preconditions.insert("uid".to_owned(), uid.clone().into());
+        }
+        if let Some(version) = &self.resource_version {
+            preconditions.insert("resourceVersion".to_owned(), version.clone().into());
+        }
+        if !preconditions.is_empty() {
+            body["preconditions"] = Value::Object(preconditions);
+        }
+        body
+    }
+}
+
+/// A page of a list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectList<K> {
+    pub metadata: ListMeta,
+    pub items: Vec<K>
+}
+
+/// What a deletion returns: the object, if it is kept until finalisers or dependents are done with it, else a success [`Status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Deleted<K> {
+    Object(K),
+    Status(Box<Status>)
+}
+
+/// Requests on the objects of one resource, in one namespace or all of them.
+pub struct Api<K> {
+    client: Client,
+    resource: ApiResource,
+    namespace: Option<String>,
+    kind: PhantomData<fn() -> K>
+}
+
+impl<K> Clone for Api<K> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            resource: self.resource.clone(),
+            namespace: self.namespace.clone(),
+            kind: PhantomData
+        }
+    }
+}
+
+impl<K: Resource> Api<K> {
+    pub fn namespaced(client: Client, namespace: &str) -> Self {
+        Self::new(client, K::api_resource(), Some(namespace))
+    }
+
+    /// In the namespace of the context of the client.
+    pub fn default_namespaced(client: Client) -> Self {
+        let namespace = client.default_namespace().to_owned();
+        Self::new(client, K::api_resource(), Some(&namespace))
+    }
+
+    /// Across namespaces, or of a cluster-scoped resource.
+    pub fn all(client: Client) -> Self {
+        Self::new(client, K::api_resource(), None)
+    }
+}
+
+impl<K: Object> Api<K> {
+    /// Requests on `resource`, in `namespace` or across namespaces if `None`.
+    pub fn new(client: Client, resource: ApiResource, namespace: Option<&str>) -> Self {
+        Self { client, resource, namespace: namespace.map(str::to_owned), kind: PhantomData }
+    }
+
+    pub fn resource(&self) -> &ApiResource {
+        &self.resource
+    }
+
+    fn path(&self, name: Option<&str>, subresource: Option<&str>) -> String {
+        self.resource.path(self.namespace.as_deref(), name.map(encode).as_deref(), subresource)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<K, Error> {
+        let value = self.client.request(Method::GET, &self.path(Some(name), None), None).await?;
+        K::from_json(value).map_err(Error::Decode)
+    }
+
+    /// A page of the objects matching `params`.
+    pub async fn list(&self, params: &ListParams) -> Result<ObjectList<K>, Error> {
+        let path = format!("{}{}", self.path(None, None), params.query());
+        let value = self.client.request(Method::GET, &path, None).await?;
+        let metadata = value.get("metadata").map(ListMeta::from_json).unwrap_or_default();
+        let Value::Object(mut object) = value else {
+            return Err(Error::Decode("expected a list object".to_owned()))
+        };
+        let items = match object.remove("items") {
+            Some(Value::Array(items)) => items,
+            None | Some(Value::Null) => Vec::new(),
+            Some(_) => return Err(Error::Decode("items is not a list".to_owned()))
+        };
+        // Items of lists have neither kind nor API version.
+        let items = items.into_iter().map(|mut item| {
+            if let Some(fields) = item.as_object_mut() {
+                fields.entry("apiVersion").or_insert_with(|| self.resource.api_version().into());
+                fields.entry("kind").or_insert_with(|| self.resource.kind.clone().into());
+            }
+            K::from_json(item).map_err(Error::Decode)
+        }).collect::<Result<_, _>>()?;
+        Ok(ObjectList { metadata, items })
+    }
+
+    /**
+    All the objects matching `params`, following `continue` tokens page after page, `params.limit` at a time. The metadata is that of the last page, its resource version that of the whole list.
+    */
+    pub async fn list_all(&self, params: &ListParams) -> Result<ObjectList<K>, Error> {
+        let mut params = params.clone();
+        let mut items = Vec::new();
+        loop {
+            let page = self.list(&params).await?;
+            items.extend(page.items);
+            if page.metadata.r#continue.is_empty() {
+                return Ok(ObjectList { metadata: page.metadata, items })
+            }
+            params.continue_token = Some(page.metadata.r#continue);
+        }
+    }
+
+    pub async fn create(&self, object: &K, params: &PostParams) -> Result<K, Error> {
+        let path = format!("{}{}", self.path(None, None), params.query());
+        let value = self.client.request(Method::POST, &path, Some((JSON, object.to_json()))).await?;
+        K::from_json(value).map_err(Error::Decode)
+    }
+
+    /// Replaces the object, failing with a conflict if its `resourceVersion` is no longer the latest.
+    pub async fn replace(&self, name: &str, object: &K, params: &PostParams) -> Result<K, Error> {
+        self.put(name, None, object, params).await
+    }
+
+    /// Replaces the status of the object, through its `status` subresource.
+    pub async fn replace_status(&self, name: &str, object: &K, params: &PostParams) -> Result<K, Error> {
+        self.put(name, Some("status"), object, params).await
+    }
+
+    async fn put(&self, name: &str, subresource: Option<&str>, object: &K, params: &PostParams)
+        -> Result<K, Error>
+    {
+        let path = format!("{}{}", self.path(Some(name), subresource), params.query());
+        let value = self.client.request(Method::PUT, &path, Some((JSON, object.to_json()))).await?;
+        K::from_json(value).map_err(Error::Decode)
+    }
+
+    pub async fn patch(&self, name: &str, patch: &Patch, params: &PatchParams) -> Result<K, Error> {
+        self.send_patch(name, None, patch, params).await
+    }
+
+    pub async fn patch_status(&self, name: &str, patch: &Patch, params: &PatchParams)
+        -> Result<K, Error>
+    {
+        self.send_patch(name, Some("status"), patch, params).await
+    }
+
+    async fn send_patch(&self, name: &str, subresource: Option<&str>, patch: &Patch,
+        params: &PatchParams) -> Result<K, Error>
+    {
+        let path = format!("{}{}", self.path(Some(name), subresource), params.query());
+        let body = Some((patch.content_type(), patch.body().clone()));
+        let value = self.client.request(Method::PATCH, &path, body).await?;
+        K::from_json(value).map_err(Error::Decode)
+    }
+
+    pub async fn delete(&self, name: &str, params: &DeleteParams) -> Result<Deleted<K>, Error> {
+        let value = self.client.request(Method::DELETE, &self.path(Some(name), None),
+            Some((JSON, params.body()))).await?;
+        if value.get("kind").and_then(Value::as_str) == Some("Status") {
+            return Ok(Deleted::Status(Box::new(Status::from_json(&value))))
+        }
+        K::from_json(value).map(Deleted::Object).map_err(Error::Decode)
+    }
+
+    /// Watches the objects matching `params` for changes after `resource_version`, or from their current state, as `ADDED` events, if it is empty.
+    pub async fn watch(&self, params: &WatchParams, resource_version: &str) -> Result<Watch<K>, Error> {
+        let path = format!("{}{}", self.path(None, None), params.query(resource_version));
+        let response = self.client.send(Method::GET, &path, None).await?;
+        Ok(Watch::new(response.into_body(), resource_version))
+    }
+}
+
+/// Query parameters, percent-encoded.
+#[derive(Default)]
+struct Query(String);
+
+impl Query {
+    fn push(&mut self, key: &str, value: Option<&str>) {
+        if let Some(value) = value {
+            self.0.push(if self.0.is_empty() { '?' } else { '&' });
+            self.0.push_str(key);
+            self.0.push('=');
+            self.0.push_str(&encode(value));
+        }
+    }
+}
+
+/// Percent-encodes all but the unreserved characters of RFC 3986.
+fn encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' =>
+                encoded.push(char::from(byte)),
+            byte => encoded.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    encoded
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The server is not an `http://` or `https://` URL.
+    Url(String),
+    /// The kubeconfig or the credentials it points to are not usable.
+    Config(config::Error),
+    Connect(io::Error),
+    /// The exchange with the server failed.
+    Http(String),
+    /// The server refused the request.
+    Api(Box<Status>),
+    /// The server answered with something other than what was asked for.
+    Decode(String)
+}
+
+impl Error {
+    /// The status the server refused the request with.
+    pub fn status(&self) -> Option<&Status> {
+        match self {
+            Self::Api(status) => Some(status),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url(server) => write!(f, "invalid server URL {:?}", server),
+            Self::Config(error) => write!(f, "{}", error),
+            Self::Connect(error) => write!(f, "unable to connect to the server: {}", error),
+            Self::Http(message) => write!(f, "{}", message),
+            Self::Api(status) => write!(f, "Error from server ({}): {}",
+                status.reason.as_deref().unwrap_or("Unknown"), status),
+            Self::Decode(message) => write!(f, "unable to decode the response: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<config::Error> for Error {
+    fn from(error: config::Error) -> Self {
+        Self::Config(error)
+    }
+}
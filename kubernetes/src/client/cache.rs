@@ -0,0 +1,462 @@
+/*!
+Informers: local caches of the objects of a resource, kept up to date by watching, that tell handlers what changes.
+
+The reflector of an informer lists the objects, pages at a time, then watches for changes from the resource version of the list, resuming from the last version it saw whenever the server closes the watch. When that version is gone, `410 Gone`, it lists anew, and tells handlers of the differences as if they had been watched. So it does after an event it cannot decode, once it has backed off, since watching from the same version would only serve the event again. Other errors are retried with backoff.
+
+Handlers run one task each, in the order of the changes, so a slow one only holds itself up. Every resync period, they are told of every object again, as updates where the old and new objects are the same.
+
+- Concepts <https://kubernetes.io/docs/reference/using-api/api-concepts/#efficient-detection-of-changes>
+- Source <https://github.com/kubernetes/client-go/blob/master/tools/cache/reflector.go>
+- Source <https://github.com/kubernetes/client-go/blob/master/tools/cache/shared_informer.go>
+*/
+
+pub mod store;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration
+};
+
+use tokio::{
+    sync::{mpsc, watch},
+    time::{self, Instant, Interval}
+};
+
+use crate::meta::status::Status;
+use super::{
+    Api, BoxFuture, Error, ListParams, Meta, Object, ObjectList, Watch, WatchEvent, WatchParams
+};
+
+pub use store::Store;
+
+/// Objects listed per page.
+pub const PAGE_SIZE: u32 = 500;
+/// Wait before the first retry after an error, doubled with each error in a row.
+pub const INITIAL_BACKOFF: Duration = Duration::from_millis(800);
+pub const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Where an informer lists and watches objects from: [`Api`], or anything else serving the same requests.
+pub trait ListerWatcher<K>: Send + Sync {
+    fn list<'a>(&'a self, params: &'a ListParams) -> BoxFuture<'a, Result<ObjectList<K>, Error>>;
+
+    /// Watches for changes after `resource_version`.
+    fn watch<'a>(&'a self, params: &'a WatchParams, resource_version: &'a str)
+        -> BoxFuture<'a, Result<Box<dyn Events<K>>, Error>>;
+}
+
+/// The events of a watch, until it ends.
+pub trait Events<K>: Send {
+    fn next(&mut self) -> BoxFuture<'_, Option<Result<WatchEvent<K>, Error>>>;
+}
+
+impl<K: Object + 'static> ListerWatcher<K> for Api<K> {
+    fn list<'a>(&'a self, params: &'a ListParams) -> BoxFuture<'a, Result<ObjectList<K>, Error>> {
+        Box::pin(Api::list(self, params))
+    }
+
+    fn watch<'a>(&'a self, params: &'a WatchParams, resource_version: &'a str)
+        -> BoxFuture<'a, Result<Box<dyn Events<K>>, Error>>
+    {
+        Box::pin(async move {
+            let watch = Api::watch(self, params, resource_version).await?;
+            Ok(Box::new(watch) as Box<dyn Events<K>>)
+        })
+    }
+}
+
+impl<K: Object + 'static> Events<K> for Watch<K> {
+    fn next(&mut self) -> BoxFuture<'_, Option<Result<WatchEvent<K>, Error>>> {
+        Box::pin(Watch::next(self))
+    }
+}
+
+/// A change to the store of an informer.
+#[derive(Debug)]
+pub enum Event<K> {
+    Added(Arc<K>),
+    /// Also sent on resyncs, with `old` and `new` the same object.
+    Updated { old: Arc<K>, new: Arc<K> },
+    /// The object in its last known state, which may be older than its deletion if it was missed while relisting.
+    Deleted(Arc<K>)
+}
+
+impl<K> Clone for Event<K> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Added(object) => Self::Added(object.clone()),
+            Self::Updated { old, new } => Self::Updated { old: old.clone(), new: new.clone() },
+            Self::Deleted(object) => Self::Deleted(object.clone())
+        }
+    }
+}
+
+impl<K> Event<K> {
+    /// The object as it is now, or was last.
+    pub fn object(&self) -> &Arc<K> {
+        match self {
+            Self::Added(object) | Self::Updated { new: object, .. } | Self::Deleted(object) => object
+        }
+    }
+
+    /// Whether the event is a resync rather than a change.
+    pub fn is_resync(&self) -> bool {
+        matches!(self, Self::Updated { old, new } if Arc::ptr_eq(old, new))
+    }
+}
+
+/// Reacts to the changes of an informer, one at a time.
+pub trait EventHandler<K>: Send + Sync {
+    fn handle(&self, event: &Event<K>);
+}
+
+impl<K, F: Fn(&Event<K>) + Send + Sync> EventHandler<K> for F {
+    fn handle(&self, event: &Event<K>) {
+        self(event)
+    }
+}
+
+type ErrorHandler = Box<dyn Fn(&Error) + Send + Sync>;
+
+/**
+Keeps a [`Store`] of the objects a [`ListerWatcher`] serves, and tells its handlers how it changes.
+
+Share it in an [`Arc`] and spawn [`run`][Self::run], which runs until aborted.
+*/
+pub struct Informer<K> {
+    source: Box<dyn ListerWatcher<K>>,
+    /// Selectors of the lists and watches.
+    params: ListParams,
+    resync: Option<Duration>,
+    store: Store<K>,
+    /// Channels of the handler tasks, locked while the store changes so that handlers see changes in order.
+    handlers: Mutex<Vec<mpsc::UnboundedSender<Event<K>>>>,
+    synced: watch::Sender<bool>,
+    on_error: Option<ErrorHandler>
+}
+
+impl<K: Meta + Send + Sync + 'static> Informer<K> {
+    /**
+    An informer of the objects `source` serves matching the label and field selectors of `params`, and resyncing every `resync` period if any.
+    */
+    pub fn new(source: impl ListerWatcher<K> + 'static, params: ListParams, resync: Option<Duration>)
+        -> Self
+    {
+        Self {
+            source: Box::new(source),
+            params,
+            resync,
+            store: Store::new(),
+            handlers: Mutex::new(Vec::new()),
+            synced: watch::channel(false).0,
+            on_error: None
+        }
+    }
+
+    /// Calls `on_error` with the errors that are retried, which are otherwise silent.
+    pub fn on_error(mut self, on_error: impl Fn(&Error) + Send + Sync + 'static) -> Self {
+        self.on_error = Some(Box::new(on_error));
+        self
+    }
+
+    /// The store, to read from and add indexers to.
+    pub fn store(&self) -> &Store<K> {
+        &self.store
+    }
+
+    /**
+    Runs `handler` on a task of its own for every change from now on, after an [`Event::Added`] for every object already in the store. Must be called within a Tokio runtime.
+    */
+    pub fn add_handler(&self, handler: impl EventHandler<K> + 'static) {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<Event<K>>();
+        let mut handlers = self.lock_handlers();
+        for object in self.store.list() {
+            let _ = sender.send(Event::Added(object));
+        }
+        handlers.push(sender);
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                handler.handle(&event);
+            }
+        });
+    }
+
+    /// Whether the first list has been stored.
+    pub fn has_synced(&self) -> bool {
+        *self.synced.borrow()
+    }
+
+    /// Waits until the first list has been stored.
+    pub async fn wait_for_sync(&self) {
+        let mut synced = self.synced.subscribe();
+        let _ = synced.wait_for(|synced| *synced).await;
+    }
+
+    fn lock_handlers(&self) -> std::sync::MutexGuard<'_, Vec<mpsc::UnboundedSender<Event<K>>>> {
+        self.handlers.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn notify(handlers: &mut Vec<mpsc::UnboundedSender<Event<K>>>, event: Event<K>) {
+        handlers.retain(|sender| sender.send(event.clone()).is_ok());
+    }
+
+    /// Lists and watches for as long as it runs, which is until it is aborted.
+    pub async fn run(&self) {
+        let mut backoff = INITIAL_BACKOFF;
+        // Version to watch from, none until listed, or when it is gone.
+        let mut resource_version: Option<String> = None;
+        let mut resync = self.resync.map(|period| time::interval_at(Instant::now() + period, period));
+        loop {
+            let result = match resource_version.as_mut() {
+                None => self.list().await.map(|version| resource_version = Some(version)),
+                Some(version) => self.watch(version, &mut resync).await
+            };
+            match result {
+                Ok(()) => backoff = INITIAL_BACKOFF,
+                Err(error) => {
+                    if let Some(on_error) = &self.on_error {
+                        on_error(&error);
+                    }
+                    if error.status().is_some_and(Status::is_gone) {
+                        resource_version = None;
+                        continue
+                    }
+                    if let Error::Decode(_) = error {
+                        resource_version = None;
+                    }
+                    time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+    }
+
+    /**
+    Lists all objects, [`PAGE_SIZE`] at a time, replaces those of the store with them and tells handlers of the differences, returning the version of the list.
+
+    If the version of the first pages is gone before the last, lists all in one page.
+    */
+    async fn list(&self) -> Result<String, Error> {
+        let mut params = ListParams {
+            limit: Some(PAGE_SIZE),
+            continue_token: None,
+            resource_version: None,
+            ..self.params.clone()
+        };
+        let mut items = Vec::new();
+        let metadata = loop {
+            let page = match self.source.list(&params).await {
+                Err(error) if params.continue_token.is_some()
+                    && error.status().is_some_and(Status::is_gone) =>
+                {
+                    items.clear();
+                    params.limit = None;
+                    params.continue_token = None;
+                    continue
+                }
+                result => result?
+            };
+            items.extend(page.items.into_iter().map(Arc::new));
+            if page.metadata.r#continue.is_empty() {
+                break page.metadata
+            }
+            params.continue_token = Some(page.metadata.r#continue);
+        };
+        let mut handlers = self.lock_handlers();
+        let replaced = self.store.replace(items);
+        for (old, new) in replaced.inserted {
+            let event = match old {
+                Some(old) if old.resource_version() == new.resource_version() => continue,
+                Some(old) => Event::Updated { old, new },
+                None => Event::Added(new)
+            };
+            Self::notify(&mut handlers, event);
+        }
+        for object in replaced.removed {
+            Self::notify(&mut handlers, Event::Deleted(object));
+        }
+        self.synced.send_replace(true);
+        Ok(metadata.resource_version.unwrap_or_default())
+    }
+
+    /// Watches from `resource_version`, keeping it up to date, until the server closes the watch; resyncs meanwhile.
+    async fn watch(&self, resource_version: &mut String, resync: &mut Option<Interval>)
+        -> Result<(), Error>
+    {
+        let params = WatchParams {
+            label_selector: self.params.label_selector.clone(),
+            field_selector: self.params.field_selector.clone(),
+            timeout_seconds: None,
+            allow_bookmarks: true
+        };
+        let mut events = {
+            let watch = self.source.watch(&params, resource_version);
+            tokio::pin!(watch);
+            loop {
+                tokio::select! {
+                    events = &mut watch => break events?,
+                    _ = tick(resync) => self.resync()
+                }
+            }
+        };
+        loop {
+            let event = tokio::select! {
+                event = events.next() => event,
+                _ = tick(resync) => {
+                    self.resync();
+                    continue
+                }
+            };
+            let event = match event {
+                None => return Ok(()),
+                Some(event) => event?
+            };
+            let object = match event {
+                WatchEvent::Bookmark(version) => {
+                    *resource_version = version;
+                    continue
+                }
+                WatchEvent::Error(status) => return Err(Error::Api(status)),
+                WatchEvent::Added(object) | WatchEvent::Modified(object) => {
+                    let object = Arc::new(object);
+                    let mut handlers = self.lock_handlers();
+                    let event = match self.store.insert(object.clone()) {
+                        Some(old) => Event::Updated { old, new: object.clone() },
+                        None => Event::Added(object.clone())
+                    };
+                    Self::notify(&mut handlers, event);
+                    object
+                }
+                WatchEvent::Deleted(object) => {
+                    let object = Arc::new(object);
+                    let mut handlers = self.lock_handlers();
+                    let last = self.store.remove(&object.key()).unwrap_or_else(|| object.clone());
+                    Self::notify(&mut handlers, Event::Deleted(last));
+                    object
+                }
+            };
+            if let Some(version) = object.resource_version() {
+                *resource_version = version.to_owned();
+            }
+        }
+    }
+
+    fn resync(&self) {
+        let mut handlers = self.lock_handlers();
+        for object in self.store.list() {
+            Self::notify(&mut handlers, Event::Updated { old: object.clone(), new: object });
+        }
+    }
+}
+
+/// Waits for the next tick, forever if there is no interval.
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde_json::{json, Value};
+
+    use crate::{
+        api_server::fake::FakeApiServer,
+        core::Pod,
+        meta::list_metadata::ListMeta
+    };
+    use super::*;
+
+    /// Lists one object at a new version each time, and watches that serve an event it cannot decode.
+    #[derive(Default)]
+    struct Source {
+        lists: AtomicUsize,
+        watches: Mutex<Vec<String>>
+    }
+
+    impl ListerWatcher<Value> for Arc<Source> {
+        fn list<'a>(&'a self, _params: &'a ListParams)
+            -> BoxFuture<'a, Result<ObjectList<Value>, Error>>
+        {
+            let version = (self.lists.fetch_add(1, Ordering::SeqCst) + 1).to_string();
+            Box::pin(async move {
+                Ok(ObjectList {
+                    metadata: ListMeta { resource_version: Some(version.clone()), ..Default::default() },
+                    items: vec![json!({
+                        "metadata": { "name": "a", "namespace": "default", "resourceVersion": version }
+                    })]
+                })
+            })
+        }
+
+        fn watch<'a>(&'a self, _params: &'a WatchParams, resource_version: &'a str)
+            -> BoxFuture<'a, Result<Box<dyn Events<Value>>, Error>>
+        {
+            self.watches.lock().unwrap().push(resource_version.to_owned());
+            Box::pin(async { Ok(Box::new(Undecodable) as Box<dyn Events<Value>>) })
+        }
+    }
+
+    struct Undecodable;
+
+    impl Events<Value> for Undecodable {
+        fn next(&mut self) -> BoxFuture<'_, Option<Result<WatchEvent<Value>, Error>>> {
+            Box::pin(async { Some(Err(Error::Decode("unexpected end of input".to_owned()))) })
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn relists_after_undecodable_events() {
+        let source = Arc::new(Source::default());
+        let informer = Arc::new(Informer::new(source.clone(), ListParams::default(), None));
+        let task = tokio::spawn({
+            let informer = informer.clone();
+            async move { informer.run().await }
+        });
+        // Backing off the initial 0.8s after each error, the lists in between succeeding.
+        time::sleep(INITIAL_BACKOFF * 5 / 2).await;
+        task.abort();
+        assert_eq!(*source.watches.lock().unwrap(), ["1", "2", "3"]);
+        assert_eq!(informer.store().list()[0]["metadata"]["resourceVersion"], "3");
+    }
+
+    fn pod(name: &str, node: &str) -> Value {
+        json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": { "name": name, "namespace": "default" },
+            "spec": { "nodeName": node, "containers": [{ "name": "app", "image": "app:1" }] }
+        })
+    }
+
+    #[tokio::test]
+    async fn informs_of_typed_objects() {
+        let server = FakeApiServer::start().await.unwrap();
+        server.create(pod("a", "node-1")).unwrap();
+        let api = Api::<Pod>::namespaced(server.client(), "default");
+        let informer = Arc::new(Informer::new(api, ListParams::default(), None));
+        informer.store().add_indexer("node", store::by_node_name);
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        informer.add_handler(move |event: &Event<Pod>| {
+            let _ = sender.send(event.clone());
+        });
+        let task = tokio::spawn({
+            let informer = informer.clone();
+            async move { informer.run().await }
+        });
+        informer.wait_for_sync().await;
+        let Some(Event::Added(added)) = receiver.recv().await else { panic!("expected a") };
+        assert_eq!(added.spec.node_name.as_deref(), Some("node-1"));
+
+        server.create(pod("b", "node-2")).unwrap();
+        let Some(Event::Added(added)) = receiver.recv().await else { panic!("expected b") };
+        assert_eq!(added.metadata.name.as_deref(), Some("b"));
+        assert_eq!(added.spec.containers[0].image.as_deref(), Some("app:1"));
+        assert_eq!(informer.store().by_index("node", "node-2").len(), 1);
+        task.abort();
+    }
+}
@@ -0,0 +1,208 @@
+//! The local store of an informer: the objects it has seen, by key and by the values of its indexers.
+//!
+//! Source <https://github.com/kubernetes/client-go/blob/master/tools/cache/thread_safe_store.go>
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard}
+};
+
+use crate::{client::Meta, core::Pod};
+
+/// Name of the [`by_namespace`] index.
+pub const NAMESPACE_INDEX: &str = "namespace";
+/// Name of the [`by_owner_uid`] index.
+pub const OWNER_INDEX: &str = "owner";
+/// Name of the [`by_node_name`] index.
+pub const NODE_NAME_INDEX: &str = "nodeName";
+
+/// The values an object is indexed under.
+pub type IndexFn<K> = Arc<dyn Fn(&K) -> Vec<String> + Send + Sync>;
+
+/// The namespace of the object, none if cluster-scoped.
+pub fn by_namespace<K: Meta>(object: &K) -> Vec<String> {
+    object.namespace().filter(|namespace| !namespace.is_empty())
+        .map(str::to_owned).into_iter().collect()
+}
+
+/// The UIDs of the owners of the object.
+pub fn by_owner_uid<K: Meta>(object: &K) -> Vec<String> {
+    object.owner_references().into_iter().map(|reference| reference.uid).collect()
+}
+
+/// The node the pod is bound to, none if unscheduled.
+pub fn by_node_name(pod: &Pod) -> Vec<String> {
+    pod.spec.node_name.iter().filter(|name| !name.is_empty()).cloned().collect()
+}
+
+/// Objects by [`key`][Meta::key], shared between an informer and its readers, cheap to clone.
+pub struct Store<K> {
+    inner: Arc<RwLock<Inner<K>>>
+}
+
+struct Inner<K> {
+    items: BTreeMap<String, Arc<K>>,
+    indexers: HashMap<String, IndexFn<K>>,
+    /// Keys of the objects by value, by index.
+    indices: HashMap<String, HashMap<String, BTreeSet<String>>>
+}
+
+impl<K> Clone for Store<K> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+impl<K: Meta> Default for Store<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Meta> Store<K> {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Inner {
+                items: BTreeMap::new(),
+                indexers: HashMap::new(),
+                indices: HashMap::new()
+            }))
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Inner<K>> {
+        self.inner.read().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Inner<K>> {
+        self.inner.write().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Indexes objects under `name` by what `index` returns for them, those already in the store included.
+    pub fn add_indexer(&self, name: &str, index: impl Fn(&K) -> Vec<String> + Send + Sync + 'static) {
+        let mut inner = self.write();
+        let index: IndexFn<K> = Arc::new(index);
+        let mut values: HashMap<String, BTreeSet<String>> = HashMap::new();
+        for (key, object) in &inner.items {
+            for value in index(object) {
+                values.entry(value).or_default().insert(key.clone());
+            }
+        }
+        inner.indices.insert(name.to_owned(), values);
+        inner.indexers.insert(name.to_owned(), index);
+    }
+
+    pub fn get(&self, key: &str) -> Option<Arc<K>> {
+        self.read().items.get(key).cloned()
+    }
+
+    /// The object `name` in `namespace`, `None` for cluster-scoped objects.
+    pub fn get_by_name(&self, namespace: Option<&str>, name: &str) -> Option<Arc<K>> {
+        match namespace {
+            Some(namespace) => self.get(&format!("{}/{}", namespace, name)),
+            None => self.get(name)
+        }
+    }
+
+    /// All objects, by key.
+    pub fn list(&self) -> Vec<Arc<K>> {
+        self.read().items.values().cloned().collect()
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.read().items.keys().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().items.is_empty()
+    }
+
+    /// The objects indexed under `value` by the indexer `name`, none if there is no such indexer.
+    pub fn by_index(&self, name: &str, value: &str) -> Vec<Arc<K>> {
+        let inner = self.read();
+        inner.indices.get(name).and_then(|values| values.get(value)).into_iter().flatten()
+            .filter_map(|key| inner.items.get(key).cloned())
+            .collect()
+    }
+
+    /// The values of the indexer `name` that objects are indexed under.
+    pub fn index_values(&self, name: &str) -> Vec<String> {
+        let inner = self.read();
+        let mut values: Vec<String> = inner.indices.get(name).into_iter().flatten()
+            .map(|(value, _)| value.clone())
+            .collect();
+        values.sort();
+        values
+    }
+
+    /// Adds or replaces the object, returning the one it replaces.
+    pub fn insert(&self, object: Arc<K>) -> Option<Arc<K>> {
+        self.write().insert(object)
+    }
+
+    /// Removes the object of `key`, returning it.
+    pub fn remove(&self, key: &str) -> Option<Arc<K>> {
+        self.write().remove(key)
+    }
+
+    /**
+    Replaces all objects with `objects`, as after a list, returning those that were removed and, for those added or replaced, what they replace.
+    */
+    pub fn replace(&self, objects: Vec<Arc<K>>) -> Replaced<K> {
+        let mut inner = self.write();
+        let mut stale: BTreeMap<String, Arc<K>> = std::mem::take(&mut inner.items);
+        for values in inner.indices.values_mut() {
+            values.clear();
+        }
+        let mut replaced = Replaced { inserted: Vec::new(), removed: Vec::new() };
+        for object in objects {
+            let old = stale.remove(&object.key());
+            inner.insert(object.clone());
+            replaced.inserted.push((old, object));
+        }
+        replaced.removed = stale.into_values().collect();
+        replaced
+    }
+}
+
+/// What [`Store::replace`] did.
+pub struct Replaced<K> {
+    /// Objects added, with those they replace.
+    pub inserted: Vec<(Option<Arc<K>>, Arc<K>)>,
+    pub removed: Vec<Arc<K>>
+}
+
+impl<K: Meta> Inner<K> {
+    fn insert(&mut self, object: Arc<K>) -> Option<Arc<K>> {
+        let key = object.key();
+        let old = self.remove(&key);
+        for (name, index) in &self.indexers {
+            let values = self.indices.entry(name.clone()).or_default();
+            for value in index(&object) {
+                values.entry(value).or_default().insert(key.clone());
+            }
+        }
+        self.items.insert(key, object);
+        old
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Arc<K>> {
+        let old = self.items.remove(key)?;
+        for (name, index) in &self.indexers {
+            let Some(values) = self.indices.get_mut(name) else { continue };
+            for value in index(&old) {
+                if let Some(keys) = values.get_mut(&value) {
+                    keys.remove(key);
+                    if keys.is_empty() {
+                        values.remove(&value);
+                    }
+                }
+            }
+        }
+        Some(old)
+    }
+}
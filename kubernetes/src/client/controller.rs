@@ -0,0 +1,347 @@
+/*!
+Controllers: reconcilers driven by informers through a work queue.
+
+A controller reconciles objects by [`Request`], the namespace and name of an object, never by event: whatever changed, the reconciler reads the current state, from the stores of informers usually, and acts to bring it to the desired one. Informers only say which objects to look at, the objects themselves or, for the objects they own, their owners.
+
+Requests failing are retried with per-item backoff, and requests are let through a token bucket overall, by the [`RateLimiter`] of the queue. Reconcilers may also ask to look again after a while.
+
+- Concepts <https://kubernetes.io/docs/concepts/architecture/controller/>
+- Source <https://github.com/kubernetes-sigs/controller-runtime/blob/main/pkg/internal/controller/controller.go>
+*/
+
+pub mod queue;
+
+use std::{
+    fmt,
+    future::Future,
+    sync::Arc,
+    time::Duration
+};
+
+use tokio::task::JoinSet;
+
+use crate::meta::{
+    metadata::OwnerReference,
+    Metadata
+};
+use super::{
+    cache::{Event, Informer},
+    BoxFuture, Meta
+};
+
+pub use queue::{RateLimiter, WorkQueue};
+use queue::MaxOf;
+
+/// The object to reconcile.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Request {
+    /// `None` for cluster-scoped objects.
+    pub namespace: Option<String>,
+    pub name: String
+}
+
+impl Request {
+    pub fn new(namespace: Option<&str>, name: &str) -> Self {
+        Self { namespace: namespace.map(str::to_owned), name: name.to_owned() }
+    }
+
+    pub fn from_object<K: Meta>(object: &K) -> Self {
+        Self::new(object.namespace().filter(|namespace| !namespace.is_empty()),
+            object.name().unwrap_or_default())
+    }
+
+    /// The request of the owner `reference` refers to, from an object in `namespace`; owners are in the namespace of what they own, or cluster-scoped.
+    pub fn from_owner(namespace: Option<&str>, reference: &OwnerReference) -> Self {
+        Self::new(namespace, &reference.name)
+    }
+
+    /// The key of the object in a [`Store`][super::cache::Store].
+    pub fn key(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.namespace {
+            Some(namespace) => write!(f, "{}/{}", namespace, self.name),
+            None => f.write_str(&self.name)
+        }
+    }
+}
+
+/// What to do with a request once reconciled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Nothing until the object changes again.
+    Done,
+    /// Reconcile again after the backoff of the request, as if it failed.
+    Requeue,
+    /// Reconcile again after this long, as for what changes with time.
+    RequeueAfter(Duration)
+}
+
+/// Brings an object to its desired state.
+pub trait Reconcile: Send + Sync + 'static {
+    type Error: fmt::Display + Send;
+
+    /// Reconciles the object of `request`, which may no longer exist. Must be idempotent: it may run any number of times for the same state.
+    fn reconcile<'a>(&'a self, request: &'a Request) -> BoxFuture<'a, Result<Action, Self::Error>>;
+}
+
+/// What a reconciler guarding its cleanup with a finaliser must do with an object, from [`finalising`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finalising {
+    /// Add the finaliser and update the object, before anything else.
+    Add,
+    /// Reconcile as usual.
+    Apply,
+    /// Clean up, then remove the finaliser and update the object, so that it is deleted.
+    Cleanup,
+    /// The object is being deleted and already cleaned up; do nothing.
+    Done
+}
+
+/// What to do with an object of `metadata`, guarded with `finaliser`.
+pub fn finalising(metadata: &Metadata, finaliser: &str) -> Finalising {
+    match (metadata.is_being_deleted(), metadata.has_finaliser(finaliser)) {
+        (false, false) => Finalising::Add,
+        (false, true) => Finalising::Apply,
+        (true, true) => Finalising::Cleanup,
+        (true, false) => Finalising::Done
+    }
+}
+
+type ErrorHandler<E> = Arc<dyn Fn(&Request, &E) + Send + Sync>;
+
+/// Runs a reconciler on the requests queued by the informers it watches.
+pub struct Controller<R: Reconcile> {
+    reconciler: Arc<R>,
+    queue: Arc<WorkQueue<Request>>,
+    on_error: Option<ErrorHandler<R::Error>>
+}
+
+impl<R: Reconcile> Controller<R> {
+    /// With per-request backoff from 5ms to 1000s, and 10 requests a second overall in bursts of 100.
+    pub fn new(reconciler: R) -> Self {
+        Self::with_rate_limiter(reconciler, MaxOf::default_controller())
+    }
+
+    pub fn with_rate_limiter(reconciler: R, rate_limiter: impl RateLimiter<Request> + 'static) -> Self {
+        Self {
+            reconciler: Arc::new(reconciler),
+            queue: Arc::new(WorkQueue::new(rate_limiter)),
+            on_error: None
+        }
+    }
+
+    /// Calls `on_error` with the errors of reconciles, which are otherwise only retried.
+    pub fn on_error(mut self, on_error: impl Fn(&Request, &R::Error) + Send + Sync + 'static) -> Self {
+        self.on_error = Some(Arc::new(on_error));
+        self
+    }
+
+    pub fn reconciler(&self) -> &Arc<R> {
+        &self.reconciler
+    }
+
+    /// The queue, to add requests to directly.
+    pub fn queue(&self) -> &Arc<WorkQueue<Request>> {
+        &self.queue
+    }
+
+    /// Reconciles the objects of `informer` when they change.
+    pub fn watch<K: Meta + Send + Sync + 'static>(&self, informer: &Informer<K>) {
+        self.watch_mapped(informer, |object: &K| vec![Request::from_object(object)]);
+    }
+
+    /**
+    Reconciles the controllers of the objects of `informer` when they change, those whose owner reference marked as controller is of `kind` in the group of `api_version`.
+    */
+    pub fn watch_owned<K: Meta + Send + Sync + 'static>(&self, informer: &Informer<K>,
+        api_version: &str, kind: &str)
+    {
+        let owner_group = group(api_version).to_owned();
+        let kind = kind.to_owned();
+        self.watch_mapped(informer, move |object: &K| {
+            let namespace = object.namespace().filter(|namespace| !namespace.is_empty());
+            object.owner_references().iter()
+                .filter(|reference| reference.controller == Some(true) && reference.kind == kind
+                    && group(&reference.api_version) == owner_group)
+                .map(|reference| Request::from_owner(namespace, reference))
+                .collect()
+        });
+    }
+
+    /// Reconciles the requests `map` returns for the objects of `informer` when they change, before and after.
+    pub fn watch_mapped<K: Meta + Send + Sync + 'static>(&self, informer: &Informer<K>,
+        map: impl Fn(&K) -> Vec<Request> + Send + Sync + 'static)
+    {
+        let queue = self.queue.clone();
+        informer.add_handler(move |event: &Event<K>| {
+            let requests = match event {
+                Event::Added(object) | Event::Deleted(object) => map(object),
+                Event::Updated { old, new } if Arc::ptr_eq(old, new) => map(new),
+                Event::Updated { old, new } => {
+                    let mut requests = map(old);
+                    requests.extend(map(new));
+                    requests
+                }
+            };
+            for request in requests {
+                queue.add(request);
+            }
+        });
+    }
+
+    /**
+    Reconciles with `workers` at once until `shutdown` completes, then lets the reconciles in progress finish and returns; requests still queued are dropped.
+    */
+    pub async fn run(&self, workers: usize, shutdown: impl Future<Output = ()>) {
+        let mut tasks = JoinSet::new();
+        for _ in 0..workers.max(1) {
+            let reconciler = self.reconciler.clone();
+            let queue = self.queue.clone();
+            let on_error = self.on_error.clone();
+            tasks.spawn(async move {
+                while let Some(request) = queue.get().await {
+                    if queue.is_shutting_down() {
+                        queue.done(&request);
+                        break
+                    }
+                    match reconciler.reconcile(&request).await {
+                        Ok(Action::Done) => queue.forget(&request),
+                        Ok(Action::Requeue) => queue.add_rate_limited(request.clone()),
+                        Ok(Action::RequeueAfter(delay)) => {
+                            queue.forget(&request);
+                            queue.add_after(request.clone(), delay);
+                        }
+                        Err(error) => {
+                            if let Some(on_error) = &on_error {
+                                on_error(&request, &error);
+                            }
+                            queue.add_rate_limited(request.clone());
+                        }
+                    }
+                    queue.done(&request);
+                }
+            });
+        }
+        shutdown.await;
+        self.queue.shut_down();
+        while tasks.join_next().await.is_some() {}
+    }
+}
+
+/// The group of `api_version`, empty for the core group.
+fn group(api_version: &str) -> &str {
+    api_version.rsplit_once('/').map_or("", |(group, _)| group)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde_json::json;
+    use tokio::sync::mpsc;
+
+    use crate::{
+        api_server::fake::FakeApiServer,
+        client::{Api, ListParams},
+        core::Pod,
+        time::Time
+    };
+    use super::*;
+
+    /// Records the requests it reconciles, failing the first attempt at each.
+    struct Recorder {
+        attempts: Mutex<Vec<Request>>,
+        reconciled: mpsc::UnboundedSender<Request>
+    }
+
+    impl Reconcile for Recorder {
+        type Error = String;
+
+        fn reconcile<'a>(&'a self, request: &'a Request)
+            -> BoxFuture<'a, Result<Action, Self::Error>>
+        {
+            Box::pin(async move {
+                let mut attempts = self.attempts.lock().unwrap();
+                let first = !attempts.contains(request);
+                attempts.push(request.clone());
+                if first {
+                    return Err(format!("{} is not ready", request))
+                }
+                let _ = self.reconciled.send(request.clone());
+                Ok(Action::Done)
+            })
+        }
+    }
+
+    fn pod(name: &str, owner: &str) -> serde_json::Value {
+        json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {
+                "name": name,
+                "namespace": "default",
+                "ownerReferences": [{
+                    "apiVersion": "apps/v1",
+                    "kind": "ReplicaSet",
+                    "name": owner,
+                    "uid": format!("{}-uid", owner),
+                    "controller": true
+                }]
+            },
+            "spec": { "containers": [{ "name": "app", "image": "app:1" }] }
+        })
+    }
+
+    #[test]
+    fn finalises() {
+        let mut metadata = Metadata::default();
+        assert_eq!(finalising(&metadata, "example.com/cleanup"), Finalising::Add);
+        metadata.finalisers.push("example.com/cleanup".to_owned());
+        assert_eq!(finalising(&metadata, "example.com/cleanup"), Finalising::Apply);
+        metadata.deletion_timestamp = Some(Time::now());
+        assert_eq!(finalising(&metadata, "example.com/cleanup"), Finalising::Cleanup);
+        metadata.finalisers.clear();
+        assert_eq!(finalising(&metadata, "example.com/cleanup"), Finalising::Done);
+    }
+
+    #[tokio::test]
+    async fn reconciles_owners_until_they_succeed() {
+        let server = FakeApiServer::start().await.unwrap();
+        server.create(pod("web-1", "web")).unwrap();
+        server.create(pod("web-2", "web")).unwrap();
+        let api = Api::<Pod>::namespaced(server.client(), "default");
+        let informer = Arc::new(Informer::new(api, ListParams::default(), None));
+        let (sender, mut reconciled) = mpsc::unbounded_channel();
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let controller = Controller::new(Recorder { attempts: Mutex::default(), reconciled: sender })
+            .on_error({
+                let errors = errors.clone();
+                move |_, error: &String| errors.lock().unwrap().push(error.clone())
+            });
+        controller.watch_owned(&informer, "apps/v1", "ReplicaSet");
+        // Pods of other kinds of owners are not reconciled.
+        controller.watch_owned(&informer, "apps/v1", "StatefulSet");
+        let informing = tokio::spawn({
+            let informer = informer.clone();
+            async move { informer.run().await }
+        });
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let running = tokio::spawn(async move {
+            controller.run(2, async { stopped.await.ok(); }).await;
+            controller
+        });
+
+        assert_eq!(reconciled.recv().await, Some(Request::new(Some("default"), "web")));
+        stop.send(()).unwrap();
+        let controller = running.await.unwrap();
+        informing.abort();
+        assert_eq!(*errors.lock().unwrap(), ["default/web is not ready"]);
+        let attempts = controller.reconciler().attempts.lock().unwrap();
+        assert!(attempts.iter().all(|request| request.name == "web"));
+    }
+}
@@ -0,0 +1,392 @@
+/*!
+The work queue of controllers: items to reconcile, each queued once however often it is added, and never handed to two workers at once.
+
+An item added while a worker has it is queued again when the worker is done with it. Failing items are retried later and later, as the [`RateLimiter`] of the queue says.
+
+- Source <https://github.com/kubernetes/client-go/blob/master/util/workqueue/queue.go>
+- Source <https://github.com/kubernetes/client-go/blob/master/util/workqueue/default_rate_limiters.go>
+*/
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    hash::Hash,
+    sync::{Mutex, MutexGuard},
+    time::Duration
+};
+
+use tokio::{
+    sync::Notify,
+    time::{self, Instant}
+};
+
+/// How long failing items wait before they are retried.
+pub trait RateLimiter<T>: Send {
+    /// How long `item` waits at `now`, counted as a failure of it.
+    fn when(&mut self, item: &T, now: Instant) -> Duration;
+    /// Stops counting the failures of `item`, as it succeeded.
+    fn forget(&mut self, item: &T);
+    /// Failures counted of `item`.
+    fn retries(&self, item: &T) -> u32;
+}
+
+/// Waits twice as long after each failure of an item: `base`, `2 * base`... up to `max`.
+#[derive(Debug, Clone)]
+pub struct ItemBackoff<T> {
+    pub base: Duration,
+    pub max: Duration,
+    failures: HashMap<T, u32>
+}
+
+impl<T: Eq + Hash> ItemBackoff<T> {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self { base, max, failures: HashMap::new() }
+    }
+}
+
+impl<T: Clone + Eq + Hash + Send> RateLimiter<T> for ItemBackoff<T> {
+    fn when(&mut self, item: &T, _now: Instant) -> Duration {
+        let failures = self.failures.entry(item.clone()).or_default();
+        let exponent = *failures;
+        *failures += 1;
+        2u32.checked_pow(exponent)
+            .and_then(|factor| self.base.checked_mul(factor))
+            .map_or(self.max, |wait| wait.min(self.max))
+    }
+
+    fn forget(&mut self, item: &T) {
+        self.failures.remove(item);
+    }
+
+    fn retries(&self, item: &T) -> u32 {
+        self.failures.get(item).copied().unwrap_or_default()
+    }
+}
+
+/// Lets `burst` items through at once, then `qps` per second across items.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    pub qps: f64,
+    pub burst: u32,
+    tokens: f64,
+    last: Option<Instant>
+}
+
+impl TokenBucket {
+    pub fn new(qps: f64, burst: u32) -> Self {
+        Self { qps, burst, tokens: f64::from(burst), last: None }
+    }
+}
+
+impl<T> RateLimiter<T> for TokenBucket {
+    fn when(&mut self, _item: &T, now: Instant) -> Duration {
+        if let Some(last) = self.last {
+            let elapsed = now.saturating_duration_since(last).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * self.qps).min(f64::from(self.burst));
+        }
+        self.last = Some(now);
+        // Reserves a token, possibly ahead of time, so that waits add up.
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 || self.qps <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.qps)
+        }
+    }
+
+    fn forget(&mut self, _item: &T) {}
+
+    fn retries(&self, _item: &T) -> u32 {
+        0
+    }
+}
+
+/// The longest wait of its rate limiters.
+pub struct MaxOf<T>(pub Vec<Box<dyn RateLimiter<T>>>);
+
+impl<T: Clone + Eq + Hash + Send + 'static> MaxOf<T> {
+    /// Per-item backoff from 5ms to 1000s, and 10 items a second overall, in bursts of 100.
+    pub fn default_controller() -> Self {
+        Self(vec![
+            Box::new(ItemBackoff::new(Duration::from_millis(5), Duration::from_secs(1000))),
+            Box::new(TokenBucket::new(10.0, 100))
+        ])
+    }
+}
+
+impl<T> RateLimiter<T> for MaxOf<T> {
+    fn when(&mut self, item: &T, now: Instant) -> Duration {
+        self.0.iter_mut().map(|limiter| limiter.when(item, now)).max().unwrap_or_default()
+    }
+
+    fn forget(&mut self, item: &T) {
+        for limiter in &mut self.0 {
+            limiter.forget(item);
+        }
+    }
+
+    fn retries(&self, item: &T) -> u32 {
+        self.0.iter().map(|limiter| limiter.retries(item)).max().unwrap_or_default()
+    }
+}
+
+/// A deduplicating, delaying and rate-limited queue shared by the workers of a controller.
+pub struct WorkQueue<T> {
+    state: Mutex<State<T>>,
+    /// Woken when items are added or the queue shuts down.
+    notify: Notify
+}
+
+struct State<T> {
+    queue: VecDeque<T>,
+    /// Items to be handed out, queued or held by workers.
+    dirty: HashSet<T>,
+    /// Items held by workers.
+    processing: HashSet<T>,
+    /// Items due later, by when and then order of addition.
+    waiting: BTreeMap<(Instant, u64), T>,
+    /// When each waiting item is due, so that earlier additions win.
+    due: HashMap<T, (Instant, u64)>,
+    sequence: u64,
+    rate_limiter: Box<dyn RateLimiter<T>>,
+    shutting_down: bool
+}
+
+impl<T: Clone + Eq + Hash + Send> WorkQueue<T> {
+    pub fn new(rate_limiter: impl RateLimiter<T> + 'static) -> Self {
+        Self {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                dirty: HashSet::new(),
+                processing: HashSet::new(),
+                waiting: BTreeMap::new(),
+                due: HashMap::new(),
+                sequence: 0,
+                rate_limiter: Box::new(rate_limiter),
+                shutting_down: false
+            }),
+            notify: Notify::new()
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Queues `item` unless it is queued already; ignored once shutting down.
+    pub fn add(&self, item: T) {
+        let mut state = self.lock();
+        if state.add(item) {
+            self.notify.notify_one();
+        }
+    }
+
+    /// Queues `item` after `delay`, or sooner if it is added meanwhile.
+    pub fn add_after(&self, item: T, delay: Duration) {
+        if delay.is_zero() {
+            return self.add(item)
+        }
+        let mut state = self.lock();
+        if state.shutting_down {
+            return
+        }
+        let when = Instant::now() + delay;
+        if state.due.get(&item).is_some_and(|due| due.0 <= when) {
+            return
+        }
+        if let Some(due) = state.due.remove(&item) {
+            state.waiting.remove(&due);
+        }
+        state.sequence += 1;
+        let due = (when, state.sequence);
+        state.waiting.insert(due, item.clone());
+        state.due.insert(item, due);
+        // Workers waiting for a later item must wait less.
+        self.notify.notify_waiters();
+    }
+
+    /// Queues `item` after the wait the rate limiter says, counting a failure of it.
+    pub fn add_rate_limited(&self, item: T) {
+        let delay = self.lock().rate_limiter.when(&item, Instant::now());
+        self.add_after(item, delay);
+    }
+
+    /// Stops counting the failures of `item`, as it succeeded.
+    pub fn forget(&self, item: &T) {
+        self.lock().rate_limiter.forget(item);
+    }
+
+    pub fn retries(&self, item: &T) -> u32 {
+        self.lock().rate_limiter.retries(item)
+    }
+
+    /// Items queued, not counting those held by workers or waiting.
+    pub fn len(&self) -> usize {
+        self.lock().queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /**
+    The next item, once one is queued, for the caller to hold until [`done`][Self::done]; `None` once the queue shuts down and is empty.
+    */
+    pub async fn get(&self) -> Option<T> {
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            let next = {
+                let mut state = self.lock();
+                state.promote(Instant::now());
+                if let Some(item) = state.queue.pop_front() {
+                    state.processing.insert(item.clone());
+                    state.dirty.remove(&item);
+                    return Some(item)
+                }
+                if state.shutting_down {
+                    return None
+                }
+                state.waiting.keys().next().map(|due| due.0)
+            };
+            match next {
+                Some(when) => {
+                    tokio::select! {
+                        _ = &mut notified => {}
+                        _ = time::sleep_until(when) => {}
+                    }
+                }
+                None => notified.await
+            }
+        }
+    }
+
+    /// Releases `item`, queueing it again if it was added while held.
+    pub fn done(&self, item: &T) {
+        let mut state = self.lock();
+        state.processing.remove(item);
+        if state.dirty.contains(item) {
+            state.queue.push_back(item.clone());
+            self.notify.notify_one();
+        }
+    }
+
+    /// Stops taking items: [`get`][Self::get] returns those queued, then `None`. Waiting items are dropped.
+    pub fn shut_down(&self) {
+        let mut state = self.lock();
+        state.shutting_down = true;
+        state.waiting.clear();
+        state.due.clear();
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.lock().shutting_down
+    }
+}
+
+impl<T: Clone + Eq + Hash> State<T> {
+    /// Whether the item was queued.
+    fn add(&mut self, item: T) -> bool {
+        if self.shutting_down || self.dirty.contains(&item) {
+            return false
+        }
+        if let Some(due) = self.due.remove(&item) {
+            self.waiting.remove(&due);
+        }
+        self.dirty.insert(item.clone());
+        if self.processing.contains(&item) {
+            return false
+        }
+        self.queue.push_back(item);
+        true
+    }
+
+    /// Queues the waiting items due at `now`.
+    fn promote(&mut self, now: Instant) {
+        while let Some(entry) = self.waiting.first_entry() {
+            if entry.key().0 > now {
+                break
+            }
+            let item = entry.remove();
+            self.due.remove(&item);
+            self.add(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_per_item() {
+        let now = Instant::now();
+        let mut backoff = ItemBackoff::new(Duration::from_millis(5), Duration::from_millis(30));
+        let waits: Vec<Duration> = (0..5).map(|_| backoff.when(&"a", now)).collect();
+        assert_eq!(waits, [5, 10, 20, 30, 30].map(Duration::from_millis));
+        assert_eq!(backoff.when(&"b", now), Duration::from_millis(5));
+        assert_eq!(RateLimiter::retries(&backoff, &"a"), 5);
+        RateLimiter::forget(&mut backoff, &"a");
+        assert_eq!(backoff.when(&"a", now), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn lets_bursts_through() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, 2);
+        let mut when = |at: Instant| RateLimiter::<()>::when(&mut bucket, &(), at);
+        assert_eq!(when(now), Duration::ZERO);
+        assert_eq!(when(now), Duration::ZERO);
+        assert_eq!(when(now), Duration::from_millis(500));
+        assert_eq!(when(now), Duration::from_secs(1));
+        // A second later the tokens reserved are paid back.
+        assert_eq!(when(now + Duration::from_secs(1)), Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn hands_items_to_one_worker_at_a_time() {
+        let queue = WorkQueue::new(ItemBackoff::new(Duration::from_millis(5), Duration::from_secs(1)));
+        queue.add("a");
+        queue.add("b");
+        queue.add("a");
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.get().await, Some("a"));
+        // Added while held, so queued again only once done.
+        queue.add("a");
+        assert_eq!(queue.get().await, Some("b"));
+        assert!(queue.is_empty());
+        queue.done(&"a");
+        assert_eq!(queue.get().await, Some("a"));
+        queue.done(&"a");
+        queue.done(&"b");
+        queue.add("c");
+        queue.shut_down();
+        queue.add("d");
+        assert_eq!(queue.get().await, Some("c"));
+        assert_eq!(queue.get().await, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn delays_items() {
+        let queue = WorkQueue::new(ItemBackoff::new(Duration::from_millis(5), Duration::from_secs(1)));
+        let start = Instant::now();
+        queue.add_after("a", Duration::from_secs(10));
+        queue.add_after("b", Duration::from_secs(5));
+        // The earlier of two additions wins.
+        queue.add_after("a", Duration::from_secs(20));
+        queue.add_after("b", Duration::from_secs(2));
+        assert!(queue.is_empty());
+        assert_eq!(queue.get().await, Some("b"));
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+        assert_eq!(queue.get().await, Some("a"));
+        assert_eq!(start.elapsed(), Duration::from_secs(10));
+
+        queue.add_rate_limited("c");
+        queue.add_rate_limited("c");
+        assert_eq!(queue.retries(&"c"), 2);
+        assert_eq!(queue.get().await, Some("c"));
+        // The first wait, 5ms, is the earlier.
+        assert_eq!(start.elapsed(), Duration::from_millis(10_005));
+    }
+}
@@ -0,0 +1,410 @@
+//! Resource types as the client addresses them, and the objects it sends and receives.
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    apps::deployment::{self, Deployment},
+    coordination::{lease::Spec, Lease},
+    core::{pod, Pod},
+    kubectl::resource,
+    meta::{metadata::OwnerReference, Metadata}
+};
+
+/// A resource type served by the API: where its objects are and whether they are namespaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiResource {
+    /// Empty for the core group.
+    pub group: String,
+    pub version: String,
+    pub kind: String,
+    /// As in paths, `deployments`.
+    pub plural: String,
+    pub namespaced: bool
+}
+
+impl ApiResource {
+    pub fn new(group: &str, version: &str, kind: &str, plural: &str, namespaced: bool) -> Self {
+        Self {
+            group: group.to_owned(),
+            version: version.to_owned(),
+            kind: kind.to_owned(),
+            plural: plural.to_owned(),
+            namespaced
+        }
+    }
+
+    /// `v1` or `<group>/<version>`.
+    pub fn api_version(&self) -> String {
+        if self.group.is_empty() {
+            self.version.clone()
+        } else {
+            format!("{}/{}", self.group, self.version)
+        }
+    }
+
+    /**
+    The path of the collection, or of the object `name` and its `subresource`: `/api/v1/namespaces/<namespace>/pods/<name>/status` for the core group, `/apis/<group>/<version>/...` for the others.
+
+    `namespace` is ignored for cluster-scoped resources, and lists across namespaces if `None`.
+    */
+    pub fn path(&self, namespace: Option<&str>, name: Option<&str>, subresource: Option<&str>) -> String {
+        let mut path = if self.group.is_empty() {
+            format!("/api/{}", self.version)
+        } else {
+            format!("/apis/{}/{}", self.group, self.version)
+        };
+        if let Some(namespace) = namespace.filter(|_| self.namespaced) {
+            path.push_str("/namespaces/");
+            path.push_str(namespace);
+        }
+        path.push('/');
+        path.push_str(&self.plural);
+        if let Some(name) = name {
+            path.push('/');
+            path.push_str(name);
+            if let Some(subresource) = subresource {
+                path.push('/');
+                path.push_str(subresource);
+            }
+        }
+        path
+    }
+}
+
+impl From<&resource::Resource> for ApiResource {
+    fn from(resource: &resource::Resource) -> Self {
+        Self::new(resource.group, resource.version, resource.kind, resource.name, resource.namespaced)
+    }
+}
+
+/// An object as sent and received in JSON.
+pub trait Object: Sized + Send {
+    fn from_json(value: Value) -> Result<Self, String>;
+    fn to_json(&self) -> Value;
+}
+
+/// A typed object of a known resource type.
+pub trait Resource: Object {
+    fn api_resource() -> ApiResource;
+}
+
+/**
+Access to the metadata of an object, by which caches key and index it.
+
+Typed objects only give their [`Metadata`]; the other methods read from it.
+*/
+pub trait Meta {
+    fn metadata(&self) -> Option<&Metadata>;
+
+    fn namespace(&self) -> Option<&str> {
+        self.metadata().and_then(|metadata| metadata.namespace.as_deref())
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.metadata().and_then(|metadata| metadata.name.as_deref())
+    }
+
+    fn uid(&self) -> Option<&str> {
+        self.metadata().and_then(|metadata| metadata.uid.as_deref())
+    }
+
+    fn resource_version(&self) -> Option<&str> {
+        self.metadata().and_then(|metadata| metadata.resource_version.as_deref())
+    }
+
+    fn owner_references(&self) -> Vec<OwnerReference> {
+        self.metadata().map(|metadata| metadata.owner_references.clone()).unwrap_or_default()
+    }
+
+    /// `<namespace>/<name>`, or `<name>` for cluster-scoped objects.
+    fn key(&self) -> String {
+        let name = self.name().unwrap_or_default();
+        match self.namespace().filter(|namespace| !namespace.is_empty()) {
+            Some(namespace) => format!("{}/{}", namespace, name),
+            None => name.to_owned()
+        }
+    }
+}
+
+impl Meta for Value {
+    fn metadata(&self) -> Option<&Metadata> {
+        None
+    }
+
+    fn namespace(&self) -> Option<&str> {
+        self.pointer("/metadata/namespace").and_then(Value::as_str)
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.pointer("/metadata/name").and_then(Value::as_str)
+    }
+
+    fn uid(&self) -> Option<&str> {
+        self.pointer("/metadata/uid").and_then(Value::as_str)
+    }
+
+    fn resource_version(&self) -> Option<&str> {
+        self.pointer("/metadata/resourceVersion").and_then(Value::as_str)
+    }
+
+    fn owner_references(&self) -> Vec<OwnerReference> {
+        self.pointer("/metadata/ownerReferences").and_then(Value::as_array).into_iter().flatten()
+            .map(OwnerReference::from_json)
+            .collect()
+    }
+}
+
+impl Meta for Pod {
+    fn metadata(&self) -> Option<&Metadata> {
+        Some(&self.metadata)
+    }
+}
+
+impl Meta for Deployment {
+    fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+}
+
+impl Meta for Lease {
+    fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+}
+
+/// Objects of any resource, unstructured.
+impl Object for Value {
+    fn from_json(value: Value) -> Result<Self, String> {
+        Ok(value)
+    }
+
+    fn to_json(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl Object for Lease {
+    fn from_json(value: Value) -> Result<Self, String> {
+        let invalid = |message: String| format!("error decoding lease: {}", message);
+        check_kind(&value, "Lease").map_err(invalid)?;
+        let empty = Map::new();
+        let spec = value.get("spec").and_then(Value::as_object).unwrap_or(&empty);
+        let string = |key: &str| spec.get(key).and_then(Value::as_str).map(str::to_owned);
+        let int = |key: &str| spec.get(key).and_then(Value::as_i64)
+            .and_then(|value| i32::try_from(value).ok());
+        let time = |key: &str| string(key)
+            .map(|time| time.parse().map_err(invalid))
+            .transpose();
+        Ok(Lease {
+            metadata: value.get("metadata").map(Metadata::from_json),
+            spec: Spec {
+                acquire_time: time("acquireTime")?,
+                holder_identity: string("holderIdentity"),
+                lease_duration_seconds: int("leaseDurationSeconds"),
+                lease_transitions: int("leaseTransitions"),
+                renew_time: time("renewTime")?
+            }
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        let Spec {
+            acquire_time,
+            holder_identity,
+            lease_duration_seconds,
+            lease_transitions,
+            renew_time
+        } = &self.spec;
+        let mut spec = Map::new();
+        if let Some(holder_identity) = holder_identity {
+            spec.insert("holderIdentity".to_owned(), holder_identity.as_str().into());
+        }
+        if let Some(seconds) = lease_duration_seconds {
+            spec.insert("leaseDurationSeconds".to_owned(), (*seconds).into());
+        }
+        if let Some(time) = acquire_time {
+            spec.insert("acquireTime".to_owned(), time.to_string().into());
+        }
+        if let Some(time) = renew_time {
+            spec.insert("renewTime".to_owned(), time.to_string().into());
+        }
+        if let Some(transitions) = lease_transitions {
+            spec.insert("leaseTransitions".to_owned(), (*transitions).into());
+        }
+        json!({
+            "apiVersion": "coordination.k8s.io/v1",
+            "kind": "Lease",
+            "metadata": self.metadata.as_ref().map_or_else(|| json!({}), Metadata::to_json),
+            "spec": spec
+        })
+    }
+}
+
+impl Resource for Lease {
+    fn api_resource() -> ApiResource {
+        ApiResource::new("coordination.k8s.io", "v1", "Lease", "leases", true)
+    }
+}
+
+impl Object for Pod {
+    fn from_json(value: Value) -> Result<Self, String> {
+        check_kind(&value, "Pod")
+            .map_err(|message| format!("error decoding pod: {}", message))?;
+        Ok(Pod {
+            metadata: value.get("metadata").map(Metadata::from_json).unwrap_or_default(),
+            spec: value.get("spec").map(pod::Spec::from_json).unwrap_or_default(),
+            status: value.get("status").map(pod::Status::from_json)
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        let mut value = json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": self.metadata.to_json(),
+            "spec": self.spec.to_json()
+        });
+        if let Some(status) = &self.status {
+            value["status"] = status.to_json();
+        }
+        value
+    }
+}
+
+impl Resource for Pod {
+    fn api_resource() -> ApiResource {
+        ApiResource::new("", "v1", "Pod", "pods", true)
+    }
+}
+
+impl Object for Deployment {
+    fn from_json(value: Value) -> Result<Self, String> {
+        check_kind(&value, "Deployment")
+            .map_err(|message| format!("error decoding deployment: {}", message))?;
+        Ok(Deployment {
+            metadata: value.get("metadata").map(Metadata::from_json),
+            spec: value.get("spec").map(deployment::Spec::from_json),
+            status: value.get("status").map(deployment::Status::from_json)
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        let mut value = json!({
+            "apiVersion": "apps/v1",
+            "kind": "Deployment",
+            "metadata": self.metadata.as_ref().map_or_else(|| json!({}), Metadata::to_json)
+        });
+        if let Some(spec) = &self.spec {
+            value["spec"] = spec.to_json();
+        }
+        if let Some(status) = &self.status {
+            value["status"] = status.to_json();
+        }
+        value
+    }
+}
+
+impl Resource for Deployment {
+    fn api_resource() -> ApiResource {
+        ApiResource::new("apps", "v1", "Deployment", "deployments", true)
+    }
+}
+
+/// Fails unless `value` is an object of `kind`, or of no kind as in the items of lists.
+fn check_kind(value: &Value, kind: &str) -> Result<(), String> {
+    let Value::Object(object) = value else {
+        return Err("expected a JSON object".to_owned())
+    };
+    match object.get("kind").and_then(Value::as_str) {
+        Some(other) if other != kind => Err(format!("expected kind {}", kind)),
+        _ => Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        api_server::fake::FakeApiServer,
+        apps::deployment::DeploymentStrategy,
+        client::{Api, PostParams},
+        IntOrString
+    };
+    use super::*;
+
+    #[test]
+    fn builds_paths() {
+        assert_eq!(Pod::api_resource().path(Some("default"), Some("web"), Some("status")),
+            "/api/v1/namespaces/default/pods/web/status");
+        assert_eq!(Pod::api_resource().path(None, None, None), "/api/v1/pods");
+        assert_eq!(Deployment::api_resource().path(Some("default"), None, None),
+            "/apis/apps/v1/namespaces/default/deployments");
+        assert_eq!(Deployment::api_resource().api_version(), "apps/v1");
+    }
+
+    #[tokio::test]
+    async fn reads_and_writes_typed_objects() {
+        let server = FakeApiServer::start().await.unwrap();
+        server.create(json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": { "name": "web", "namespace": "default", "labels": { "app": "web" } },
+            "spec": {
+                "nodeName": "node-1",
+                "restartPolicy": "OnFailure",
+                "containers": [{
+                    "name": "nginx",
+                    "image": "nginx:1.25",
+                    "ports": [{ "containerPort": 80, "protocol": "TCP" }],
+                    "resources": { "requests": { "cpu": "100m" } }
+                }]
+            },
+            "status": {
+                "phase": "Running",
+                "conditions": [{
+                    "type": "Ready",
+                    "status": "True",
+                    "lastTransitionTime": "2024-01-01T00:00:00Z"
+                }]
+            }
+        })).unwrap();
+        let pods = Api::<Pod>::namespaced(server.client(), "default");
+        let mut pod = pods.get("web").await.unwrap();
+        assert_eq!(pod.spec.node_name.as_deref(), Some("node-1"));
+        assert_eq!(pod.spec.restart_policy, pod::RestartPolicy::OnFailure);
+        assert_eq!(pod.spec.containers[0].image.as_deref(), Some("nginx:1.25"));
+        assert_eq!(pod.spec.containers[0].ports[0].container_port, 80);
+        assert!(pod.is_ready());
+
+        pod.metadata.labels.insert("tier".to_owned(), "frontend".to_owned());
+        let replaced = pods.replace("web", &pod, &PostParams::default()).await.unwrap();
+        assert_eq!(replaced.metadata.labels.get("tier").map(String::as_str), Some("frontend"));
+        let stored = server.get(&Pod::api_resource(), Some("default"), "web").unwrap();
+        assert_eq!(stored["spec"]["containers"][0]["resources"]["requests"]["cpu"], "100m");
+        assert_eq!(stored["spec"]["restartPolicy"], "OnFailure");
+
+        let deployment = Deployment::from_json(json!({
+            "metadata": { "name": "web" },
+            "spec": {
+                "replicas": 3,
+                "selector": { "matchLabels": { "app": "web" } },
+                "template": {
+                    "metadata": { "labels": { "app": "web" } },
+                    "spec": { "containers": [{ "name": "nginx", "image": "nginx:1.25" }] }
+                },
+                "strategy": { "type": "RollingUpdate", "rollingUpdate": { "maxSurge": "25%" } }
+            }
+        })).unwrap();
+        let deployments = Api::<Deployment>::namespaced(server.client(), "default");
+        let created = deployments.create(&deployment, &PostParams::default()).await.unwrap();
+        let spec = created.spec.unwrap();
+        assert_eq!(spec.replicas, Some(3));
+        assert!(spec.selector.matches(&spec.template.metadata.unwrap().labels));
+        let Some(DeploymentStrategy::RollingUpdate(rolling_update)) = spec.strategy else {
+            panic!("expected a rolling update, got {:?}", spec.strategy)
+        };
+        assert_eq!(rolling_update.max_surge, Some(IntOrString::String("25%".to_owned())));
+        assert!(Deployment::from_json(json!({ "kind": "Pod" })).is_err());
+    }
+}
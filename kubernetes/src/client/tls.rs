@@ -0,0 +1,233 @@
+/*!
+A [`Connector`] speaking TLS with rustls, for `https://` servers, set up as the kubeconfig says: its certificate authorities, the client certificate and key of the user, and `insecure-skip-tls-verify`.
+
+- Source <https://github.com/kubernetes/client-go/blob/master/transport/transport.go>
+*/
+
+use std::{io, sync::Arc};
+
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{self, WebPkiSupportedAlgorithms},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName, UnixTime},
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme
+};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+
+use super::{BoxFuture, Connector, Io, Tls};
+
+/// TCP connections, over TLS for `https://` servers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RustlsConnector;
+
+impl Connector for RustlsConnector {
+    fn connect<'a>(&'a self, host: &'a str, port: u16, tls: Option<&'a Tls>)
+        -> BoxFuture<'a, io::Result<Box<dyn Io>>>
+    {
+        Box::pin(async move {
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            let stream = TcpStream::connect((host, port)).await?;
+            let Some(tls) = tls else {
+                return Ok(Box::new(stream) as Box<dyn Io>)
+            };
+            let server_name = ServerName::try_from(tls.server_name.clone())
+                .map_err(|error| invalid(format!("invalid server name {:?}: {}",
+                    tls.server_name, error)))?;
+            let stream = TlsConnector::from(Arc::new(config(tls)?))
+                .connect(server_name, stream).await?;
+            Ok(Box::new(stream) as Box<dyn Io>)
+        })
+    }
+}
+
+/// The rustls configuration of `tls`.
+pub fn config(tls: &Tls) -> io::Result<ClientConfig> {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|error| invalid(error.to_string()))?;
+    let builder = if tls.insecure_skip_verify {
+        builder.dangerous().with_custom_certificate_verifier(Arc::new(
+            SkipVerification(provider.signature_verification_algorithms)))
+    } else {
+        builder.with_root_certificates(roots(&tls.certificate_authorities)?)
+    };
+    match &tls.client_certificate {
+        Some((certificate, key)) => {
+            let chain = CertificateDer::pem_slice_iter(certificate)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|error| invalid(format!("invalid client certificate: {}", error)))?;
+            let key = PrivateKeyDer::from_pem_slice(key)
+                .map_err(|error| invalid(format!("invalid client key: {}", error)))?;
+            builder.with_client_auth_cert(chain, key)
+                .map_err(|error| invalid(error.to_string()))
+        }
+        None => Ok(builder.with_no_client_auth())
+    }
+}
+
+/// The certificate authorities in the PEM `data`, or the system's if it is empty.
+fn roots(data: &[u8]) -> io::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    if data.is_empty() {
+        let native = rustls_native_certs::load_native_certs();
+        roots.add_parsable_certificates(native.certs);
+        if roots.is_empty() {
+            return Err(invalid("no system certificate authorities found".to_owned()))
+        }
+        return Ok(roots)
+    }
+    for certificate in CertificateDer::pem_slice_iter(data) {
+        let certificate = certificate.map_err(|error| invalid(
+            format!("invalid certificate authority: {}", error)))?;
+        roots.add(certificate).map_err(|error| invalid(
+            format!("invalid certificate authority: {}", error)))?;
+    }
+    Ok(roots)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Accepts any server certificate, as `insecure-skip-tls-verify` asks, but still checks that the server holds its key.
+#[derive(Debug)]
+struct SkipVerification(WebPkiSupportedAlgorithms);
+
+impl ServerCertVerifier for SkipVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(message, certificate, signature, &self.0)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(message, certificate, signature, &self.0)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair};
+    use rustls::{server::WebPkiClientVerifier, ServerConfig};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener
+    };
+    use tokio_rustls::TlsAcceptor;
+
+    use super::*;
+
+    /// A certificate authority, and a server certificate for `localhost` and a client certificate it signed, as PEM.
+    struct Pki {
+        ca: String,
+        server: (String, String),
+        client: (String, String)
+    }
+
+    fn pki() -> Pki {
+        let mut params = CertificateParams::new(Vec::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = CertifiedIssuer::self_signed(params, KeyPair::generate().unwrap()).unwrap();
+        let sign = |names: Vec<String>| {
+            let key = KeyPair::generate().unwrap();
+            let certificate = CertificateParams::new(names).unwrap().signed_by(&key, &ca).unwrap();
+            (certificate.pem(), key.serialize_pem())
+        };
+        Pki {
+            server: sign(vec!["localhost".to_owned()]),
+            client: sign(vec!["admin".to_owned()]),
+            ca: ca.pem()
+        }
+    }
+
+    /// Serves one connection over TLS with the server certificate of `pki`, requiring a client certificate, and answers `pong` to `ping`.
+    async fn serve(pki: &Pki) -> u16 {
+        let roots = roots(pki.ca.as_bytes()).unwrap();
+        let provider = Arc::new(crypto::ring::default_provider());
+        let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+            .build().unwrap();
+        let config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions().unwrap()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(
+                vec![CertificateDer::from_pem_slice(pki.server.0.as_bytes()).unwrap()],
+                PrivateKeyDer::from_pem_slice(pki.server.1.as_bytes()).unwrap())
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            if let Ok(mut stream) = TlsAcceptor::from(Arc::new(config)).accept(stream).await {
+                let mut ping = [0; 4];
+                stream.read_exact(&mut ping).await.unwrap();
+                stream.write_all(b"pong").await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+        port
+    }
+
+    async fn ping(port: u16, tls: &Tls) -> io::Result<Vec<u8>> {
+        let mut stream = RustlsConnector.connect("127.0.0.1", port, Some(tls)).await?;
+        stream.write_all(b"ping").await?;
+        let mut pong = Vec::new();
+        stream.read_to_end(&mut pong).await?;
+        Ok(pong)
+    }
+
+    #[tokio::test]
+    async fn verifies_the_server_and_presents_the_client_certificate() {
+        let pki = pki();
+        let port = serve(&pki).await;
+        let tls = Tls {
+            server_name: "localhost".to_owned(),
+            certificate_authorities: pki.ca.clone().into_bytes(),
+            client_certificate: Some((pki.client.0.clone().into_bytes(),
+                pki.client.1.clone().into_bytes())),
+            insecure_skip_verify: false
+        };
+        assert_eq!(ping(port, &tls).await.unwrap(), b"pong");
+    }
+
+    #[tokio::test]
+    async fn rejects_servers_of_other_authorities_unless_told_to_skip_verification() {
+        let (pki, other) = (pki(), pki());
+        let mut tls = Tls {
+            server_name: "localhost".to_owned(),
+            certificate_authorities: other.ca.clone().into_bytes(),
+            client_certificate: Some((pki.client.0.clone().into_bytes(),
+                pki.client.1.clone().into_bytes())),
+            insecure_skip_verify: false
+        };
+        assert!(ping(serve(&pki).await, &tls).await.is_err());
+        tls.insecure_skip_verify = true;
+        assert_eq!(ping(serve(&pki).await, &tls).await.unwrap(), b"pong");
+    }
+}
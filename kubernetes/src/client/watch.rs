@@ -0,0 +1,188 @@
+/*!
+Watches: streams of the changes to the objects of a resource, one JSON event per line.
+
+A watch starts after a resource version, that of a list usually, and every event carries the version it happened at. Resuming from the last one seen loses nothing, unless the server has compacted it away, in which case it answers `410 Gone` and the client must list anew.
+
+- Concepts <https://kubernetes.io/docs/reference/using-api/api-concepts/#efficient-detection-of-changes>
+- Source <https://github.com/kubernetes/client-go/blob/master/tools/watch/retrywatcher.go>
+*/
+
+use http_body_util::BodyExt;
+use hyper::body::Incoming;
+use serde_json::Value;
+
+use crate::meta::status::Status;
+use super::{Api, Error, Object, WatchParams};
+
+/// A change to an object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent<K> {
+    Added(K),
+    Modified(K),
+    /// The object in its last state.
+    Deleted(K),
+    /// Nothing changed, but the watch is now at this resource version.
+    Bookmark(String),
+    /// The watch failed, and ends.
+    Error(Box<Status>)
+}
+
+impl<K> WatchEvent<K> {
+    /// `ADDED`, `MODIFIED`, `DELETED`, `BOOKMARK` or `ERROR`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Added(_) => "ADDED",
+            Self::Modified(_) => "MODIFIED",
+            Self::Deleted(_) => "DELETED",
+            Self::Bookmark(_) => "BOOKMARK",
+            Self::Error(_) => "ERROR"
+        }
+    }
+
+    pub fn object(&self) -> Option<&K> {
+        match self {
+            Self::Added(object) | Self::Modified(object) | Self::Deleted(object) => Some(object),
+            Self::Bookmark(_) | Self::Error(_) => None
+        }
+    }
+}
+
+/// One watch request, until the server closes it.
+pub struct Watch<K> {
+    body: Incoming,
+    buffer: Vec<u8>,
+    resource_version: String,
+    done: bool,
+    kind: std::marker::PhantomData<fn() -> K>
+}
+
+impl<K: Object> Watch<K> {
+    pub(crate) fn new(body: Incoming, resource_version: &str) -> Self {
+        Self {
+            body,
+            buffer: Vec::new(),
+            resource_version: resource_version.to_owned(),
+            done: false,
+            kind: std::marker::PhantomData
+        }
+    }
+
+    /// Version of the last event, to resume from; that the watch started from before any.
+    pub fn resource_version(&self) -> &str {
+        &self.resource_version
+    }
+
+    /// The next event, or `None` once the server has closed the watch.
+    pub async fn next(&mut self) -> Option<Result<WatchEvent<K>, Error>> {
+        loop {
+            if let Some(end) = self.buffer.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue
+                }
+                return Some(self.decode(&line))
+            }
+            if self.done {
+                if self.buffer.iter().all(u8::is_ascii_whitespace) {
+                    return None
+                }
+                let line = std::mem::take(&mut self.buffer);
+                return Some(self.decode(&line))
+            }
+            match self.body.frame().await {
+                Some(Ok(frame)) => if let Ok(data) = frame.into_data() {
+                    self.buffer.extend_from_slice(&data);
+                },
+                Some(Err(error)) => {
+                    self.done = true;
+                    self.buffer.clear();
+                    return Some(Err(Error::Http(error.to_string())))
+                }
+                None => self.done = true
+            }
+        }
+    }
+
+    fn decode(&mut self, line: &[u8]) -> Result<WatchEvent<K>, Error> {
+        let event: Value = serde_json::from_slice(line)
+            .map_err(|error| Error::Decode(error.to_string()))?;
+        let Value::Object(mut event) = event else {
+            return Err(Error::Decode("expected a watch event".to_owned()))
+        };
+        let object = event.remove("object").unwrap_or_default();
+        let kind = event.get("type").and_then(Value::as_str).unwrap_or_default();
+        if kind == "ERROR" {
+            return Ok(WatchEvent::Error(Box::new(Status::from_json(&object))))
+        }
+        if let Some(version) = object.pointer("/metadata/resourceVersion").and_then(Value::as_str) {
+            self.resource_version = version.to_owned();
+        }
+        let object = || K::from_json(object.clone()).map_err(Error::Decode);
+        match kind {
+            "ADDED" => object().map(WatchEvent::Added),
+            "MODIFIED" => object().map(WatchEvent::Modified),
+            "DELETED" => object().map(WatchEvent::Deleted),
+            "BOOKMARK" => Ok(WatchEvent::Bookmark(self.resource_version.clone())),
+            kind => Err(Error::Decode(format!("unknown watch event type {:?}", kind)))
+        }
+    }
+}
+
+/**
+A watch that outlives the requests it is made of: when the server closes one, as it does after `timeoutSeconds` or on its own, it watches again from the last resource version it saw, bookmarks included.
+
+When that version is gone, [`next`][Self::next] fails with a `410` [`Status`] and starts over from the current state at the next call, as `ADDED` events; callers keeping a cache should relist instead, and [`resume`][Self::resume] from the version of the list.
+*/
+pub struct Watcher<K> {
+    api: Api<K>,
+    params: WatchParams,
+    resource_version: String,
+    watch: Option<Watch<K>>
+}
+
+impl<K: Object> Watcher<K> {
+    /// Watches after `resource_version`, or from the current state if empty.
+    pub fn new(api: Api<K>, params: WatchParams, resource_version: &str) -> Self {
+        Self { api, params, resource_version: resource_version.to_owned(), watch: None }
+    }
+
+    pub fn resource_version(&self) -> &str {
+        &self.resource_version
+    }
+
+    /// Watches after `resource_version` from the next call on.
+    pub fn resume(&mut self, resource_version: &str) {
+        self.resource_version = resource_version.to_owned();
+        self.watch = None;
+    }
+
+    /// The next event, which is never [`WatchEvent::Error`]: errors of the watch and of its requests are returned as [`Error`]s.
+    pub async fn next(&mut self) -> Result<WatchEvent<K>, Error> {
+        loop {
+            let watch = match &mut self.watch {
+                Some(watch) => watch,
+                None => match self.api.watch(&self.params, &self.resource_version).await {
+                    Ok(watch) => self.watch.insert(watch),
+                    Err(error) => return Err(self.fail(error))
+                }
+            };
+            match watch.next().await {
+                Some(Ok(WatchEvent::Error(status))) => return Err(self.fail(Error::Api(status))),
+                Some(Ok(event)) => {
+                    self.resource_version = watch.resource_version().to_owned();
+                    return Ok(event)
+                }
+                Some(Err(error)) => return Err(self.fail(error)),
+                None => self.watch = None
+            }
+        }
+    }
+
+    fn fail(&mut self, error: Error) -> Error {
+        self.watch = None;
+        if error.status().is_some_and(Status::is_gone) {
+            self.resource_version.clear();
+        }
+        error
+    }
+}
@@ -3,12 +3,7 @@ pub mod kube_proxy;
 pub mod kube_scheduler;
 pub mod kubelet;
 
-// use kfl::Decode;
-
-pub use api_server::ApiServer;
-pub use kube_proxy::KubeProxy;
-pub use kube_scheduler::KubeScheduler;
-
+// 
 // #[derive(Debug, Decode)]
 // pub enum Config {
 //     ApiServer(ApiServer),
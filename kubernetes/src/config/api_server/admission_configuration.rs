@@ -4,18 +4,34 @@
 
 use std::path::PathBuf;
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
+
+use crate::decode::Fields;
 
 /// <https://kubernetes.io/docs/reference/config-api/apiserver-config.v1/#apiserver-config-k8s-io-v1-AdmissionConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct AdmissionConfiguration {
-    plugins: Option<Plugin>
+    pub plugins: Option<Plugin>
+}
+
+impl Decode for AdmissionConfiguration {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let admission_configuration = Self {
+            plugins: fields.child("plugins")?
+        };
+        fields.finish()?;
+        Ok(admission_configuration)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/apiserver-config.v1/#apiserver-config-k8s-io-v1-Plugin>
 #[derive(Debug, Decode)]
 pub struct Plugin {
-    name: String,
-    path: Option<PathBuf>,
-    configuration: Option<String>
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub path: Option<PathBuf>,
+    #[kfl(property, default)]
+    pub configuration: Option<String>
 }
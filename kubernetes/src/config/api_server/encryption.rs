@@ -1,59 +1,459 @@
-//! Reference <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/>
+//! - Concepts <https://kubernetes.io/docs/tasks/administer-cluster/encrypt-data/>
+//! - Reference <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/>
+//! - Source <https://github.com/kubernetes/kubernetes/tree/master/staging/src/k8s.io/apiserver/pkg/storage/value/encrypt>
 
-use std::time::Duration;
+pub mod aes;
+pub mod kms;
+pub mod secretbox;
 
-use kfl::Decode;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+    time::Duration
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
+
+use crate::decode::Fields;
+
+use kms::KmsService;
 
 /// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-EncryptionConfiguration>
 #[derive(Debug, Decode)]
 pub struct EncryptionConfiguration {
-    resources: Vec<Resource>
+    /// A list containing resources, and their corresponding encryption providers.
+    #[kfl(children)]
+    pub resources: Vec<Resource>
 }
 
-/// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-Resource>
-#[derive(Debug, Decode)]
+impl EncryptionConfiguration {
+    /// The entry whose [`resources`][Resource::resources] cover `resource`, given as `<resource>.<group>` (just `<resource>` for the core group), exact names taking precedence over wildcards.
+    pub fn resource(&self, resource: &str) -> Option<&Resource> {
+        let group = resource.split_once('.').map_or("", |(_, group)| group);
+        let patterns = [
+            resource.to_owned(),
+            format!("*.{}", group),
+            "*.*".to_owned()
+        ];
+        patterns.iter().find_map(|pattern| self.resources.iter()
+            .find(|entry| entry.resources.iter().any(|name| name == pattern)))
+    }
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-ResourceConfiguration>
+#[derive(Debug)]
 pub struct Resource {
-    resources:  Vec<String>,
-    providers: Vec<Provider>
+    /// A list of kubernetes resources which have to be encrypted. The resource names are derived from `resource` or `resource.group` of the group/version/resource. eg: `pandas.awesome.bears.example` is a custom resource with 'group': `awesome.bears.example`, 'resource': `pandas`. Use `*.*` to encrypt all resources and `*.<group>` to encrypt all resources in a specific group.
+    pub resources: Vec<String>,
+    /// A stack of transformers used to read and write the resources to the disk. eg: `aesgcm`, `aescbc`, `secretbox`, `identity`, `kms`.
+    pub providers: Vec<Provider>
+}
+
+impl Decode for Resource {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let resource = Self {
+            resources: fields.values("resources")?,
+            providers: fields.children("provider")?
+        };
+        fields.finish()?;
+        Ok(resource)
+    }
 }
 
+/// Exactly one of the fields is set.
+///
 /// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-ProviderConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Provider {
-    aesgcm: Aes,
-    aescbc: Aes,
-    secretbox: Secretbox,
-    identity: Identity,
-    kms: Kms
+    /// The configuration for the AES-GCM transformer.
+    pub aesgcm: Option<Aes>,
+    /// The configuration for the AES-CBC transformer.
+    pub aescbc: Option<Aes>,
+    /// The configuration for the Secretbox transformer.
+    pub secretbox: Option<Secretbox>,
+    /// The (empty) configuration for the identity transformer.
+    pub identity: Option<Identity>,
+    /// The configuration for the KMS transformer.
+    pub kms: Option<Kms>
+}
+
+impl Decode for Provider {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let provider = Self {
+            aesgcm: fields.child("aesgcm")?,
+            aescbc: fields.child("aescbc")?,
+            secretbox: fields.child("secretbox")?,
+            identity: fields.child("identity")?,
+            kms: fields.child("kms")?
+        };
+        fields.finish()?;
+        Ok(provider)
+    }
 }
 
-/// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-Aes>
+/// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-AESConfiguration>
 #[derive(Debug, Decode)]
 pub struct Aes {
-    keys: Vec<Key>
+    /// A list of keys to be used for creating the AES transformer. Each key has to be 32 bytes long for AES-CBC and 16, 24 or 32 bytes for AES-GCM.
+    #[kfl(children)]
+    pub keys: Vec<Key>
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-Key>
 #[derive(Debug, Decode)]
 pub struct Key {
-    name: String,
-    secret: String,
+    /// The name of the key to be used while storing data to disk.
+    #[kfl(property, default)]
+    pub name: String,
+    /// The actual key, encoded in base64.
+    #[kfl(property, default)]
+    pub secret: String,
 }
 
+/// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-SecretboxConfiguration>
 #[derive(Debug, Decode)]
 pub struct Secretbox {
-    keys: Vec<Key>
+    /// A list of keys to be used for creating the Secretbox transformer. Each key has to be 32 bytes long.
+    #[kfl(children)]
+    pub keys: Vec<Key>
 }
 
-/// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-Identity>
+/// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-IdentityConfiguration>
 #[derive(Debug, Decode)]
 pub struct Identity {}
 
-/// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-Kms>
-#[derive(Debug, Decode)]
+/// <https://kubernetes.io/docs/reference/config-api/apiserver-encryption.v1/#apiserver-config-k8s-io-v1-KMSConfiguration>
+#[derive(Debug)]
 pub struct Kms {
-    name: String,
-    cache_size: Option<i32>,
-    endpoint: String,
-    timeout: Option<Duration>
+    /// `api_version` of KeyManagementService, `v1` or `v2`.
+    pub api_version: Option<String>,
+    /// The name of the KMS plugin to be used.
+    pub name: String,
+    /// The maximum number of secrets which are cached in memory. Only used by `v1`.
+    pub cache_size: Option<i32>,
+    /// The gRPC server listening address, for example `unix:///var/run/kms-provider.sock`.
+    pub endpoint: String,
+    /// Timeout for gRPC calls to kms-plugin (ex. 5s). The default is 3 seconds.
+    pub timeout: Option<Duration>
+}
+
+impl Decode for Kms {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let kms = Self {
+            api_version: fields.property("api-version")?,
+            name: fields.property("name")?.unwrap_or_default(),
+            cache_size: fields.property("cache-size")?,
+            endpoint: fields.property("endpoint")?.unwrap_or_default(),
+            timeout: fields.duration("timeout")?
+        };
+        fields.finish()?;
+        Ok(kms)
+    }
+}
+
+/// Prefix of every encrypted value, followed by `<provider>:<version>:<key name>:`.
+pub const PREFIX: &[u8] = b"k8s:enc:";
+
+/// A storage encoding of resource payloads, applied below its `k8s:enc:…:` prefix.
+pub trait Transformer {
+    /// Decodes `data` read from etcd. `context` is the etcd key of the value, authenticated by AEAD formats.
+    fn transform_from_storage(&self, data: &[u8], context: &[u8])
+        -> Result<Vec<u8>, Error>;
+    /// Encodes `data` to be written to etcd under the key `context`.
+    fn transform_to_storage(&self, data: &[u8], context: &[u8])
+        -> Result<Vec<u8>, Error>;
+}
+
+/// Stores values as they are, and refuses to read encrypted ones.
+#[derive(Debug)]
+pub struct IdentityTransformer;
+
+impl Transformer for IdentityTransformer {
+    fn transform_from_storage(&self, data: &[u8], _context: &[u8])
+        -> Result<Vec<u8>, Error>
+    {
+        if data.starts_with(PREFIX) {
+            return Err(Error::Encrypted)
+        }
+        Ok(data.to_vec())
+    }
+
+    fn transform_to_storage(&self, data: &[u8], _context: &[u8])
+        -> Result<Vec<u8>, Error>
+    {
+        Ok(data.to_vec())
+    }
+}
+
+/// A [`Transformer`] along with the prefix identifying the values it wrote.
+pub struct PrefixTransformer {
+    pub prefix: Vec<u8>,
+    pub transformer: Box<dyn Transformer>
+}
+
+impl fmt::Debug for PrefixTransformer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrefixTransformer")
+            .field("prefix", &String::from_utf8_lossy(&self.prefix))
+            .finish()
+    }
+}
+
+/**
+The providers of one [`Resource`], in configuration order.
+
+Values are written with the first provider (its first key) and read with whichever provider's prefix they carry, so rotating a key is a matter of adding the new key first, rewriting every value, then removing the old key.
+*/
+#[derive(Debug)]
+pub struct Transformers {
+    transformers: Vec<PrefixTransformer>
+}
+
+impl Transformers {
+    pub fn new(transformers: Vec<PrefixTransformer>) -> Self {
+        Self { transformers }
+    }
+
+    /// Builds the providers of `resource`. KMS providers are looked up by [`name`][Kms::name] in `kms`.
+    pub fn from_config(
+        resource: &Resource,
+        kms: &HashMap<String, Arc<dyn KmsService>>
+    ) -> Result<Self, Error> {
+        let mut transformers = Vec::new();
+        for provider in &resource.providers {
+            match provider {
+                Provider { aesgcm: Some(config), aescbc: None, secretbox: None,
+                           identity: None, kms: None } =>
+                    for key in &config.keys {
+                        transformers.push(PrefixTransformer {
+                            prefix: prefix("aesgcm", "v1", &key.name),
+                            transformer: Box::new(aes::Gcm::new(decode(key)?)?)
+                        });
+                    },
+                Provider { aesgcm: None, aescbc: Some(config), secretbox: None,
+                           identity: None, kms: None } =>
+                    for key in &config.keys {
+                        transformers.push(PrefixTransformer {
+                            prefix: prefix("aescbc", "v1", &key.name),
+                            transformer: Box::new(aes::Cbc::new(decode(key)?)?)
+                        });
+                    },
+                Provider { aesgcm: None, aescbc: None, secretbox: Some(config),
+                           identity: None, kms: None } =>
+                    for key in &config.keys {
+                        transformers.push(PrefixTransformer {
+                            prefix: prefix("secretbox", "v1", &key.name),
+                            transformer: Box::new(
+                                secretbox::Secretbox::new(decode(key)?)?)
+                        });
+                    },
+                Provider { aesgcm: None, aescbc: None, secretbox: None,
+                           identity: Some(_), kms: None } =>
+                    transformers.push(PrefixTransformer {
+                        prefix: Vec::new(),
+                        transformer: Box::new(IdentityTransformer)
+                    }),
+                Provider { aesgcm: None, aescbc: None, secretbox: None,
+                           identity: None, kms: Some(config) } => {
+                    if config.api_version.as_deref().unwrap_or("v1") != "v2" {
+                        return Err(Error::Unsupported(format!(
+                            "kms {} api version", config.name)))
+                    }
+                    let service = kms.get(&config.name).ok_or_else(||
+                        Error::Kms(format!("no plugin named {}", config.name)))?;
+                    transformers.push(PrefixTransformer {
+                        prefix: prefix("kms", "v2", &config.name),
+                        transformer: Box::new(kms::Envelope::new(
+                            config.name.clone(), service.clone()))
+                    });
+                }
+                _ => return Err(Error::InvalidProvider)
+            }
+        }
+        Ok(Self::new(transformers))
+    }
+
+    /// Encodes `data` with the first provider, ready to be written under the etcd key `context`.
+    pub fn write(&self, data: &[u8], context: &[u8]) -> Result<Vec<u8>, Error> {
+        let first = self.transformers.first().ok_or(Error::InvalidProvider)?;
+        let mut value = first.prefix.clone();
+        value.extend(first.transformer.transform_to_storage(data, context)?);
+        Ok(value)
+    }
+
+    /// Decodes `data` read from the etcd key `context`. The flag is set when the value was not written by the first provider and should be rewritten.
+    pub fn read(&self, data: &[u8], context: &[u8])
+        -> Result<(Vec<u8>, bool), Error>
+    {
+        for (index, transformer) in self.transformers.iter().enumerate() {
+            let Some(payload) = data.strip_prefix(transformer.prefix.as_slice())
+            else {
+                continue
+            };
+            match transformer.transformer.transform_from_storage(payload, context) {
+                // An identity provider listed before an encrypting one must let it read encrypted values.
+                Err(_) if transformer.prefix.is_empty() => continue,
+                result => return result.map(|value| (value, index != 0))
+            }
+        }
+        Err(Error::NoMatchingPrefix)
+    }
+
+    /// Re-encodes `data` with the first provider if it was written by another one, as `kubectl get secrets -A -o json | kubectl replace -f -` does after a rotation.
+    pub fn rotate(&self, data: &[u8], context: &[u8])
+        -> Result<Option<Vec<u8>>, Error>
+    {
+        match self.read(data, context)? {
+            (value, true) => self.write(&value, context).map(Some),
+            (_, false) => Ok(None)
+        }
+    }
+}
+
+/// `k8s:enc:<provider>:<version>:<name>:`
+pub fn prefix(provider: &str, version: &str, name: &str) -> Vec<u8> {
+    format!("k8s:enc:{}:{}:{}:", provider, version, name).into_bytes()
+}
+
+fn decode(key: &Key) -> Result<Vec<u8>, Error> {
+    STANDARD.decode(&key.secret)
+        .map_err(|_| Error::InvalidKey(key.name.clone()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A key is not valid base64 or has a length its provider does not accept.
+    InvalidKey(String),
+    /// A provider sets none or several of its fields, or a resource has no provider at all.
+    InvalidProvider,
+    /// The configuration asks for something not implemented here.
+    Unsupported(String),
+    /// The value carries none of the configured prefixes.
+    NoMatchingPrefix,
+    /// The identity provider was given an encrypted value.
+    Encrypted,
+    /// The value is truncated or fails decryption or authentication.
+    InvalidData(&'static str),
+    /// The KMS plugin failed.
+    Kms(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidKey(name) => write!(f, "invalid key {}", name),
+            Self::InvalidProvider =>
+                write!(f, "exactly one provider must be set per entry"),
+            Self::Unsupported(what) => write!(f, "unsupported {}", what),
+            Self::NoMatchingPrefix => write!(f, "no matching prefix found"),
+            Self::Encrypted =>
+                write!(f, "identity transformer tried to read encrypted data"),
+            Self::InvalidData(reason) => write!(f, "invalid data: {}", reason),
+            Self::Kms(message) => write!(f, "kms: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Fills `buffer` with random bytes for keys and nonces.
+pub(crate) fn random(buffer: &mut [u8]) -> Result<(), Error> {
+    getrandom::fill(buffer)
+        .map_err(|_| Error::Unsupported("random source".to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decode::decode;
+    use super::*;
+
+    const CONTEXT: &[u8] = b"/registry/secrets/default/token";
+
+    /// A configuration for secrets, and for every resource of `apps`, with `providers` in order.
+    fn configuration(providers: &str) -> EncryptionConfiguration {
+        decode("encryption.kdl", &format!(r#"
+            encryption-configuration {{
+                resource {{
+                    resources "secrets" "*.apps"
+                    {}
+                }}
+                resource {{
+                    resources "*.*"
+                    provider {{
+                        identity
+                    }}
+                }}
+            }}
+        "#, providers)).unwrap()
+    }
+
+    fn key(provider: &str, name: &str, byte: u8) -> String {
+        format!(r#"
+            provider {{
+                {} {{
+                    key name="{}" secret="{}"
+                }}
+            }}
+        "#, provider, name, STANDARD.encode([byte; 32]))
+    }
+
+    fn transformers(providers: &[String]) -> Transformers {
+        let configuration = configuration(&providers.join("\n"));
+        Transformers::from_config(configuration.resource("secrets").unwrap(), &HashMap::new())
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_resources() {
+        let configuration = configuration(&key("aesgcm", "key1", 1));
+        assert_eq!(configuration.resource("secrets").unwrap().resources[0], "secrets");
+        assert_eq!(configuration.resource("deployments.apps").unwrap().resources[0], "secrets");
+        assert_eq!(configuration.resource("configmaps").unwrap().resources, ["*.*"]);
+    }
+
+    #[test]
+    fn rotates_keys() {
+        let identity = "provider {\n identity\n}".to_owned();
+        let old = transformers(&[key("aescbc", "old", 1), identity]);
+        let stored = old.write(b"secret", CONTEXT).unwrap();
+        assert!(stored.starts_with(b"k8s:enc:aescbc:v1:old:"));
+        assert_eq!(old.read(&stored, CONTEXT).unwrap(), (b"secret".to_vec(), false));
+        // Values written before encryption was configured are read as they are.
+        assert_eq!(old.read(b"plain", CONTEXT).unwrap(), (b"plain".to_vec(), true));
+
+        let new = transformers(&[key("aesgcm", "new", 2), key("secretbox", "box", 3),
+            key("aescbc", "old", 1)]);
+        let rotated = new.rotate(&stored, CONTEXT).unwrap().unwrap();
+        assert!(rotated.starts_with(b"k8s:enc:aesgcm:v1:new:"));
+        assert_eq!(new.read(&rotated, CONTEXT).unwrap(), (b"secret".to_vec(), false));
+        assert_eq!(new.rotate(&rotated, CONTEXT).unwrap(), None);
+        assert_eq!(new.read(b"plain", CONTEXT), Err(Error::NoMatchingPrefix));
+        assert!(old.read(&rotated, CONTEXT).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_providers() {
+        let config = configuration(r#"
+            provider {
+                aesgcm {
+                    key name="short" secret="c2hvcnQ="
+                }
+            }
+        "#);
+        assert_eq!(Transformers::from_config(&config.resources[0], &HashMap::new())
+            .unwrap_err(), Error::InvalidKey("of 5 bytes".to_owned()));
+        let config = configuration(r#"
+            provider {
+                identity
+                secretbox
+            }
+        "#);
+        assert_eq!(Transformers::from_config(&config.resources[0], &HashMap::new())
+            .unwrap_err(), Error::InvalidProvider);
+    }
 }
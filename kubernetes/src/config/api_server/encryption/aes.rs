@@ -0,0 +1,202 @@
+//! `aescbc` and `aesgcm` providers.
+//!
+//! - Source <https://github.com/kubernetes/kubernetes/blob/master/staging/src/k8s.io/apiserver/pkg/storage/value/encrypt/aes/aes.go>
+//! - Source <https://github.com/kubernetes/kubernetes/blob/master/staging/src/k8s.io/apiserver/pkg/storage/value/encrypt/aes/aes_cbc.go>
+
+use aes::{Aes128, Aes192, Aes256};
+use aes_gcm::{
+    aead::{consts::U12, Aead, Nonce, Payload},
+    AesGcm, KeyInit
+};
+use cbc::cipher::{
+    block_padding::Pkcs7, BlockCipherDecrypt, BlockCipherEncrypt,
+    BlockModeDecrypt, BlockModeEncrypt, KeyIvInit
+};
+
+use super::{random, Error, Transformer};
+
+const BLOCK_SIZE: usize = 16;
+const NONCE_SIZE: usize = 12;
+
+/**
+AES in CBC mode with PKCS#7 padding. Values are stored as `<16 byte IV><ciphertext>`.
+
+CBC is not authenticated and is vulnerable to padding oracle attacks; it is kept for existing clusters, new ones should use [`Gcm`] or KMS.
+*/
+#[derive(Debug)]
+pub struct Cbc {
+    key: Vec<u8>
+}
+
+impl Cbc {
+    /// `key` is the raw 16, 24 or 32 bytes key.
+    pub fn new(key: Vec<u8>) -> Result<Self, Error> {
+        match key.len() {
+            16 | 24 | 32 => Ok(Self { key }),
+            _ => Err(Error::InvalidKey(format!("of {} bytes", key.len())))
+        }
+    }
+}
+
+impl Transformer for Cbc {
+    fn transform_from_storage(&self, data: &[u8], _context: &[u8])
+        -> Result<Vec<u8>, Error>
+    {
+        if data.len() < BLOCK_SIZE {
+            return Err(Error::InvalidData("the stored data was shorter than the required size"))
+        }
+        let (iv, ciphertext) = data.split_at(BLOCK_SIZE);
+        if ciphertext.len() % BLOCK_SIZE != 0 {
+            return Err(Error::InvalidData("the stored data is not a multiple of the block size"))
+        }
+        let decrypted = match self.key.len() {
+            16 => cbc_decrypt::<Aes128>(&self.key, iv, ciphertext),
+            24 => cbc_decrypt::<Aes192>(&self.key, iv, ciphertext),
+            _ => cbc_decrypt::<Aes256>(&self.key, iv, ciphertext)
+        };
+        decrypted.ok_or(Error::InvalidData("invalid PKCS7 data (empty or not padded)"))
+    }
+
+    fn transform_to_storage(&self, data: &[u8], _context: &[u8])
+        -> Result<Vec<u8>, Error>
+    {
+        let mut iv = [0; BLOCK_SIZE];
+        random(&mut iv)?;
+        let ciphertext = match self.key.len() {
+            16 => cbc_encrypt::<Aes128>(&self.key, &iv, data),
+            24 => cbc_encrypt::<Aes192>(&self.key, &iv, data),
+            _ => cbc_encrypt::<Aes256>(&self.key, &iv, data)
+        };
+        let mut value = iv.to_vec();
+        value.extend(ciphertext);
+        Ok(value)
+    }
+}
+
+fn cbc_encrypt<C>(key: &[u8], iv: &[u8], data: &[u8]) -> Vec<u8>
+    where C: BlockCipherEncrypt, cbc::Encryptor<C>: KeyIvInit + BlockModeEncrypt
+{
+    cbc::Encryptor::<C>::new_from_slices(key, iv)
+        .expect("key and IV sizes are checked")
+        .encrypt_padded_vec::<Pkcs7>(data)
+}
+
+fn cbc_decrypt<C>(key: &[u8], iv: &[u8], data: &[u8]) -> Option<Vec<u8>>
+    where C: BlockCipherDecrypt, cbc::Decryptor<C>: KeyIvInit + BlockModeDecrypt
+{
+    cbc::Decryptor::<C>::new_from_slices(key, iv).ok()?
+        .decrypt_padded_vec::<Pkcs7>(data).ok()
+}
+
+/**
+AES in GCM mode with a random 12 byte nonce. Values are stored as `<nonce><ciphertext><16 byte tag>`, and the etcd key is authenticated as additional data so a value cannot be moved to another key.
+
+Random nonces make a key unsafe after about 2^32 writes, which is why keys must be rotated often.
+*/
+#[derive(Debug)]
+pub struct Gcm {
+    key: Vec<u8>
+}
+
+impl Gcm {
+    /// `key` is the raw 16, 24 or 32 bytes key.
+    pub fn new(key: Vec<u8>) -> Result<Self, Error> {
+        match key.len() {
+            16 | 24 | 32 => Ok(Self { key }),
+            _ => Err(Error::InvalidKey(format!("of {} bytes", key.len())))
+        }
+    }
+}
+
+impl Transformer for Gcm {
+    fn transform_from_storage(&self, data: &[u8], context: &[u8])
+        -> Result<Vec<u8>, Error>
+    {
+        open(&self.key, data, context)
+    }
+
+    fn transform_to_storage(&self, data: &[u8], context: &[u8])
+        -> Result<Vec<u8>, Error>
+    {
+        seal(&self.key, data, context)
+    }
+}
+
+/// Encrypts `data` with AES-GCM under a 16, 24 or 32 bytes `key` into `<nonce><ciphertext><tag>`, authenticating `aad`.
+pub(crate) fn seal(key: &[u8], data: &[u8], aad: &[u8])
+    -> Result<Vec<u8>, Error>
+{
+    let mut nonce = [0; NONCE_SIZE];
+    random(&mut nonce)?;
+    let ciphertext = match key.len() {
+        16 => gcm_encrypt::<Aes128>(key, &nonce, data, aad),
+        24 => gcm_encrypt::<Aes192>(key, &nonce, data, aad),
+        32 => gcm_encrypt::<Aes256>(key, &nonce, data, aad),
+        _ => None
+    }.ok_or(Error::InvalidData("encryption failed"))?;
+    let mut value = nonce.to_vec();
+    value.extend(ciphertext);
+    Ok(value)
+}
+
+/// Reverses [`seal`].
+pub(crate) fn open(key: &[u8], data: &[u8], aad: &[u8])
+    -> Result<Vec<u8>, Error>
+{
+    if data.len() < NONCE_SIZE {
+        return Err(Error::InvalidData("the stored data was shorter than the required size"))
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_SIZE);
+    match key.len() {
+        16 => gcm_decrypt::<Aes128>(key, nonce, ciphertext, aad),
+        24 => gcm_decrypt::<Aes192>(key, nonce, ciphertext, aad),
+        32 => gcm_decrypt::<Aes256>(key, nonce, ciphertext, aad),
+        _ => None
+    }.ok_or(Error::InvalidData("message authentication failed"))
+}
+
+fn gcm_encrypt<C>(key: &[u8], nonce: &[u8], data: &[u8], aad: &[u8])
+    -> Option<Vec<u8>>
+    where AesGcm<C, U12>: KeyInit + Aead
+{
+    let nonce = Nonce::<AesGcm<C, U12>>::try_from(nonce).ok()?;
+    AesGcm::<C, U12>::new_from_slice(key).ok()?
+        .encrypt(&nonce, Payload { msg: data, aad }).ok()
+}
+
+fn gcm_decrypt<C>(key: &[u8], nonce: &[u8], data: &[u8], aad: &[u8])
+    -> Option<Vec<u8>>
+    where AesGcm<C, U12>: KeyInit + Aead
+{
+    let nonce = Nonce::<AesGcm<C, U12>>::try_from(nonce).ok()?;
+    AesGcm::<C, U12>::new_from_slice(key).ok()?
+        .decrypt(&nonce, Payload { msg: data, aad }).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binds_values_to_their_keys() {
+        let gcm = Gcm::new(vec![7; 16]).unwrap();
+        let stored = gcm.transform_to_storage(b"secret", b"/registry/secrets/a").unwrap();
+        assert_eq!(stored.len(), NONCE_SIZE + 6 + 16);
+        assert_eq!(gcm.transform_from_storage(&stored, b"/registry/secrets/a").unwrap(), b"secret");
+        assert!(gcm.transform_from_storage(&stored, b"/registry/secrets/b").is_err());
+        let mut tampered = stored.clone();
+        tampered[NONCE_SIZE] ^= 1;
+        assert!(gcm.transform_from_storage(&tampered, b"/registry/secrets/a").is_err());
+    }
+
+    #[test]
+    fn pads_blocks() {
+        let cbc = Cbc::new(vec![7; 32]).unwrap();
+        let stored = cbc.transform_to_storage(&[1; BLOCK_SIZE], b"").unwrap();
+        // A full block of padding follows a whole block.
+        assert_eq!(stored.len(), 3 * BLOCK_SIZE);
+        assert_eq!(cbc.transform_from_storage(&stored, b"").unwrap(), [1; BLOCK_SIZE]);
+        assert!(cbc.transform_from_storage(&stored[..2 * BLOCK_SIZE + 1], b"").is_err());
+        assert!(Cbc::new(vec![7; 20]).is_err());
+    }
+}
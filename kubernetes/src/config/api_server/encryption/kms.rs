@@ -0,0 +1,548 @@
+//! `kms` provider, API version `v2`.
+//!
+//! - Concepts <https://kubernetes.io/docs/tasks/administer-cluster/kms-provider/>
+//! - Source <https://github.com/kubernetes/kubernetes/blob/master/staging/src/k8s.io/apiserver/pkg/storage/value/encrypt/envelope/kmsv2/envelope.go>
+//! - Source <https://github.com/kubernetes/kubernetes/blob/master/staging/src/k8s.io/apiserver/pkg/storage/value/encrypt/aes/aes_extended_nonce.go>
+//! - Source <https://github.com/kubernetes/kubernetes/blob/master/staging/src/k8s.io/kms/apis/v2/api.proto>
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex}
+};
+
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use super::{aes, random, Error, Transformer};
+
+/// Size of the data encryption keys, and of the seeds keys are derived from.
+pub const DEK_SIZE: usize = 32;
+
+/// Size of the random info a key is derived from a seed with, stored before each value.
+pub const INFO_SIZE: usize = 32;
+
+/// Longest key ID a plugin may return.
+pub const KEY_ID_MAX_SIZE: usize = 1024;
+
+/// `StatusResponse.healthz` of a healthy plugin.
+pub const HEALTHZ_OK: &str = "ok";
+
+/**
+The `KeyManagementService` a KMS plugin serves on its gRPC endpoint.
+
+`uid` identifies a request across the API server and plugin logs.
+*/
+pub trait KmsService: Send + Sync {
+    /// Reports the health of the plugin and the key it currently encrypts with.
+    fn status(&self) -> Result<StatusResponse, Error>;
+    /// Decrypts a data encryption key sealed by [`encrypt`][Self::encrypt].
+    fn decrypt(&self, uid: &str, request: &DecryptRequest)
+        -> Result<Vec<u8>, Error>;
+    /// Seals a data encryption key with the current key encryption key.
+    fn encrypt(&self, uid: &str, plaintext: &[u8])
+        -> Result<EncryptResponse, Error>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusResponse {
+    /// `v2`.
+    pub version: String,
+    /// `ok` when healthy.
+    pub healthz: String,
+    /// The key encryption key currently in use.
+    pub key_id: String
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecryptRequest {
+    pub ciphertext: Vec<u8>,
+    pub key_id: String,
+    pub annotations: BTreeMap<String, Vec<u8>>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptResponse {
+    pub ciphertext: Vec<u8>,
+    pub key_id: String,
+    /// Extra data the plugin needs back to decrypt, keys must be fully qualified domain names.
+    pub annotations: BTreeMap<String, Vec<u8>>
+}
+
+/**
+The value stored after the `k8s:enc:kms:v2:<name>:` prefix, protobuf encoded.
+
+Source <https://github.com/kubernetes/kubernetes/blob/master/staging/src/k8s.io/apiserver/pkg/storage/value/encrypt/envelope/kmsv2/v2/api.proto>
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EncryptedObject {
+    /// The payload, AES-GCM encrypted with the data encryption key.
+    pub encrypted_data: Vec<u8>,
+    /// The key encryption key that sealed the data encryption key.
+    pub key_id: String,
+    /// The data encryption key as sealed by the plugin.
+    pub encrypted_dek_source: Vec<u8>,
+    pub annotations: BTreeMap<String, Vec<u8>>,
+    pub encrypted_dek_source_type: EncryptedDekSourceType
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EncryptedDekSourceType {
+    /// The data encryption key is used directly as an AES-GCM key.
+    #[default]
+    AesGcmKey,
+    /// The data encryption key is a seed HKDF derives a key from for each write.
+    HkdfSha256XNonceAesGcmSeed
+}
+
+impl EncryptedObject {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        put_bytes(&mut buffer, 1, &self.encrypted_data);
+        put_bytes(&mut buffer, 2, self.key_id.as_bytes());
+        put_bytes(&mut buffer, 3, &self.encrypted_dek_source);
+        for (key, value) in &self.annotations {
+            let mut entry = Vec::new();
+            put_bytes(&mut entry, 1, key.as_bytes());
+            put_bytes(&mut entry, 2, value);
+            put_bytes(&mut buffer, 4, &entry);
+        }
+        if self.encrypted_dek_source_type
+            == EncryptedDekSourceType::HkdfSha256XNonceAesGcmSeed
+        {
+            put_varint(&mut buffer, 5 << 3);
+            put_varint(&mut buffer, 1);
+        }
+        buffer
+    }
+
+    pub fn decode(mut data: &[u8]) -> Result<Self, Error> {
+        let mut object = Self::default();
+        while !data.is_empty() {
+            let (field, value) = next_field(&mut data)?;
+            match (field, value) {
+                (1, Field::Bytes(bytes)) => object.encrypted_data = bytes.to_vec(),
+                (2, Field::Bytes(bytes)) => object.key_id = string(bytes)?,
+                (3, Field::Bytes(bytes)) =>
+                    object.encrypted_dek_source = bytes.to_vec(),
+                (4, Field::Bytes(mut entry)) => {
+                    let (mut key, mut value) = (String::new(), Vec::new());
+                    while !entry.is_empty() {
+                        match next_field(&mut entry)? {
+                            (1, Field::Bytes(bytes)) => key = string(bytes)?,
+                            (2, Field::Bytes(bytes)) => value = bytes.to_vec(),
+                            _ => {}
+                        }
+                    }
+                    object.annotations.insert(key, value);
+                }
+                (5, Field::Varint(0)) => object.encrypted_dek_source_type =
+                    EncryptedDekSourceType::AesGcmKey,
+                (5, Field::Varint(1)) => object.encrypted_dek_source_type =
+                    EncryptedDekSourceType::HkdfSha256XNonceAesGcmSeed,
+                (1..=5, _) =>
+                    return Err(Error::InvalidData("malformed EncryptedObject")),
+                _ => {}
+            }
+        }
+        if object.encrypted_data.is_empty() {
+            return Err(Error::InvalidData("encrypted data is empty"))
+        }
+        if object.key_id.is_empty() || object.key_id.len() > KEY_ID_MAX_SIZE {
+            return Err(Error::InvalidData("invalid key ID"))
+        }
+        if object.encrypted_dek_source.is_empty() {
+            return Err(Error::InvalidData("encrypted DEK source is empty"))
+        }
+        Ok(object)
+    }
+}
+
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed
+}
+
+fn put_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn put_bytes(buffer: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    if bytes.is_empty() {
+        return
+    }
+    put_varint(buffer, field << 3 | 2);
+    put_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+fn varint(data: &mut &[u8]) -> Result<u64, Error> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first()
+            .ok_or(Error::InvalidData("truncated varint"))?;
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte < 0x80 {
+            return Ok(value)
+        }
+    }
+    Err(Error::InvalidData("varint overflow"))
+}
+
+fn next_field<'a>(data: &mut &'a [u8]) -> Result<(u64, Field<'a>), Error> {
+    let tag = varint(data)?;
+    let value = match tag & 7 {
+        0 => Field::Varint(varint(data)?),
+        1 | 5 => {
+            let size = if tag & 7 == 1 { 8 } else { 4 };
+            if data.len() < size {
+                return Err(Error::InvalidData("truncated field"))
+            }
+            *data = &data[size..];
+            Field::Fixed
+        }
+        2 => {
+            let size = usize::try_from(varint(data)?)
+                .map_err(|_| Error::InvalidData("truncated field"))?;
+            if data.len() < size {
+                return Err(Error::InvalidData("truncated field"))
+            }
+            let (bytes, rest) = data.split_at(size);
+            *data = rest;
+            Field::Bytes(bytes)
+        }
+        _ => return Err(Error::InvalidData("unsupported wire type"))
+    };
+    Ok((tag >> 3, value))
+}
+
+fn string(bytes: &[u8]) -> Result<String, Error> {
+    String::from_utf8(bytes.to_vec())
+        .map_err(|_| Error::InvalidData("invalid UTF-8"))
+}
+
+/**
+Envelope encryption through a KMS plugin: a seed is sealed by the plugin's key encryption key, every write is AES-GCM encrypted with a key HKDF derives from the seed and random info stored before the value, and the sealed seed is stored along as an [`EncryptedObject`].
+
+The seed is generated once and reused until the key ID the plugin reports changes, found by [`poll_status`][Self::poll_status], so writes do not call the plugin. Rotating the key encryption key in the plugin needs no configuration change, values carry the key ID they were sealed with. Values whose data encryption key is used directly, as API servers before 1.29 wrote them, are still read.
+*/
+pub struct Envelope {
+    name: String,
+    service: Arc<dyn KmsService>,
+    seed: Mutex<Option<Seed>>
+}
+
+/// The seed writes derive their keys from, as the plugin sealed it.
+struct Seed {
+    seed: [u8; DEK_SIZE],
+    encrypted: Vec<u8>,
+    key_id: String,
+    annotations: BTreeMap<String, Vec<u8>>
+}
+
+impl Envelope {
+    pub fn new(name: String, service: Arc<dyn KmsService>) -> Self {
+        Self { name, service, seed: Mutex::new(None) }
+    }
+
+    /**
+    Checks the health and key ID of the plugin, as the API server does every minute, and drops the seed if it was sealed with another key than the current one, so that the next write seals a new one.
+    */
+    pub fn poll_status(&self) -> Result<(), Error> {
+        let status = self.service.status()?;
+        if status.healthz != HEALTHZ_OK {
+            return Err(Error::Kms(format!("{} is unhealthy: {}", self.name, status.healthz)))
+        }
+        let mut seed = self.seed.lock().unwrap();
+        if seed.as_ref().is_some_and(|seed| seed.key_id != status.key_id) {
+            *seed = None;
+        }
+        Ok(())
+    }
+
+    fn generate_seed(&self) -> Result<Seed, Error> {
+        let mut seed = [0; DEK_SIZE];
+        random(&mut seed)?;
+        let response = self.service.encrypt(&uid(), &seed)?;
+        if response.key_id.is_empty() || response.key_id.len() > KEY_ID_MAX_SIZE {
+            return Err(Error::Kms(format!("invalid key ID from {}", self.name)))
+        }
+        Ok(Seed {
+            seed,
+            encrypted: response.ciphertext,
+            key_id: response.key_id,
+            annotations: response.annotations
+        })
+    }
+}
+
+impl fmt::Debug for Envelope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Envelope").field("name", &self.name).finish()
+    }
+}
+
+impl Transformer for Envelope {
+    fn transform_from_storage(&self, data: &[u8], context: &[u8])
+        -> Result<Vec<u8>, Error>
+    {
+        let object = EncryptedObject::decode(data)?;
+        let cached = self.seed.lock().unwrap().as_ref()
+            .filter(|seed| seed.encrypted == object.encrypted_dek_source
+                && seed.key_id == object.key_id)
+            .map(|seed| seed.seed.to_vec());
+        let dek = match cached {
+            Some(seed) => seed,
+            None => self.service.decrypt(&uid(), &DecryptRequest {
+                ciphertext: object.encrypted_dek_source,
+                key_id: object.key_id,
+                annotations: object.annotations
+            })?
+        };
+        match object.encrypted_dek_source_type {
+            EncryptedDekSourceType::AesGcmKey =>
+                aes::open(&dek, &object.encrypted_data, context),
+            EncryptedDekSourceType::HkdfSha256XNonceAesGcmSeed => {
+                if object.encrypted_data.len() < INFO_SIZE {
+                    return Err(Error::InvalidData("the stored data was shorter than the required size"))
+                }
+                let (info, encrypted_data) = object.encrypted_data.split_at(INFO_SIZE);
+                aes::open(&derive_key(&dek, info)?, encrypted_data, context)
+            }
+        }
+    }
+
+    fn transform_to_storage(&self, data: &[u8], context: &[u8])
+        -> Result<Vec<u8>, Error>
+    {
+        let mut seed = self.seed.lock().unwrap();
+        let seed = match &mut *seed {
+            Some(seed) => seed,
+            None => seed.insert(self.generate_seed()?)
+        };
+        let mut info = [0; INFO_SIZE];
+        random(&mut info)?;
+        let mut encrypted_data = info.to_vec();
+        encrypted_data.extend(aes::seal(&derive_key(&seed.seed, &info)?, data, context)?);
+        Ok(EncryptedObject {
+            encrypted_data,
+            key_id: seed.key_id.clone(),
+            encrypted_dek_source: seed.encrypted.clone(),
+            annotations: seed.annotations.clone(),
+            encrypted_dek_source_type: EncryptedDekSourceType::HkdfSha256XNonceAesGcmSeed
+        }.encode())
+    }
+}
+
+/// The AES-256 key of a value, HKDF-SHA256 expanded from `seed` with `info`, without the extract step.
+fn derive_key(seed: &[u8], info: &[u8]) -> Result<[u8; DEK_SIZE], Error> {
+    let hkdf = Hkdf::<Sha256>::from_prk(seed)
+        .map_err(|_| Error::InvalidKey(format!("seed of {} bytes", seed.len())))?;
+    let mut key = [0; DEK_SIZE];
+    hkdf.expand(info, &mut key).expect("a key is shorter than 255 hashes");
+    Ok(key)
+}
+
+fn uid() -> String {
+    let mut bytes = [0; 16];
+    random(&mut bytes).ok();
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/**
+A KMS plugin keeping its key encryption keys in memory, for tests and local clusters.
+
+Keys are named `key-<n>`, numbered in the order they are generated; [`rotate`][Self::rotate] makes a new one current while the old ones still decrypt.
+*/
+#[derive(Debug, Default)]
+pub struct LocalKms {
+    keys: Mutex<Keys>
+}
+
+#[derive(Debug, Default)]
+struct Keys {
+    /// Keys generated so far, removed ones included, so that no ID is reused.
+    generated: usize,
+    keys: Vec<(String, [u8; 32])>
+}
+
+impl Keys {
+    fn current(&self) -> Result<&(String, [u8; 32]), Error> {
+        self.keys.last().ok_or_else(|| Error::Kms("no key".to_owned()))
+    }
+}
+
+impl LocalKms {
+    pub fn new() -> Result<Self, Error> {
+        let kms = Self::default();
+        kms.rotate()?;
+        Ok(kms)
+    }
+
+    /// Generates a new key encryption key and returns its ID.
+    pub fn rotate(&self) -> Result<String, Error> {
+        let mut key = [0; 32];
+        random(&mut key)?;
+        let mut keys = self.keys.lock().unwrap();
+        keys.generated += 1;
+        let key_id = format!("key-{}", keys.generated);
+        keys.keys.push((key_id.clone(), key));
+        Ok(key_id)
+    }
+
+    /// Forgets `key_id`, making what it sealed unreadable.
+    pub fn remove(&self, key_id: &str) {
+        self.keys.lock().unwrap().keys.retain(|(id, _)| id != key_id);
+    }
+}
+
+impl KmsService for LocalKms {
+    fn status(&self) -> Result<StatusResponse, Error> {
+        let keys = self.keys.lock().unwrap();
+        let (key_id, _) = keys.current()?;
+        Ok(StatusResponse {
+            version: "v2".to_owned(),
+            healthz: HEALTHZ_OK.to_owned(),
+            key_id: key_id.clone()
+        })
+    }
+
+    fn decrypt(&self, _uid: &str, request: &DecryptRequest)
+        -> Result<Vec<u8>, Error>
+    {
+        let keys = self.keys.lock().unwrap();
+        let (_, key) = keys.keys.iter().find(|(id, _)| *id == request.key_id)
+            .ok_or_else(|| Error::Kms(format!("unknown key {}", request.key_id)))?;
+        aes::open(key, &request.ciphertext, request.key_id.as_bytes())
+    }
+
+    fn encrypt(&self, _uid: &str, plaintext: &[u8])
+        -> Result<EncryptResponse, Error>
+    {
+        let keys = self.keys.lock().unwrap();
+        let (key_id, key) = keys.current()?;
+        Ok(EncryptResponse {
+            ciphertext: aes::seal(key, plaintext, key_id.as_bytes())?,
+            key_id: key_id.clone(),
+            annotations: BTreeMap::new()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    const CONTEXT: &[u8] = b"/registry/secrets/default/token";
+
+    /// Counts the seeds and data encryption keys sealed by a [`LocalKms`].
+    #[derive(Debug, Default)]
+    struct CountingKms {
+        kms: LocalKms,
+        encrypted: AtomicUsize
+    }
+
+    impl KmsService for CountingKms {
+        fn status(&self) -> Result<StatusResponse, Error> {
+            self.kms.status()
+        }
+
+        fn decrypt(&self, uid: &str, request: &DecryptRequest) -> Result<Vec<u8>, Error> {
+            self.kms.decrypt(uid, request)
+        }
+
+        fn encrypt(&self, uid: &str, plaintext: &[u8]) -> Result<EncryptResponse, Error> {
+            self.encrypted.fetch_add(1, Ordering::SeqCst);
+            self.kms.encrypt(uid, plaintext)
+        }
+    }
+
+    fn envelope() -> (Arc<CountingKms>, Envelope) {
+        let kms = Arc::new(CountingKms::default());
+        kms.kms.rotate().unwrap();
+        (kms.clone(), Envelope::new("local".to_owned(), kms))
+    }
+
+    #[test]
+    fn round_trip() {
+        let (kms, envelope) = envelope();
+        let stored = envelope.transform_to_storage(b"secret", CONTEXT).unwrap();
+        let object = EncryptedObject::decode(&stored).unwrap();
+        assert_eq!(object.encrypted_dek_source_type, EncryptedDekSourceType::HkdfSha256XNonceAesGcmSeed);
+        assert_eq!(object.key_id, "key-1");
+        assert_eq!(envelope.transform_from_storage(&stored, CONTEXT).unwrap(), b"secret");
+        // Another API server has no seed cached and asks the plugin for it.
+        let other = Envelope::new("local".to_owned(), kms);
+        assert_eq!(other.transform_from_storage(&stored, CONTEXT).unwrap(), b"secret");
+        assert!(other.transform_from_storage(&stored, b"/registry/secrets/default/other").is_err());
+    }
+
+    #[test]
+    fn reads_values_sealed_with_the_key_directly() {
+        let (kms, envelope) = envelope();
+        let dek = [7; DEK_SIZE];
+        let response = kms.encrypt("", &dek).unwrap();
+        let stored = EncryptedObject {
+            encrypted_data: aes::seal(&dek, b"secret", CONTEXT).unwrap(),
+            key_id: response.key_id,
+            encrypted_dek_source: response.ciphertext,
+            annotations: response.annotations,
+            encrypted_dek_source_type: EncryptedDekSourceType::AesGcmKey
+        }.encode();
+        assert_eq!(envelope.transform_from_storage(&stored, CONTEXT).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn seed_is_reused_until_the_key_changes() {
+        let (kms, envelope) = envelope();
+        let first = envelope.transform_to_storage(b"one", CONTEXT).unwrap();
+        envelope.transform_to_storage(b"two", CONTEXT).unwrap();
+        envelope.poll_status().unwrap();
+        envelope.transform_to_storage(b"three", CONTEXT).unwrap();
+        assert_eq!(kms.encrypted.load(Ordering::SeqCst), 1);
+
+        kms.kms.rotate().unwrap();
+        envelope.poll_status().unwrap();
+        let second = envelope.transform_to_storage(b"four", CONTEXT).unwrap();
+        assert_eq!(kms.encrypted.load(Ordering::SeqCst), 2);
+        assert_eq!(EncryptedObject::decode(&second).unwrap().key_id, "key-2");
+        assert_eq!(envelope.transform_from_storage(&first, CONTEXT).unwrap(), b"one");
+    }
+
+    #[test]
+    fn key_ids_are_not_reused() {
+        let kms = LocalKms::new().unwrap();
+        assert_eq!(kms.rotate().unwrap(), "key-2");
+        kms.remove("key-1");
+        assert_eq!(kms.rotate().unwrap(), "key-3");
+        assert_eq!(kms.status().unwrap().key_id, "key-3");
+        let sealed = kms.encrypt("", b"dek").unwrap();
+        kms.remove("key-3");
+        assert_eq!(kms.rotate().unwrap(), "key-4");
+        assert!(kms.decrypt("", &DecryptRequest {
+            ciphertext: sealed.ciphertext,
+            key_id: sealed.key_id,
+            annotations: BTreeMap::new()
+        }).is_err());
+    }
+
+    #[test]
+    fn encrypted_object_round_trip() {
+        let object = EncryptedObject {
+            encrypted_data: vec![1, 2, 3],
+            key_id: "key-1".to_owned(),
+            encrypted_dek_source: vec![4; 40],
+            annotations: BTreeMap::from([("kms.example.com/v".to_owned(), b"1".to_vec())]),
+            encrypted_dek_source_type: EncryptedDekSourceType::HkdfSha256XNonceAesGcmSeed
+        };
+        assert_eq!(EncryptedObject::decode(&object.encode()).unwrap(), object);
+    }
+}
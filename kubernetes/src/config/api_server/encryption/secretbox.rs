@@ -0,0 +1,52 @@
+//! `secretbox` provider.
+//!
+//! Source <https://github.com/kubernetes/kubernetes/blob/master/staging/src/k8s.io/apiserver/pkg/storage/value/encrypt/secretbox/secretbox.go>
+
+use crypto_secretbox::{
+    aead::Aead, KeyInit, Nonce, XSalsa20Poly1305
+};
+
+use super::{random, Error, Transformer};
+
+const NONCE_SIZE: usize = 24;
+
+/// NaCl secretbox (XSalsa20 and Poly1305) with a 32 bytes key. Values are stored as `<24 byte nonce><16 byte tag><ciphertext>`, as Go's `secretbox.Seal` lays them out.
+pub struct Secretbox {
+    cipher: XSalsa20Poly1305
+}
+
+impl Secretbox {
+    /// `key` is the raw 32 bytes key.
+    pub fn new(key: Vec<u8>) -> Result<Self, Error> {
+        XSalsa20Poly1305::new_from_slice(&key)
+            .map(|cipher| Self { cipher })
+            .map_err(|_| Error::InvalidKey(format!("of {} bytes", key.len())))
+    }
+}
+
+impl Transformer for Secretbox {
+    fn transform_from_storage(&self, data: &[u8], _context: &[u8])
+        -> Result<Vec<u8>, Error>
+    {
+        if data.len() < NONCE_SIZE {
+            return Err(Error::InvalidData("the stored data was shorter than the required size"))
+        }
+        let (nonce, sealed) = data.split_at(NONCE_SIZE);
+        let nonce: [u8; NONCE_SIZE] = nonce.try_into()
+            .expect("split at the nonce size");
+        self.cipher.decrypt(&Nonce::from(nonce), sealed)
+            .map_err(|_| Error::InvalidData("failed to decrypt message"))
+    }
+
+    fn transform_to_storage(&self, data: &[u8], _context: &[u8])
+        -> Result<Vec<u8>, Error>
+    {
+        let mut nonce = [0; NONCE_SIZE];
+        random(&mut nonce)?;
+        let sealed = self.cipher.encrypt(&Nonce::from(nonce), data)
+            .map_err(|_| Error::InvalidData("encryption failed"))?;
+        let mut value = nonce.to_vec();
+        value.extend(sealed);
+        Ok(value)
+    }
+}
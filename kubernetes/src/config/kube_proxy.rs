@@ -5,7 +5,9 @@ use std::{
     time::Duration
 };
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
+
+use crate::decode::Fields;
 
 // #[derive(Debug, Decode)]
 // pub enum KubeProxy {
@@ -13,96 +15,265 @@ use kfl::Decode;
 // }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-proxy-config.v1alpha1/#kubeproxy-config-k8s-io-v1alpha1-KubeProxyConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct KubeProxyConfiguration {
-    feature_gates: HashMap<String, bool>,
-    bind_address: String,
-    healthz_bind_address: String,
-    metrics_bind_address: String,
-    bind_address_hard_fail: bool,
-    enable_profiling: bool,
-    cluster_cidr: String,
-    hostname_override: String,
-    client_connection: ClientConnection,
-    iptables: IpTables,
-    ipvs: IPVS,
-    oom_score_adj: i32,
-    mode: ProxyMode,
-    port_range: String,
-    udp_idle_timeout: Duration,
-    conntrack: Conntrack,
-    config_sync_period: Duration,
-    node_port_addresses: Vec<String>,
-    winkernel: Winkernel,
-    show_hidden_metrics_for_version: String,
-    detect_local_mode: LocalMode,
-    detect_local: DetectLocal
+    pub feature_gates: HashMap<String, bool>,
+    pub bind_address: String,
+    pub healthz_bind_address: String,
+    pub metrics_bind_address: String,
+    pub bind_address_hard_fail: bool,
+    pub enable_profiling: bool,
+    pub cluster_cidr: String,
+    pub hostname_override: String,
+    pub client_connection: ClientConnection,
+    pub iptables: IpTables,
+    pub ipvs: IPVS,
+    pub oom_score_adj: i32,
+    pub mode: ProxyMode,
+    pub port_range: String,
+    pub udp_idle_timeout: Duration,
+    pub conntrack: Conntrack,
+    pub config_sync_period: Duration,
+    pub node_port_addresses: Vec<String>,
+    pub winkernel: Winkernel,
+    pub show_hidden_metrics_for_version: String,
+    pub detect_local_mode: LocalMode,
+    pub detect_local: DetectLocal
+}
+
+impl Decode for KubeProxyConfiguration {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let kube_proxy_configuration = Self {
+            feature_gates: fields.entries("feature-gates")?,
+            bind_address: fields.property("bind-address")?.unwrap_or_default(),
+            healthz_bind_address: fields.property("healthz-bind-address")?.unwrap_or_default(),
+            metrics_bind_address: fields.property("metrics-bind-address")?.unwrap_or_default(),
+            bind_address_hard_fail: fields.property("bind-address-hard-fail")?.unwrap_or_default(),
+            enable_profiling: fields.property("enable-profiling")?.unwrap_or_default(),
+            cluster_cidr: fields.property("cluster-cidr")?.unwrap_or_default(),
+            hostname_override: fields.property("hostname-override")?.unwrap_or_default(),
+            client_connection: fields.child("client-connection")?.unwrap_or_default(),
+            iptables: fields.child("iptables")?.unwrap_or_default(),
+            ipvs: fields.child("ipvs")?.unwrap_or_default(),
+            oom_score_adj: fields.property("oom-score-adj")?.unwrap_or_default(),
+            mode: fields.property("mode")?.unwrap_or_default(),
+            port_range: fields.property("port-range")?.unwrap_or_default(),
+            udp_idle_timeout: fields.duration("udp-idle-timeout")?.unwrap_or_default(),
+            conntrack: fields.child("conntrack")?.unwrap_or_default(),
+            config_sync_period: fields.duration("config-sync-period")?.unwrap_or_default(),
+            node_port_addresses: fields.values("node-port-addresses")?,
+            winkernel: fields.child("winkernel")?.unwrap_or_default(),
+            show_hidden_metrics_for_version: fields.property("show-hidden-metrics-for-version")?.unwrap_or_default(),
+            detect_local_mode: fields.property("detect-local-mode")?.unwrap_or_default(),
+            detect_local: fields.child("detect-local")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(kube_proxy_configuration)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-proxy-config.v1alpha1/#ClientConnectionConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct ClientConnection {
-    kubeconfig: String,
-    accept_content_types: String,
-    content_type: String,
-    qps: f32,
-    burst: i32
+    #[kfl(property, default)]
+    pub kubeconfig: String,
+    #[kfl(property, default)]
+    pub accept_content_types: String,
+    #[kfl(property, default)]
+    pub content_type: String,
+    #[kfl(property, default)]
+    pub qps: f32,
+    #[kfl(property, default)]
+    pub burst: i32
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-proxy-config.v1alpha1/#kubeproxy-config-k8s-io-v1alpha1-KubeProxyIPTablesConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct IpTables {
-    masquerade_bit: i32,
-    masquerade_all: bool,
-    sync_period: Duration,
-    min_sync_period: Duration
+    pub masquerade_bit: MasqueradeBit,
+    pub masquerade_all: bool,
+    pub sync_period: Duration,
+    pub min_sync_period: Duration
+}
+
+impl Decode for IpTables {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let ip_tables = Self {
+            masquerade_bit: fields.property("masquerade-bit")?.unwrap_or_default(),
+            masquerade_all: fields.property("masquerade-all")?.unwrap_or_default(),
+            sync_period: fields.duration("sync-period")?.unwrap_or_default(),
+            min_sync_period: fields.duration("min-sync-period")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(ip_tables)
+    }
+}
+
+/// The bit of the packet mark flagging connections for SNAT, in `0..=31`, 14 by default. Values out of range are rejected when the configuration is decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MasqueradeBit(u8);
+
+impl MasqueradeBit {
+    pub fn new(bit: i32) -> Result<Self, String> {
+        match u8::try_from(bit) {
+            Ok(bit) if bit <= 31 => Ok(Self(bit)),
+            _ => Err(format!("masqueradeBit must be in the range 0 to 31, got {}", bit))
+        }
+    }
+
+    /// The packet mark, the bit set.
+    pub fn mark(&self) -> u32 {
+        1 << self.0
+    }
+}
+
+impl Default for MasqueradeBit {
+    fn default() -> Self {
+        Self(14)
+    }
+}
+
+impl DecodeScalar for MasqueradeBit {
+    fn decode(scalar: &kfl::ast::Scalar, ctx: &mut kfl::context::Context)
+        -> Result<Self, kfl::errors::DecodeError>
+    {
+        let bit = i32::decode(scalar, ctx)?;
+        Self::new(bit).map_err(|message|
+            kfl::errors::DecodeError::conversion(ctx.span(&scalar), message))
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-proxy-config.v1alpha1/#kubeproxy-config-k8s-io-v1alpha1-KubeProxyIPVSConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct IPVS {
-    sync_period: Duration,
-    min_sync_period: Duration,
-    scheduler: String,
-    exclude_cidrs: Vec<String>,
-    strict_arp: bool,
-    tcp_timeout: Duration,
-    tcp_fin_timeout: Duration,
-    udp_timeout: Duration
+    pub sync_period: Duration,
+    pub min_sync_period: Duration,
+    pub scheduler: String,
+    pub exclude_cidrs: Vec<String>,
+    pub strict_arp: bool,
+    pub tcp_timeout: Duration,
+    pub tcp_fin_timeout: Duration,
+    pub udp_timeout: Duration
+}
+
+impl Decode for IPVS {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let ipvs = Self {
+            sync_period: fields.duration("sync-period")?.unwrap_or_default(),
+            min_sync_period: fields.duration("min-sync-period")?.unwrap_or_default(),
+            scheduler: fields.property("scheduler")?.unwrap_or_default(),
+            exclude_cidrs: fields.values("exclude-cidrs")?,
+            strict_arp: fields.property("strict-arp")?.unwrap_or_default(),
+            tcp_timeout: fields.duration("tcp-timeout")?.unwrap_or_default(),
+            tcp_fin_timeout: fields.duration("tcp-fin-timeout")?.unwrap_or_default(),
+            udp_timeout: fields.duration("udp-timeout")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(ipvs)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-proxy-config.v1alpha1/#kubeproxy-config-k8s-io-v1alpha1-ProxyMode>
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, Default)]
 pub enum ProxyMode {
-    // #[kfl(rename(serialize = "ipvs"))]
-    IPVirtualServer
+    #[default]
+    IpTables,
+    IPVirtualServer,
+    NfTables
+}
+
+impl DecodeScalar for ProxyMode {
+    fn decode(scalar: &kfl::ast::Scalar, ctx: &mut Context) -> Result<Self, DecodeError> {
+        match String::decode(scalar, ctx)?.as_str() {
+            "iptables" => Ok(Self::IpTables),
+            "ipvs" => Ok(Self::IPVirtualServer),
+            "nftables" => Ok(Self::NfTables),
+            _ => Err(DecodeError::conversion(ctx.span(&scalar),
+                "expected one of `iptables`, `ipvs`, `nftables`"))
+        }
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-proxy-config.v1alpha1/#kubeproxy-config-k8s-io-v1alpha1-KubeProxyConntrackConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Conntrack {
-    max_per_core: i32,
-    min: i32,
-    tcp_established_timeout: Duration,
-    tcp_close_wait_timeout: Duration
+    pub max_per_core: i32,
+    pub min: i32,
+    pub tcp_established_timeout: Duration,
+    pub tcp_close_wait_timeout: Duration
+}
+
+impl Decode for Conntrack {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let conntrack = Self {
+            max_per_core: fields.property("max-per-core")?.unwrap_or_default(),
+            min: fields.property("min")?.unwrap_or_default(),
+            tcp_established_timeout: fields.duration("tcp-established-timeout")?.unwrap_or_default(),
+            tcp_close_wait_timeout: fields.duration("tcp-close-wait-timeout")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(conntrack)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-proxy-config.v1alpha1/#kubeproxy-config-k8s-io-v1alpha1-KubeProxyWinkernelConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Winkernel {
-    network_name: String,
-    source_vip: String,
-    enable_dsr: bool,
-    root_hns_endpoint_name: String,
-    forward_health_check_vip: bool
+    #[kfl(property, default)]
+    pub network_name: String,
+    #[kfl(property, default)]
+    pub source_vip: String,
+    #[kfl(property, default)]
+    pub enable_dsr: bool,
+    #[kfl(property, default)]
+    pub root_hns_endpoint_name: String,
+    #[kfl(property, default)]
+    pub forward_health_check_vip: bool
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-proxy-config.v1alpha1/#kubeproxy-config-k8s-io-v1alpha1-LocalMode>
 pub type LocalMode = String;
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-proxy-config.v1alpha1/#kubeproxy-config-k8s-io-v1alpha1-DetectLocalConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct DetectLocal {
-    bridge_interface: String,
-    interface_name_prefix: String
+    #[kfl(property, default)]
+    pub bridge_interface: String,
+    #[kfl(property, default)]
+    pub interface_name_prefix: String
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_durations() {
+        let config: KubeProxyConfiguration = crate::decode::decode("kube-proxy.kdl", r#"
+            kube-proxy-configuration mode="ipvs" cluster-cidr="10.244.0.0/16" config-sync-period="15m" {
+                iptables masquerade-bit=15 sync-period="30s" min-sync-period="1s"
+                conntrack tcp-established-timeout="24h0m0s"
+            }
+        "#).unwrap();
+        assert!(matches!(config.mode, ProxyMode::IPVirtualServer));
+        assert_eq!(config.cluster_cidr, "10.244.0.0/16");
+        assert_eq!(config.config_sync_period, Duration::from_secs(15 * 60));
+        assert_eq!(config.iptables.masquerade_bit.mark(), 0x8000);
+        assert_eq!(config.iptables.sync_period, Duration::from_secs(30));
+        assert_eq!(config.conntrack.tcp_established_timeout, Duration::from_secs(24 * 60 * 60));
+        assert!(crate::decode::decode::<KubeProxyConfiguration>("kube-proxy.kdl",
+            r#"kube-proxy-configuration config-sync-period="soon""#).is_err());
+    }
+
+    #[test]
+    fn masquerade_bit_range() {
+        assert_eq!(MasqueradeBit::default().mark(), 0x4000);
+        assert_eq!(MasqueradeBit::new(31).unwrap().mark(), 0x8000_0000);
+        assert!(MasqueradeBit::new(32).is_err());
+        assert!(MasqueradeBit::new(-1).is_err());
+    }
 }
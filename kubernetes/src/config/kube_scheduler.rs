@@ -5,9 +5,9 @@
 
 use std::time::Duration;
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
-use crate::core::pod::NodeAffinity;
+use crate::{core::pod::NodeAffinity, decode::Fields};
 
 // #[derive(Debug, Decode)]
 // pub enum KubeScheduler {
@@ -17,145 +17,265 @@ use crate::core::pod::NodeAffinity;
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-DefaultPreemptionArgs>
 #[derive(Debug, Decode)]
 pub struct DefaultPreemptionArgs {
-    min_candidate_nodes_percentage: i32,
-    min_candidate_nodes_absolute: i32
+    #[kfl(property, default)]
+    pub min_candidate_nodes_percentage: i32,
+    #[kfl(property, default)]
+    pub min_candidate_nodes_absolute: i32
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-InterPodAffinityArgs>
 #[derive(Debug, Decode)]
 pub struct InterPodAffinityArgs {
-    hard_pod_affinity_weight: i32
+    #[kfl(property, default)]
+    pub hard_pod_affinity_weight: i32
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-KubeSchedulerConfiguration>
 #[derive(Debug, Decode)]
 pub struct KubeSchedulerConfiguration {
-    parallelism: i32,
-    leader_election: LeaderElection,
-    client_connection: ClientConnection,
-    debugging: Debugging,
-    percentage_of_nodes_to_score: i32,
-    pod_initial_backoff_seconds: i64,
-    pod_max_backoff_seconds: i64,
-    profiles: Vec<Profile>,
-    extenders: Vec<Extender>
+    #[kfl(property, default)]
+    pub parallelism: i32,
+    #[kfl(child)]
+    pub leader_election: LeaderElection,
+    #[kfl(child)]
+    pub client_connection: ClientConnection,
+    #[kfl(child)]
+    pub debugging: Debugging,
+    #[kfl(property, default)]
+    pub percentage_of_nodes_to_score: i32,
+    #[kfl(property, default)]
+    pub pod_initial_backoff_seconds: i64,
+    #[kfl(property, default)]
+    pub pod_max_backoff_seconds: i64,
+    #[kfl(children)]
+    pub profiles: Vec<Profile>,
+    #[kfl(children)]
+    pub extenders: Vec<Extender>
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#LeaderElectionConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct LeaderElection {
-    leader_elect: bool,
-    lease_duration: Duration,
-    renew_deadline: Duration,
-    retry_period: Duration,
-    resource_lock: String,
-    resource_name: String,
-    resource_namespace: String
+    pub leader_elect: bool,
+    pub lease_duration: Duration,
+    pub renew_deadline: Duration,
+    pub retry_period: Duration,
+    pub resource_lock: String,
+    pub resource_name: String,
+    pub resource_namespace: String
+}
+
+impl Decode for LeaderElection {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let leader_election = Self {
+            leader_elect: fields.property("leader-elect")?.unwrap_or_default(),
+            lease_duration: fields.duration("lease-duration")?.unwrap_or_default(),
+            renew_deadline: fields.duration("renew-deadline")?.unwrap_or_default(),
+            retry_period: fields.duration("retry-period")?.unwrap_or_default(),
+            resource_lock: fields.property("resource-lock")?.unwrap_or_default(),
+            resource_name: fields.property("resource-name")?.unwrap_or_default(),
+            resource_namespace: fields.property("resource-namespace")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(leader_election)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#ClientConnectionConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct ClientConnection {
-    kubeconfig: String,
-    accept_content_types: String,
-    content_type: String,
-    qps: f32,
-    burst: i32
+    #[kfl(property, default)]
+    pub kubeconfig: String,
+    #[kfl(property, default)]
+    pub accept_content_types: String,
+    #[kfl(property, default)]
+    pub content_type: String,
+    #[kfl(property, default)]
+    pub qps: f32,
+    #[kfl(property, default)]
+    pub burst: i32
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#DebuggingConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Debugging {
-    enable_profiling: bool,
-    enable_contention_profiling: bool
+    #[kfl(property, default)]
+    pub enable_profiling: bool,
+    #[kfl(property, default)]
+    pub enable_contention_profiling: bool
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-KubeSchedulerProfile>
 #[derive(Debug, Decode)]
 pub struct Profile {
-    scheduler_name: String,
-    plugins: Plugins,
-    plugin_config: Vec<PluginConfig>
+    #[kfl(property, default)]
+    pub scheduler_name: String,
+    #[kfl(child)]
+    pub plugins: Plugins,
+    #[kfl(children)]
+    pub plugin_config: Vec<PluginConfig>
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-Plugins>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Plugins {
-    queue_sort: PluginSet,
-    pre_filter: PluginSet,
-    filter: PluginSet,
-    post_filter: PluginSet,
-    pre_score: PluginSet,
-    score: PluginSet,
-    reserve: PluginSet,
-    permit: PluginSet,
-    pre_bind: PluginSet,
-    bind: PluginSet,
-    post_bind: PluginSet,
-    multi_point: PluginSet
+    pub queue_sort: PluginSet,
+    pub pre_filter: PluginSet,
+    pub filter: PluginSet,
+    pub post_filter: PluginSet,
+    pub pre_score: PluginSet,
+    pub score: PluginSet,
+    pub reserve: PluginSet,
+    pub permit: PluginSet,
+    pub pre_bind: PluginSet,
+    pub bind: PluginSet,
+    pub post_bind: PluginSet,
+    pub multi_point: PluginSet
+}
+
+impl Decode for Plugins {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let plugins = Self {
+            queue_sort: fields.child("queue-sort")?.unwrap_or_default(),
+            pre_filter: fields.child("pre-filter")?.unwrap_or_default(),
+            filter: fields.child("filter")?.unwrap_or_default(),
+            post_filter: fields.child("post-filter")?.unwrap_or_default(),
+            pre_score: fields.child("pre-score")?.unwrap_or_default(),
+            score: fields.child("score")?.unwrap_or_default(),
+            reserve: fields.child("reserve")?.unwrap_or_default(),
+            permit: fields.child("permit")?.unwrap_or_default(),
+            pre_bind: fields.child("pre-bind")?.unwrap_or_default(),
+            bind: fields.child("bind")?.unwrap_or_default(),
+            post_bind: fields.child("post-bind")?.unwrap_or_default(),
+            multi_point: fields.child("multi-point")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(plugins)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-PluginSet>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct PluginSet {
-    enabled: Vec<Plugin>,
-    disabled: Vec<Plugin>
+    pub enabled: Vec<Plugin>,
+    pub disabled: Vec<Plugin>
+}
+
+impl Decode for PluginSet {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let plugin_set = Self {
+            enabled: fields.children("enabled")?,
+            disabled: fields.children("disabled")?
+        };
+        fields.finish()?;
+        Ok(plugin_set)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-Plugin>
 #[derive(Debug, Decode)]
 pub struct Plugin {
-    name: String,
-    weight: i32
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub weight: i32
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-PluginConfig>
 #[derive(Debug, Decode)]
 pub struct PluginConfig {
-    name: String,
-    args: Vec<u8>
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub args: Vec<u8>
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-Extender>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Extender {
-    url_prefix: String,
-    filter_verb: String,
-    preempt_verb: String,
+    pub url_prefix: String,
+    pub filter_verb: String,
+    pub preempt_verb: String,
     // prioritize_verb
-    prioritise_verb: String,
-    weight: i64,
-    bind_verb: String,
-    enable_https: bool,
-    tls_config: ExtenderTLSConfig,
-    http_timeout: Duration,
-    node_cache_capable: bool,
-    managed_resources: Vec<ExtenderManagedResource>,
-    ignorable: bool
+    pub prioritise_verb: String,
+    pub weight: i64,
+    pub bind_verb: String,
+    pub enable_https: bool,
+    pub tls_config: ExtenderTLSConfig,
+    pub http_timeout: Duration,
+    pub node_cache_capable: bool,
+    pub managed_resources: Vec<ExtenderManagedResource>,
+    pub ignorable: bool
+}
+
+impl Decode for Extender {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let extender = Self {
+            url_prefix: fields.property("url-prefix")?.unwrap_or_default(),
+            filter_verb: fields.property("filter-verb")?.unwrap_or_default(),
+            preempt_verb: fields.property("preempt-verb")?.unwrap_or_default(),
+            prioritise_verb: fields.property("prioritise-verb")?.unwrap_or_default(),
+            weight: fields.property("weight")?.unwrap_or_default(),
+            bind_verb: fields.property("bind-verb")?.unwrap_or_default(),
+            enable_https: fields.property("enable-https")?.unwrap_or_default(),
+            tls_config: fields.child("tls-config")?.ok_or_else(|| fields.missing("tls-config"))?,
+            http_timeout: fields.duration("http-timeout")?.unwrap_or_default(),
+            node_cache_capable: fields.property("node-cache-capable")?.unwrap_or_default(),
+            managed_resources: fields.children("extender-managed-resource")?,
+            ignorable: fields.property("ignorable")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(extender)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-ExtenderTLSConfig>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct ExtenderTLSConfig {
-    insecure: bool,
-    server_name: String,
-    cert_file: String,
-    key_file: String,
-    ca_file: String,
-    cert_data: Vec<u8>,
-    key_data: Vec<u8>,
-    ca_data: Vec<u8>,
+    #[kfl(property, default)]
+    pub insecure: bool,
+    #[kfl(property, default)]
+    pub server_name: String,
+    #[kfl(property, default)]
+    pub cert_file: String,
+    #[kfl(property, default)]
+    pub key_file: String,
+    #[kfl(property, default)]
+    pub ca_file: String,
+    #[kfl(property, default)]
+    pub cert_data: Vec<u8>,
+    #[kfl(property, default)]
+    pub key_data: Vec<u8>,
+    #[kfl(property, default)]
+    pub ca_data: Vec<u8>,
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-ExtenderManagedResource>
 #[derive(Debug, Decode)]
 pub struct ExtenderManagedResource {
-    name: String,
-    ignored_by_scheduler: bool
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub ignored_by_scheduler: bool
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kube-scheduler-config.v1/#kubescheduler-config-k8s-io-v1-NodeAffinityArgs>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct NodeAffinityArgs {
-    added_affinity: Option<NodeAffinity>,
+    pub added_affinity: Option<NodeAffinity>,
+}
+
+impl Decode for NodeAffinityArgs {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let node_affinity_args = Self {
+            added_affinity: fields.child("added-affinity")?
+        };
+        fields.finish()?;
+        Ok(node_affinity_args)
+    }
 }
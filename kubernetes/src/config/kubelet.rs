@@ -9,101 +9,234 @@ use std::{
     time::Duration,
 };
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
+use crate::decode::Fields;
+
+/// <https://kubernetes.io/docs/reference/config-api/kubelet-config.v1/#kubelet-config-k8s-io-v1-CredentialProviderConfig>
 #[derive(Debug, Decode)]
 pub struct CredentialProviderConfig {
+    /// A list of credential provider plugins that will be enabled by the kubelet. Multiple providers may match against a single image, in which case credentials from all providers will be returned to the kubelet.
+    #[kfl(children)]
+    pub providers: Vec<CredentialProvider>
 }
 
-/// <https://kubernetes.io/docs/reference/config-api/kubelet-config.v1beta1/#kubelet-config-k8s-io-v1beta1-KubeletConfiguration>
+/// <https://kubernetes.io/docs/reference/config-api/kubelet-config.v1/#kubelet-config-k8s-io-v1-CredentialProvider>
+#[derive(Debug)]
+pub struct CredentialProvider {
+    /// The required name of the credential provider. It must match the name of the provider executable as seen by the kubelet, in the directory passed by `--image-credential-provider-bin-dir`.
+    pub name: String,
+    /**
+    A required list of strings used to match against images in order to determine if this provider should be invoked. If one of the strings matches the requested image from the kubelet, the plugin will be invoked and given a chance to provide credentials.
+
+    Each entry may contain an optional port and path. Globs can be used in the domain, but not in the port or the path, and match a single subdomain segment, so `*.io` does not match `*.k8s.io`. A match exists when the number of domain parts and each part match, the ports match if given, and the image path starts with the entry path.
+
+    Example values: `*.registry.io`, `*.*.registry.io`, `registry.io:8080/path`.
+    */
+    pub match_images: Vec<String>,
+    /// The default duration the plugin will cache credentials in-memory if a cache duration is not provided in the plugin response.
+    pub default_cache_duration: Option<Duration>,
+    /// The input version of `CredentialProviderRequest`. The returned `CredentialProviderResponse` must use the same encoding version as the input.
+    pub api_version: String,
+    /// Arguments to pass to the command when executing it.
+    pub args: Vec<String>,
+    /// Additional environment variables to expose to the process. These are unioned with the host's environment, as well as variables client-go uses to pass argument to the plugin.
+    pub env: Vec<ExecEnvVar>
+}
+
+impl Decode for CredentialProvider {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let credential_provider = Self {
+            name: fields.property("name")?.unwrap_or_default(),
+            match_images: fields.values("match-images")?,
+            default_cache_duration: fields.duration("default-cache-duration")?,
+            api_version: fields.property("api-version")?.unwrap_or_default(),
+            args: fields.values("args")?,
+            env: fields.children("exec-env-var")?
+        };
+        fields.finish()?;
+        Ok(credential_provider)
+    }
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubelet-config.v1/#kubelet-config-k8s-io-v1-ExecEnvVar>
 #[derive(Debug, Decode)]
+pub struct ExecEnvVar {
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub value: String
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubelet-config.v1beta1/#kubelet-config-k8s-io-v1beta1-KubeletConfiguration>
+#[derive(Debug)]
 pub struct KubeletConfiguration {
-    enable_server: bool,
-    static_pod_path: Option<PathBuf>,
-    sync_frequency: Option<Duration>,
-    file_check_frequency: Option<Duration>,
-    http_check_frequency: Option<Duration>,
+    pub enable_server: bool,
+    pub static_pod_path: Option<PathBuf>,
+    pub sync_frequency: Option<Duration>,
+    pub file_check_frequency: Option<Duration>,
+    pub http_check_frequency: Option<Duration>,
     // #[kfl(rename(serialize = "staticPodURL"))]
-    static_pod_url: Option<String>,
+    pub static_pod_url: Option<String>,
     // #[kfl(rename(serialize = "staticPodURLHeader"))]
-    static_pod_url_header: HashMap<String, String>,
-    address: Option<IpAddr>,
-    port: Option<i32>,
-    read_only_port: Option<i32>,
-    tls_cert_file: Option<String>,
-    tls_private_key_file: Option<String>,
-    tls_cipher_suites: Vec<String>,
-    tls_min_version: Option<String>,
-    rotate_certificates: Option<bool>,
+    pub static_pod_url_header: HashMap<String, String>,
+    pub address: Option<IpAddr>,
+    pub port: Option<i32>,
+    pub read_only_port: Option<i32>,
+    pub tls_cert_file: Option<String>,
+    pub tls_private_key_file: Option<String>,
+    pub tls_cipher_suites: Vec<String>,
+    pub tls_min_version: Option<String>,
+    pub rotate_certificates: Option<bool>,
     // #[kfl(rename(serialize = "serverTLSBootstrap"))]
-    server_tls_bootstrap: Option<bool>,
-    authentication: Option<KubeletAuthentication>,
+    pub server_tls_bootstrap: Option<bool>,
+    pub authentication: Option<KubeletAuthentication>,
     // #[kfl(rename(serialize = "authorization"))]
-    authorisation: Option<KubeletAuthorisation>,
+    pub authorisation: Option<KubeletAuthorisation>,
     // #[kfl(rename(serialize = "registryPullQPS"))]
-    registry_pull_qps: Option<i32>,
-    registry_burst: Option<i32>,
+    pub registry_pull_qps: Option<i32>,
+    pub registry_burst: Option<i32>,
     // #[kfl(rename(serialize = "eventRecordQPS"))]
-    event_record_qps: Option<i32>,
-    event_burst: Option<i32>,
-    enable_debugging_handlers: Option<bool>,
-    enable_contention_profiling: Option<bool>,
-    healthz_port: Option<i32>,
-    healthz_bind_address: Option<String>,
-    oom_score_adj: Option<i32>,
-    cluster_domain: Option<String>,
+    pub event_record_qps: Option<i32>,
+    pub event_burst: Option<i32>,
+    pub enable_debugging_handlers: Option<bool>,
+    pub enable_contention_profiling: Option<bool>,
+    pub healthz_port: Option<i32>,
+    pub healthz_bind_address: Option<String>,
+    pub oom_score_adj: Option<i32>,
+    pub cluster_domain: Option<String>,
     // #[kfl(rename(serialize = "clusterDNS"))]
-    cluster_dns: Vec<String>,
-    streaming_connection_idle_timeout: Option<Duration>,
-    node_status_update_frequency: Option<Duration>,
-    node_status_report_frequency: Option<Duration>,
-    node_lease_duration_seconds: Option<i32>,
+    pub cluster_dns: Vec<String>,
+    pub streaming_connection_idle_timeout: Option<Duration>,
+    pub node_status_update_frequency: Option<Duration>,
+    pub node_status_report_frequency: Option<Duration>,
+    pub node_lease_duration_seconds: Option<i32>,
     // #[kfl(rename(serialize = "imageMinimumGCAge"))]
-    image_minimum_gc_age: Option<Duration>,
+    pub image_minimum_gc_age: Option<Duration>,
     // #[kfl(rename(serialize = "imageGCHighThresholdPercent"))]
-    image_gc_high_threshold_percent: Option<i32>,
+    pub image_gc_high_threshold_percent: Option<i32>,
     // #[kfl(rename(serialize = "imageGCLowThresholdPercent"))]
-    image_gc_low_threshold_percent: Option<i32>,
-    volume_stats_agg_period: Option<Duration>,
-    kubelet_cgroups: Option<String>,
-    system_cgroups: Option<String>,
-    cgroup_root: Option<String>,
+    pub image_gc_low_threshold_percent: Option<i32>,
+    pub volume_stats_agg_period: Option<Duration>,
+    pub kubelet_cgroups: Option<String>,
+    pub system_cgroups: Option<String>,
+    pub cgroup_root: Option<String>,
     // #[kfl(rename(serialize = "cgroupsPerQOS"))]
-    cgroups_per_qos: Option<bool>,
-    cgroup_driver: Option<String>,
-    cpu_manager_policy: Option<String>,
-    cpu_manager_policy_options: HashMap<String, String>,
-    cpu_manager_reconcile_period: Option<Duration>,
-    memory_manager_policy: Option<String>,
-    topology_manager_policy: Option<String>,
-    topology_manager_scope: Option<String>,
-    qos_reserved: HashMap<String, String>,
-    runtime_request_timeout: Option<Duration>,
-    hairpin_mode: Option<String>,
-    max_pods: Option<i32>,
+    pub cgroups_per_qos: Option<bool>,
+    pub cgroup_driver: Option<String>,
+    pub cpu_manager_policy: Option<String>,
+    pub cpu_manager_policy_options: HashMap<String, String>,
+    pub cpu_manager_reconcile_period: Option<Duration>,
+    pub memory_manager_policy: Option<String>,
+    pub topology_manager_policy: Option<String>,
+    pub topology_manager_scope: Option<String>,
+    pub qos_reserved: HashMap<String, String>,
+    pub runtime_request_timeout: Option<Duration>,
+    pub hairpin_mode: Option<String>,
+    pub max_pods: Option<i32>,
     // #[kfl(rename(serialize = "podCIDR"))]
-    pod_cidr: Option<String>,
-    pod_pids_limit: Option<i64>,
-    resolv_conf: Option<String>,
-    run_once: Option<String>,
+    pub pod_cidr: Option<String>,
+    pub pod_pids_limit: Option<i64>,
+    pub resolv_conf: Option<String>,
+    pub run_once: Option<String>,
     // #[kfl(rename(serialize = "cpuCFSQuota"))]
-    cpu_cfs_quota: Option<bool>,
+    pub cpu_cfs_quota: Option<bool>,
     // #[kfl(rename(serialize = "cpuCFSQuota"))]
-    cpu_cfs_quota_period: Option<Duration>,
-    node_status_max_images: Option<i32>,
-    max_open_files: Option<i64>,
-    content_type: Option<String>,
+    pub cpu_cfs_quota_period: Option<Duration>,
+    pub node_status_max_images: Option<i32>,
+    pub max_open_files: Option<i64>,
+    pub content_type: Option<String>,
     // #[kfl(rename(serialize = "cpuCFSQuota"))]
-    kube_api_qps: Option<i32>,
+    pub kube_api_qps: Option<i32>,
     // #[kfl(rename(serialize = "kubeAPIBurst"))]
-    kube_api_burst: Option<i32>,
+    pub kube_api_burst: Option<i32>,
     // #[kfl(rename(serialize = "serializeImagePulls"))]
-    serialise_image_pulls: Option<bool>,
-    eviction_hard: HashMap<String, String>,
-    eviction_soft: HashMap<String, String>,
-    eviction_soft_grace_period: HashMap<String, String>,
-    eviction_pressure_transition_period: Option<Duration>,
-    eviction_max_pod_grace_period: Option<i32>
+    pub serialise_image_pulls: Option<bool>,
+    pub eviction_hard: HashMap<String, String>,
+    pub eviction_soft: HashMap<String, String>,
+    pub eviction_soft_grace_period: HashMap<String, String>,
+    pub eviction_pressure_transition_period: Option<Duration>,
+    pub eviction_max_pod_grace_period: Option<i32>
+}
+
+impl Decode for KubeletConfiguration {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let kubelet_configuration = Self {
+            enable_server: fields.property("enable-server")?.unwrap_or_default(),
+            static_pod_path: fields.property("static-pod-path")?,
+            sync_frequency: fields.duration("sync-frequency")?,
+            file_check_frequency: fields.duration("file-check-frequency")?,
+            http_check_frequency: fields.duration("http-check-frequency")?,
+            static_pod_url: fields.property("static-pod-url")?,
+            static_pod_url_header: fields.entries("static-pod-url-header")?,
+            address: fields.parse("address")?,
+            port: fields.property("port")?,
+            read_only_port: fields.property("read-only-port")?,
+            tls_cert_file: fields.property("tls-cert-file")?,
+            tls_private_key_file: fields.property("tls-private-key-file")?,
+            tls_cipher_suites: fields.values("tls-cipher-suites")?,
+            tls_min_version: fields.property("tls-min-version")?,
+            rotate_certificates: fields.property("rotate-certificates")?,
+            server_tls_bootstrap: fields.property("server-tls-bootstrap")?,
+            authentication: fields.child("authentication")?,
+            authorisation: fields.child("authorisation")?,
+            registry_pull_qps: fields.property("registry-pull-qps")?,
+            registry_burst: fields.property("registry-burst")?,
+            event_record_qps: fields.property("event-record-qps")?,
+            event_burst: fields.property("event-burst")?,
+            enable_debugging_handlers: fields.property("enable-debugging-handlers")?,
+            enable_contention_profiling: fields.property("enable-contention-profiling")?,
+            healthz_port: fields.property("healthz-port")?,
+            healthz_bind_address: fields.property("healthz-bind-address")?,
+            oom_score_adj: fields.property("oom-score-adj")?,
+            cluster_domain: fields.property("cluster-domain")?,
+            cluster_dns: fields.values("cluster-dns")?,
+            streaming_connection_idle_timeout: fields.duration("streaming-connection-idle-timeout")?,
+            node_status_update_frequency: fields.duration("node-status-update-frequency")?,
+            node_status_report_frequency: fields.duration("node-status-report-frequency")?,
+            node_lease_duration_seconds: fields.property("node-lease-duration-seconds")?,
+            image_minimum_gc_age: fields.duration("image-minimum-gc-age")?,
+            image_gc_high_threshold_percent: fields.property("image-gc-high-threshold-percent")?,
+            image_gc_low_threshold_percent: fields.property("image-gc-low-threshold-percent")?,
+            volume_stats_agg_period: fields.duration("volume-stats-agg-period")?,
+            kubelet_cgroups: fields.property("kubelet-cgroups")?,
+            system_cgroups: fields.property("system-cgroups")?,
+            cgroup_root: fields.property("cgroup-root")?,
+            cgroups_per_qos: fields.property("cgroups-per-qos")?,
+            cgroup_driver: fields.property("cgroup-driver")?,
+            cpu_manager_policy: fields.property("cpu-manager-policy")?,
+            cpu_manager_policy_options: fields.entries("cpu-manager-policy-options")?,
+            cpu_manager_reconcile_period: fields.duration("cpu-manager-reconcile-period")?,
+            memory_manager_policy: fields.property("memory-manager-policy")?,
+            topology_manager_policy: fields.property("topology-manager-policy")?,
+            topology_manager_scope: fields.property("topology-manager-scope")?,
+            qos_reserved: fields.entries("qos-reserved")?,
+            runtime_request_timeout: fields.duration("runtime-request-timeout")?,
+            hairpin_mode: fields.property("hairpin-mode")?,
+            max_pods: fields.property("max-pods")?,
+            pod_cidr: fields.property("pod-cidr")?,
+            pod_pids_limit: fields.property("pod-pids-limit")?,
+            resolv_conf: fields.property("resolv-conf")?,
+            run_once: fields.property("run-once")?,
+            cpu_cfs_quota: fields.property("cpu-cfs-quota")?,
+            cpu_cfs_quota_period: fields.duration("cpu-cfs-quota-period")?,
+            node_status_max_images: fields.property("node-status-max-images")?,
+            max_open_files: fields.property("max-open-files")?,
+            content_type: fields.property("content-type")?,
+            kube_api_qps: fields.property("kube-api-qps")?,
+            kube_api_burst: fields.property("kube-api-burst")?,
+            serialise_image_pulls: fields.property("serialise-image-pulls")?,
+            eviction_hard: fields.entries("eviction-hard")?,
+            eviction_soft: fields.entries("eviction-soft")?,
+            eviction_soft_grace_period: fields.entries("eviction-soft-grace-period")?,
+            eviction_pressure_transition_period: fields.duration("eviction-pressure-transition-period")?,
+            eviction_max_pod_grace_period: fields.property("eviction-max-pod-grace-period")?
+        };
+        fields.finish()?;
+        Ok(kubelet_configuration)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubelet-config.v1beta1/#kubelet-config-k8s-io-v1beta1-KubeletAuthentication>
@@ -1,3 +1,4 @@
+pub mod leader_election;
 pub mod lease;
 
 // use kfl::Decode;
@@ -0,0 +1,610 @@
+/*!
+Leader election over a [`Lease`], as client-go's `leaderelection` package does it.
+
+Candidates race to write their identity into the lease's [`holder_identity`][Spec::holder_identity]; the holder renews [`renew_time`][Spec::renew_time] every [`retry_period`][Config::retry_period] and the others take over once it has not done so for [`lease_duration`][Config::lease_duration]. Expiry is judged against the local clock, from the moment the candidate last saw the lease change, so clocks need not agree across nodes, only tick at the same rate.
+
+Leases are kept by the API server: [`Client`] is the [`LeaseStore`] of production, [`etcd::EtcdStore`] one for components that run before the API server does, and [`MemoryStore`] one for tests.
+
+- Concepts <https://kubernetes.io/docs/concepts/architecture/leases/#leader-election>
+- Source <https://github.com/kubernetes/client-go/tree/master/tools/leaderelection>
+*/
+
+pub mod etcd;
+
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex
+    },
+    time::{Duration, Instant}
+};
+
+use crate::{
+    client::{self, Api, BoxFuture, Client, PostParams},
+    coordination::lease::{Lease, Spec},
+    meta::{status, Metadata},
+    time::MicroTime
+};
+
+/// How much [`retry_period`][Config::retry_period] is randomly stretched by while not leading, so candidates do not retry in lockstep.
+pub const JITTER_FACTOR: f64 = 1.2;
+
+/// <https://pkg.go.dev/k8s.io/client-go/tools/leaderelection#LeaderElectionConfig>
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub namespace: String,
+    pub name: String,
+    /// Unique among the candidates, usually the hostname with a random suffix.
+    pub identity: String,
+    /// How long non-leaders wait after the last observed change of the lease before taking it over.
+    pub lease_duration: Duration,
+    /// How long the leader keeps retrying to renew before giving up leadership.
+    pub renew_deadline: Duration,
+    /// How long to wait between attempts to acquire or renew.
+    pub retry_period: Duration,
+    /// Whether to give up the lease when [`run`][LeaderElector::run] is stopped, so another candidate can take over without waiting for it to expire.
+    pub release_on_cancel: bool
+}
+
+impl Config {
+    /// A configuration with the defaults of `kube-controller-manager` and `kube-scheduler`: 15s lease duration, 10s renew deadline and 2s retry period.
+    pub fn new(namespace: &str, name: &str, identity: &str) -> Self {
+        Self {
+            namespace: namespace.to_owned(),
+            name: name.to_owned(),
+            identity: identity.to_owned(),
+            lease_duration: Duration::from_secs(15),
+            renew_deadline: Duration::from_secs(10),
+            retry_period: Duration::from_secs(2),
+            release_on_cancel: false
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |message: &str| Err(Error::InvalidConfig(message.to_owned()));
+        if self.name.is_empty() {
+            return invalid("lease name must not be empty")
+        }
+        if self.identity.is_empty() {
+            return invalid("identity must not be empty")
+        }
+        if self.lease_duration <= self.renew_deadline {
+            return invalid("lease duration must be greater than renew deadline")
+        }
+        if self.renew_deadline <= self.retry_period.mul_f64(JITTER_FACTOR) {
+            return invalid("renew deadline must be greater than retry period * jitter factor")
+        }
+        if self.retry_period.is_zero() {
+            return invalid("retry period must be greater than zero")
+        }
+        if self.lease_duration.as_secs() == 0 {
+            return invalid("lease duration must be at least one second")
+        }
+        Ok(())
+    }
+}
+
+/**
+Where leases are read from and written to, the API server's `coordination.k8s.io/v1` leases in production.
+
+Writes are optimistic: [`update`][Self::update] must fail with [`Error::Conflict`] if the [`resource_version`][Metadata::resource_version] of the lease is not the stored one, which is what makes the election safe.
+*/
+pub trait LeaseStore: Send + Sync {
+    fn get<'a>(&'a self, namespace: &'a str, name: &'a str)
+        -> BoxFuture<'a, Result<Option<Lease>, Error>>;
+    /// Stores a new lease and returns it with its resource version, or fails with [`Error::AlreadyExists`].
+    fn create(&self, lease: Lease) -> BoxFuture<'_, Result<Lease, Error>>;
+    /// Replaces a lease and returns it with its new resource version, or fails with [`Error::Conflict`] or [`Error::NotFound`].
+    fn update(&self, lease: Lease) -> BoxFuture<'_, Result<Lease, Error>>;
+}
+
+impl<T: LeaseStore + ?Sized> LeaseStore for &T {
+    fn get<'a>(&'a self, namespace: &'a str, name: &'a str)
+        -> BoxFuture<'a, Result<Option<Lease>, Error>>
+    {
+        (**self).get(namespace, name)
+    }
+
+    fn create(&self, lease: Lease) -> BoxFuture<'_, Result<Lease, Error>> {
+        (**self).create(lease)
+    }
+
+    fn update(&self, lease: Lease) -> BoxFuture<'_, Result<Lease, Error>> {
+        (**self).update(lease)
+    }
+}
+
+/// Leases of the API server.
+impl LeaseStore for Client {
+    fn get<'a>(&'a self, namespace: &'a str, name: &'a str)
+        -> BoxFuture<'a, Result<Option<Lease>, Error>>
+    {
+        Box::pin(async move {
+            match Api::<Lease>::namespaced(self.clone(), namespace).get(name).await {
+                Ok(lease) => Ok(Some(lease)),
+                Err(error) if reason(&error) == Some(status::NOT_FOUND) => Ok(None),
+                Err(error) => Err(store_error(error, format!("{}/{}", namespace, name)))
+            }
+        })
+    }
+
+    fn create(&self, lease: Lease) -> BoxFuture<'_, Result<Lease, Error>> {
+        Box::pin(async move {
+            let key = key(&lease)?;
+            let (namespace, _) = key.split_once('/').unwrap_or_default();
+            Api::<Lease>::namespaced(self.clone(), namespace)
+                .create(&lease, &PostParams::default()).await
+                .map_err(|error| store_error(error, key))
+        })
+    }
+
+    fn update(&self, lease: Lease) -> BoxFuture<'_, Result<Lease, Error>> {
+        Box::pin(async move {
+            let key = key(&lease)?;
+            let (namespace, name) = key.split_once('/').unwrap_or_default();
+            Api::<Lease>::namespaced(self.clone(), namespace)
+                .replace(name, &lease, &PostParams::default()).await
+                .map_err(|error| store_error(error, key))
+        })
+    }
+}
+
+fn reason(error: &client::Error) -> Option<&str> {
+    error.status().and_then(|status| status.reason.as_deref())
+}
+
+/// The error of the store for what the server answered about the lease `key`.
+fn store_error(error: client::Error, key: String) -> Error {
+    match reason(&error) {
+        Some(status::NOT_FOUND) => Error::NotFound(key),
+        Some(status::ALREADY_EXISTS) => Error::AlreadyExists(key),
+        Some(status::CONFLICT) => Error::Conflict(key),
+        _ => Error::Store(error.to_string())
+    }
+}
+
+/// A [`LeaseStore`] in memory, for tests and for electing among threads of one process.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    /// By `<namespace>/<name>`.
+    leases: Mutex<HashMap<String, Lease>>,
+    revision: AtomicU64
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LeaseStore for MemoryStore {
+    fn get<'a>(&'a self, namespace: &'a str, name: &'a str)
+        -> BoxFuture<'a, Result<Option<Lease>, Error>>
+    {
+        let leases = self.leases.lock().unwrap();
+        let lease = leases.get(&format!("{}/{}", namespace, name)).cloned();
+        Box::pin(async move { Ok(lease) })
+    }
+
+    fn create(&self, lease: Lease) -> BoxFuture<'_, Result<Lease, Error>> {
+        let result = self.insert(lease);
+        Box::pin(async move { result })
+    }
+
+    fn update(&self, lease: Lease) -> BoxFuture<'_, Result<Lease, Error>> {
+        let result = self.replace(lease);
+        Box::pin(async move { result })
+    }
+}
+
+impl MemoryStore {
+    fn insert(&self, mut lease: Lease) -> Result<Lease, Error> {
+        let key = key(&lease)?;
+        let mut leases = self.leases.lock().unwrap();
+        if leases.contains_key(&key) {
+            return Err(Error::AlreadyExists(key))
+        }
+        let revision = self.revision.fetch_add(1, Ordering::Relaxed) + 1;
+        let metadata = lease.metadata.get_or_insert_with(Metadata::default);
+        metadata.resource_version = Some(revision.to_string());
+        leases.insert(key, lease.clone());
+        Ok(lease)
+    }
+
+    fn replace(&self, mut lease: Lease) -> Result<Lease, Error> {
+        let key = key(&lease)?;
+        let mut leases = self.leases.lock().unwrap();
+        let Some(stored) = leases.get(&key) else {
+            return Err(Error::NotFound(key))
+        };
+        let version = |lease: &Lease| lease.metadata.as_ref()
+            .and_then(|metadata| metadata.resource_version.clone());
+        if version(stored) != version(&lease) {
+            return Err(Error::Conflict(key))
+        }
+        let revision = self.revision.fetch_add(1, Ordering::Relaxed) + 1;
+        let metadata = lease.metadata.get_or_insert_with(Metadata::default);
+        metadata.resource_version = Some(revision.to_string());
+        leases.insert(key, lease.clone());
+        Ok(lease)
+    }
+}
+
+/// `<namespace>/<name>` of a lease.
+fn key(lease: &Lease) -> Result<String, Error> {
+    match &lease.metadata {
+        Some(Metadata { name: Some(name), namespace, .. }) => Ok(format!("{}/{}",
+            namespace.as_deref().unwrap_or("default"), name)),
+        _ => Err(Error::Store("lease has no name".to_owned()))
+    }
+}
+
+/// <https://pkg.go.dev/k8s.io/client-go/tools/leaderelection#LeaderCallbacks>
+pub trait Callbacks {
+    /// Called when this candidate becomes the leader, to start the work it guards.
+    fn on_started_leading(&mut self);
+    /// Called when this candidate stops being the leader, to stop that work at once since another candidate may take over after [`lease_duration`][Config::lease_duration].
+    fn on_stopped_leading(&mut self);
+    /// Called with the identity of the holder whenever it changes, this candidate included.
+    fn on_new_leader(&mut self, _identity: &str) {}
+}
+
+/// A candidate in the election of one lease.
+pub struct LeaderElector<S> {
+    config: Config,
+    store: S,
+    callbacks: Box<dyn Callbacks + Send>,
+    /// The lease as last read or written.
+    observed: Option<Lease>,
+    /// When [`observed`][Self::observed] last changed, by the local clock.
+    observed_time: Option<Instant>,
+    leading: bool,
+    last_renew: Option<Instant>,
+    reported_leader: Option<String>
+}
+
+impl<S: LeaseStore> LeaderElector<S> {
+    pub fn new(config: Config, store: S, callbacks: Box<dyn Callbacks + Send>)
+        -> Result<Self, Error>
+    {
+        config.validate()?;
+        Ok(Self {
+            config,
+            store,
+            callbacks,
+            observed: None,
+            observed_time: None,
+            leading: false,
+            last_renew: None,
+            reported_leader: None
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The holder of the lease as last observed, if any.
+    pub fn leader(&self) -> Option<&str> {
+        self.observed.as_ref()
+            .and_then(|lease| lease.spec.holder_identity.as_deref())
+            .filter(|holder| !holder.is_empty())
+    }
+
+    /// Whether the last observed holder of the lease is this candidate. Unlike [`is_leading`][Self::is_leading], this does not say whether the lease is still held.
+    pub fn is_leader(&self) -> bool {
+        self.leader() == Some(self.config.identity.as_str())
+    }
+
+    /// Whether this candidate is leading, between the calls to [`on_started_leading`][Callbacks::on_started_leading] and [`on_stopped_leading`][Callbacks::on_stopped_leading].
+    pub fn is_leading(&self) -> bool {
+        self.leading
+    }
+
+    /**
+    Tries once to acquire the lease, or to renew it if this candidate holds it, and returns whether it now holds it.
+
+    The lease is taken over from another holder only once it has not changed for its [`lease_duration_seconds`][Spec::lease_duration_seconds] by the local clock, `now`. Losing a race with another candidate is not an error.
+    */
+    pub async fn try_acquire_or_renew(&mut self, now: Instant) -> Result<bool, Error> {
+        let time = MicroTime::now();
+        let mut spec = Spec {
+            holder_identity: Some(self.config.identity.clone()),
+            lease_duration_seconds: Some(
+                self.config.lease_duration.as_secs().try_into().unwrap_or(i32::MAX)),
+            acquire_time: Some(time),
+            renew_time: Some(time),
+            lease_transitions: Some(0)
+        };
+        let Some(current) = self.store.get(&self.config.namespace, &self.config.name).await? else {
+            let lease = Lease {
+                metadata: Some(Metadata {
+                    name: Some(self.config.name.clone()),
+                    namespace: Some(self.config.namespace.clone()),
+                    ..Metadata::default()
+                }),
+                spec
+            };
+            return match self.store.create(lease).await {
+                Ok(lease) => {
+                    self.observe(lease, now);
+                    Ok(true)
+                }
+                Err(Error::AlreadyExists(_)) => Ok(false),
+                Err(error) => Err(error)
+            }
+        };
+        self.observe(current.clone(), now);
+        let holder = current.spec.holder_identity.as_deref().unwrap_or_default();
+        if !holder.is_empty() && holder != self.config.identity
+            && !self.is_expired(now)
+        {
+            return Ok(false)
+        }
+        if holder == self.config.identity {
+            spec.acquire_time = current.spec.acquire_time;
+            spec.lease_transitions = current.spec.lease_transitions;
+        } else {
+            spec.lease_transitions = Some(
+                current.spec.lease_transitions.unwrap_or_default() + 1);
+        }
+        match self.store.update(Lease { spec, ..current }).await {
+            Ok(lease) => {
+                self.observe(lease, now);
+                Ok(true)
+            }
+            Err(Error::Conflict(_)) => Ok(false),
+            Err(error) => Err(error)
+        }
+    }
+
+    /// Whether the observed lease has not changed for its duration.
+    fn is_expired(&self, now: Instant) -> bool {
+        let duration = self.observed.as_ref()
+            .and_then(|lease| lease.spec.lease_duration_seconds)
+            .map_or(self.config.lease_duration,
+                |seconds| Duration::from_secs(seconds.max(0) as u64));
+        self.observed_time
+            .is_none_or(|observed_time| observed_time + duration <= now)
+    }
+
+    fn observe(&mut self, lease: Lease, now: Instant) {
+        let changed = self.observed.as_ref()
+            .is_none_or(|observed| observed.spec != lease.spec);
+        if changed {
+            self.observed_time = Some(now);
+        }
+        self.observed = Some(lease);
+    }
+
+    /**
+    Gives up the lease if this candidate holds it, so the next candidate can take it over at once rather than after the lease expires: the holder is cleared and the duration set to one second.
+
+    Returns whether the lease is no longer held by this candidate. Does not call [`on_stopped_leading`][Callbacks::on_stopped_leading].
+    */
+    pub async fn release(&mut self, now: Instant) -> Result<bool, Error> {
+        if !self.is_leader() {
+            return Ok(true)
+        }
+        let Some(current) = self.observed.clone() else {
+            return Ok(true)
+        };
+        let time = MicroTime::now();
+        let spec = Spec {
+            holder_identity: None,
+            lease_duration_seconds: Some(1),
+            acquire_time: Some(time),
+            renew_time: Some(time),
+            lease_transitions: current.spec.lease_transitions
+        };
+        match self.store.update(Lease { spec, ..current }).await {
+            Ok(lease) => {
+                self.observe(lease, now);
+                self.last_renew = None;
+                Ok(true)
+            }
+            Err(Error::Conflict(_)) => Ok(false),
+            Err(error) => Err(error)
+        }
+    }
+
+    /**
+    Runs one round of the election at `now` and returns whether this candidate is leading afterwards, calling the [`Callbacks`] on transitions.
+
+    A candidate that is not leading starts leading once it acquires the lease. A leader stops once it has failed to renew for [`renew_deadline`][Config::renew_deadline]; an error of the store counts as a failure to renew and is returned after the state is updated. Unlike client-go, a candidate that stopped leading keeps competing.
+    */
+    pub async fn tick(&mut self, now: Instant) -> Result<bool, Error> {
+        let result = self.try_acquire_or_renew(now).await;
+        if let Ok(true) = result {
+            self.last_renew = Some(now);
+            if !self.leading {
+                self.leading = true;
+                self.callbacks.on_started_leading();
+            }
+        } else if self.leading && self.last_renew.is_none_or(|last_renew|
+            now.duration_since(last_renew) >= self.config.renew_deadline)
+        {
+            self.leading = false;
+            self.callbacks.on_stopped_leading();
+        }
+        self.report_leader();
+        result.map(|_| self.leading)
+    }
+
+    fn report_leader(&mut self) {
+        let Some(leader) = self.leader().map(str::to_owned) else {
+            return
+        };
+        if self.reported_leader.as_deref() == Some(leader.as_str()) {
+            return
+        }
+        self.callbacks.on_new_leader(&leader);
+        self.reported_leader = Some(leader);
+    }
+
+    /**
+    Calls [`tick`][Self::tick] every [`retry_period`][Config::retry_period], jittered while not leading, until `shutdown` completes.
+
+    On shutdown, a leader releases the lease if [`release_on_cancel`][Config::release_on_cancel] is set and [`on_stopped_leading`][Callbacks::on_stopped_leading] is called.
+    */
+    pub async fn run(&mut self, shutdown: impl Future<Output = ()>) {
+        tokio::pin!(shutdown);
+        loop {
+            let leading = self.tick(Instant::now()).await.unwrap_or(self.leading);
+            let period = if leading {
+                self.config.retry_period
+            } else {
+                jitter(self.config.retry_period)
+            };
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = tokio::time::sleep(period) => {}
+            }
+        }
+        if self.leading {
+            if self.config.release_on_cancel {
+                let _ = self.release(Instant::now()).await;
+            }
+            self.leading = false;
+            self.callbacks.on_stopped_leading();
+        }
+    }
+}
+
+/// `period` plus a random fraction of up to [`JITTER_FACTOR`] times it.
+fn jitter(period: Duration) -> Duration {
+    let mut bytes = [0u8; 4];
+    if getrandom::fill(&mut bytes).is_err() {
+        return period
+    }
+    let fraction = f64::from(u32::from_le_bytes(bytes)) / f64::from(u32::MAX);
+    period + period.mul_f64(JITTER_FACTOR * fraction)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    InvalidConfig(String),
+    /// The lease does not exist.
+    NotFound(String),
+    /// The lease was created by someone else first.
+    AlreadyExists(String),
+    /// The lease was written by someone else since it was read.
+    Conflict(String),
+    /// The store failed or holds something that is not a lease.
+    Store(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidConfig(message) =>
+                write!(f, "invalid leader election config: {}", message),
+            Self::NotFound(lease) => write!(f, "lease {} not found", lease),
+            Self::AlreadyExists(lease) =>
+                write!(f, "lease {} already exists", lease),
+            Self::Conflict(lease) => write!(f,
+                "operation cannot be fulfilled on lease {}: the object has been modified",
+                lease),
+            Self::Store(message) => write!(f, "{}", message)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::api_server::fake::FakeApiServer;
+    use super::*;
+
+    /// Records the transitions of a candidate.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Recorder {
+        fn events(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl Callbacks for Recorder {
+        fn on_started_leading(&mut self) {
+            self.0.lock().unwrap().push("started".to_owned());
+        }
+
+        fn on_stopped_leading(&mut self) {
+            self.0.lock().unwrap().push("stopped".to_owned());
+        }
+
+        fn on_new_leader(&mut self, identity: &str) {
+            self.0.lock().unwrap().push(format!("leader {}", identity));
+        }
+    }
+
+    fn candidate<S: LeaseStore>(store: S, identity: &str)
+        -> (LeaderElector<S>, Recorder)
+    {
+        let recorder = Recorder::default();
+        let config = Config::new("kube-system", "kube-scheduler", identity);
+        let elector = LeaderElector::new(config, store, Box::new(recorder.clone())).unwrap();
+        (elector, recorder)
+    }
+
+    async fn elect<S: LeaseStore>(store: S, other: S) {
+        let (mut a, a_events) = candidate(store, "a");
+        let (mut b, b_events) = candidate(other, "b");
+        let start = Instant::now();
+        assert_eq!(a.tick(start).await, Ok(true));
+        assert_eq!(b.tick(start).await, Ok(false));
+        assert_eq!(b.leader(), Some("a"));
+
+        // Renewed, then not for longer than the lease duration.
+        let renewed = start + Duration::from_secs(2);
+        assert_eq!(a.tick(renewed).await, Ok(true));
+        assert_eq!(b.tick(renewed).await, Ok(false));
+        assert_eq!(b.tick(renewed + Duration::from_secs(14)).await, Ok(false));
+        assert_eq!(b.tick(renewed + Duration::from_secs(15)).await, Ok(true));
+
+        // The old leader gives up, having failed to renew for longer than the deadline.
+        let later = renewed + Duration::from_secs(16);
+        assert_eq!(a.tick(later).await, Ok(false));
+        assert_eq!(a_events.events(), ["started", "leader a", "stopped", "leader b"]);
+        assert_eq!(b_events.events(), ["leader a", "started", "leader b"]);
+
+        // Released, the lease is taken over at once.
+        assert_eq!(b.release(later).await, Ok(true));
+        assert_eq!(a.tick(later + Duration::from_secs(1)).await, Ok(true));
+        assert_eq!(a.leader(), Some("a"));
+    }
+
+    #[tokio::test]
+    async fn elects_in_memory() {
+        let store = MemoryStore::new();
+        elect(&store, &store).await;
+    }
+
+    #[tokio::test]
+    async fn elects_through_the_api_server() {
+        let server = FakeApiServer::start().await.unwrap();
+        elect(server.client(), server.client()).await;
+        let lease = server.client().get("kube-system", "kube-scheduler").await.unwrap().unwrap();
+        assert_eq!(lease.spec.holder_identity.as_deref(), Some("a"));
+        assert_eq!(lease.spec.lease_transitions, Some(2));
+    }
+
+    #[tokio::test]
+    async fn runs_until_shutdown() {
+        let store = MemoryStore::new();
+        let (mut elector, events) = candidate(&store, "a");
+        elector.config.release_on_cancel = true;
+        elector.run(async {}).await;
+        assert_eq!(events.events(), ["started", "leader a", "stopped"]);
+        let lease = store.get("kube-system", "kube-scheduler").await.unwrap().unwrap();
+        assert_eq!(lease.spec.holder_identity, None);
+    }
+}
@@ -0,0 +1,212 @@
+/*!
+A [`LeaseStore`] writing leases to etcd directly, under the keys the API server uses, for components that elect before an API server is reachable.
+
+The etcd client itself is left to the caller behind [`KeyValue`], two operations any client provides, for example with `etcd-client`:
+
+```ignore
+let txn = Txn::new()
+    .when([Compare::mod_revision(key, CompareOp::Equal, revision)])
+    .and_then([TxnOp::put(key, value, None)]);
+```
+
+- Source <https://github.com/kubernetes/kubernetes/blob/master/staging/src/k8s.io/apiserver/pkg/storage/etcd3/store.go>
+*/
+
+use crate::{
+    client::{BoxFuture, Object},
+    coordination::lease::Lease,
+    meta::Metadata
+};
+use super::{Error, LeaseStore};
+
+/// Key prefix of leases in the API server's etcd, followed by `<namespace>/<name>`.
+pub const PREFIX: &str = "/registry/leases/";
+
+/// A value and its `mod_revision`.
+pub type Entry = (Vec<u8>, i64);
+
+/// The etcd operations [`EtcdStore`] needs.
+pub trait KeyValue: Send + Sync {
+    /// The value of `key`.
+    fn get<'a>(&'a self, key: &'a str)
+        -> BoxFuture<'a, Result<Option<Entry>, Error>>;
+    /// Puts `value` if the `mod_revision` of `key` is `revision`, zero meaning that `key` must not exist, in a single transaction. Returns the revision of the put, or `None` if the comparison failed.
+    fn put_if<'a>(&'a self, key: &'a str, revision: i64, value: Vec<u8>)
+        -> BoxFuture<'a, Result<Option<i64>, Error>>;
+}
+
+/// Leases stored as JSON, their [`resource_version`][Metadata::resource_version] the etcd `mod_revision` of their key as with the API server.
+pub struct EtcdStore<C> {
+    client: C,
+    prefix: String
+}
+
+impl<C: KeyValue> EtcdStore<C> {
+    pub fn new(client: C) -> Self {
+        Self::with_prefix(client, PREFIX)
+    }
+
+    /// A store keeping leases under `prefix`, as `--etcd-prefix` does for the API server.
+    pub fn with_prefix(client: C, prefix: &str) -> Self {
+        Self { client, prefix: prefix.to_owned() }
+    }
+
+    fn key(&self, namespace: &str, name: &str) -> String {
+        format!("{}{}/{}", self.prefix, namespace, name)
+    }
+
+    /// Puts `lease` if its key is at `revision`, and returns it with the revision of the put as its resource version.
+    async fn put(&self, mut lease: Lease, revision: i64) -> Result<Option<Lease>, Error> {
+        let metadata = lease.metadata.get_or_insert_with(Metadata::default);
+        let Some(name) = metadata.name.clone() else {
+            return Err(Error::Store("lease has no name".to_owned()))
+        };
+        let namespace = metadata.namespace.get_or_insert_with(|| "default".to_owned())
+            .clone();
+        // The API server does not store the resource version, it is the revision of the key.
+        metadata.resource_version = None;
+        let key = self.key(&namespace, &name);
+        let value = lease.to_json().to_string().into_bytes();
+        Ok(self.client.put_if(&key, revision, value).await?
+            .map(|revision| {
+                let metadata = lease.metadata.get_or_insert_with(Metadata::default);
+                metadata.resource_version = Some(revision.to_string());
+                lease
+            }))
+    }
+}
+
+impl<C: KeyValue> LeaseStore for EtcdStore<C> {
+    fn get<'a>(&'a self, namespace: &'a str, name: &'a str)
+        -> BoxFuture<'a, Result<Option<Lease>, Error>>
+    {
+        Box::pin(async move {
+            let key = self.key(namespace, name);
+            let Some((value, revision)) = self.client.get(&key).await? else {
+                return Ok(None)
+            };
+            let value = serde_json::from_slice(&value)
+                .map_err(|error| Error::Store(format!("error decoding lease: {}", error)))?;
+            let mut lease = Lease::from_json(value).map_err(Error::Store)?;
+            let metadata = lease.metadata.get_or_insert_with(Metadata::default);
+            metadata.resource_version = Some(revision.to_string());
+            Ok(Some(lease))
+        })
+    }
+
+    fn create(&self, lease: Lease) -> BoxFuture<'_, Result<Lease, Error>> {
+        Box::pin(async move {
+            let name = lease_name(&lease);
+            self.put(lease, 0).await?
+                .ok_or(Error::AlreadyExists(name))
+        })
+    }
+
+    fn update(&self, lease: Lease) -> BoxFuture<'_, Result<Lease, Error>> {
+        Box::pin(async move {
+            let name = lease_name(&lease);
+            let revision = lease.metadata.as_ref()
+                .and_then(|metadata| metadata.resource_version.as_deref())
+                .and_then(|version| version.parse::<i64>().ok())
+                .filter(|revision| *revision > 0)
+                .ok_or_else(|| Error::Conflict(name.clone()))?;
+            self.put(lease, revision).await?
+                .ok_or(Error::Conflict(name))
+        })
+    }
+}
+
+/// `<namespace>/<name>` of a lease, for errors.
+fn lease_name(lease: &Lease) -> String {
+    let metadata = lease.metadata.as_ref();
+    format!("{}/{}",
+        metadata.and_then(|metadata| metadata.namespace.as_deref())
+            .unwrap_or("default"),
+        metadata.and_then(|metadata| metadata.name.as_deref())
+            .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::Mutex
+    };
+
+    use crate::coordination::lease::Spec;
+    use super::*;
+
+    /// Keys in memory with their `mod_revision`, as etcd keeps them.
+    #[derive(Default)]
+    struct FakeEtcd {
+        /// The current revision, and the keys.
+        keys: Mutex<(i64, HashMap<String, Entry>)>
+    }
+
+    impl KeyValue for FakeEtcd {
+        fn get<'a>(&'a self, key: &'a str)
+            -> BoxFuture<'a, Result<Option<Entry>, Error>>
+        {
+            let value = self.keys.lock().unwrap().1.get(key).cloned();
+            Box::pin(async move { Ok(value) })
+        }
+
+        fn put_if<'a>(&'a self, key: &'a str, revision: i64, value: Vec<u8>)
+            -> BoxFuture<'a, Result<Option<i64>, Error>>
+        {
+            let mut keys = self.keys.lock().unwrap();
+            let (current, keys) = &mut *keys;
+            let result = if keys.get(key).map_or(0, |(_, revision)| *revision) == revision {
+                *current += 1;
+                keys.insert(key.to_owned(), (value, *current));
+                Some(*current)
+            } else {
+                None
+            };
+            Box::pin(async move { Ok(result) })
+        }
+    }
+
+    fn lease(holder: &str) -> Lease {
+        Lease {
+            metadata: Some(Metadata {
+                name: Some("kube-scheduler".to_owned()),
+                namespace: Some("kube-system".to_owned()),
+                ..Metadata::default()
+            }),
+            spec: Spec {
+                holder_identity: Some(holder.to_owned()),
+                lease_duration_seconds: Some(15),
+                ..Spec::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn compares_and_swaps() {
+        let store = EtcdStore::new(FakeEtcd::default());
+        let created = store.create(lease("a")).await.unwrap();
+        assert_eq!(created.metadata.as_ref().unwrap().resource_version.as_deref(), Some("1"));
+        assert_eq!(store.create(lease("b")).await.unwrap_err(),
+            Error::AlreadyExists("kube-system/kube-scheduler".to_owned()));
+
+        let stored = store.get("kube-system", "kube-scheduler").await.unwrap().unwrap();
+        assert_eq!(stored.spec, created.spec);
+        assert_eq!(stored.metadata.as_ref().unwrap().resource_version.as_deref(), Some("1"));
+        let (value, _) = store.client.get("/registry/leases/kube-system/kube-scheduler").await
+            .unwrap().unwrap();
+        assert!(!String::from_utf8(value).unwrap().contains("resourceVersion"));
+
+        // The first writer of revision 1 wins, the other is stale.
+        let updated = store.update(Lease { spec: lease("b").spec, ..stored.clone() }).await
+            .unwrap();
+        assert_eq!(updated.metadata.as_ref().unwrap().resource_version.as_deref(), Some("2"));
+        assert_eq!(store.update(Lease { spec: lease("c").spec, ..stored }).await.unwrap_err(),
+            Error::Conflict("kube-system/kube-scheduler".to_owned()));
+        assert_eq!(store.update(lease("c")).await.unwrap_err(),
+            Error::Conflict("kube-system/kube-scheduler".to_owned()));
+        let stored = store.get("kube-system", "kube-scheduler").await.unwrap().unwrap();
+        assert_eq!(stored.spec.holder_identity.as_deref(), Some("b"));
+        assert!(store.get("kube-system", "other").await.unwrap().is_none());
+    }
+}
@@ -8,18 +8,25 @@ use crate::{
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/lease-v1/#Lease>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone, Default)]
 pub struct Lease {
-    metadata: Option<Metadata>,
-    spec: Spec
+    #[kfl(child, default)]
+    pub metadata: Option<Metadata>,
+    #[kfl(child, default)]
+    pub spec: Spec
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/lease-v1/#Spec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
 pub struct Spec {
-    acquire_time: Option<MicroTime>,
-    holder_identity: Option<String>,
-    lease_duration_seconds: Option<i32>,
-    lease_transitions: Option<i32>,
-    renew_time: Option<MicroTime>
+    #[kfl(property, default)]
+    pub acquire_time: Option<MicroTime>,
+    #[kfl(property, default)]
+    pub holder_identity: Option<String>,
+    #[kfl(property, default)]
+    pub lease_duration_seconds: Option<i32>,
+    #[kfl(property, default)]
+    pub lease_transitions: Option<i32>,
+    #[kfl(property, default)]
+    pub renew_time: Option<MicroTime>
 }
@@ -1,6 +1,7 @@
 pub mod binding;
 pub mod config_map;
 pub mod endpoints;
+pub mod environment;
 pub mod field_selector;
 pub mod limit_range;
 pub mod local_reference;
@@ -20,6 +21,7 @@ pub mod service;
 pub mod service_account;
 pub mod typed_local_reference;
 pub mod volume;
+pub mod volume_binding;
 
 // use kfl::Decode;
 
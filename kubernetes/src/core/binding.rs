@@ -1,15 +1,28 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/binding-v1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
     core::reference::Reference,
+    decode::Fields,
     meta::metadata::Metadata,
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/binding-v1/#Binding>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Binding {
-    metadata: Option<Metadata>,
-    target: Reference
+    pub metadata: Option<Metadata>,
+    pub target: Reference
+}
+
+impl Decode for Binding {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let binding = Self {
+            metadata: fields.child("metadata")?,
+            target: fields.child("target")?.ok_or_else(|| fields.missing("target"))?
+        };
+        fields.finish()?;
+        Ok(binding)
+    }
 }
@@ -3,20 +3,34 @@
 //! - Reference <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/config-map-v1/>
 
 use std::collections::HashMap;
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
-use crate::meta::metadata::Metadata;
+use crate::{decode::Fields, meta::metadata::Metadata};
 
 /// ConfigMap holds configuration data for pods to consume.
 /// 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/config-map-v1/#ConfigMap>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct ConfigMap {
-    metadata: Metadata,
+    pub metadata: Metadata,
     /// BinaryData contains the binary data. Each key must consist of alphanumeric characters, '-', '_' or '.'. BinaryData can contain byte sequences that are not in the UTF-8 range. The keys stored in BinaryData must not overlap with the ones in the Data field, this is enforced during validation process. Using this field will require 1.10+ apiserver and kubelet.
-    binary_data: HashMap<String, Vec<u8>>,
+    pub binary_data: HashMap<String, Vec<u8>>,
     /// Data contains the configuration data. Each key must consist of alphanumeric characters, '-', '_' or '.'. Values with non-UTF-8 byte sequences must use the BinaryData field. The keys stored in Data must not overlap with the keys in the BinaryData field, this is enforced during validation process.
-    data: HashMap<String, String>,
+    pub data: HashMap<String, String>,
     /// Immutable, if set to true, ensures that data stored in the ConfigMap cannot be updated (only object metadata can be modified). If not set to true, the field can be modified at any time. Defaulted to nil.
-    immutable: Option<bool>
+    pub immutable: Option<bool>
+}
+
+impl Decode for ConfigMap {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let config_map = Self {
+            metadata: fields.child("metadata")?.unwrap_or_default(),
+            binary_data: fields.entries("binary-data")?,
+            data: fields.entries("data")?,
+            immutable: fields.property("immutable")?
+        };
+        fields.finish()?;
+        Ok(config_map)
+    }
 }
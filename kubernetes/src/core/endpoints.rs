@@ -2,10 +2,11 @@
 Reference <https://kubernetes.io/docs/reference/kubernetes-api/service-resources/endpoints-v1/>
 */
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
     core::reference::Reference,
+    decode::Fields,
     meta::metadata::Metadata,
     protocol::Protocol,
 };
@@ -13,29 +14,62 @@ use crate::{
 /// <https://kubernetes.io/docs/reference/kubernetes-api/service-resources/endpoints-v1/#Endpoints>
 #[derive(Debug, Decode)]
 pub struct Endpoints {
-    metadata: Metadata,
-    subsets: Vec<EndpointSubset>
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(children)]
+    pub subsets: Vec<EndpointSubset>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct EndpointSubset {
-    addresses: Vec<EndpointAddress>,
-    not_ready_addresses: Vec<EndpointAddress>,
-    ports: Vec<EndpointPort>,
+    pub addresses: Vec<EndpointAddress>,
+    pub not_ready_addresses: Vec<EndpointAddress>,
+    pub ports: Vec<EndpointPort>,
 }
 
-#[derive(Debug, Decode)]
+impl Decode for EndpointSubset {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let endpoint_subset = Self {
+            addresses: fields.children("addresse")?,
+            not_ready_addresses: fields.children("not-ready-addresse")?,
+            ports: fields.children("endpoint-port")?
+        };
+        fields.finish()?;
+        Ok(endpoint_subset)
+    }
+}
+
+#[derive(Debug)]
 pub struct EndpointAddress {
-    ip: String,
-    hostname: Option<String>,
-    node_name: Option<String>,
-    target_ref: Option<Reference>
+    pub ip: String,
+    pub hostname: Option<String>,
+    pub node_name: Option<String>,
+    pub target_ref: Option<Reference>
+}
+
+impl Decode for EndpointAddress {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let endpoint_address = Self {
+            ip: fields.property("ip")?.unwrap_or_default(),
+            hostname: fields.property("hostname")?,
+            node_name: fields.property("node-name")?,
+            target_ref: fields.child("target-ref")?
+        };
+        fields.finish()?;
+        Ok(endpoint_address)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct EndpointPort {
-    port: i32,
-    protocol: Option<Protocol>,
-    name: Option<String>,
-    app_protocol: Option<String>
+    #[kfl(property, default)]
+    pub port: i32,
+    #[kfl(property, default)]
+    pub protocol: Option<Protocol>,
+    #[kfl(property, default)]
+    pub name: Option<String>,
+    #[kfl(property, default)]
+    pub app_protocol: Option<String>
 }
@@ -0,0 +1,457 @@
+/*!
+The environment of a container as the kubelet resolves it, evaluated offline: variables from `env_from` config maps and secrets, then from `env`, with values, config map and secret keys and the downward API, and `$(VAR)` references expanded in values, `command` and `args`.
+
+`$(VAR)` is replaced with the value of `VAR` if it is defined, among the variables defined before in values and among all of them in `command` and `args`, and left as it is otherwise; `$$` escapes `$`, so that `$$(VAR)` is `$(VAR)`. Variables of service links are not included.
+
+- Tasks <https://kubernetes.io/docs/tasks/inject-data-application/define-interdependent-environment-variables/>
+- Tasks <https://kubernetes.io/docs/tasks/inject-data-application/environment-variable-expose-pod-information/>
+- Tasks <https://kubernetes.io/docs/tasks/configure-pod-container/configure-pod-configmap/#configure-all-key-value-pairs-in-a-configmap-as-container-environment-variables>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/kubelet/kubelet_pods.go>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/third_party/forked/golang/expansion/expand.go>
+*/
+
+use std::{
+    collections::HashMap,
+    fmt
+};
+
+use crate::{
+    core::{
+        pod::{Container, EnvVar, Pod},
+        secret::Secret,
+        ConfigMap, FieldSelector, ResourceFieldSelector
+    },
+    quantity::{self, Amount, Amounts}
+};
+
+/// The objects the environment of a container refers to.
+#[derive(Debug, Default)]
+pub struct Sources<'a> {
+    /// The config maps in the namespace of the pod.
+    pub config_maps: &'a [ConfigMap],
+    /// The secrets in the namespace of the pod.
+    pub secrets: &'a [Secret],
+    /// The allocatable resources of the node of the pod, which resource limits not set default to.
+    pub node_allocatable: Amounts
+}
+
+/// The resolved environment of a container.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Environment {
+    /// The variables by name, in the order they are first defined; definitions after replace the values.
+    pub env: Vec<(String, String)>,
+    pub command: Vec<String>,
+    pub args: Vec<String>,
+    /// Keys of `env_from` sources skipped as not valid variable names, prefixed.
+    pub invalid_keys: Vec<String>
+}
+
+impl Environment {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.env.iter().find(|(own, _)| own == name).map(|(_, value)| value.as_str())
+    }
+
+    fn set(&mut self, name: &str, value: String) {
+        match self.env.iter_mut().find(|(own, _)| own == name) {
+            Some((_, own)) => *own = value,
+            None => self.env.push((name.to_owned(), value))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    ConfigMapNotFound(String),
+    SecretNotFound(String),
+    /// A config map or secret, by kind and name, without the key.
+    KeyNotFound { kind: &'static str, name: String, key: String },
+    UnsupportedFieldPath(String),
+    UnsupportedResource(String),
+    ContainerNotFound(String),
+    Quantity(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ConfigMapNotFound(name) => write!(f, "configmap \"{}\" not found", name),
+            Self::SecretNotFound(name) => write!(f, "secret \"{}\" not found", name),
+            Self::KeyNotFound { kind, name, key } =>
+                write!(f, "couldn't find key {} in {} {}", key, kind, name),
+            Self::UnsupportedFieldPath(path) => write!(f, "unsupported fieldPath: {}", path),
+            Self::UnsupportedResource(resource) =>
+                write!(f, "unsupported container resource: {}", resource),
+            Self::ContainerNotFound(name) => write!(f, "container {} not found", name),
+            Self::Quantity(error) => f.write_str(error)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<quantity::Error> for Error {
+    fn from(error: quantity::Error) -> Self {
+        Self::Quantity(error.to_string())
+    }
+}
+
+/**
+Resolves the environment of `container` of `pod`, as the kubelet does before starting it.
+
+`env_from` sources come first, in order, then `env`, in order, each replacing the variables of the same name defined before. References to config maps, secrets or their keys that do not exist are errors unless optional, and are then skipped.
+*/
+pub fn resolve(pod: &Pod, container: &Container, sources: &Sources<'_>)
+    -> Result<Environment, Error>
+{
+    let namespace = pod.metadata.namespace.as_deref().unwrap_or("default");
+    let mut environment = Environment::default();
+    for source in &container.env_from {
+        let prefix = source.prefix.as_deref().unwrap_or_default();
+        let mut values: Vec<(String, String)> = Vec::new();
+        if let Some(reference) = &source.config_map_ref {
+            let name = reference.name.as_deref().unwrap_or_default();
+            match sources.config_maps.iter().find(|config_map| config_map.metadata.name.as_deref() == Some(name)) {
+                Some(config_map) => values.extend(config_map.data.iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))),
+                None if reference.optional == Some(true) => {}
+                None => return Err(Error::ConfigMapNotFound(name.to_owned()))
+            }
+        }
+        if let Some(reference) = &source.secret_ref {
+            let name = reference.name.as_deref().unwrap_or_default();
+            match find_secret(sources, name) {
+                Some(secret) => values.extend(secret_data(secret)),
+                None if reference.optional == Some(true) => {}
+                None => return Err(Error::SecretNotFound(name.to_owned()))
+            }
+        }
+        values.sort();
+        for (key, value) in values {
+            let name = format!("{}{}", prefix, key);
+            if is_env_var_name(&name) {
+                environment.set(&name, value);
+            } else {
+                environment.invalid_keys.push(name);
+            }
+        }
+    }
+    for variable in &container.env {
+        if let Some(value) = resolve_var(pod, container, variable, namespace, sources, &environment)? {
+            environment.set(&variable.name, value);
+        }
+    }
+    let lookup = |name: &str| environment.get(name).map(str::to_owned);
+    let command = container.command.iter().map(|part| expand(part, lookup)).collect();
+    let args = container.args.iter().map(|part| expand(part, lookup)).collect();
+    Ok(Environment { command, args, ..environment })
+}
+
+/// The value of `variable`, none if it refers to an optional key that does not exist.
+fn resolve_var(pod: &Pod, container: &Container, variable: &EnvVar, namespace: &str,
+    sources: &Sources<'_>, environment: &Environment) -> Result<Option<String>, Error>
+{
+    let value = variable.value.as_deref().unwrap_or_default();
+    let Some(source) = variable.value_from.as_ref().filter(|_| value.is_empty()) else {
+        return Ok(Some(expand(value, |name| environment.get(name).map(str::to_owned))))
+    };
+    if let Some(selector) = &source.field_ref {
+        return field_value(pod, selector).map(Some)
+    }
+    if let Some(selector) = &source.resource_field_ref {
+        return resource_value(pod, container, selector, &sources.node_allocatable).map(Some)
+    }
+    if let Some(selector) = &source.config_map_key_ref {
+        let name = selector.name.as_deref().unwrap_or_default();
+        let optional = selector.optional == Some(true);
+        let Some(config_map) = sources.config_maps.iter()
+            .find(|config_map| config_map.metadata.name.as_deref() == Some(name))
+        else {
+            return if optional { Ok(None) } else { Err(Error::ConfigMapNotFound(name.to_owned())) }
+        };
+        return match config_map.data.get(&selector.key) {
+            Some(value) => Ok(Some(value.clone())),
+            None if optional => Ok(None),
+            None => Err(Error::KeyNotFound {
+                kind: "ConfigMap",
+                name: format!("{}/{}", namespace, name),
+                key: selector.key.clone()
+            })
+        }
+    }
+    if let Some(selector) = &source.secret_key_ref {
+        let name = selector.name.as_deref().unwrap_or_default();
+        let optional = selector.optional == Some(true);
+        let Some(secret) = find_secret(sources, name) else {
+            return if optional { Ok(None) } else { Err(Error::SecretNotFound(name.to_owned())) }
+        };
+        return match secret.get(&selector.key) {
+            Some(value) => Ok(Some(String::from_utf8_lossy(value).into_owned())),
+            None if optional => Ok(None),
+            None => Err(Error::KeyNotFound {
+                kind: "Secret",
+                name: format!("{}/{}", namespace, name),
+                key: selector.key.clone()
+            })
+        }
+    }
+    Ok(Some(String::new()))
+}
+
+fn find_secret<'a>(sources: &Sources<'a>, name: &str) -> Option<&'a Secret> {
+    sources.secrets.iter().find(|secret| secret.metadata.name.as_deref() == Some(name))
+}
+
+/// The values of `secret` by key, `string_data` over `data` as the API server merges them.
+fn secret_data(secret: &Secret) -> Vec<(String, String)> {
+    let mut values: HashMap<&str, String> = secret.data.iter().flatten()
+        .map(|(key, value)| (key.as_str(), String::from_utf8_lossy(value).into_owned()))
+        .collect();
+    values.extend(secret.string_data.iter().map(|(key, value)| (key.as_str(), value.clone())));
+    values.into_iter().map(|(key, value)| (key.to_owned(), value)).collect()
+}
+
+/**
+The value of the field of `pod` `selector` selects, for the downward API: `metadata.name`, `metadata.namespace`, `metadata.uid`, `metadata.labels['<key>']`, `metadata.annotations['<key>']`, `spec.nodeName`, `spec.serviceAccountName`, `status.hostIP`, `status.podIP` or `status.podIPs`.
+*/
+pub fn field_value(pod: &Pod, selector: &FieldSelector) -> Result<String, Error> {
+    let path = selector.field_path.to_string_lossy();
+    let subscript = |prefix: &str| path.strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix("['"))
+        .and_then(|rest| rest.strip_suffix("']"))
+        .map(str::to_owned);
+    let status = pod.status.as_ref();
+    let value = match path.as_ref() {
+        "metadata.name" => pod.metadata.name.clone(),
+        "metadata.namespace" => pod.metadata.namespace.clone(),
+        "metadata.uid" => pod.metadata.uid.clone(),
+        "spec.nodeName" => pod.spec.node_name.clone(),
+        "spec.serviceAccountName" => pod.spec.service_account_name.clone(),
+        "status.hostIP" => status.map(|status| status.host_ip.clone()).filter(|ip| !ip.is_empty()),
+        "status.podIP" => status.map(|status| status.pod_ip.clone()).filter(|ip| !ip.is_empty()),
+        "status.podIPs" => status.map(|status| status.pod_ips.iter()
+            .map(|ip| ip.ip.as_str()).collect::<Vec<_>>().join(",")),
+        _ => if let Some(key) = subscript("metadata.labels") {
+            pod.metadata.labels.get(&key).cloned()
+        } else if let Some(key) = subscript("metadata.annotations") {
+            pod.metadata.annotations.get(&key).cloned()
+        } else {
+            return Err(Error::UnsupportedFieldPath(path.into_owned()))
+        }
+    };
+    Ok(value.unwrap_or_default())
+}
+
+/**
+The value of the resource of the container `selector` selects, `container` unless it names another, for the downward API: `limits.` or `requests.` then `cpu`, `memory`, `ephemeral-storage` or `hugepages-<size>`, divided by the divisor and rounded up.
+
+Limits not set default to `node_allocatable`.
+*/
+pub fn resource_value(pod: &Pod, container: &Container, selector: &ResourceFieldSelector,
+    node_allocatable: &Amounts) -> Result<String, Error>
+{
+    let container = match selector.container_name.as_deref().filter(|name| !name.is_empty()) {
+        Some(name) => pod.spec.containers.iter().chain(&pod.spec.init_containers)
+            .find(|container| container.name == name)
+            .ok_or_else(|| Error::ContainerNotFound(name.to_owned()))?,
+        None => container
+    };
+    let unsupported = || Error::UnsupportedResource(selector.resource.clone());
+    let (kind, resource) = selector.resource.split_once('.').ok_or_else(unsupported)?;
+    if !matches!(resource, "cpu" | "memory" | "ephemeral-storage") && !resource.starts_with("hugepages-") {
+        return Err(unsupported())
+    }
+    let mut amounts = match kind {
+        "limits" => container.limits()?,
+        "requests" => container.requests()?,
+        _ => return Err(unsupported())
+    };
+    if kind == "limits" {
+        for (name, amount) in node_allocatable {
+            amounts.entry(name.clone()).or_insert(*amount);
+        }
+    }
+    let amount = amounts.get(resource).copied().unwrap_or_default();
+    let divisor: Amount = match selector.divisor.as_deref().filter(|divisor| !divisor.is_empty()) {
+        Some(divisor) => divisor.parse()?,
+        None => Amount::from_value(1, quantity::Format::Decimal)
+    };
+    if divisor.is_zero() {
+        return Err(Error::Quantity(format!("divisor of {} is zero", selector.resource)))
+    }
+    // CPU is divided in milli units, the others in units, rounding the divisor up.
+    let (value, divisor) = if resource == "cpu" {
+        (amount.milli_value(), divisor.milli_value())
+    } else {
+        (amount.value(), divisor.value())
+    };
+    Ok(div_ceil(value, divisor).to_string())
+}
+
+fn div_ceil(value: i128, divisor: i128) -> i128 {
+    let quotient = value / divisor;
+    if value % divisor > 0 { quotient + 1 } else { quotient }
+}
+
+/**
+Expands the `$(NAME)` references in `input` with `lookup`, leaving those it does not know as they are, and `$$` to `$`.
+
+A `$` followed by anything else, an unterminated `$(` included, is kept as it is.
+*/
+pub fn expand(input: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(position) = rest.find('$') {
+        output.push_str(&rest[..position]);
+        let after = &rest[position + 1..];
+        match after.chars().next() {
+            Some('$') => {
+                output.push('$');
+                rest = &after[1..];
+            }
+            Some('(') => match after.find(')') {
+                Some(end) => {
+                    let name = &after[1..end];
+                    match lookup(name) {
+                        Some(value) => output.push_str(&value),
+                        None => {
+                            output.push_str("$(");
+                            output.push_str(name);
+                            output.push(')');
+                        }
+                    }
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    output.push_str("$(");
+                    rest = &after[1..];
+                }
+            },
+            Some(next) => {
+                output.push('$');
+                output.push(next);
+                rest = &after[next.len_utf8()..];
+            }
+            None => {
+                output.push('$');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Whether `name` is a valid environment variable name: letters, digits, `-`, `_` and `.`, not starting with a digit.
+pub fn is_env_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|first| first.is_ascii_alphabetic() || matches!(first, '-' | '_' | '.'))
+        && chars.all(|char| char.is_ascii_alphanumeric() || matches!(char, '-' | '_' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decode::decode;
+    use super::*;
+
+    fn pod(env: &str) -> Pod {
+        decode("pod.kdl", &format!(r#"
+            pod {{
+                metadata "web" namespace="shop" {{
+                    labels app="web"
+                }}
+                spec {{
+                    container "app" {{
+                        command "run" "--greeting=$(GREETING)" "$$(GREETING)" "$(UNKNOWN)"
+                        resources {{
+                            limits memory="128Mi"
+                        }}
+                        {}
+                    }}
+                }}
+            }}
+        "#, env)).unwrap()
+    }
+
+    #[test]
+    fn resolves_and_expands_variables() {
+        let pod = pod(r#"
+            env-from-source prefix="CFG_" {
+                config-map-ref name="settings"
+            }
+            env-var name="GREETING" value="hello $(CFG_user)"
+            env-var name="NAME" {
+                value-from {
+                    field-ref field-path="metadata.name"
+                }
+            }
+            env-var name="APP" {
+                value-from {
+                    field-ref field-path="metadata.labels['app']"
+                }
+            }
+            env-var name="MEMORY" {
+                value-from {
+                    resource-field-ref resource="limits.memory" divisor="1Mi"
+                }
+            }
+            env-var name="CPU" {
+                value-from {
+                    resource-field-ref resource="limits.cpu" divisor="1m"
+                }
+            }
+        "#);
+        let config_maps: [ConfigMap; 1] = [decode("config_map.kdl", r#"
+            config-map {
+                metadata "settings"
+                data user="alice" "bad key"="x"
+            }
+        "#).unwrap()];
+        let sources = Sources {
+            config_maps: &config_maps,
+            node_allocatable: [("cpu".to_owned(), "2".parse().unwrap())].into(),
+            ..Sources::default()
+        };
+        let environment = resolve(&pod, &pod.spec.containers[0], &sources).unwrap();
+        assert_eq!(environment.get("CFG_user"), Some("alice"));
+        assert_eq!(environment.get("GREETING"), Some("hello alice"));
+        assert_eq!(environment.get("NAME"), Some("web"));
+        assert_eq!(environment.get("APP"), Some("web"));
+        assert_eq!(environment.get("MEMORY"), Some("128"));
+        assert_eq!(environment.get("CPU"), Some("2000"));
+        assert_eq!(environment.invalid_keys, ["CFG_bad key"]);
+        assert_eq!(environment.command,
+            ["run", "--greeting=hello alice", "$(GREETING)", "$(UNKNOWN)"]);
+    }
+
+    #[test]
+    fn skips_only_optional_missing_sources() {
+        let missing = pod(r#"
+            env-var name="PASSWORD" {
+                value-from {
+                    secret-key-ref name="credentials" key="password"
+                }
+            }
+        "#);
+        assert_eq!(resolve(&missing, &missing.spec.containers[0], &Sources::default()),
+            Err(Error::SecretNotFound("credentials".to_owned())));
+
+        let optional = pod(r#"
+            env-var name="PASSWORD" {
+                value-from {
+                    secret-key-ref name="credentials" key="password" optional=true
+                }
+            }
+        "#);
+        let environment = resolve(&optional, &optional.spec.containers[0], &Sources::default())
+            .unwrap();
+        assert_eq!(environment.get("PASSWORD"), None);
+    }
+
+    #[test]
+    fn expands_references() {
+        let lookup = |name: &str| (name == "A").then(|| "1".to_owned());
+        assert_eq!(expand("$(A)-$(B)-$$(A)-$A-$(A", lookup), "1-$(B)-$(A)-$A-$(A");
+        assert!(is_env_var_name("my.var-1"));
+        assert!(!is_env_var_name("1var"));
+    }
+}
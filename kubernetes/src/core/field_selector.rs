@@ -9,8 +9,8 @@ use kfl::Decode;
 pub struct FieldSelector {
     /// Path of the field to select in the specified API version.
     #[kfl(property)]
-    field_path: PathBuf,
+    pub field_path: PathBuf,
     /// Version of the schema the FieldPath is written in terms of, defaults to "v1".
     #[kfl(property, default = "v1".into())]
-    api_version: String
+    pub api_version: String
 }
@@ -1,44 +1,318 @@
 //! - Concepts <https://kubernetes.io/docs/concepts/policy/limit-range/>
 //! - Reference <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/limit-range-v1/>
+//! - Source <https://github.com/kubernetes/kubernetes/blob/master/plugin/pkg/admission/limitranger/admission.go>
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt
+};
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
 
 use crate::{
+    core::pod::{Container, Pod, Spec},
+    decode::Fields,
     meta::metadata::Metadata,
-    quantity::Quantity
+    quantity::{self, Amount, Amounts, Quantity}
 };
 
 /// LimitRange sets resource usage limits for each kind of resource in a Namespace.
 /// <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/limit-range-v1/#LimitRange>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct LimitRange {
-    metadata: Metadata,
-    spec: LimitRangeSpec,
+    pub metadata: Metadata,
+    pub spec: LimitRangeSpec,
+}
+
+impl Decode for LimitRange {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let limit_range = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            spec: fields.child("spec")?.ok_or_else(|| fields.missing("spec"))?
+        };
+        fields.finish()?;
+        Ok(limit_range)
+    }
+}
+
+impl LimitRange {
+    /**
+    Admits `pod` as the `LimitRanger` admission plugin does: the default limits and requests of the `Container` items are set on the containers that do not specify them, after requests default to the limits that are specified, then every container and the pod as a whole, by its [effective requests][Pod::requests] and limits, are checked against the `min`, `max` and `max_limit_request_ratio` of the `Container` and `Pod` items.
+
+    All constraints the pod violates are reported together.
+    */
+    pub fn admit(&self, pod: &mut Pod) -> Result<(), Error> {
+        for container in containers_mut(&mut pod.spec) {
+            let resources = container.resources.get_or_insert_with(Default::default);
+            for (name, limit) in &resources.limits {
+                resources.requests.entry(name.clone()).or_insert_with(|| limit.clone());
+            }
+        }
+        for item in self.items(LimitType::Container) {
+            let (limits, requests) = item.defaults();
+            for container in containers_mut(&mut pod.spec) {
+                let resources = container.resources.get_or_insert_with(Default::default);
+                for (name, limit) in &limits {
+                    resources.limits.entry(name.clone()).or_insert_with(|| limit.clone());
+                }
+                for (name, request) in &requests {
+                    resources.requests.entry(name.clone()).or_insert_with(|| request.clone());
+                }
+            }
+        }
+        let mut violations = Vec::new();
+        for item in self.items(LimitType::Container) {
+            for container in pod.spec.init_containers.iter().chain(&pod.spec.containers) {
+                item.check(&container.requests()?, &container.limits()?, &mut violations)?;
+            }
+        }
+        for item in self.items(LimitType::Pod) {
+            item.check(&pod.requests()?, &pod.limits()?, &mut violations)?;
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Forbidden(violations))
+        }
+    }
+
+    fn items(&self, r#type: LimitType) -> impl Iterator<Item = &LimitRangeItem> {
+        self.spec.limits.iter().filter(move |item| item.r#type == r#type)
+    }
+}
+
+fn containers_mut(spec: &mut Spec) -> impl Iterator<Item = &mut Container> {
+    spec.init_containers.iter_mut().chain(spec.containers.iter_mut())
 }
 
 /// LimitRangeSpec defines a min/max usage limit for resources that match on kind.
 /// <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/limit-range-v1/#LimitRangeSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct LimitRangeSpec {
     /// Limits is the list of LimitRangeItem objects that are enforced.
-    limits: Vec<LimitRangeItem>,
+    #[kfl(children)]
+    pub limits: Vec<LimitRangeItem>,
 }
 
 /// LimitRangeItem defines a min/max usage limit for any resource that matches on kind.
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct LimitRangeItem {
     /// Type of resource that this limit applies to.
-    r#type: String,
+    pub r#type: LimitType,
     /// Default resource requirement limit value by resource name if resource limit is omitted.
-    default: HashMap<String, Quantity>,
+    pub default: HashMap<String, Quantity>,
     /// DefaultRequest is the default resource requirement request value by resource name if resource request is omitted.
-    default_request: HashMap<String, Quantity>,
+    pub default_request: HashMap<String, Quantity>,
     /// Max usage constraints on this kind by resource name.
-    max: HashMap<String, Quantity>,
+    pub max: HashMap<String, Quantity>,
     /// MaxLimitRequestRatio if specified, the named resource must have a request and limit that are both non-zero where limit divided by request is less than or equal to the enumerated value; this represents the max burst for the named resource.
-    max_limit_request_ratio: HashMap<String, Quantity>,
+    pub max_limit_request_ratio: HashMap<String, Quantity>,
     /// Min usage constraints on this kind by resource name.
-    min: HashMap<String, Quantity>
+    pub min: HashMap<String, Quantity>
+}
+
+impl Decode for LimitRangeItem {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let limit_range_item = Self {
+            r#type: fields.property("type")?.unwrap_or_default(),
+            default: fields.entries("default")?,
+            default_request: fields.entries("default-request")?,
+            max: fields.entries("max")?,
+            max_limit_request_ratio: fields.entries("max-limit-request-ratio")?,
+            min: fields.entries("min")?
+        };
+        fields.finish()?;
+        Ok(limit_range_item)
+    }
+}
+
+impl LimitRangeItem {
+    /// The default limits and requests, as the API server defaults them: limits to `max`, and requests to the default limits or else to `min`.
+    pub fn defaults(&self) -> (HashMap<String, Quantity>, HashMap<String, Quantity>) {
+        let mut limits = self.default.clone();
+        for (name, max) in &self.max {
+            limits.entry(name.clone()).or_insert_with(|| max.clone());
+        }
+        let mut requests = self.default_request.clone();
+        for (name, value) in limits.iter().chain(&self.min) {
+            requests.entry(name.clone()).or_insert_with(|| value.clone());
+        }
+        (limits, requests)
+    }
+
+    fn check(
+        &self,
+        requests: &Amounts,
+        limits: &Amounts,
+        violations: &mut Vec<String>
+    ) -> Result<(), Error> {
+        let kind = self.r#type.as_str();
+        let mut names: Vec<_> = self.min.keys().collect();
+        names.sort();
+        for name in names {
+            let min: Amount = self.min[name].parse()?;
+            match (requests.get(name), limits.get(name)) {
+                (None, _) => violations.push(format!(
+                    "minimum {} usage per {} is {}.  No request is specified", name, kind, min)),
+                (Some(request), _) if *request < min => violations.push(format!(
+                    "minimum {} usage per {} is {}, but request is {}", name, kind, min, request)),
+                (_, Some(limit)) if *limit < min => violations.push(format!(
+                    "minimum {} usage per {} is {}, but limit is {}", name, kind, min, limit)),
+                _ => {}
+            }
+        }
+        let mut names: Vec<_> = self.max.keys().collect();
+        names.sort();
+        for name in names {
+            let max: Amount = self.max[name].parse()?;
+            match (requests.get(name), limits.get(name)) {
+                (_, None) => violations.push(format!(
+                    "maximum {} usage per {} is {}.  No limit is specified", name, kind, max)),
+                (_, Some(limit)) if *limit > max => violations.push(format!(
+                    "maximum {} usage per {} is {}, but limit is {}", name, kind, max, limit)),
+                (Some(request), _) if *request > max => violations.push(format!(
+                    "maximum {} usage per {} is {}, but request is {}", name, kind, max, request)),
+                _ => {}
+            }
+        }
+        let mut names: Vec<_> = self.max_limit_request_ratio.keys().collect();
+        names.sort();
+        for name in names {
+            let ratio: Amount = self.max_limit_request_ratio[name].parse()?;
+            let prefix = format!("{} max limit to request ratio per {} is {}", name, kind, ratio);
+            let request = requests.get(name).filter(|request| !request.is_zero());
+            let limit = limits.get(name).filter(|limit| !limit.is_zero());
+            match (request, limit) {
+                (None, _) => violations.push(format!(
+                    "{}, but no request is specified or request is 0", prefix)),
+                (_, None) => violations.push(format!(
+                    "{}, but no limit is specified or limit is 0", prefix)),
+                (Some(request), Some(limit)) => {
+                    let observed = limit.as_f64() / request.as_f64();
+                    if observed > ratio.as_f64() {
+                        violations.push(format!("{}, but provided ratio is {:.6}",
+                            prefix, observed));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a [`LimitRangeItem`] constrains.
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LimitType {
+    /// Each container of a pod, init containers included.
+    #[default]
+    Container,
+    /// A pod as a whole.
+    Pod,
+    /// Each claim, by its storage request.
+    PersistentVolumeClaim
+}
+
+impl LimitType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Container => "Container",
+            Self::Pod => "Pod",
+            Self::PersistentVolumeClaim => "PersistentVolumeClaim"
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Quantity(quantity::Error),
+    /// The constraints the object violates.
+    Forbidden(Vec<String>)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Quantity(error) => error.fmt(f),
+            Self::Forbidden(violations) => write!(f, "[{}]", violations.join(", "))
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<quantity::Error> for Error {
+    fn from(error: quantity::Error) -> Self {
+        Self::Quantity(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decode::decode;
+    use super::*;
+
+    fn limit_range() -> LimitRange {
+        decode("limit_range.kdl", r#"
+            limit-range {
+                metadata "limits"
+                spec {
+                    limit-range-item type="container" {
+                        default cpu="500m" memory="256Mi"
+                        default-request cpu="200m"
+                        max cpu="1"
+                        min memory="64Mi"
+                        max-limit-request-ratio cpu="4"
+                    }
+                    limit-range-item type="pod" {
+                        max memory="1Gi"
+                    }
+                }
+            }
+        "#).unwrap()
+    }
+
+    fn pod(resources: &str) -> Pod {
+        decode("pod.kdl", &format!(r#"
+            pod {{
+                spec {{
+                    container "app" {{
+                        resources {{
+                            {}
+                        }}
+                    }}
+                }}
+            }}
+        "#, resources)).unwrap()
+    }
+
+    #[test]
+    fn defaults_unspecified_resources() {
+        let mut pod = pod(r#"limits memory="512Mi""#);
+        limit_range().admit(&mut pod).unwrap();
+        let resources = pod.spec.containers[0].resources.as_ref().unwrap();
+        assert_eq!(resources.limits["cpu"], "500m");
+        assert_eq!(resources.limits["memory"], "512Mi");
+        assert_eq!(resources.requests["cpu"], "200m");
+        // Requests default to the limits specified before the defaults of the range.
+        assert_eq!(resources.requests["memory"], "512Mi");
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let mut pod = pod(r#"
+            requests cpu="100m" memory="32Mi"
+            limits cpu="2" memory="2Gi"
+        "#);
+        let Err(Error::Forbidden(violations)) = limit_range().admit(&mut pod) else {
+            panic!("expected the pod to be forbidden")
+        };
+        assert_eq!(violations, [
+            "minimum memory usage per Container is 64Mi, but request is 32Mi",
+            "maximum cpu usage per Container is 1, but limit is 2",
+            "cpu max limit to request ratio per Container is 4, but provided ratio is 20.000000",
+            "maximum memory usage per Pod is 1Gi, but limit is 2Gi"
+        ]);
+    }
 }
@@ -4,5 +4,6 @@ use kfl::Decode;
 
 #[derive(Debug, Decode)]
 pub struct LocalReference {
-    name: String
+    #[kfl(property, default)]
+    pub name: String
 }
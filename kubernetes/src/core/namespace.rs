@@ -1,23 +1,38 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/namespace-v1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
-use crate::meta::{condition::Condition, metadata::Metadata};
+use crate::{decode::Fields, meta::{condition::Condition, metadata::Metadata}};
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/namespace-v1/#Namespace>
 #[derive(Debug, Decode)]
 pub struct Namespace {
-    metadata: Metadata,
-    spec: Option<Spec>,
-    status: Option<Status>
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child, default)]
+    pub spec: Option<Spec>,
+    #[kfl(child, default)]
+    pub status: Option<Status>
 } 
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Spec {
-    finalisers: Vec<String>
+    pub finalisers: Vec<String>
+}
+
+impl Decode for Spec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            finalisers: fields.values("finalisers")?
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct Status {
-    conditions: Vec<Condition>
+    #[kfl(children)]
+    pub conditions: Vec<Condition>
 }
@@ -3,10 +3,11 @@
 
 use std::collections::HashMap;
 
-use kfl::Decode;
+use kfl::{context::Context, errors::DecodeError, Decode, DecodeScalar};
 
 use crate::{
     core::pod::TaintEffect,
+    decode::Fields,
     meta::{condition::Condition, metadata::Metadata},
     quantity::Quantity,
     time::Time,
@@ -15,89 +16,132 @@ use crate::{
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/node-v1/#Node>
 #[derive(Debug, Decode)]
 pub struct Node {
-    metadata: Metadata,
-    spec: Spec,
-    status: Option<Status>,
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child)]
+    pub spec: Spec,
+    #[kfl(child, default)]
+    pub status: Option<Status>,
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/node-v1/#NodeSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Spec {
     /// Represents the pod IP range assigned to the node.
-    pod_cidr: Option<String>,
+    pub pod_cidr: Option<String>,
     /// Represents the IP ranges assigned to the node for usage by Pods on that node. If this field is specified, the 0th entry must match the `pod_cidr` field. It may contain at most 1 value for each of Ipv4 and Ipv6.
-    pod_cidrs: Vec<String>,
+    pub pod_cidrs: Vec<String>,
     /// ID of the node assigned by the cloud provider in the format: `<provider-name>://<provider-specific-node-id>`.
-    provider_id: Option<String>,
+    pub provider_id: Option<String>,
     /// If specified, the node's taints.
-    taints: Vec<Taint>,
+    pub taints: Vec<Taint>,
     /// Controls node schedulability of new pods. By default, node is schedulable.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/architecture/nodes/#manual-node-administration>
-    unschedulable: Option<bool>
+    pub unschedulable: Option<bool>
+}
+
+impl Decode for Spec {
+    fn decode(node: &kfl::ast::Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            pod_cidr: fields.property("pod-cidr")?,
+            pod_cidrs: fields.values("pod-cidrs")?,
+            provider_id: fields.property("provider-id")?,
+            taints: fields.children("taint")?,
+            unschedulable: fields.property("unschedulable")?
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
 }
 
 /// The node this Taint is attached to has the 'effect' on any pod that does not tolerate the Taint.
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone)]
 pub struct Taint {
     /// Effect of the taint on pods that do not tolerate the taint. Valid effects are `NoSchedule`, `PreferNoSchedule` and `NoExecute`.
-    effect: TaintEffect,
+    #[kfl(property, default)]
+    pub effect: TaintEffect,
     /// The taint key to be applied to a node.
-    key: String,
+    #[kfl(property, default)]
+    pub key: String,
     /// Represents the time at which the taint was added. It is only written for `NoExecute` taints.
-    time_added: Option<Time>,
+    #[kfl(property, default)]
+    pub time_added: Option<Time>,
     /// The taint value corresponding to the taint key.
-    value: Option<String>
+    #[kfl(property, default)]
+    pub value: Option<String>
 }
 
 /// NodeStatus is information about the current status of a node.
 /// 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/node-v1/#NodeStatus>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Status {
     /// List of addresses reachable to the node. Queried from cloud provider, if available.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/architecture/nodes/#addresses>
     ///
     /// Note: This field is declared as mergeable, but the merge key is not sufficiently unique, which can cause data corruption when it is merged. Callers should instead use a full-replacement patch. See <https://github.com/kubernetes/kubernetes/pull/79391> for an example.
-    addresses: Vec<NodeAddress>,
+    pub addresses: Vec<NodeAddress>,
     /// Represents the resources of a node that are available for scheduling. Defaults to [`capacity`][Self::capacity].
-    allocatable: HashMap<String, Quantity>,
+    pub allocatable: HashMap<String, Quantity>,
     /// Represents the total resources of a node.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#capacity>
-    capacity: HashMap<String, Quantity>,
+    pub capacity: HashMap<String, Quantity>,
     /// An array of current observed node conditions.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/architecture/nodes/#condition>
-    conditions: Vec<Condition<NodeConditionType>>,
+    pub conditions: Vec<Condition<NodeConditionType>>,
     /// Status of the config assigned to the node via the dynamic Kubelet config feature.
-    config: Option<NodeConfigStatus>,
+    pub config: Option<NodeConfigStatus>,
     /// Endpoints of daemons running on the Node.
-    daemon_endpoints: DaemonEndpoints,
+    pub daemon_endpoints: DaemonEndpoints,
     /// List of container images on this node.
-    images: Vec<ContainerImage>,
+    pub images: Vec<ContainerImage>,
     /// Set of ids/uuids to uniquely identify the node.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/architecture/nodes/#info>
-    node_info: Option<NodeSystemInfo>
+    pub node_info: Option<NodeSystemInfo>
+}
+
+impl Decode for Status {
+    fn decode(node: &kfl::ast::Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let status = Self {
+            addresses: fields.children("node-address")?,
+            allocatable: fields.entries("allocatable")?,
+            capacity: fields.entries("capacity")?,
+            conditions: fields.children("condition")?,
+            config: fields.child("config")?,
+            daemon_endpoints: fields.child("daemon-endpoints")?.unwrap_or_default(),
+            images: fields.children("container-image")?,
+            node_info: fields.child("node-info")?
+        };
+        fields.finish()?;
+        Ok(status)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct NodeAddress {
-    address: String,
-    r#type: NodeAddressType
+    #[kfl(property, default)]
+    pub address: String,
+    #[kfl(property, default)]
+    pub r#type: NodeAddressType
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, DecodeScalar, Default)]
 pub enum NodeAddressType {
     Hostname,
     ExternalIP,
+    #[default]
     InternalIP
 }
 
 /// <https://kubernetes.io/docs/concepts/architecture/nodes/#condition>
-#[derive(Debug, DecodeScalar)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
 pub enum NodeConditionType {
     /// `True` if the node is healthy and ready to accept pods, `False` if the node is not healthy and is not accepting pods, and `Unknown` if the node controller has not heard from the node in the last node-monitor-grace-period (default is 40 seconds).
     Ready,
@@ -113,52 +157,87 @@ pub enum NodeConditionType {
 
 #[derive(Debug, Decode)]
 pub struct NodeConfigStatus {
-    error: Option<String>,
+    #[kfl(property, default)]
+    pub error: Option<String>,
 }
 
 /// DaemonEndpoints lists ports opened by daemons running on the Node.
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct DaemonEndpoints {
     /// Endpoint on which Kubelet is listening.
-    kubelet_endpoint: Option<DaemonEndpoint>
+    pub kubelet_endpoint: Option<DaemonEndpoint>
+}
+
+impl Decode for DaemonEndpoints {
+    fn decode(node: &kfl::ast::Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let daemon_endpoints = Self {
+            kubelet_endpoint: fields.child("kubelet-endpoint")?
+        };
+        fields.finish()?;
+        Ok(daemon_endpoints)
+    }
 }
 
 /// DaemonEndpoint contains information about a single Daemon endpoint.
 #[derive(Debug, Decode)]
 pub struct DaemonEndpoint {
     /// Port number of the given endpoint.
-    port: u16
+    #[kfl(property, default)]
+    pub port: u16
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct ContainerImage {
     /// Names by which this image is known. e.g. `[ "kubernetes.example/hyperkube:v1.0.7", "cloud-vendor.registry.example/cloud-vendor/hyperkube:v1.0.7" ]`
-    names: Vec<String>,
+    pub names: Vec<String>,
     /// The size of the image in bytes.
-    size_bytes: Option<u64>
+    pub size_bytes: Option<u64>
+}
+
+impl Decode for ContainerImage {
+    fn decode(node: &kfl::ast::Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let container_image = Self {
+            names: fields.values("names")?,
+            size_bytes: fields.property("size-bytes")?
+        };
+        fields.finish()?;
+        Ok(container_image)
+    }
 }
 
 /// NodeSystemInfo is a set of ids/uuids to uniquely identify the node.
 #[derive(Debug, Decode)]
 pub struct NodeSystemInfo {
     /// The Architecture reported by the node.
-    architecture: String,
+    #[kfl(property, default)]
+    pub architecture: String,
     /// Boot ID reported by the node.
-    boot_id: String,
+    #[kfl(property, default)]
+    pub boot_id: String,
     /// ContainerRuntime Version reported by the node through runtime remote API (e.g. `containerd://1.4.2`).
-    container_runtime_version: String,
+    #[kfl(property, default)]
+    pub container_runtime_version: String,
     /// Kernel Version reported by the node from **uname -r** (e.g. `5.15.49-linuxkit`).
-    kernel_version: String,
+    #[kfl(property, default)]
+    pub kernel_version: String,
     /// KubeProxy Version reported by the node.
-    kube_proxy_version: String,
+    #[kfl(property, default)]
+    pub kube_proxy_version: String,
     /// Kubelet Version reported by the node.
-    kubelet_version: String,
+    #[kfl(property, default)]
+    pub kubelet_version: String,
     /// MachineID reported by the node. For unique machine identification in the cluster this field is preferred. Learn more from man(5) machine-id: <http://man7.org/linux/man-pages/man5/machine-id.5.html>
-    machine_id: String,
+    #[kfl(property, default)]
+    pub machine_id: String,
     /// The Operating System reported by the node
-    operating_system: String,
+    #[kfl(property, default)]
+    pub operating_system: String,
     /// OS Image reported by the node from `/etc/os-release` (e.g. `Alpine Linux edge`).
-    os_image: String,
+    #[kfl(property, default)]
+    pub os_image: String,
     /// SystemUUID reported by the node. For unique machine identification MachineID is preferred. This field is specific to Red Hat hosts <https://access.redhat.com/documentation/en-us/red_hat_subscription_management/1/html/rhsm/uuid>
-    system_uuid: String
+    #[kfl(property, default)]
+    pub system_uuid: String
 }
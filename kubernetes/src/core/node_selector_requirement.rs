@@ -1,10 +1,44 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/node-selector-requirement/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
-#[derive(Debug, Decode)]
+use crate::decode::Fields;
+
+#[derive(Debug, Clone)]
 pub struct NodeSelectorRequirement {
-    key: String,
-    operator: String,
-    values: Vec<String>
+    /// The label key, or field, the requirement applies to.
+    pub key: String,
+    /// `In`, `NotIn`, `Exists`, `DoesNotExist`, `Gt` or `Lt`.
+    pub operator: String,
+    /// One value for `Gt` and `Lt`, an integer, none for `Exists` and `DoesNotExist`.
+    pub values: Vec<String>
+}
+
+impl Decode for NodeSelectorRequirement {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let node_selector_requirement = Self {
+            key: fields.property("key")?.unwrap_or_default(),
+            operator: fields.property("operator")?.unwrap_or_default(),
+            values: fields.values("values")?
+        };
+        fields.finish()?;
+        Ok(node_selector_requirement)
+    }
+}
+
+impl NodeSelectorRequirement {
+    /// Whether `value`, that of the key if the node has it, satisfies the requirement; never for unknown operators.
+    pub fn matches(&self, value: Option<&str>) -> bool {
+        let integers = || Some((value?.parse::<i64>().ok()?, self.values.first()?.parse::<i64>().ok()?));
+        match self.operator.as_str() {
+            "In" => value.is_some_and(|value| self.values.iter().any(|own| own == value)),
+            "NotIn" => value.is_none_or(|value| self.values.iter().all(|own| own != value)),
+            "Exists" => value.is_some(),
+            "DoesNotExist" => value.is_none(),
+            "Gt" => integers().is_some_and(|(value, bound)| value > bound),
+            "Lt" => integers().is_some_and(|(value, bound)| value < bound),
+            _ => false
+        }
+    }
 }
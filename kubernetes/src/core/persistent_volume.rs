@@ -6,51 +6,55 @@ use std::{
     path::PathBuf
 };
 
-use kfl::{Decode, DecodeScalar};
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
 
 use crate::{
     core::Reference,
+    decode::Fields,
     meta::Metadata,
     node_selector::NodeSelector,
     quantity::Quantity,
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/persistent-volume-v1/#PersistentVolume>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone)]
 pub struct PersistentVolume {
-    metadata: Metadata,
-    spec: Spec,
-    status: Option<Status>
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child)]
+    pub spec: Spec,
+    #[kfl(child, default)]
+    pub status: Option<Status>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/persistent-volume-v1/#Spec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, Default)]
 pub struct Spec {
     /// All ways the volume can be mounted.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#access-modes>
-    access_modes: Vec<AccessMode>,
+    pub access_modes: Vec<AccessMode>,
     /// Description of the persistent volume's resources and capacity.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#capacity>
-    capacity: HashMap<String, Quantity>,
+    pub capacity: HashMap<String, Quantity>,
     /// Part of a bi-directional binding between PersistentVolume and PersistentVolumeClaim. Expected to be non-null when bound. [`claim.volume_name`][crate::core::persistent_volume_claim::PersistentVolumeClaimSpec::volume_name] is the authoritative bind between PV and PVC.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#binding>
-    claim_ref: Option<Reference>,
+    pub claim_ref: Option<Reference>,
     /// The list of mount options, e.g. ["ro", "soft"]. Not validated - mount will simply fail if one is invalid.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#mount-options>
-    mount_options: Vec<MountOption>,
+    pub mount_options: Vec<MountOption>,
     /// Constraints that limit what nodes this volume can be accessed from. This field influences the scheduling of pods that use this volume.
-    node_affinity: Option<NodeAffinity>,
-    persistent_volume_reclaim_policy: Option<ReclaimPolicy>,
-    storage_class_name: Option<String>,
-    volume_mode: Option<VolumeMode>,
+    pub node_affinity: Option<NodeAffinity>,
+    pub persistent_volume_reclaim_policy: Option<ReclaimPolicy>,
+    pub storage_class_name: Option<String>,
+    pub volume_mode: Option<VolumeMode>,
     // Local
     // <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/persistent-volume-v1/#local>
-    host_path: Option<HostPath>,
-    local: Option<Local>,
+    pub host_path: Option<HostPath>,
+    pub local: Option<Local>,
 
     /*
     Persistent Volumes
@@ -79,8 +83,28 @@ pub struct Spec {
     // vsphere_volume: Option<VsphereVirtualDiscVolumeSource>
 }
 
+impl Decode for Spec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            access_modes: fields.values("access-modes")?,
+            capacity: fields.entries("capacity")?,
+            claim_ref: fields.child("claim-ref")?,
+            mount_options: fields.values("mount-options")?,
+            node_affinity: fields.child("node-affinity")?,
+            persistent_volume_reclaim_policy: fields.property("persistent-volume-reclaim-policy")?,
+            storage_class_name: fields.property("storage-class-name")?,
+            volume_mode: fields.property("volume-mode")?,
+            host_path: fields.child("host-path")?,
+            local: fields.child("local")?
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
+}
+
 /// <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#access-modes>
-#[derive(Debug, DecodeScalar)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
 pub enum AccessMode {
     ReadWriteOnce,
     ReadOnlyMany,
@@ -89,49 +113,99 @@ pub enum AccessMode {
 }
 
 /// <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#mount-options>
-#[derive(Debug, DecodeScalar)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
 pub enum MountOption {
     Ro,
     Soft
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone)]
 pub struct NodeAffinity {
-    required: NodeSelector,
+    pub required: NodeSelector,
+}
+
+impl Decode for NodeAffinity {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let node_affinity = Self {
+            required: fields.child("required")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(node_affinity)
+    }
 }
 
 /// <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#reclaiming>
-#[derive(Debug, Decode, Default)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ReclaimPolicy {
+    /// The volume is kept, released, for an administrator to reclaim.
     Retain,
+    /// The volume is deleted, with its storage asset.
     #[default]
-    Delete
+    Delete,
+    /// Deprecated: the volume is scrubbed and made available again.
+    Recycle
 }
 
 /// <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#volume-mode>
-#[derive(Debug, DecodeScalar, Default)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum VolumeMode {
     #[default]
     Filesystem,
     Block
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone)]
 pub struct HostPath {
-    path: PathBuf,
-    r#type: Option<String>
+    #[kfl(property, default)]
+    pub path: PathBuf,
+    #[kfl(property, default)]
+    pub r#type: Option<String>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone)]
 pub struct Local {
-    path: String,
-    fs_type: Option<String>
+    #[kfl(property, default)]
+    pub path: String,
+    #[kfl(property, default)]
+    pub fs_type: Option<String>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/persistent-volume-v1/#Status>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone, Default)]
 pub struct Status {
-    message: Option<String>,
-    phase: Option<String>,
-    reason: Option<String>
+    #[kfl(property, default)]
+    pub message: Option<String>,
+    #[kfl(property, default)]
+    pub phase: Option<Phase>,
+    #[kfl(property, default)]
+    pub reason: Option<String>
+}
+
+/// <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#phase>
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Phase {
+    /// Not yet available.
+    #[default]
+    Pending,
+    /// Free, not yet bound to a claim.
+    Available,
+    /// Bound to a claim.
+    Bound,
+    /// The claim was deleted, and the volume not yet reclaimed.
+    Released,
+    /// Failed its automated reclamation.
+    Failed
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "Pending",
+            Self::Available => "Available",
+            Self::Bound => "Bound",
+            Self::Released => "Released",
+            Self::Failed => "Failed"
+        }
+    }
 }
@@ -2,13 +2,14 @@
 
 use std::collections::HashMap;
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
 
 use crate::{
     core::{
         persistent_volume::{AccessMode, VolumeMode},
         typed_local_reference::TypedLocalReference,
     },
+    decode::Fields,
     meta::{
         condition::Condition,
         label_selector::Selector,
@@ -18,41 +19,119 @@ use crate::{
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/persistent-volume-claim-v1/#PersistentVolumeClaim>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone)]
 pub struct PersistentVolumeClaim {
-    metadata: Metadata,
-    spec: Spec,
-    status: Option<Status>
+    #[kfl(child, default)]
+    pub metadata: Metadata,
+    #[kfl(child, default)]
+    pub spec: Spec,
+    #[kfl(child, default)]
+    pub status: Option<Status>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/persistent-volume-claim-v1/#Spec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, Default)]
 pub struct Spec {
-    access_modes: Vec<AccessMode>,
-    selector: Option<Selector>,
-    resources: Option<Resource>,
-    volume_name: Option<String>,
-    storage_class_name: Option<String>,
-    volume_mode: Option<VolumeMode>,
+    /// The access modes the volume must have, all of them.
+    pub access_modes: Vec<AccessMode>,
+    /// A label query over volumes to consider for binding.
+    pub selector: Option<Selector>,
+    /// The minimum resources the volume should have, as `storage` in `requests`.
+    pub resources: Option<Resource>,
+    /// The binding reference to the volume backing this claim.
+    pub volume_name: Option<String>,
+    /// The name of the storage class; none for the default class, empty for no class.
+    pub storage_class_name: Option<String>,
+    /// `Filesystem` if `None`.
+    pub volume_mode: Option<VolumeMode>,
     // Beta Level
     /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/persistent-volume-claim-v1/#beta-level>
-    data_source: Option<TypedLocalReference>,
-    data_source_ref: Option<TypedLocalReference>,
+    pub data_source: Option<TypedLocalReference>,
+    pub data_source_ref: Option<TypedLocalReference>,
 }
 
-#[derive(Debug, Decode)]
+impl Decode for Spec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            access_modes: fields.values("access-modes")?,
+            selector: fields.child("selector")?,
+            resources: fields.child("resources")?,
+            volume_name: fields.property("volume-name")?,
+            storage_class_name: fields.property("storage-class-name")?,
+            volume_mode: fields.property("volume-mode")?,
+            data_source: fields.child("data-source")?,
+            data_source_ref: fields.child("data-source-ref")?
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Resource {
-    limits: HashMap<String, Quantity>,
-    requests: HashMap<String, Quantity>,
+    pub limits: HashMap<String, Quantity>,
+    pub requests: HashMap<String, Quantity>,
+}
+
+impl Decode for Resource {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let resource = Self {
+            limits: fields.entries("limits")?,
+            requests: fields.entries("requests")?
+        };
+        fields.finish()?;
+        Ok(resource)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/persistent-volume-claim-v1/#Status>
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, Default)]
 pub struct Status {
-    access_modes: Vec<AccessMode>,
-    allocated_resources: HashMap<String, Quantity>,
-    capacity: HashMap<String, Quantity>,
-    conditions: Vec<Condition>,
-    phase: Option<String>,
-    resize_status: Option<String>
+    /// The access modes of the volume bound.
+    pub access_modes: Vec<AccessMode>,
+    pub allocated_resources: HashMap<String, Quantity>,
+    /// The resources of the volume bound.
+    pub capacity: HashMap<String, Quantity>,
+    pub conditions: Vec<Condition>,
+    pub phase: Option<Phase>,
+    pub resize_status: Option<String>
+}
+
+impl Decode for Status {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let status = Self {
+            access_modes: fields.values("access-modes")?,
+            allocated_resources: fields.entries("allocated-resources")?,
+            capacity: fields.entries("capacity")?,
+            conditions: fields.children("condition")?,
+            phase: fields.property("phase")?,
+            resize_status: fields.property("resize-status")?
+        };
+        fields.finish()?;
+        Ok(status)
+    }
+}
+
+/// <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#binding>
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Phase {
+    /// Not yet bound.
+    #[default]
+    Pending,
+    Bound,
+    /// Bound to a volume that no longer exists.
+    Lost
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "Pending",
+            Self::Bound => "Bound",
+            Self::Lost => "Lost"
+        }
+    }
 }
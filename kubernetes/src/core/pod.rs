@@ -3,58 +3,313 @@
 
 use std::{
     collections::HashMap,
-    path::PathBuf
+    fmt,
+    path::PathBuf,
+    str::FromStr,
+    time::Duration
 };
 
-use kfl::{Decode, DecodeScalar};
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
+use serde_json::{json, Map, Value};
 
 use crate::{
-    core::{FieldSelector, LocalReference, ResourceFieldSelector, Volume},
-    meta::{Condition, Selector, Metadata},
+    core::{node::Taint, FieldSelector, LocalReference, ResourceFieldSelector, Volume},
+    decode::Fields,
+    meta::{
+        condition::ConditionStatus,
+        Condition, Selector, Metadata
+    },
     node_selector::{NodeSelector, NodeSelectorTerm},
     protocol::Protocol,
-    quantity::Quantity,
+    quantity::{self, Amounts, Quantity},
     time::Time,
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#Pod>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Pod {
-    metadata: Metadata,
-    spec: Spec,
-    status: Option<Status>
+    #[kfl(child, default)]
+    pub metadata: Metadata,
+    #[kfl(child, default)]
+    pub spec: Spec,
+    #[kfl(child, default)]
+    pub status: Option<Status>
+}
+
+impl Pod {
+    /// Whether the pod reports the `Ready` condition as `True`.
+    pub fn is_ready(&self) -> bool {
+        self.has_condition(ConditionType::Ready)
+    }
+
+    /**
+    Whether the pod has been ready for at least `min_ready_seconds` at `now`, as controllers count available replicas.
+    */
+    pub fn is_available(&self, min_ready_seconds: u32, now: Time) -> bool {
+        self.status.as_ref()
+            .and_then(|status| status.conditions.iter().find(|condition|
+                condition.r#type == ConditionType::Ready && condition.status == ConditionStatus::True))
+            .is_some_and(|condition| min_ready_seconds == 0 || condition.last_transition_time.0
+                + Duration::from_secs(u64::from(min_ready_seconds)) <= now.0)
+    }
+
+    /// Whether the pod is being deleted.
+    pub fn is_terminating(&self) -> bool {
+        self.metadata.deletion_timestamp.is_some()
+    }
+
+    fn has_condition(&self, r#type: ConditionType) -> bool {
+        self.status.as_ref().is_some_and(|status| status.conditions.iter()
+            .any(|condition| condition.r#type == r#type
+                && condition.status == ConditionStatus::True))
+    }
+
+    /**
+    The QoS class of the pod, from the CPU and memory of its containers, init containers included:
+
+    - [`Guaranteed`][QosClass::Guaranteed] if every container limits both, and requests what it limits, requests defaulting to limits.
+    - [`BestEffort`][QosClass::BestEffort] if no container requests or limits either.
+    - [`Burstable`][QosClass::Burstable] otherwise.
+    */
+    pub fn qos_class(&self) -> Result<QosClass, quantity::Error> {
+        let mut requests = Amounts::new();
+        let mut limits = Amounts::new();
+        let mut guaranteed = true;
+        for container in self.spec.init_containers.iter().chain(&self.spec.containers) {
+            let compute = |mut amounts: Amounts| {
+                amounts.retain(|name, amount|
+                    (name == "cpu" || name == "memory") && !amount.is_zero());
+                amounts
+            };
+            for (name, amount) in compute(container.requests()?) {
+                *requests.entry(name).or_default() += amount;
+            }
+            let container_limits = compute(container.limits()?);
+            if container_limits.len() < 2 {
+                guaranteed = false;
+            }
+            for (name, amount) in container_limits {
+                *limits.entry(name).or_default() += amount;
+            }
+        }
+        Ok(if requests.is_empty() && limits.is_empty() {
+            QosClass::BestEffort
+        } else if guaranteed && requests.iter().all(|(name, request)| limits.get(name) == Some(request))
+            && requests.len() == limits.len()
+        {
+            QosClass::Guaranteed
+        } else {
+            QosClass::Burstable
+        })
+    }
+
+    /**
+    The effective requests of the pod, what it is scheduled and charged by: the largest of what its containers request together and what each init container does, plus the [`overhead`][Spec::overhead] of resources requested.
+
+    Sidecars, init containers restarted [`Always`][RestartPolicy::Always], keep running, so they add to the containers and to the init containers after them.
+    */
+    pub fn requests(&self) -> Result<Amounts, quantity::Error> {
+        self.effective(Container::requests)
+    }
+
+    /// The effective limits of the pod, combined as [`requests`][Self::requests] are.
+    pub fn limits(&self) -> Result<Amounts, quantity::Error> {
+        self.effective(Container::limits)
+    }
+
+    fn effective(&self, parse: fn(&Container) -> Result<Amounts, quantity::Error>)
+        -> Result<Amounts, quantity::Error>
+    {
+        let add = |total: &mut Amounts, amounts: &Amounts| for (name, amount) in amounts {
+            *total.entry(name.clone()).or_default() += *amount;
+        };
+        let max = |total: &mut Amounts, amounts: &Amounts| for (name, amount) in amounts {
+            let total = total.entry(name.clone()).or_default();
+            if amount > total {
+                *total = *amount;
+            }
+        };
+        let mut total = Amounts::new();
+        for container in &self.spec.containers {
+            add(&mut total, &parse(container)?);
+        }
+        let mut sidecars = Amounts::new();
+        let mut init = Amounts::new();
+        for container in &self.spec.init_containers {
+            let amounts = parse(container)?;
+            if container.restart_policy == Some(RestartPolicy::Always) {
+                add(&mut total, &amounts);
+                add(&mut sidecars, &amounts);
+                max(&mut init, &sidecars);
+            } else {
+                let mut running = sidecars.clone();
+                add(&mut running, &amounts);
+                max(&mut init, &running);
+            }
+        }
+        max(&mut total, &init);
+        for (name, overhead) in quantity::parse_all(&self.spec.overhead)? {
+            if let Some(amount) = total.get_mut(&name) {
+                *amount += overhead;
+            }
+        }
+        Ok(total)
+    }
+
+    /// The restarts of all containers of the pod, init containers included.
+    pub fn restart_count(&self) -> i32 {
+        self.status.iter()
+            .flat_map(|status| status.init_container_statuses.iter()
+                .chain(&status.container_statuses))
+            .map(|status| status.restart_count)
+            .sum()
+    }
+
+    /**
+    The pod as `kubectl get pods` shows it: its ready containers, its status and its restarts.
+
+    The status is the phase or the reason of the pod, overridden by the first init container not done, as `Init:1/3`, `Init:CrashLoopBackOff` or `Init:Error`, else by the reason the first container not running is waiting or terminated, as `CrashLoopBackOff` or `Completed`, and by `Terminating` once the pod is being deleted.
+    */
+    pub fn display_status(&self) -> DisplayStatus {
+        let default = Status::default();
+        let status = self.status.as_ref().unwrap_or(&default);
+        let sidecars: Vec<&str> = self.spec.init_containers.iter()
+            .filter(|container| container.restart_policy == Some(RestartPolicy::Always))
+            .map(|container| container.name.as_str())
+            .collect();
+        let mut display = DisplayStatus {
+            ready: 0,
+            total: self.spec.containers.len() + sidecars.len(),
+            status: if status.reason.is_empty() { &status.phase } else { &status.reason }
+                .clone(),
+            restarts: 0,
+            last_restart: None
+        };
+        let mut initialising = false;
+        for (index, container) in status.init_container_statuses.iter().enumerate() {
+            display.count_restarts(container);
+            let state = container.state.as_ref();
+            let terminated = state.and_then(|state| state.terminated.as_ref());
+            let waiting = state.and_then(|state| state.waiting.as_ref())
+                .and_then(|waiting| waiting.reason.as_deref())
+                .filter(|reason| !reason.is_empty() && *reason != "PodInitializing");
+            if terminated.is_some_and(|terminated| terminated.exit_code == Some(0)) {
+                continue
+            }
+            if sidecars.contains(&container.name.as_str()) && container.started == Some(true) {
+                if container.ready {
+                    display.ready += 1;
+                }
+                continue
+            }
+            display.status = match (terminated, waiting) {
+                (Some(terminated), _) => format!("Init:{}", terminated_reason(terminated)),
+                (None, Some(reason)) => format!("Init:{}", reason),
+                (None, None) =>
+                    format!("Init:{}/{}", index, self.spec.init_containers.len())
+            };
+            initialising = true;
+            break
+        }
+        if !initialising || self.has_condition(ConditionType::Initialized) {
+            display.restarts = 0;
+            let mut running = false;
+            for container in status.container_statuses.iter().rev() {
+                display.count_restarts(container);
+                let state = container.state.as_ref();
+                let waiting = state.and_then(|state| state.waiting.as_ref())
+                    .and_then(|waiting| waiting.reason.as_deref())
+                    .filter(|reason| !reason.is_empty());
+                let terminated = state.and_then(|state| state.terminated.as_ref());
+                if let Some(reason) = waiting {
+                    display.status = reason.to_owned();
+                } else if let Some(terminated) = terminated {
+                    display.status = terminated_reason(terminated);
+                } else if container.ready && state.is_some_and(|state| state.running.is_some()) {
+                    running = true;
+                    display.ready += 1;
+                }
+            }
+            if display.status == "Completed" && running {
+                display.status = if self.is_ready() { "Running" } else { "NotReady" }.to_owned();
+            }
+        }
+        if self.metadata.deletion_timestamp.is_some() {
+            if status.reason == "NodeLost" {
+                display.status = "Unknown".to_owned();
+            } else if status.phase != "Succeeded" && status.phase != "Failed" {
+                display.status = "Terminating".to_owned();
+            }
+        }
+        display
+    }
+}
+
+/// Reason a container terminated, or its signal or exit code if none was given.
+fn terminated_reason(terminated: &ContainerStateTerminated) -> String {
+    match (terminated.reason.as_deref(), terminated.signal) {
+        (Some(reason), _) if !reason.is_empty() => reason.to_owned(),
+        (_, Some(signal)) if signal != 0 => format!("Signal:{}", signal),
+        _ => format!("ExitCode:{}", terminated.exit_code.unwrap_or_default())
+    }
+}
+
+/// A pod as `kubectl get pods` shows it, by [`Pod::display_status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayStatus {
+    /// Containers and started sidecars that are ready, the first half of the `READY` column.
+    pub ready: usize,
+    /// Containers and sidecars, the second half of the `READY` column.
+    pub total: usize,
+    /// The `STATUS` column.
+    pub status: String,
+    /// Restarts of the init containers while initialising, else of the containers.
+    pub restarts: i32,
+    /// When a container last terminated before being restarted, shown as `(5m ago)` after the restarts.
+    pub last_restart: Option<Time>
+}
+
+impl DisplayStatus {
+    fn count_restarts(&mut self, container: &ContainerStatus) {
+        self.restarts += container.restart_count;
+        let finished_at = container.last_state.as_ref()
+            .and_then(|state| state.terminated.as_ref())
+            .and_then(|terminated| terminated.finished_at);
+        if finished_at > self.last_restart {
+            self.last_restart = finished_at;
+        }
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#PodSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Spec {
     // Containers
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#containers>
 
     /// List of containers belonging to the pod. Containers cannot currently be added or removed. There must be at least one container in a Pod. Cannot be updated.
-    containers: Vec<Container>,
+    pub containers: Vec<Container>,
     /// List of initialisation containers belonging to the pod. Init containers are executed in order prior to containers being started. If any init container fails, the pod is considered to have failed and is handled according to its [`restart_policy`][Self::restart_policy]. The name for an init container or normal container must be unique among all containers. Init containers may not have Lifecycle actions, Readiness probes, Liveness probes, or Startup probes. The resourceRequirements of an init container are taken into account during scheduling by finding the highest request/limit for each resource type, and then using the max of of that value or the sum of the normal containers. Limits are applied to init containers in a similar fashion. Init containers cannot currently be added or removed. Cannot be updated.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/workloads/pods/init-containers/>
-    init_containers: Vec<Container>,
+    pub init_containers: Vec<Container>,
     /// List of ephemeral containers run in this pod. Ephemeral containers may be run in an existing pod to perform user-initiated actions such as debugging. This list cannot be specified when creating a pod, and it cannot be modified by updating the pod spec. In order to add an ephemeral container to an existing pod, use the pod's ephemeralcontainers subresource.
-    ephemeral_containers: Vec<EphemeralContainer>,
+    pub ephemeral_containers: Vec<EphemeralContainer>,
     /// `image_pull_secrets` is an optional list of references to secrets in the same namespace to use for pulling any of the images used by this PodSpec. If specified, these secrets will be passed to individual puller implementations for them to use.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/containers/images#specifying-imagepullsecrets-on-a-pod>
-    image_pull_secrets: Vec<LocalReference>,
+    pub image_pull_secrets: Vec<LocalReference>,
     /// Indicates whether information about services should be injected into pod's environment variables, matching the syntax of Docker links. Optional: Defaults to true.
-    #[kfl(default = true)]
-    enable_service_links: bool,
+    pub enable_service_links: bool,
     /// Specifies the OS of the containers in the pod. Some pod and container fields are restricted if this is set.
-    os: Option<PodOS>,
+    pub os: Option<PodOS>,
 
     // Volumes
 
     /// List of volumes that can be mounted by containers belonging to the pod.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/storage/volumes/>
-    volumes: Vec<Volume>,
+    pub volumes: Vec<Volume>,
 
     // Scheduling
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#scheduling>
@@ -62,33 +317,33 @@ pub struct Spec {
     /// Selector which must be true for the pod to fit on a node. Selector which must match a node's labels for the pod to be scheduled on that node.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/scheduling-eviction/assign-pod-node/>
-    node_selector: HashMap<String, String>,
+    pub node_selector: HashMap<String, String>,
     /// Request to schedule this pod onto a specific node. If it is non-empty, the scheduler simply schedules this pod onto that node, assuming that it fits resource requirements.
-    node_name: Option<String>,
+    pub node_name: Option<String>,
     /// If specified, the pod's scheduling constraints.
-    affinity: Option<Affinity>,
+    pub affinity: Option<Affinity>,
     /// If specified, the pod's tolerations.
-    tolerations: Vec<Toleration>,
+    pub tolerations: Vec<Toleration>,
     /// If specified, the pod will be dispatched by specified scheduler. If not specified, the pod will be dispatched by default scheduler.
-    scheduler_name: Option<String>,
+    pub scheduler_name: Option<String>,
     /// `runtime_class_name` refers to a [`RuntimeClass`][RuntimeClass] object in the node.k8s.io group, which should be used to run this pod. If no [`RuntimeClass`][RuntimeClass] resource matches the named class, the pod will not be run. If unset or empty, the 'legacy' [`RuntimeClass`][RuntimeClass] will be used, which is an implicit class with an empty definition that uses the default runtime handler.
     ///
     /// More info: <https://github.com/kubernetes/enhancements/tree/master/keps/sig-node/585-runtime-class>
     ///
     /// [RuntimeClass]: crate::node::runtime_class::RuntimeClass
-    runtime_class_name: Option<String>,
+    pub runtime_class_name: Option<String>,
     /// If specified, indicates the pod's priority. `"system-node-critical"` and `"system-cluster-critical"` are two special keywords which indicate the highest priorities with the former being the highest priority. Any other name must be defined by creating a PriorityClass object with that name. If not specified, the pod priority will be default or zero if there is no default.
-    priority_class_name: Option<String>,
+    pub priority_class_name: Option<String>,
     /// The priority value. Various system components use this field to find the priority of the pod. When Priority Admission Controller is enabled, it prevents users from setting this field. The admission controller populates this field from [`priority_class_name`][Self::priority_class_name]. The higher the value, the higher the priority.
-    priority: Option<i32>,
+    pub priority: Option<i32>,
     /// `preemption_policy` is the Policy for preempting pods with lower priority. One of `Never`, `PreemptLowerPriority`. Defaults to `PreemptLowerPriority` if unset.
-    preemption_policy: Option<PreemptionPolicy>,
+    pub preemption_policy: Option<PreemptionPolicy>,
     /// `topology_spread_constraints` describes how a group of pods ought to spread across topology domains. Scheduler will schedule pods in a way which abides by the constraints. All `topology_spread_constraints` are ANDed.
-    topology_spread_constraints: Vec<TopologySpreadConstraint>,
+    pub topology_spread_constraints: Vec<TopologySpreadConstraint>,
     /// `overhead` represents the resource overhead associated with running a pod for a given RuntimeClass. This field will be auto-populated at admission time by the RuntimeClass admission controller. If the RuntimeClass admission controller is enabled, overhead must not be set in Pod create requests. The RuntimeClass admission controller will reject Pod create requests which have the overhead already set. If RuntimeClass is configured and selected in the PodSpec, Overhead will be set to the value defined in the corresponding RuntimeClass, otherwise it will remain unset and treated as zero.
     ///
     /// More info: <https://github.com/kubernetes/enhancements/blob/master/keps/sig-node/688-pod-overhead/README.md>
-    overhead: HashMap<String, Quantity>,
+    pub overhead: HashMap<String, Quantity>,
 
     // Lifecycle
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#lifecycle>
@@ -96,51 +351,43 @@ pub struct Spec {
     /// Restart policy for all containers within the pod. One of `Always`, `OnFailure`, `Never`. Default to `Always`.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/workloads/pods/pod-lifecycle/#restart-policy>
-    #[kfl(default = RestartPolicy::Always)]
-    restart_policy: RestartPolicy,
+    pub restart_policy: RestartPolicy,
     /// Optional duration in seconds the pod needs to terminate gracefully. May be decreased in delete request. Value must be non-negative integer. The value zero indicates stop immediately via the kill signal (no opportunity to shut down). If this value is empty, the default grace period will be used instead. The grace period is the duration in seconds after the processes running in the pod are sent a termination signal and the time when the processes are forcibly halted with a kill signal. Set this value longer than the expected cleanup time for your process. Defaults to 30 seconds.
-    #[kfl(default = "30")]
-    termination_grace_period_seconds: Option<u64>,
+    pub termination_grace_period_seconds: Option<u64>,
     /// Optional duration in seconds the pod may be active on the node relative to StartTime before the system will actively try to mark it failed and kill associated containers. Value must be a positive integer.
-    active_deadline_seconds: Option<u64>,
+    pub active_deadline_seconds: Option<u64>,
     /// If specified, all readiness gates will be evaluated for pod readiness. A pod is ready when all its containers are ready AND all conditions specified in the readiness gates have status equal to `True`.
     ///
     /// More info: <https://github.com/kubernetes/enhancements/tree/master/keps/sig-network/580-pod-readiness-gates>
-    readiness_gates: Vec<ReadinessGate>,
+    pub readiness_gates: Vec<ReadinessGate>,
 
     // Hostname and Name Resolution
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#hostname-and-name-resolution>
 
     /// Specifies the hostname of the Pod If not specified, the pod's hostname will be set to a system-defined value.
-    hostname: Option<String>,
+    pub hostname: Option<String>,
     /// If `true` the pod's hostname will be configured as the pod's Fqdn, rather than the leaf name (the default). In Linux containers, this means setting the Fqdn in the hostname field of the kernel (the nodename field of struct utsname). In Windows containers, this means setting the registry value of hostname for the registry key HKEY_LOCAL_MACHINE\SYSTEM\CurrentControlSet\Services\Tcpip\Parameters to Fqdn. If a pod does not have Fqdn, this has no effect. Default to `false`.
-    #[kfl(default)]
-    set_hostname_as_fqdn: bool,
+    pub set_hostname_as_fqdn: bool,
     /// If specified, the fully qualified Pod hostname will be `"<hostname>.<subdomain>.<pod namespace>.svc.<cluster domain>"`. If not specified, the pod will not have a domainname at all.
-    subdomain: Option<String>,
+    pub subdomain: Option<String>,
     /// `host_aliases` is an optional list of hosts and IPs that will be injected into the pod's hosts file if specified. This is only valid for non-[`host_network`][Self::host_network] pods.
-    host_aliases: Vec<HostAlias>,
+    pub host_aliases: Vec<HostAlias>,
     /// Specifies the DNS parameters of a pod. Parameters specified here will be merged to the generated DNS configuration based on [`dns_policy`][Self::dns_policy].
-    dns_config: Option<DnsConfig>,
+    pub dns_config: Option<DnsConfig>,
     /// Set DNS policy for the pod. Defaults to `ClusterFirst`. Valid values are `ClusterFirstWithHostNet`, `ClusterFirst`, `Default` or `None`. DNS parameters given in [`dns_config`][Self::dns_config] will be merged with the policy selected with `dns_policy`. To have DNS options set along with [`host_network`][Self::host_network], you have to specify DNS policy explicitly to `ClusterFirstWithHostNet`.
-    #[kfl(default = DnsPolicy::ClusterFirst)]
-    dns_policy: DnsPolicy,
+    pub dns_policy: DnsPolicy,
 
     // Hosts Namespaces
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#hosts-namespaces>
 
     /// Host networking requested for this pod. Use the host's network namespace. If this option is set, the ports that will be used must be specified. Default to `false`.
-    #[kfl(default)]
-    host_network: bool,
+    pub host_network: bool,
     /// Use the host's pid namespace. Optional: Default to `false`.
-    #[kfl(default)]
-    host_pid: bool,
+    pub host_pid: bool,
     /// Use the host's ipc namespace. Optional: Default to `false`.
-    #[kfl(default)]
-    host_ipc: bool,
+    pub host_ipc: bool,
     /// Share a single process namespace between all of the containers in a pod. When this is set containers will be able to view and signal processes from other containers in the same pod, and the first process in each container will not be assigned PID 1. [`host_pid`][Self::host_pid] and `share_process_namespace` cannot both be set. Optional: Default to `false`.
-    #[kfl(default)]
-    share_process_namespace: bool,
+    pub share_process_namespace: bool,
 
     // Service Account
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#service-account>
@@ -148,31 +395,183 @@ pub struct Spec {
     /// Name of the ServiceAccount to use to run this pod.
     ///
     /// More info: [Configure Service Accounts for Pods](https://kubernetes.io/docs/tasks/configure-pod-container/configure-service-account/)
-    service_account_name: Option<String>,
+    pub service_account_name: Option<String>,
     /// `automount_service_account_token` indicates whether a service account token should be automatically mounted.
-    automount_service_account_token: Option<bool>,
+    pub automount_service_account_token: Option<bool>,
 
     // Security Context
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#security-context>
 
     /// `security_context` holds pod-level security attributes and common container settings. Optional: Defaults to empty. See type description for default values of each field.
-    security_context: Option<PodSecurityContext>,
+    pub security_context: Option<PodSecurityContext>,
 
     // Alpha Level
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#alpha-level>
 
     /// Use the host's user namespace. Optional: Default to `true`. If set to `true` or not present, the pod will be run in the host user namespace, useful for when the pod needs a feature only available to the host user namespace, such as loading a kernel module with CAP_SYS_MODULE. When set to `false`, a new userns is created for the pod. Setting `false` is useful for mitigating container breakout vulnerabilities even allowing users to run their containers as root without actually having root privileges on the host. This field is alpha-level and is only honored by servers that enable the UserNamespacesSupport feature.
-    #[kfl(default = true)]
-    host_users: bool
+    pub host_users: bool
+}
+
+impl Decode for Spec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            containers: fields.children("container")?,
+            init_containers: fields.children("init-container")?,
+            ephemeral_containers: fields.children("ephemeral-container")?,
+            image_pull_secrets: fields.children("local-reference")?,
+            enable_service_links: fields.property("enable-service-links")?.unwrap_or(true),
+            os: fields.child("os")?,
+            volumes: fields.children("volume")?,
+            node_selector: fields.entries("node-selector")?,
+            node_name: fields.property("node-name")?,
+            affinity: fields.child("affinity")?,
+            tolerations: fields.children("toleration")?,
+            scheduler_name: fields.property("scheduler-name")?,
+            runtime_class_name: fields.property("runtime-class-name")?,
+            priority_class_name: fields.property("priority-class-name")?,
+            priority: fields.property("priority")?,
+            preemption_policy: fields.property("preemption-policy")?,
+            topology_spread_constraints: fields.children("topology-spread-constraint")?,
+            overhead: fields.entries("overhead")?,
+            restart_policy: fields.property("restart-policy")?.unwrap_or(RestartPolicy::Always),
+            termination_grace_period_seconds: fields.property("termination-grace-period-seconds")?.or(Some(30)),
+            active_deadline_seconds: fields.property("active-deadline-seconds")?,
+            readiness_gates: fields.children("readiness-gate")?,
+            hostname: fields.property("hostname")?,
+            set_hostname_as_fqdn: fields.property("set-hostname-as-fqdn")?.unwrap_or_default(),
+            subdomain: fields.property("subdomain")?,
+            host_aliases: fields.children("host-alias")?,
+            dns_config: fields.child("dns-config")?,
+            dns_policy: fields.property("dns-policy")?.unwrap_or(DnsPolicy::ClusterFirst),
+            host_network: fields.property("host-network")?.unwrap_or_default(),
+            host_pid: fields.property("host-pid")?.unwrap_or_default(),
+            host_ipc: fields.property("host-ipc")?.unwrap_or_default(),
+            share_process_namespace: fields.property("share-process-namespace")?.unwrap_or_default(),
+            service_account_name: fields.property("service-account-name")?,
+            automount_service_account_token: fields.property("automount-service-account-token")?,
+            security_context: fields.child("security-context")?,
+            host_users: fields.property("host-users")?.unwrap_or(true)
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
+}
+
+/// The defaults of the fields, as when decoded.
+impl Default for Spec {
+    fn default() -> Self {
+        Self {
+            containers: Default::default(),
+            init_containers: Default::default(),
+            ephemeral_containers: Default::default(),
+            image_pull_secrets: Default::default(),
+            enable_service_links: true,
+            os: Default::default(),
+            volumes: Default::default(),
+            node_selector: Default::default(),
+            node_name: Default::default(),
+            affinity: Default::default(),
+            tolerations: Default::default(),
+            scheduler_name: Default::default(),
+            runtime_class_name: Default::default(),
+            priority_class_name: Default::default(),
+            priority: Default::default(),
+            preemption_policy: Default::default(),
+            topology_spread_constraints: Default::default(),
+            overhead: Default::default(),
+            restart_policy: RestartPolicy::Always,
+            termination_grace_period_seconds: Some(30),
+            active_deadline_seconds: Default::default(),
+            readiness_gates: Default::default(),
+            hostname: Default::default(),
+            set_hostname_as_fqdn: Default::default(),
+            subdomain: Default::default(),
+            host_aliases: Default::default(),
+            dns_config: Default::default(),
+            dns_policy: DnsPolicy::ClusterFirst,
+            host_network: Default::default(),
+            host_pid: Default::default(),
+            host_ipc: Default::default(),
+            share_process_namespace: Default::default(),
+            service_account_name: Default::default(),
+            automount_service_account_token: Default::default(),
+            security_context: Default::default(),
+            host_users: true
+        }
+    }
+}
+
+impl Spec {
+    /// The spec of a pod in JSON, with the fields its controllers, the scheduler and the kubelet look at: its containers, where it runs and how it restarts. The others are left to their defaults.
+    pub fn from_json(value: &Value) -> Self {
+        let default = Self::default();
+        let string = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_owned);
+        let array = |key: &str| value.get(key).and_then(Value::as_array).into_iter().flatten();
+        let bool = |key: &str| value.get(key).and_then(Value::as_bool);
+        Self {
+            containers: array("containers").map(Container::from_json).collect(),
+            init_containers: array("initContainers").map(Container::from_json).collect(),
+            enable_service_links: bool("enableServiceLinks").unwrap_or(default.enable_service_links),
+            node_selector: value.get("nodeSelector").and_then(Value::as_object).into_iter().flatten()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_owned())))
+                .collect(),
+            node_name: string("nodeName"),
+            tolerations: array("tolerations").map(Toleration::from_json).collect(),
+            scheduler_name: string("schedulerName"),
+            priority_class_name: string("priorityClassName"),
+            priority: value.get("priority").and_then(Value::as_i64)
+                .and_then(|priority| i32::try_from(priority).ok()),
+            restart_policy: string("restartPolicy").and_then(|policy| policy.parse().ok())
+                .unwrap_or(default.restart_policy),
+            termination_grace_period_seconds: value.get("terminationGracePeriodSeconds")
+                .and_then(Value::as_u64)
+                .or(default.termination_grace_period_seconds),
+            active_deadline_seconds: value.get("activeDeadlineSeconds").and_then(Value::as_u64),
+            hostname: string("hostname"),
+            subdomain: string("subdomain"),
+            host_network: bool("hostNetwork").unwrap_or_default(),
+            service_account_name: string("serviceAccountName"),
+            host_users: bool("hostUsers").unwrap_or(default.host_users),
+            ..default
+        }
+    }
+
+    /// The fields [`from_json`][Self::from_json] reads, in JSON.
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        let mut insert = |key: &str, value: Option<Value>| if let Some(value) = value {
+            object.insert(key.to_owned(), value);
+        };
+        let list = |items: Vec<Value>| (!items.is_empty()).then_some(Value::Array(items));
+        insert("containers", Some(self.containers.iter().map(Container::to_json).collect()));
+        insert("initContainers", list(self.init_containers.iter().map(Container::to_json).collect()));
+        insert("enableServiceLinks", Some(self.enable_service_links.into()));
+        insert("nodeSelector", (!self.node_selector.is_empty()).then(|| json!(self.node_selector)));
+        insert("nodeName", self.node_name.clone().map(Value::from));
+        insert("tolerations", list(self.tolerations.iter().map(Toleration::to_json).collect()));
+        insert("schedulerName", self.scheduler_name.clone().map(Value::from));
+        insert("priorityClassName", self.priority_class_name.clone().map(Value::from));
+        insert("priority", self.priority.map(Value::from));
+        insert("restartPolicy", Some(self.restart_policy.as_str().into()));
+        insert("terminationGracePeriodSeconds",
+            self.termination_grace_period_seconds.map(Value::from));
+        insert("activeDeadlineSeconds", self.active_deadline_seconds.map(Value::from));
+        insert("hostname", self.hostname.clone().map(Value::from));
+        insert("subdomain", self.subdomain.clone().map(Value::from));
+        insert("hostNetwork", self.host_network.then_some(Value::Bool(true)));
+        insert("serviceAccountName", self.service_account_name.clone().map(Value::from));
+        insert("hostUsers", (!self.host_users).then_some(Value::Bool(false)));
+        Value::Object(object)
+    }
 }
 
 /// - Concepts <https://kubernetes.io/docs/concepts/containers/>
 /// - Reference <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#Container>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Container {
     /// Name of the container specified as a DNS_LABEL. Each container in a pod must have a unique name (DNS_LABEL). Cannot be updated.
-    #[kfl(argument)]
-    name: String,
+    pub name: String,
 
     // Image
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#image>
@@ -182,13 +581,11 @@ pub struct Container {
     /// More info: <https://kubernetes.io/docs/concepts/containers/images/>
     ///
     /// This field is optional to allow higher level config management to default or override container images in workload controllers like Deployments and StatefulSets.
-    #[kfl(property)]
-    image: Option<String>,
+    pub image: Option<String>,
     /// Image pull policy. One of `Always`, `Never`, `IfNotPresent`. Defaults to `Always` if `:latest` tag is specified, or `IfNotPresent` otherwise. Cannot be updated.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/containers/images/#updating-images>
-    #[kfl(property, default = ImagePullPolicy::Always)]
-    image_pull_policy: ImagePullPolicy,
+    pub image_pull_policy: ImagePullPolicy,
 
     // Entrypoint
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#entrypoint>
@@ -196,19 +593,19 @@ pub struct Container {
     /// Entrypoint array. Not executed within a shell. The container image's `ENTRYPOINT` is used if this is not provided. Variable references `$(VAR_NAME)` are expanded using the container's environment. If a variable cannot be resolved, the reference in the input string will be unchanged. Double `$$` are reduced to a single `$`, which allows for escaping the `$(VAR_NAME)` syntax: i.e. `"$$(VAR_NAME)"` will produce the string literal `"$(VAR_NAME)"`. Escaped references will never be expanded, regardless of whether the variable exists or not. Cannot be updated.
     ///
     /// More info: [Define a Command and Arguments for a Container](https://kubernetes.io/docs/tasks/inject-data-application/define-command-argument-container/#running-a-command-in-a-shell)
-    command: Vec<String>,
+    pub command: Vec<String>,
     /// Arguments to the entrypoint. The container image's `CMD` is used if this is not provided. Variable references `$(VAR_NAME)` are expanded using the container's environment. If a variable cannot be resolved, the reference in the input string will be unchanged. Double `$$` are reduced to a single `$`, which allows for escaping the `$(VAR_NAME)` syntax: i.e. `"$$(VAR_NAME)"` will produce the string literal `"$(VAR_NAME)"`. Escaped references will never be expanded, regardless of whether the variable exists or not. Cannot be updated.
     ///
     /// More info: [Define a Command and Arguments for a Container](https://kubernetes.io/docs/tasks/inject-data-application/define-command-argument-container/#running-a-command-in-a-shell)
-    args: Vec<String>,
+    pub args: Vec<String>,
     /// Container's working directory. If not specified, the container runtime's default will be used, which might be configured in the container image. Cannot be updated.
-    working_dir: Option<PathBuf>,
+    pub working_dir: Option<PathBuf>,
 
     // Ports
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#ports>
 
     /// List of ports to expose from the container. Not specifying a port here DOES NOT prevent that port from being exposed. Any port which is listening on the default `"0.0.0.0"` address inside a container will be accessible from the network. Modifying this array with strategic merge patch may corrupt the data. For more information See <https://github.com/kubernetes/kubernetes/issues/108255>. Cannot be updated.
-    ports: Vec<Port>,
+    pub ports: Vec<Port>,
 
     /*
     Environment Variables
@@ -216,19 +613,17 @@ pub struct Container {
     */
 
     /// List of environment variables to set in the container. Cannot be updated.
-    env: Vec<EnvVar>,
+    pub env: Vec<EnvVar>,
     /// List of sources to populate environment variables in the container. The keys defined within a source must be a `C_IDENTIFIER`. All invalid keys will be reported as an event when the container is starting. When a key exists in multiple sources, the value associated with the last source will take precedence. Values defined by an Env with a duplicate key will take precedence. Cannot be updated.
-    env_from: Vec<EnvFromSource>,
+    pub env_from: Vec<EnvFromSource>,
 
     // Volumes
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#volumes-1>
 
     /// Pod volumes to mount into the container's filesystem. Cannot be updated.
-    #[kfl(children)]
-    volume_mounts: Vec<VolumeMount>,
+    pub volume_mounts: Vec<VolumeMount>,
     /// `volume_devices` is the list of block devices to be used by the container.
-    #[kfl(children)]
-    volume_devices: Vec<VolumeDevice>,
+    pub volume_devices: Vec<VolumeDevice>,
 
     // Resources
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#resources>
@@ -236,54 +631,222 @@ pub struct Container {
     /// Compute Resources required by this container. Cannot be updated.
     ///
     /// More info: [Resource Management for Pods and Containers](https://kubernetes.io/docs/concepts/configuration/manage-resources-containers/)
-    resources: Option<Resource>,
+    pub resources: Option<Resource>,
+    /// Restart policy of an init container. `Always` makes it a sidecar, which keeps running alongside the containers once started, and is restarted when it exits. Cannot be set on other containers.
+    ///
+    /// More info: <https://kubernetes.io/docs/concepts/workloads/pods/sidecar-containers/>
+    pub restart_policy: Option<RestartPolicy>,
 
     // Lifecycle
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#lifecycle-1>
 
     /// Actions that the management system should take in response to container lifecycle events. Cannot be updated.
-    lifecycle: Option<Lifecycle>,
+    pub lifecycle: Option<Lifecycle>,
     /// Optional: Path at which the file to which the container's termination message will be written is mounted into the container's filesystem. Message written is intended to be brief final status, such as an assertion failure message. Will be truncated by the node if greater than 4096 bytes. The total message length across all containers will be limited to 12kb. Defaults to `"/dev/termination-log"`. Cannot be updated.
-    #[kfl(default = "/dev/termination-log".into())]
-    termination_message_path: PathBuf,
+    pub termination_message_path: PathBuf,
     /// Indicate how the termination message should be populated. File will use the contents of [`termination_message_path`][Self::termination_message_path] to populate the container status message on both success and failure. `FallbackToLogsOnError` will use the last chunk of container log output if the termination message file is empty and the container exited with an error. The log output is limited to 2048 bytes or 80 lines, whichever is smaller. Defaults to `File`. Cannot be updated.
-    #[kfl(default = TerminationMessagePolicy::File)]
-    termination_message_policy: TerminationMessagePolicy,
+    pub termination_message_policy: TerminationMessagePolicy,
     /// Periodic probe of container liveness. Container will be restarted if the probe fails. Cannot be updated.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/workloads/pods/pod-lifecycle/#container-probes>
-    liveness_probe: Option<Probe>,
+    pub liveness_probe: Option<Probe>,
     /// Periodic probe of container service readiness. Container will be removed from service endpoints if the probe fails. Cannot be updated.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/workloads/pods/pod-lifecycle/#container-probes>
-    readiness_probe: Option<Probe>,
+    pub readiness_probe: Option<Probe>,
     /// StartupProbe indicates that the Pod has successfully initialised. If specified, no other probes are executed until this completes successfully. If this probe fails, the Pod will be restarted, just as if the [`liveness_probe`][Self::liveness_probe] failed. This can be used to provide different probe parameters at the beginning of a Pod's lifecycle, when it might take a long time to load data or warm a cache, than during steady-state operation. This cannot be updated.
     ///
     /// More info: <https://kubernetes.io/docs/concepts/workloads/pods/pod-lifecycle/#container-probes>
-    startup_probe: Option<Probe>,
+    pub startup_probe: Option<Probe>,
 
     // Security Context
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#security-context-1>
     /// `security_context` defines the security options the container should be run with. If set, the fields of [`SecurityContext`][SecurityContext] override the equivalent fields of [`PodSecurityContext`][PodSecurityContext].
     ///
     /// More info: [Configure a Security Context for a Pod or Container](https://kubernetes.io/docs/tasks/configure-pod-container/security-context/)
-    security_context: Option<SecurityContext>,
+    pub security_context: Option<SecurityContext>,
     
     // Debugging
     // <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#debugging>
 
     /// Whether this container should allocate a buffer for stdin in the container runtime. If this is not set, reads from stdin in the container will always result in EOF. Default is `false`.
-    #[kfl(default)]
-    stdin: bool,
+    pub stdin: bool,
     /// Whether the container runtime should close the stdin channel after it has been opened by a single attach. When stdin is true the stdin stream will remain open across multiple attach sessions. If stdinOnce is set to true, stdin is opened on container start, is empty until the first client attaches to stdin, and then remains open and accepts data until the client disconnects, at which time stdin is closed and remains closed until the container is restarted. If this flag is false, a container processes that reads from stdin will never receive an EOF. Default is `false`.
-    #[kfl(default)]
-    stdin_once: bool,
+    pub stdin_once: bool,
     /// Whether this container should allocate a TTY for itself, also requires 'stdin' to be true. Default is `false`.
-    #[kfl(default)]
-    tty: bool,
+    pub tty: bool,
+}
+
+impl Decode for Container {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let container = Self {
+            name: fields.argument()?.ok_or_else(|| fields.missing("name"))?,
+            image: fields.property("image")?,
+            image_pull_policy: fields.property("image-pull-policy")?.unwrap_or(ImagePullPolicy::Always),
+            command: fields.values("command")?,
+            args: fields.values("args")?,
+            working_dir: fields.property("working-dir")?,
+            ports: fields.children("port")?,
+            env: fields.children("env-var")?,
+            env_from: fields.children("env-from-source")?,
+            volume_mounts: fields.children("volume-mount")?,
+            volume_devices: fields.children("volume-device")?,
+            resources: fields.child("resources")?,
+            restart_policy: fields.property("restart-policy")?,
+            lifecycle: fields.child("lifecycle")?,
+            termination_message_path: fields.property("termination-message-path")?.unwrap_or_else(|| "/dev/termination-log".into()),
+            termination_message_policy: fields.property("termination-message-policy")?.unwrap_or(TerminationMessagePolicy::File),
+            liveness_probe: fields.child("liveness-probe")?,
+            readiness_probe: fields.child("readiness-probe")?,
+            startup_probe: fields.child("startup-probe")?,
+            security_context: fields.child("security-context")?,
+            stdin: fields.property("stdin")?.unwrap_or_default(),
+            stdin_once: fields.property("stdin-once")?.unwrap_or_default(),
+            tty: fields.property("tty")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(container)
+    }
+}
+
+/// The defaults of the fields, as when decoded.
+impl Default for Container {
+    fn default() -> Self {
+        Self {
+            name: Default::default(),
+            image: Default::default(),
+            image_pull_policy: ImagePullPolicy::Always,
+            command: Default::default(),
+            args: Default::default(),
+            working_dir: Default::default(),
+            ports: Default::default(),
+            env: Default::default(),
+            env_from: Default::default(),
+            volume_mounts: Default::default(),
+            volume_devices: Default::default(),
+            resources: Default::default(),
+            restart_policy: Default::default(),
+            lifecycle: Default::default(),
+            termination_message_path: "/dev/termination-log".into(),
+            termination_message_policy: TerminationMessagePolicy::File,
+            liveness_probe: Default::default(),
+            readiness_probe: Default::default(),
+            startup_probe: Default::default(),
+            security_context: Default::default(),
+            stdin: Default::default(),
+            stdin_once: Default::default(),
+            tty: Default::default()
+        }
+    }
+}
+
+impl Container {
+    /// The requests of the container, defaulting to its limits as the API server defaults them.
+    pub fn requests(&self) -> Result<Amounts, quantity::Error> {
+        let Some(resources) = &self.resources else { return Ok(Amounts::new()) };
+        let mut requests = quantity::parse_all(&resources.limits)?;
+        requests.extend(quantity::parse_all(&resources.requests)?);
+        Ok(requests)
+    }
+
+    pub fn limits(&self) -> Result<Amounts, quantity::Error> {
+        match &self.resources {
+            Some(resources) => quantity::parse_all(&resources.limits),
+            None => Ok(Amounts::new())
+        }
+    }
+
+    /// A container in JSON, with its image, command, ports, environment variables of plain values, resources and restart policy. The other fields are left to their defaults.
+    pub fn from_json(value: &Value) -> Self {
+        let string = |value: &Value, key: &str| value.get(key).and_then(Value::as_str)
+            .map(str::to_owned);
+        let array = |key: &str| value.get(key).and_then(Value::as_array).into_iter().flatten();
+        let strings = |key: &str| array(key)
+            .filter_map(|value| Some(value.as_str()?.to_owned()))
+            .collect();
+        let quantities = |resources: &Value, key: &str| resources.get(key)
+            .and_then(Value::as_object).into_iter().flatten()
+            .filter_map(|(name, quantity)| Some((name.clone(), quantity.as_str()?.to_owned())))
+            .collect();
+        let port = |port: &Value| Port {
+            container_port: port.get("containerPort").and_then(Value::as_u64)
+                .and_then(|port| u16::try_from(port).ok())
+                .unwrap_or_default(),
+            host_ip: string(port, "hostIP"),
+            host_port: port.get("hostPort").and_then(Value::as_u64)
+                .and_then(|port| u16::try_from(port).ok()),
+            name: string(port, "name"),
+            protocol: string(port, "protocol").and_then(|protocol| protocol.parse().ok())
+                .unwrap_or_default()
+        };
+        Self {
+            name: string(value, "name").unwrap_or_default(),
+            image: string(value, "image"),
+            image_pull_policy: string(value, "imagePullPolicy")
+                .and_then(|policy| policy.parse().ok())
+                .unwrap_or_default(),
+            command: strings("command"),
+            args: strings("args"),
+            working_dir: string(value, "workingDir").map(PathBuf::from),
+            ports: array("ports").map(port).collect(),
+            env: array("env").map(|env| EnvVar {
+                name: string(env, "name").unwrap_or_default(),
+                value: string(env, "value"),
+                value_from: None
+            }).collect(),
+            resources: value.get("resources").map(|resources| Resource {
+                limits: quantities(resources, "limits"),
+                requests: quantities(resources, "requests")
+            }),
+            restart_policy: string(value, "restartPolicy").and_then(|policy| policy.parse().ok()),
+            ..Self::default()
+        }
+    }
+
+    /// The fields [`from_json`][Self::from_json] reads, in JSON.
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        let mut insert = |key: &str, value: Option<Value>| if let Some(value) = value {
+            object.insert(key.to_owned(), value);
+        };
+        insert("name", Some(self.name.clone().into()));
+        insert("image", self.image.clone().map(Value::from));
+        insert("imagePullPolicy", Some(self.image_pull_policy.as_str().into()));
+        insert("command", (!self.command.is_empty()).then(|| json!(self.command)));
+        insert("args", (!self.args.is_empty()).then(|| json!(self.args)));
+        insert("workingDir", self.working_dir.as_ref()
+            .map(|dir| dir.to_string_lossy().into_owned().into()));
+        insert("ports", (!self.ports.is_empty()).then(|| self.ports.iter().map(|port| {
+            let mut value = json!({
+                "containerPort": port.container_port,
+                "protocol": port.protocol.as_str()
+            });
+            if let Some(name) = &port.name {
+                value["name"] = name.clone().into();
+            }
+            if let Some(host_ip) = &port.host_ip {
+                value["hostIP"] = host_ip.clone().into();
+            }
+            if let Some(host_port) = port.host_port {
+                value["hostPort"] = host_port.into();
+            }
+            value
+        }).collect()));
+        insert("env", (!self.env.is_empty()).then(|| self.env.iter().map(|env| match &env.value {
+            Some(value) => json!({ "name": env.name, "value": value }),
+            None => json!({ "name": env.name })
+        }).collect()));
+        insert("resources", self.resources.as_ref().map(|resources| json!({
+            "limits": resources.limits,
+            "requests": resources.requests
+        })));
+        insert("restartPolicy", self.restart_policy.map(|policy| policy.as_str().into()));
+        Value::Object(object)
+    }
 }
 
-#[derive(Debug, DecodeScalar, Default)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ImagePullPolicy {
     #[default]
     Always,
@@ -291,88 +854,165 @@ pub enum ImagePullPolicy {
     IfNotPresent
 }
 
+impl ImagePullPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Always => "Always",
+            Self::Never => "Never",
+            Self::IfNotPresent => "IfNotPresent"
+        }
+    }
+}
+
+impl FromStr for ImagePullPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Always" => Ok(Self::Always),
+            "Never" => Ok(Self::Never),
+            "IfNotPresent" => Ok(Self::IfNotPresent),
+            _ => Err(format!("unknown image pull policy {}", s))
+        }
+    }
+}
+
 /// Port represents a network port in a single container.
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Port {
     /// Number of port to expose on the pod's IP address.
     #[kfl(property)]
-    container_port: u16,
+    pub container_port: u16,
     /// What host IP to bind the external port to.
     #[kfl(property, default)]
-    host_ip: Option<String>,
+    pub host_ip: Option<String>,
     /// Number of port to expose on the host. If HostNetwork is specified, this must match Port. Most containers do not need this.
     #[kfl(property, default)]
-    host_port: Option<u16>,
+    pub host_port: Option<u16>,
     /// If specified, this must be an `IANA_SVC_NAME` and unique within the pod. Each named port in a pod must have a unique name. Name for the port that can be referred to by services.
     #[kfl(property, default)]
-    name: Option<String>,
+    pub name: Option<String>,
     /// Protocol for port. Must be UDP, TCP, or SCTP. Defaults to `TCP`.
     #[kfl(property, default = Protocol::Tcp)]
-    protocol: Protocol
+    pub protocol: Protocol
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct EnvVar {
-    name: String,
-    value: Option<String>,
-    value_from: Option<EnvVarSource>,
+    pub name: String,
+    pub value: Option<String>,
+    pub value_from: Option<EnvVarSource>,
 }
 
-#[derive(Debug, Decode)]
+impl Decode for EnvVar {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let env_var = Self {
+            name: fields.property("name")?.unwrap_or_default(),
+            value: fields.property("value")?,
+            value_from: fields.child("value-from")?
+        };
+        fields.finish()?;
+        Ok(env_var)
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct EnvVarSource {
-    config_map_key_ref: Option<ConfigMapKeySelector>,
-    field_ref: Option<FieldSelector>,
-    resource_field_ref: Option<ResourceFieldSelector>,
-    secret_key_ref: Option<SecretKeySelector>
+    pub config_map_key_ref: Option<ConfigMapKeySelector>,
+    pub field_ref: Option<FieldSelector>,
+    pub resource_field_ref: Option<ResourceFieldSelector>,
+    pub secret_key_ref: Option<SecretKeySelector>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for EnvVarSource {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let env_var_source = Self {
+            config_map_key_ref: fields.child("config-map-key-ref")?,
+            field_ref: fields.child("field-ref")?,
+            resource_field_ref: fields.child("resource-field-ref")?,
+            secret_key_ref: fields.child("secret-key-ref")?
+        };
+        fields.finish()?;
+        Ok(env_var_source)
+    }
+}
+
+#[derive(Debug, Decode, Default)]
 pub struct ConfigMapKeySelector {
-    key: String,
-    name: Option<String>,
-    optional: Option<bool>
+    #[kfl(property, default)]
+    pub key: String,
+    #[kfl(property, default)]
+    pub name: Option<String>,
+    #[kfl(property, default)]
+    pub optional: Option<bool>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct SecretKeySelector {
-    key: String,
-    name: Option<String>,
-    optional: Option<bool>
+    #[kfl(property, default)]
+    pub key: String,
+    #[kfl(property, default)]
+    pub name: Option<String>,
+    #[kfl(property, default)]
+    pub optional: Option<bool>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct EnvFromSource {
-    config_map_ref: Option<ConfigMapEnvSource>,
-    prefix: Option<String>,
-    secret_ref: Option<SecretEnvSource>
+    pub config_map_ref: Option<ConfigMapEnvSource>,
+    pub prefix: Option<String>,
+    pub secret_ref: Option<SecretEnvSource>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for EnvFromSource {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let env_from_source = Self {
+            config_map_ref: fields.child("config-map-ref")?,
+            prefix: fields.property("prefix")?,
+            secret_ref: fields.child("secret-ref")?
+        };
+        fields.finish()?;
+        Ok(env_from_source)
+    }
+}
+
+#[derive(Debug, Decode, Default)]
 pub struct ConfigMapEnvSource {
-    name: Option<String>,
-    optional: Option<bool>
+    #[kfl(property, default)]
+    pub name: Option<String>,
+    #[kfl(property, default)]
+    pub optional: Option<bool>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct SecretEnvSource {
-    name: Option<String>,
-    optional: Option<bool>
+    #[kfl(property, default)]
+    pub name: Option<String>,
+    #[kfl(property, default)]
+    pub optional: Option<bool>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct VolumeMount {
     #[kfl(argument)]
-    name: String,
+    pub name: String,
     #[kfl(property)]
-    path: PathBuf,
-    mount_propagation: Option<MountPropagation>,
-    read_only: Option<bool>,
-    sub_path: Option<String>,
-    sub_path_expr: Option<String>
+    pub path: PathBuf,
+    #[kfl(property, default)]
+    pub mount_propagation: Option<MountPropagation>,
+    #[kfl(property, default)]
+    pub read_only: Option<bool>,
+    #[kfl(property, default)]
+    pub sub_path: Option<String>,
+    #[kfl(property, default)]
+    pub sub_path_expr: Option<String>
 }
 
 /// <https://kubernetes.io/docs/concepts/storage/volumes/#mount-propagation>
-#[derive(Debug, Decode, Default)]
+#[derive(Debug, Clone, DecodeScalar, Default)]
 pub enum MountPropagation {
     #[default]
     None,
@@ -380,110 +1020,226 @@ pub enum MountPropagation {
     Bidirectional
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct VolumeDevice {
-    device_path: PathBuf,
-    name: String
+    #[kfl(property, default)]
+    pub device_path: PathBuf,
+    #[kfl(property, default)]
+    pub name: String
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Resource {
-    limits: HashMap<String, Quantity>,
-    requests: HashMap<String, Quantity>
+    pub limits: HashMap<String, Quantity>,
+    pub requests: HashMap<String, Quantity>
 }
 
-#[derive(Debug, Decode, Default)]
+impl Decode for Resource {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let resource = Self {
+            limits: fields.entries("limits")?,
+            requests: fields.entries("requests")?
+        };
+        fields.finish()?;
+        Ok(resource)
+    }
+}
+
+#[derive(Debug, Clone, DecodeScalar, Default)]
 pub enum TerminationMessagePolicy {
     FallbackToLogsOnError,
     #[default]
     File
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Lifecycle {
-    post_start: Option<LifecycleHandler>,
-    pre_stop: Option<LifecycleHandler>,
+    pub post_start: Option<LifecycleHandler>,
+    pub pre_stop: Option<LifecycleHandler>,
 }
 
-#[derive(Debug, Decode)]
+impl Decode for Lifecycle {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let lifecycle = Self {
+            post_start: fields.child("post-start")?,
+            pre_stop: fields.child("pre-stop")?
+        };
+        fields.finish()?;
+        Ok(lifecycle)
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct LifecycleHandler {
-    exec: Option<ExecAction>,
-    http_get: Option<HTTPGetAction>,
-    tcp_socket: Option<TCPSocketAction>,
+    pub exec: Option<ExecAction>,
+    pub http_get: Option<HTTPGetAction>,
+    pub tcp_socket: Option<TCPSocketAction>,
 }
 
-#[derive(Debug, Decode)]
+impl Decode for LifecycleHandler {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let lifecycle_handler = Self {
+            exec: fields.child("exec")?,
+            http_get: fields.child("http-get")?,
+            tcp_socket: fields.child("tcp-socket")?
+        };
+        fields.finish()?;
+        Ok(lifecycle_handler)
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct ExecAction {
-    command: Vec<String>
+    pub command: Vec<String>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for ExecAction {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let exec_action = Self {
+            command: fields.values("command")?
+        };
+        fields.finish()?;
+        Ok(exec_action)
+    }
+}
+
+#[derive(Debug, Decode, Default)]
 pub struct HTTPGetAction {
-    port: u16,
-    host: Option<String>,
-    http_headers: Vec<HTTPHeader>,
-    path: Option<PathBuf>,
-    scheme: Option<String>
+    #[kfl(property, default)]
+    pub port: u16,
+    #[kfl(property, default)]
+    pub host: Option<String>,
+    #[kfl(children)]
+    pub http_headers: Vec<HTTPHeader>,
+    #[kfl(property, default)]
+    pub path: Option<PathBuf>,
+    #[kfl(property, default)]
+    pub scheme: Option<String>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct HTTPHeader {
-    name: String,
-    value: String
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub value: String
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct TCPSocketAction {
-    port: u16,
-    host: Option<String>
+    #[kfl(property, default)]
+    pub port: u16,
+    #[kfl(property, default)]
+    pub host: Option<String>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Probe {
-    exec: Option<ExecAction>,
-    http_get: Option<HTTPGetAction>,
-    tcp_socket: Option<TCPSocketAction>,
-    initial_delay_seconds: Option<i32>,
-    termination_grace_period_seconds: Option<i64>,
-    period_seconds: Option<i32>,
-    timeout_seconds: Option<i32>,
-    failure_threshold: Option<i32>,
-    success_threshold: Option<i32>,
-    grpc: Option<GRPCAction>,
+    pub exec: Option<ExecAction>,
+    pub http_get: Option<HTTPGetAction>,
+    pub tcp_socket: Option<TCPSocketAction>,
+    pub initial_delay_seconds: Option<i32>,
+    pub termination_grace_period_seconds: Option<i64>,
+    pub period_seconds: Option<i32>,
+    pub timeout_seconds: Option<i32>,
+    pub failure_threshold: Option<i32>,
+    pub success_threshold: Option<i32>,
+    pub grpc: Option<GRPCAction>,
 }
 
-#[derive(Debug, Decode)]
+impl Decode for Probe {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let probe = Self {
+            exec: fields.child("exec")?,
+            http_get: fields.child("http-get")?,
+            tcp_socket: fields.child("tcp-socket")?,
+            initial_delay_seconds: fields.property("initial-delay-seconds")?,
+            termination_grace_period_seconds: fields.property("termination-grace-period-seconds")?,
+            period_seconds: fields.property("period-seconds")?,
+            timeout_seconds: fields.property("timeout-seconds")?,
+            failure_threshold: fields.property("failure-threshold")?,
+            success_threshold: fields.property("success-threshold")?,
+            grpc: fields.child("grpc")?
+        };
+        fields.finish()?;
+        Ok(probe)
+    }
+}
+
+#[derive(Debug, Decode, Default)]
 pub struct GRPCAction {
-    port: u16,
-    service: Option<String>
+    #[kfl(property, default)]
+    pub port: u16,
+    #[kfl(property, default)]
+    pub service: Option<String>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct SecurityContext {
-    run_as_user: Option<i64>,
-    run_as_non_root: Option<bool>,
-    run_as_group: Option<i64>,
-    read_only_root_filesystem: Option<bool>,
-    proc_mount: Option<String>,
-    privileged: Option<bool>,
-    allow_privilege_escalation: Option<bool>,
-    capabilities: Option<Capabilities>,
-    seccomp_profile: Option<SeccompProfile>,
-    se_linux_options: Option<SELinuxOptions>,
-    windows_options: Option<WindowsSecurityContextOptions>
+    pub run_as_user: Option<i64>,
+    pub run_as_non_root: Option<bool>,
+    pub run_as_group: Option<i64>,
+    pub read_only_root_filesystem: Option<bool>,
+    pub proc_mount: Option<String>,
+    pub privileged: Option<bool>,
+    pub allow_privilege_escalation: Option<bool>,
+    pub capabilities: Option<Capabilities>,
+    pub seccomp_profile: Option<SeccompProfile>,
+    pub se_linux_options: Option<SELinuxOptions>,
+    pub windows_options: Option<WindowsSecurityContextOptions>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for SecurityContext {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let security_context = Self {
+            run_as_user: fields.property("run-as-user")?,
+            run_as_non_root: fields.property("run-as-non-root")?,
+            run_as_group: fields.property("run-as-group")?,
+            read_only_root_filesystem: fields.property("read-only-root-filesystem")?,
+            proc_mount: fields.property("proc-mount")?,
+            privileged: fields.property("privileged")?,
+            allow_privilege_escalation: fields.property("allow-privilege-escalation")?,
+            capabilities: fields.child("capabilities")?,
+            seccomp_profile: fields.child("seccomp-profile")?,
+            se_linux_options: fields.child("se-linux-options")?,
+            windows_options: fields.child("windows-options")?
+        };
+        fields.finish()?;
+        Ok(security_context)
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct Capabilities {
-    add: Vec<String>,
-    drop: Vec<String>,
+    pub add: Vec<String>,
+    pub drop: Vec<String>,
+}
+
+impl Decode for Capabilities {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let capabilities = Self {
+            add: fields.values("add")?,
+            drop: fields.values("drop")?
+        };
+        fields.finish()?;
+        Ok(capabilities)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#EphemeralContainer>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct EphemeralContainer {
-    name: String,
-    target_container_name: Option<String>
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub target_container_name: Option<String>
 }
 
 #[derive(Debug, Decode)]
@@ -491,129 +1247,289 @@ pub struct PodOS {
     /// Name is the name of the operating system. The currently supported values are `Linux` and `Windows`. Additional value may be defined in future and can be one of: <https://github.com/opencontainers/runtime-spec/blob/master/config.md#platform-specific-configuration>
     ///
     /// Clients should expect to handle additional values and treat unrecognised values in this field as os: null
-    name: PodOSName
+    #[kfl(property, default)]
+    pub name: PodOSName
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, DecodeScalar, Default)]
 pub enum PodOSName {
+    #[default]
     Linux,
     Windows
 }
 
 /// Affinity is a group of affinity scheduling rules.
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Affinity {
     /// Describes node affinity scheduling rules for the pod.
-    node_affinity: Option<NodeAffinity>,
+    #[kfl(child, default)]
+    pub node_affinity: Option<NodeAffinity>,
     /// Describes pod affinity scheduling rules (e.g. co-locate this pod in the same node, zone, etc. as some other pod(s)).
-    pod_affinity: Option<PodAffinity>,
+    #[kfl(child, default)]
+    pub pod_affinity: Option<PodAffinity>,
     /// Describes pod anti-affinity scheduling rules (e.g. avoid putting this pod in the same node, zone, etc. as some other pod(s)).
-    pod_anti_affinity: Option<PodAntiAffinity>
+    #[kfl(child, default)]
+    pub pod_anti_affinity: Option<PodAntiAffinity>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#NodeAffinity>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct NodeAffinity {
-    preferred_during_scheduling_ignored_during_execution:
+    pub preferred_during_scheduling_ignored_during_execution:
         Vec<PreferredSchedulingTerm>,
-    required_during_scheduling_ignored_during_execution: Option<NodeSelector>
+    pub required_during_scheduling_ignored_during_execution: Option<NodeSelector>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for NodeAffinity {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let node_affinity = Self {
+            preferred_during_scheduling_ignored_during_execution: fields.children("preferred-scheduling-term")?,
+            required_during_scheduling_ignored_during_execution: fields.child("required-during-scheduling-ignored-during-execution")?
+        };
+        fields.finish()?;
+        Ok(node_affinity)
+    }
+}
+
+#[derive(Debug)]
 pub struct PreferredSchedulingTerm {
-    preference: NodeSelectorTerm,
-    weight: i32
+    pub preference: NodeSelectorTerm,
+    pub weight: i32
+}
+
+impl Decode for PreferredSchedulingTerm {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let preferred_scheduling_term = Self {
+            preference: fields.child("preference")?.ok_or_else(|| fields.missing("preference"))?,
+            weight: fields.property("weight")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(preferred_scheduling_term)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#PodAffinity>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct PodAffinity {
-    preferred_during_scheduling_ignored_during_execution:
+    #[kfl(children)]
+    pub preferred_during_scheduling_ignored_during_execution:
         Vec<WeightedPodAffinityTerm>,
-    required_during_scheduling_ignored_during_execution:
+    #[kfl(children)]
+    pub required_during_scheduling_ignored_during_execution:
         Vec<PodAffinityTerm>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct WeightedPodAffinityTerm {
-    pod_affinity_term: PodAffinityTerm,
-    weight: i32
+    #[kfl(child, default)]
+    pub pod_affinity_term: PodAffinityTerm,
+    #[kfl(property, default)]
+    pub weight: i32
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct PodAffinityTerm {
-    topology_key: String,
-    label_selector: Option<Selector>,
-    namespace_selector: Option<Selector>,
-    namespaces: Vec<String>
+    pub topology_key: String,
+    pub label_selector: Option<Selector>,
+    pub namespace_selector: Option<Selector>,
+    pub namespaces: Vec<String>
+}
+
+impl Decode for PodAffinityTerm {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let pod_affinity_term = Self {
+            topology_key: fields.property("topology-key")?.unwrap_or_default(),
+            label_selector: fields.child("label-selector")?,
+            namespace_selector: fields.child("namespace-selector")?,
+            namespaces: fields.values("namespaces")?
+        };
+        fields.finish()?;
+        Ok(pod_affinity_term)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#PodAntiAffinity>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct PodAntiAffinity {
-    preferred_during_scheduling_ignored_during_execution:
+    #[kfl(children)]
+    pub preferred_during_scheduling_ignored_during_execution:
         Vec<WeightedPodAffinityTerm>,
-    required_during_scheduling_ignored_during_execution:
+    #[kfl(children)]
+    pub required_during_scheduling_ignored_during_execution:
         Vec<PodAffinityTerm>
 }
 
 /// The pod this Toleration is attached to tolerates any taint that matches the triple <key,value,effect> using the matching operator .
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Toleration {
     /// Key is the taint key that the toleration applies to. Empty means match all taint keys. If the key is empty, [`operator`][Self::operator] must be `Exists`; this combination means to match all values and all keys.
-    key: Option<String>,
+    #[kfl(property, default)]
+    pub key: Option<String>,
     /// `operator` represents a key's relationship to the value. Valid operators are `Exists` and `Equal`. Defaults to `Equal`. `Exists` is equivalent to wildcard for value, so that a pod can tolerate all taints of a particular category.
     // #[kfl(rename(serialize = "TolerationOperator::Equal"))]
-    operator: TolerationOperator,
+    #[kfl(property, default)]
+    pub operator: TolerationOperator,
     /// Value is the taint value the toleration matches to. If the [`operator`][Self::operator] is `Exists`, the value should be empty, otherwise just a regular string.
-    value: Option<String>,
+    #[kfl(property, default)]
+    pub value: Option<String>,
     /// `effect` indicates the taint effect to match. Empty means match all taint effects. When specified, allowed values are `NoSchedule`, `PreferNoSchedule` and `NoExecute`.
-    effect: Option<TaintEffect>,
+    #[kfl(property, default)]
+    pub effect: Option<TaintEffect>,
     /// `toleration_seconds` represents the period of time the toleration (which must be of effect `NoExecute`, otherwise this field is ignored) tolerates the taint. By default, it is not set, which means tolerate the taint forever (do not evict). Zero and negative values will be treated as 0 (evict immediately) by the system.
-    toleration_seconds: Option<u64>
+    #[kfl(property, default)]
+    pub toleration_seconds: Option<u64>
 }
 
-#[derive(Debug, Decode, Default)]
+impl Toleration {
+    /// Whether the toleration matches `taint`: its key, unless empty with `Exists`, its value, unless `Exists`, and its effect, unless empty.
+    pub fn tolerates(&self, taint: &Taint) -> bool {
+        let key = self.key.as_deref().unwrap_or_default();
+        self.effect.is_none_or(|effect| effect == taint.effect)
+            && (key.is_empty() && self.operator == TolerationOperator::Exists || key == taint.key)
+            && match self.operator {
+                TolerationOperator::Exists => true,
+                TolerationOperator::Equal => self.value.as_deref().unwrap_or_default()
+                    == taint.value.as_deref().unwrap_or_default()
+            }
+    }
+
+    pub fn from_json(value: &Value) -> Self {
+        let string = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_owned);
+        Self {
+            key: string("key"),
+            operator: string("operator").and_then(|operator| operator.parse().ok())
+                .unwrap_or_default(),
+            value: string("value"),
+            effect: string("effect").and_then(|effect| effect.parse().ok()),
+            toleration_seconds: value.get("tolerationSeconds").and_then(Value::as_u64)
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({ "operator": self.operator.as_str() });
+        if let Some(key) = &self.key {
+            value["key"] = key.clone().into();
+        }
+        if let Some(toleration_value) = &self.value {
+            value["value"] = toleration_value.clone().into();
+        }
+        if let Some(effect) = self.effect {
+            value["effect"] = effect.as_str().into();
+        }
+        if let Some(seconds) = self.toleration_seconds {
+            value["tolerationSeconds"] = seconds.into();
+        }
+        value
+    }
+}
+
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum TolerationOperator {
     Exists,
     #[default]
     Equal
 }
 
-#[derive(Debug, DecodeScalar)]
+impl TolerationOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exists => "Exists",
+            Self::Equal => "Equal"
+        }
+    }
+}
+
+impl FromStr for TolerationOperator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Exists" => Ok(Self::Exists),
+            "Equal" => Ok(Self::Equal),
+            _ => Err(format!("unknown toleration operator {}", s))
+        }
+    }
+}
+
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum TaintEffect {
+    #[default]
     NoSchedule,
     PreferNoSchedule,
     NoExecute
 }
 
-#[derive(Debug, Decode, Default)]
+impl TaintEffect {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoSchedule => "NoSchedule",
+            Self::PreferNoSchedule => "PreferNoSchedule",
+            Self::NoExecute => "NoExecute"
+        }
+    }
+}
+
+impl FromStr for TaintEffect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "NoSchedule" => Ok(Self::NoSchedule),
+            "PreferNoSchedule" => Ok(Self::PreferNoSchedule),
+            "NoExecute" => Ok(Self::NoExecute),
+            _ => Err(format!("unknown taint effect {}", s))
+        }
+    }
+}
+
+#[derive(Debug, Clone, DecodeScalar, Default)]
 pub enum PreemptionPolicy {
     Never,
     #[default]
     PreemptLowerPriority
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct TopologySpreadConstraint {
-    max_skew: i32,
-    topology_key: String,
-    when_unsatisfiable: WhenUnsatisfiable,
-    label_selector: Option<Selector>,
-    match_label_keys: Vec<String>,
-    min_domains: Option<i32>,
-    node_affinity_policy: Option<String>,
-    node_taints_policy: Option<String>,
+    pub max_skew: i32,
+    pub topology_key: String,
+    pub when_unsatisfiable: WhenUnsatisfiable,
+    pub label_selector: Option<Selector>,
+    pub match_label_keys: Vec<String>,
+    pub min_domains: Option<i32>,
+    pub node_affinity_policy: Option<String>,
+    pub node_taints_policy: Option<String>,
 }
 
-#[derive(Debug, Decode, Default)]
+impl Decode for TopologySpreadConstraint {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let topology_spread_constraint = Self {
+            max_skew: fields.property("max-skew")?.unwrap_or_default(),
+            topology_key: fields.property("topology-key")?.unwrap_or_default(),
+            when_unsatisfiable: fields.property("when-unsatisfiable")?.unwrap_or_default(),
+            label_selector: fields.child("label-selector")?,
+            match_label_keys: fields.values("match-label-keys")?,
+            min_domains: fields.property("min-domains")?,
+            node_affinity_policy: fields.property("node-affinity-policy")?,
+            node_taints_policy: fields.property("node-taints-policy")?
+        };
+        fields.finish()?;
+        Ok(topology_spread_constraint)
+    }
+}
+
+#[derive(Debug, Clone, DecodeScalar, Default)]
 pub enum WhenUnsatisfiable {
     #[default]
     DoNotSchedule,
     ScheduleAnyway
 }
 
-#[derive(Debug, Decode, Default)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum RestartPolicy {
     #[default]
     Always,
@@ -621,31 +1537,82 @@ pub enum RestartPolicy {
     Never
 }
 
-#[derive(Debug, Decode)]
+impl RestartPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Always => "Always",
+            Self::OnFailure => "OnFailure",
+            Self::Never => "Never"
+        }
+    }
+}
+
+impl FromStr for RestartPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Always" => Ok(Self::Always),
+            "OnFailure" => Ok(Self::OnFailure),
+            "Never" => Ok(Self::Never),
+            _ => Err(format!("unknown restart policy {}", s))
+        }
+    }
+}
+
+#[derive(Debug, Decode, Default)]
 pub struct ReadinessGate {
-    condition_type: String
+    #[kfl(property, default)]
+    pub condition_type: String
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct HostAlias {
-    hostnames: Vec<String>,
-    ip: Option<String>
+    pub hostnames: Vec<String>,
+    pub ip: Option<String>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for HostAlias {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let host_alias = Self {
+            hostnames: fields.values("hostnames")?,
+            ip: fields.property("ip")?
+        };
+        fields.finish()?;
+        Ok(host_alias)
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct DnsConfig {
-    nameservers: Vec<String>,
-    options: Vec<PodDNSConfigOption>,
-    searches: Vec<String>
+    pub nameservers: Vec<String>,
+    pub options: Vec<PodDNSConfigOption>,
+    pub searches: Vec<String>
 }
 
-#[derive(Debug, Decode)]
-pub struct PodDNSConfigOption {
-    name: String,
-    value: Option<String>,
+impl Decode for DnsConfig {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let dns_config = Self {
+            nameservers: fields.values("nameservers")?,
+            options: fields.children("pod-dns-config-option")?,
+            searches: fields.values("searches")?
+        };
+        fields.finish()?;
+        Ok(dns_config)
+    }
 }
 
 #[derive(Debug, Decode, Default)]
+pub struct PodDNSConfigOption {
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, DecodeScalar, Default)]
 pub enum DnsPolicy {
     ClusterFirstWithHostNet,
     #[default]
@@ -655,16 +1622,16 @@ pub enum DnsPolicy {
 }
 
 /// `PodSecurityContext` holds pod-level security attributes and common container settings. Some fields are also present in [`container.security_context`][Container::security_context]. Field values of [`container.security_context`][Container::security_context] take precedence over field values of `PodSecurityContext`.
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct PodSecurityContext {
     /// The UID to run the entrypoint of the container process. Defaults to user specified in image metadata if unspecified. May also be set in SecurityContext. If set in both SecurityContext and `PodSecurityContext`, the value specified in `SecurityContext` takes precedence for that container. Note that this field cannot be set when [`spec.os.name`][PodOS::name] is `Windows`.
-    run_as_user: Option<u32>,
+    pub run_as_user: Option<u32>,
     /// Indicates that the container must run as a non-root user. If `true`, the Kubelet will validate the image at runtime to ensure that it does not run as UID 0 (root) and fail to start the container if it does. If unset or `false`, no such validation will be performed. May also be set in `SecurityContext`. If set in both `SecurityContext` and `PodSecurityContext`, the value specified in `SecurityContext` takes precedence.
-    run_as_non_root: Option<bool>,
+    pub run_as_non_root: Option<bool>,
     /// The GID to run the entrypoint of the container process. Uses runtime default if unset. May also be set in `SecurityContext`. If set in both `SecurityContext` and `PodSecurityContext`, the value specified in `SecurityContext` takes precedence for that container. Note that this field cannot be set when [`spec.os.name`][PodOS::name] is `Windows`.
-    run_as_group: Option<u32>,
+    pub run_as_group: Option<u32>,
     /// A list of groups applied to the first process run in each container, in addition to the container's primary GID. If unspecified, no groups will be added to any container. Note that this field cannot be set when [`spec.os.name`][PodOS::name] is `Windows`.
-    supplemental_groups: Vec<i64>,
+    pub supplemental_groups: Vec<i64>,
     /**
     A special supplemental group that applies to all containers in a pod. Some volume types allow the Kubelet to change the ownership of that volume to be owned by the pod:
 
@@ -674,115 +1641,606 @@ pub struct PodSecurityContext {
 
     If unset, the Kubelet will not modify the ownership and permissions of any volume. Note that this field cannot be set when [`spec.os.name`][PodOS::name] is `Windows`.
     */
-    fs_group: Option<i64>,
+    pub fs_group: Option<i64>,
     /// `fs_group_change_policy` defines behaviour of changing ownership and permission of the volume before being exposed inside Pod. This field will only apply to volume types which support `fs_group` based ownership (and permissions). It will have no effect on ephemeral volume types such as: secret, configmaps and emptydir. Valid values are `OnRootMismatch` and `Always`. If not specified, `Always` is used. Note that this field cannot be set when [`spec.os.name`][PodOS::name] is `Windows`.
-    #[kfl(default = FSGroupChangePolicy::Always)]
-    fs_group_change_policy: FSGroupChangePolicy,
+    pub fs_group_change_policy: FSGroupChangePolicy,
     /// The seccomp options to use by the containers in this pod. Note that this field cannot be set when [`spec.os.name`][PodOS::name] is `Windows`.
-    seccomp_profile: Option<SeccompProfile>,
+    pub seccomp_profile: Option<SeccompProfile>,
     /// The SELinux context to be applied to all containers. If unspecified, the container runtime will allocate a random SELinux context for each container. May also be set in SecurityContext. If set in both SecurityContext and `PodSecurityContext`, the value specified in SecurityContext takes precedence for that container. Note that this field cannot be set when [`spec.os.name`][PodOS::name] is `Windows`.
-    se_linux_options: Option<SELinuxOptions>,
+    pub se_linux_options: Option<SELinuxOptions>,
     /// `sysctls` hold a list of namespaced sysctls used for the pod. Pods with unsupported sysctls (by the container runtime) might fail to launch. Note that this field cannot be set when [`spec.os.name`][PodOS::name] is `Windows`.
-    sysctls: Vec<Sysctl>,
-    windows_options: Option<WindowsSecurityContextOptions>
+    pub sysctls: Vec<Sysctl>,
+    pub windows_options: Option<WindowsSecurityContextOptions>
 }
 
-#[derive(Debug, Decode, Default)]
+impl Decode for PodSecurityContext {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let pod_security_context = Self {
+            run_as_user: fields.property("run-as-user")?,
+            run_as_non_root: fields.property("run-as-non-root")?,
+            run_as_group: fields.property("run-as-group")?,
+            supplemental_groups: fields.values("supplemental-groups")?,
+            fs_group: fields.property("fs-group")?,
+            fs_group_change_policy: fields.property("fs-group-change-policy")?.unwrap_or(FSGroupChangePolicy::Always),
+            seccomp_profile: fields.child("seccomp-profile")?,
+            se_linux_options: fields.child("se-linux-options")?,
+            sysctls: fields.children("sysctl")?,
+            windows_options: fields.child("windows-options")?
+        };
+        fields.finish()?;
+        Ok(pod_security_context)
+    }
+}
+
+#[derive(Debug, Clone, DecodeScalar, Default)]
 pub enum FSGroupChangePolicy {
     OnRootMismatch,
     #[default]
     Always
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct SeccompProfile {
-    r#type: String,
-    localhost_profile: Option<String>,
+    #[kfl(property, default)]
+    pub r#type: String,
+    #[kfl(property, default)]
+    pub localhost_profile: Option<String>,
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct SELinuxOptions {
-    level: Option<String>,
-    role: Option<String>,
-    r#type: Option<String>,
-    user: Option<String>,
+    #[kfl(property, default)]
+    pub level: Option<String>,
+    #[kfl(property, default)]
+    pub role: Option<String>,
+    #[kfl(property, default)]
+    pub r#type: Option<String>,
+    #[kfl(property, default)]
+    pub user: Option<String>,
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Sysctl {
-    name: String,
-    value: String
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub value: String
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct WindowsSecurityContextOptions {
-    gmsa_credential_spec: Option<String>,
-    gmsa_credential_spec_name: Option<String>,
-    host_process: Option<bool>,
-    run_as_user_name: Option<String>
+    #[kfl(property, default)]
+    pub gmsa_credential_spec: Option<String>,
+    #[kfl(property, default)]
+    pub gmsa_credential_spec_name: Option<String>,
+    #[kfl(property, default)]
+    pub host_process: Option<bool>,
+    #[kfl(property, default)]
+    pub run_as_user_name: Option<String>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-v1/#Status>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Status {
-    nominated_node_name: String,
-    host_ip: String,
-    start_time: Time,
-    phase: String,
-    message: String,
-    reason: String,
-    pod_ip: String,
-    pod_ips: Vec<PodIP>,
-    conditions: Vec<Condition>,
-    qos_class: Option<String>,
-    init_container_statuses: Vec<ContainerStatus>,
-    container_statuses: Vec<ContainerStatus>,
-    ephemeral_container_statuses: Vec<ContainerStatus>,
+    pub nominated_node_name: String,
+    pub host_ip: String,
+    pub start_time: Time,
+    pub phase: String,
+    pub message: String,
+    pub reason: String,
+    pub pod_ip: String,
+    pub pod_ips: Vec<PodIP>,
+    pub conditions: Vec<Condition<ConditionType>>,
+    pub qos_class: Option<QosClass>,
+    pub init_container_statuses: Vec<ContainerStatus>,
+    pub container_statuses: Vec<ContainerStatus>,
+    pub ephemeral_container_statuses: Vec<ContainerStatus>,
 }
 
-#[derive(Debug, Decode)]
+impl Decode for Status {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let status = Self {
+            nominated_node_name: fields.property("nominated-node-name")?.unwrap_or_default(),
+            host_ip: fields.property("host-ip")?.unwrap_or_default(),
+            start_time: fields.property("start-time")?.unwrap_or_default(),
+            phase: fields.property("phase")?.unwrap_or_default(),
+            message: fields.property("message")?.unwrap_or_default(),
+            reason: fields.property("reason")?.unwrap_or_default(),
+            pod_ip: fields.property("pod-ip")?.unwrap_or_default(),
+            pod_ips: fields.children("pod-ip")?,
+            conditions: fields.children("condition")?,
+            qos_class: fields.property("qos-class")?,
+            init_container_statuses: fields.children("init-container-status")?,
+            container_statuses: fields.children("container-status")?,
+            ephemeral_container_statuses: fields.children("ephemeral-container-status")?
+        };
+        fields.finish()?;
+        Ok(status)
+    }
+}
+
+impl Status {
+    /// The status of a pod in JSON, its conditions of unknown types left out.
+    pub fn from_json(value: &Value) -> Self {
+        let string = |key: &str| value.get(key).and_then(Value::as_str).unwrap_or_default()
+            .to_owned();
+        let array = |key: &str| value.get(key).and_then(Value::as_array).into_iter().flatten();
+        Self {
+            nominated_node_name: string("nominatedNodeName"),
+            host_ip: string("hostIP"),
+            start_time: string("startTime").parse().unwrap_or_default(),
+            phase: string("phase"),
+            message: string("message"),
+            reason: string("reason"),
+            pod_ip: string("podIP"),
+            pod_ips: array("podIPs").map(|ip| PodIP {
+                ip: ip.get("ip").and_then(Value::as_str).unwrap_or_default().to_owned()
+            }).collect(),
+            conditions: array("conditions").filter_map(Condition::from_json).collect(),
+            qos_class: string("qosClass").parse().ok(),
+            init_container_statuses: array("initContainerStatuses")
+                .map(ContainerStatus::from_json)
+                .collect(),
+            container_statuses: array("containerStatuses").map(ContainerStatus::from_json).collect(),
+            ephemeral_container_statuses: array("ephemeralContainerStatuses")
+                .map(ContainerStatus::from_json)
+                .collect()
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        let mut insert = |key: &str, value: &str| if !value.is_empty() {
+            object.insert(key.to_owned(), value.into());
+        };
+        insert("nominatedNodeName", &self.nominated_node_name);
+        insert("hostIP", &self.host_ip);
+        insert("phase", &self.phase);
+        insert("message", &self.message);
+        insert("reason", &self.reason);
+        insert("podIP", &self.pod_ip);
+        insert("qosClass", self.qos_class.map(|class| class.as_str()).unwrap_or_default());
+        object.insert("startTime".to_owned(), self.start_time.to_string().into());
+        let mut list = |key: &str, items: Vec<Value>| if !items.is_empty() {
+            object.insert(key.to_owned(), Value::Array(items));
+        };
+        list("podIPs", self.pod_ips.iter().map(|ip| json!({ "ip": ip.ip })).collect());
+        list("conditions", self.conditions.iter().map(Condition::to_json).collect());
+        list("initContainerStatuses",
+            self.init_container_statuses.iter().map(ContainerStatus::to_json).collect());
+        list("containerStatuses",
+            self.container_statuses.iter().map(ContainerStatus::to_json).collect());
+        list("ephemeralContainerStatuses",
+            self.ephemeral_container_statuses.iter().map(ContainerStatus::to_json).collect());
+        Value::Object(object)
+    }
+}
+
+/// <https://kubernetes.io/docs/concepts/workloads/pods/pod-qos/>
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
+pub enum QosClass {
+    /// Every container has equal CPU and memory requests and limits; evicted last.
+    Guaranteed,
+    /// Some container requests or limits CPU or memory.
+    Burstable,
+    /// No container requests or limits CPU or memory; evicted first.
+    BestEffort
+}
+
+impl QosClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Guaranteed => "Guaranteed",
+            Self::Burstable => "Burstable",
+            Self::BestEffort => "BestEffort"
+        }
+    }
+}
+
+impl FromStr for QosClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Guaranteed" => Ok(Self::Guaranteed),
+            "Burstable" => Ok(Self::Burstable),
+            "BestEffort" => Ok(Self::BestEffort),
+            _ => Err(format!("unknown QoS class {}", s))
+        }
+    }
+}
+
+/// <https://kubernetes.io/docs/concepts/workloads/pods/pod-lifecycle/#pod-conditions>
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionType {
+    /// The Pod has been scheduled to a node.
+    PodScheduled,
+    /// All init containers have completed successfully.
+    Initialized,
+    /// All containers in the Pod are ready.
+    ContainersReady,
+    /// The Pod is able to serve requests and should be added to the load balancing pools of all matching Services.
+    Ready,
+    /// The Pod is about to be terminated due to a disruption such as preemption, eviction or garbage collection.
+    DisruptionTarget
+}
+
+impl ConditionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PodScheduled => "PodScheduled",
+            Self::Initialized => "Initialized",
+            Self::ContainersReady => "ContainersReady",
+            Self::Ready => "Ready",
+            Self::DisruptionTarget => "DisruptionTarget"
+        }
+    }
+}
+
+impl fmt::Display for ConditionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ConditionType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "PodScheduled" => Ok(Self::PodScheduled),
+            "Initialized" => Ok(Self::Initialized),
+            "ContainersReady" => Ok(Self::ContainersReady),
+            "Ready" => Ok(Self::Ready),
+            "DisruptionTarget" => Ok(Self::DisruptionTarget),
+            _ => Err(format!("unknown condition type {}", s))
+        }
+    }
+}
+
+#[derive(Debug, Decode, Default)]
 pub struct PodIP {
-    ip: String
+    #[kfl(property, default)]
+    pub ip: String
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct ContainerStatus {
-    name: String,
-    image: String,
-    image_id: String,
-    container_id: Option<String>,
-    state: Option<ContainerState>,
-    last_state: Option<ContainerState>,
-    ready: bool,
-    restart_count: i32,
-    started: Option<bool>
+    pub name: String,
+    pub image: String,
+    pub image_id: String,
+    pub container_id: Option<String>,
+    pub state: Option<ContainerState>,
+    pub last_state: Option<ContainerState>,
+    pub ready: bool,
+    pub restart_count: i32,
+    pub started: Option<bool>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for ContainerStatus {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let container_status = Self {
+            name: fields.property("name")?.unwrap_or_default(),
+            image: fields.property("image")?.unwrap_or_default(),
+            image_id: fields.property("image-id")?.unwrap_or_default(),
+            container_id: fields.property("container-id")?,
+            state: fields.child("state")?,
+            last_state: fields.child("last-state")?,
+            ready: fields.property("ready")?.unwrap_or_default(),
+            restart_count: fields.property("restart-count")?.unwrap_or_default(),
+            started: fields.property("started")?
+        };
+        fields.finish()?;
+        Ok(container_status)
+    }
+}
+
+impl ContainerStatus {
+    pub fn from_json(value: &Value) -> Self {
+        let string = |value: &Value, key: &str| value.get(key).and_then(Value::as_str)
+            .map(str::to_owned);
+        let int = |value: &Value, key: &str| value.get(key).and_then(Value::as_i64)
+            .and_then(|value| i32::try_from(value).ok());
+        let time = |value: &Value, key: &str| string(value, key)
+            .and_then(|time| time.parse().ok());
+        let state = |state: &Value| ContainerState {
+            running: state.get("running").map(|running| ContainerStateRunning {
+                started_at: time(running, "startedAt")
+            }),
+            terminated: state.get("terminated").map(|terminated| ContainerStateTerminated {
+                container_id: string(terminated, "containerID"),
+                exit_code: int(terminated, "exitCode"),
+                started_at: time(terminated, "startedAt"),
+                finished_at: time(terminated, "finishedAt"),
+                message: string(terminated, "message"),
+                reason: string(terminated, "reason"),
+                signal: int(terminated, "signal")
+            }),
+            waiting: state.get("waiting").map(|waiting| ContainerStateWaiting {
+                message: string(waiting, "message"),
+                reason: string(waiting, "reason")
+            })
+        };
+        Self {
+            name: string(value, "name").unwrap_or_default(),
+            image: string(value, "image").unwrap_or_default(),
+            image_id: string(value, "imageID").unwrap_or_default(),
+            container_id: string(value, "containerID"),
+            state: value.get("state").map(state),
+            last_state: value.get("lastState").map(state),
+            ready: value.get("ready").and_then(Value::as_bool).unwrap_or_default(),
+            restart_count: int(value, "restartCount").unwrap_or_default(),
+            started: value.get("started").and_then(Value::as_bool)
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let state = |state: &ContainerState| {
+            let mut value = json!({});
+            let fields = |fields: &[(&str, Option<Value>)]| Value::Object(fields.iter()
+                .filter_map(|(key, value)| Some(((*key).to_owned(), value.clone()?)))
+                .collect());
+            let time = |time: Option<Time>| time.map(|time| time.to_string().into());
+            if let Some(running) = &state.running {
+                value["running"] = fields(&[("startedAt", time(running.started_at))]);
+            }
+            if let Some(terminated) = &state.terminated {
+                value["terminated"] = fields(&[
+                    ("containerID", terminated.container_id.clone().map(Value::from)),
+                    ("exitCode", terminated.exit_code.map(Value::from)),
+                    ("startedAt", time(terminated.started_at)),
+                    ("finishedAt", time(terminated.finished_at)),
+                    ("message", terminated.message.clone().map(Value::from)),
+                    ("reason", terminated.reason.clone().map(Value::from)),
+                    ("signal", terminated.signal.map(Value::from))
+                ]);
+            }
+            if let Some(waiting) = &state.waiting {
+                value["waiting"] = fields(&[
+                    ("message", waiting.message.clone().map(Value::from)),
+                    ("reason", waiting.reason.clone().map(Value::from))
+                ]);
+            }
+            value
+        };
+        let mut value = json!({
+            "name": self.name,
+            "image": self.image,
+            "imageID": self.image_id,
+            "ready": self.ready,
+            "restartCount": self.restart_count
+        });
+        if let Some(container_id) = &self.container_id {
+            value["containerID"] = container_id.clone().into();
+        }
+        if let Some(started) = self.started {
+            value["started"] = started.into();
+        }
+        if let Some(current) = &self.state {
+            value["state"] = state(current);
+        }
+        if let Some(last_state) = &self.last_state {
+            value["lastState"] = state(last_state);
+        }
+        value
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct ContainerState {
-    running: Option<ContainerStateRunning>,
-    terminated: Option<ContainerStateTerminated>,
-    waiting: Option<ContainerStateWaiting>
+    pub running: Option<ContainerStateRunning>,
+    pub terminated: Option<ContainerStateTerminated>,
+    pub waiting: Option<ContainerStateWaiting>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for ContainerState {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let container_state = Self {
+            running: fields.child("running")?,
+            terminated: fields.child("terminated")?,
+            waiting: fields.child("waiting")?
+        };
+        fields.finish()?;
+        Ok(container_state)
+    }
+}
+
+#[derive(Debug, Decode, Default)]
 pub struct ContainerStateRunning {
-    started_at: Option<Time>
+    #[kfl(property, default)]
+    pub started_at: Option<Time>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct ContainerStateTerminated {
-    container_id: Option<String>,
-    exit_code: Option<i32>,
-    started_at: Option<Time>,
-    finished_at: Option<Time>,
-    message: Option<String>,
-    reason: Option<String>,
-    signal: Option<i32>,
+    #[kfl(property, default)]
+    pub container_id: Option<String>,
+    #[kfl(property, default)]
+    pub exit_code: Option<i32>,
+    #[kfl(property, default)]
+    pub started_at: Option<Time>,
+    #[kfl(property, default)]
+    pub finished_at: Option<Time>,
+    #[kfl(property, default)]
+    pub message: Option<String>,
+    #[kfl(property, default)]
+    pub reason: Option<String>,
+    #[kfl(property, default)]
+    pub signal: Option<i32>,
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct ContainerStateWaiting {
-    message: Option<String>,
-    reason: Option<String>,
+    #[kfl(property, default)]
+    pub message: Option<String>,
+    #[kfl(property, default)]
+    pub reason: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use crate::{client::Object, decode::decode};
+    use super::*;
+
+    fn pod(spec: &str) -> Pod {
+        decode("pod.kdl", &format!("pod {{\n{}\n}}", spec)).unwrap()
+    }
+
+    fn with_status(status: Value) -> Pod {
+        Pod::from_json(json!({
+            "metadata": { "name": "web" },
+            "spec": {
+                "initContainers": [{ "name": "migrate" }, { "name": "seed" }],
+                "containers": [{ "name": "app" }, { "name": "proxy" }]
+            },
+            "status": status
+        })).unwrap()
+    }
+
+    #[test]
+    fn decodes_defaults_of_omitted_fields() {
+        let spec: Spec = decode("pod.kdl", r#"
+            spec restart-policy="on-failure" termination-grace-period-seconds=5 host-users=false {
+                container "nginx" image="nginx" image-pull-policy="if-not-present" {
+                    args "--port" "80"
+                }
+                container "sidecar" image="busybox" termination-message-path="/tmp/log"
+            }
+        "#).unwrap();
+        assert_eq!(spec.restart_policy, RestartPolicy::OnFailure);
+        assert_eq!(spec.termination_grace_period_seconds, Some(5));
+        assert!(!spec.host_users);
+        assert!(spec.enable_service_links);
+        assert!(matches!(spec.dns_policy, DnsPolicy::ClusterFirst));
+
+        let [nginx, sidecar] = &spec.containers[..] else {
+            panic!("expected two containers, got {:?}", spec.containers)
+        };
+        assert_eq!(nginx.image_pull_policy, ImagePullPolicy::IfNotPresent);
+        assert_eq!(nginx.args, ["--port", "80"]);
+        assert_eq!(nginx.termination_message_path, Path::new("/dev/termination-log"));
+        assert_eq!(sidecar.image_pull_policy, ImagePullPolicy::Always);
+        assert_eq!(sidecar.termination_message_path, Path::new("/tmp/log"));
+
+        let spec: Spec = decode("pod.kdl", "spec").unwrap();
+        assert_eq!(spec.restart_policy, RestartPolicy::Always);
+        assert_eq!(spec.termination_grace_period_seconds, Some(30));
+        assert!(spec.host_users);
+    }
+
+    #[test]
+    fn classifies_quality_of_service() {
+        let best_effort = pod(r#"
+            spec {
+                container "app"
+            }
+        "#);
+        assert_eq!(best_effort.qos_class().unwrap(), QosClass::BestEffort);
+        let guaranteed = pod(r#"
+            spec {
+                init-container "migrate" {
+                    resources {
+                        limits cpu="1" memory="1Gi"
+                    }
+                }
+                container "app" {
+                    resources {
+                        requests cpu="500m" memory="256Mi"
+                        limits cpu="500m" memory="256Mi"
+                    }
+                }
+            }
+        "#);
+        assert_eq!(guaranteed.qos_class().unwrap(), QosClass::Guaranteed);
+        let burstable = pod(r#"
+            spec {
+                container "app" {
+                    resources {
+                        requests cpu="500m" memory="256Mi"
+                        limits cpu="1" memory="256Mi"
+                    }
+                }
+            }
+        "#);
+        assert_eq!(burstable.qos_class().unwrap(), QosClass::Burstable);
+    }
+
+    #[test]
+    fn computes_effective_requests() {
+        let pod = pod(r#"
+            spec {
+                overhead cpu="50m"
+                init-container "migrate" {
+                    resources {
+                        requests cpu="2" memory="128Mi"
+                    }
+                }
+                init-container "proxy" restart-policy="always" {
+                    resources {
+                        requests cpu="100m" memory="64Mi"
+                    }
+                }
+                container "app" {
+                    resources {
+                        requests cpu="500m" memory="256Mi"
+                    }
+                }
+                container "worker" {
+                    resources {
+                        requests cpu="250m" memory="256Mi"
+                    }
+                }
+            }
+        "#);
+        let requests = pod.requests().unwrap();
+        // The migration outweighs the containers for CPU, not for memory; the sidecar adds to the containers.
+        assert_eq!(requests["cpu"].to_string(), "2050m");
+        assert_eq!(requests["memory"].to_string(), "576Mi");
+        assert!(pod.limits().unwrap().is_empty());
+    }
+
+    #[test]
+    fn displays_status() {
+        let running = json!({ "running": { "startedAt": "2024-01-01T00:00:00Z" } });
+        let initialising = with_status(json!({
+            "phase": "Pending",
+            "initContainerStatuses": [
+                { "name": "migrate", "state": { "terminated": { "exitCode": 0 } } },
+                { "name": "seed", "restartCount": 1, "state": running }
+            ]
+        }));
+        let display = initialising.display_status();
+        assert_eq!((display.ready, display.total, display.status.as_str(), display.restarts),
+            (0, 2, "Init:1/2", 1));
+
+        let crashing = with_status(json!({
+            "phase": "Running",
+            "containerStatuses": [
+                { "name": "app", "ready": true, "restartCount": 0, "state": running },
+                {
+                    "name": "proxy",
+                    "restartCount": 4,
+                    "state": { "waiting": { "reason": "CrashLoopBackOff" } },
+                    "lastState": { "terminated": { "exitCode": 1, "finishedAt": "2024-01-01T00:05:00Z" } }
+                }
+            ]
+        }));
+        let display = crashing.display_status();
+        assert_eq!((display.ready, display.status.as_str(), display.restarts),
+            (1, "CrashLoopBackOff", 4));
+        assert_eq!(display.last_restart, Some("2024-01-01T00:05:00Z".parse().unwrap()));
+        assert_eq!(crashing.restart_count(), 4);
+
+        let mut terminating = crashing;
+        terminating.metadata.deletion_timestamp = Some(Time::now());
+        assert_eq!(terminating.display_status().status, "Terminating");
+    }
 }
@@ -1,22 +1,55 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-template-v1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
+use serde_json::{json, Value};
 
 use crate::{
     core::pod,
+    decode::Fields,
     meta::metadata::Metadata,
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-template-v1/#PodTemplate>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct PodTemplate {
-    metadata: Metadata,
-    template: PodTemplateSpec
+    pub metadata: Metadata,
+    pub template: PodTemplateSpec
+}
+
+impl Decode for PodTemplate {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let pod_template = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            template: fields.child("template")?.ok_or_else(|| fields.missing("template"))?
+        };
+        fields.finish()?;
+        Ok(pod_template)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/pod-template-v1/#PodTemplateSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct PodTemplateSpec {
-    metadata: Option<Metadata>,
-    spec: pod::Spec
+    #[kfl(child, default)]
+    pub metadata: Option<Metadata>,
+    #[kfl(child, default)]
+    pub spec: pod::Spec
+}
+
+impl PodTemplateSpec {
+    pub fn from_json(value: &Value) -> Self {
+        Self {
+            metadata: value.get("metadata").map(Metadata::from_json),
+            spec: value.get("spec").map(pod::Spec::from_json).unwrap_or_default()
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({ "spec": self.spec.to_json() });
+        if let Some(metadata) = &self.metadata {
+            value["metadata"] = metadata.to_json();
+        }
+        value
+    }
 }
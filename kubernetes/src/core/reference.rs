@@ -2,12 +2,18 @@
 
 use kfl::Decode;
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
 pub struct Reference {
-    field_path: String,
-    kind: String,
-    name: String,
-    namespace: String,
-    resource_version: String,
-    uid: String
+    #[kfl(property, default)]
+    pub field_path: String,
+    #[kfl(property, default)]
+    pub kind: String,
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub namespace: String,
+    #[kfl(property, default)]
+    pub resource_version: String,
+    #[kfl(property, default)]
+    pub uid: String
 }
@@ -2,37 +2,61 @@
 
 use std::collections::HashMap;
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
     core::pod_template::PodTemplateSpec,
+    decode::Fields,
     meta::{condition::Condition, metadata::Metadata},
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/replication-controller-v1/#ReplicationController>
 #[derive(Debug, Decode)]
 pub struct ReplicationController {
-    metadata: Metadata,
-    spec: Spec,
-    status: Status
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child)]
+    pub spec: Spec,
+    #[kfl(child)]
+    pub status: Status
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/replication-controller-v1/#Spec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Spec {
-    selector: HashMap<String, String>,
-    template: PodTemplateSpec,
-    replicas: i32,
-    min_ready_seconds: i32
+    pub selector: HashMap<String, String>,
+    pub template: PodTemplateSpec,
+    pub replicas: i32,
+    pub min_ready_seconds: i32
+}
+
+impl Decode for Spec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            selector: fields.entries("selector")?,
+            template: fields.child("template")?.unwrap_or_default(),
+            replicas: fields.property("replicas")?.unwrap_or_default(),
+            min_ready_seconds: fields.property("min-ready-seconds")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/workload-resources/replication-controller-v1/#Status>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Status {
-    replicas: i32,
-    available_replicas: Option<i32>,
-    ready_replicas: Option<i32>,
-    fully_labeled_replicas: Option<i32>,
-    conditions: Vec<Condition>,
-    observed_generation: Option<i64>
+    #[kfl(property, default)]
+    pub replicas: i32,
+    #[kfl(property, default)]
+    pub available_replicas: Option<i32>,
+    #[kfl(property, default)]
+    pub ready_replicas: Option<i32>,
+    #[kfl(property, default)]
+    pub fully_labeled_replicas: Option<i32>,
+    #[kfl(children)]
+    pub conditions: Vec<Condition>,
+    #[kfl(property, default)]
+    pub observed_generation: Option<i64>
 }
@@ -4,9 +4,16 @@ use kfl::Decode;
 
 use crate::quantity::Quantity;
 
+/// ResourceFieldSelector represents container resources (cpu, memory) and their output format.
 #[derive(Debug, Decode)]
 pub struct ResourceFieldSelector {
-    resource: String,
-    container_name: Option<String>,
-    divisor: Quantity
+    /// Required: resource to select, as `limits.cpu` or `requests.memory`.
+    #[kfl(property, default)]
+    pub resource: String,
+    /// Container name: required for volumes, optional for env vars, which default to their own container.
+    #[kfl(property, default)]
+    pub container_name: Option<String>,
+    /// Specifies the output format of the exposed resources, defaults to `1`.
+    #[kfl(property, default)]
+    pub divisor: Option<Quantity>
 }
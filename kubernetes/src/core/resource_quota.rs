@@ -1,56 +1,467 @@
 /*!
 - Concepts <https://kubernetes.io/docs/concepts/policy/resource-quotas/>
 - References <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/resource-quota-v1/>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/quota/v1/evaluator/core/pods.go>
 */
 
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt
+};
 
-use kfl::{Decode, DecodeScalar};
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
 
 use crate::{
-   meta::metadata::Metadata,
-   quantity::Quantity
+    core::pod::{Container, Pod},
+    decode::Fields,
+    meta::metadata::Metadata,
+    quantity::{self, Amount, Amounts, Quantity}
 };
 
+/// Compute resources quotas may require every container to request or limit.
+const COMPUTE: [&str; 9] = [
+    "cpu", "memory", "ephemeral-storage",
+    "requests.cpu", "requests.memory", "requests.ephemeral-storage",
+    "limits.cpu", "limits.memory", "limits.ephemeral-storage"
+];
+
 /// <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/resource-quota-v1/#ResourceQuota>
 #[derive(Debug, Decode)]
 pub struct ResourceQuota {
-   metadata: Metadata,
-   spec: ResourceQuotaSpec,
-   status: Option<ResourceQuotaStatus>
+    #[kfl(child, default)]
+    pub metadata: Metadata,
+    #[kfl(child, default)]
+    pub spec: ResourceQuotaSpec,
+    #[kfl(child, default)]
+    pub status: Option<ResourceQuotaStatus>
+}
+
+impl ResourceQuota {
+    /// Whether the quota tracks `pod`: it is in every scope of [`scopes`][ResourceQuotaSpec::scopes] and matches every requirement of the [`scope_selector`][ResourceQuotaSpec::scope_selector].
+    pub fn matches(&self, pod: &Pod) -> bool {
+        self.spec.scopes.iter().all(|scope| scope.matches(pod))
+            && self.spec.scope_selector.iter()
+                .flat_map(|selector| &selector.match_expressions)
+                .all(|requirement| requirement.matches(pod))
+    }
+
+    /**
+    Charges `pod` against the quota as the `ResourceQuota` admission plugin does on create, returning what the quota's `used` becomes: unchanged if the quota does not track the pod, else with the pod's [`usage`] added for every resource it has a `hard` limit for.
+
+    The pod is rejected when the quota's usage is not computed yet, when the quota limits a compute resource some container neither requests nor limits, or when the pod would take any resource over its limit.
+    */
+    pub fn charge(&self, pod: &Pod) -> Result<HashMap<String, Quantity>, Error> {
+        let name = self.metadata.name.clone().unwrap_or_default();
+        let used = self.status.as_ref().map(|status| &status.used);
+        let mut charged = used.cloned().unwrap_or_default();
+        if !self.matches(pod) {
+            return Ok(charged)
+        }
+        let hard = quantity::parse_all(&self.spec.hard)?;
+        let used = quantity::parse_all(used.unwrap_or(&HashMap::new()))?;
+        let mut unknown: Vec<String> = hard.keys()
+            .filter(|resource| !used.contains_key(*resource))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort();
+            return Err(Error::StatusUnknown { quota: name, resources: unknown })
+        }
+        let tracks_compute = !self.spec.scopes.contains(&Scope::BestEffort);
+        if tracks_compute {
+            let required: BTreeSet<&str> = COMPUTE.into_iter()
+                .filter(|resource| hard.contains_key(*resource))
+                .collect();
+            let mut missing: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+            for container in pod.spec.init_containers.iter().chain(&pod.spec.containers) {
+                let specified = container_usage(container)?;
+                for resource in &required {
+                    if !specified.contains_key(*resource) {
+                        missing.entry(resource).or_default().push(&container.name);
+                    }
+                }
+            }
+            if !missing.is_empty() {
+                let resources = missing.into_iter()
+                    .map(|(resource, containers)|
+                        format!("{} for: {}", resource, containers.join(",")))
+                    .collect();
+                return Err(Error::Unspecified { quota: name, resources })
+            }
+        }
+        let usage = usage(pod)?;
+        let mut exceeded = Vec::new();
+        for (resource, limit) in &hard {
+            let Some(requested) = usage.get(resource) else { continue };
+            if !tracks_compute && resource != "pods" && resource != "count/pods" {
+                continue
+            }
+            let used = used[resource];
+            let total = used + *requested;
+            if total > *limit {
+                exceeded.push((resource.clone(), *requested, used, *limit));
+            }
+            charged.insert(resource.clone(), total.to_string());
+        }
+        if !exceeded.is_empty() {
+            exceeded.sort_by(|a, b| a.0.cmp(&b.0));
+            return Err(Error::Exceeded { quota: name, resources: exceeded })
+        }
+        Ok(charged)
+    }
+}
+
+/**
+Charges `pod` against every quota of its namespace, recording the new usage in their status only if none rejects it.
+*/
+pub fn admit(quotas: &mut [ResourceQuota], pod: &Pod) -> Result<(), Error> {
+    let charged = quotas.iter()
+        .map(|quota| quota.charge(pod))
+        .collect::<Result<Vec<_>, _>>()?;
+    for (quota, used) in quotas.iter_mut().zip(charged) {
+        let status = quota.status.get_or_insert_with(|| ResourceQuotaStatus {
+            hard: quota.spec.hard.clone(),
+            used: HashMap::new()
+        });
+        status.used = used;
+    }
+    Ok(())
+}
+
+/**
+The quota usage of `pod`, by the resource names a quota may limit:
+
+- `pods` and `count/pods`, one.
+- `cpu`, `memory` and `ephemeral-storage`, also as `requests.*`, and `limits.*`, the pod's [effective requests][Pod::requests] and [limits][Pod::limits].
+- `hugepages-*`, also as `requests.hugepages-*`, and `requests.*` of extended resources such as `nvidia.com/gpu`.
+*/
+pub fn usage(pod: &Pod) -> Result<Amounts, Error> {
+    let mut usage = compute_usage(&pod.requests()?, &pod.limits()?);
+    usage.insert("pods".to_owned(), Amount::from_value(1, quantity::Format::Decimal));
+    usage.insert("count/pods".to_owned(), Amount::from_value(1, quantity::Format::Decimal));
+    Ok(usage)
+}
+
+fn container_usage(container: &Container) -> Result<Amounts, Error> {
+    Ok(compute_usage(&container.requests()?, &container.limits()?))
+}
+
+fn compute_usage(requests: &Amounts, limits: &Amounts) -> Amounts {
+    let mut usage = HashMap::new();
+    for (name, request) in requests {
+        if matches!(name.as_str(), "cpu" | "memory" | "ephemeral-storage")
+            || name.starts_with("hugepages-")
+        {
+            usage.insert(name.clone(), *request);
+            usage.insert(format!("requests.{}", name), *request);
+        } else if is_extended(name) {
+            usage.insert(format!("requests.{}", name), *request);
+        }
+    }
+    for (name, limit) in limits {
+        if matches!(name.as_str(), "cpu" | "memory" | "ephemeral-storage") {
+            usage.insert(format!("limits.{}", name), *limit);
+        }
+    }
+    usage
+}
+
+/// Whether `name` is an extended resource, one with a domain other than `kubernetes.io`.
+fn is_extended(name: &str) -> bool {
+    name.split_once('/').is_some_and(|(domain, _)|
+        domain != "kubernetes.io" && !domain.ends_with(".kubernetes.io")
+            && !name.starts_with("requests."))
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/resource-quota-v1/#ResourceQuotaSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct ResourceQuotaSpec {
-   hard: HashMap<String, Quantity>,
-   scope_selector: Option<ScopeSelector>,
-   scopes: Vec<String>
+    pub hard: HashMap<String, Quantity>,
+    pub scope_selector: Option<ScopeSelector>,
+    pub scopes: Vec<Scope>
+}
+
+impl Decode for ResourceQuotaSpec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            hard: fields.entries("hard")?,
+            scope_selector: fields.child("scope-selector")?,
+            scopes: fields.values("scopes")?
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct ScopeSelector {
-   match_expressions: Vec<ScopedResourceSelectorRequirement>
+    #[kfl(children)]
+    pub match_expressions: Vec<ScopedResourceSelectorRequirement>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct ScopedResourceSelectorRequirement {
-   operator: ScopedResourceSelectorRequirementOperator,
-   scope_name: String,
-   values: Vec<String>
+    pub operator: ScopedResourceSelectorRequirementOperator,
+    pub scope_name: Scope,
+    pub values: Vec<String>
+}
+
+impl Decode for ScopedResourceSelectorRequirement {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let requirement = Self {
+            operator: fields.property("operator")?.unwrap_or_default(),
+            scope_name: fields.property("scope-name")?.unwrap_or_default(),
+            values: fields.values("values")?
+        };
+        fields.finish()?;
+        Ok(requirement)
+    }
 }
 
-#[derive(Debug, DecodeScalar)]
+impl ScopedResourceSelectorRequirement {
+    /// Whether `pod` meets the requirement: for [`PriorityClass`][Scope::PriorityClass], its priority class name is in or not in the values or set or not, and for other scopes, it is or is not in the scope.
+    pub fn matches(&self, pod: &Pod) -> bool {
+        use ScopedResourceSelectorRequirementOperator::*;
+        if self.scope_name != Scope::PriorityClass {
+            return self.scope_name.matches(pod) == matches!(self.operator, In | Exists)
+        }
+        let name = pod.spec.priority_class_name.as_deref().unwrap_or_default();
+        match self.operator {
+            In => self.values.iter().any(|value| value == name),
+            NotIn => !self.values.iter().any(|value| value == name),
+            Exists => !name.is_empty(),
+            DoesNotExist => name.is_empty()
+        }
+    }
+}
+
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ScopedResourceSelectorRequirementOperator {
-   In,
-   NotIn,
-   Exists,
-   DoesNotExist
+    #[default]
+    In,
+    NotIn,
+    Exists,
+    DoesNotExist
+}
+
+/// <https://kubernetes.io/docs/concepts/policy/resource-quotas/#quota-scopes>
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scope {
+    /// Pods with an active deadline.
+    #[default]
+    Terminating,
+    /// Pods without an active deadline.
+    NotTerminating,
+    /// Pods of the `BestEffort` QoS class, which only `pods` is tracked for.
+    BestEffort,
+    NotBestEffort,
+    /// Pods with a priority class.
+    PriorityClass,
+    /// Pods with affinity terms for other namespaces.
+    CrossNamespacePodAffinity
+}
+
+impl Scope {
+    pub fn matches(&self, pod: &Pod) -> bool {
+        match self {
+            Self::Terminating => pod.spec.active_deadline_seconds.is_some(),
+            Self::NotTerminating => pod.spec.active_deadline_seconds.is_none(),
+            Self::BestEffort => is_best_effort(pod),
+            Self::NotBestEffort => !is_best_effort(pod),
+            Self::PriorityClass => pod.spec.priority_class_name.as_deref()
+                .is_some_and(|name| !name.is_empty()),
+            Self::CrossNamespacePodAffinity => has_cross_namespace_affinity(pod)
+        }
+    }
+}
+
+/// Whether no container of `pod` requests or limits CPU or memory.
+fn is_best_effort(pod: &Pod) -> bool {
+    pod.spec.init_containers.iter().chain(&pod.spec.containers)
+        .filter_map(|container| container.resources.as_ref())
+        .flat_map(|resources| resources.requests.keys().chain(resources.limits.keys()))
+        .all(|name| name != "cpu" && name != "memory")
+}
+
+fn has_cross_namespace_affinity(pod: &Pod) -> bool {
+    let Some(affinity) = &pod.spec.affinity else { return false };
+    let affinity_terms = affinity.pod_affinity.iter().flat_map(|affinity|
+        affinity.required_during_scheduling_ignored_during_execution.iter()
+            .chain(affinity.preferred_during_scheduling_ignored_during_execution.iter()
+                .map(|weighted| &weighted.pod_affinity_term)));
+    let anti_affinity_terms = affinity.pod_anti_affinity.iter().flat_map(|affinity|
+        affinity.required_during_scheduling_ignored_during_execution.iter()
+            .chain(affinity.preferred_during_scheduling_ignored_during_execution.iter()
+                .map(|weighted| &weighted.pod_affinity_term)));
+    affinity_terms.chain(anti_affinity_terms)
+        .any(|term| !term.namespaces.is_empty() || term.namespace_selector.is_some())
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/resource-quota-v1/#ResourceQuotaStatus>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct ResourceQuotaStatus {
-   hard: HashMap<String, Quantity>,
-   used: HashMap<String, Quantity>
+    pub hard: HashMap<String, Quantity>,
+    pub used: HashMap<String, Quantity>
+}
+
+impl Decode for ResourceQuotaStatus {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let status = Self {
+            hard: fields.entries("hard")?,
+            used: fields.entries("used")?
+        };
+        fields.finish()?;
+        Ok(status)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Quantity(quantity::Error),
+    /// The quota's usage of some resources is not computed yet.
+    StatusUnknown { quota: String, resources: Vec<String> },
+    /// Containers do not specify compute resources the quota limits, as `limits.cpu for: app,sidecar`.
+    Unspecified { quota: String, resources: Vec<String> },
+    /// The resources the pod would take over the limit, with what it requests, what is used and the limit.
+    Exceeded { quota: String, resources: Vec<(String, Amount, Amount, Amount)> }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Quantity(error) => error.fmt(f),
+            Self::StatusUnknown { quota, resources } => write!(f,
+                "status unknown for quota: {}, resources: {}", quota, resources.join(",")),
+            Self::Unspecified { quota, resources } => write!(f,
+                "failed quota: {}: must specify {}", quota, resources.join("; ")),
+            Self::Exceeded { quota, resources } => {
+                let list = |column: fn(&(String, Amount, Amount, Amount)) -> Amount| resources
+                    .iter()
+                    .map(|resource| format!("{}={}", resource.0, column(resource)))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                write!(f, "exceeded quota: {}, requested: {}, used: {}, limited: {}", quota,
+                    list(|resource| resource.1), list(|resource| resource.2),
+                    list(|resource| resource.3))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<quantity::Error> for Error {
+    fn from(error: quantity::Error) -> Self {
+        Self::Quantity(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decode::decode;
+    use super::*;
+
+    fn pod(resources: &str) -> Pod {
+        decode("pod.kdl", &format!(r#"
+            pod {{
+                spec {{
+                    container "app" {{
+                        resources {{
+                            {}
+                        }}
+                    }}
+                }}
+            }}
+        "#, resources)).unwrap()
+    }
+
+    fn quota(input: &str) -> ResourceQuota {
+        decode("quota.kdl", input).unwrap()
+    }
+
+    #[test]
+    fn charges_pods_within_the_limits() {
+        let mut quotas = [quota(r#"
+            resource-quota {
+                metadata "compute"
+                spec {
+                    hard pods="2" requests.cpu="1" limits.memory="1Gi"
+                }
+                status {
+                    used pods="1" requests.cpu="500m" limits.memory="512Mi"
+                }
+            }
+        "#)];
+        let small = pod(r#"
+            requests cpu="250m"
+            limits memory="256Mi"
+        "#);
+        admit(&mut quotas, &small).unwrap();
+        let used = &quotas[0].status.as_ref().unwrap().used;
+        assert_eq!(used["pods"], "2");
+        assert_eq!(used["requests.cpu"], "750m");
+        assert_eq!(used["limits.memory"], "768Mi");
+
+        let large = pod(r#"
+            requests cpu="500m"
+            limits memory="256Mi"
+        "#);
+        let Err(Error::Exceeded { resources, .. }) = quotas[0].charge(&large) else {
+            panic!("expected the quota to be exceeded")
+        };
+        let exceeded: Vec<&str> = resources.iter().map(|(name, ..)| name.as_str()).collect();
+        assert_eq!(exceeded, ["pods", "requests.cpu"]);
+
+        let unspecified = pod(r#"requests cpu="100m""#);
+        assert_eq!(quotas[0].charge(&unspecified).unwrap_err().to_string(),
+            "failed quota: compute: must specify limits.memory for: app");
+    }
+
+    #[test]
+    fn tracks_pods_in_scope() {
+        let quota = quota(r#"
+            resource-quota {
+                metadata "best-effort"
+                spec {
+                    hard pods="1" cpu="1"
+                    scopes "best-effort"
+                }
+                status {
+                    used pods="1" cpu="0"
+                }
+            }
+        "#);
+        // Pods out of scope are not charged, nor are compute resources of those in it.
+        let burstable = pod(r#"requests cpu="100m""#);
+        assert!(!quota.matches(&burstable));
+        assert_eq!(quota.charge(&burstable).unwrap()["pods"], "1");
+        let best_effort = pod("");
+        assert!(matches!(quota.charge(&best_effort),
+            Err(Error::Exceeded { resources, .. }) if resources.len() == 1));
+
+        let unknown = ResourceQuota { status: None, ..quota };
+        assert!(matches!(unknown.charge(&best_effort), Err(Error::StatusUnknown { .. })));
+    }
+
+    #[test]
+    fn selects_priority_classes() {
+        let quota = quota(r#"
+            resource-quota {
+                spec {
+                    scope-selector {
+                        scoped-resource-selector-requirement operator="in" scope-name="priority-class" {
+                            values "high"
+                        }
+                    }
+                }
+            }
+        "#);
+        let mut pod = pod("");
+        assert!(!quota.matches(&pod));
+        pod.spec.priority_class_name = Some("high".to_owned());
+        assert!(quota.matches(&pod));
+    }
 }
@@ -2,32 +2,537 @@
 //! - <https://kubernetes.io/docs/concepts/configuration/secret/>
 //! - <https://kubernetes.io/docs/concepts/security/secrets-good-practices/>
 //! - Reference <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/secret-v1/>
+//! - Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/apis/core/validation/validation.go>
 
-use std::collections::HashMap;
-use kfl::Decode;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr
+};
 
-use crate::meta::metadata::Metadata;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
+use serde_json::{json, Map, Value};
+use x509_cert::{der::pem, Certificate};
+
+use crate::{decode::Fields, meta::metadata::Metadata};
+
+/// Largest total size of the data of a Secret.
+pub const MAX_SIZE: usize = 1024 * 1024;
+
+/// Key of the certificate chain of a [`Tls`][SecretType::Tls] Secret.
+pub const TLS_CERT_KEY: &str = "tls.crt";
+/// Key of the private key of a [`Tls`][SecretType::Tls] Secret.
+pub const TLS_PRIVATE_KEY_KEY: &str = "tls.key";
+/// Key of the optional CA certificates of a [`Tls`][SecretType::Tls] Secret.
+pub const TLS_CA_KEY: &str = "ca.crt";
+pub const BASIC_AUTH_USERNAME_KEY: &str = "username";
+pub const BASIC_AUTH_PASSWORD_KEY: &str = "password";
+pub const SSH_AUTH_PRIVATE_KEY: &str = "ssh-privatekey";
+pub const DOCKER_CONFIG_KEY: &str = ".dockercfg";
+pub const DOCKER_CONFIG_JSON_KEY: &str = ".dockerconfigjson";
+/// Annotation naming the ServiceAccount of a [`ServiceAccountToken`][SecretType::ServiceAccountToken] Secret.
+pub const SERVICE_ACCOUNT_NAME_ANNOTATION: &str = "kubernetes.io/service-account.name";
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/secret-v1/#Secret>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Secret {
-    metadata: Metadata,
-    data: Option<HashMap<String, Vec<u8>>>,
-    immutable: Option<bool>,
-    string_data: HashMap<String, String>,
-    r#type: Option<SecretType>
+    pub metadata: Metadata,
+    /// Values by key, base64 encoded on the wire.
+    pub data: Option<HashMap<String, Vec<u8>>>,
+    pub immutable: Option<bool>,
+    /// Write-only values by key, in plain text, merged into [`data`][Self::data] on write and taking precedence over it.
+    pub string_data: HashMap<String, String>,
+    pub r#type: Option<SecretType>
+}
+
+impl Decode for Secret {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let secret = Self {
+            metadata: fields.child("metadata")?.unwrap_or_default(),
+            data: Some(fields.entries("data")?).filter(|data: &HashMap<_, _>| !data.is_empty()),
+            immutable: fields.property("immutable")?,
+            string_data: fields.entries("string-data")?,
+            r#type: fields.property("type")?
+        };
+        fields.finish()?;
+        Ok(secret)
+    }
+}
+
+impl Secret {
+    /// The type, [`Opaque`][SecretType::Opaque] unless set.
+    pub fn secret_type(&self) -> SecretType {
+        self.r#type.unwrap_or(SecretType::Opaque)
+    }
+
+    /// The value of `key`, from [`string_data`][Self::string_data] before [`data`][Self::data] as the API server merges them.
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.string_data.get(key).map(|value| value.as_bytes())
+            .or_else(|| self.data.as_ref()?.get(key).map(Vec::as_slice))
+    }
+
+    /// Moves [`string_data`][Self::string_data] into [`data`][Self::data], overwriting the values of the same keys, as the API server does on write.
+    pub fn merge_string_data(&mut self) {
+        if self.string_data.is_empty() {
+            return
+        }
+        let data = self.data.get_or_insert_with(HashMap::new);
+        for (key, value) in self.string_data.drain() {
+            data.insert(key, value.into_bytes());
+        }
+    }
+
+    /// [`data`][Self::data] merged with [`string_data`][Self::string_data], base64 encoded as on the wire.
+    pub fn encoded_data(&self) -> BTreeMap<String, String> {
+        let mut encoded: BTreeMap<String, String> = self.data.iter().flatten()
+            .map(|(key, value)| (key.clone(), STANDARD.encode(value)))
+            .collect();
+        for (key, value) in &self.string_data {
+            encoded.insert(key.clone(), STANDARD.encode(value));
+        }
+        encoded
+    }
+
+    /// Sets `key` to the value `encoded` in base64, as read from the wire.
+    pub fn insert_encoded(&mut self, key: &str, encoded: &str) -> Result<(), Error> {
+        let value = STANDARD.decode(encoded)
+            .map_err(|error| Error::Base64(key.to_owned(), error.to_string()))?;
+        self.data.get_or_insert_with(HashMap::new).insert(key.to_owned(), value);
+        Ok(())
+    }
+
+    /**
+    Checks the Secret as the API server does on write: every key is a valid config key, the data all together is at most [`MAX_SIZE`], and the keys or annotations its type requires are present.
+
+    - [`ServiceAccountToken`][SecretType::ServiceAccountToken] requires the [`SERVICE_ACCOUNT_NAME_ANNOTATION`] annotation.
+    - [`Dockercfg`][SecretType::Dockercfg] and [`DockerConfigJson`][SecretType::DockerConfigJson] require [`DOCKER_CONFIG_KEY`] and [`DOCKER_CONFIG_JSON_KEY`] respectively, holding JSON.
+    - [`BasicAuth`][SecretType::BasicAuth] requires [`BASIC_AUTH_USERNAME_KEY`] or [`BASIC_AUTH_PASSWORD_KEY`].
+    - [`SshAuth`][SecretType::SshAuth] requires [`SSH_AUTH_PRIVATE_KEY`].
+    - [`Tls`][SecretType::Tls] requires [`TLS_CERT_KEY`] and [`TLS_PRIVATE_KEY_KEY`].
+    - [`BootstrapToken`][SecretType::BootstrapToken] requires `token-id` and `token-secret`.
+    */
+    pub fn validate(&self) -> Result<(), Error> {
+        let keys = self.data.iter().flatten().map(|(key, _)| key)
+            .chain(self.string_data.keys());
+        for key in keys {
+            validate_key(key)?;
+        }
+        let size: usize = self.data.iter().flatten()
+            .filter(|(key, _)| !self.string_data.contains_key(*key))
+            .map(|(_, value)| value.len())
+            .chain(self.string_data.values().map(String::len))
+            .sum();
+        if size > MAX_SIZE {
+            return Err(Error::TooLarge(size))
+        }
+        let r#type = self.secret_type();
+        let require = |key: &str| match self.get(key) {
+            Some(_) => Ok(()),
+            None => Err(Error::MissingKey(r#type, key.to_owned()))
+        };
+        match r#type {
+            SecretType::Opaque => Ok(()),
+            SecretType::ServiceAccountToken => {
+                if self.metadata.annotations.get(SERVICE_ACCOUNT_NAME_ANNOTATION)
+                    .is_none_or(|name| name.is_empty())
+                {
+                    return Err(Error::MissingAnnotation(
+                        SERVICE_ACCOUNT_NAME_ANNOTATION.to_owned()))
+                }
+                Ok(())
+            }
+            SecretType::Dockercfg | SecretType::DockerConfigJson => {
+                let key = match r#type {
+                    SecretType::Dockercfg => DOCKER_CONFIG_KEY,
+                    _ => DOCKER_CONFIG_JSON_KEY
+                };
+                require(key)?;
+                serde_json::from_slice::<Value>(self.get(key).unwrap_or_default())
+                    .map_err(|error| Error::Json(key.to_owned(), error.to_string()))?;
+                Ok(())
+            }
+            SecretType::BasicAuth => require(BASIC_AUTH_USERNAME_KEY)
+                .or_else(|_| require(BASIC_AUTH_PASSWORD_KEY)),
+            SecretType::SshAuth => require(SSH_AUTH_PRIVATE_KEY),
+            SecretType::Tls => {
+                require(TLS_CERT_KEY)?;
+                require(TLS_PRIVATE_KEY_KEY)
+            }
+            SecretType::BootstrapToken => {
+                require("token-id")?;
+                require("token-secret")
+            }
+        }
+    }
+
+    /// The certificate chain and private key of a [`Tls`][SecretType::Tls] Secret, parsed from PEM.
+    pub fn tls(&self) -> Result<TlsSecret, Error> {
+        self.expect_type(SecretType::Tls)?;
+        self.validate()?;
+        let pem = |key: &str| self.get(key).unwrap_or_default();
+        let cert = certificates(TLS_CERT_KEY, pem(TLS_CERT_KEY))?;
+        if cert.is_empty() {
+            return Err(Error::Pem(TLS_CERT_KEY.to_owned(), "no certificate".to_owned()))
+        }
+        let ca = match self.get(TLS_CA_KEY) {
+            Some(ca) => certificates(TLS_CA_KEY, ca)?,
+            None => Vec::new()
+        };
+        let key = PrivateKey::from_pem(pem(TLS_PRIVATE_KEY_KEY))
+            .map_err(|message| Error::Pem(TLS_PRIVATE_KEY_KEY.to_owned(), message))?;
+        Ok(TlsSecret { cert, key, ca })
+    }
+
+    /// The registry credentials of a [`DockerConfigJson`][SecretType::DockerConfigJson] or [`Dockercfg`][SecretType::Dockercfg] Secret.
+    pub fn docker_config(&self) -> Result<DockerConfig, Error> {
+        let key = match self.secret_type() {
+            SecretType::DockerConfigJson => DOCKER_CONFIG_JSON_KEY,
+            SecretType::Dockercfg => DOCKER_CONFIG_KEY,
+            actual => return Err(Error::WrongType {
+                expected: SecretType::DockerConfigJson,
+                actual
+            })
+        };
+        self.validate()?;
+        let json = self.get(key).unwrap_or_default();
+        DockerConfig::from_json(json, key == DOCKER_CONFIG_JSON_KEY)
+            .map_err(|message| Error::Json(key.to_owned(), message))
+    }
+
+    fn expect_type(&self, expected: SecretType) -> Result<(), Error> {
+        match self.secret_type() {
+            actual if actual == expected => Ok(()),
+            actual => Err(Error::WrongType { expected, actual })
+        }
+    }
+}
+
+/// Checks `key` is a valid config key: at most 253 alphanumerics, `-`, `_` and `.`, and not `.` or `..`.
+pub fn validate_key(key: &str) -> Result<(), Error> {
+    let valid = !key.is_empty() && key.len() <= 253 && key != "." && key != ".."
+        && key.bytes().all(|byte| byte.is_ascii_alphanumeric() || b"-._".contains(&byte));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidKey(key.to_owned()))
+    }
+}
+
+fn certificates(key: &str, pem: &[u8]) -> Result<Vec<Certificate>, Error> {
+    Certificate::load_pem_chain(pem).map_err(|error| Error::Pem(key.to_owned(), error.to_string()))
 }
 
 /// <https://kubernetes.io/docs/concepts/configuration/secret/#secret-types>
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SecretType {
     Opaque,
-    // #[kfl(rename(serialize = "kubernetes.io/service-account-token"))]
     ServiceAccountToken,
-    // #[kfl(rename(serialize = "kubernetes.io/basic-auth"))]
+    Dockercfg,
+    DockerConfigJson,
     BasicAuth,
-    // #[kfl(rename(serialize = "kubernetes.io/ssh-auth"))]
     SshAuth,
-    // #[kfl(rename(serialize = "kubernetes.io/tls"))]
-    Tls
+    Tls,
+    BootstrapToken
+}
+
+impl SecretType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Opaque => "Opaque",
+            Self::ServiceAccountToken => "kubernetes.io/service-account-token",
+            Self::Dockercfg => "kubernetes.io/dockercfg",
+            Self::DockerConfigJson => "kubernetes.io/dockerconfigjson",
+            Self::BasicAuth => "kubernetes.io/basic-auth",
+            Self::SshAuth => "kubernetes.io/ssh-auth",
+            Self::Tls => "kubernetes.io/tls",
+            Self::BootstrapToken => "bootstrap.kubernetes.io/token"
+        }
+    }
+}
+
+impl fmt::Display for SecretType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SecretType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "" | "Opaque" => Self::Opaque,
+            "kubernetes.io/service-account-token" => Self::ServiceAccountToken,
+            "kubernetes.io/dockercfg" => Self::Dockercfg,
+            "kubernetes.io/dockerconfigjson" => Self::DockerConfigJson,
+            "kubernetes.io/basic-auth" => Self::BasicAuth,
+            "kubernetes.io/ssh-auth" => Self::SshAuth,
+            "kubernetes.io/tls" => Self::Tls,
+            "bootstrap.kubernetes.io/token" => Self::BootstrapToken,
+            _ => return Err(Error::UnknownType(s.to_owned()))
+        })
+    }
+}
+
+impl DecodeScalar for SecretType {
+    fn decode(scalar: &kfl::ast::Scalar, ctx: &mut kfl::context::Context)
+        -> Result<Self, kfl::errors::DecodeError>
+    {
+        let r#type = String::decode(scalar, ctx)?;
+        r#type.parse().map_err(|error: Error|
+            kfl::errors::DecodeError::conversion(ctx.span(&scalar), error.to_string()))
+    }
+}
+
+/// <https://kubernetes.io/docs/concepts/configuration/secret/#tls-secrets>
+#[derive(Debug, Clone)]
+pub struct TlsSecret {
+    /// Certificate chain, leaf first.
+    pub cert: Vec<Certificate>,
+    pub key: PrivateKey,
+    /// CA certificates to verify peers with, if any.
+    pub ca: Vec<Certificate>
+}
+
+/// DER encoded private key, by the PEM format it came in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrivateKey {
+    /// `PRIVATE KEY`
+    Pkcs8(Vec<u8>),
+    /// `RSA PRIVATE KEY`
+    Pkcs1(Vec<u8>),
+    /// `EC PRIVATE KEY`
+    Sec1(Vec<u8>)
+}
+
+impl PrivateKey {
+    /// Parses the first private key block of `input`, skipping others such as `EC PARAMETERS`.
+    pub fn from_pem(input: &[u8]) -> Result<Self, String> {
+        let text = std::str::from_utf8(input).map_err(|error| error.to_string())?;
+        let mut rest = text;
+        while let Some(start) = rest.find("-----BEGIN ") {
+            let block = &rest[start..];
+            let end = block.find("-----END ")
+                .and_then(|end| block[end..].find('\n').map(|newline| end + newline + 1))
+                .unwrap_or(block.len());
+            let (label, der) = pem::decode_vec(&block.as_bytes()[..end])
+                .map_err(|error| error.to_string())?;
+            match label {
+                "PRIVATE KEY" => return Ok(Self::Pkcs8(der)),
+                "RSA PRIVATE KEY" => return Ok(Self::Pkcs1(der)),
+                "EC PRIVATE KEY" => return Ok(Self::Sec1(der)),
+                _ => rest = &block[end..]
+            }
+        }
+        Err("no private key".to_owned())
+    }
+
+    /// The DER encoding.
+    pub fn der(&self) -> &[u8] {
+        match self {
+            Self::Pkcs8(der) | Self::Pkcs1(der) | Self::Sec1(der) => der
+        }
+    }
+}
+
+/**
+Registry credentials of an image pull Secret, by registry.
+
+- Concepts <https://kubernetes.io/docs/concepts/containers/images/#specifying-imagepullsecrets-on-a-pod>
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DockerConfig {
+    pub auths: BTreeMap<String, DockerAuth>
+}
+
+/// Credentials for one registry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DockerAuth {
+    pub username: String,
+    pub password: String,
+    pub email: Option<String>
+}
+
+impl DockerConfig {
+    /**
+    Parses the JSON of [`DOCKER_CONFIG_JSON_KEY`], `{"auths": {registry: entry}}` if `wrapped`, else the legacy one of [`DOCKER_CONFIG_KEY`], `{registry: entry}`.
+
+    The username and password of an entry come from its `auth`, the base64 encoding of `username:password`, when it has one, as the kubelet's keyring reads them.
+    */
+    pub fn from_json(input: &[u8], wrapped: bool) -> Result<Self, String> {
+        let value: Value = serde_json::from_slice(input).map_err(|error| error.to_string())?;
+        let auths = match (&value, wrapped) {
+            (Value::Object(object), true) => match object.get("auths") {
+                Some(Value::Object(auths)) => auths,
+                Some(_) => return Err("auths is not an object".to_owned()),
+                None => return Ok(Self::default())
+            },
+            (Value::Object(object), false) => object,
+            _ => return Err("not an object".to_owned())
+        };
+        let mut config = Self::default();
+        for (registry, entry) in auths {
+            let Value::Object(entry) = entry else {
+                return Err(format!("entry for {} is not an object", registry))
+            };
+            let field = |name: &str| match entry.get(name) {
+                Some(Value::String(value)) => Ok(Some(value.clone())),
+                None | Some(Value::Null) => Ok(None),
+                Some(_) => Err(format!("{} of {} is not a string", name, registry))
+            };
+            let mut auth = DockerAuth {
+                username: field("username")?.unwrap_or_default(),
+                password: field("password")?.unwrap_or_default(),
+                email: field("email")?
+            };
+            if let Some(encoded) = field("auth")?.filter(|encoded| !encoded.is_empty()) {
+                let decoded = STANDARD.decode(&encoded).ok()
+                    .and_then(|decoded| String::from_utf8(decoded).ok())
+                    .ok_or_else(|| format!("auth of {} is not base64", registry))?;
+                let (username, password) = decoded.split_once(':')
+                    .ok_or_else(|| format!("auth of {} is not username:password", registry))?;
+                auth.username = username.to_owned();
+                auth.password = password.to_owned();
+            }
+            config.auths.insert(registry.clone(), auth);
+        }
+        Ok(config)
+    }
+
+    /// Serialises the config as [`DOCKER_CONFIG_JSON_KEY`] holds it, as `kubectl create secret docker-registry` writes it.
+    pub fn to_json(&self) -> String {
+        let auths: Map<String, Value> = self.auths.iter().map(|(registry, auth)| {
+            let mut entry = json!({
+                "username": auth.username,
+                "password": auth.password,
+                "auth": STANDARD.encode(format!("{}:{}", auth.username, auth.password))
+            });
+            if let Some(email) = &auth.email {
+                entry["email"] = Value::String(email.clone());
+            }
+            (registry.clone(), entry)
+        }).collect();
+        json!({ "auths": auths }).to_string()
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// A key is not a valid config key.
+    InvalidKey(String),
+    /// The data all together is larger than [`MAX_SIZE`].
+    TooLarge(usize),
+    /// A key the type requires is missing.
+    MissingKey(SecretType, String),
+    MissingAnnotation(String),
+    UnknownType(String),
+    WrongType { expected: SecretType, actual: SecretType },
+    /// The value of a key is not valid base64.
+    Base64(String, String),
+    /// The value of a key is not the PEM it should be.
+    Pem(String, String),
+    /// The value of a key is not the JSON it should be.
+    Json(String, String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidKey(key) => write!(f, "invalid key \"{}\": a valid config key must consist of alphanumeric characters, '-', '_' or '.'", key),
+            Self::TooLarge(size) =>
+                write!(f, "data is {} bytes, larger than the maximum of {}", size, MAX_SIZE),
+            Self::MissingKey(r#type, key) =>
+                write!(f, "{} secret requires key \"{}\"", r#type, key),
+            Self::MissingAnnotation(annotation) =>
+                write!(f, "missing annotation \"{}\"", annotation),
+            Self::UnknownType(r#type) => write!(f, "unknown secret type \"{}\"", r#type),
+            Self::WrongType { expected, actual } =>
+                write!(f, "secret is of type {}, not {}", actual, expected),
+            Self::Base64(key, message) => write!(f, "{}: invalid base64: {}", key, message),
+            Self::Pem(key, message) => write!(f, "{}: invalid PEM: {}", key, message),
+            Self::Json(key, message) => write!(f, "{}: invalid JSON: {}", key, message)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret(r#type: SecretType, string_data: &[(&str, &str)]) -> Secret {
+        Secret {
+            metadata: Metadata::default(),
+            data: None,
+            immutable: None,
+            string_data: string_data.iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            r#type: Some(r#type)
+        }
+    }
+
+    #[test]
+    fn validates_keys_per_type() {
+        assert!(secret(SecretType::Opaque, &[("config.yaml", "")]).validate().is_ok());
+        assert!(matches!(secret(SecretType::Opaque, &[("a/b", "")]).validate(),
+            Err(Error::InvalidKey(key)) if key == "a/b"));
+        assert!(matches!(secret(SecretType::Tls, &[(TLS_CERT_KEY, "")]).validate(),
+            Err(Error::MissingKey(SecretType::Tls, key)) if key == TLS_PRIVATE_KEY_KEY));
+        assert!(secret(SecretType::BasicAuth, &[(BASIC_AUTH_PASSWORD_KEY, "")]).validate()
+            .is_ok());
+        assert!(matches!(secret(SecretType::DockerConfigJson, &[(DOCKER_CONFIG_JSON_KEY, "{")])
+            .validate(), Err(Error::Json(..))));
+        assert!(matches!(secret(SecretType::ServiceAccountToken, &[]).validate(),
+            Err(Error::MissingAnnotation(_))));
+        let large = "x".repeat(MAX_SIZE + 1);
+        assert!(matches!(secret(SecretType::Opaque, &[("large", &large)]).validate(),
+            Err(Error::TooLarge(_))));
+        assert!(matches!(secret(SecretType::Opaque, &[]).tls(),
+            Err(Error::WrongType { expected: SecretType::Tls, actual: SecretType::Opaque })));
+    }
+
+    #[test]
+    fn merges_string_data_over_data() {
+        let mut secret = secret(SecretType::Opaque, &[("password", "new")]);
+        secret.insert_encoded("password", "b2xk").unwrap();
+        secret.insert_encoded("username", "YWRtaW4=").unwrap();
+        assert!(matches!(secret.insert_encoded("token", "not base64!"), Err(Error::Base64(..))));
+        assert_eq!(secret.get("password"), Some(&b"new"[..]));
+        assert_eq!(secret.encoded_data()["password"], "bmV3");
+
+        secret.merge_string_data();
+        assert!(secret.string_data.is_empty());
+        assert_eq!(secret.get("password"), Some(&b"new"[..]));
+        assert_eq!(secret.get("username"), Some(&b"admin"[..]));
+    }
+
+    #[test]
+    fn reads_docker_configs() {
+        let config = DockerConfig {
+            auths: [("registry.example.com".to_owned(), DockerAuth {
+                username: "user".to_owned(),
+                password: "pa:ss".to_owned(),
+                email: None
+            })].into()
+        };
+        let json = config.to_json();
+        let secret = secret(SecretType::DockerConfigJson, &[(DOCKER_CONFIG_JSON_KEY, &json)]);
+        assert_eq!(secret.docker_config().unwrap(), config);
+
+        // The legacy format is not wrapped in `auths`, and `auth` wins over the other fields.
+        let legacy = r#"{"registry.example.com": {"username": "other", "auth": "dXNlcjpwYTpzcw=="}}"#;
+        assert_eq!(DockerConfig::from_json(legacy.as_bytes(), false).unwrap(), config);
+        assert!(DockerConfig::from_json(br#"{"auths": []}"#, true).is_err());
+    }
+
+    #[test]
+    fn parses_types() {
+        assert_eq!("".parse::<SecretType>().unwrap(), SecretType::Opaque);
+        assert_eq!(SecretType::Tls.as_str().parse::<SecretType>().unwrap(), SecretType::Tls);
+        assert!("kubernetes.io/unknown".parse::<SecretType>().is_err());
+    }
 }
@@ -2,16 +2,19 @@
 Reference <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/typed-local-object-reference/>
 */
 
-use kfl::Decode;
+use kfl::{Decode, DecodeScalar};
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone)]
 pub struct TypedLocalReference {
-    kind: String,
-    name: String,
-    api_group: Option<String>
+    #[kfl(property, default)]
+    pub kind: String,
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub api_group: Option<String>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, DecodeScalar)]
 pub enum TypedLocalReferenceKind {
 
 }
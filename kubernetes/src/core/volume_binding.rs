@@ -0,0 +1,811 @@
+/*!
+Binding of persistent volume claims to persistent volumes, as the persistent volume controller and the volume binding of the scheduler do, evaluated offline.
+
+A claim is bound to the smallest available volume that satisfies it: enough capacity, all its access modes, the same volume mode and storage class, labels its selector matches and, once a node is chosen, node affinity that node matches. A claim of a class with a provisioner that no volume satisfies gets a volume provisioned. Claims of `WaitForFirstConsumer` classes wait until a pod using them is scheduled, see [`Binder::schedule`]. Volumes whose claims are deleted are released, then reclaimed as their reclaim policy says.
+
+- Concepts <https://kubernetes.io/docs/concepts/storage/persistent-volumes/#binding>
+- Concepts <https://kubernetes.io/docs/concepts/storage/storage-classes/#volume-binding-mode>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/volume/persistentvolume/pv_controller.go>
+- Source <https://github.com/kubernetes/component-helpers/blob/master/storage/volume/pv_helpers.go>
+*/
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt
+};
+
+use crate::{
+    core::{
+        node::Node,
+        node_selector_requirement::NodeSelectorRequirement,
+        persistent_volume::{self, NodeAffinity, PersistentVolume, VolumeMode},
+        persistent_volume_claim::{self, PersistentVolumeClaim},
+        Reference
+    },
+    meta::Metadata,
+    node_selector::{NodeSelector, NodeSelectorTerm},
+    quantity::{Amount, Quantity},
+    storage::storage_class::{StorageClass, VolumeBindingMode}
+};
+
+/// Annotation of claims the controller has bound, so that it does not bind them again.
+pub const BIND_COMPLETED: &str = "pv.kubernetes.io/bind-completed";
+/// Annotation of the claims and volumes the controller, not a user, bound.
+pub const BOUND_BY_CONTROLLER: &str = "pv.kubernetes.io/bound-by-controller";
+/// Annotation of delayed claims, the node chosen for the first pod using them.
+pub const SELECTED_NODE: &str = "volume.kubernetes.io/selected-node";
+/// Annotation of provisioned volumes, the provisioner that created them.
+pub const PROVISIONED_BY: &str = "pv.kubernetes.io/provisioned-by";
+/// Annotation of claims to provision, the provisioner to do it.
+pub const STORAGE_PROVISIONER: &str = "volume.kubernetes.io/storage-provisioner";
+/// Annotation of the storage class of claims that name none, `true`.
+pub const IS_DEFAULT_CLASS: &str = "storageclass.kubernetes.io/is-default-class";
+/// Provisioner of classes whose volumes are created by hand.
+pub const NO_PROVISIONER: &str = "kubernetes.io/no-provisioner";
+
+/// What [`Binder::sync`] and [`Binder::schedule`] did. Claims are by `namespace/name`, volumes by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Bound { claim: String, volume: String },
+    /// The volume was provisioned for the claim, and bound to it.
+    Provisioned { claim: String, volume: String },
+    /// The claim waits for a pod using it to be scheduled.
+    WaitForFirstConsumer { claim: String },
+    /// No volume satisfies the claim, and none can be provisioned, or the volume the claim names does not satisfy it, what the controller reports as `VolumeMismatch`.
+    FailedBinding { claim: String, message: String },
+    /// The volume of the bound claim no longer exists.
+    Lost { claim: String },
+    /// The claim of the volume no longer exists.
+    Released { volume: String },
+    /// The released volume was deleted, as its reclaim policy says.
+    Deleted { volume: String },
+    /// The released volume was scrubbed and made available again.
+    Recycled { volume: String }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    ClaimNotFound(String),
+    /// The claim cannot be bound on the node, with why.
+    Unschedulable { claim: String, node: String, message: String }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ClaimNotFound(claim) =>
+                write!(f, "persistentvolumeclaim \"{}\" not found", claim),
+            Self::Unschedulable { claim, node, message } =>
+                write!(f, "claim {} cannot be bound on node {}: {}", claim, node, message)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The volumes, claims and storage classes of a cluster, and the binding between them.
+#[derive(Debug, Clone, Default)]
+pub struct Binder {
+    /// By name.
+    pub volumes: BTreeMap<String, PersistentVolume>,
+    /// By `namespace/name`.
+    pub claims: BTreeMap<String, PersistentVolumeClaim>,
+    /// By name.
+    pub classes: BTreeMap<String, StorageClass>
+}
+
+impl Binder {
+    pub fn add_class(&mut self, class: StorageClass) {
+        let name = class.metadata.as_ref().and_then(|metadata| metadata.name.clone())
+            .unwrap_or_default();
+        self.classes.insert(name, class);
+    }
+
+    pub fn add_volume(&mut self, volume: PersistentVolume) {
+        let name = volume.metadata.name.clone().unwrap_or_default();
+        self.volumes.insert(name, volume);
+    }
+
+    /// Adds the claim, pending, in the default storage class if it names none.
+    pub fn add_claim(&mut self, mut claim: PersistentVolumeClaim) {
+        if claim.spec.storage_class_name.is_none() {
+            claim.spec.storage_class_name = self.default_class().map(str::to_owned);
+        }
+        claim.status.get_or_insert_with(Default::default);
+        self.claims.insert(claim_key(&claim), claim);
+    }
+
+    pub fn delete_claim(&mut self, key: &str) -> Option<PersistentVolumeClaim> {
+        self.claims.remove(key)
+    }
+
+    pub fn delete_volume(&mut self, name: &str) -> Option<PersistentVolume> {
+        self.volumes.remove(name)
+    }
+
+    /// The class annotated as default, the newest if several are.
+    pub fn default_class(&self) -> Option<&str> {
+        self.classes.iter()
+            .filter(|(_, class)| class.metadata.as_ref().and_then(|metadata|
+                metadata.annotations.get(IS_DEFAULT_CLASS)).is_some_and(|value| value == "true"))
+            .max_by_key(|(_, class)| class.metadata.as_ref()
+                .and_then(|metadata| metadata.creation_timestamp))
+            .map(|(name, _)| name.as_str())
+    }
+
+    fn binding_mode(&self, claim: &PersistentVolumeClaim) -> VolumeBindingMode {
+        self.classes.get(class_name(claim))
+            .and_then(|class| class.volume_binding_mode)
+            .unwrap_or_default()
+    }
+
+    /**
+    The volume to bind `claim` to: the volume pre-bound to it if that satisfies it, else the smallest available volume that does.
+
+    Without `node`, volumes are not matched to claims of `WaitForFirstConsumer` classes, unless pre-bound; with it, volumes must also have node affinity the node matches.
+    */
+    pub fn find_matching_volume(&self, claim: &PersistentVolumeClaim, node: Option<&Node>)
+        -> Option<&str>
+    {
+        let delayed = self.binding_mode(claim) == VolumeBindingMode::WaitForFirstConsumer;
+        let requested = requested(claim);
+        let mut best: Option<(&str, Amount)> = None;
+        for (name, volume) in &self.volumes {
+            let Some(capacity) = storage(&volume.spec.capacity) else { continue };
+            if let Some(reference) = &volume.spec.claim_ref {
+                if refers_to(reference, claim) && satisfies(volume, claim, node) {
+                    return Some(name)
+                }
+                continue
+            }
+            if delayed && node.is_none()
+                || !matches!(phase(volume), persistent_volume::Phase::Pending
+                    | persistent_volume::Phase::Available)
+                || capacity < requested
+                || !satisfies(volume, claim, node)
+                || best.is_some_and(|(_, smallest)| smallest <= capacity)
+            {
+                continue
+            }
+            best = Some((name, capacity));
+        }
+        best.map(|(name, _)| name)
+    }
+
+    /**
+    Brings claims and volumes one step closer to being bound, as a pass of the controller: claims first, then volumes.
+
+    Pending claims are bound to the volume they name if it satisfies them, or that [`find_matching_volume`][Self::find_matching_volume] finds, or to one provisioned for them; bound claims whose volume is gone are lost. Volumes without claims are available; those whose claims are gone are released, then retained, deleted or recycled.
+    */
+    pub fn sync(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        let keys: Vec<String> = self.claims.keys().cloned().collect();
+        for key in keys {
+            self.sync_claim(&key, &mut events);
+        }
+        let names: Vec<String> = self.volumes.keys().cloned().collect();
+        for name in names {
+            self.sync_volume(&name, &mut events);
+        }
+        events
+    }
+
+    fn sync_claim(&mut self, key: &str, events: &mut Vec<Event>) {
+        let Some(claim) = self.claims.get(key) else { return };
+        let claim_phase = claim.status.as_ref().and_then(|status| status.phase)
+            .unwrap_or_default();
+        match claim.spec.volume_name.as_deref().filter(|name| !name.is_empty()) {
+            Some(name) if claim_phase != persistent_volume_claim::Phase::Pending => {
+                if !self.volumes.contains_key(name)
+                    && claim_phase != persistent_volume_claim::Phase::Lost
+                {
+                    set_claim_phase(self.claims.get_mut(key), persistent_volume_claim::Phase::Lost);
+                    events.push(Event::Lost { claim: key.to_owned() });
+                }
+            }
+            Some(name) => {
+                let name = name.to_owned();
+                match self.volumes.get(&name) {
+                    Some(volume) if volume.spec.claim_ref.as_ref()
+                        .is_none_or(|reference| refers_to(reference, claim)) =>
+                    {
+                        let mismatch = if storage(&volume.spec.capacity)
+                            .is_none_or(|capacity| capacity < requested(claim))
+                        {
+                            Some("requested PV is too small")
+                        } else {
+                            mismatch(volume, claim, None)
+                        };
+                        match mismatch {
+                            Some(why) => events.push(Event::FailedBinding {
+                                claim: key.to_owned(),
+                                message: format!("Cannot bind to requested volume \"{}\": {}", name, why)
+                            }),
+                            None => {
+                                self.bind(key, &name);
+                                events.push(Event::Bound { claim: key.to_owned(), volume: name });
+                            }
+                        }
+                    }
+                    Some(_) => events.push(Event::FailedBinding {
+                        claim: key.to_owned(),
+                        message: format!("volume \"{}\" already bound to a different claim", name)
+                    }),
+                    None => events.push(Event::FailedBinding {
+                        claim: key.to_owned(),
+                        message: format!("volume \"{}\" not found", name)
+                    })
+                }
+            }
+            None => {
+                if let Some(name) = self.find_matching_volume(claim, None) {
+                    let name = name.to_owned();
+                    self.bind(key, &name);
+                    events.push(Event::Bound { claim: key.to_owned(), volume: name });
+                } else if self.binding_mode(claim) == VolumeBindingMode::WaitForFirstConsumer {
+                    events.push(Event::WaitForFirstConsumer { claim: key.to_owned() });
+                } else if self.provisioner(claim).is_some() {
+                    events.extend(self.provision(key, None));
+                } else {
+                    let message = match class_name(claim) {
+                        "" => "no persistent volumes available for this claim and no storage class is set"
+                            .to_owned(),
+                        class => format!("storageclass \"{}\" has no provisioner", class)
+                    };
+                    events.push(Event::FailedBinding { claim: key.to_owned(), message });
+                }
+            }
+        }
+    }
+
+    fn sync_volume(&mut self, name: &str, events: &mut Vec<Event>) {
+        let Some(volume) = self.volumes.get(name) else { return };
+        let Some(reference) = &volume.spec.claim_ref else {
+            set_volume_phase(self.volumes.get_mut(name), persistent_volume::Phase::Available);
+            return
+        };
+        let key = format!("{}/{}", reference.namespace, reference.name);
+        match self.claims.get(&key).filter(|claim| refers_to(reference, claim)) {
+            None => {
+                if phase(volume) != persistent_volume::Phase::Released {
+                    set_volume_phase(self.volumes.get_mut(name), persistent_volume::Phase::Released);
+                    events.push(Event::Released { volume: name.to_owned() });
+                }
+                self.reclaim(name, events);
+            }
+            Some(claim) if claim.spec.volume_name.as_deref() == Some(name) =>
+                set_volume_phase(self.volumes.get_mut(name), persistent_volume::Phase::Bound),
+            Some(claim) if claim.spec.volume_name.as_deref().is_some_and(|other| !other.is_empty())
+                && volume.metadata.annotations.contains_key(BOUND_BY_CONTROLLER) =>
+            {
+                // Bound by the controller to a claim since bound to another volume.
+                let volume = self.volumes.get_mut(name).expect("volume exists");
+                volume.spec.claim_ref = None;
+                volume.metadata.annotations.remove(BOUND_BY_CONTROLLER);
+                set_volume_phase(Some(volume), persistent_volume::Phase::Available);
+            }
+            // Pre-bound, waiting for its claim.
+            Some(_) => set_volume_phase(self.volumes.get_mut(name), persistent_volume::Phase::Available)
+        }
+    }
+
+    /// Reclaims the released volume `name` as its reclaim policy says.
+    fn reclaim(&mut self, name: &str, events: &mut Vec<Event>) {
+        let Some(volume) = self.volumes.get_mut(name) else { return };
+        match volume.spec.persistent_volume_reclaim_policy.unwrap_or_default() {
+            persistent_volume::ReclaimPolicy::Retain => {}
+            persistent_volume::ReclaimPolicy::Delete => {
+                self.volumes.remove(name);
+                events.push(Event::Deleted { volume: name.to_owned() });
+            }
+            persistent_volume::ReclaimPolicy::Recycle => {
+                volume.spec.claim_ref = None;
+                volume.metadata.annotations.remove(BOUND_BY_CONTROLLER);
+                set_volume_phase(Some(volume), persistent_volume::Phase::Available);
+                events.push(Event::Recycled { volume: name.to_owned() });
+            }
+        }
+    }
+
+    /**
+    Binds the claim `claim_key` of a `WaitForFirstConsumer` class once a pod using it is scheduled to `node`: to a volume `node` can access, or to one provisioned for it there.
+
+    Errors if the claim cannot be bound on `node`, which the scheduler then filters out. Claims already bound only check their volume is accessible from `node`.
+    */
+    pub fn schedule(&mut self, claim_key: &str, node: &Node) -> Result<Vec<Event>, Error> {
+        let claim = self.claims.get(claim_key)
+            .ok_or_else(|| Error::ClaimNotFound(claim_key.to_owned()))?;
+        let node_name = node.metadata.name.clone().unwrap_or_default();
+        let unschedulable = |message: &str| Error::Unschedulable {
+            claim: claim_key.to_owned(),
+            node: node_name.clone(),
+            message: message.to_owned()
+        };
+        if let Some(name) = claim.spec.volume_name.as_deref().filter(|name| !name.is_empty()) {
+            return match self.volumes.get(name) {
+                Some(volume) if accessible(volume, node) => Ok(Vec::new()),
+                Some(_) => Err(unschedulable("node(s) had volume node affinity conflict")),
+                None => Err(unschedulable(&format!("volume \"{}\" not found", name)))
+            }
+        }
+        if let Some(name) = self.find_matching_volume(claim, Some(node)) {
+            let name = name.to_owned();
+            self.bind(claim_key, &name);
+            return Ok(vec![Event::Bound { claim: claim_key.to_owned(), volume: name }])
+        }
+        let Some(class) = self.provisioner(claim).and_then(|_| self.classes.get(class_name(claim)))
+        else {
+            return Err(unschedulable("node(s) didn't find available persistent volumes to bind"))
+        };
+        if !class.allowed_topologies.is_empty()
+            && !class.allowed_topologies.iter().any(|term| term.matches(&node.metadata.labels))
+        {
+            return Err(unschedulable("node(s) didn't match the allowed topologies of the storage class"))
+        }
+        if let Some(claim) = self.claims.get_mut(claim_key) {
+            claim.metadata.annotations.insert(SELECTED_NODE.to_owned(), node_name.clone());
+        }
+        Ok(self.provision(claim_key, Some(node)))
+    }
+
+    /// The provisioner of the class of `claim`, if it has one.
+    fn provisioner(&self, claim: &PersistentVolumeClaim) -> Option<&str> {
+        self.classes.get(class_name(claim))
+            .map(|class| class.provisioner.as_str())
+            .filter(|provisioner| !provisioner.is_empty() && *provisioner != NO_PROVISIONER)
+    }
+
+    /**
+    Provisions a volume for the claim `key`, as the external provisioner of its class would, and binds the claim to it.
+
+    The volume has the requested capacity, the access modes and volume mode of the claim, and the reclaim policy and mount options of the class. On `node`, it is restricted to the values of the labels of `node` that the allowed topologies of the class name.
+    */
+    fn provision(&mut self, key: &str, node: Option<&Node>) -> Vec<Event> {
+        let Some(claim) = self.claims.get(key) else { return Vec::new() };
+        let Some(class) = self.classes.get(class_name(claim)) else { return Vec::new() };
+        let name = format!("pvc-{}", claim.metadata.uid.clone()
+            .unwrap_or_else(|| key.replace('/', "-")));
+        let capacity: HashMap<String, Quantity> = claim.spec.resources.as_ref()
+            .and_then(|resources| resources.requests.get("storage"))
+            .map(|quantity| ("storage".to_owned(), quantity.clone()))
+            .into_iter().collect();
+        let node_affinity = node.and_then(|node| class.allowed_topologies.iter()
+            .find(|term| term.matches(&node.metadata.labels))
+            .map(|term| NodeAffinity {
+                required: NodeSelector {
+                    node_selector_terms: vec![NodeSelectorTerm {
+                        match_expressions: term.match_label_expressions.iter()
+                            .map(|requirement| NodeSelectorRequirement {
+                                key: requirement.key.clone(),
+                                operator: "In".to_owned(),
+                                values: node.metadata.labels.get(&requirement.key)
+                                    .cloned().into_iter().collect()
+                            })
+                            .collect(),
+                        match_fields: Vec::new()
+                    }]
+                }
+            }));
+        let volume = PersistentVolume {
+            metadata: Metadata {
+                name: Some(name.clone()),
+                annotations: HashMap::from([
+                    (PROVISIONED_BY.to_owned(), class.provisioner.clone())
+                ]),
+                ..Default::default()
+            },
+            spec: persistent_volume::Spec {
+                access_modes: claim.spec.access_modes.clone(),
+                capacity,
+                claim_ref: Some(reference(claim)),
+                mount_options: class.mount_options.clone(),
+                node_affinity,
+                persistent_volume_reclaim_policy:
+                    Some(class.reclaim_policy.unwrap_or_default()),
+                storage_class_name: claim.spec.storage_class_name.clone(),
+                volume_mode: claim.spec.volume_mode,
+                host_path: None,
+                local: None
+            },
+            status: Some(persistent_volume::Status {
+                phase: Some(persistent_volume::Phase::Pending),
+                ..Default::default()
+            })
+        };
+        let provisioner = class.provisioner.clone();
+        if let Some(claim) = self.claims.get_mut(key) {
+            claim.metadata.annotations.insert(STORAGE_PROVISIONER.to_owned(), provisioner);
+        }
+        self.volumes.insert(name.clone(), volume);
+        self.bind(key, &name);
+        vec![Event::Provisioned { claim: key.to_owned(), volume: name }]
+    }
+
+    /// Binds the claim `key` and the volume `name` both ways, as bound.
+    fn bind(&mut self, key: &str, name: &str) {
+        let (Some(claim), Some(volume)) = (self.claims.get_mut(key), self.volumes.get_mut(name))
+        else { return };
+        if volume.spec.claim_ref.is_none() {
+            volume.metadata.annotations.insert(BOUND_BY_CONTROLLER.to_owned(), "yes".to_owned());
+        }
+        volume.spec.claim_ref = Some(reference(claim));
+        set_volume_phase(Some(volume), persistent_volume::Phase::Bound);
+        if claim.spec.volume_name.as_deref().is_none_or(str::is_empty) {
+            claim.spec.volume_name = Some(name.to_owned());
+            claim.metadata.annotations.insert(BOUND_BY_CONTROLLER.to_owned(), "yes".to_owned());
+        }
+        claim.metadata.annotations.insert(BIND_COMPLETED.to_owned(), "yes".to_owned());
+        let status = claim.status.get_or_insert_with(Default::default);
+        status.phase = Some(persistent_volume_claim::Phase::Bound);
+        status.capacity = volume.spec.capacity.clone();
+        status.access_modes = volume.spec.access_modes.clone();
+    }
+}
+
+/// The key of `claim` in [`Binder::claims`], `namespace/name`.
+pub fn claim_key(claim: &PersistentVolumeClaim) -> String {
+    format!("{}/{}", claim.metadata.namespace.as_deref().unwrap_or("default"),
+        claim.metadata.name.as_deref().unwrap_or_default())
+}
+
+/// The storage class of `claim`, empty for none.
+fn class_name(claim: &PersistentVolumeClaim) -> &str {
+    claim.spec.storage_class_name.as_deref().unwrap_or_default()
+}
+
+/// The storage `claim` requests, none if it requests none.
+fn requested(claim: &PersistentVolumeClaim) -> Amount {
+    claim.spec.resources.as_ref()
+        .and_then(|resources| storage(&resources.requests))
+        .unwrap_or_default()
+}
+
+fn storage(resources: &HashMap<String, Quantity>) -> Option<Amount> {
+    resources.get("storage")?.parse().ok()
+}
+
+fn phase(volume: &PersistentVolume) -> persistent_volume::Phase {
+    volume.status.as_ref().and_then(|status| status.phase).unwrap_or_default()
+}
+
+fn set_volume_phase(volume: Option<&mut PersistentVolume>, phase: persistent_volume::Phase) {
+    if let Some(volume) = volume {
+        volume.status.get_or_insert_with(Default::default).phase = Some(phase);
+    }
+}
+
+fn set_claim_phase(claim: Option<&mut PersistentVolumeClaim>, phase: persistent_volume_claim::Phase) {
+    if let Some(claim) = claim {
+        claim.status.get_or_insert_with(Default::default).phase = Some(phase);
+    }
+}
+
+/// A claim reference to `claim`.
+fn reference(claim: &PersistentVolumeClaim) -> Reference {
+    Reference {
+        kind: "PersistentVolumeClaim".to_owned(),
+        namespace: claim.metadata.namespace.clone().unwrap_or_else(|| "default".to_owned()),
+        name: claim.metadata.name.clone().unwrap_or_default(),
+        uid: claim.metadata.uid.clone().unwrap_or_default(),
+        resource_version: claim.metadata.resource_version.clone().unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+/// Whether `reference` refers to `claim`, to any claim of its name if it has no UID.
+fn refers_to(reference: &Reference, claim: &PersistentVolumeClaim) -> bool {
+    reference.namespace == claim.metadata.namespace.as_deref().unwrap_or("default")
+        && Some(reference.name.as_str()) == claim.metadata.name.as_deref()
+        && (reference.uid.is_empty()
+            || claim.metadata.uid.as_deref().is_none_or(|uid| uid == reference.uid))
+}
+
+/// Whether pods on `node` can use `volume`.
+fn accessible(volume: &PersistentVolume, node: &Node) -> bool {
+    volume.spec.node_affinity.as_ref().is_none_or(|affinity| affinity.required.matches(
+        &node.metadata.labels, node.metadata.name.as_deref().unwrap_or_default()))
+}
+
+/// Whether `volume` satisfies `claim`, on `node` if chosen, leaving capacity aside.
+fn satisfies(volume: &PersistentVolume, claim: &PersistentVolumeClaim, node: Option<&Node>) -> bool {
+    mismatch(volume, claim, node).is_none()
+}
+
+/// Why `volume` does not satisfy `claim`, on `node` if chosen, leaving capacity aside.
+fn mismatch(volume: &PersistentVolume, claim: &PersistentVolumeClaim, node: Option<&Node>)
+    -> Option<&'static str>
+{
+    if volume.spec.volume_mode.unwrap_or_default()
+        != claim.spec.volume_mode.unwrap_or(VolumeMode::Filesystem)
+    {
+        Some("incompatible volumeMode")
+    } else if volume.spec.storage_class_name.as_deref().unwrap_or_default() != class_name(claim) {
+        Some("storageClassName does not match")
+    } else if claim.spec.selector.as_ref()
+        .is_some_and(|selector| !selector.matches(&volume.metadata.labels))
+    {
+        Some("volume labels do not match the claim's selector")
+    } else if !claim.spec.access_modes.iter().all(|mode| volume.spec.access_modes.contains(mode)) {
+        Some("incompatible accessMode")
+    } else if node.is_some_and(|node| !accessible(volume, node)) {
+        Some("volume node affinity conflict")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::persistent_volume::{AccessMode, ReclaimPolicy},
+        storage::storage_class::{TopologySelectorLabelRequirement, TopologySelectorTerm}
+    };
+    use super::*;
+
+    fn volume(name: &str, storage: &str, access_modes: &[AccessMode]) -> PersistentVolume {
+        PersistentVolume {
+            metadata: Metadata { name: Some(name.to_owned()), ..Metadata::default() },
+            spec: persistent_volume::Spec {
+                access_modes: access_modes.to_vec(),
+                capacity: [("storage".to_owned(), storage.to_owned())].into(),
+                ..persistent_volume::Spec::default()
+            },
+            status: None
+        }
+    }
+
+    /// A claim of `storage` named `name` in the default namespace, for the volume `volume_name`.
+    fn claim(name: &str, storage: &str, volume_name: &str) -> PersistentVolumeClaim {
+        PersistentVolumeClaim {
+            metadata: Metadata {
+                name: Some(name.to_owned()),
+                namespace: Some("default".to_owned()),
+                ..Metadata::default()
+            },
+            spec: persistent_volume_claim::Spec {
+                access_modes: vec![AccessMode::ReadWriteOnce],
+                resources: Some(persistent_volume_claim::Resource {
+                    requests: [("storage".to_owned(), storage.to_owned())].into(),
+                    ..Default::default()
+                }),
+                volume_name: Some(volume_name.to_owned()),
+                storage_class_name: Some(String::new()),
+                ..persistent_volume_claim::Spec::default()
+            },
+            status: None
+        }
+    }
+
+    /// A claim of `storage` named `name` in the default namespace, in the class `class`.
+    fn unbound_claim(name: &str, storage: &str, class: Option<&str>) -> PersistentVolumeClaim {
+        let mut claim = claim(name, storage, "");
+        claim.spec.volume_name = None;
+        claim.spec.storage_class_name = class.map(str::to_owned);
+        claim
+    }
+
+    fn class(name: &str, provisioner: &str, volume_binding_mode: VolumeBindingMode) -> StorageClass {
+        StorageClass {
+            metadata: Some(Metadata { name: Some(name.to_owned()), ..Metadata::default() }),
+            provisioner: provisioner.to_owned(),
+            allow_volume_expansion: None,
+            allowed_topologies: Vec::new(),
+            mount_options: Vec::new(),
+            parameters: HashMap::new(),
+            reclaim_policy: None,
+            volume_binding_mode: Some(volume_binding_mode)
+        }
+    }
+
+    fn node(name: &str, zone: &str) -> Node {
+        Node {
+            metadata: Metadata {
+                name: Some(name.to_owned()),
+                labels: [
+                    ("kubernetes.io/hostname".to_owned(), name.to_owned()),
+                    ("topology.kubernetes.io/zone".to_owned(), zone.to_owned())
+                ].into(),
+                ..Metadata::default()
+            },
+            spec: Default::default(),
+            status: None
+        }
+    }
+
+    /// Node affinity to the node named `name`.
+    fn on(name: &str) -> NodeAffinity {
+        NodeAffinity {
+            required: NodeSelector {
+                node_selector_terms: vec![NodeSelectorTerm {
+                    match_expressions: vec![NodeSelectorRequirement {
+                        key: "kubernetes.io/hostname".to_owned(),
+                        operator: "In".to_owned(),
+                        values: vec![name.to_owned()]
+                    }],
+                    match_fields: Vec::new()
+                }]
+            }
+        }
+    }
+
+    #[test]
+    fn binds_claims_to_the_smallest_volume_that_satisfies_them() {
+        let mut binder = Binder::default();
+        binder.add_volume(volume("small", "1Gi", &[AccessMode::ReadWriteOnce]));
+        binder.add_volume(volume("medium", "8Gi", &[AccessMode::ReadWriteOnce]));
+        binder.add_volume(volume("large", "20Gi", &[AccessMode::ReadWriteOnce]));
+        binder.add_volume(volume("read-only", "6Gi", &[AccessMode::ReadOnlyMany]));
+        let mut classy = volume("classy", "6Gi", &[AccessMode::ReadWriteOnce]);
+        classy.spec.storage_class_name = Some("fast".to_owned());
+        binder.add_volume(classy);
+        let mut fast = volume("fast", "10Gi", &[AccessMode::ReadWriteOnce]);
+        fast.metadata.labels.insert("tier".to_owned(), "fast".to_owned());
+        binder.add_volume(fast);
+        binder.add_claim(unbound_claim("data", "5Gi", Some("")));
+        let mut labelled = unbound_claim("labelled", "5Gi", Some(""));
+        labelled.spec.selector = Some("tier=fast".parse().unwrap());
+        binder.add_claim(labelled);
+        assert_eq!(binder.sync(), [
+            Event::Bound { claim: "default/data".to_owned(), volume: "medium".to_owned() },
+            Event::Bound { claim: "default/labelled".to_owned(), volume: "fast".to_owned() }
+        ]);
+        let data = &binder.claims["default/data"];
+        assert_eq!(data.spec.volume_name.as_deref(), Some("medium"));
+        let status = data.status.as_ref().unwrap();
+        assert_eq!(status.phase, Some(persistent_volume_claim::Phase::Bound));
+        assert_eq!(status.capacity["storage"], "8Gi");
+        assert!(data.metadata.annotations.contains_key(BIND_COMPLETED));
+        assert_eq!(binder.volumes["medium"].spec.claim_ref.as_ref().unwrap().name, "data");
+        assert_eq!(phase(&binder.volumes["medium"]), persistent_volume::Phase::Bound);
+        assert_eq!(phase(&binder.volumes["large"]), persistent_volume::Phase::Available);
+    }
+
+    #[test]
+    fn waits_for_the_first_consumer_to_bind_on_its_node() {
+        let mut binder = Binder::default();
+        binder.add_class(class("local", NO_PROVISIONER, VolumeBindingMode::WaitForFirstConsumer));
+        for name in ["a", "b"] {
+            let mut local = volume(name, "10Gi", &[AccessMode::ReadWriteOnce]);
+            local.spec.storage_class_name = Some("local".to_owned());
+            local.spec.node_affinity = Some(on(name));
+            binder.add_volume(local);
+        }
+        binder.add_claim(unbound_claim("data", "5Gi", Some("local")));
+        assert_eq!(binder.sync(),
+            [Event::WaitForFirstConsumer { claim: "default/data".to_owned() }]);
+        assert_eq!(binder.schedule("default/data", &node("c", "zone-a")), Err(Error::Unschedulable {
+            claim: "default/data".to_owned(),
+            node: "c".to_owned(),
+            message: "node(s) didn't find available persistent volumes to bind".to_owned()
+        }));
+        assert_eq!(binder.schedule("default/data", &node("b", "zone-a")),
+            Ok(vec![Event::Bound { claim: "default/data".to_owned(), volume: "b".to_owned() }]));
+        assert_eq!(binder.schedule("default/data", &node("b", "zone-a")), Ok(Vec::new()));
+        assert!(matches!(binder.schedule("default/data", &node("a", "zone-a")),
+            Err(Error::Unschedulable { message, .. })
+                if message == "node(s) had volume node affinity conflict"));
+        assert_eq!(binder.schedule("default/gone", &node("a", "zone-a")),
+            Err(Error::ClaimNotFound("default/gone".to_owned())));
+    }
+
+    #[test]
+    fn provisions_volumes_in_the_default_class() {
+        let mut binder = Binder::default();
+        let mut standard = class("standard", "ebs.csi.aws.com", VolumeBindingMode::Immediate);
+        standard.metadata.as_mut().unwrap().annotations
+            .insert(IS_DEFAULT_CLASS.to_owned(), "true".to_owned());
+        binder.add_class(standard);
+        binder.add_class(class("manual", NO_PROVISIONER, VolumeBindingMode::Immediate));
+        binder.add_claim(unbound_claim("data", "5Gi", None));
+        binder.add_claim(unbound_claim("manual", "5Gi", Some("manual")));
+        assert_eq!(binder.sync(), [
+            Event::Provisioned {
+                claim: "default/data".to_owned(),
+                volume: "pvc-default-data".to_owned()
+            },
+            Event::FailedBinding {
+                claim: "default/manual".to_owned(),
+                message: "storageclass \"manual\" has no provisioner".to_owned()
+            }
+        ]);
+        let claim = &binder.claims["default/data"];
+        assert_eq!(claim.spec.storage_class_name.as_deref(), Some("standard"));
+        assert_eq!(claim.metadata.annotations[STORAGE_PROVISIONER], "ebs.csi.aws.com");
+        let volume = &binder.volumes["pvc-default-data"];
+        assert_eq!(volume.metadata.annotations[PROVISIONED_BY], "ebs.csi.aws.com");
+        assert_eq!(volume.spec.capacity["storage"], "5Gi");
+        assert_eq!(volume.spec.persistent_volume_reclaim_policy, Some(ReclaimPolicy::Delete));
+        assert_eq!(phase(volume), persistent_volume::Phase::Bound);
+    }
+
+    #[test]
+    fn provisions_delayed_volumes_in_allowed_topologies() {
+        let mut binder = Binder::default();
+        let mut zonal = class("zonal", "ebs.csi.aws.com", VolumeBindingMode::WaitForFirstConsumer);
+        zonal.allowed_topologies = vec![TopologySelectorTerm {
+            match_label_expressions: vec![TopologySelectorLabelRequirement {
+                key: "topology.kubernetes.io/zone".to_owned(),
+                values: vec!["zone-a".to_owned()]
+            }]
+        }];
+        binder.add_class(zonal);
+        binder.add_claim(unbound_claim("data", "5Gi", Some("zonal")));
+        assert_eq!(binder.sync(),
+            [Event::WaitForFirstConsumer { claim: "default/data".to_owned() }]);
+        assert!(matches!(binder.schedule("default/data", &node("b", "zone-b")),
+            Err(Error::Unschedulable { message, .. })
+                if message == "node(s) didn't match the allowed topologies of the storage class"));
+        assert_eq!(binder.schedule("default/data", &node("a", "zone-a")), Ok(vec![
+            Event::Provisioned {
+                claim: "default/data".to_owned(),
+                volume: "pvc-default-data".to_owned()
+            }
+        ]));
+        assert_eq!(binder.claims["default/data"].metadata.annotations[SELECTED_NODE], "a");
+        let affinity = binder.volumes["pvc-default-data"].spec.node_affinity.as_ref().unwrap();
+        let requirement = &affinity.required.node_selector_terms[0].match_expressions[0];
+        assert_eq!(requirement.key, "topology.kubernetes.io/zone");
+        assert_eq!(requirement.values, ["zone-a"]);
+    }
+
+    #[test]
+    fn reclaims_released_volumes_as_their_policy_says() {
+        let mut binder = Binder::default();
+        for (name, policy) in [
+            ("retain", ReclaimPolicy::Retain),
+            ("delete", ReclaimPolicy::Delete),
+            ("recycle", ReclaimPolicy::Recycle),
+            ("gone", ReclaimPolicy::Retain)
+        ] {
+            let mut volume = volume(name, "10Gi", &[AccessMode::ReadWriteOnce]);
+            volume.spec.persistent_volume_reclaim_policy = Some(policy);
+            binder.add_volume(volume);
+            binder.add_claim(claim(name, "5Gi", name));
+        }
+        assert_eq!(binder.sync().len(), 4);
+        for name in ["retain", "delete", "recycle"] {
+            binder.delete_claim(&format!("default/{}", name));
+        }
+        binder.delete_volume("gone");
+        assert_eq!(binder.sync(), [
+            Event::Lost { claim: "default/gone".to_owned() },
+            Event::Released { volume: "delete".to_owned() },
+            Event::Deleted { volume: "delete".to_owned() },
+            Event::Released { volume: "recycle".to_owned() },
+            Event::Recycled { volume: "recycle".to_owned() },
+            Event::Released { volume: "retain".to_owned() }
+        ]);
+        assert_eq!(binder.sync(), []);
+        assert!(!binder.volumes.contains_key("delete"));
+        assert_eq!(phase(&binder.volumes["retain"]), persistent_volume::Phase::Released);
+        assert_eq!(phase(&binder.volumes["recycle"]), persistent_volume::Phase::Available);
+        assert!(binder.volumes["recycle"].spec.claim_ref.is_none());
+    }
+
+    #[test]
+    fn pre_bound_claims_are_only_bound_to_volumes_that_satisfy_them() {
+        let mut binder = Binder::default();
+        binder.add_volume(volume("small", "1Gi", &[AccessMode::ReadWriteOnce]));
+        binder.add_volume(volume("read-only", "10Gi", &[AccessMode::ReadOnlyMany]));
+        binder.add_volume(volume("fit", "10Gi", &[AccessMode::ReadWriteOnce]));
+        binder.add_claim(claim("too-big", "5Gi", "small"));
+        binder.add_claim(claim("writer", "5Gi", "read-only"));
+        binder.add_claim(claim("data", "5Gi", "fit"));
+        assert_eq!(binder.sync(), [
+            Event::Bound { claim: "default/data".to_owned(), volume: "fit".to_owned() },
+            Event::FailedBinding {
+                claim: "default/too-big".to_owned(),
+                message: "Cannot bind to requested volume \"small\": requested PV is too small"
+                    .to_owned()
+            },
+            Event::FailedBinding {
+                claim: "default/writer".to_owned(),
+                message: "Cannot bind to requested volume \"read-only\": incompatible accessMode"
+                    .to_owned()
+            }
+        ]);
+        assert!(binder.volumes["small"].spec.claim_ref.is_none());
+        assert!(binder.claims["default/too-big"].spec.volume_name.is_some()
+            && !binder.claims["default/too-big"].metadata.annotations.contains_key(BIND_COMPLETED));
+    }
+}
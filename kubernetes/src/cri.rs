@@ -1,94 +1,64 @@
-//! <https://github.com/kubernetes/kubernetes/tree/master/staging/src/k8s.io/cri-api>
+/*!
+The Container Runtime Interface, the gRPC API between the kubelet and container runtimes such as containerd and CRI-O, served on a Unix socket.
 
-pub mod runtime;
-
-use std::time::Duration;
+Implement [`RuntimeService`], with the containers of its [`ContainerManager`], and [`ImageService`] and [`serve`][server::serve] them to write a runtime, or [`connect`][client::connect] to one and call it through [`RuntimeServiceClient`][client::RuntimeServiceClient] and [`ImageServiceClient`][client::ImageServiceClient], which implement the same traits. [`FakeRuntime`][fake::FakeRuntime] implements both in memory to stand in for a runtime in tests.
 
-use runtime::{
-    AttachRequest,
-    AttachResponse,
-    CheckpointContainerRequest,
-    Container,
-    ContainerConfig,
-    ContainerEventResponse,
-    ContainerFilter,
-    ContainerResources,
-    ContainerStatusResponse,
-    ExecRequest,
-    ExecResponse,
-    PodSandboxConfig
-};
+- Concepts <https://kubernetes.io/docs/concepts/architecture/cri/>
+- Source <https://github.com/kubernetes/kubernetes/tree/master/staging/src/k8s.io/cri-api>
+*/
 
-/// Contains methods to manipulate containers managed by a container runtime. The methods are thread-safe.
-pub trait ContainerManager {
-    /// Creates a new container in specified PodSandbox.
-    fn create_container(
-        &self,
-        pod_sandbox_id: String,
-        config: &ContainerConfig,
-        sandbox_config: &PodSandboxConfig
-    ) -> Result<String>;
-
-    /// Starts the container.
-    fn start_container(&self, container_id: String) -> Result<()>;
+pub mod api;
+pub mod client;
+pub mod fake;
+pub mod image;
+pub mod runtime;
+pub mod server;
 
-    /// Stops a running container with a grace period (i.e., timeout).
-    fn stop_container(&self, container_id: String, timeout: i64) -> Result<()>;
+use std::{fmt, io, pin::Pin};
 
-    /// Removes the container.
-    fn remove_container(&self, container_id: String) -> Result<()>;
+use tokio_stream::Stream;
+use tonic::Status;
 
-    /// Lists all containers by filters.
-    fn list_containers(
-        &self,
-        filter: &ContainerFilter
-    ) -> Result<Vec<&Container>>;
+pub use image::ImageService;
+pub use runtime::{ContainerManager, RuntimeService};
 
-    /// Returns the status of the container.
-    fn container_status(
-        &self, container_id: String, verbose: bool
-    ) -> Result<&ContainerStatusResponse>;
+/// Version of the kubelet runtime API, as reported in [`VersionRequest`][api::VersionRequest].
+pub const API_VERSION: &str = "v1";
+/// Condition type of a runtime ready to run pods.
+pub const RUNTIME_READY: &str = "RuntimeReady";
+/// Condition type of a runtime whose network is ready.
+pub const NETWORK_READY: &str = "NetworkReady";
 
-    /// Updates ContainerConfig of the container synchronously.
-    /// If runtime fails to transactionally update the requested resources, an error is returned.
-    fn update_container_resources(
-        &self,
-        container_id: String,
-        resources: &ContainerResources
-    ) -> Result<()>;
+/// Stream of [`ContainerManager::get_container_events`].
+pub type EventStream = Pin<Box<
+    dyn Stream<Item = Result<api::ContainerEventResponse, Status>> + Send>>;
 
-    /// Executes a command in the container, and returns the stdout output.
-    /// If command exits with a non-zero exit code, an error is returned.
-    fn exec_sync(
-        &self,
-        container_id: String,
-        cmd: Vec<String>,
-        timeout: Duration
-    ) -> (stdout Vec<u8>, stderr Vec<u8>, err error);
-    
-    /// Prepares a streaming endpoint to execute a command in the container, and returns the address.
-    fn exec(&self, req: &ExecRequest) -> Result<&ExecResponse>;
+#[derive(Debug)]
+pub enum Error {
+    /// Binding or connecting to the socket failed.
+    Io(io::Error),
+    Transport(tonic::transport::Error)
+}
 
-    /// Attach prepares a streaming endpoint to attach to a running container, and returns the address.
-    fn attach(
-        &self,
-        req: &AttachRequest
-    ) -> Result<&AttachResponse>;
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{}", error),
+            Self::Transport(error) => write!(f, "{}", error)
+        }
+    }
+}
 
-    /// Asks runtime to reopen the stdout/stderr log file
-    /// for the container. If it returns error, new container log file MUST NOT
-    /// be created.
-    fn reopen_container_log(&self, container_id: String) -> error;
+impl std::error::Error for Error {}
 
-    /// Checkpoints a container.
-    fn checkpoint_container(
-        &self,
-        options: &CheckpointContainerRequest
-    ) -> Result<()>;
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
 
-    /// Gets container events from the CRI runtime.
-    fn get_container_events(
-        &self,
-        container_events_ch: chan &ContainerEventResponse
-    ) -> Result<()>;
+impl From<tonic::transport::Error> for Error {
+    fn from(error: tonic::transport::Error) -> Self {
+        Self::Transport(error)
+    }
 }
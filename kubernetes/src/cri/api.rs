@@ -0,0 +1,11 @@
+/*!
+Messages of the `runtime.v1` protobuf package, generated from `proto/runtime/v1/api.proto` by the build script.
+
+Enumerations are stored as `i32` in messages, with a getter of the same name returning the enum.
+
+Source <https://github.com/kubernetes/cri-api/blob/master/pkg/apis/runtime/v1/api.proto>
+*/
+
+#![allow(clippy::all)]
+
+include!(concat!(env!("OUT_DIR"), "/runtime.v1.rs"));
@@ -0,0 +1,381 @@
+//! Clients of a runtime's gRPC services over its Unix socket, as the kubelet's remote runtime.
+
+use std::{io, path::PathBuf};
+
+use hyper_util::rt::TokioIo;
+use prost::Message;
+use tokio::net::UnixStream;
+use tonic::{
+    client::Grpc,
+    codegen::http::uri::PathAndQuery,
+    transport::{Channel, Endpoint, Uri},
+    Status
+};
+use tonic_prost::ProstCodec;
+use tower::service_fn;
+
+use super::{api::*, ContainerManager, Error, EventStream, ImageService, RuntimeService};
+
+/**
+Connects to the runtime listening on the Unix socket `endpoint`, a path optionally prefixed with `unix://` as in `--container-runtime-endpoint`.
+
+The channel reconnects on its own and can be shared by a [`RuntimeServiceClient`] and an [`ImageServiceClient`].
+*/
+pub async fn connect(endpoint: impl AsRef<str>) -> Result<Channel, Error> {
+    let endpoint = endpoint.as_ref();
+    let path = PathBuf::from(endpoint.strip_prefix("unix://").unwrap_or(endpoint));
+    // The URI is required but unused, the connector ignores it.
+    let channel = Endpoint::from_static("http://[::]:50051")
+        .connect_with_connector(service_fn(move |_: Uri| {
+            let path = path.clone();
+            async move {
+                Ok::<_, io::Error>(TokioIo::new(UnixStream::connect(path).await?))
+            }
+        }))
+        .await?;
+    Ok(channel)
+}
+
+async fn unary<Req, Resp>(grpc: &Grpc<Channel>, path: &'static str, request: Req)
+    -> Result<Resp, Status>
+where
+    Req: Message + 'static,
+    Resp: Message + Default + 'static
+{
+    let mut grpc = grpc.clone();
+    grpc.ready().await
+        .map_err(|error| Status::unknown(format!("service was not ready: {}", error)))?;
+    grpc.unary(tonic::Request::new(request), PathAndQuery::from_static(path),
+        ProstCodec::default())
+        .await
+        .map(tonic::Response::into_inner)
+}
+
+/// Client of `runtime.v1.RuntimeService`.
+#[derive(Debug, Clone)]
+pub struct RuntimeServiceClient {
+    inner: Grpc<Channel>
+}
+
+impl RuntimeServiceClient {
+    pub fn new(channel: Channel) -> Self {
+        Self { inner: Grpc::new(channel) }
+    }
+}
+
+#[tonic::async_trait]
+impl ContainerManager for RuntimeServiceClient {
+    async fn create_container(&self, request: CreateContainerRequest)
+        -> Result<CreateContainerResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/CreateContainer", request).await
+    }
+
+    async fn start_container(&self, request: StartContainerRequest)
+        -> Result<StartContainerResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/StartContainer", request).await
+    }
+
+    async fn stop_container(&self, request: StopContainerRequest)
+        -> Result<StopContainerResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/StopContainer", request).await
+    }
+
+    async fn remove_container(&self, request: RemoveContainerRequest)
+        -> Result<RemoveContainerResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/RemoveContainer", request).await
+    }
+
+    async fn list_containers(&self, request: ListContainersRequest)
+        -> Result<ListContainersResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/ListContainers", request).await
+    }
+
+    async fn container_status(&self, request: ContainerStatusRequest)
+        -> Result<ContainerStatusResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/ContainerStatus", request).await
+    }
+
+    async fn update_container_resources(
+        &self,
+        request: UpdateContainerResourcesRequest
+    ) -> Result<UpdateContainerResourcesResponse, Status> {
+        unary(&self.inner, "/runtime.v1.RuntimeService/UpdateContainerResources",
+            request).await
+    }
+
+    async fn reopen_container_log(&self, request: ReopenContainerLogRequest)
+        -> Result<ReopenContainerLogResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/ReopenContainerLog", request)
+            .await
+    }
+
+    async fn exec_sync(&self, request: ExecSyncRequest)
+        -> Result<ExecSyncResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/ExecSync", request).await
+    }
+
+    async fn exec(&self, request: ExecRequest) -> Result<ExecResponse, Status> {
+        unary(&self.inner, "/runtime.v1.RuntimeService/Exec", request).await
+    }
+
+    async fn attach(&self, request: AttachRequest)
+        -> Result<AttachResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/Attach", request).await
+    }
+
+    async fn checkpoint_container(&self, request: CheckpointContainerRequest)
+        -> Result<CheckpointContainerResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/CheckpointContainer", request)
+            .await
+    }
+
+    async fn get_container_events(&self, request: GetEventsRequest)
+        -> Result<EventStream, Status>
+    {
+        let mut grpc = self.inner.clone();
+        grpc.ready().await
+            .map_err(|error| Status::unknown(format!("service was not ready: {}", error)))?;
+        let stream = grpc.server_streaming(
+            tonic::Request::new(request),
+            PathAndQuery::from_static("/runtime.v1.RuntimeService/GetContainerEvents"),
+            ProstCodec::default()
+        ).await?.into_inner();
+        Ok(Box::pin(stream))
+    }
+}
+
+#[tonic::async_trait]
+impl RuntimeService for RuntimeServiceClient {
+    async fn version(&self, request: VersionRequest)
+        -> Result<VersionResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/Version", request).await
+    }
+
+    async fn run_pod_sandbox(&self, request: RunPodSandboxRequest)
+        -> Result<RunPodSandboxResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/RunPodSandbox", request).await
+    }
+
+    async fn stop_pod_sandbox(&self, request: StopPodSandboxRequest)
+        -> Result<StopPodSandboxResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/StopPodSandbox", request).await
+    }
+
+    async fn remove_pod_sandbox(&self, request: RemovePodSandboxRequest)
+        -> Result<RemovePodSandboxResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/RemovePodSandbox", request).await
+    }
+
+    async fn pod_sandbox_status(&self, request: PodSandboxStatusRequest)
+        -> Result<PodSandboxStatusResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/PodSandboxStatus", request).await
+    }
+
+    async fn list_pod_sandbox(&self, request: ListPodSandboxRequest)
+        -> Result<ListPodSandboxResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/ListPodSandbox", request).await
+    }
+
+    async fn port_forward(&self, request: PortForwardRequest)
+        -> Result<PortForwardResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/PortForward", request).await
+    }
+
+    async fn container_stats(&self, request: ContainerStatsRequest)
+        -> Result<ContainerStatsResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/ContainerStats", request).await
+    }
+
+    async fn list_container_stats(&self, request: ListContainerStatsRequest)
+        -> Result<ListContainerStatsResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/ListContainerStats", request)
+            .await
+    }
+
+    async fn pod_sandbox_stats(&self, request: PodSandboxStatsRequest)
+        -> Result<PodSandboxStatsResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/PodSandboxStats", request).await
+    }
+
+    async fn list_pod_sandbox_stats(&self, request: ListPodSandboxStatsRequest)
+        -> Result<ListPodSandboxStatsResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/ListPodSandboxStats", request)
+            .await
+    }
+
+    async fn update_runtime_config(&self, request: UpdateRuntimeConfigRequest)
+        -> Result<UpdateRuntimeConfigResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/UpdateRuntimeConfig", request)
+            .await
+    }
+
+    async fn status(&self, request: StatusRequest)
+        -> Result<StatusResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/Status", request).await
+    }
+
+    async fn list_metric_descriptors(&self, request: ListMetricDescriptorsRequest)
+        -> Result<ListMetricDescriptorsResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/ListMetricDescriptors", request)
+            .await
+    }
+
+    async fn list_pod_sandbox_metrics(&self, request: ListPodSandboxMetricsRequest)
+        -> Result<ListPodSandboxMetricsResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/ListPodSandboxMetrics", request)
+            .await
+    }
+
+    async fn runtime_config(&self, request: RuntimeConfigRequest)
+        -> Result<RuntimeConfigResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.RuntimeService/RuntimeConfig", request).await
+    }
+}
+
+/// Client of `runtime.v1.ImageService`.
+#[derive(Debug, Clone)]
+pub struct ImageServiceClient {
+    inner: Grpc<Channel>
+}
+
+impl ImageServiceClient {
+    pub fn new(channel: Channel) -> Self {
+        Self { inner: Grpc::new(channel) }
+    }
+}
+
+#[tonic::async_trait]
+impl ImageService for ImageServiceClient {
+    async fn list_images(&self, request: ListImagesRequest)
+        -> Result<ListImagesResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.ImageService/ListImages", request).await
+    }
+
+    async fn image_status(&self, request: ImageStatusRequest)
+        -> Result<ImageStatusResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.ImageService/ImageStatus", request).await
+    }
+
+    async fn pull_image(&self, request: PullImageRequest)
+        -> Result<PullImageResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.ImageService/PullImage", request).await
+    }
+
+    async fn remove_image(&self, request: RemoveImageRequest)
+        -> Result<RemoveImageResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.ImageService/RemoveImage", request).await
+    }
+
+    async fn image_fs_info(&self, request: ImageFsInfoRequest)
+        -> Result<ImageFsInfoResponse, Status>
+    {
+        unary(&self.inner, "/runtime.v1.ImageService/ImageFsInfo", request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use tokio::sync::oneshot;
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::cri::{fake::{FakeRuntime, RUNTIME_NAME}, server};
+
+    /// Serves `runtime` and a fresh image service on a socket of its own, returning a channel to them and the sender stopping the server.
+    async fn start(name: &str, runtime: FakeRuntime) -> (Channel, oneshot::Sender<()>) {
+        let path = std::env::temp_dir()
+            .join(format!("kfl-cri-{}-{}.sock", name, std::process::id()));
+        let endpoint = format!("unix://{}", path.display());
+        let (stop, stopped) = oneshot::channel();
+        tokio::spawn(server::serve(endpoint.clone(), runtime,
+            FakeRuntime::new(SystemTime::UNIX_EPOCH),
+            async { stopped.await.ok(); }));
+        for _ in 0..100 {
+            if let Ok(channel) = connect(&endpoint).await {
+                return (channel, stop)
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("runtime did not listen on {}", endpoint);
+    }
+
+    #[tokio::test]
+    async fn calls_the_runtime_over_its_socket() {
+        let runtime = FakeRuntime::new(SystemTime::UNIX_EPOCH);
+        runtime.fail_next("Version", Status::unavailable("starting"));
+        let (channel, _stop) = start("runtime", runtime).await;
+        let client = RuntimeServiceClient::new(channel.clone());
+        let status = client.version(VersionRequest::default()).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+        assert_eq!(status.message(), "starting");
+        let version = client.version(VersionRequest::default()).await.unwrap();
+        assert_eq!(version.runtime_name, RUNTIME_NAME);
+
+        let images = ImageServiceClient::new(channel);
+        let image = ImageSpec { image: "nginx:latest".to_owned(), ..Default::default() };
+        images.pull_image(PullImageRequest {
+            image: Some(image.clone()),
+            ..Default::default()
+        }).await.unwrap();
+        let listed = images.list_images(ListImagesRequest::default()).await.unwrap();
+        assert_eq!(listed.images.len(), 1);
+        assert!(listed.images[0].repo_tags.contains(&image.image));
+    }
+
+    #[tokio::test]
+    async fn streams_container_events() {
+        let runtime = FakeRuntime::new(SystemTime::UNIX_EPOCH);
+        runtime.add_image("nginx:latest", 1 << 20);
+        let (channel, _stop) = start("events", runtime).await;
+        let client = RuntimeServiceClient::new(channel);
+        let mut events = client.get_container_events(GetEventsRequest::default())
+            .await.unwrap();
+        let sandbox = client.run_pod_sandbox(RunPodSandboxRequest {
+            config: Some(PodSandboxConfig::default()),
+            ..Default::default()
+        }).await.unwrap().pod_sandbox_id;
+        let container_id = client.create_container(CreateContainerRequest {
+            pod_sandbox_id: sandbox,
+            config: Some(ContainerConfig {
+                image: Some(ImageSpec { image: "nginx:latest".to_owned(), ..Default::default() }),
+                ..Default::default()
+            }),
+            sandbox_config: None
+        }).await.unwrap().container_id;
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(event.container_id, container_id);
+        assert_eq!(event.container_event_type(), ContainerEventType::ContainerCreatedEvent);
+    }
+}
@@ -0,0 +1,999 @@
+/*!
+An in-memory runtime for testing the kubelet side of the CRI, deterministic down to IDs and timestamps.
+
+Nothing runs: containers move through their states as calls come in and as [`FakeRuntime::advance`] moves the runtime's clock, according to the [`Fault`]s injected for them. Logs are written with [`FakeRuntime::write_log`] and events are kept in order besides being streamed to [`get_container_events`][ContainerManager::get_container_events].
+*/
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, SystemTime, UNIX_EPOCH}
+};
+
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tonic::Status;
+
+use crate::time::MicroTime;
+
+use super::{
+    api::*, ContainerManager, EventStream, ImageService, RuntimeService, API_VERSION,
+    NETWORK_READY, RUNTIME_READY
+};
+
+pub const RUNTIME_NAME: &str = "fake";
+pub const RUNTIME_VERSION: &str = "0.1.0";
+/// Exit code of a process killed by `SIGKILL`.
+pub const KILLED: i32 = 137;
+
+/// Misbehaviour of the containers of a name, on every attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Starting takes this long, the container stays created meanwhile.
+    SlowStart(Duration),
+    /// Exits with the code after running this long.
+    CrashLoop { exit_code: i32, after: Duration },
+    /// Killed for running out of memory after running this long.
+    OutOfMemory { after: Duration }
+}
+
+/// Stream a log line was written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr
+}
+
+impl Stream {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stdout => "stdout",
+            Self::Stderr => "stderr"
+        }
+    }
+}
+
+pub struct FakeRuntime {
+    state: Mutex<State>
+}
+
+struct State {
+    /// Nanoseconds since the epoch.
+    now: i64,
+    next_id: u64,
+    sandboxes: BTreeMap<String, Sandbox>,
+    containers: BTreeMap<String, Record>,
+    images: BTreeMap<String, Image>,
+    faults: HashMap<String, Vec<Fault>>,
+    /// Errors the next calls of a method, by gRPC method name, fail with.
+    failures: HashMap<String, VecDeque<Status>>,
+    exec: HashMap<Vec<String>, ExecSyncResponse>,
+    conditions: Vec<RuntimeCondition>,
+    runtime_config: Option<RuntimeConfig>,
+    events: Vec<ContainerEventResponse>,
+    subscribers: Vec<mpsc::UnboundedSender<Result<ContainerEventResponse, Status>>>
+}
+
+struct Sandbox {
+    status: PodSandboxStatus
+}
+
+/// A container and what the runtime keeps besides its status.
+struct Record {
+    status: ContainerStatus,
+    pod_sandbox_id: String,
+    /// What happens to the container next, and when.
+    pending: Option<(i64, Transition)>,
+    logs: Vec<(i64, Stream, String)>
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Transition {
+    Start,
+    Exit { exit_code: i32, reason: &'static str }
+}
+
+impl FakeRuntime {
+    /// A runtime with its clock at `now`, ready and without images.
+    pub fn new(now: SystemTime) -> Self {
+        let condition = |r#type: &str| RuntimeCondition {
+            r#type: r#type.to_owned(),
+            status: true,
+            ..Default::default()
+        };
+        Self {
+            state: Mutex::new(State {
+                now: nanos(now),
+                next_id: 0,
+                sandboxes: BTreeMap::new(),
+                containers: BTreeMap::new(),
+                images: BTreeMap::new(),
+                faults: HashMap::new(),
+                failures: HashMap::new(),
+                exec: HashMap::new(),
+                conditions: vec![condition(RUNTIME_READY), condition(NETWORK_READY)],
+                runtime_config: None,
+                events: Vec::new(),
+                subscribers: Vec::new()
+            })
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Current time of the runtime's clock.
+    pub fn now(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_nanos(self.lock().now as u64)
+    }
+
+    /// Moves the clock forward, starting and exiting containers whose time has come on the way.
+    pub fn advance(&self, by: Duration) {
+        let mut state = self.lock();
+        let until = state.now + by.as_nanos() as i64;
+        while let Some((at, id)) = state.containers.iter()
+            .filter_map(|(id, container)| container.pending
+                .map(|(at, _)| (at, id.clone())))
+            .filter(|(at, _)| *at <= until)
+            .min()
+        {
+            state.now = state.now.max(at);
+            let Some((_, transition)) = state.containers.get_mut(&id)
+                .and_then(|container| container.pending.take())
+            else {
+                continue
+            };
+            match transition {
+                Transition::Start => state.run(&id),
+                Transition::Exit { exit_code, reason } =>
+                    state.exit(&id, exit_code, reason)
+            }
+        }
+        state.now = until;
+    }
+
+    /// Makes every container named `name`, in any sandbox, misbehave.
+    pub fn inject_fault(&self, name: &str, fault: Fault) {
+        self.lock().faults.entry(name.to_owned()).or_default().push(fault);
+    }
+
+    /// Makes the next call of `method`, a gRPC method name such as `PullImage`, fail with `status`. Queued failures are returned in order, one per call.
+    pub fn fail_next(&self, method: &str, status: Status) {
+        self.lock().failures.entry(method.to_owned()).or_default().push_back(status);
+    }
+
+    /// Sets the response of [`exec_sync`][ContainerManager::exec_sync] of `cmd` in any running container. Other commands succeed with no output.
+    pub fn set_exec(&self, cmd: Vec<String>, response: ExecSyncResponse) {
+        self.lock().exec.insert(cmd, response);
+    }
+
+    /// Sets a condition reported by [`status`][RuntimeService::status], such as [`NETWORK_READY`].
+    pub fn set_condition(&self, r#type: &str, status: bool, reason: &str, message: &str) {
+        let condition = RuntimeCondition {
+            r#type: r#type.to_owned(),
+            status,
+            reason: reason.to_owned(),
+            message: message.to_owned()
+        };
+        let mut state = self.lock();
+        match state.conditions.iter_mut().find(|c| c.r#type == condition.r#type) {
+            Some(existing) => *existing = condition,
+            None => state.conditions.push(condition)
+        }
+    }
+
+    /// Adds an image as if it had been pulled.
+    pub fn add_image(&self, reference: &str, size: u64) {
+        let mut state = self.lock();
+        state.images.insert(reference.to_owned(), image(reference, size));
+    }
+
+    /// Makes a running container exit on its own now.
+    pub fn exit(&self, container_id: &str, exit_code: i32) -> Result<(), Status> {
+        let mut state = self.lock();
+        state.container(container_id)?;
+        state.exit(container_id, exit_code, exit_reason(exit_code));
+        Ok(())
+    }
+
+    /// Appends a line to the log of a container.
+    pub fn write_log(&self, container_id: &str, stream: Stream, line: &str)
+        -> Result<(), Status>
+    {
+        let mut state = self.lock();
+        let now = state.now;
+        state.container_mut(container_id)?.logs.push((now, stream, line.to_owned()));
+        Ok(())
+    }
+
+    /// Log of a container in the CRI logging format, a full line per line written.
+    pub fn logs(&self, container_id: &str) -> Result<String, Status> {
+        let state = self.lock();
+        let container = state.container(container_id)?;
+        Ok(container.logs.iter()
+            .map(|(at, stream, line)| format!("{} {} F {}\n",
+                MicroTime(UNIX_EPOCH + Duration::from_nanos(*at as u64)),
+                stream.as_str(), line))
+            .collect())
+    }
+
+    /// Every container event so far, oldest first.
+    pub fn events(&self) -> Vec<ContainerEventResponse> {
+        self.lock().events.clone()
+    }
+}
+
+impl State {
+    fn fail(&mut self, method: &str) -> Result<(), Status> {
+        match self.failures.get_mut(method).and_then(VecDeque::pop_front) {
+            Some(status) => Err(status),
+            None => Ok(())
+        }
+    }
+
+    fn id(&mut self) -> String {
+        self.next_id += 1;
+        digest(&self.next_id.to_be_bytes())
+    }
+
+    fn sandbox(&self, id: &str) -> Result<&Sandbox, Status> {
+        self.sandboxes.get(id).ok_or_else(||
+            Status::not_found(format!("pod sandbox {} not found", id)))
+    }
+
+    fn container(&self, id: &str) -> Result<&Record, Status> {
+        self.containers.get(id).ok_or_else(||
+            Status::not_found(format!("container {} not found", id)))
+    }
+
+    fn container_mut(&mut self, id: &str) -> Result<&mut Record, Status> {
+        self.containers.get_mut(id).ok_or_else(||
+            Status::not_found(format!("container {} not found", id)))
+    }
+
+    fn faults(&self, name: &str) -> &[Fault] {
+        self.faults.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    fn event(&mut self, container_id: &str, r#type: ContainerEventType) {
+        let Some(container) = self.containers.get(container_id) else {
+            return
+        };
+        let sandbox = self.sandboxes.get(&container.pod_sandbox_id);
+        let event = ContainerEventResponse {
+            container_id: container_id.to_owned(),
+            container_event_type: r#type as i32,
+            created_at: self.now,
+            pod_sandbox_status: sandbox.map(|sandbox| sandbox.status.clone()),
+            containers_statuses: self.containers.values()
+                .filter(|c| c.pod_sandbox_id == container.pod_sandbox_id)
+                .map(|c| c.status.clone())
+                .collect()
+        };
+        self.subscribers.retain(|subscriber| subscriber.send(Ok(event.clone())).is_ok());
+        self.events.push(event);
+    }
+
+    /// Moves a created container to running and schedules the exit of a faulty one.
+    fn run(&mut self, id: &str) {
+        let now = self.now;
+        let Some(container) = self.containers.get(id) else {
+            return
+        };
+        let exit = self.faults(&container.status.metadata.as_ref()
+            .map_or(String::new(), |metadata| metadata.name.clone()))
+            .iter()
+            .find_map(|fault| match *fault {
+                Fault::CrashLoop { exit_code, after } => Some((after, Transition::Exit {
+                    exit_code, reason: exit_reason(exit_code)
+                })),
+                Fault::OutOfMemory { after } => Some((after, Transition::Exit {
+                    exit_code: KILLED, reason: "OOMKilled"
+                })),
+                Fault::SlowStart(_) => None
+            });
+        let Some(container) = self.containers.get_mut(id) else {
+            return
+        };
+        container.status.set_state(ContainerState::ContainerRunning);
+        container.status.started_at = now;
+        container.pending = exit.map(|(after, transition)|
+            (now + after.as_nanos() as i64, transition));
+        self.event(id, ContainerEventType::ContainerStartedEvent);
+    }
+
+    /// Moves a container that is not already exited to exited.
+    fn exit(&mut self, id: &str, exit_code: i32, reason: &str) {
+        let now = self.now;
+        let Some(container) = self.containers.get_mut(id) else {
+            return
+        };
+        if container.status.state() == ContainerState::ContainerExited {
+            return
+        }
+        container.status.set_state(ContainerState::ContainerExited);
+        container.status.finished_at = now;
+        container.status.exit_code = exit_code;
+        container.status.reason = reason.to_owned();
+        container.pending = None;
+        self.event(id, ContainerEventType::ContainerStoppedEvent);
+    }
+
+    fn remove_container(&mut self, id: &str) {
+        if self.containers.contains_key(id) {
+            self.event(id, ContainerEventType::ContainerDeletedEvent);
+            self.containers.remove(id);
+        }
+    }
+
+    fn find_image(&self, spec: &ImageSpec) -> Option<&Image> {
+        self.images.get(&spec.image)
+            .or_else(|| self.images.values().find(|image| image.id == spec.image))
+    }
+}
+
+fn nanos(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_nanos() as i64)
+}
+
+/// Hex encoded SHA-256 of `input`.
+fn digest(input: &[u8]) -> String {
+    Sha256::digest(input).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn exit_reason(exit_code: i32) -> &'static str {
+    if exit_code == 0 { "Completed" } else { "Error" }
+}
+
+fn image(reference: &str, size: u64) -> Image {
+    let id = format!("sha256:{}", digest(reference.as_bytes()));
+    let (repo_tags, repo_digests) = match reference.split_once('@') {
+        Some(_) => (Vec::new(), vec![reference.to_owned()]),
+        None => {
+            let repository = reference.rsplit_once(':')
+                .filter(|(_, tag)| !tag.contains('/'))
+                .map_or(reference, |(repository, _)| repository);
+            (vec![reference.to_owned()], vec![format!("{}@{}", repository, id)])
+        }
+    };
+    Image {
+        repo_tags,
+        repo_digests,
+        size,
+        spec: Some(ImageSpec { image: id.clone(), ..Default::default() }),
+        id,
+        ..Default::default()
+    }
+}
+
+fn labels_match(selector: &HashMap<String, String>, labels: &HashMap<String, String>)
+    -> bool
+{
+    selector.iter().all(|(key, value)| labels.get(key) == Some(value))
+}
+
+#[tonic::async_trait]
+impl ContainerManager for FakeRuntime {
+    async fn create_container(&self, request: CreateContainerRequest)
+        -> Result<CreateContainerResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("CreateContainer")?;
+        let sandbox = state.sandbox(&request.pod_sandbox_id)?;
+        if sandbox.status.state() != PodSandboxState::SandboxReady {
+            return Err(Status::failed_precondition(format!(
+                "pod sandbox {} is not ready", request.pod_sandbox_id)))
+        }
+        let config = request.config
+            .ok_or_else(|| Status::invalid_argument("config is required"))?;
+        let spec = config.image.clone().unwrap_or_default();
+        let image = state.find_image(&spec).ok_or_else(|| Status::not_found(
+            format!("image {} not found", spec.image)))?;
+        let (image_ref, image_id) = (image.id.clone(), image.id.clone());
+        let id = state.id();
+        let mut status = ContainerStatus {
+            id: id.clone(),
+            metadata: config.metadata,
+            created_at: state.now,
+            image: Some(spec),
+            image_ref,
+            image_id,
+            labels: config.labels,
+            annotations: config.annotations,
+            mounts: config.mounts,
+            log_path: config.log_path,
+            stop_signal: config.stop_signal,
+            resources: config.linux.and_then(|linux| linux.resources)
+                .map(|linux| ContainerResources { linux: Some(linux), windows: None }),
+            ..Default::default()
+        };
+        status.set_state(ContainerState::ContainerCreated);
+        state.containers.insert(id.clone(), Record {
+            status,
+            pod_sandbox_id: request.pod_sandbox_id,
+            pending: None,
+            logs: Vec::new()
+        });
+        state.event(&id, ContainerEventType::ContainerCreatedEvent);
+        Ok(CreateContainerResponse { container_id: id })
+    }
+
+    async fn start_container(&self, request: StartContainerRequest)
+        -> Result<StartContainerResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("StartContainer")?;
+        let id = request.container_id;
+        let container = state.container(&id)?;
+        if container.status.state() != ContainerState::ContainerCreated
+            || container.pending.is_some()
+        {
+            return Err(Status::failed_precondition(format!(
+                "container {} is not in created state", id)))
+        }
+        let name = container.status.metadata.as_ref()
+            .map_or(String::new(), |metadata| metadata.name.clone());
+        let delay = state.faults(&name).iter().find_map(|fault| match fault {
+            Fault::SlowStart(delay) => Some(*delay),
+            _ => None
+        });
+        match delay {
+            Some(delay) => {
+                let at = state.now + delay.as_nanos() as i64;
+                state.container_mut(&id)?.pending = Some((at, Transition::Start));
+            }
+            None => state.run(&id)
+        }
+        Ok(StartContainerResponse {})
+    }
+
+    async fn stop_container(&self, request: StopContainerRequest)
+        -> Result<StopContainerResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("StopContainer")?;
+        let container = state.container(&request.container_id)?;
+        if container.status.state() == ContainerState::ContainerCreated {
+            return Ok(StopContainerResponse {})
+        }
+        // A grace period is taken to be honoured, no timeout to kill at once.
+        let (exit_code, reason) = if request.timeout > 0 {
+            (0, "Completed")
+        } else {
+            (KILLED, "Error")
+        };
+        state.exit(&request.container_id, exit_code, reason);
+        Ok(StopContainerResponse {})
+    }
+
+    async fn remove_container(&self, request: RemoveContainerRequest)
+        -> Result<RemoveContainerResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("RemoveContainer")?;
+        state.exit(&request.container_id, KILLED, "Error");
+        state.remove_container(&request.container_id);
+        Ok(RemoveContainerResponse {})
+    }
+
+    async fn list_containers(&self, request: ListContainersRequest)
+        -> Result<ListContainersResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("ListContainers")?;
+        let filter = request.filter.unwrap_or_default();
+        let containers = state.containers.values()
+            .filter(|container| filter.id.is_empty() || container.status.id == filter.id)
+            .filter(|container| filter.pod_sandbox_id.is_empty()
+                || container.pod_sandbox_id == filter.pod_sandbox_id)
+            .filter(|container| filter.state.as_ref()
+                .is_none_or(|value| value.state == container.status.state))
+            .filter(|container| labels_match(&filter.label_selector,
+                &container.status.labels))
+            .map(|container| Container {
+                id: container.status.id.clone(),
+                pod_sandbox_id: container.pod_sandbox_id.clone(),
+                metadata: container.status.metadata.clone(),
+                image: container.status.image.clone(),
+                image_ref: container.status.image_ref.clone(),
+                state: container.status.state,
+                created_at: container.status.created_at,
+                labels: container.status.labels.clone(),
+                annotations: container.status.annotations.clone(),
+                image_id: container.status.image_id.clone()
+            })
+            .collect();
+        Ok(ListContainersResponse { containers })
+    }
+
+    async fn container_status(&self, request: ContainerStatusRequest)
+        -> Result<ContainerStatusResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("ContainerStatus")?;
+        let container = state.container(&request.container_id)?;
+        Ok(ContainerStatusResponse {
+            status: Some(container.status.clone()),
+            ..Default::default()
+        })
+    }
+
+    async fn update_container_resources(
+        &self,
+        request: UpdateContainerResourcesRequest
+    ) -> Result<UpdateContainerResourcesResponse, Status> {
+        let mut state = self.lock();
+        state.fail("UpdateContainerResources")?;
+        let container = state.container_mut(&request.container_id)?;
+        container.status.resources = Some(ContainerResources {
+            linux: request.linux,
+            windows: request.windows
+        });
+        Ok(UpdateContainerResourcesResponse {})
+    }
+
+    async fn reopen_container_log(&self, request: ReopenContainerLogRequest)
+        -> Result<ReopenContainerLogResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("ReopenContainerLog")?;
+        let container = state.container(&request.container_id)?;
+        if container.status.state() != ContainerState::ContainerRunning {
+            return Err(Status::failed_precondition(format!(
+                "container {} is not running", request.container_id)))
+        }
+        Ok(ReopenContainerLogResponse {})
+    }
+
+    async fn exec_sync(&self, request: ExecSyncRequest)
+        -> Result<ExecSyncResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("ExecSync")?;
+        let container = state.container(&request.container_id)?;
+        if container.status.state() != ContainerState::ContainerRunning {
+            return Err(Status::failed_precondition(format!(
+                "container {} is not running", request.container_id)))
+        }
+        Ok(state.exec.get(&request.cmd).cloned().unwrap_or_default())
+    }
+
+    async fn exec(&self, _request: ExecRequest) -> Result<ExecResponse, Status> {
+        Err(Status::unimplemented("the fake runtime has no streaming server"))
+    }
+
+    async fn attach(&self, _request: AttachRequest)
+        -> Result<AttachResponse, Status>
+    {
+        Err(Status::unimplemented("the fake runtime has no streaming server"))
+    }
+
+    async fn get_container_events(&self, _request: GetEventsRequest)
+        -> Result<EventStream, Status>
+    {
+        let mut state = self.lock();
+        state.fail("GetContainerEvents")?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        state.subscribers.push(sender);
+        Ok(Box::pin(UnboundedReceiverStream::new(receiver)))
+    }
+}
+
+#[tonic::async_trait]
+impl RuntimeService for FakeRuntime {
+    async fn version(&self, _request: VersionRequest)
+        -> Result<VersionResponse, Status>
+    {
+        self.lock().fail("Version")?;
+        Ok(VersionResponse {
+            version: "0.1.0".to_owned(),
+            runtime_name: RUNTIME_NAME.to_owned(),
+            runtime_version: RUNTIME_VERSION.to_owned(),
+            runtime_api_version: API_VERSION.to_owned()
+        })
+    }
+
+    async fn run_pod_sandbox(&self, request: RunPodSandboxRequest)
+        -> Result<RunPodSandboxResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("RunPodSandbox")?;
+        let config = request.config
+            .ok_or_else(|| Status::invalid_argument("config is required"))?;
+        let id = state.id();
+        let ip = format!("10.88.{}.{}", state.next_id / 254 % 256, state.next_id % 254 + 1);
+        let mut status = PodSandboxStatus {
+            id: id.clone(),
+            metadata: config.metadata,
+            created_at: state.now,
+            network: Some(PodSandboxNetworkStatus { ip, ..Default::default() }),
+            labels: config.labels,
+            annotations: config.annotations,
+            runtime_handler: request.runtime_handler,
+            ..Default::default()
+        };
+        status.set_state(PodSandboxState::SandboxReady);
+        state.sandboxes.insert(id.clone(), Sandbox { status });
+        Ok(RunPodSandboxResponse { pod_sandbox_id: id })
+    }
+
+    async fn stop_pod_sandbox(&self, request: StopPodSandboxRequest)
+        -> Result<StopPodSandboxResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("StopPodSandbox")?;
+        let id = request.pod_sandbox_id;
+        let Some(sandbox) = state.sandboxes.get_mut(&id) else {
+            return Ok(StopPodSandboxResponse {})
+        };
+        sandbox.status.set_state(PodSandboxState::SandboxNotready);
+        let containers: Vec<String> = state.containers.iter()
+            .filter(|(_, container)| container.pod_sandbox_id == id)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for container in containers {
+            state.exit(&container, KILLED, "Error");
+        }
+        Ok(StopPodSandboxResponse {})
+    }
+
+    async fn remove_pod_sandbox(&self, request: RemovePodSandboxRequest)
+        -> Result<RemovePodSandboxResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("RemovePodSandbox")?;
+        let id = request.pod_sandbox_id;
+        let containers: Vec<String> = state.containers.iter()
+            .filter(|(_, container)| container.pod_sandbox_id == id)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for container in containers {
+            state.exit(&container, KILLED, "Error");
+            state.remove_container(&container);
+        }
+        state.sandboxes.remove(&id);
+        Ok(RemovePodSandboxResponse {})
+    }
+
+    async fn pod_sandbox_status(&self, request: PodSandboxStatusRequest)
+        -> Result<PodSandboxStatusResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("PodSandboxStatus")?;
+        let sandbox = state.sandbox(&request.pod_sandbox_id)?;
+        Ok(PodSandboxStatusResponse {
+            status: Some(sandbox.status.clone()),
+            containers_statuses: state.containers.values()
+                .filter(|container| container.pod_sandbox_id == request.pod_sandbox_id)
+                .map(|container| container.status.clone())
+                .collect(),
+            timestamp: state.now,
+            ..Default::default()
+        })
+    }
+
+    async fn list_pod_sandbox(&self, request: ListPodSandboxRequest)
+        -> Result<ListPodSandboxResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("ListPodSandbox")?;
+        let filter = request.filter.unwrap_or_default();
+        let items = state.sandboxes.values()
+            .map(|sandbox| &sandbox.status)
+            .filter(|status| filter.id.is_empty() || status.id == filter.id)
+            .filter(|status| filter.state.as_ref()
+                .is_none_or(|value| value.state == status.state))
+            .filter(|status| labels_match(&filter.label_selector, &status.labels))
+            .map(|status| PodSandbox {
+                id: status.id.clone(),
+                metadata: status.metadata.clone(),
+                state: status.state,
+                created_at: status.created_at,
+                labels: status.labels.clone(),
+                annotations: status.annotations.clone(),
+                runtime_handler: status.runtime_handler.clone()
+            })
+            .collect();
+        Ok(ListPodSandboxResponse { items })
+    }
+
+    async fn port_forward(&self, _request: PortForwardRequest)
+        -> Result<PortForwardResponse, Status>
+    {
+        Err(Status::unimplemented("the fake runtime has no streaming server"))
+    }
+
+    async fn container_stats(&self, request: ContainerStatsRequest)
+        -> Result<ContainerStatsResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("ContainerStats")?;
+        let container = state.container(&request.container_id)?;
+        Ok(ContainerStatsResponse { stats: Some(stats(container, state.now)) })
+    }
+
+    async fn list_container_stats(&self, request: ListContainerStatsRequest)
+        -> Result<ListContainerStatsResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("ListContainerStats")?;
+        let filter = request.filter.unwrap_or_default();
+        let stats = state.containers.values()
+            .filter(|container| container.status.state()
+                == ContainerState::ContainerRunning)
+            .filter(|container| filter.id.is_empty() || container.status.id == filter.id)
+            .filter(|container| filter.pod_sandbox_id.is_empty()
+                || container.pod_sandbox_id == filter.pod_sandbox_id)
+            .filter(|container| labels_match(&filter.label_selector,
+                &container.status.labels))
+            .map(|container| stats(container, state.now))
+            .collect();
+        Ok(ListContainerStatsResponse { stats })
+    }
+
+    async fn pod_sandbox_stats(&self, _request: PodSandboxStatsRequest)
+        -> Result<PodSandboxStatsResponse, Status>
+    {
+        Err(Status::unimplemented("PodSandboxStats"))
+    }
+
+    async fn list_pod_sandbox_stats(&self, _request: ListPodSandboxStatsRequest)
+        -> Result<ListPodSandboxStatsResponse, Status>
+    {
+        Err(Status::unimplemented("ListPodSandboxStats"))
+    }
+
+    async fn update_runtime_config(&self, request: UpdateRuntimeConfigRequest)
+        -> Result<UpdateRuntimeConfigResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("UpdateRuntimeConfig")?;
+        state.runtime_config = request.runtime_config;
+        Ok(UpdateRuntimeConfigResponse {})
+    }
+
+    async fn status(&self, _request: StatusRequest)
+        -> Result<StatusResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("Status")?;
+        Ok(StatusResponse {
+            status: Some(RuntimeStatus { conditions: state.conditions.clone() }),
+            ..Default::default()
+        })
+    }
+}
+
+/// Usage of a container, all zero as nothing runs.
+fn stats(container: &Record, now: i64) -> ContainerStats {
+    let zero = || Some(UInt64Value { value: 0 });
+    ContainerStats {
+        attributes: Some(ContainerAttributes {
+            id: container.status.id.clone(),
+            metadata: container.status.metadata.clone(),
+            labels: container.status.labels.clone(),
+            annotations: container.status.annotations.clone()
+        }),
+        cpu: Some(CpuUsage {
+            timestamp: now,
+            usage_core_nano_seconds: zero(),
+            usage_nano_cores: zero()
+        }),
+        memory: Some(MemoryUsage {
+            timestamp: now,
+            working_set_bytes: zero(),
+            usage_bytes: zero(),
+            rss_bytes: zero(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[tonic::async_trait]
+impl ImageService for FakeRuntime {
+    async fn list_images(&self, request: ListImagesRequest)
+        -> Result<ListImagesResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("ListImages")?;
+        let images = match request.filter.and_then(|filter| filter.image) {
+            Some(spec) if !spec.image.is_empty() =>
+                state.find_image(&spec).cloned().into_iter().collect(),
+            _ => state.images.values().cloned().collect()
+        };
+        Ok(ListImagesResponse { images })
+    }
+
+    async fn image_status(&self, request: ImageStatusRequest)
+        -> Result<ImageStatusResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("ImageStatus")?;
+        let spec = request.image.unwrap_or_default();
+        Ok(ImageStatusResponse {
+            image: state.find_image(&spec).cloned(),
+            ..Default::default()
+        })
+    }
+
+    async fn pull_image(&self, request: PullImageRequest)
+        -> Result<PullImageResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("PullImage")?;
+        let spec = request.image.unwrap_or_default();
+        if spec.image.is_empty() {
+            return Err(Status::invalid_argument("image is required"))
+        }
+        let image = state.images.entry(spec.image.clone())
+            .or_insert_with(|| image(&spec.image, 0));
+        Ok(PullImageResponse { image_ref: image.id.clone() })
+    }
+
+    async fn remove_image(&self, request: RemoveImageRequest)
+        -> Result<RemoveImageResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("RemoveImage")?;
+        let spec = request.image.unwrap_or_default();
+        if let Some(id) = state.find_image(&spec).map(|image| image.id.clone()) {
+            state.images.retain(|_, image| image.id != id);
+        }
+        Ok(RemoveImageResponse {})
+    }
+
+    async fn image_fs_info(&self, _request: ImageFsInfoRequest)
+        -> Result<ImageFsInfoResponse, Status>
+    {
+        let mut state = self.lock();
+        state.fail("ImageFsInfo")?;
+        let used = state.images.values().map(|image| image.size).sum();
+        Ok(ImageFsInfoResponse {
+            image_filesystems: vec![FilesystemUsage {
+                timestamp: state.now,
+                fs_id: Some(FilesystemIdentifier {
+                    mountpoint: "/var/lib/fake/images".to_owned()
+                }),
+                used_bytes: Some(UInt64Value { value: used }),
+                inodes_used: Some(UInt64Value { value: state.images.len() as u64 })
+            }],
+            container_filesystems: Vec::new()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reports_the_stop_signal() {
+        let runtime = FakeRuntime::new(SystemTime::UNIX_EPOCH);
+        runtime.add_image("nginx:latest", 1 << 20);
+        let sandbox = runtime.run_pod_sandbox(RunPodSandboxRequest {
+            config: Some(PodSandboxConfig::default()),
+            ..Default::default()
+        }).await.unwrap().pod_sandbox_id;
+        let mut config = ContainerConfig {
+            image: Some(ImageSpec { image: "nginx:latest".to_owned(), ..Default::default() }),
+            ..Default::default()
+        };
+        config.set_stop_signal(Signal::Sigquit);
+        let container_id = runtime.create_container(CreateContainerRequest {
+            pod_sandbox_id: sandbox,
+            config: Some(config),
+            sandbox_config: None
+        }).await.unwrap().container_id;
+        let status = runtime.container_status(ContainerStatusRequest {
+            container_id,
+            verbose: false
+        }).await.unwrap().status.unwrap();
+        assert_eq!(status.stop_signal(), Signal::Sigquit);
+    }
+
+    /// Creates a container named `name` in a new sandbox, from an image added for it.
+    async fn create(runtime: &FakeRuntime, name: &str) -> String {
+        runtime.add_image("app:1", 1 << 20);
+        let pod_sandbox_id = runtime.run_pod_sandbox(RunPodSandboxRequest {
+            config: Some(PodSandboxConfig::default()),
+            ..Default::default()
+        }).await.unwrap().pod_sandbox_id;
+        runtime.create_container(CreateContainerRequest {
+            pod_sandbox_id,
+            config: Some(ContainerConfig {
+                metadata: Some(ContainerMetadata { name: name.to_owned(), attempt: 0 }),
+                image: Some(ImageSpec { image: "app:1".to_owned(), ..Default::default() }),
+                ..Default::default()
+            }),
+            sandbox_config: None
+        }).await.unwrap().container_id
+    }
+
+    async fn start(runtime: &FakeRuntime, container_id: &str) {
+        runtime.start_container(StartContainerRequest {
+            container_id: container_id.to_owned()
+        }).await.unwrap();
+    }
+
+    async fn status(runtime: &FakeRuntime, container_id: &str) -> ContainerStatus {
+        runtime.container_status(ContainerStatusRequest {
+            container_id: container_id.to_owned(),
+            verbose: false
+        }).await.unwrap().status.unwrap()
+    }
+
+    #[tokio::test]
+    async fn misbehaves_as_injected() {
+        let runtime = FakeRuntime::new(SystemTime::UNIX_EPOCH);
+        runtime.inject_fault("slow", Fault::SlowStart(Duration::from_secs(5)));
+        runtime.inject_fault("crash", Fault::CrashLoop {
+            exit_code: 2,
+            after: Duration::from_secs(10)
+        });
+        runtime.inject_fault("oom", Fault::OutOfMemory { after: Duration::from_secs(3) });
+        let slow = create(&runtime, "slow").await;
+        let crash = create(&runtime, "crash").await;
+        let oom = create(&runtime, "oom").await;
+        for id in [&slow, &crash, &oom] {
+            start(&runtime, id).await;
+        }
+        assert_eq!(status(&runtime, &slow).await.state(), ContainerState::ContainerCreated);
+        assert_eq!(status(&runtime, &crash).await.state(), ContainerState::ContainerRunning);
+
+        runtime.advance(Duration::from_secs(5));
+        let started = status(&runtime, &slow).await;
+        assert_eq!(started.state(), ContainerState::ContainerRunning);
+        assert_eq!(started.started_at, 5_000_000_000);
+        let killed = status(&runtime, &oom).await;
+        assert_eq!((killed.state(), killed.exit_code, killed.reason.as_str()),
+            (ContainerState::ContainerExited, KILLED, "OOMKilled"));
+        assert_eq!(killed.finished_at, 3_000_000_000);
+
+        runtime.advance(Duration::from_secs(5));
+        let crashed = status(&runtime, &crash).await;
+        assert_eq!((crashed.state(), crashed.exit_code, crashed.reason.as_str()),
+            (ContainerState::ContainerExited, 2, "Error"));
+        // Exited containers cannot be started again, only created anew.
+        assert!(runtime.start_container(StartContainerRequest { container_id: crash })
+            .await.is_err());
+    }
+
+    #[tokio::test]
+    async fn records_logs_events_and_failures() {
+        let runtime = FakeRuntime::new(SystemTime::UNIX_EPOCH);
+        let id = create(&runtime, "app").await;
+        start(&runtime, &id).await;
+        runtime.advance(Duration::from_millis(1500));
+        runtime.write_log(&id, Stream::Stderr, "ready").unwrap();
+        assert_eq!(runtime.logs(&id).unwrap(), "1970-01-01T00:00:01.500000Z stderr F ready\n");
+
+        runtime.set_exec(vec!["true".to_owned()], ExecSyncResponse { exit_code: 0, ..Default::default() });
+        runtime.fail_next("ExecSync", Status::unavailable("busy"));
+        let exec = || runtime.exec_sync(ExecSyncRequest {
+            container_id: id.clone(),
+            cmd: vec!["true".to_owned()],
+            timeout: 1
+        });
+        assert_eq!(exec().await.unwrap_err().code(), tonic::Code::Unavailable);
+        assert!(exec().await.is_ok());
+
+        runtime.stop_container(StopContainerRequest { container_id: id.clone(), timeout: 0 })
+            .await.unwrap();
+        assert_eq!(status(&runtime, &id).await.exit_code, KILLED);
+        runtime.remove_container(RemoveContainerRequest { container_id: id.clone() })
+            .await.unwrap();
+        let events: Vec<ContainerEventType> = runtime.events().iter()
+            .map(ContainerEventResponse::container_event_type)
+            .collect();
+        assert_eq!(events, [
+            ContainerEventType::ContainerCreatedEvent,
+            ContainerEventType::ContainerStartedEvent,
+            ContainerEventType::ContainerStoppedEvent,
+            ContainerEventType::ContainerDeletedEvent
+        ]);
+        assert!(runtime.logs(&id).is_err());
+    }
+}
@@ -0,0 +1,31 @@
+use tonic::Status;
+
+use super::api::*;
+
+/// ImageService defines the public APIs for managing images.
+#[tonic::async_trait]
+pub trait ImageService: Send + Sync + 'static {
+    /// Lists existing images.
+    async fn list_images(&self, request: ListImagesRequest)
+        -> Result<ListImagesResponse, Status>;
+
+    /// Returns the status of the image. If the image is not
+    /// present, returns a response with ImageStatusResponse.Image set to
+    /// nil.
+    async fn image_status(&self, request: ImageStatusRequest)
+        -> Result<ImageStatusResponse, Status>;
+
+    /// Pulls an image with authentication config.
+    async fn pull_image(&self, request: PullImageRequest)
+        -> Result<PullImageResponse, Status>;
+
+    /// Removes the image.
+    /// This call is idempotent, and must not return an error if the image has
+    /// already been removed.
+    async fn remove_image(&self, request: RemoveImageRequest)
+        -> Result<RemoveImageResponse, Status>;
+
+    /// Returns information of the filesystem that is used to store images.
+    async fn image_fs_info(&self, request: ImageFsInfoRequest)
+        -> Result<ImageFsInfoResponse, Status>;
+}
@@ -1,185 +1,179 @@
-/// Runtime service defines the public APIs for remote container runtimes
-pub trait RuntimeService {
-    /// Version returns the runtime name, runtime version, and runtime API version.
-    fn version(request: VersionRequest) -> VersionResponse;
-
-    /// Creates and starts a pod-level sandbox. Runtimes must ensure
-    /// the sandbox is in the ready state on success.
-    fn run_pod_sandbox(
-        request: RunPodSandboxRequest
-    ) -> (RunPodSandboxResponse);
+use tonic::Status;
 
-    /// Stops any running process that is part of the sandbox and
-    /// reclaims network resources (e.g., IP addresses) allocated to the sandbox.
-    /// If there are any running containers in the sandbox, they must be forcibly
-    /// terminated.
-    /// This call is idempotent, and must not return an error if all relevant
-    /// resources have already been reclaimed. kubelet will call StopPodSandbox
-    /// at least once before calling RemovePodSandbox. It will also attempt to
-    /// reclaim resources eagerly, as soon as a sandbox is not needed. Hence,
-    /// multiple StopPodSandbox calls are expected.
-    fn stop_pod_sandbox(
-        request: StopPodSandboxRequest
-    ) -> StopPodSandboxResponse;
-    
-    /// Removes the sandbox. If there are any running containers
-    /// in the sandbox, they must be forcibly terminated and removed.
-    /// This call is idempotent, and must not return an error if the sandbox has
-    /// already been removed.
-    fn remove_pod_sandbox(
-        request: RemovePodSandboxRequest
-    ) -> RemovePodSandboxResponse;
-
-    /// Returns the status of the PodSandbox. If the PodSandbox is not present, returns an error.
-    fn pod_sandbox_status(
-        request: PodSandboxStatusRequest
-    ) -> (PodSandboxStatusResponse);
-    /// Returns a list of PodSandboxes.
-    fn list_pod_sandbox(
-        request: ListPodSandboxRequest
-    ) -> (ListPodSandboxResponse);
+use super::{api::*, EventStream};
 
+/// ContainerManager contains methods to manipulate containers managed by a
+/// container runtime.
+#[tonic::async_trait]
+pub trait ContainerManager: Send + Sync + 'static {
     /// Creates a new container in specified PodSandbox
-    fn create_container(
-        request: CreateContainerRequest
-    ) -> (CreateContainerResponse);
+    async fn create_container(&self, request: CreateContainerRequest)
+        -> Result<CreateContainerResponse, Status>;
+
     /// Starts the container.
-    fn start_container(
-        request: StartContainerRequest
-    ) -> (StartContainerResponse);
+    async fn start_container(&self, request: StartContainerRequest)
+        -> Result<StartContainerResponse, Status>;
 
     /// Stops a running container with a grace period (i.e., timeout).
     /// This call is idempotent, and must not return an error if the container has
     /// already been stopped.
     /// The runtime must forcibly kill the container after the grace period is
     /// reached.
-    fn stop_container(
-        request: StopContainerRequest
-    ) -> (StopContainerResponse);
+    async fn stop_container(&self, request: StopContainerRequest)
+        -> Result<StopContainerResponse, Status>;
 
     /// Removes the container. If the container is running, the
     /// container must be forcibly removed.
     /// This call is idempotent, and must not return an error if the container has
     /// already been removed.
-    fn remove_container(
-        request: RemoveContainerRequest
-    ) -> (RemoveContainerResponse);
+    async fn remove_container(&self, request: RemoveContainerRequest)
+        -> Result<RemoveContainerResponse, Status>;
+
     /// Lists all containers by filters.
-    fn list_containers(
-        request: ListContainersRequest
-    ) -> (ListContainersResponse);
+    async fn list_containers(&self, request: ListContainersRequest)
+        -> Result<ListContainersResponse, Status>;
 
     /// Returns status of the container. If the container is not
     /// present, returns an error.
-    fn container_status(
-        request: ContainerStatusRequest
-    ) -> (ContainerStatusResponse);
+    async fn container_status(&self, request: ContainerStatusRequest)
+        -> Result<ContainerStatusResponse, Status>;
 
     /// Updates ContainerConfig of the container synchronously.
     /// If runtime fails to transactionally update the requested resources, an error is returned.
-    fn update_container_resources(
+    async fn update_container_resources(
+        &self,
         request: UpdateContainerResourcesRequest
-    ) -> (UpdateContainerResourcesResponse);
-    
+    ) -> Result<UpdateContainerResourcesResponse, Status>;
+
     /// Asks runtime to reopen the stdout/stderr log file
     /// for the container. This is often called after the log file has been
     /// rotated. If the container is not running, container runtime can choose
     /// to either create a new log file and return nil, or return an error.
     /// Once it returns error, new container log file MUST NOT be created.
-    fn reopen_container_log(request: ReopenContainerLogRequest) -> (ReopenContainerLogResponse);
+    async fn reopen_container_log(&self, request: ReopenContainerLogRequest)
+        -> Result<ReopenContainerLogResponse, Status>;
 
     /// Runs a command in a container synchronously.
-    fn exec_sync(request: ExecSyncRequest) -> (ExecSyncResponse);
-    /// Prepares a streaming endpoint to execute a command in the container.
-    fn exec(request: ExecRequest) -> (ExecResponse);
-    /// Prepares a streaming endpoint to attach to a running container.
-    fn attach(request: AttachRequest) -> (AttachResponse);
-    /// Prepares a streaming endpoint to forward ports from a PodSandbox.
-    fn port_forward(request: PortForwardRequest) -> (PortForwardResponse);
-
-    /// ContainerStats returns stats of the container. If the container does not
-    /// exist, the call returns an error.
-    fn container_stats(
-        request: ContainerStatsRequest
-    ) -> (ContainerStatsResponse);
-    /// Returns stats of all running containers.
-    fn list_container_stats(
-        request: ListContainerStatsRequest
-    ) -> (ListContainerStatsResponse);
+    async fn exec_sync(&self, request: ExecSyncRequest)
+        -> Result<ExecSyncResponse, Status>;
 
-    /// Returns stats of the pod sandbox. If the pod sandbox does not
-    /// exist, the call returns an error.
-    fn pod_sandbox_stats(
-        request: PodSandboxStatsRequest
-    ) -> (PodSandboxStatsResponse);
-    /// Returns stats of the pod sandboxes matching a filter.
-    fn list_pod_sandbox_stats(
-        request: ListPodSandboxStatsRequest
-    ) -> (ListPodSandboxStatsResponse);
-
-    /// Updates the runtime configuration based on the given request.
-    fn update_runtime_config(
-        request: UpdateRuntimeConfigRequest
-    ) -> (UpdateRuntimeConfigResponse);
+    /// Prepares a streaming endpoint to execute a command in the container.
+    async fn exec(&self, request: ExecRequest) -> Result<ExecResponse, Status>;
 
-    /// Returns the status of the runtime.
-    fn status(request: StatusRequest) -> (StatusResponse);
+    /// Prepares a streaming endpoint to attach to a running container.
+    async fn attach(&self, request: AttachRequest)
+        -> Result<AttachResponse, Status>;
 
     /// Checkpoints a container
-    fn checkpoint_container(
-        request: CheckpointContainerRequest
-    ) -> (CheckpointContainerResponse);
+    async fn checkpoint_container(&self, _request: CheckpointContainerRequest)
+        -> Result<CheckpointContainerResponse, Status>
+    {
+        Err(Status::unimplemented("CheckpointContainer"))
+    }
 
     /// Gets container events from the CRI runtime
-    fn get_container_events(
-        request: GetEventsRequest
-    ) -> (stream ContainerEventResponse);
-}
-
-pub struct ContainerConfig {
-    
+    async fn get_container_events(&self, _request: GetEventsRequest)
+        -> Result<EventStream, Status>
+    {
+        Err(Status::unimplemented("GetContainerEvents"))
+    }
 }
 
-pub struct PodSandboxConfig {
-    
-}
-
-pub struct ContainerFilter {
-
-}
-
-pub struct Container {
-    
-}
+/// Runtime service defines the public APIs for remote container runtimes
+#[tonic::async_trait]
+pub trait RuntimeService: ContainerManager {
+    /// Version returns the runtime name, runtime version, and runtime API version.
+    async fn version(&self, request: VersionRequest)
+        -> Result<VersionResponse, Status>;
 
-pub struct ContainerStatusResponse {
-    
-}
+    /// Creates and starts a pod-level sandbox. Runtimes must ensure
+    /// the sandbox is in the ready state on success.
+    async fn run_pod_sandbox(&self, request: RunPodSandboxRequest)
+        -> Result<RunPodSandboxResponse, Status>;
 
-pub struct ContainerResources {
-    
-}
+    /// Stops any running process that is part of the sandbox and
+    /// reclaims network resources (e.g., IP addresses) allocated to the sandbox.
+    /// If there are any running containers in the sandbox, they must be forcibly
+    /// terminated.
+    /// This call is idempotent, and must not return an error if all relevant
+    /// resources have already been reclaimed. kubelet will call StopPodSandbox
+    /// at least once before calling RemovePodSandbox. It will also attempt to
+    /// reclaim resources eagerly, as soon as a sandbox is not needed. Hence,
+    /// multiple StopPodSandbox calls are expected.
+    async fn stop_pod_sandbox(&self, request: StopPodSandboxRequest)
+        -> Result<StopPodSandboxResponse, Status>;
 
-pub struct ExecRequest {
+    /// Removes the sandbox. If there are any running containers
+    /// in the sandbox, they must be forcibly terminated and removed.
+    /// This call is idempotent, and must not return an error if the sandbox has
+    /// already been removed.
+    async fn remove_pod_sandbox(&self, request: RemovePodSandboxRequest)
+        -> Result<RemovePodSandboxResponse, Status>;
 
-}
+    /// Returns the status of the PodSandbox. If the PodSandbox is not present, returns an error.
+    async fn pod_sandbox_status(&self, request: PodSandboxStatusRequest)
+        -> Result<PodSandboxStatusResponse, Status>;
 
-pub struct ExecResponse {
+    /// Returns a list of PodSandboxes.
+    async fn list_pod_sandbox(&self, request: ListPodSandboxRequest)
+        -> Result<ListPodSandboxResponse, Status>;
 
-}
+    /// Prepares a streaming endpoint to forward ports from a PodSandbox.
+    async fn port_forward(&self, request: PortForwardRequest)
+        -> Result<PortForwardResponse, Status>;
 
-pub struct AttachRequest {
-    
-}
+    /// ContainerStats returns stats of the container. If the container does not
+    /// exist, the call returns an error.
+    async fn container_stats(&self, request: ContainerStatsRequest)
+        -> Result<ContainerStatsResponse, Status>;
 
-pub struct AttachResponse {
+    /// Returns stats of all running containers.
+    async fn list_container_stats(&self, request: ListContainerStatsRequest)
+        -> Result<ListContainerStatsResponse, Status>;
 
-}
+    /// Returns stats of the pod sandbox. If the pod sandbox does not
+    /// exist, the call returns an error.
+    async fn pod_sandbox_stats(&self, request: PodSandboxStatsRequest)
+        -> Result<PodSandboxStatsResponse, Status>;
 
-pub struct CheckpointContainerRequest {
+    /// Returns stats of the pod sandboxes matching a filter.
+    async fn list_pod_sandbox_stats(&self, request: ListPodSandboxStatsRequest)
+        -> Result<ListPodSandboxStatsResponse, Status>;
 
-}
+    /// Updates the runtime configuration based on the given request.
+    async fn update_runtime_config(&self, request: UpdateRuntimeConfigRequest)
+        -> Result<UpdateRuntimeConfigResponse, Status>;
 
-pub struct ContainerEventResponse {
-    
+    /// Returns the status of the runtime.
+    async fn status(&self, request: StatusRequest)
+        -> Result<StatusResponse, Status>;
+
+    /// Gets the descriptors for the metrics that will be returned in ListPodSandboxMetrics.
+    /// This list should be static at startup: either the client and server restart together when
+    /// adding or removing metrics descriptors, or they should not change.
+    async fn list_metric_descriptors(&self, _request: ListMetricDescriptorsRequest)
+        -> Result<ListMetricDescriptorsResponse, Status>
+    {
+        Err(Status::unimplemented("ListMetricDescriptors"))
+    }
+
+    /// Gets pod sandbox metrics from CRI Runtime
+    async fn list_pod_sandbox_metrics(&self, _request: ListPodSandboxMetricsRequest)
+        -> Result<ListPodSandboxMetricsResponse, Status>
+    {
+        Err(Status::unimplemented("ListPodSandboxMetrics"))
+    }
+
+    /// Returns configuration information of the runtime.
+    /// A couple of notes:
+    /// - The RuntimeConfigRequest object is not to be confused with the contents of UpdateRuntimeConfigRequest.
+    ///   The former is for having runtime tell Kubelet what to do, the latter vice versa.
+    /// - It is the expectation of the Kubelet that these fields are static for the lifecycle of the Kubelet.
+    ///   The Kubelet will not re-request the RuntimeConfiguration after startup, and CRI implementations should
+    ///   avoid updating them without a full node reboot.
+    async fn runtime_config(&self, _request: RuntimeConfigRequest)
+        -> Result<RuntimeConfigResponse, Status>
+    {
+        Err(Status::unimplemented("RuntimeConfig"))
+    }
 }
@@ -0,0 +1,293 @@
+//! gRPC services of [`RuntimeService`] and [`ImageService`] implementations, and serving them on a Unix socket as runtimes do.
+
+use std::{
+    convert::Infallible,
+    future::Future,
+    os::unix::fs::FileTypeExt,
+    path::Path,
+    sync::Arc,
+    task::{Context, Poll}
+};
+
+use prost::Message;
+use tokio::net::UnixListener;
+use tokio_stream::wrappers::UnixListenerStream;
+use tonic::{
+    codegen::{http, Body, BoxFuture, Service, StdError},
+    server::{Grpc, NamedService, ServerStreamingService, UnaryService},
+    transport::Server,
+    Status
+};
+use tonic_prost::ProstCodec;
+
+use super::{api, Error, EventStream, ImageService, RuntimeService};
+
+/**
+Serves `runtime` and `image` on the Unix socket `endpoint`, a path optionally prefixed with `unix://` as in `--container-runtime-endpoint`, until `shutdown` completes.
+
+A socket left at the path by a previous run is removed first.
+*/
+pub async fn serve<R: RuntimeService, I: ImageService>(
+    endpoint: impl AsRef<str>,
+    runtime: R,
+    image: I,
+    shutdown: impl Future<Output = ()>
+) -> Result<(), Error> {
+    let endpoint = endpoint.as_ref();
+    let path = Path::new(endpoint.strip_prefix("unix://").unwrap_or(endpoint));
+    if std::fs::symlink_metadata(path)
+        .is_ok_and(|metadata| metadata.file_type().is_socket())
+    {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    Server::builder()
+        .add_service(RuntimeServiceServer::new(runtime))
+        .add_service(ImageServiceServer::new(image))
+        .serve_with_incoming_shutdown(UnixListenerStream::new(listener), shutdown)
+        .await?;
+    Ok(())
+}
+
+/// The `runtime.v1.RuntimeService` gRPC service of a [`RuntimeService`].
+#[derive(Debug)]
+pub struct RuntimeServiceServer<T> {
+    inner: Arc<T>
+}
+
+impl<T> RuntimeServiceServer<T> {
+    pub fn new(inner: T) -> Self {
+        Self::from_arc(Arc::new(inner))
+    }
+
+    pub fn from_arc(inner: Arc<T>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T> Clone for RuntimeServiceServer<T> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+impl<T> NamedService for RuntimeServiceServer<T> {
+    const NAME: &'static str = "runtime.v1.RuntimeService";
+}
+
+impl<T: RuntimeService, B> Service<http::Request<B>> for RuntimeServiceServer<T>
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static
+{
+    type Response = http::Response<tonic::body::Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let inner = self.inner.clone();
+        match request.uri().path() {
+            "/runtime.v1.RuntimeService/Version" => unary(request,
+                move |request| async move { inner.version(request).await }),
+            "/runtime.v1.RuntimeService/RunPodSandbox" => unary(request,
+                move |request| async move { inner.run_pod_sandbox(request).await }),
+            "/runtime.v1.RuntimeService/StopPodSandbox" => unary(request,
+                move |request| async move { inner.stop_pod_sandbox(request).await }),
+            "/runtime.v1.RuntimeService/RemovePodSandbox" => unary(request,
+                move |request| async move { inner.remove_pod_sandbox(request).await }),
+            "/runtime.v1.RuntimeService/PodSandboxStatus" => unary(request,
+                move |request| async move { inner.pod_sandbox_status(request).await }),
+            "/runtime.v1.RuntimeService/ListPodSandbox" => unary(request,
+                move |request| async move { inner.list_pod_sandbox(request).await }),
+            "/runtime.v1.RuntimeService/CreateContainer" => unary(request,
+                move |request| async move { inner.create_container(request).await }),
+            "/runtime.v1.RuntimeService/StartContainer" => unary(request,
+                move |request| async move { inner.start_container(request).await }),
+            "/runtime.v1.RuntimeService/StopContainer" => unary(request,
+                move |request| async move { inner.stop_container(request).await }),
+            "/runtime.v1.RuntimeService/RemoveContainer" => unary(request,
+                move |request| async move { inner.remove_container(request).await }),
+            "/runtime.v1.RuntimeService/ListContainers" => unary(request,
+                move |request| async move { inner.list_containers(request).await }),
+            "/runtime.v1.RuntimeService/ContainerStatus" => unary(request,
+                move |request| async move { inner.container_status(request).await }),
+            "/runtime.v1.RuntimeService/UpdateContainerResources" => unary(request,
+                move |request| async move {
+                    inner.update_container_resources(request).await
+                }),
+            "/runtime.v1.RuntimeService/ReopenContainerLog" => unary(request,
+                move |request| async move { inner.reopen_container_log(request).await }),
+            "/runtime.v1.RuntimeService/ExecSync" => unary(request,
+                move |request| async move { inner.exec_sync(request).await }),
+            "/runtime.v1.RuntimeService/Exec" => unary(request,
+                move |request| async move { inner.exec(request).await }),
+            "/runtime.v1.RuntimeService/Attach" => unary(request,
+                move |request| async move { inner.attach(request).await }),
+            "/runtime.v1.RuntimeService/PortForward" => unary(request,
+                move |request| async move { inner.port_forward(request).await }),
+            "/runtime.v1.RuntimeService/ContainerStats" => unary(request,
+                move |request| async move { inner.container_stats(request).await }),
+            "/runtime.v1.RuntimeService/ListContainerStats" => unary(request,
+                move |request| async move { inner.list_container_stats(request).await }),
+            "/runtime.v1.RuntimeService/PodSandboxStats" => unary(request,
+                move |request| async move { inner.pod_sandbox_stats(request).await }),
+            "/runtime.v1.RuntimeService/ListPodSandboxStats" => unary(request,
+                move |request| async move { inner.list_pod_sandbox_stats(request).await }),
+            "/runtime.v1.RuntimeService/UpdateRuntimeConfig" => unary(request,
+                move |request| async move { inner.update_runtime_config(request).await }),
+            "/runtime.v1.RuntimeService/Status" => unary(request,
+                move |request| async move { inner.status(request).await }),
+            "/runtime.v1.RuntimeService/CheckpointContainer" => unary(request,
+                move |request| async move { inner.checkpoint_container(request).await }),
+            "/runtime.v1.RuntimeService/GetContainerEvents" => events(request,
+                move |request| async move { inner.get_container_events(request).await }),
+            "/runtime.v1.RuntimeService/ListMetricDescriptors" => unary(request,
+                move |request| async move { inner.list_metric_descriptors(request).await }),
+            "/runtime.v1.RuntimeService/ListPodSandboxMetrics" => unary(request,
+                move |request| async move { inner.list_pod_sandbox_metrics(request).await }),
+            "/runtime.v1.RuntimeService/RuntimeConfig" => unary(request,
+                move |request| async move { inner.runtime_config(request).await }),
+            _ => unimplemented()
+        }
+    }
+}
+
+/// The `runtime.v1.ImageService` gRPC service of an [`ImageService`].
+#[derive(Debug)]
+pub struct ImageServiceServer<T> {
+    inner: Arc<T>
+}
+
+impl<T> ImageServiceServer<T> {
+    pub fn new(inner: T) -> Self {
+        Self::from_arc(Arc::new(inner))
+    }
+
+    pub fn from_arc(inner: Arc<T>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T> Clone for ImageServiceServer<T> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+impl<T> NamedService for ImageServiceServer<T> {
+    const NAME: &'static str = "runtime.v1.ImageService";
+}
+
+impl<T: ImageService, B> Service<http::Request<B>> for ImageServiceServer<T>
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static
+{
+    type Response = http::Response<tonic::body::Body>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let inner = self.inner.clone();
+        match request.uri().path() {
+            "/runtime.v1.ImageService/ListImages" => unary(request,
+                move |request| async move { inner.list_images(request).await }),
+            "/runtime.v1.ImageService/ImageStatus" => unary(request,
+                move |request| async move { inner.image_status(request).await }),
+            "/runtime.v1.ImageService/PullImage" => unary(request,
+                move |request| async move { inner.pull_image(request).await }),
+            "/runtime.v1.ImageService/RemoveImage" => unary(request,
+                move |request| async move { inner.remove_image(request).await }),
+            "/runtime.v1.ImageService/ImageFsInfo" => unary(request,
+                move |request| async move { inner.image_fs_info(request).await }),
+            _ => unimplemented()
+        }
+    }
+}
+
+/// A unary call handled by `handler`.
+struct Unary<F>(Option<F>);
+
+impl<Req, Resp, F, Fut> UnaryService<Req> for Unary<F>
+where
+    F: FnOnce(Req) -> Fut,
+    Fut: Future<Output = Result<Resp, Status>> + Send + 'static
+{
+    type Response = Resp;
+    type Future = BoxFuture<tonic::Response<Resp>, Status>;
+
+    fn call(&mut self, request: tonic::Request<Req>) -> Self::Future {
+        let Some(handler) = self.0.take() else {
+            return Box::pin(async { Err(Status::internal("handler called twice")) })
+        };
+        let future = handler(request.into_inner());
+        Box::pin(async move { future.await.map(tonic::Response::new) })
+    }
+}
+
+fn unary<B, Req, Resp, F, Fut>(request: http::Request<B>, handler: F)
+    -> BoxFuture<http::Response<tonic::body::Body>, Infallible>
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+    Req: Message + Default + Send + 'static,
+    Resp: Message + Send + 'static,
+    F: FnOnce(Req) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Resp, Status>> + Send + 'static
+{
+    Box::pin(async move {
+        let mut grpc = Grpc::new(ProstCodec::<Resp, Req>::default());
+        Ok(grpc.unary(Unary(Some(handler)), request).await)
+    })
+}
+
+/// The server streaming `GetContainerEvents` call handled by `handler`.
+struct Events<F>(Option<F>);
+
+impl<F, Fut> ServerStreamingService<api::GetEventsRequest> for Events<F>
+where
+    F: FnOnce(api::GetEventsRequest) -> Fut,
+    Fut: Future<Output = Result<EventStream, Status>> + Send + 'static
+{
+    type Response = api::ContainerEventResponse;
+    type ResponseStream = EventStream;
+    type Future = BoxFuture<tonic::Response<EventStream>, Status>;
+
+    fn call(&mut self, request: tonic::Request<api::GetEventsRequest>) -> Self::Future {
+        let Some(handler) = self.0.take() else {
+            return Box::pin(async { Err(Status::internal("handler called twice")) })
+        };
+        let future = handler(request.into_inner());
+        Box::pin(async move { future.await.map(tonic::Response::new) })
+    }
+}
+
+fn events<B, F, Fut>(request: http::Request<B>, handler: F)
+    -> BoxFuture<http::Response<tonic::body::Body>, Infallible>
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+    F: FnOnce(api::GetEventsRequest) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<EventStream, Status>> + Send + 'static
+{
+    Box::pin(async move {
+        let mut grpc = Grpc::new(ProstCodec::<
+            api::ContainerEventResponse, api::GetEventsRequest>::default());
+        Ok(grpc.server_streaming(Events(Some(handler)), request).await)
+    })
+}
+
+/// Response to a method the service does not have.
+fn unimplemented() -> BoxFuture<http::Response<tonic::body::Body>, Infallible> {
+    Box::pin(async {
+        Ok(Status::unimplemented("unknown method").into_http())
+    })
+}
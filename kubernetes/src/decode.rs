@@ -0,0 +1,354 @@
+/*!
+Decoding of models from KDL.
+
+[`decode`] decodes a model and fails on every error found. Prefer it to `kfl::decode`, which only returns the first error a decoder returns and drops those the derived decoders record and move on from, such as a child node that does not decode, leaving the field it was meant for at its default.
+
+The derive finds a child by the name of its type, so it cannot tell apart two fields of the same type, nor decode a field whose name differs from its type, a list of scalars or a map. Models with such fields implement `Decode` by hand over [`Fields`], which keeps to the layout of the derived models:
+
+- a scalar is a property named after its field, `restart-policy="always"`;
+- a struct is a child node named after its field, `template { .. }`;
+- a list of structs is a child node per item, named after the item type or, when siblings share that type, after the field in the singular, `init-container "setup" { .. }`;
+- an enum of nodes is the child node of its variant, `rolling-update max-surge="25%"`;
+- a list of scalars is a child node named after its field with the values as arguments, `args "--port" "80"`;
+- a map is a child node named after its field with the entries as properties, `labels app="web"`.
+*/
+
+use std::{
+    collections::BTreeSet,
+    fmt,
+    str::FromStr,
+    time::Duration
+};
+
+use heck::ToKebabCase;
+use kfl::{
+    ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar
+};
+
+use crate::time::parse_duration;
+
+/// Decodes the first node of `input` as a `T`.
+pub fn decode<T: Decode>(file_name: &str, input: &str) -> Result<T, Error> {
+    let mut ctx = Context::default();
+    // Names the source of syntax errors, which `kfl::parse` expects set.
+    ctx.set::<&str>("");
+    let nodes = kfl::parse(&mut ctx, input)
+        .map_err(|error| Error::Parse { file_name: file_name.to_owned(), error })?;
+    let result = match nodes.first() {
+        Some(node) => T::decode(node, &mut ctx),
+        None => Err(DecodeError::MissingNode { message: "no node to decode".to_owned() })
+    };
+    let mut errors = ctx.errors;
+    match result {
+        Ok(value) if errors.is_empty() => Ok(value),
+        Ok(_) => Err(Error::Decode { file_name: file_name.to_owned(), errors }),
+        Err(error) => {
+            errors.insert(0, error);
+            Err(Error::Decode { file_name: file_name.to_owned(), errors })
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The input is not KDL.
+    Parse { file_name: String, error: kfl::Error },
+    /// The input does not have the shape of the model, with every error found.
+    Decode { file_name: String, errors: Vec<DecodeError> }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { file_name, error } => write!(f, "{}: {}", file_name, error),
+            Self::Decode { file_name, errors } => {
+                write!(f, "{}: ", file_name)?;
+                for (index, error) in errors.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str("; ")?;
+                    }
+                    write!(f, "{}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The fields of `node` as they are decoded, which rejects whatever is left over when [finished][Self::finish].
+pub(crate) struct Fields<'a> {
+    node: &'a Node,
+    ctx: &'a mut Context,
+    /// Arguments taken so far.
+    arguments: usize,
+    /// Properties and children taken so far, by name.
+    taken: BTreeSet<&'a str>,
+    /// Children taken so far, by position, for those decoded by type rather than name.
+    taken_children: BTreeSet<usize>
+}
+
+impl<'a> Fields<'a> {
+    pub fn new(node: &'a Node, ctx: &'a mut Context) -> Self {
+        Self { node, ctx, arguments: 0, taken: BTreeSet::new(), taken_children: BTreeSet::new() }
+    }
+
+    /// The error for a required `field` that is missing.
+    pub fn missing(&self, field: &str) -> DecodeError {
+        DecodeError::missing(self.ctx.span(&self.node), format!("{} is required", field))
+    }
+
+    /// The first argument.
+    pub fn argument<T: DecodeScalar>(&mut self) -> Result<Option<T>, DecodeError> {
+        self.arguments = 1;
+        self.node.arguments.first().map(|scalar| T::decode(scalar, self.ctx)).transpose()
+    }
+
+    /// All the arguments.
+    pub fn arguments<T: DecodeScalar>(&mut self) -> Result<Vec<T>, DecodeError> {
+        self.arguments = self.node.arguments.len();
+        self.node.arguments.iter().map(|scalar| T::decode(scalar, self.ctx)).collect()
+    }
+
+    /// The property `name`.
+    pub fn property<T: DecodeScalar>(&mut self, name: &'a str) -> Result<Option<T>, DecodeError> {
+        self.taken.insert(name);
+        self.node.properties.get(name).map(|scalar| T::decode(scalar, self.ctx)).transpose()
+    }
+
+    /// The property `name` as a Go duration, `sync-period="30s"`.
+    pub fn duration(&mut self, name: &'a str) -> Result<Option<Duration>, DecodeError> {
+        let Some(value) = self.property::<String>(name)? else {
+            return Ok(None)
+        };
+        parse_duration(&value).map(Some).ok_or_else(|| DecodeError::conversion(
+            self.ctx.span(&self.node), format!("invalid duration `{}`", value.escape_default())))
+    }
+
+    /// The property `name` parsed as a `T`, for scalars kfl does not decode itself.
+    pub fn parse<T: FromStr>(&mut self, name: &'a str) -> Result<Option<T>, DecodeError>
+        where T::Err: std::error::Error + Send + Sync + 'static
+    {
+        let Some(value) = self.property::<String>(name)? else {
+            return Ok(None)
+        };
+        value.parse().map(Some).map_err(|error| DecodeError::conversion(self.ctx.span(&self.node), error))
+    }
+
+    /// The child `name`, decoded as a `T` whatever name the type gives its nodes.
+    pub fn child<T: Decode>(&mut self, name: &'a str) -> Result<Option<T>, DecodeError> {
+        let mut children = self.named(name);
+        let Some(child) = children.next() else {
+            return Ok(None)
+        };
+        if let Some(duplicate) = children.next() {
+            return Err(DecodeError::unexpected(self.ctx.span(&duplicate), "node",
+                format!("duplicate node `{}`, single node expected", name)))
+        }
+        decode_as(child, self.ctx).map(Some)
+    }
+
+    /// The children `name`, each decoded as a `T`.
+    pub fn children<T: Decode>(&mut self, name: &'a str) -> Result<Vec<T>, DecodeError> {
+        let children: Vec<_> = self.named(name).collect();
+        children.into_iter().map(|child| decode_as(child, self.ctx)).collect()
+    }
+
+    /// The child that decodes as a `T`, which for an enum of nodes is named after its variant.
+    pub fn variant<T: Decode>(&mut self) -> Result<Option<T>, DecodeError> {
+        let children = self.node.children.as_deref().unwrap_or_default();
+        for (index, child) in children.iter().enumerate() {
+            if self.taken.contains(child.node_name.as_ref()) || self.taken_children.contains(&index) {
+                continue
+            }
+            // A child of another type fails to decode, so its errors are only kept for the one that does.
+            let mut scratch = Context::default();
+            if let Ok(value) = T::decode(child, &mut scratch) {
+                self.taken_children.insert(index);
+                self.ctx.errors.append(&mut scratch.errors);
+                return Ok(Some(value))
+            }
+        }
+        Ok(None)
+    }
+
+    /// The arguments of the child `name`.
+    pub fn values<T: DecodeScalar>(&mut self, name: &'a str) -> Result<Vec<T>, DecodeError> {
+        let Some(child) = self.child_node(name)? else {
+            return Ok(Vec::new())
+        };
+        child.arguments.iter().map(|scalar| T::decode(scalar, self.ctx)).collect()
+    }
+
+    /// The properties of the child `name`.
+    pub fn entries<T: DecodeScalar, M: FromIterator<(String, T)>>(&mut self, name: &'a str)
+        -> Result<M, DecodeError>
+    {
+        let Some(child) = self.child_node(name)? else {
+            return Ok(M::from_iter([]))
+        };
+        child.properties.iter()
+            .map(|(key, scalar)| Ok((key.to_string(), T::decode(scalar, self.ctx)?)))
+            .collect()
+    }
+
+    /// The children of the child `name` by their names, each decoded as a `T`.
+    pub fn nodes<T: Decode, M: FromIterator<(String, T)>>(&mut self, name: &'a str)
+        -> Result<M, DecodeError>
+    {
+        let Some(child) = self.child_node(name)? else {
+            return Ok(M::from_iter([]))
+        };
+        child.children.iter().flatten()
+            .map(|node| Ok((node.node_name.to_string(), decode_as(node, self.ctx)?)))
+            .collect()
+    }
+
+    /// The children of the child `name` by their names, each with its arguments as the values.
+    pub fn lists<T: DecodeScalar, M: FromIterator<(String, Vec<T>)>>(&mut self, name: &'a str)
+        -> Result<M, DecodeError>
+    {
+        let Some(child) = self.child_node(name)? else {
+            return Ok(M::from_iter([]))
+        };
+        child.children.iter().flatten()
+            .map(|node| Ok((node.node_name.to_string(), node.arguments.iter()
+                .map(|scalar| T::decode(scalar, self.ctx))
+                .collect::<Result<_, _>>()?)))
+            .collect()
+    }
+
+    /// Fails on any argument, property or child that no field took.
+    pub fn finish(self) -> Result<(), DecodeError> {
+        if let Some(scalar) = self.node.arguments.get(self.arguments) {
+            return Err(DecodeError::unexpected(self.ctx.span(&scalar), "argument",
+                "unexpected argument"))
+        }
+        if let Some(name) = self.node.properties.keys().find(|name| !self.taken.contains(name.as_ref())) {
+            return Err(DecodeError::unexpected(self.ctx.span(&name), "property",
+                format!("unexpected property `{}`", name.escape_default())))
+        }
+        let children = self.node.children.as_deref().unwrap_or_default();
+        if let Some(child) = children.iter().enumerate()
+            .find(|(index, child)| !self.taken.contains(child.node_name.as_ref())
+                && !self.taken_children.contains(index))
+            .map(|(_, child)| child)
+        {
+            return Err(DecodeError::unexpected(self.ctx.span(&child), "node",
+                format!("unexpected node `{}`", child.node_name.escape_default())))
+        }
+        Ok(())
+    }
+
+    fn named(&mut self, name: &'a str) -> impl Iterator<Item = &'a Node> {
+        self.taken.insert(name);
+        self.node.children.as_deref().unwrap_or_default().iter()
+            .filter(move |child| child.node_name.as_ref() == name)
+    }
+
+    fn child_node(&mut self, name: &'a str) -> Result<Option<&'a Node>, DecodeError> {
+        let mut children = self.named(name);
+        let child = children.next();
+        if let Some(duplicate) = children.next() {
+            return Err(DecodeError::unexpected(self.ctx.span(&duplicate), "node",
+                format!("duplicate node `{}`, single node expected", name)))
+        }
+        Ok(child)
+    }
+}
+
+/// Decodes `node` as a `T` under the name the derive gives the nodes of `T`, its type name in kebab case.
+pub(crate) fn decode_as<T: Decode>(node: &Node, ctx: &mut Context) -> Result<T, DecodeError> {
+    let path = std::any::type_name::<T>();
+    let path = path.split_once('<').map_or(path, |(path, _)| path);
+    let name = path.rsplit("::").next().unwrap_or(path).to_kebab_case();
+    if *node.node_name == *name {
+        return T::decode(node, ctx)
+    }
+    let renamed = Node { node_name: name.into(), ..node.clone() };
+    T::decode(&renamed, ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        pod::{Pod, RestartPolicy},
+        service::{ClusterIp, Service, Type}
+    };
+
+    #[test]
+    fn pod() {
+        let pod: Pod = decode("pod.kdl", r#"
+            pod {
+                metadata "web" namespace="default" {
+                    labels app="web" tier="frontend"
+                }
+                spec node-name="node-1" restart-policy="never" {
+                    container "nginx" image="nginx:1.25" {
+                        args "--port" "80"
+                        port container-port=80 name="http"
+                        env-var name="MODE" value="production"
+                    }
+                    init-container "setup" image="busybox" {
+                        command "sh" "-c" "true"
+                    }
+                    node-selector disk="ssd"
+                }
+            }
+        "#).unwrap();
+        assert_eq!(pod.metadata.name.as_deref(), Some("web"));
+        assert_eq!(pod.metadata.namespace.as_deref(), Some("default"));
+        assert_eq!(pod.metadata.labels["tier"], "frontend");
+        assert_eq!(pod.spec.node_name.as_deref(), Some("node-1"));
+        assert_eq!(pod.spec.restart_policy, RestartPolicy::Never);
+        assert_eq!(pod.spec.node_selector["disk"], "ssd");
+        let [container] = &pod.spec.containers[..] else {
+            panic!("expected one container, got {:?}", pod.spec.containers)
+        };
+        assert_eq!(container.name, "nginx");
+        assert_eq!(container.image.as_deref(), Some("nginx:1.25"));
+        assert_eq!(container.args, ["--port", "80"]);
+        assert_eq!(container.ports[0].container_port, 80);
+        assert_eq!(container.env[0].value.as_deref(), Some("production"));
+        assert_eq!(pod.spec.init_containers[0].command, ["sh", "-c", "true"]);
+    }
+
+    #[test]
+    fn service() {
+        let service: Service = decode("service.kdl", r#"
+            service {
+                metadata "web"
+                spec type="node-port" cluster-ip="None" {
+                    selector app="web"
+                    port port=80 target-port=8080
+                    port port=443 target-port="https"
+                }
+            }
+        "#).unwrap();
+        assert!(matches!(service.spec.r#type, Type::NodePort));
+        assert!(matches!(service.spec.cluster_ip, Some(ClusterIp::None)));
+        assert_eq!(service.spec.selector["app"], "web");
+        assert_eq!(service.spec.ports.len(), 2);
+        assert_eq!(service.spec.ports[1].port, 443);
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        assert!(decode::<Pod>("pod.kdl", r#"
+            pod {
+                spec {
+                    container "nginx" image="nginx" imagePullPolicy="Always"
+                }
+            }
+        "#).is_err());
+        assert!(decode::<Pod>("pod.kdl", r#"
+            pod {
+                spec {
+                    containers { container "nginx" }
+                }
+            }
+        "#).is_err());
+    }
+}
@@ -3,56 +3,87 @@
 
 use std::collections::HashMap;
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
 
 use crate::{
     core::{
         endpoints::EndpointPort,
         reference::Reference
     },
+    decode::Fields,
     meta::metadata::Metadata
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/service-resources/endpoint-slice-v1/#EndpointSlice>
 #[derive(Debug, Decode)]
 pub struct EndpointSlice {
-    metadata: Metadata,
-    address_type: AddressType,
-    endpoints: Vec<Endpoint>,
-    ports: Vec<EndpointPort>
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(property, default)]
+    pub address_type: AddressType,
+    #[kfl(children)]
+    pub endpoints: Vec<Endpoint>,
+    #[kfl(children)]
+    pub ports: Vec<EndpointPort>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, DecodeScalar, PartialEq, Eq, Default)]
 pub enum AddressType {
+    #[default]
     Ipv4,
     Ipv6,
     Fqdn
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Endpoint {
-    conditions: Option<EndpointConditions>,
-    deprecated_topology: HashMap<String, String>,
-    hints: Option<EndpointHints>,
-    hostname: Option<String>,
-    node_name: Option<String>,
-    target_ref: Option<Reference>,
-    zone: Option<String>
+    /// Addresses of this endpoint. The contents of this field are interpreted according to the corresponding [`address_type`][EndpointSlice::address_type] field. Consumers must handle different types of addresses in the context of their own capabilities. This must contain at least one address but no more than 100. These are all assumed to be fungible and clients may choose to only use the first element.
+    pub addresses: Vec<String>,
+    pub conditions: Option<EndpointConditions>,
+    pub deprecated_topology: HashMap<String, String>,
+    pub hints: Option<EndpointHints>,
+    pub hostname: Option<String>,
+    pub node_name: Option<String>,
+    pub target_ref: Option<Reference>,
+    pub zone: Option<String>
+}
+
+impl Decode for Endpoint {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let endpoint = Self {
+            addresses: fields.values("addresses")?,
+            conditions: fields.child("conditions")?,
+            deprecated_topology: fields.entries("deprecated-topology")?,
+            hints: fields.child("hints")?,
+            hostname: fields.property("hostname")?,
+            node_name: fields.property("node-name")?,
+            target_ref: fields.child("target-ref")?,
+            zone: fields.property("zone")?
+        };
+        fields.finish()?;
+        Ok(endpoint)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct EndpointConditions {
-    ready: Option<bool>,
-    serving: Option<bool>,
-    terminating: Option<bool>,
+    #[kfl(property, default)]
+    pub ready: Option<bool>,
+    #[kfl(property, default)]
+    pub serving: Option<bool>,
+    #[kfl(property, default)]
+    pub terminating: Option<bool>,
 }
 
 #[derive(Debug, Decode)]
 pub struct EndpointHints {
-    for_zones: Vec<ForZone>,
+    #[kfl(children)]
+    pub for_zones: Vec<ForZone>,
 }
 
 #[derive(Debug, Decode)]
 pub struct ForZone {
-    name: String,
+    #[kfl(property, default)]
+    pub name: String,
 }
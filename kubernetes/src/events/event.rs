@@ -1,30 +1,56 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/event-v1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
 
 use crate::{
     core::reference::Reference,
+    decode::Fields,
     meta::metadata::Metadata,
     time::{MicroTime, Time}
 };
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Event {
-    metadata: Metadata,
-    event_time: MicroTime,
-    action: Option<String>,
-    deprecated_count: Option<i32>,
-    deprecated_first_timestamp: Option<Time>,
-    deprecated_last_timestamp: Option<Time>,
-    deprecated_source: Option<EventSource>,
-    note: Option<String>,
-    reason: Option<String>,
-    regarding: Option<Reference>,
-    related: Option<Reference>,
-    reporting_controller: Option<String>,
-    reporting_instance: Option<String>,
-    series: Option<EventSeries>,
-    r#type: Option<EventType>
+    pub metadata: Metadata,
+    pub event_time: MicroTime,
+    pub action: Option<String>,
+    pub deprecated_count: Option<i32>,
+    pub deprecated_first_timestamp: Option<Time>,
+    pub deprecated_last_timestamp: Option<Time>,
+    pub deprecated_source: Option<EventSource>,
+    pub note: Option<String>,
+    pub reason: Option<String>,
+    pub regarding: Option<Reference>,
+    pub related: Option<Reference>,
+    pub reporting_controller: Option<String>,
+    pub reporting_instance: Option<String>,
+    pub series: Option<EventSeries>,
+    pub r#type: Option<EventType>
+}
+
+impl Decode for Event {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let event = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            event_time: fields.property("event-time")?.unwrap_or_default(),
+            action: fields.property("action")?,
+            deprecated_count: fields.property("deprecated-count")?,
+            deprecated_first_timestamp: fields.property("deprecated-first-timestamp")?,
+            deprecated_last_timestamp: fields.property("deprecated-last-timestamp")?,
+            deprecated_source: fields.child("deprecated-source")?,
+            note: fields.property("note")?,
+            reason: fields.property("reason")?,
+            regarding: fields.child("regarding")?,
+            related: fields.child("related")?,
+            reporting_controller: fields.property("reporting-controller")?,
+            reporting_instance: fields.property("reporting-instance")?,
+            series: fields.child("series")?,
+            r#type: fields.property("type")?
+        };
+        fields.finish()?;
+        Ok(event)
+    }
 }
 
 #[derive(Debug, Decode)]
@@ -37,7 +63,7 @@ pub struct EventSeries {
 
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, DecodeScalar)]
 pub enum EventType {
     Normal,
     Warning
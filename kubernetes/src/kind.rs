@@ -1,6 +1,6 @@
-use kfl::Decode;
+use kfl::DecodeScalar;
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, DecodeScalar)]
 pub enum Kind {
     ClusterRole,
     ServiceAccount
@@ -1,14 +1,20 @@
 //! Reference <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/>
 
+pub mod manifests;
+pub mod validation;
+
 use std::{
     collections::HashMap,
+    fmt,
+    str::FromStr,
     time::Duration
 };
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
 
 use crate::{
     core::node::Taint,
+    decode::Fields,
     time::Time
 };
 
@@ -20,132 +26,342 @@ use crate::{
 // }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-ClusterConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct ClusterConfiguration {
-    etcd: Option<Etcd>,
-    networking: Option<Networking>,
-    kubernetes_version: Option<String>,
-    control_plane_endpoint: Option<String>,
-    api_server: Option<ApiServer>,
-    controller_manager: Option<ControlPlaneComponent>,
-    scheduler: Option<ControlPlaneComponent>,
-    dns: Option<DNS>,
-    certificates_dir: Option<String>,
-    image_repository: Option<String>,
-    feature_gates: Option<HashMap<String, bool>>,
-    cluster_name: Option<String>
+    pub etcd: Option<Etcd>,
+    pub networking: Option<Networking>,
+    pub kubernetes_version: Option<String>,
+    pub control_plane_endpoint: Option<String>,
+    pub api_server: Option<ApiServer>,
+    pub controller_manager: Option<ControlPlaneComponent>,
+    pub scheduler: Option<ControlPlaneComponent>,
+    pub dns: Option<DNS>,
+    pub certificates_dir: Option<String>,
+    pub image_repository: Option<String>,
+    pub feature_gates: Option<HashMap<String, bool>>,
+    pub cluster_name: Option<String>
+}
+
+impl Decode for ClusterConfiguration {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let cluster_configuration = Self {
+            etcd: fields.child("etcd")?,
+            networking: fields.child("networking")?,
+            kubernetes_version: fields.property("kubernetes-version")?,
+            control_plane_endpoint: fields.property("control-plane-endpoint")?,
+            api_server: fields.child("api-server")?,
+            controller_manager: fields.child("controller-manager")?,
+            scheduler: fields.child("scheduler")?,
+            dns: fields.child("dns")?,
+            certificates_dir: fields.property("certificates-dir")?,
+            image_repository: fields.property("image-repository")?,
+            feature_gates: Some(fields.entries("feature-gates")?).filter(|feature_gates: &HashMap<_, _>| !feature_gates.is_empty()),
+            cluster_name: fields.property("cluster-name")?
+        };
+        fields.finish()?;
+        Ok(cluster_configuration)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-Etcd>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Etcd {
-    local: Option<LocalEtcd>,
-    external: Option<ExternalEtcd>
+    pub local: Option<LocalEtcd>,
+    pub external: Option<ExternalEtcd>
+}
+
+impl Decode for Etcd {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let etcd = Self {
+            local: fields.child("local")?,
+            external: fields.child("external")?
+        };
+        fields.finish()?;
+        Ok(etcd)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-LocalEtcd>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct LocalEtcd {
-    image_meta: ImageMeta,
-    data_dir: String,
-    extra_args: HashMap<String, String>,
-    server_cert_sans: Vec<String>,
-    peer_cert_sans: Vec<String>,
+    pub image_meta: ImageMeta,
+    pub data_dir: String,
+    pub extra_args: HashMap<String, String>,
+    pub server_cert_sans: Vec<String>,
+    pub peer_cert_sans: Vec<String>,
+}
+
+impl Decode for LocalEtcd {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let local_etcd = Self {
+            image_meta: fields.child("image-meta")?.unwrap_or_default(),
+            data_dir: fields.property("data-dir")?.unwrap_or_default(),
+            extra_args: fields.entries("extra-args")?,
+            server_cert_sans: fields.values("server-cert-sans")?,
+            peer_cert_sans: fields.values("peer-cert-sans")?
+        };
+        fields.finish()?;
+        Ok(local_etcd)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-ImageMeta>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct ImageMeta {
-    image_repository: Option<String>,
-    image_tag: Option<String>
+    #[kfl(property, default)]
+    pub image_repository: Option<String>,
+    #[kfl(property, default)]
+    pub image_tag: Option<String>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct ExternalEtcd {
-    endpoints: Vec<String>,
-    ca_file: String,
-    cert_file: String,
-    key_file: String
+    pub endpoints: Vec<String>,
+    pub ca_file: String,
+    pub cert_file: String,
+    pub key_file: String
+}
+
+impl Decode for ExternalEtcd {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let external_etcd = Self {
+            endpoints: fields.values("endpoints")?,
+            ca_file: fields.property("ca-file")?.unwrap_or_default(),
+            cert_file: fields.property("cert-file")?.unwrap_or_default(),
+            key_file: fields.property("key-file")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(external_etcd)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-Networking>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Networking {
-    service_subnet: Option<String>,
-    pod_subnet: Option<String>,
-    dns_domain: Option<String>,
+    #[kfl(property, default)]
+    pub service_subnet: Option<String>,
+    #[kfl(property, default)]
+    pub pod_subnet: Option<String>,
+    #[kfl(property, default)]
+    pub dns_domain: Option<String>,
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-ApiServer>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct ApiServer {
-    control_plane_component: ControlPlaneComponent,
-    cert_sans: Vec<String>,
-    timeout_for_control_plane: Option<Duration>
+    pub control_plane_component: ControlPlaneComponent,
+    pub cert_sans: Vec<String>,
+    pub timeout_for_control_plane: Option<Duration>
+}
+
+impl Decode for ApiServer {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let api_server = Self {
+            control_plane_component: fields.child("control-plane-component")?.unwrap_or_default(),
+            cert_sans: fields.values("cert-sans")?,
+            timeout_for_control_plane: fields.duration("timeout-for-control-plane")?
+        };
+        fields.finish()?;
+        Ok(api_server)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-ControlPlaneComponent>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct ControlPlaneComponent {
-    extra_args: HashMap<String, String>,
-    extra_volumes: Vec<HostPathMount>
+    pub extra_args: HashMap<String, String>,
+    pub extra_volumes: Vec<HostPathMount>
+}
+
+impl Decode for ControlPlaneComponent {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let control_plane_component = Self {
+            extra_args: fields.entries("extra-args")?,
+            extra_volumes: fields.children("host-path-mount")?
+        };
+        fields.finish()?;
+        Ok(control_plane_component)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-HostPathMount>
 #[derive(Debug, Decode)]
 pub struct HostPathMount {
-    name: String,
-    host_path: String,
-    mount_path: String,
-    read_only: Option<bool>,
-    path_type: Option<String>  // TODO
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub host_path: String,
+    #[kfl(property, default)]
+    pub mount_path: String,
+    #[kfl(property, default)]
+    pub read_only: Option<bool>,
+    #[kfl(property, default)]
+    pub path_type: Option<String>  // TODO
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-DNS>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct DNS {
-    image_meta: ImageMeta
+    #[kfl(child, default)]
+    pub image_meta: ImageMeta
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-InitConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct InitConfiguration {
-    bootstrap_tokens: Vec<BootstrapToken>,
-    node_registration: Option<NodeRegistrationOptions>,
-    local_api_endpoint: Option<APIEndpoint>,
-    certificate_key: Option<String>,
-    skip_phases: Vec<String>,
-    patches: Option<Patches>
+    pub bootstrap_tokens: Vec<BootstrapToken>,
+    pub node_registration: Option<NodeRegistrationOptions>,
+    pub local_api_endpoint: Option<APIEndpoint>,
+    pub certificate_key: Option<String>,
+    pub skip_phases: Vec<String>,
+    pub patches: Option<Patches>
+}
+
+impl Decode for InitConfiguration {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let init_configuration = Self {
+            bootstrap_tokens: fields.children("bootstrap-token")?,
+            node_registration: fields.child("node-registration")?,
+            local_api_endpoint: fields.child("local-api-endpoint")?,
+            certificate_key: fields.property("certificate-key")?,
+            skip_phases: fields.values("skip-phases")?,
+            patches: fields.child("patches")?
+        };
+        fields.finish()?;
+        Ok(init_configuration)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#BootstrapToken>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct BootstrapToken {
-    token: BootstrapTokenString,
-    description: Option<String>,
-    ttl: Option<Duration>,
-    expires: Option<Time>,
-    usages: Vec<String>,
-    groups: Vec<String>
+    pub token: BootstrapTokenString,
+    pub description: Option<String>,
+    pub ttl: Option<Duration>,
+    pub expires: Option<Time>,
+    pub usages: Vec<String>,
+    pub groups: Vec<String>
+}
+
+impl Decode for BootstrapToken {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let bootstrap_token = Self {
+            token: fields.property("token")?.unwrap_or_default(),
+            description: fields.property("description")?,
+            ttl: fields.duration("ttl")?,
+            expires: fields.property("expires")?,
+            usages: fields.values("usages")?,
+            groups: fields.values("groups")?
+        };
+        fields.finish()?;
+        Ok(bootstrap_token)
+    }
 }
 
+/// A token of the form `[a-z0-9]{6}.[a-z0-9]{16}`. The first part is the public token ID, used as the name of the `bootstrap-token-<id>` Secret, and the second part the token secret.
+///
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#BootstrapTokenString>
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct BootstrapTokenString {
+    pub id: String,
+    pub secret: String
+}
+
+impl BootstrapTokenString {
+    /// Length of [`id`][Self::id].
+    pub const ID_LENGTH: usize = 6;
+    /// Length of [`secret`][Self::secret].
+    pub const SECRET_LENGTH: usize = 16;
+    const CHARSET: &'static [u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    /// Generates a random token, as `kubeadm token generate` does.
+    pub fn generate() -> Result<Self, getrandom::Error> {
+        let mut token = String::with_capacity(
+            Self::ID_LENGTH + 1 + Self::SECRET_LENGTH);
+        let mut byte = [0u8];
+        while token.len() < Self::ID_LENGTH + Self::SECRET_LENGTH {
+            getrandom::fill(&mut byte)?;
+            // Reject the values that would bias the modulo.
+            if usize::from(byte[0]) < 256 - 256 % Self::CHARSET.len() {
+                token.push(char::from(
+                    Self::CHARSET[usize::from(byte[0]) % Self::CHARSET.len()]));
+            }
+        }
+        let secret = token.split_off(Self::ID_LENGTH);
+        Ok(Self { id: token, secret })
+    }
+}
+
+impl FromStr for BootstrapTokenString {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let valid = |part: &str, length|
+            part.len() == length
+            && part.bytes().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+        match s.split_once('.') {
+            Some((id, secret)) if valid(id, Self::ID_LENGTH)
+                && valid(secret, Self::SECRET_LENGTH) =>
+                Ok(Self { id: id.to_owned(), secret: secret.to_owned() }),
+            _ => Err(format!(
+                "the bootstrap token {:?} was not of the form {:?}",
+                s, "[a-z0-9]{6}.[a-z0-9]{16}"))
+        }
+    }
+}
+
+impl fmt::Display for BootstrapTokenString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.id, self.secret)
+    }
+}
+
+impl DecodeScalar for BootstrapTokenString {
+    fn decode(scalar: &kfl::ast::Scalar, ctx: &mut kfl::context::Context)
+        -> Result<Self, kfl::errors::DecodeError>
+    {
+        let token = String::decode(scalar, ctx)?;
+        token.parse().map_err(|message: String|
+            kfl::errors::DecodeError::conversion(ctx.span(&scalar), message))
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-NodeRegistrationOptions>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct NodeRegistrationOptions {
-    name: Option<String>,
-    cri_socket: Option<String>,
-    taints: Vec<Taint>,
-    kubelet_extra_args: HashMap<String, String>,
-    ignore_preflight_errors: Vec<String>,
-    image_pull_policy: Option<PullPolicy>
+    pub name: Option<String>,
+    pub cri_socket: Option<String>,
+    pub taints: Vec<Taint>,
+    pub kubelet_extra_args: HashMap<String, String>,
+    pub ignore_preflight_errors: Vec<String>,
+    pub image_pull_policy: Option<PullPolicy>
 }
 
-#[derive(Debug, Decode, Default)]
+impl Decode for NodeRegistrationOptions {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let node_registration_options = Self {
+            name: fields.property("name")?,
+            cri_socket: fields.property("cri-socket")?,
+            taints: fields.children("taint")?,
+            kubelet_extra_args: fields.entries("kubelet-extra-args")?,
+            ignore_preflight_errors: fields.values("ignore-preflight-errors")?,
+            image_pull_policy: fields.property("image-pull-policy")?
+        };
+        fields.finish()?;
+        Ok(node_registration_options)
+    }
+}
+
+#[derive(Debug, Clone, DecodeScalar, Default)]
 pub enum PullPolicy {
     Always,
     #[default]
@@ -154,56 +370,116 @@ pub enum PullPolicy {
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-APIEndpoint>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct APIEndpoint {
-    advertise_address: Option<String>,
-    bind_port: Option<i32>
+    #[kfl(property, default)]
+    pub advertise_address: Option<String>,
+    #[kfl(property, default)]
+    pub bind_port: Option<i32>
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-Patches>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Patches {
-    directory: Option<String>
+    #[kfl(property, default)]
+    pub directory: Option<String>
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-JoinConfiguration>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct JoinConfiguration {
-    node_registration: Option<NodeRegistrationOptions>,
-    ca_cert_path: Option<String>,
-    discovery: Discovery,
-    control_plane: Option<JoinControlPlane>,
-    skip_phases: Vec<String>,
-    patches: Option<Patches>
+    pub node_registration: Option<NodeRegistrationOptions>,
+    pub ca_cert_path: Option<String>,
+    pub discovery: Discovery,
+    pub control_plane: Option<JoinControlPlane>,
+    pub skip_phases: Vec<String>,
+    pub patches: Option<Patches>
+}
+
+impl Decode for JoinConfiguration {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let join_configuration = Self {
+            node_registration: fields.child("node-registration")?,
+            ca_cert_path: fields.property("ca-cert-path")?,
+            discovery: fields.child("discovery")?.ok_or_else(|| fields.missing("discovery"))?,
+            control_plane: fields.child("control-plane")?,
+            skip_phases: fields.values("skip-phases")?,
+            patches: fields.child("patches")?
+        };
+        fields.finish()?;
+        Ok(join_configuration)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-Discovery>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Discovery {
-    bootstrap_token: Option<BootstrapTokenDiscovery>,
-    file: Option<FileDiscovery>,
-    tls_bootstrap_token: Option<String>,
-    timeout: Option<Duration>
+    pub bootstrap_token: Option<BootstrapTokenDiscovery>,
+    pub file: Option<FileDiscovery>,
+    pub tls_bootstrap_token: Option<String>,
+    pub timeout: Option<Duration>
+}
+
+impl Decode for Discovery {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let discovery = Self {
+            bootstrap_token: fields.child("bootstrap-token")?,
+            file: fields.child("file")?,
+            tls_bootstrap_token: fields.property("tls-bootstrap-token")?,
+            timeout: fields.duration("timeout")?
+        };
+        fields.finish()?;
+        Ok(discovery)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-BootstrapTokenDiscovery>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct BootstrapTokenDiscovery {
-    token: String,
-    api_server_endpoint: Option<String>,
-    ca_cert_hashes: Vec<String>,
-    unsafe_skip_ca_verification: Option<bool>
+    pub token: String,
+    pub api_server_endpoint: Option<String>,
+    pub ca_cert_hashes: Vec<String>,
+    pub unsafe_skip_ca_verification: Option<bool>
+}
+
+impl Decode for BootstrapTokenDiscovery {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let bootstrap_token_discovery = Self {
+            token: fields.property("token")?.unwrap_or_default(),
+            api_server_endpoint: fields.property("api-server-endpoint")?,
+            ca_cert_hashes: fields.values("ca-cert-hashes")?,
+            unsafe_skip_ca_verification: fields.property("unsafe-skip-ca-verification")?
+        };
+        fields.finish()?;
+        Ok(bootstrap_token_discovery)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-FileDiscovery>
 #[derive(Debug, Decode)]
 pub struct FileDiscovery {
-    kube_config_path: String
+    #[kfl(property, default)]
+    pub kube_config_path: String
 }
 
 /// <https://kubernetes.io/docs/reference/config-api/kubeadm-config.v1beta3/#kubeadm-k8s-io-v1beta3-JoinControlPlane>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct JoinControlPlane {
-    local_api_endpoint: Option<APIEndpoint>,
-    certificate_key: Option<String>
+    pub local_api_endpoint: Option<APIEndpoint>,
+    pub certificate_key: Option<String>
+}
+
+impl Decode for JoinControlPlane {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let join_control_plane = Self {
+            local_api_endpoint: fields.child("local-api-endpoint")?,
+            certificate_key: fields.property("certificate-key")?
+        };
+        fields.finish()?;
+        Ok(join_control_plane)
+    }
 }
@@ -0,0 +1,439 @@
+//! Static pod manifests `kubeadm init` writes to `/etc/kubernetes/manifests` for the control plane and local etcd.
+//!
+//! - Source <https://github.com/kubernetes/kubernetes/blob/master/cmd/kubeadm/app/phases/controlplane/manifests.go>
+//! - Source <https://github.com/kubernetes/kubernetes/blob/master/cmd/kubeadm/app/phases/etcd/local.go>
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf
+};
+
+use crate::{
+    core::{
+        pod::{
+            Container, HTTPGetAction, ImagePullPolicy, PodSecurityContext,
+            Probe, Resource, SeccompProfile, Spec, VolumeMount
+        },
+        volume::{host_path, HostPath},
+        Pod, Volume
+    },
+    kubeadm::{APIEndpoint, ClusterConfiguration, ControlPlaneComponent, HostPathMount},
+    meta::Metadata
+};
+
+pub const KUBE_API_SERVER: &str = "kube-apiserver";
+pub const KUBE_CONTROLLER_MANAGER: &str = "kube-controller-manager";
+pub const KUBE_SCHEDULER: &str = "kube-scheduler";
+pub const ETCD: &str = "etcd";
+
+pub const KUBERNETES_DIR: &str = "/etc/kubernetes";
+pub const DEFAULT_CERTIFICATES_DIR: &str = "/etc/kubernetes/pki";
+pub const DEFAULT_IMAGE_REPOSITORY: &str = "registry.k8s.io";
+pub const DEFAULT_KUBERNETES_VERSION: &str = "v1.31.0";
+pub const DEFAULT_ETCD_VERSION: &str = "3.5.15-0";
+pub const DEFAULT_ETCD_DATA_DIR: &str = "/var/lib/etcd";
+pub const DEFAULT_SERVICE_SUBNET: &str = "10.96.0.0/12";
+pub const DEFAULT_DNS_DOMAIN: &str = "cluster.local";
+pub const DEFAULT_CLUSTER_NAME: &str = "kubernetes";
+pub const DEFAULT_API_BIND_PORT: i32 = 6443;
+pub const ETCD_LISTEN_CLIENT_PORT: u16 = 2379;
+pub const ETCD_LISTEN_PEER_PORT: u16 = 2380;
+pub const ETCD_METRICS_PORT: u16 = 2381;
+
+/// Priority of the `system-node-critical` class every static pod runs with.
+const SYSTEM_NODE_CRITICAL_PRIORITY: i32 = 2000001000;
+
+/// The `kube-apiserver`, `kube-controller-manager` and `kube-scheduler` static pods, in that order. `endpoint` is the [`local_api_endpoint`][crate::kubeadm::InitConfiguration::local_api_endpoint] of the node.
+pub fn control_plane(config: &ClusterConfiguration, endpoint: &APIEndpoint)
+    -> Vec<Pod>
+{
+    vec![
+        api_server(config, endpoint),
+        controller_manager(config),
+        scheduler(config)
+    ]
+}
+
+pub fn api_server(config: &ClusterConfiguration, endpoint: &APIEndpoint) -> Pod {
+    let pki = certificates_dir(config);
+    let address = advertise_address(endpoint);
+    let port = endpoint.bind_port.unwrap_or(DEFAULT_API_BIND_PORT);
+    let networking = config.networking.as_ref();
+    let service_subnet = networking
+        .and_then(|networking| networking.service_subnet.as_deref())
+        .unwrap_or(DEFAULT_SERVICE_SUBNET);
+    let dns_domain = networking
+        .and_then(|networking| networking.dns_domain.as_deref())
+        .unwrap_or(DEFAULT_DNS_DOMAIN);
+    let mut args = args([
+        ("advertise-address", address.to_owned()),
+        ("allow-privileged", "true".to_owned()),
+        ("authorization-mode", "Node,RBAC".to_owned()),
+        ("client-ca-file", format!("{}/ca.crt", pki)),
+        ("enable-admission-plugins", "NodeRestriction".to_owned()),
+        ("enable-bootstrap-token-auth", "true".to_owned()),
+        ("kubelet-client-certificate", format!("{}/apiserver-kubelet-client.crt", pki)),
+        ("kubelet-client-key", format!("{}/apiserver-kubelet-client.key", pki)),
+        ("kubelet-preferred-address-types", "InternalIP,ExternalIP,Hostname".to_owned()),
+        ("proxy-client-cert-file", format!("{}/front-proxy-client.crt", pki)),
+        ("proxy-client-key-file", format!("{}/front-proxy-client.key", pki)),
+        ("requestheader-allowed-names", "front-proxy-client".to_owned()),
+        ("requestheader-client-ca-file", format!("{}/front-proxy-ca.crt", pki)),
+        ("requestheader-extra-headers-prefix", "X-Remote-Extra-".to_owned()),
+        ("requestheader-group-headers", "X-Remote-Group".to_owned()),
+        ("requestheader-username-headers", "X-Remote-User".to_owned()),
+        ("secure-port", port.to_string()),
+        ("service-account-issuer", format!("https://kubernetes.default.svc.{}", dns_domain)),
+        ("service-account-key-file", format!("{}/sa.pub", pki)),
+        ("service-account-signing-key-file", format!("{}/sa.key", pki)),
+        ("service-cluster-ip-range", service_subnet.to_owned()),
+        ("tls-cert-file", format!("{}/apiserver.crt", pki)),
+        ("tls-private-key-file", format!("{}/apiserver.key", pki))
+    ]);
+    match config.etcd.as_ref().and_then(|etcd| etcd.external.as_ref()) {
+        Some(external) => {
+            args.insert("etcd-servers".to_owned(), external.endpoints.join(","));
+            for (flag, file) in [("etcd-cafile", &external.ca_file),
+                                 ("etcd-certfile", &external.cert_file),
+                                 ("etcd-keyfile", &external.key_file)]
+            {
+                if !file.is_empty() {
+                    args.insert(flag.to_owned(), file.clone());
+                }
+            }
+        }
+        None => {
+            args.insert("etcd-servers".to_owned(),
+                format!("https://127.0.0.1:{}", ETCD_LISTEN_CLIENT_PORT));
+            args.insert("etcd-cafile".to_owned(), format!("{}/etcd/ca.crt", pki));
+            args.insert("etcd-certfile".to_owned(),
+                format!("{}/apiserver-etcd-client.crt", pki));
+            args.insert("etcd-keyfile".to_owned(),
+                format!("{}/apiserver-etcd-client.key", pki));
+        }
+    }
+    let component = config.api_server.as_ref()
+        .map(|api_server| &api_server.control_plane_component);
+    let mut volumes = vec![
+        host_path_volume("ca-certs", "/etc/ssl/certs", host_path::Type::DirectoryOrCreate, true),
+        host_path_volume("k8s-certs", pki, host_path::Type::DirectoryOrCreate, true)
+    ];
+    extend(&mut args, &mut volumes, component);
+    let probe = |path: &str, failure_threshold: i32| probe(
+        address, port, path, "HTTPS", failure_threshold);
+    let mut container = container(KUBE_API_SERVER,
+        image(config, KUBE_API_SERVER), args, &volumes, "250m");
+    container.liveness_probe = Some(probe("/livez", 8));
+    container.readiness_probe = Some(Probe {
+        period_seconds: Some(1),
+        failure_threshold: Some(3),
+        ..probe("/readyz", 3)
+    });
+    container.startup_probe = Some(probe("/livez", 24));
+    let mut pod = pod(KUBE_API_SERVER, container, volumes);
+    pod.metadata.annotations.insert(
+        "kubeadm.kubernetes.io/kube-apiserver.advertise-address.endpoint".to_owned(),
+        format!("{}:{}", address, port));
+    pod
+}
+
+pub fn controller_manager(config: &ClusterConfiguration) -> Pod {
+    let pki = certificates_dir(config);
+    let kubeconfig = format!("{}/controller-manager.conf", KUBERNETES_DIR);
+    let mut args = args([
+        ("authentication-kubeconfig", kubeconfig.clone()),
+        ("authorization-kubeconfig", kubeconfig.clone()),
+        ("bind-address", "127.0.0.1".to_owned()),
+        ("client-ca-file", format!("{}/ca.crt", pki)),
+        ("cluster-name", config.cluster_name.clone()
+            .unwrap_or_else(|| DEFAULT_CLUSTER_NAME.to_owned())),
+        ("cluster-signing-cert-file", format!("{}/ca.crt", pki)),
+        ("cluster-signing-key-file", format!("{}/ca.key", pki)),
+        ("controllers", "*,bootstrapsigner,tokencleaner".to_owned()),
+        ("kubeconfig", kubeconfig.clone()),
+        ("leader-elect", "true".to_owned()),
+        ("requestheader-client-ca-file", format!("{}/front-proxy-ca.crt", pki)),
+        ("root-ca-file", format!("{}/ca.crt", pki)),
+        ("service-account-private-key-file", format!("{}/sa.key", pki)),
+        ("use-service-account-credentials", "true".to_owned())
+    ]);
+    let networking = config.networking.as_ref();
+    if let Some(pod_subnet) = networking
+        .and_then(|networking| networking.pod_subnet.as_ref())
+    {
+        args.insert("allocate-node-cidrs".to_owned(), "true".to_owned());
+        args.insert("cluster-cidr".to_owned(), pod_subnet.clone());
+        args.insert("service-cluster-ip-range".to_owned(), networking
+            .and_then(|networking| networking.service_subnet.clone())
+            .unwrap_or_else(|| DEFAULT_SERVICE_SUBNET.to_owned()));
+    }
+    let mut volumes = vec![
+        host_path_volume("ca-certs", "/etc/ssl/certs", host_path::Type::DirectoryOrCreate, true),
+        host_path_volume("flexvolume-dir", "/usr/libexec/kubernetes/kubelet-plugins/volume/exec", host_path::Type::DirectoryOrCreate, false),
+        host_path_volume("k8s-certs", pki, host_path::Type::DirectoryOrCreate, true),
+        host_path_volume("kubeconfig", &kubeconfig, host_path::Type::FileOrCreate, true)
+    ];
+    extend(&mut args, &mut volumes, config.controller_manager.as_ref());
+    let mut container = container(KUBE_CONTROLLER_MANAGER,
+        image(config, KUBE_CONTROLLER_MANAGER), args, &volumes, "200m");
+    container.liveness_probe = Some(probe("127.0.0.1", 10257, "/healthz", "HTTPS", 8));
+    container.startup_probe = Some(probe("127.0.0.1", 10257, "/healthz", "HTTPS", 24));
+    pod(KUBE_CONTROLLER_MANAGER, container, volumes)
+}
+
+pub fn scheduler(config: &ClusterConfiguration) -> Pod {
+    let kubeconfig = format!("{}/scheduler.conf", KUBERNETES_DIR);
+    let mut args = args([
+        ("authentication-kubeconfig", kubeconfig.clone()),
+        ("authorization-kubeconfig", kubeconfig.clone()),
+        ("bind-address", "127.0.0.1".to_owned()),
+        ("kubeconfig", kubeconfig.clone()),
+        ("leader-elect", "true".to_owned())
+    ]);
+    let mut volumes = vec![
+        host_path_volume("kubeconfig", &kubeconfig, host_path::Type::FileOrCreate, true)
+    ];
+    extend(&mut args, &mut volumes, config.scheduler.as_ref());
+    let mut container = container(KUBE_SCHEDULER,
+        image(config, KUBE_SCHEDULER), args, &volumes, "100m");
+    container.liveness_probe = Some(probe("127.0.0.1", 10259, "/healthz", "HTTPS", 8));
+    container.startup_probe = Some(probe("127.0.0.1", 10259, "/healthz", "HTTPS", 24));
+    pod(KUBE_SCHEDULER, container, volumes)
+}
+
+/// The static pod of the local etcd member `node_name`, or `None` if the cluster uses external etcd.
+pub fn local_etcd(
+    config: &ClusterConfiguration,
+    endpoint: &APIEndpoint,
+    node_name: &str
+) -> Option<Pod> {
+    let local = match &config.etcd {
+        Some(etcd) => etcd.local.as_ref(),
+        None => None
+    };
+    if config.etcd.as_ref().is_some_and(|etcd| etcd.external.is_some()) {
+        return None
+    }
+    let pki = format!("{}/etcd", certificates_dir(config));
+    let address = advertise_address(endpoint);
+    let host = if address.contains(':') {
+        format!("[{}]", address)
+    } else {
+        address.to_owned()
+    };
+    let client_url = format!("https://{}:{}", host, ETCD_LISTEN_CLIENT_PORT);
+    let peer_url = format!("https://{}:{}", host, ETCD_LISTEN_PEER_PORT);
+    let data_dir = local.map(|local| local.data_dir.as_str())
+        .filter(|data_dir| !data_dir.is_empty())
+        .unwrap_or(DEFAULT_ETCD_DATA_DIR);
+    let mut args = args([
+        ("advertise-client-urls", client_url.clone()),
+        ("cert-file", format!("{}/server.crt", pki)),
+        ("client-cert-auth", "true".to_owned()),
+        ("data-dir", data_dir.to_owned()),
+        ("experimental-initial-corrupt-check", "true".to_owned()),
+        ("experimental-watch-progress-notify-interval", "5s".to_owned()),
+        ("initial-advertise-peer-urls", peer_url.clone()),
+        ("initial-cluster", format!("{}={}", node_name, peer_url)),
+        ("key-file", format!("{}/server.key", pki)),
+        ("listen-client-urls", format!("https://127.0.0.1:{},{}",
+            ETCD_LISTEN_CLIENT_PORT, client_url)),
+        ("listen-metrics-urls", format!("http://127.0.0.1:{}", ETCD_METRICS_PORT)),
+        ("listen-peer-urls", peer_url),
+        ("name", node_name.to_owned()),
+        ("peer-cert-file", format!("{}/peer.crt", pki)),
+        ("peer-client-cert-auth", "true".to_owned()),
+        ("peer-key-file", format!("{}/peer.key", pki)),
+        ("peer-trusted-ca-file", format!("{}/ca.crt", pki)),
+        ("snapshot-count", "10000".to_owned()),
+        ("trusted-ca-file", format!("{}/ca.crt", pki))
+    ]);
+    if let Some(local) = local {
+        args.extend(local.extra_args.iter()
+            .map(|(key, value)| (key.clone(), value.clone())));
+    }
+    let volumes = vec![
+        host_path_volume("etcd-certs", &pki, host_path::Type::DirectoryOrCreate, false),
+        host_path_volume("etcd-data", data_dir, host_path::Type::DirectoryOrCreate, false)
+    ];
+    let image_meta = local.map(|local| &local.image_meta);
+    let repository = image_meta
+        .and_then(|meta| meta.image_repository.as_deref())
+        .unwrap_or_else(|| image_repository(config));
+    let tag = image_meta
+        .and_then(|meta| meta.image_tag.as_deref())
+        .unwrap_or(DEFAULT_ETCD_VERSION);
+    let mut container = container(ETCD, format!("{}/{}:{}", repository, ETCD, tag),
+        args, &volumes, "100m");
+    if let Some(resources) = &mut container.resources {
+        resources.requests.insert("memory".to_owned(), "100Mi".to_owned());
+    }
+    let health = |failure_threshold| probe("127.0.0.1", ETCD_METRICS_PORT,
+        "/health?exclude=NOSPACE&serializable=true", "HTTP", failure_threshold);
+    container.liveness_probe = Some(health(8));
+    container.startup_probe = Some(health(24));
+    let mut pod = pod(ETCD, container, volumes);
+    pod.metadata.annotations.insert(
+        "kubeadm.kubernetes.io/etcd.advertise-client-urls".to_owned(), client_url);
+    Some(pod)
+}
+
+fn certificates_dir(config: &ClusterConfiguration) -> &str {
+    config.certificates_dir.as_deref().unwrap_or(DEFAULT_CERTIFICATES_DIR)
+}
+
+fn image_repository(config: &ClusterConfiguration) -> &str {
+    config.image_repository.as_deref().unwrap_or(DEFAULT_IMAGE_REPOSITORY)
+}
+
+fn advertise_address(endpoint: &APIEndpoint) -> &str {
+    endpoint.advertise_address.as_deref().unwrap_or("127.0.0.1")
+}
+
+/// `<repository>/<component>:<version>`, with `+` replaced as it is not allowed in image tags.
+pub fn image(config: &ClusterConfiguration, component: &str) -> String {
+    let version = config.kubernetes_version.as_deref()
+        .unwrap_or(DEFAULT_KUBERNETES_VERSION);
+    let version = if version.starts_with('v') {
+        version.to_owned()
+    } else {
+        format!("v{}", version)
+    };
+    format!("{}/{}:{}", image_repository(config), component,
+        version.replace('+', "_"))
+}
+
+fn args<const N: usize>(defaults: [(&str, String); N]) -> BTreeMap<String, String> {
+    defaults.into_iter()
+        .map(|(key, value)| (key.to_owned(), value))
+        .collect()
+}
+
+/// Applies the [`extra_args`][ControlPlaneComponent::extra_args], which override the defaults, and [`extra_volumes`][ControlPlaneComponent::extra_volumes] of a component.
+fn extend(
+    args: &mut BTreeMap<String, String>,
+    volumes: &mut Vec<(Volume, PathBuf, bool)>,
+    component: Option<&ControlPlaneComponent>
+) {
+    let Some(component) = component else {
+        return
+    };
+    args.extend(component.extra_args.iter()
+        .map(|(key, value)| (key.clone(), value.clone())));
+    for HostPathMount { name, host_path, mount_path, read_only, path_type }
+        in &component.extra_volumes
+    {
+        let r#type = match path_type.as_deref() {
+            Some("DirectoryOrCreate") => host_path::Type::DirectoryOrCreate,
+            Some("Directory") => host_path::Type::Directory,
+            Some("FileOrCreate") => host_path::Type::FileOrCreate,
+            Some("File") => host_path::Type::File,
+            Some("Socket") => host_path::Type::Socket,
+            Some("CharDevice") => host_path::Type::CharDevice,
+            Some("BlockDevice") => host_path::Type::BlockDevice,
+            _ => host_path::Type::Empty
+        };
+        let (volume, _, read_only) = host_path_volume(name, host_path, r#type,
+            read_only.unwrap_or(false));
+        volumes.push((volume, mount_path.into(), read_only));
+    }
+}
+
+/// A host path volume mounted at the same path in the container.
+fn host_path_volume(
+    name: &str,
+    path: &str,
+    r#type: host_path::Type,
+    read_only: bool
+) -> (Volume, PathBuf, bool) {
+    let volume = Volume {
+        name: name.to_owned(),
+        host_path: Some(HostPath {
+            path: path.into(),
+            r#type: Some(r#type)
+        }),
+        ..Volume::default()
+    };
+    (volume, path.into(), read_only)
+}
+
+fn container(
+    name: &str,
+    image: String,
+    args: BTreeMap<String, String>,
+    volumes: &[(Volume, PathBuf, bool)],
+    cpu: &str
+) -> Container {
+    let command = std::iter::once(name.to_owned())
+        .chain(args.iter().map(|(key, value)| format!("--{}={}", key, value)))
+        .collect();
+    Container {
+        name: name.to_owned(),
+        image: Some(image),
+        image_pull_policy: ImagePullPolicy::IfNotPresent,
+        command,
+        volume_mounts: volumes.iter()
+            .map(|(volume, path, read_only)| VolumeMount {
+                name: volume.name.clone(),
+                path: path.clone(),
+                read_only: read_only.then_some(true),
+                ..VolumeMount::default()
+            })
+            .collect(),
+        resources: Some(Resource {
+            requests: HashMap::from([("cpu".to_owned(), cpu.to_owned())]),
+            ..Resource::default()
+        }),
+        ..Container::default()
+    }
+}
+
+fn probe(host: &str, port: impl TryInto<u16>, path: &str, scheme: &str, failure_threshold: i32)
+    -> Probe
+{
+    Probe {
+        http_get: Some(HTTPGetAction {
+            port: port.try_into().unwrap_or_default(),
+            host: Some(host.to_owned()),
+            path: Some(path.into()),
+            scheme: Some(scheme.to_owned()),
+            ..HTTPGetAction::default()
+        }),
+        initial_delay_seconds: Some(10),
+        timeout_seconds: Some(15),
+        period_seconds: Some(10),
+        failure_threshold: Some(failure_threshold),
+        ..Probe::default()
+    }
+}
+
+/// A static pod in `kube-system` labelled `component=<name>` and `tier=control-plane`.
+fn pod(name: &str, container: Container, volumes: Vec<(Volume, PathBuf, bool)>) -> Pod {
+    Pod {
+        metadata: Metadata {
+            name: Some(name.to_owned()),
+            namespace: Some("kube-system".to_owned()),
+            labels: HashMap::from([
+                ("component".to_owned(), name.to_owned()),
+                ("tier".to_owned(), "control-plane".to_owned())
+            ]),
+            ..Metadata::default()
+        },
+        spec: Spec {
+            containers: vec![container],
+            volumes: volumes.into_iter().map(|(volume, _, _)| volume).collect(),
+            host_network: true,
+            priority_class_name: Some("system-node-critical".to_owned()),
+            priority: Some(SYSTEM_NODE_CRITICAL_PRIORITY),
+            security_context: Some(PodSecurityContext {
+                seccomp_profile: Some(SeccompProfile {
+                    r#type: "RuntimeDefault".to_owned(),
+                    localhost_profile: None
+                }),
+                ..PodSecurityContext::default()
+            }),
+            ..Spec::default()
+        },
+        status: None
+    }
+}
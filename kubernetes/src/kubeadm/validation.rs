@@ -0,0 +1,508 @@
+//! Validation of kubeadm configuration, run by `kubeadm init`, `kubeadm join` and `kubeadm config validate`.
+//!
+//! Source <https://github.com/kubernetes/kubernetes/blob/master/cmd/kubeadm/app/apis/kubeadm/validation/validation.go>
+
+use std::{
+    fmt,
+    net::IpAddr,
+    path::Path
+};
+
+use crate::{
+    kubeadm::{
+        APIEndpoint, ApiServer, BootstrapToken, BootstrapTokenDiscovery,
+        ClusterConfiguration, ControlPlaneComponent, Discovery, Etcd,
+        InitConfiguration, JoinConfiguration, Networking, NodeRegistrationOptions
+    },
+    networking::cidr
+};
+
+/// Usages a bootstrap token may be given.
+pub const BOOTSTRAP_TOKEN_USAGES: [&str; 2] = ["signing", "authentication"];
+
+/// Groups a bootstrap token authenticates as must start with this prefix.
+pub const BOOTSTRAP_GROUP_PREFIX: &str = "system:bootstrappers:";
+
+/// Most subnets a dual-stack subnet list may hold.
+const MAX_DUAL_STACK_SUBNETS: usize = 2;
+
+/// A single invalid field, as in `field.Error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub r#type: ErrorType,
+    /// Path of the field, for example `networking.podSubnet`.
+    pub field: String,
+    pub value: String,
+    pub detail: String
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorType {
+    Required,
+    Invalid,
+    Forbidden
+}
+
+impl Error {
+    fn required(field: &str, detail: &str) -> Self {
+        Self {
+            r#type: ErrorType::Required,
+            field: field.to_owned(),
+            value: String::new(),
+            detail: detail.to_owned()
+        }
+    }
+
+    fn invalid(field: &str, value: impl fmt::Display, detail: &str) -> Self {
+        Self {
+            r#type: ErrorType::Invalid,
+            field: field.to_owned(),
+            value: value.to_string(),
+            detail: detail.to_owned()
+        }
+    }
+
+    fn forbidden(field: &str, detail: &str) -> Self {
+        Self {
+            r#type: ErrorType::Forbidden,
+            field: field.to_owned(),
+            value: String::new(),
+            detail: detail.to_owned()
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.r#type {
+            ErrorType::Required => write!(f, "{}: Required value", self.field)?,
+            ErrorType::Invalid => write!(f, "{}: Invalid value: {:?}",
+                self.field, self.value)?,
+            ErrorType::Forbidden => write!(f, "{}: Forbidden", self.field)?
+        }
+        if !self.detail.is_empty() {
+            write!(f, ": {}", self.detail)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub fn validate_cluster_configuration(config: &ClusterConfiguration)
+    -> Vec<Error>
+{
+    let mut errors = Vec::new();
+    if let Some(networking) = &config.networking {
+        validate_networking(networking, "networking", &mut errors);
+    }
+    if let Some(api_server) = &config.api_server {
+        validate_api_server(api_server, "apiServer", &mut errors);
+    }
+    if let Some(controller_manager) = &config.controller_manager {
+        validate_component(controller_manager, "controllerManager", &mut errors);
+    }
+    if let Some(scheduler) = &config.scheduler {
+        validate_component(scheduler, "scheduler", &mut errors);
+    }
+    if let Some(etcd) = &config.etcd {
+        validate_etcd(etcd, "etcd", &mut errors);
+    }
+    if let Some(endpoint) = &config.control_plane_endpoint {
+        if !is_valid_host_port(endpoint) {
+            errors.push(Error::invalid("controlPlaneEndpoint", endpoint,
+                "must be a valid host or IP, with an optional port"));
+        }
+    }
+    if let Some(version) = &config.kubernetes_version {
+        if !is_valid_version(version) {
+            errors.push(Error::invalid("kubernetesVersion", version,
+                "must be a semantic version such as v1.30.0"));
+        }
+    }
+    if let Some(certificates_dir) = &config.certificates_dir {
+        if !Path::new(certificates_dir).is_absolute() {
+            errors.push(Error::invalid("certificatesDir", certificates_dir,
+                "must be an absolute path"));
+        }
+    }
+    if let Some(cluster_name) = &config.cluster_name {
+        if !is_dns1123_subdomain(cluster_name) {
+            errors.push(Error::invalid("clusterName", cluster_name,
+                "must be a valid DNS-1123 subdomain"));
+        }
+    }
+    errors
+}
+
+pub fn validate_init_configuration(config: &InitConfiguration) -> Vec<Error> {
+    let mut errors = Vec::new();
+    for (index, token) in config.bootstrap_tokens.iter().enumerate() {
+        validate_bootstrap_token(token, &format!("bootstrapTokens[{}]", index),
+            &mut errors);
+    }
+    if let Some(node_registration) = &config.node_registration {
+        validate_node_registration(node_registration, "nodeRegistration",
+            &mut errors);
+    }
+    if let Some(endpoint) = &config.local_api_endpoint {
+        validate_api_endpoint(endpoint, "localAPIEndpoint", &mut errors);
+    }
+    if let Some(key) = &config.certificate_key {
+        validate_certificate_key(key, "certificateKey", &mut errors);
+    }
+    errors
+}
+
+pub fn validate_join_configuration(config: &JoinConfiguration) -> Vec<Error> {
+    let mut errors = Vec::new();
+    validate_discovery(&config.discovery, "discovery", &mut errors);
+    if let Some(node_registration) = &config.node_registration {
+        validate_node_registration(node_registration, "nodeRegistration",
+            &mut errors);
+    }
+    if let Some(ca_cert_path) = &config.ca_cert_path {
+        if !Path::new(ca_cert_path).is_absolute() {
+            errors.push(Error::invalid("caCertPath", ca_cert_path,
+                "must be an absolute path"));
+        }
+    }
+    if let Some(control_plane) = &config.control_plane {
+        if let Some(endpoint) = &control_plane.local_api_endpoint {
+            validate_api_endpoint(endpoint, "controlPlane.localAPIEndpoint",
+                &mut errors);
+        }
+        if let Some(key) = &control_plane.certificate_key {
+            validate_certificate_key(key, "controlPlane.certificateKey",
+                &mut errors);
+        }
+    }
+    errors
+}
+
+fn validate_networking(networking: &Networking, path: &str, errors: &mut Vec<Error>) {
+    if let Some(subnet) = &networking.service_subnet {
+        validate_subnets(subnet, &format!("{}.serviceSubnet", path), errors);
+    }
+    if let Some(subnet) = &networking.pod_subnet {
+        validate_subnets(subnet, &format!("{}.podSubnet", path), errors);
+    }
+    if let Some(domain) = &networking.dns_domain {
+        if !is_dns1123_subdomain(domain) {
+            errors.push(Error::invalid(&format!("{}.dnsDomain", path), domain,
+                "must be a valid DNS-1123 subdomain"));
+        }
+    }
+}
+
+/// A comma-separated list of at most one IPv4 and one IPv6 CIDR.
+fn validate_subnets(subnets: &str, path: &str, errors: &mut Vec<Error>) {
+    let cidrs: Vec<&str> = subnets.split(',').collect();
+    if cidrs.len() > MAX_DUAL_STACK_SUBNETS {
+        errors.push(Error::invalid(path, subnets,
+            "expected a single IP family or one subnet of each family"));
+        return
+    }
+    let mut families = Vec::new();
+    for cidr in cidrs {
+        match cidr::parse(cidr) {
+            Some((ip, _)) => families.push(ip.is_ipv4()),
+            None => errors.push(Error::invalid(path, cidr,
+                "couldn't parse subnet"))
+        }
+    }
+    if families.len() == 2 && families[0] == families[1] {
+        errors.push(Error::invalid(path, subnets,
+            "expected one subnet of each IP family"));
+    }
+}
+
+fn validate_api_server(api_server: &ApiServer, path: &str, errors: &mut Vec<Error>) {
+    validate_component(&api_server.control_plane_component, path, errors);
+    for san in &api_server.cert_sans {
+        if san.parse::<IpAddr>().is_err() && !is_wildcard_dns1123_subdomain(san) {
+            errors.push(Error::invalid(&format!("{}.certSANs", path), san,
+                "altname is not a valid IP address, DNS label or a DNS label with subdomain wildcards"));
+        }
+    }
+}
+
+fn validate_component(
+    component: &ControlPlaneComponent,
+    path: &str,
+    errors: &mut Vec<Error>
+) {
+    for key in component.extra_args.keys() {
+        if key.is_empty() || key.starts_with('-') {
+            errors.push(Error::invalid(&format!("{}.extraArgs", path), key,
+                "argument names must not be empty nor start with a dash"));
+        }
+    }
+    for (index, volume) in component.extra_volumes.iter().enumerate() {
+        let path = format!("{}.extraVolumes[{}]", path, index);
+        if volume.name.is_empty() {
+            errors.push(Error::required(&format!("{}.name", path), ""));
+        }
+        if !Path::new(&volume.host_path).is_absolute() {
+            errors.push(Error::invalid(&format!("{}.hostPath", path),
+                &volume.host_path, "must be an absolute path"));
+        }
+        if !Path::new(&volume.mount_path).is_absolute() {
+            errors.push(Error::invalid(&format!("{}.mountPath", path),
+                &volume.mount_path, "must be an absolute path"));
+        }
+    }
+}
+
+fn validate_etcd(etcd: &Etcd, path: &str, errors: &mut Vec<Error>) {
+    match (&etcd.local, &etcd.external) {
+        (None, None) => errors.push(Error::required(path,
+            "either .Etcd.Local or .Etcd.External is required")),
+        (Some(_), Some(_)) => errors.push(Error::invalid(path, "",
+            ".Etcd.Local and .Etcd.External are mutually exclusive")),
+        (Some(local), None) => {
+            if !Path::new(&local.data_dir).is_absolute() {
+                errors.push(Error::invalid(&format!("{}.local.dataDir", path),
+                    &local.data_dir, "must be an absolute path"));
+            }
+            for (field, sans) in [("serverCertSANs", &local.server_cert_sans),
+                                  ("peerCertSANs", &local.peer_cert_sans)]
+            {
+                for san in sans {
+                    if san.parse::<IpAddr>().is_err()
+                        && !is_wildcard_dns1123_subdomain(san)
+                    {
+                        errors.push(Error::invalid(
+                            &format!("{}.local.{}", path, field), san,
+                            "altname is not a valid IP address, DNS label or a DNS label with subdomain wildcards"));
+                    }
+                }
+            }
+        }
+        (None, Some(external)) => {
+            let path = format!("{}.external", path);
+            if external.endpoints.is_empty() {
+                errors.push(Error::required(&format!("{}.endpoints", path),
+                    "at least one etcd endpoint is required"));
+            }
+            for endpoint in &external.endpoints {
+                if !is_valid_url(endpoint) {
+                    errors.push(Error::invalid(&format!("{}.endpoints", path),
+                        endpoint, "URL parse error"));
+                }
+            }
+            let tls = [&external.ca_file, &external.cert_file, &external.key_file];
+            if tls.iter().any(|file| !file.is_empty())
+                && (external.cert_file.is_empty() || external.key_file.is_empty())
+            {
+                errors.push(Error::invalid(&format!("{}.certFile", path),
+                    &external.cert_file,
+                    "both certFile and keyFile must be set to use TLS client authentication"));
+            }
+            for (field, file) in [("caFile", &external.ca_file),
+                                  ("certFile", &external.cert_file),
+                                  ("keyFile", &external.key_file)]
+            {
+                if !file.is_empty() && !Path::new(file).is_absolute() {
+                    errors.push(Error::invalid(&format!("{}.{}", path, field),
+                        file, "must be an absolute path"));
+                }
+            }
+        }
+    }
+}
+
+fn validate_bootstrap_token(
+    token: &BootstrapToken,
+    path: &str,
+    errors: &mut Vec<Error>
+) {
+    if token.ttl.is_some() && token.expires.is_some() {
+        errors.push(Error::forbidden(&format!("{}.expires", path),
+            "ttl and expires are mutually exclusive"));
+    }
+    for usage in &token.usages {
+        if !BOOTSTRAP_TOKEN_USAGES.contains(&usage.as_str()) {
+            errors.push(Error::invalid(&format!("{}.usages", path), usage,
+                "unknown usage, valid usages are signing and authentication"));
+        }
+    }
+    for group in &token.groups {
+        if !is_valid_bootstrap_group(group) {
+            errors.push(Error::invalid(&format!("{}.groups", path), group,
+                "group must match system:bootstrappers:[a-z0-9:-]{0,255}[a-z0-9]"));
+        }
+    }
+}
+
+/// `system:bootstrappers:[a-z0-9:-]{0,255}[a-z0-9]`
+pub fn is_valid_bootstrap_group(group: &str) -> bool {
+    let Some(name) = group.strip_prefix(BOOTSTRAP_GROUP_PREFIX) else {
+        return false
+    };
+    !name.is_empty()
+        && name.len() <= 256
+        && name.bytes().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()
+            || c == b':' || c == b'-')
+        && name.ends_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
+
+fn validate_node_registration(
+    options: &NodeRegistrationOptions,
+    path: &str,
+    errors: &mut Vec<Error>
+) {
+    if let Some(name) = &options.name {
+        if !is_dns1123_subdomain(name) {
+            errors.push(Error::invalid(&format!("{}.name", path), name,
+                "must be a valid DNS-1123 subdomain"));
+        }
+    }
+    if let Some(socket) = &options.cri_socket {
+        let valid = match socket.split_once("://") {
+            Some(("unix", path)) => Path::new(path).is_absolute(),
+            Some(_) => false,
+            None => Path::new(socket).is_absolute()
+        };
+        if !valid {
+            errors.push(Error::invalid(&format!("{}.criSocket", path), socket,
+                "must be an absolute path or a unix:// URL"));
+        }
+    }
+}
+
+fn validate_api_endpoint(
+    endpoint: &APIEndpoint,
+    path: &str,
+    errors: &mut Vec<Error>
+) {
+    if let Some(address) = &endpoint.advertise_address {
+        if address.parse::<IpAddr>().is_err() {
+            errors.push(Error::invalid(&format!("{}.advertiseAddress", path),
+                address, "must be a valid IP address"));
+        }
+    }
+    if let Some(port) = endpoint.bind_port {
+        if !(1..=65535).contains(&port) {
+            errors.push(Error::invalid(&format!("{}.bindPort", path), port,
+                "must be between 1 and 65535, inclusive"));
+        }
+    }
+}
+
+/// 32 bytes encoded as 64 hexadecimal characters.
+fn validate_certificate_key(key: &str, path: &str, errors: &mut Vec<Error>) {
+    if key.len() != 64 || !key.bytes().all(|c| c.is_ascii_hexdigit()) {
+        errors.push(Error::invalid(path, key,
+            "certificate key must be 64 hexadecimal characters"));
+    }
+}
+
+fn validate_discovery(discovery: &Discovery, path: &str, errors: &mut Vec<Error>) {
+    match (&discovery.bootstrap_token, &discovery.file) {
+        (None, None) => errors.push(Error::required(path,
+            "bootstrapToken or file must be set")),
+        (Some(_), Some(_)) => errors.push(Error::invalid(path, "",
+            "bootstrapToken and file cannot both be set")),
+        (Some(token), None) => validate_bootstrap_token_discovery(
+            token, &format!("{}.bootstrapToken", path), errors),
+        (None, Some(file)) => {
+            if file.kube_config_path.is_empty() {
+                errors.push(Error::required(
+                    &format!("{}.file.kubeConfigPath", path), ""));
+            }
+        }
+    }
+    if let Some(token) = &discovery.tls_bootstrap_token {
+        if !token.is_empty() && token.parse::<super::BootstrapTokenString>().is_err() {
+            errors.push(Error::invalid(&format!("{}.tlsBootstrapToken", path),
+                token, "the bootstrap token is invalid"));
+        }
+    }
+}
+
+fn validate_bootstrap_token_discovery(
+    discovery: &BootstrapTokenDiscovery,
+    path: &str,
+    errors: &mut Vec<Error>
+) {
+    if discovery.token.parse::<super::BootstrapTokenString>().is_err() {
+        errors.push(Error::invalid(&format!("{}.token", path), &discovery.token,
+            "the bootstrap token is invalid"));
+    }
+    match &discovery.api_server_endpoint {
+        Some(endpoint) if is_valid_host_port(endpoint) => {}
+        Some(endpoint) => errors.push(Error::invalid(
+            &format!("{}.apiServerEndpoint", path), endpoint,
+            "must be a valid host or IP with a port")),
+        None => errors.push(Error::required(
+            &format!("{}.apiServerEndpoint", path), ""))
+    }
+    if discovery.ca_cert_hashes.is_empty()
+        && !discovery.unsafe_skip_ca_verification.unwrap_or(false)
+    {
+        errors.push(Error::invalid(&format!("{}.caCertHashes", path), "",
+            "using token-based discovery without caCertHashes can be unsafe. Set unsafeSkipCAVerification as true in your kubeadm config file or pass --discovery-token-unsafe-skip-ca-verification flag to continue"));
+    }
+    for hash in &discovery.ca_cert_hashes {
+        let valid = hash.strip_prefix("sha256:").is_some_and(|digest|
+            digest.len() == 64 && digest.bytes().all(|c| c.is_ascii_hexdigit()));
+        if !valid {
+            errors.push(Error::invalid(&format!("{}.caCertHashes", path), hash,
+                "must be of the form sha256:<64 hexadecimal characters>"));
+        }
+    }
+}
+
+fn is_dns1123_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= 63
+        && label.bytes().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()
+            || c == b'-')
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+}
+
+pub(crate) fn is_dns1123_subdomain(name: &str) -> bool {
+    name.len() <= 253 && name.split('.').all(is_dns1123_label)
+}
+
+fn is_wildcard_dns1123_subdomain(name: &str) -> bool {
+    is_dns1123_subdomain(name.strip_prefix("*.").unwrap_or(name))
+}
+
+/// `host`, `host:port`, `ip` or `ip:port`, IPv6 addresses bracketed when followed by a port.
+fn is_valid_host_port(endpoint: &str) -> bool {
+    if endpoint.parse::<IpAddr>().is_ok() {
+        return true
+    }
+    let (host, port) = match endpoint.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (endpoint, None)
+    };
+    let host = host.strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    port.is_none_or(|port| port.parse::<u16>().is_ok_and(|port| port > 0))
+        && (host.parse::<IpAddr>().is_ok() || is_dns1123_subdomain(host))
+}
+
+fn is_valid_url(url: &str) -> bool {
+    match url.split_once("://") {
+        Some(("http" | "https" | "unix" | "unixs", rest)) => {
+            let authority = rest.split('/').next().unwrap_or_default();
+            !authority.is_empty() && is_valid_host_port(authority)
+        }
+        _ => false
+    }
+}
+
+/// `v1.30.0`, with an optional pre-release or build suffix such as `v1.31.0-rc.1`. The `v` is optional.
+fn is_valid_version(version: &str) -> bool {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<&str> = core.split('.').collect();
+    parts.len() == 3
+        && parts.iter().all(|part|
+            !part.is_empty() && part.bytes().all(|c| c.is_ascii_digit()))
+}
@@ -1,35 +1,1002 @@
-pub struct Create {
-}
-
-pub struct Run {
-}
-
-pub struct Exec {
-}
-
-pub struct Get {
-}
-
-pub struct Apply {
-}
-
-pub struct Delete {
-}
-
-pub struct Expose {
-}
-
-pub struct Set {
-}
-
-pub struct PortForward {
-}
-
-pub struct Config {
-}
-
-pub struct Describe {
-}
-
-pub struct Logs {
-}
+/*!
+`kubectl` commands, parsed from their arguments as `kubectl` parses them and run offline against a local [`Store`] of objects instead of an API server.
+
+- Reference <https://kubernetes.io/docs/reference/kubectl/>
+- Reference <https://kubernetes.io/docs/reference/kubectl/generated/kubectl/>
+- Source <https://github.com/kubernetes/kubectl/tree/master/pkg/cmd>
+*/
+
+pub mod apply;
+pub mod config;
+pub mod describe;
+pub mod jsonpath;
+pub mod printer;
+pub mod resource;
+pub mod store;
+
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr
+};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    meta::{
+        garbage_collection::{Graph, Object, PropagationPolicy},
+        label_selector::Selector,
+        patch
+    },
+    time::Time
+};
+
+use self::{
+    apply::{Change, LAST_APPLIED},
+    printer::{Output, Table, TableOptions},
+    resource::Resource,
+    store::{lookup, string, Key, Store}
+};
+
+pub use self::config::Config;
+
+/// Namespace of commands not given `--namespace`.
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// A command with its arguments.
+#[derive(Debug, Clone)]
+pub enum Command {
+    Get(Get),
+    Describe(Describe),
+    Create(Create),
+    Apply(Apply),
+    Delete(Delete)
+}
+
+impl Command {
+    /// Parses `args`, the command name first, as `["get", "pods", "-o", "wide"]`.
+    pub fn parse(args: &[&str]) -> Result<Self, Error> {
+        let Some((command, args)) = args.split_first() else {
+            return Err(Error::Usage("no command given".to_owned()))
+        };
+        match *command {
+            "get" => Get::parse(args).map(Self::Get),
+            "describe" => Describe::parse(args).map(Self::Describe),
+            "create" => Create::parse(args).map(Self::Create),
+            "apply" => Apply::parse(args).map(Self::Apply),
+            "delete" => Delete::parse(args).map(Self::Delete),
+            command => Err(Error::Usage(format!("unknown command \"{}\" for \"kubectl\"", command)))
+        }
+    }
+
+    /// Runs the command over `store`, writing what `kubectl` prints to `out`.
+    pub fn run(&self, store: &mut Store, now: Time, out: &mut impl Write) -> Result<(), Error> {
+        match self {
+            Self::Get(get) => get.run(store, now, out),
+            Self::Describe(describe) => describe.run(store, now, out),
+            Self::Create(create) => create.run(store, now, out),
+            Self::Apply(apply) => apply.run(store, now, out),
+            Self::Delete(delete) => delete.run(store, now, out)
+        }
+    }
+}
+
+/// Objects of a resource type, all of them if no names are given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub resource: &'static Resource,
+    pub names: Vec<String>
+}
+
+impl Target {
+    /**
+    Parses `TYPE[,TYPE...] [NAME...]` or `TYPE/NAME...`, as `kubectl get` and `kubectl delete` take their targets.
+    */
+    pub fn parse_all(args: &[String], verb: &str) -> Result<Vec<Self>, Error> {
+        let Some(first) = args.first() else {
+            return Err(Error::Usage(format!(
+                "You must specify the type of resource to {}. Use \"kubectl api-resources\" for a \
+                complete list of supported resources.", verb)))
+        };
+        let mut targets: Vec<Self> = Vec::new();
+        if args.iter().any(|arg| arg.contains('/')) {
+            for arg in args {
+                let (name, object) = arg.split_once('/').ok_or_else(|| Error::Usage(
+                    "there is no need to specify a resource type as a separate argument when \
+                    passing arguments in resource/name form".to_owned()))?;
+                let resource = find(name)?;
+                match targets.iter_mut().find(|target| target.resource == resource) {
+                    Some(target) => target.names.push(object.to_owned()),
+                    None => targets.push(Self { resource, names: vec![object.to_owned()] })
+                }
+            }
+        } else {
+            for name in first.split(',').filter(|name| !name.is_empty()) {
+                targets.push(Self { resource: find(name)?, names: args[1..].to_vec() });
+            }
+        }
+        Ok(targets)
+    }
+
+    /**
+    The objects of this target in `namespace`, or in all of them if `None`, matching the selectors and in the order of the names given.
+    */
+    pub fn select<'a>(
+        &self,
+        store: &'a Store,
+        namespace: Option<&'a str>,
+        selector: Option<&Selector>,
+        field_selector: Option<&FieldSelector>
+    ) -> Result<Vec<&'a Value>, Error> {
+        let namespace = namespace.filter(|_| self.resource.namespaced);
+        if self.names.is_empty() {
+            return Ok(store.list(self.resource.kind, namespace)
+                .map(|(_, object)| object)
+                .filter(|object| selector.is_none_or(|selector| selector.matches(&labels(object))))
+                .filter(|object| field_selector.is_none_or(|selector| selector.matches(object)))
+                .collect())
+        }
+        self.names.iter().map(|name| {
+            let key = Key {
+                kind: self.resource.kind.to_owned(),
+                namespace: namespace.map(str::to_owned),
+                name: name.clone()
+            };
+            let found = match namespace {
+                Some(_) => store.get(&key),
+                // Across namespaces, a name is looked up in any of them.
+                None => store.list(self.resource.kind, None)
+                    .find(|(key, _)| &key.name == name)
+                    .map(|(_, object)| object)
+            };
+            found.ok_or_else(|| Error::NotFound {
+                resource: group_resource(self.resource),
+                name: name.clone()
+            })
+        }).collect()
+    }
+}
+
+/// A `--field-selector`: comma-separated `field=value`, `field==value` or `field!=value` requirements over dotted paths.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FieldSelector {
+    pub requirements: Vec<FieldRequirement>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldRequirement {
+    /// Dotted path, as `status.phase`.
+    pub field: String,
+    pub value: String,
+    /// Whether the field must differ from `value`.
+    pub negated: bool
+}
+
+impl FieldSelector {
+    /// Whether every requirement holds of `object`; missing fields are empty.
+    pub fn matches(&self, object: &Value) -> bool {
+        self.requirements.iter().all(|requirement| {
+            let value = match lookup(object, &requirement.field) {
+                Some(Value::String(value)) => value.clone(),
+                Some(Value::Null) | None => String::new(),
+                Some(value) => value.to_string()
+            };
+            (value == requirement.value) != requirement.negated
+        })
+    }
+}
+
+impl FromStr for FieldSelector {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let requirements = s.split(',').filter(|requirement| !requirement.is_empty())
+            .map(|requirement| {
+                let (field, value, negated) = if let Some((field, value)) = requirement.split_once("!=") {
+                    (field, value, true)
+                } else if let Some((field, value)) = requirement.split_once('=') {
+                    (field, value.strip_prefix('=').unwrap_or(value), false)
+                } else {
+                    return Err(Error::Usage(format!("invalid selector: '{}'; can't understand '{}'",
+                        s, requirement)))
+                };
+                Ok(FieldRequirement {
+                    field: field.trim().to_owned(),
+                    value: value.trim().to_owned(),
+                    negated
+                })
+            }).collect::<Result<_, _>>()?;
+        Ok(Self { requirements })
+    }
+}
+
+/// `kubectl get`.
+#[derive(Debug, Clone, Default)]
+pub struct Get {
+    pub targets: Vec<Target>,
+    pub namespace: Option<String>,
+    pub all_namespaces: bool,
+    pub selector: Option<Selector>,
+    pub field_selector: Option<FieldSelector>,
+    pub output: Output,
+    pub show_labels: bool
+}
+
+impl Get {
+    pub fn parse(args: &[&str]) -> Result<Self, Error> {
+        let flags = Flags::parse(args, &[
+            NAMESPACE, ALL_NAMESPACES, SELECTOR, FIELD_SELECTOR,
+            ("output", Some('o'), true),
+            ("show-labels", None, false)
+        ])?;
+        Ok(Self {
+            targets: Target::parse_all(&flags.arguments, "get")?,
+            namespace: flags.value("namespace").map(str::to_owned),
+            all_namespaces: flags.is_set("all-namespaces"),
+            selector: flags.selector()?,
+            field_selector: flags.value("field-selector").map(str::parse).transpose()?,
+            output: flags.value("output").unwrap_or_default().parse()?,
+            show_labels: flags.is_set("show-labels")
+        })
+    }
+
+    /**
+    Prints the objects of the targets.
+
+    A single object asked for by name is printed by itself in JSON, YAML or through a template, anything else as a `List`. Tables of several resource types are printed one after another, with names prefixed by their type. When there is nothing to print in a table, the notice `kubectl` writes to standard error is written to `out`.
+    */
+    pub fn run(&self, store: &Store, now: Time, out: &mut impl Write) -> Result<(), Error> {
+        let namespace = namespace(&self.namespace);
+        let namespace = (!self.all_namespaces).then_some(namespace);
+        let mut found = Vec::new();
+        for target in &self.targets {
+            let objects = target.select(store, namespace, self.selector.as_ref(),
+                self.field_selector.as_ref())?;
+            found.push((target, objects));
+        }
+        let single = match found.as_slice() {
+            [(target, objects)] if target.names.len() == 1 => objects.first().copied(),
+            _ => None
+        };
+        let items = || printer::list(found.iter()
+            .flat_map(|(_, objects)| objects.iter().map(|object| (*object).clone()))
+            .collect());
+        match &self.output {
+            Output::Json => {
+                let value = single.cloned().unwrap_or_else(items);
+                writeln!(out, "{}", serde_json::to_string_pretty(&value)
+                    .map_err(|error| Error::Invalid(error.to_string()))?)?;
+            }
+            Output::Yaml => {
+                write!(out, "{}", printer::to_yaml(&single.cloned().unwrap_or_else(items)))?;
+            }
+            Output::JsonPath(template) => {
+                write!(out, "{}", template.render(&single.cloned().unwrap_or_else(items)))?;
+            }
+            _ if found.iter().all(|(_, objects)| objects.is_empty()) => match namespace {
+                Some(namespace) if found.iter().any(|(target, _)| target.resource.namespaced) =>
+                    writeln!(out, "No resources found in {} namespace.", namespace)?,
+                _ => writeln!(out, "No resources found")?
+            },
+            Output::Name => for (target, objects) in &found {
+                for object in objects {
+                    writeln!(out, "{}/{}", target.resource.qualified_name(),
+                        string(object, "metadata.name"))?;
+                }
+            },
+            Output::CustomColumns(columns) => {
+                let table = Table {
+                    headers: columns.headers(),
+                    rows: found.iter()
+                        .flat_map(|(_, objects)| objects.iter().map(|object| columns.row(object)))
+                        .collect()
+                };
+                write!(out, "{}", table)?;
+            }
+            Output::Table | Output::Wide => {
+                let mut first = true;
+                for (target, objects) in found.iter().filter(|(_, objects)| !objects.is_empty()) {
+                    let qualified = target.resource.qualified_name();
+                    let options = TableOptions {
+                        wide: self.output == Output::Wide,
+                        namespace: self.all_namespaces && target.resource.namespaced,
+                        labels: self.show_labels,
+                        kind: (found.len() > 1).then_some(qualified.as_str())
+                    };
+                    if !first {
+                        writeln!(out)?;
+                    }
+                    first = false;
+                    write!(out, "{}", printer::table(target.resource.kind, objects, options, now))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `kubectl describe`.
+#[derive(Debug, Clone, Default)]
+pub struct Describe {
+    /// Names are prefixes of the names of the objects to describe, unless one matches exactly.
+    pub targets: Vec<Target>,
+    pub namespace: Option<String>,
+    pub all_namespaces: bool,
+    pub selector: Option<Selector>
+}
+
+impl Describe {
+    pub fn parse(args: &[&str]) -> Result<Self, Error> {
+        let flags = Flags::parse(args, &[NAMESPACE, ALL_NAMESPACES, SELECTOR])?;
+        Ok(Self {
+            targets: Target::parse_all(&flags.arguments, "describe")?,
+            namespace: flags.value("namespace").map(str::to_owned),
+            all_namespaces: flags.is_set("all-namespaces"),
+            selector: flags.selector()?
+        })
+    }
+
+    /// Prints the description of each object, separated by blank lines.
+    pub fn run(&self, store: &Store, now: Time, out: &mut impl Write) -> Result<(), Error> {
+        let namespace = (!self.all_namespaces).then_some(namespace(&self.namespace));
+        let mut objects = Vec::new();
+        for target in &self.targets {
+            let all = Target { resource: target.resource, names: Vec::new() }
+                .select(store, namespace, self.selector.as_ref(), None)?;
+            if target.names.is_empty() {
+                objects.extend(all);
+                continue
+            }
+            for name in &target.names {
+                let exact: Vec<&Value> = all.iter().copied()
+                    .filter(|object| string(object, "metadata.name") == name)
+                    .collect();
+                let matching = if exact.is_empty() {
+                    all.iter().copied()
+                        .filter(|object| string(object, "metadata.name").starts_with(name.as_str()))
+                        .collect()
+                } else {
+                    exact
+                };
+                if matching.is_empty() {
+                    return Err(Error::NotFound {
+                        resource: group_resource(target.resource),
+                        name: name.clone()
+                    })
+                }
+                objects.extend(matching);
+            }
+        }
+        for (index, object) in objects.into_iter().enumerate() {
+            if index > 0 {
+                writeln!(out)?;
+                writeln!(out)?;
+            }
+            write!(out, "{}", describe::describe(object, store, now))?;
+        }
+        Ok(())
+    }
+}
+
+/// `--dry-run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DryRun {
+    #[default]
+    None,
+    /// Only prints what would be sent.
+    Client,
+    /// Sends requests the server processes without persisting; it needs a server.
+    Server
+}
+
+impl DryRun {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Client => "client",
+            Self::Server => "server"
+        }
+    }
+}
+
+impl FromStr for DryRun {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            // A bare `--dry-run` is the deprecated spelling of `--dry-run=client`.
+            "client" | "true" | "unchanged" => Ok(Self::Client),
+            "server" => Ok(Self::Server),
+            _ => Err(Error::Usage(format!(
+                "Invalid dry-run value ({}). Must be \"none\", \"server\", or \"client\".", s)))
+        }
+    }
+}
+
+/// What [`Apply`] or [`Create`] did, or would do, to an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Created,
+    Configured,
+    Unchanged
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Configured => "configured",
+            Self::Unchanged => "unchanged"
+        }
+    }
+}
+
+/// An object created or applied, with the fields that changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Applied {
+    /// `deployment.apps` or `pod`.
+    pub resource: String,
+    pub name: String,
+    pub action: Action,
+    pub dry_run: DryRun,
+    pub changes: Vec<Change>
+}
+
+impl fmt::Display for Applied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{} {}", self.resource, self.name, self.action.as_str())?;
+        match self.dry_run {
+            DryRun::None => Ok(()),
+            dry_run => write!(f, " ({} dry run)", dry_run.as_str())
+        }
+    }
+}
+
+/// `kubectl create -f`.
+#[derive(Debug, Clone, Default)]
+pub struct Create {
+    pub filenames: Vec<PathBuf>,
+    pub namespace: Option<String>,
+    pub dry_run: DryRun
+}
+
+impl Create {
+    pub fn parse(args: &[&str]) -> Result<Self, Error> {
+        let flags = Flags::parse(args, &[NAMESPACE, FILENAME, DRY_RUN])?;
+        Ok(Self {
+            filenames: flags.filenames()?,
+            namespace: flags.value("namespace").map(str::to_owned),
+            dry_run: flags.dry_run()?
+        })
+    }
+
+    /// Creates `object`, which must not exist yet.
+    pub fn create(&self, store: &mut Store, object: Value, now: Time) -> Result<Applied, Error> {
+        let (resource, object) = prepare(object, self.namespace.as_deref())?;
+        let key = Key::of(&object)?;
+        if store.get(&key).is_some() {
+            return Err(Error::AlreadyExists { resource: group_resource(resource), name: key.name })
+        }
+        insert(store, resource, object, self.dry_run, now)
+    }
+
+    /// Creates the objects of the files, printing a line for each.
+    pub fn run(&self, store: &mut Store, now: Time, out: &mut impl Write) -> Result<(), Error> {
+        for object in read(&self.filenames)? {
+            writeln!(out, "{}", self.create(store, object, now)?)?;
+        }
+        Ok(())
+    }
+}
+
+/**
+`kubectl apply -f`.
+
+Objects are created with the configuration applied recorded in [`LAST_APPLIED`], or patched with the three-way merge of that record, the configuration applied and the live object; see [`apply`].
+*/
+#[derive(Debug, Clone, Default)]
+pub struct Apply {
+    pub filenames: Vec<PathBuf>,
+    pub namespace: Option<String>,
+    pub dry_run: DryRun
+}
+
+impl Apply {
+    pub fn parse(args: &[&str]) -> Result<Self, Error> {
+        let flags = Flags::parse(args, &[NAMESPACE, FILENAME, DRY_RUN])?;
+        Ok(Self {
+            filenames: flags.filenames()?,
+            namespace: flags.value("namespace").map(str::to_owned),
+            dry_run: flags.dry_run()?
+        })
+    }
+
+    /// Applies `object`, leaving `store` as it is on a dry run.
+    pub fn apply(&self, store: &mut Store, object: Value, now: Time) -> Result<Applied, Error> {
+        let (resource, object) = prepare(object, self.namespace.as_deref())?;
+        let modified = apply::annotate(&object);
+        let key = Key::of(&modified)?;
+        let Some(current) = store.get(&key) else {
+            return insert(store, resource, modified, self.dry_run, now)
+        };
+        let original = lookup(current, "metadata.annotations")
+            .and_then(|annotations| annotations.get(LAST_APPLIED))
+            .and_then(Value::as_str)
+            .and_then(|recorded| serde_json::from_str::<Value>(recorded).ok());
+        let patch = apply::three_way_patch(original.as_ref(), &modified, current);
+        let mut patched = current.clone();
+        patch::merge_patch(&mut patched, &patch);
+        let changes = apply::diff(current, &patched);
+        let applied = Applied {
+            resource: resource.qualified_name(),
+            name: key.name.clone(),
+            action: if changes.is_empty() { Action::Unchanged } else { Action::Configured },
+            dry_run: self.dry_run,
+            changes
+        };
+        if self.dry_run == DryRun::None && applied.action == Action::Configured {
+            store.insert(patched)?;
+        }
+        Ok(applied)
+    }
+
+    /// Applies the objects of the files, printing a line for each and, on a client dry run, the fields that would change.
+    pub fn run(&self, store: &mut Store, now: Time, out: &mut impl Write) -> Result<(), Error> {
+        for object in read(&self.filenames)? {
+            let applied = self.apply(store, object, now)?;
+            writeln!(out, "{}", applied)?;
+            if applied.dry_run == DryRun::Client && applied.action == Action::Configured {
+                for change in &applied.changes {
+                    writeln!(out, "  {}", change)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `kubectl delete`.
+#[derive(Debug, Clone, Default)]
+pub struct Delete {
+    pub targets: Vec<Target>,
+    pub namespace: Option<String>,
+    pub all_namespaces: bool,
+    pub selector: Option<Selector>,
+    /// Deletes every object of the types, as `--all`.
+    pub all: bool,
+    pub cascade: PropagationPolicy,
+    pub dry_run: DryRun
+}
+
+impl Delete {
+    pub fn parse(args: &[&str]) -> Result<Self, Error> {
+        let flags = Flags::parse(args, &[
+            NAMESPACE, ALL_NAMESPACES, SELECTOR, DRY_RUN,
+            ("all", None, false),
+            ("cascade", None, false)
+        ])?;
+        let cascade = match flags.value("cascade") {
+            None | Some("background" | "true") => PropagationPolicy::Background,
+            Some("foreground") => PropagationPolicy::Foreground,
+            Some("orphan" | "false") => PropagationPolicy::Orphan,
+            Some(cascade) => return Err(Error::Usage(format!(
+                "invalid cascade value ({}). Must be \"background\", \"foreground\", or \"orphan\"",
+                cascade)))
+        };
+        let delete = Self {
+            targets: Target::parse_all(&flags.arguments, "delete")?,
+            namespace: flags.value("namespace").map(str::to_owned),
+            all_namespaces: flags.is_set("all-namespaces"),
+            selector: flags.selector()?,
+            all: flags.is_set("all"),
+            cascade,
+            dry_run: flags.dry_run()?
+        };
+        if !delete.all && delete.selector.is_none()
+            && delete.targets.iter().any(|target| target.names.is_empty())
+        {
+            return Err(Error::Usage("resource(s) were provided, but no name was specified".to_owned()))
+        }
+        Ok(delete)
+    }
+
+    /**
+    Deletes the objects of the targets, cascading to their dependents through the owner graph of the objects with UIDs as the garbage collector would, and prints a line for each object deleted by name.
+
+    Objects kept by finalisers are left in `store` with their deletion timestamp set.
+    */
+    pub fn run(&self, store: &mut Store, now: Time, out: &mut impl Write) -> Result<(), Error> {
+        let namespace = (!self.all_namespaces).then_some(namespace(&self.namespace));
+        let mut keys = Vec::new();
+        for target in &self.targets {
+            for object in target.select(store, namespace, self.selector.as_ref(), None)? {
+                keys.push((target.resource, Key::of(object)?));
+            }
+        }
+        for (resource, key) in keys {
+            // Dependents deleted along an earlier object are gone already.
+            let Some(object) = store.get(&key) else {
+                continue
+            };
+            let uid = string(object, "metadata.uid").to_owned();
+            let suffix = match self.dry_run {
+                DryRun::None => "",
+                DryRun::Client => " (client dry run)",
+                DryRun::Server => return Err(Error::Usage(
+                    "--dry-run=server needs an API server".to_owned()))
+            };
+            if self.dry_run == DryRun::None {
+                if uid.is_empty() {
+                    store.remove(&key);
+                } else {
+                    cascade(store, &uid, self.cascade, now)?;
+                }
+            }
+            writeln!(out, "{} \"{}\" deleted{}", resource.qualified_name(), key.name, suffix)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Run {
+}
+
+pub struct Exec {
+}
+
+pub struct Expose {
+}
+
+pub struct Set {
+}
+
+pub struct PortForward {
+}
+
+pub struct Logs {
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Usage(String),
+    /// A resource type not served, by the name given.
+    UnknownResource(String),
+    UnknownKind { api_version: String, kind: String },
+    /// `resource` is the plural with its group, as `deployments.apps`.
+    NotFound { resource: String, name: String },
+    AlreadyExists { resource: String, name: String },
+    Invalid(String),
+    Template(String),
+    Io(io::Error)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(message) | Self::Invalid(message) => f.write_str(message),
+            Self::UnknownResource(name) =>
+                write!(f, "the server doesn't have a resource type \"{}\"", name),
+            Self::UnknownKind { api_version, kind } =>
+                write!(f, "no matches for kind \"{}\" in version \"{}\"", kind, api_version),
+            Self::NotFound { resource, name } => write!(f, "{} \"{}\" not found", resource, name),
+            Self::AlreadyExists { resource, name } =>
+                write!(f, "{} \"{}\" already exists", resource, name),
+            Self::Template(message) => write!(f, "error parsing template: {}", message),
+            Self::Io(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+type Flag = (&'static str, Option<char>, bool);
+
+const NAMESPACE: Flag = ("namespace", Some('n'), true);
+const ALL_NAMESPACES: Flag = ("all-namespaces", Some('A'), false);
+const SELECTOR: Flag = ("selector", Some('l'), true);
+const FIELD_SELECTOR: Flag = ("field-selector", None, true);
+const FILENAME: Flag = ("filename", Some('f'), true);
+// Takes its value only after `=`, so that a bare `--dry-run` does not swallow the next argument.
+const DRY_RUN: Flag = ("dry-run", None, false);
+
+/// Arguments split into flags, by long name, and the rest.
+#[derive(Debug, Default)]
+struct Flags {
+    values: HashMap<&'static str, Vec<String>>,
+    arguments: Vec<String>
+}
+
+impl Flags {
+    /**
+    Splits `args` given the flags of a command, as `(name, short name, whether it takes a value)`: `--name value`, `--name=value`, `-n value` and `-nvalue` for flags with values, `--name` and `--name=value` for the others. Arguments after `--` are not flags.
+    */
+    fn parse(args: &[&str], known: &[Flag]) -> Result<Self, Error> {
+        let mut flags = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = if *arg == "--" {
+                flags.arguments.extend(args.by_ref().map(|arg| arg.to_string()));
+                break
+            } else if let Some(long) = arg.strip_prefix("--") {
+                let (name, value) = long.split_once('=').map_or((long, None), |(name, value)| (name, Some(value)));
+                let flag = known.iter().find(|flag| flag.0 == name)
+                    .ok_or_else(|| Error::Usage(format!("unknown flag: --{}", name)))?;
+                (flag, value.map(str::to_owned))
+            } else if let Some(short) = arg.strip_prefix('-').filter(|short| !short.is_empty()) {
+                let letter = short.chars().next().unwrap_or_default();
+                let flag = known.iter().find(|flag| flag.1 == Some(letter))
+                    .ok_or_else(|| Error::Usage(format!(
+                        "unknown shorthand flag: '{}' in {}", letter, arg)))?;
+                let rest = &short[letter.len_utf8()..];
+                let rest = rest.strip_prefix('=').unwrap_or(rest);
+                (flag, (!rest.is_empty()).then(|| rest.to_owned()))
+            } else {
+                flags.arguments.push(arg.to_string());
+                continue
+            };
+            let (name, _, takes_value) = *flag;
+            let value = match inline {
+                Some(value) => value,
+                None if takes_value => args.next().map(|value| value.to_string())
+                    .ok_or_else(|| Error::Usage(format!("flag needs an argument: --{}", name)))?,
+                None => "true".to_owned()
+            };
+            flags.values.entry(name).or_default().push(value);
+        }
+        Ok(flags)
+    }
+
+    /// The last value of `name`.
+    fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).and_then(|values| values.last()).map(String::as_str)
+    }
+
+    fn is_set(&self, name: &str) -> bool {
+        self.value(name).is_some_and(|value| value != "false")
+    }
+
+    fn selector(&self) -> Result<Option<Selector>, Error> {
+        self.value("selector").map(|selector| selector.parse()
+            .map_err(|error: crate::meta::label_selector::Error| Error::Usage(error.to_string())))
+            .transpose()
+    }
+
+    fn filenames(&self) -> Result<Vec<PathBuf>, Error> {
+        let filenames: Vec<PathBuf> = self.values.get("filename").into_iter().flatten()
+            .flat_map(|filenames| filenames.split(','))
+            .map(PathBuf::from)
+            .collect();
+        if filenames.is_empty() {
+            return Err(Error::Usage("must specify one of -f and -k".to_owned()))
+        }
+        Ok(filenames)
+    }
+
+    fn dry_run(&self) -> Result<DryRun, Error> {
+        self.value("dry-run").map_or(Ok(DryRun::None), str::parse)
+    }
+}
+
+fn find(name: &str) -> Result<&'static Resource, Error> {
+    resource::find(name).ok_or_else(|| Error::UnknownResource(name.to_owned()))
+}
+
+fn namespace(namespace: &Option<String>) -> &str {
+    namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE)
+}
+
+/// The plural of `resource` with its group, as errors name resources.
+fn group_resource(resource: &Resource) -> String {
+    if resource.group.is_empty() {
+        resource.name.to_owned()
+    } else {
+        format!("{}.{}", resource.name, resource.group)
+    }
+}
+
+fn labels(object: &Value) -> HashMap<String, String> {
+    lookup(object, "metadata.labels").and_then(Value::as_object).into_iter().flatten()
+        .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_owned())))
+        .collect()
+}
+
+/// The objects of JSON manifests, each a single object, a stream of them or a `List`.
+fn read(filenames: &[PathBuf]) -> Result<Vec<Value>, Error> {
+    let mut objects = Vec::new();
+    for filename in filenames {
+        let content = fs::read_to_string(filename)?;
+        for value in serde_json::Deserializer::from_str(&content).into_iter::<Value>() {
+            let value = value.map_err(|error| Error::Invalid(
+                format!("error parsing {}: {}", filename.display(), error)))?;
+            match value.get("items").and_then(Value::as_array) {
+                Some(items) if string(&value, "kind").ends_with("List") =>
+                    objects.extend(items.iter().cloned()),
+                _ => objects.push(value)
+            }
+        }
+    }
+    Ok(objects)
+}
+
+/// The resource of `object`, and `object` in the namespace of the command if it is namespaced.
+fn prepare(mut object: Value, namespace: Option<&str>) -> Result<(&'static Resource, Value), Error> {
+    let api_version = string(&object, "apiVersion");
+    let kind = string(&object, "kind").to_owned();
+    let resource = resource::for_kind(api_version, &kind).ok_or_else(|| Error::UnknownKind {
+        api_version: api_version.to_owned(),
+        kind: kind.clone()
+    })?;
+    if !resource.namespaced {
+        return Ok((resource, object))
+    }
+    let metadata = object.get_mut("metadata").and_then(Value::as_object_mut)
+        .ok_or_else(|| Error::Invalid(format!("{} has no metadata", kind)))?;
+    match (metadata.get("namespace").and_then(Value::as_str), namespace) {
+        (Some(own), Some(namespace)) if own != namespace => return Err(Error::Invalid(format!(
+            "the namespace from the provided object \"{}\" does not match the namespace \"{}\". \
+            You must pass '--namespace={}' to perform this operation.", own, namespace, own))),
+        (Some(_), _) => {}
+        (None, namespace) => {
+            metadata.insert("namespace".to_owned(),
+                Value::String(namespace.unwrap_or(DEFAULT_NAMESPACE).to_owned()));
+        }
+    }
+    Ok((resource, object))
+}
+
+/// Stores a new object, stamped with its creation time, unless on a dry run.
+fn insert(store: &mut Store, resource: &Resource, mut object: Value, dry_run: DryRun, now: Time)
+    -> Result<Applied, Error>
+{
+    if dry_run == DryRun::Server {
+        return Err(Error::Usage("--dry-run=server needs an API server".to_owned()))
+    }
+    if let Some(metadata) = object.get_mut("metadata").and_then(Value::as_object_mut) {
+        metadata.entry("creationTimestamp").or_insert_with(|| Value::String(now.to_string()));
+    }
+    let applied = Applied {
+        resource: resource.qualified_name(),
+        name: Key::of(&object)?.name,
+        action: Action::Created,
+        dry_run,
+        changes: apply::diff(&Value::Object(Map::new()), &object)
+    };
+    if dry_run == DryRun::None {
+        store.insert(object)?;
+    }
+    Ok(applied)
+}
+
+/// Deletes the object with `uid` through the owner graph of `store`, then writes back what remains of the graph.
+fn cascade(store: &mut Store, uid: &str, policy: PropagationPolicy, now: Time) -> Result<(), Error> {
+    let objects: Vec<(Key, Object)> = store.iter()
+        .filter(|(_, object)| !string(object, "metadata.uid").is_empty())
+        .map(|(key, object)| (key.clone(), Object::from_json(object)))
+        .collect();
+    let keys: HashMap<String, Key> = objects.iter()
+        .map(|(key, object)| (object.uid().to_owned(), key.clone()))
+        .collect();
+    let mut graph = Graph::new(objects.into_iter().map(|(_, object)| object))
+        .map_err(|error| Error::Invalid(error.to_string()))?;
+    let deletion = graph.delete(uid, policy, now).map_err(|error| Error::Invalid(error.to_string()))?;
+    for uid in &deletion.removed {
+        store.remove(&keys[uid]);
+    }
+    for object in graph.objects() {
+        if let Some(value) = store.get_mut(&keys[object.uid()]) {
+            object.write_json(value);
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn run(store: &mut Store, args: &[&str]) -> Result<String, Error> {
+        let now = "2024-01-01T00:10:00Z".parse().unwrap();
+        let mut out = Vec::new();
+        Command::parse(args)?.run(store, now, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn config_map(data: Value) -> Value {
+        json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {"name": "settings", "labels": {"app": "web"}},
+            "data": data
+        })
+    }
+
+    #[test]
+    fn parses_flags() {
+        let Command::Get(get) = Command::parse(&[
+            "get", "pods,services", "web", "-napps", "-o", "wide", "--selector=app=web",
+            "--show-labels"
+        ]).unwrap() else {
+            panic!("expected get")
+        };
+        assert_eq!(get.targets.len(), 2);
+        assert_eq!(get.targets[1].names, ["web"]);
+        assert_eq!(get.namespace.as_deref(), Some("apps"));
+        assert_eq!(get.output, Output::Wide);
+        assert!(get.selector.is_some() && get.show_labels);
+
+        let Command::Apply(apply) = Command::parse(&[
+            "apply", "-f", "a.json,b.json", "--dry-run", "-f", "c.json"
+        ]).unwrap() else {
+            panic!("expected apply")
+        };
+        assert_eq!(apply.filenames.len(), 3);
+        assert_eq!(apply.dry_run, DryRun::Client);
+
+        assert!(matches!(Command::parse(&["get", "pods", "--watch"]), Err(Error::Usage(_))));
+        assert!(matches!(Command::parse(&["get", "pods", "-o"]), Err(Error::Usage(_))));
+        assert!(matches!(Command::parse(&["delete", "pods"]), Err(Error::Usage(_))));
+        assert!(matches!(Command::parse(&["get", "widgets"]), Err(Error::UnknownResource(_))));
+    }
+
+    #[test]
+    fn applies_objects() {
+        let mut store = Store::new();
+        let now = "2024-01-01T00:00:00Z".parse().unwrap();
+        let apply = Apply::default();
+        let created = apply.apply(&mut store, config_map(json!({"a": "1", "b": "2"})), now)
+            .unwrap();
+        assert_eq!(created.to_string(), "configmap/settings created");
+
+        // Fields set by others survive, fields removed from the configuration do not.
+        let key = store.iter().next().map(|(key, _)| key.clone()).unwrap();
+        store.get_mut(&key).unwrap()["data"]["c"] = json!("3");
+        let dry_run = Apply { dry_run: DryRun::Client, ..Default::default() };
+        let applied = dry_run.apply(&mut store, config_map(json!({"a": "10"})), now).unwrap();
+        assert_eq!(applied.to_string(), "configmap/settings configured (client dry run)");
+        assert_eq!(store.get(&key).unwrap()["data"], json!({"a": "1", "b": "2", "c": "3"}));
+        let applied = apply.apply(&mut store, config_map(json!({"a": "10"})), now).unwrap();
+        assert_eq!(applied.action, Action::Configured);
+        assert_eq!(store.get(&key).unwrap()["data"], json!({"a": "10", "c": "3"}));
+        let applied = apply.apply(&mut store, config_map(json!({"a": "10"})), now).unwrap();
+        assert_eq!(applied.action, Action::Unchanged);
+
+        assert!(matches!(Create::default().create(&mut store, config_map(json!({})), now),
+            Err(Error::AlreadyExists { .. })));
+    }
+
+    #[test]
+    fn gets_describes_and_deletes() {
+        let mut store = Store::new();
+        let now = "2024-01-01T00:00:00Z".parse().unwrap();
+        Apply::default().apply(&mut store, config_map(json!({"a": "1"})), now).unwrap();
+        assert_eq!(run(&mut store, &["get", "configmaps"]).unwrap(), "\
+            NAME       DATA   AGE\n\
+            settings   1      10m\n");
+        assert_eq!(run(&mut store, &["get", "cm", "-o", "name", "-l", "app=web"]).unwrap(),
+            "configmap/settings\n");
+        assert_eq!(run(&mut store, &["get", "configmaps", "-n", "apps"]).unwrap(),
+            "No resources found in apps namespace.\n");
+        let description = run(&mut store, &["describe", "configmap", "sett"]).unwrap();
+        assert!(description.starts_with("Name:         settings\n"), "{}", description);
+        assert!(matches!(run(&mut store, &["describe", "configmap", "other"]),
+            Err(Error::NotFound { .. })));
+        assert_eq!(run(&mut store, &["delete", "configmap", "settings", "--dry-run=client"])
+            .unwrap(), "configmap \"settings\" deleted (client dry run)\n");
+        assert_eq!(store.len(), 1);
+        assert_eq!(run(&mut store, &["delete", "configmap", "settings"]).unwrap(),
+            "configmap \"settings\" deleted\n");
+        assert!(store.is_empty());
+    }
+}
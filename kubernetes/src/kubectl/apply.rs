@@ -0,0 +1,182 @@
+/*!
+Client-side apply: the three-way merge `kubectl apply` patches live objects with, from the configuration last applied, the one being applied and the live object, and the changes the patch makes.
+
+Lists are replaced as a whole, as JSON merge patches replace them, rather than merged by the keys strategic merge patches declare for built-in kinds, such as containers by name.
+
+- Concepts <https://kubernetes.io/docs/tasks/manage-kubernetes-objects/declarative-config/#merge-patch-calculation>
+- Source <https://github.com/kubernetes/kubectl/blob/master/pkg/cmd/apply/patcher.go>
+*/
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+/// Annotation keeping the configuration last applied, to tell fields removed from it from fields set by others.
+pub const LAST_APPLIED: &str = "kubectl.kubernetes.io/last-applied-configuration";
+
+/**
+`modified` as it is recorded in [`LAST_APPLIED`]: without its own annotation and as compact JSON.
+*/
+pub fn last_applied(modified: &Value) -> String {
+    let mut modified = modified.clone();
+    if let Some(annotations) = modified.pointer_mut("/metadata/annotations")
+        .and_then(Value::as_object_mut)
+    {
+        annotations.remove(LAST_APPLIED);
+        if annotations.is_empty() {
+            modified["metadata"].as_object_mut().map(|metadata| metadata.remove("annotations"));
+        }
+    }
+    modified.to_string()
+}
+
+/// `modified` with [`LAST_APPLIED`] set to itself, as it is created or patched.
+pub fn annotate(modified: &Value) -> Value {
+    let mut annotated = modified.clone();
+    let recorded = last_applied(modified);
+    if let Some(metadata) = annotated.get_mut("metadata").and_then(Value::as_object_mut) {
+        let annotations = metadata.entry("annotations").or_insert_with(|| Value::Object(Map::new()));
+        if let Some(annotations) = annotations.as_object_mut() {
+            annotations.insert(LAST_APPLIED.to_owned(), Value::String(recorded));
+        }
+    }
+    annotated
+}
+
+/**
+The JSON merge patch from `current` to `modified`: fields of `modified` that differ from `current` are set, and fields of `original`, the configuration last applied, that `modified` no longer has are deleted. Fields only `current` has, set by others, are left alone.
+*/
+pub fn three_way_patch(original: Option<&Value>, modified: &Value, current: &Value) -> Value {
+    let mut patch = Map::new();
+    let empty = Map::new();
+    let modified_fields = modified.as_object().unwrap_or(&empty);
+    let current_fields = current.as_object().unwrap_or(&empty);
+    let original_fields = original.and_then(Value::as_object);
+    for (key, value) in modified_fields {
+        match (value, current_fields.get(key)) {
+            (Value::Object(_), Some(current @ Value::Object(_))) => {
+                let original = original_fields.and_then(|fields| fields.get(key));
+                let nested = three_way_patch(original, value, current);
+                if nested.as_object().is_some_and(|nested| !nested.is_empty()) {
+                    patch.insert(key.clone(), nested);
+                }
+            }
+            (value, Some(current)) if value == current => {}
+            (value, _) => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    for key in original_fields.into_iter().flatten().map(|(key, _)| key) {
+        if !modified_fields.contains_key(key) && current_fields.contains_key(key) {
+            patch.insert(key.clone(), Value::Null);
+        }
+    }
+    Value::Object(patch)
+}
+
+/// A field changed by a patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Dotted path of the field, with list indices as `spec.containers.0.image`.
+    pub path: String,
+    /// `None` if the field was added.
+    pub old: Option<Value>,
+    /// `None` if the field was removed.
+    pub new: Option<Value>
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, "~ {}: {} -> {}", self.path, old, new),
+            (None, Some(new)) => write!(f, "+ {}: {}", self.path, new),
+            (Some(old), None) => write!(f, "- {}: {}", self.path, old),
+            (None, None) => write!(f, "  {}", self.path)
+        }
+    }
+}
+
+/// The fields that differ between `old` and `new`, in key order, descending into objects and lists of the same length.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_into(&mut changes, String::new(), old, new);
+    changes
+}
+
+fn diff_into(changes: &mut Vec<Change>, path: String, old: &Value, new: &Value) {
+    let join = |key: &str| if path.is_empty() { key.to_owned() } else { format!("{}.{}", path, key) };
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_into(changes, join(key), old, new),
+                    (old, new) => changes.push(Change {
+                        path: join(key),
+                        old: old.cloned(),
+                        new: new.cloned()
+                    })
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) if old.len() == new.len() => {
+            for (index, (old, new)) in old.iter().zip(new).enumerate() {
+                diff_into(changes, join(&index.to_string()), old, new);
+            }
+        }
+        (old, new) if old != new => changes.push(Change {
+            path,
+            old: Some(old.clone()),
+            new: Some(new.clone())
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn records_the_configuration_applied() {
+        let modified = json!({"metadata": {"name": "web"}, "data": {"key": "value"}});
+        let annotated = annotate(&modified);
+        let recorded = annotated["metadata"]["annotations"][LAST_APPLIED].as_str().unwrap();
+        assert_eq!(serde_json::from_str::<Value>(recorded).unwrap(), modified);
+        assert_eq!(last_applied(&annotated), recorded);
+    }
+
+    #[test]
+    fn patches_three_ways() {
+        let original = json!({"spec": {"replicas": 3, "paused": false}, "data": {"a": "1"}});
+        let modified = json!({"spec": {"replicas": 5}, "data": {"a": "1"}});
+        let current = json!({
+            "spec": {"replicas": 3, "paused": false, "progressDeadlineSeconds": 600},
+            "data": {"a": "1"}
+        });
+        // Removed from the configuration, so deleted; set by the server, so kept.
+        assert_eq!(three_way_patch(Some(&original), &modified, &current),
+            json!({"spec": {"replicas": 5, "paused": null}}));
+        // Without a record nothing is known to be removed.
+        assert_eq!(three_way_patch(None, &modified, &current),
+            json!({"spec": {"replicas": 5}}));
+    }
+
+    #[test]
+    fn diffs_fields() {
+        let old = json!({"spec": {"replicas": 3, "paused": false, "ports": [80, 443]}});
+        let new = json!({"spec": {"replicas": 5, "ports": [80, 8443]}, "status": {}});
+        let changes: Vec<String> = diff(&old, &new).iter().map(Change::to_string).collect();
+        assert_eq!(changes, [
+            "- spec.paused: false",
+            "~ spec.ports.1: 443 -> 8443",
+            "~ spec.replicas: 3 -> 5",
+            "+ status: {}"
+        ]);
+    }
+}
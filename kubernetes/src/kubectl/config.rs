@@ -0,0 +1,862 @@
+/*!
+Kubeconfig files: the clusters, users and contexts clients connect with, how the files of `KUBECONFIG` merge, and the credentials a user resolves to.
+
+Files are read in JSON, the subset of YAML `kubectl config view --flatten -o json` writes.
+
+- Concepts <https://kubernetes.io/docs/concepts/configuration/organize-cluster-access-kubeconfig/>
+- Reference <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/>
+- Source <https://github.com/kubernetes/client-go/blob/master/tools/clientcmd/loader.go>
+- Source <https://github.com/kubernetes/client-go/blob/master/tools/clientcmd/client_config.go>
+*/
+
+pub mod exec;
+
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsStr,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use kfl::{errors::DecodeError, Decode, DecodeScalar};
+use serde_json::{Map, Value};
+
+use crate::{
+    authentication::client::{self, API_VERSIONS},
+    decode,
+    time::Time
+};
+use self::exec::Plugin;
+use super::DEFAULT_NAMESPACE;
+
+/// Environment variable listing the kubeconfig files to merge, separated as `PATH` is.
+pub const KUBECONFIG_ENV: &str = "KUBECONFIG";
+
+/// Kubeconfig read when neither `--kubeconfig` nor [`KUBECONFIG_ENV`] is given, relative to the home directory.
+pub const RECOMMENDED_HOME_FILE: &str = ".kube/config";
+
+/// The `oidc` auth provider, the only one still built into clients.
+pub const OIDC_AUTH_PROVIDER: &str = "oidc";
+
+/// <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/#Config>
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    #[kfl(children)]
+    pub clusters: Vec<NamedCluster>,
+    #[kfl(children)]
+    pub users: Vec<NamedAuthInfo>,
+    #[kfl(children)]
+    pub contexts: Vec<NamedContext>,
+    /// The context used when none is given with `--context`.
+    #[kfl(property, default)]
+    pub current_context: Option<String>
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/#NamedCluster>
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
+pub struct NamedCluster {
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(child, default)]
+    pub cluster: Cluster
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/#Cluster>
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
+pub struct Cluster {
+    /// Address of the API server, `https://hostname:port`.
+    #[kfl(property, default)]
+    pub server: String,
+    /// Name the server certificate is checked against instead of the host of [`server`][Self::server].
+    #[kfl(property, default)]
+    pub tls_server_name: Option<String>,
+    #[kfl(property, default)]
+    pub insecure_skip_tls_verify: bool,
+    /// Path to a PEM file of certificate authorities.
+    #[kfl(property, default)]
+    pub certificate_authority: Option<PathBuf>,
+    /// PEM-encoded certificate authorities, base64 encoded in files. Overrides [`certificate_authority`][Self::certificate_authority].
+    #[kfl(property, default)]
+    pub certificate_authority_data: Vec<u8>,
+    #[kfl(property, default)]
+    pub proxy_url: Option<String>,
+    #[kfl(property, default)]
+    pub disable_compression: bool
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/#NamedAuthInfo>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamedAuthInfo {
+    pub name: String,
+    pub user: AuthInfo
+}
+
+impl Decode for NamedAuthInfo {
+    fn decode(node: &kfl::ast::Node, ctx: &mut kfl::context::Context) -> Result<Self, DecodeError> {
+        let mut fields = decode::Fields::new(node, ctx);
+        let named_auth_info = Self {
+            name: fields.property("name")?.unwrap_or_default(),
+            user: fields.child("user")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(named_auth_info)
+    }
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/#AuthInfo>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthInfo {
+    /// Path to a PEM client certificate.
+    pub client_certificate: Option<PathBuf>,
+    /// PEM-encoded client certificate, base64 encoded in files.
+    pub client_certificate_data: Vec<u8>,
+    /// Path to the PEM key of the client certificate.
+    pub client_key: Option<PathBuf>,
+    pub client_key_data: Vec<u8>,
+    /// Bearer token.
+    pub token: Option<String>,
+    /// Path to a file of a bearer token, read whenever credentials are needed; it takes precedence over [`token`][Self::token].
+    pub token_file: Option<PathBuf>,
+    /// User to impersonate, `as` in files.
+    pub impersonate: Option<String>,
+    pub impersonate_uid: Option<String>,
+    pub impersonate_groups: Vec<String>,
+    pub impersonate_user_extra: HashMap<String, Vec<String>>,
+    /// Basic authentication.
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub auth_provider: Option<AuthProviderConfig>,
+    pub exec: Option<ExecConfig>
+}
+
+impl Decode for AuthInfo {
+    fn decode(node: &kfl::ast::Node, ctx: &mut kfl::context::Context) -> Result<Self, DecodeError> {
+        let mut fields = decode::Fields::new(node, ctx);
+        let auth_info = Self {
+            client_certificate: fields.property("client-certificate")?,
+            client_certificate_data: fields.property("client-certificate-data")?.unwrap_or_default(),
+            client_key: fields.property("client-key")?,
+            client_key_data: fields.property("client-key-data")?.unwrap_or_default(),
+            token: fields.property("token")?,
+            token_file: fields.property("token-file")?,
+            impersonate: fields.property("impersonate")?,
+            impersonate_uid: fields.property("impersonate-uid")?,
+            impersonate_groups: fields.values("impersonate-groups")?,
+            impersonate_user_extra: fields.lists("impersonate-user-extra")?,
+            username: fields.property("username")?,
+            password: fields.property("password")?,
+            auth_provider: fields.child("auth-provider")?,
+            exec: fields.child("exec")?
+        };
+        fields.finish()?;
+        Ok(auth_info)
+    }
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/#AuthProviderConfig>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthProviderConfig {
+    pub name: String,
+    pub config: HashMap<String, String>
+}
+
+impl Decode for AuthProviderConfig {
+    fn decode(node: &kfl::ast::Node, ctx: &mut kfl::context::Context) -> Result<Self, DecodeError> {
+        let mut fields = decode::Fields::new(node, ctx);
+        let auth_provider_config = Self {
+            name: fields.property("name")?.unwrap_or_default(),
+            config: fields.entries("config")?
+        };
+        fields.finish()?;
+        Ok(auth_provider_config)
+    }
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/#ExecConfig>
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecConfig {
+    /// Command to run, looked up in `PATH` unless it is a path.
+    pub command: String,
+    pub args: Vec<String>,
+    /// Added to the client's own environment.
+    pub env: Vec<ExecEnvVar>,
+    /// One of [`API_VERSIONS`], which the plugin must answer in.
+    pub api_version: String,
+    /// Shown when the command is not found.
+    pub install_hint: Option<String>,
+    /// Whether the plugin is given the [`Cluster`][client::Cluster] the credentials are for.
+    pub provide_cluster_info: bool,
+    /// Required from `client.authentication.k8s.io/v1`.
+    pub interactive_mode: Option<ExecInteractiveMode>
+}
+
+impl Decode for ExecConfig {
+    fn decode(node: &kfl::ast::Node, ctx: &mut kfl::context::Context) -> Result<Self, DecodeError> {
+        let mut fields = decode::Fields::new(node, ctx);
+        let exec_config = Self {
+            command: fields.property("command")?.unwrap_or_default(),
+            args: fields.values("args")?,
+            env: fields.children("exec-env-var")?,
+            api_version: fields.property("api-version")?.unwrap_or_default(),
+            install_hint: fields.property("install-hint")?,
+            provide_cluster_info: fields.property("provide-cluster-info")?.unwrap_or_default(),
+            interactive_mode: fields.property("interactive-mode")?
+        };
+        fields.finish()?;
+        Ok(exec_config)
+    }
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/#ExecEnvVar>
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
+pub struct ExecEnvVar {
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub value: String
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/#ExecInteractiveMode>
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
+pub enum ExecInteractiveMode {
+    /// The plugin never reads standard input.
+    Never,
+    /// The plugin reads standard input if the client has one.
+    IfAvailable,
+    /// The plugin needs standard input, and fails without one.
+    Always
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/#NamedContext>
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
+pub struct NamedContext {
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(child, default)]
+    pub context: Context
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubeconfig.v1/#Context>
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
+pub struct Context {
+    #[kfl(property, default)]
+    pub cluster: String,
+    #[kfl(property, default)]
+    pub user: String,
+    /// Namespace of commands not given `--namespace`.
+    #[kfl(property, default)]
+    pub namespace: Option<String>
+}
+
+impl Config {
+    pub fn cluster(&self, name: &str) -> Option<&Cluster> {
+        self.clusters.iter().find(|cluster| cluster.name == name).map(|named| &named.cluster)
+    }
+
+    pub fn user(&self, name: &str) -> Option<&AuthInfo> {
+        self.users.iter().find(|user| user.name == name).map(|named| &named.user)
+    }
+
+    pub fn context(&self, name: &str) -> Option<&Context> {
+        self.contexts.iter().find(|context| context.name == name).map(|named| &named.context)
+    }
+
+    /**
+    The kubeconfig files to read, in order: `explicit`, from `--kubeconfig`, alone; else the non-empty paths of `kubeconfig`, the value of [`KUBECONFIG_ENV`], without repetitions; else [`RECOMMENDED_HOME_FILE`] in `home`.
+    */
+    pub fn paths(explicit: Option<&Path>, kubeconfig: Option<&OsStr>, home: Option<&Path>)
+        -> Vec<PathBuf>
+    {
+        if let Some(explicit) = explicit {
+            return vec![explicit.to_owned()]
+        }
+        if let Some(kubeconfig) = kubeconfig.filter(|kubeconfig| !kubeconfig.is_empty()) {
+            let mut paths: Vec<PathBuf> = Vec::new();
+            for path in env::split_paths(kubeconfig) {
+                if !path.as_os_str().is_empty() && !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+            return paths
+        }
+        home.map(|home| home.join(RECOMMENDED_HOME_FILE)).into_iter().collect()
+    }
+
+    /**
+    Loads and [merges][Self::merge] the kubeconfig files clients read given `explicit`, from `--kubeconfig`, and the environment; see [`paths`][Self::paths].
+
+    Files that do not exist are skipped, except an explicit one.
+    */
+    pub fn load_default(explicit: Option<&Path>) -> Result<Self, Error> {
+        let home = env::var_os("HOME").map(PathBuf::from);
+        let paths = Self::paths(explicit, env::var_os(KUBECONFIG_ENV).as_deref(), home.as_deref());
+        let mut configs = Vec::new();
+        for path in paths {
+            match Self::load(&path) {
+                Err(Error::Io { error, .. })
+                    if error.kind() == io::ErrorKind::NotFound && explicit.is_none() => {}
+                result => configs.push(result?)
+            }
+        }
+        Ok(Self::merge(configs))
+    }
+
+    /// Reads the kubeconfig at `path`, resolving the relative paths it holds against its directory.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let input = fs::read(path).map_err(|error| Error::Io { path: path.to_owned(), error })?;
+        let mut config = Self::from_json(&input).map_err(|error| match error {
+            Error::Parse(message) => Error::Parse(format!("{}: {}", path.display(), message)),
+            error => error
+        })?;
+        if let Some(directory) = path.parent() {
+            config.resolve_paths(directory);
+        }
+        Ok(config)
+    }
+
+    /**
+    Makes the paths of files in clusters and users absolute against `directory`, that of the kubeconfig they were read from, as well as exec commands given as relative paths. Commands without a directory are left to be looked up in `PATH`.
+    */
+    pub fn resolve_paths(&mut self, directory: &Path) {
+        let resolve = |path: &mut Option<PathBuf>| if let Some(path) = path.as_mut() {
+            if path.is_relative() && !path.as_os_str().is_empty() {
+                *path = directory.join(&*path);
+            }
+        };
+        for named in &mut self.clusters {
+            resolve(&mut named.cluster.certificate_authority);
+        }
+        for named in &mut self.users {
+            let user = &mut named.user;
+            resolve(&mut user.client_certificate);
+            resolve(&mut user.client_key);
+            resolve(&mut user.token_file);
+            if let Some(exec) = user.exec.as_mut() {
+                let command = Path::new(&exec.command);
+                if command.is_relative() && command.components().count() > 1 {
+                    exec.command = directory.join(command).to_string_lossy().into_owned();
+                }
+            }
+        }
+    }
+
+    /**
+    Merges kubeconfigs as the files of `KUBECONFIG` are: the first to define a cluster, user or context by a name wins it, and the first to set the current context sets it.
+    */
+    pub fn merge(configs: impl IntoIterator<Item = Self>) -> Self {
+        let mut merged = Self::default();
+        for config in configs {
+            for named in config.clusters {
+                if merged.cluster(&named.name).is_none() {
+                    merged.clusters.push(named);
+                }
+            }
+            for named in config.users {
+                if merged.user(&named.name).is_none() {
+                    merged.users.push(named);
+                }
+            }
+            for named in config.contexts {
+                if merged.context(&named.name).is_none() {
+                    merged.contexts.push(named);
+                }
+            }
+            if merged.current_context.as_deref().is_none_or(str::is_empty) {
+                merged.current_context = config.current_context;
+            }
+        }
+        merged
+    }
+
+    /**
+    The context, cluster, user and namespace a client connects with given `overrides`, checked as clients check them before connecting.
+    */
+    pub fn resolve(&self, overrides: &Overrides) -> Result<Resolved, Error> {
+        let context_name = overrides.context.clone()
+            .or_else(|| self.current_context.clone())
+            .unwrap_or_default();
+        let context = match self.context(&context_name) {
+            Some(context) => context.clone(),
+            None if context_name.is_empty() => Context::default(),
+            None => return Err(Error::Invalid(vec![format!(
+                "context was not found for specified context: {}", context_name)]))
+        };
+        let cluster_name = overrides.cluster.clone().unwrap_or(context.cluster);
+        let user_name = overrides.user.clone().unwrap_or(context.user);
+        if context_name.is_empty() && cluster_name.is_empty() {
+            return Err(Error::Empty)
+        }
+        let mut problems = Vec::new();
+        let cluster = match self.cluster(&cluster_name) {
+            Some(cluster) if !cluster.server.is_empty() => cluster.clone(),
+            Some(_) => {
+                problems.push("cluster has no server defined".to_owned());
+                Cluster::default()
+            }
+            None => {
+                problems.push(format!("cluster \"{}\" does not exist", cluster_name));
+                Cluster::default()
+            }
+        };
+        let user = match self.user(&user_name) {
+            Some(user) => {
+                problems.extend(user.problems(&user_name));
+                user.clone()
+            }
+            None if user_name.is_empty() => AuthInfo::default(),
+            None => {
+                problems.push(format!("auth info \"{}\" does not exist", user_name));
+                AuthInfo::default()
+            }
+        };
+        if !problems.is_empty() {
+            return Err(Error::Invalid(problems))
+        }
+        Ok(Resolved {
+            namespace: overrides.namespace.clone()
+                .or(context.namespace)
+                .filter(|namespace| !namespace.is_empty())
+                .unwrap_or_else(|| DEFAULT_NAMESPACE.to_owned()),
+            context: context_name,
+            cluster_name,
+            cluster,
+            user_name,
+            user
+        })
+    }
+
+    /// Parses a kubeconfig in its JSON form.
+    pub fn from_json(input: &[u8]) -> Result<Self, Error> {
+        let value: Value = serde_json::from_slice(input)
+            .map_err(|error| Error::Parse(error.to_string()))?;
+        let object = value.as_object()
+            .ok_or_else(|| Error::Parse("expected a JSON object".to_owned()))?;
+        match object.get("kind").and_then(Value::as_str) {
+            None | Some("Config") => {}
+            Some(kind) => return Err(Error::Parse(format!("expected kind Config, got {:?}", kind)))
+        }
+        Ok(Self {
+            clusters: named(object, "clusters", "cluster", Cluster::from_json)?
+                .into_iter().map(|(name, cluster)| NamedCluster { name, cluster }).collect(),
+            users: named(object, "users", "user", AuthInfo::from_json)?
+                .into_iter().map(|(name, user)| NamedAuthInfo { name, user }).collect(),
+            contexts: named(object, "contexts", "context", Context::from_json)?
+                .into_iter().map(|(name, context)| NamedContext { name, context }).collect(),
+            current_context: Fields(object).string("current-context")?
+        })
+    }
+}
+
+impl Cluster {
+    fn from_json(fields: Fields) -> Result<Self, Error> {
+        Ok(Self {
+            server: fields.string("server")?.unwrap_or_default(),
+            tls_server_name: fields.string("tls-server-name")?,
+            insecure_skip_tls_verify: fields.bool("insecure-skip-tls-verify")?,
+            certificate_authority: fields.string("certificate-authority")?.map(PathBuf::from),
+            certificate_authority_data: fields.data("certificate-authority-data")?,
+            proxy_url: fields.string("proxy-url")?,
+            disable_compression: fields.bool("disable-compression")?
+        })
+    }
+
+    /// The certificate authorities to trust, from [`certificate_authority_data`][Self::certificate_authority_data] or else the file of [`certificate_authority`][Self::certificate_authority]; empty to trust the system's.
+    pub fn certificate_authorities(&self) -> Result<Vec<u8>, Error> {
+        if !self.certificate_authority_data.is_empty() {
+            return Ok(self.certificate_authority_data.clone())
+        }
+        self.certificate_authority.as_deref().map_or(Ok(Vec::new()), read)
+    }
+
+    /// The cluster as exec plugins are given it.
+    pub fn exec_cluster(&self) -> Result<client::Cluster, Error> {
+        Ok(client::Cluster {
+            server: self.server.clone(),
+            tls_server_name: self.tls_server_name.clone(),
+            insecure_skip_tls_verify: Some(self.insecure_skip_tls_verify),
+            certificate_authority_data: self.certificate_authorities()?,
+            proxy_url: self.proxy_url.clone(),
+            config: None
+        })
+    }
+}
+
+impl AuthInfo {
+    fn from_json(fields: Fields) -> Result<Self, Error> {
+        let auth_provider = fields.object("auth-provider")?.map(|provider| Ok::<_, Error>(AuthProviderConfig {
+            name: provider.string("name")?.unwrap_or_default(),
+            config: provider.object("config")?.map(|config| config.0.iter()
+                .map(|(key, value)| Ok((key.clone(), value.as_str().ok_or_else(|| Error::Parse(
+                    format!("auth-provider config {} is not a string", key)))?.to_owned())))
+                .collect::<Result<_, Error>>())
+                .transpose()?
+                .unwrap_or_default()
+        })).transpose()?;
+        let exec = fields.object("exec")?.map(|exec| Ok::<_, Error>(ExecConfig {
+            command: exec.string("command")?.unwrap_or_default(),
+            args: exec.strings("args")?,
+            env: exec.list("env")?.into_iter().map(|env| Ok(ExecEnvVar {
+                name: env.string("name")?.unwrap_or_default(),
+                value: env.string("value")?.unwrap_or_default()
+            })).collect::<Result<_, Error>>()?,
+            api_version: exec.string("apiVersion")?.unwrap_or_default(),
+            install_hint: exec.string("installHint")?,
+            provide_cluster_info: exec.bool("provideClusterInfo")?,
+            interactive_mode: exec.string("interactiveMode")?.filter(|mode| !mode.is_empty())
+                .map(|mode| mode.parse()).transpose()?
+        })).transpose()?;
+        let impersonate_user_extra = fields.object("as-user-extra")?.map(|extra| extra.0.keys()
+            .map(|key| Ok((key.clone(), extra.strings(key)?)))
+            .collect::<Result<_, Error>>())
+            .transpose()?
+            .unwrap_or_default();
+        Ok(Self {
+            client_certificate: fields.string("client-certificate")?.map(PathBuf::from),
+            client_certificate_data: fields.data("client-certificate-data")?,
+            client_key: fields.string("client-key")?.map(PathBuf::from),
+            client_key_data: fields.data("client-key-data")?,
+            token: fields.string("token")?,
+            token_file: fields.string("tokenFile")?.map(PathBuf::from),
+            impersonate: fields.string("as")?,
+            impersonate_uid: fields.string("as-uid")?,
+            impersonate_groups: fields.strings("as-groups")?,
+            impersonate_user_extra,
+            username: fields.string("username")?,
+            password: fields.string("password")?,
+            auth_provider,
+            exec
+        })
+    }
+
+    /// What makes the user unusable, as clients report it.
+    fn problems(&self, name: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut methods = Vec::new();
+        if self.token.as_deref().is_some_and(|token| !token.is_empty()) || self.token_file.is_some() {
+            methods.push("token");
+        }
+        if self.username.as_deref().is_some_and(|username| !username.is_empty())
+            || self.password.as_deref().is_some_and(|password| !password.is_empty())
+        {
+            methods.push("basicAuth");
+        }
+        if methods.len() > 1 {
+            problems.push(format!("more than one authentication method found for {}; found {:?}, \
+                only one is allowed", name, methods));
+        }
+        let has_certificate = self.client_certificate.is_some() || !self.client_certificate_data.is_empty();
+        let has_key = self.client_key.is_some() || !self.client_key_data.is_empty();
+        if self.client_certificate.is_some() && !self.client_certificate_data.is_empty() {
+            problems.push(format!("client-cert-data and client-cert are both specified for {}. \
+                client-cert-data will override.", name));
+        }
+        if self.client_key.is_some() && !self.client_key_data.is_empty() {
+            problems.push(format!("client-key-data and client-key are both specified for {}; \
+                client-key-data will override", name));
+        }
+        if has_certificate && !has_key {
+            problems.push(format!("client-key-data or client-key must be specified for {} to use \
+                the clientCert authentication method.", name));
+        }
+        if has_key && !has_certificate {
+            problems.push(format!("client-cert-data or client-cert must be specified for {} to use \
+                the clientCert authentication method.", name));
+        }
+        if let Some(exec) = &self.exec {
+            if self.auth_provider.is_some() {
+                problems.push(format!("authProvider cannot be provided in combination with an \
+                    exec plugin for {}", name));
+            }
+            if exec.command.is_empty() {
+                problems.push(format!("command must be specified for {} to use exec \
+                    authentication plugin", name));
+            }
+            if exec.api_version.is_empty() {
+                problems.push(format!("apiVersion must be specified for {} to use exec \
+                    authentication plugin", name));
+            } else if !API_VERSIONS.contains(&exec.api_version.as_str()) {
+                problems.push(format!("exec plugin: invalid apiVersion {:?} for {}",
+                    exec.api_version, name));
+            } else if exec.interactive_mode.is_none() && exec.api_version == API_VERSIONS[0] {
+                problems.push(format!("interactiveMode must be specified for {} to use exec \
+                    authentication plugin", name));
+            }
+        }
+        problems
+    }
+}
+
+impl Context {
+    fn from_json(fields: Fields) -> Result<Self, Error> {
+        Ok(Self {
+            cluster: fields.string("cluster")?.unwrap_or_default(),
+            user: fields.string("user")?.unwrap_or_default(),
+            namespace: fields.string("namespace")?
+        })
+    }
+}
+
+impl ExecConfig {
+    /// [`interactive_mode`][Self::interactive_mode], which defaults to `IfAvailable` before `client.authentication.k8s.io/v1`.
+    pub fn interactive_mode(&self) -> ExecInteractiveMode {
+        self.interactive_mode.unwrap_or(ExecInteractiveMode::IfAvailable)
+    }
+}
+
+impl ExecInteractiveMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Never => "Never",
+            Self::IfAvailable => "IfAvailable",
+            Self::Always => "Always"
+        }
+    }
+}
+
+impl fmt::Display for ExecInteractiveMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ExecInteractiveMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Never" => Ok(Self::Never),
+            "IfAvailable" => Ok(Self::IfAvailable),
+            "Always" => Ok(Self::Always),
+            _ => Err(Error::Parse(format!("invalid interactiveMode {:?}", s)))
+        }
+    }
+}
+
+/// `--context`, `--cluster`, `--user` and `--namespace`, overriding the current context.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Overrides {
+    pub context: Option<String>,
+    pub cluster: Option<String>,
+    pub user: Option<String>,
+    pub namespace: Option<String>
+}
+
+/// What a client connects with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved {
+    /// Empty if the cluster was given without a context.
+    pub context: String,
+    pub cluster_name: String,
+    pub cluster: Cluster,
+    /// Empty for anonymous requests.
+    pub user_name: String,
+    pub user: AuthInfo,
+    /// The namespace of the context, [`DEFAULT_NAMESPACE`] if it has none.
+    pub namespace: String
+}
+
+impl Resolved {
+    /**
+    The authenticator of the user, with its exec plugin if it has one. It runs the plugin interactively only if its configuration allows and `stdin_available`.
+
+    Keep it for as long as the client runs, since it caches what the plugin returns.
+    */
+    pub fn authenticator(&self, stdin_available: bool) -> Result<Authenticator, Error> {
+        let exec = self.user.exec.as_ref().map(|exec| Plugin::from_config(
+            exec, Some(self.cluster.exec_cluster()?), stdin_available).map_err(Error::Exec))
+            .transpose()?;
+        Ok(Authenticator { user: self.user.clone(), exec })
+    }
+}
+
+/// Credentials of a request, to send as the `Authorization` header or present as the TLS client certificate.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+    pub token: Option<String>,
+    /// Username and password.
+    pub basic: Option<(String, String)>,
+    /// PEM client certificate and its key.
+    pub client_certificate: Option<(Vec<u8>, Vec<u8>)>,
+    /// When credentials from an exec plugin expire.
+    pub expiration: Option<Time>
+}
+
+/// Resolves the credentials of a user.
+pub struct Authenticator {
+    pub user: AuthInfo,
+    exec: Option<Plugin>
+}
+
+impl Authenticator {
+    /**
+    The credentials of the user at `now`. The client certificate comes from its data or else its file; the token from the token file, read anew each time, or else the token, or else the `id-token` of the `oidc` auth provider. The exec plugin, its credentials cached until they expire, provides whatever of the token and client certificate the kubeconfig does not.
+    */
+    pub async fn credentials(&self, now: Time) -> Result<Credentials, Error> {
+        let user = &self.user;
+        let data = |data: &Vec<u8>, path: &Option<PathBuf>| -> Result<Option<Vec<u8>>, Error> {
+            if !data.is_empty() {
+                return Ok(Some(data.clone()))
+            }
+            path.as_deref().map(read).transpose()
+        };
+        let mut credentials = Credentials::default();
+        if let (Some(certificate), Some(key)) = (
+            data(&user.client_certificate_data, &user.client_certificate)?,
+            data(&user.client_key_data, &user.client_key)?
+        ) {
+            credentials.client_certificate = Some((certificate, key));
+        }
+        credentials.token = match &user.token_file {
+            Some(path) => Some(String::from_utf8_lossy(&read(path)?).trim().to_owned()),
+            None => user.token.clone().filter(|token| !token.is_empty())
+        };
+        if let Some(provider) = &user.auth_provider {
+            if provider.name != OIDC_AUTH_PROVIDER {
+                return Err(Error::AuthProvider(provider.name.clone()))
+            }
+            if credentials.token.is_none() {
+                credentials.token = provider.config.get("id-token").cloned();
+            }
+        }
+        if let (Some(username), Some(password)) = (&user.username, &user.password) {
+            credentials.basic = Some((username.clone(), password.clone()));
+        }
+        if let Some(exec) = &self.exec {
+            if credentials.token.is_none() || credentials.client_certificate.is_none() {
+                let status = exec.credentials(now).await.map_err(Error::Exec)?;
+                if credentials.token.is_none() && !status.token.is_empty() {
+                    credentials.token = Some(status.token);
+                }
+                if credentials.client_certificate.is_none() && !status.client_certificate_data.is_empty() {
+                    credentials.client_certificate = Some((
+                        status.client_certificate_data.into_bytes(),
+                        status.client_key_data.into_bytes()
+                    ));
+                }
+                credentials.expiration = status.expiration_timestamp;
+            }
+        }
+        Ok(credentials)
+    }
+
+    /// Drops the cached credentials of the exec plugin, after the server rejected them.
+    pub async fn invalidate(&self) {
+        if let Some(exec) = &self.exec {
+            exec.invalidate().await;
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|error| Error::Io { path: path.to_owned(), error })
+}
+
+/// The fields of a JSON object of a kubeconfig.
+#[derive(Clone, Copy)]
+struct Fields<'a>(&'a Map<String, Value>);
+
+impl<'a> Fields<'a> {
+    fn string(&self, name: &str) -> Result<Option<String>, Error> {
+        match self.0.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(Error::Parse(format!("{} is not a string", name)))
+        }
+    }
+
+    fn bool(&self, name: &str) -> Result<bool, Error> {
+        match self.0.get(name) {
+            None | Some(Value::Null) => Ok(false),
+            Some(Value::Bool(value)) => Ok(*value),
+            Some(_) => Err(Error::Parse(format!("{} is not a boolean", name)))
+        }
+    }
+
+    fn data(&self, name: &str) -> Result<Vec<u8>, Error> {
+        self.string(name)?.map_or(Ok(Vec::new()), |data| STANDARD.decode(data)
+            .map_err(|_| Error::Parse(format!("{} is not base64", name))))
+    }
+
+    fn object(&self, name: &str) -> Result<Option<Fields<'a>>, Error> {
+        match self.0.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::Object(object)) => Ok(Some(Fields(object))),
+            Some(_) => Err(Error::Parse(format!("{} is not an object", name)))
+        }
+    }
+
+    fn list(&self, name: &str) -> Result<Vec<Fields<'a>>, Error> {
+        match self.0.get(name) {
+            None | Some(Value::Null) => Ok(Vec::new()),
+            Some(Value::Array(items)) => items.iter().map(|item| item.as_object().map(Fields)
+                .ok_or_else(|| Error::Parse(format!("item of {} is not an object", name))))
+                .collect(),
+            Some(_) => Err(Error::Parse(format!("{} is not a list", name)))
+        }
+    }
+
+    fn strings(&self, name: &str) -> Result<Vec<String>, Error> {
+        match self.0.get(name) {
+            None | Some(Value::Null) => Ok(Vec::new()),
+            Some(Value::Array(items)) => items.iter().map(|item| item.as_str().map(str::to_owned)
+                .ok_or_else(|| Error::Parse(format!("item of {} is not a string", name))))
+                .collect(),
+            Some(_) => Err(Error::Parse(format!("{} is not a list", name)))
+        }
+    }
+}
+
+/// The entries of the list `name` of `object`, each `{"name": ..., field: {...}}`.
+fn named<T>(
+    object: &Map<String, Value>,
+    name: &str,
+    field: &str,
+    parse: impl Fn(Fields) -> Result<T, Error>
+) -> Result<Vec<(String, T)>, Error> {
+    let empty = Map::new();
+    Fields(object).list(name)?.into_iter().map(|entry| {
+        let entry_name = entry.string("name")?.unwrap_or_default();
+        let value = parse(entry.object(field)?.unwrap_or(Fields(&empty)))
+            .map_err(|error| match error {
+                Error::Parse(message) =>
+                    Error::Parse(format!("{} {:?}: {}", field, entry_name, message)),
+                error => error
+            })?;
+        Ok((entry_name, value))
+    }).collect()
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io { path: PathBuf, error: io::Error },
+    /// A kubeconfig is not valid JSON or does not have the shape of one.
+    Parse(String),
+    /// Neither a context nor a cluster was given.
+    Empty,
+    /// What makes the context unusable.
+    Invalid(Vec<String>),
+    /// An auth provider other than [`OIDC_AUTH_PROVIDER`], by name.
+    AuthProvider(String),
+    Exec(client::Error)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            Self::Parse(message) => write!(f, "error loading config file: {}", message),
+            Self::Empty => f.write_str("invalid configuration: no configuration has been provided"),
+            Self::Invalid(problems) if problems.len() == 1 =>
+                write!(f, "invalid configuration: {}", problems[0]),
+            Self::Invalid(problems) =>
+                write!(f, "invalid configuration: [{}]", problems.join(", ")),
+            Self::AuthProvider(name) => write!(f, "no Auth Provider found for name {:?}", name),
+            Self::Exec(error) => write!(f, "getting credentials: {}", error)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,179 @@
+//! The client side of exec credential plugins: running the command of a user's [`ExecConfig`] and caching the credentials it returns until they expire.
+//!
+//! Source <https://github.com/kubernetes/client-go/blob/master/plugin/pkg/client/auth/exec/exec.go>
+
+use std::{io::ErrorKind, sync::Arc};
+
+use tokio::sync::Mutex;
+
+use crate::{
+    authentication::{
+        client::{Cluster, Error, ExecCredential, ExecCredentialSpec, ExecCredentialStatus},
+        exec::{Runner, Stdin}
+    },
+    time::Time
+};
+use super::{ExecConfig, ExecInteractiveMode};
+
+/// Environment variable the [`ExecCredential`] of a request is given to plugins in.
+pub const EXEC_INFO_ENV: &str = "KUBERNETES_EXEC_INFO";
+
+/**
+A source of exec credentials.
+
+[`ExecPlugin`] runs the command of a kubeconfig; implement this trait directly to provide credentials in process. Providers may block, and are run on a thread where that is allowed.
+*/
+pub trait Provider {
+    fn provide(&self, spec: &ExecCredentialSpec) -> Result<ExecCredentialStatus, Error>;
+}
+
+/// Runs a plugin command, giving it the request in [`EXEC_INFO_ENV`] and reading the response from its stdout. Its stderr is that of this process, and so is its stdin when it is interactive, so that it can prompt the user.
+#[derive(Debug, Clone)]
+pub struct ExecPlugin {
+    pub config: ExecConfig
+}
+
+impl ExecPlugin {
+    pub fn new(config: ExecConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Provider for ExecPlugin {
+    fn provide(&self, spec: &ExecCredentialSpec) -> Result<ExecCredentialStatus, Error> {
+        let request = ExecCredential { spec: spec.clone(), status: None };
+        let mut env: Vec<_> = self.config.env.iter()
+            .map(|env| (env.name.clone(), env.value.clone()))
+            .collect();
+        env.push((EXEC_INFO_ENV.to_owned(), request.to_json(&self.config.api_version)));
+        let runner = Runner {
+            args: self.config.args.clone(),
+            env,
+            stdin: if spec.interactive { Stdin::Inherit } else { Stdin::Null },
+            inherit_stderr: true,
+            ..Runner::new(&self.config.command)
+        };
+        let output = match runner.run() {
+            Ok(output) => output,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Err(Error::Exec {
+                command: self.config.command.clone(),
+                status: None,
+                stderr: error.to_string(),
+                install_hint: self.config.install_hint.clone()
+            }),
+            Err(error) => return Err(Error::Io(format!(
+                "exec: executable {}: {}", self.config.command, error)))
+        };
+        if !output.status.success() {
+            return Err(Error::Exec {
+                command: self.config.command.clone(),
+                status: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+                install_hint: None
+            })
+        }
+        let (response, api_version) = ExecCredential::from_json(&output.stdout)?;
+        if api_version != self.config.api_version {
+            return Err(Error::UnsupportedVersion(api_version))
+        }
+        let status = response.status.ok_or_else(|| Error::InvalidResponse(
+            "exec plugin didn't return a status field".to_owned()))?;
+        status.validate()?;
+        Ok(status)
+    }
+}
+
+/// A user's plugin and the credentials it last returned.
+pub struct Plugin {
+    spec: ExecCredentialSpec,
+    provider: Arc<dyn Provider + Send + Sync>,
+    /// Held while the provider runs, so that concurrent requests wait for its credentials rather than run it again.
+    cache: Mutex<Option<ExecCredentialStatus>>
+}
+
+impl Plugin {
+    /// A plugin asking `provider` for credentials with `spec`.
+    pub fn new(
+        spec: ExecCredentialSpec,
+        provider: Arc<dyn Provider + Send + Sync>
+    ) -> Self {
+        Self { spec, provider, cache: Mutex::new(None) }
+    }
+
+    /**
+    The plugin running the command of `config`, given `cluster` if [`provide_cluster_info`][ExecConfig::provide_cluster_info] is set.
+
+    It is interactive if its [`interactive_mode`][ExecConfig::interactive_mode] allows it and `stdin_available`, and fails if the mode is `Always` but there is no standard input.
+    */
+    pub fn from_config(config: &ExecConfig, cluster: Option<Cluster>, stdin_available: bool)
+        -> Result<Self, Error>
+    {
+        let interactive = match config.interactive_mode() {
+            ExecInteractiveMode::Never => false,
+            ExecInteractiveMode::IfAvailable => stdin_available,
+            ExecInteractiveMode::Always if stdin_available => true,
+            ExecInteractiveMode::Always => return Err(Error::Interactive(config.command.clone()))
+        };
+        let spec = ExecCredentialSpec {
+            cluster: cluster.filter(|_| config.provide_cluster_info),
+            interactive
+        };
+        Ok(Self::new(spec, Arc::new(ExecPlugin::new(config.clone()))))
+    }
+
+    /**
+    The credentials of the plugin, cached until their [`expiration_timestamp`][ExecCredentialStatus::expiration_timestamp] has passed at `now`, or for as long as the plugin lives if they have none.
+
+    The provider runs on Tokio's blocking threads, so this must be called within a Tokio runtime.
+    */
+    pub async fn credentials(&self, now: Time) -> Result<ExecCredentialStatus, Error> {
+        let mut cache = self.cache.lock().await;
+        if let Some(status) = cache.as_ref().filter(|status| !status.is_expired(now)) {
+            return Ok(status.clone())
+        }
+        let (provider, spec) = (self.provider.clone(), self.spec.clone());
+        let status = tokio::task::spawn_blocking(move || provider.provide(&spec)).await
+            .map_err(|error| Error::Io(error.to_string()))??;
+        *cache = Some(status.clone());
+        Ok(status)
+    }
+
+    /// Drops the cached credentials, as clients do when the server rejects them with `401 Unauthorized`.
+    pub async fn invalidate(&self) {
+        *self.cache.lock().await = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+        time::Duration
+    };
+
+    use super::*;
+
+    /// Hands out `token-<n>` for the `n`th call, slowly.
+    #[derive(Default)]
+    struct Counter(AtomicUsize);
+
+    impl Provider for Counter {
+        fn provide(&self, _spec: &ExecCredentialSpec) -> Result<ExecCredentialStatus, Error> {
+            thread::sleep(Duration::from_millis(50));
+            let calls = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(ExecCredentialStatus { token: format!("token-{}", calls), ..Default::default() })
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_share_one_run() {
+        let plugin = Plugin::new(ExecCredentialSpec::default(), Arc::new(Counter::default()));
+        let now = Time::now();
+        let (a, b) = tokio::join!(plugin.credentials(now), plugin.credentials(now));
+        assert_eq!(a.unwrap().token, "token-1");
+        assert_eq!(b.unwrap().token, "token-1");
+        plugin.invalidate().await;
+        assert_eq!(plugin.credentials(now).await.unwrap().token, "token-2");
+    }
+}
@@ -0,0 +1,567 @@
+/*!
+Describing objects as `kubectl describe` does: their metadata, what their kind has to say about them and their events.
+
+Kinds without a describer of their own are described field by field, as custom resources are.
+
+Source <https://github.com/kubernetes/kubectl/blob/master/pkg/describe/describe.go>
+*/
+
+use serde_json::Value;
+
+use crate::time::Time;
+
+use super::{
+    apply::LAST_APPLIED,
+    printer::{self, timestamp},
+    store::{lookup, string, Store}
+};
+
+/// Aligns values of fields at the same level, as `kubectl`'s tab writer does.
+#[derive(Debug, Default)]
+struct Writer {
+    /// Level, key and value. Fields without a key continue the previous one, lines without a value are written as they are.
+    lines: Vec<(usize, String, Option<String>)>
+}
+
+impl Writer {
+    fn field(&mut self, level: usize, key: &str, value: impl Into<String>) {
+        self.lines.push((level, format!("{}:", key), Some(value.into())));
+    }
+
+    /// A field of several values, each on its own line, `<none>` if there are none.
+    fn fields(&mut self, level: usize, key: &str, values: Vec<String>) {
+        let mut values = values.into_iter();
+        self.field(level, key, values.next().unwrap_or_else(|| "<none>".to_owned()));
+        for value in values {
+            self.lines.push((level, String::new(), Some(value)));
+        }
+    }
+
+    fn line(&mut self, level: usize, text: impl Into<String>) {
+        self.lines.push((level, text.into(), None));
+    }
+
+    fn finish(self) -> String {
+        let mut widths = Vec::new();
+        for (level, key, value) in &self.lines {
+            if value.is_some() {
+                if widths.len() <= *level {
+                    widths.resize(level + 1, 0);
+                }
+                widths[*level] = widths[*level].max(key.len() + 2);
+            }
+        }
+        let mut output = String::new();
+        for (level, key, value) in self.lines {
+            output.push_str(&"  ".repeat(level));
+            match value {
+                Some(value) => {
+                    output.push_str(&format!("{:width$}", key, width = widths[level]));
+                    output.push_str(&value);
+                }
+                None => output.push_str(&key)
+            }
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// The description of `object`, with the events in `store` about it, ages taken at `now`.
+pub fn describe(object: &Value, store: &Store, now: Time) -> String {
+    let mut writer = Writer::default();
+    writer.field(0, "Name", string(object, "metadata.name"));
+    if let Some(namespace) = lookup(object, "metadata.namespace").and_then(Value::as_str) {
+        writer.field(0, "Namespace", namespace);
+    }
+    let kind = string(object, "kind");
+    match kind {
+        "Pod" => pod(&mut writer, object, now),
+        "Deployment" | "ReplicaSet" | "StatefulSet" | "DaemonSet" =>
+            workload(&mut writer, kind, object),
+        "Service" => service(&mut writer, object),
+        "ConfigMap" => config_map(&mut writer, object),
+        "Secret" => secret(&mut writer, object),
+        _ => generic(&mut writer, object)
+    }
+    events(&mut writer, object, store, now);
+    writer.finish()
+}
+
+fn metadata(writer: &mut Writer, object: &Value) {
+    writer.fields(0, "Labels", pairs(lookup(object, "metadata.labels"), "=", &[]));
+    writer.fields(0, "Annotations",
+        pairs(lookup(object, "metadata.annotations"), ": ", &[LAST_APPLIED]));
+}
+
+fn pod(writer: &mut Writer, object: &Value, now: Time) {
+    writer.field(0, "Priority", lookup(object, "spec.priority").and_then(Value::as_i64)
+        .unwrap_or_default().to_string());
+    writer.field(0, "Service Account", match string(object, "spec.serviceAccountName") {
+        "" => "default",
+        account => account
+    });
+    writer.field(0, "Node", match (string(object, "spec.nodeName"), string(object, "status.hostIP")) {
+        ("", _) => "<none>".to_owned(),
+        (node, "") => node.to_owned(),
+        (node, ip) => format!("{}/{}", node, ip)
+    });
+    if let Some(start) = timestamp(object, "status.startTime") {
+        writer.field(0, "Start Time", start.to_string());
+    }
+    metadata(writer, object);
+    let status = match timestamp(object, "metadata.deletionTimestamp") {
+        Some(time) => format!("Terminating (lasts {})", printer::human_duration(
+            now.0.duration_since(time.0).unwrap_or_default())),
+        None => string(object, "status.phase").to_owned()
+    };
+    writer.field(0, "Status", status);
+    for (key, path) in [("Reason", "status.reason"), ("Message", "status.message")] {
+        if !string(object, path).is_empty() {
+            writer.field(0, key, string(object, path));
+        }
+    }
+    writer.field(0, "IP", string(object, "status.podIP"));
+    writer.fields(0, "IPs", array(object, "status.podIPs").iter()
+        .map(|ip| format!("IP: {}", string(ip, "ip")))
+        .collect());
+    if let Some(owner) = array(object, "metadata.ownerReferences").iter()
+        .find(|owner| lookup(owner, "controller").and_then(Value::as_bool) == Some(true))
+    {
+        writer.field(0, "Controlled By", format!("{}/{}", string(owner, "kind"),
+            string(owner, "name")));
+    }
+    for (title, containers, statuses) in [
+        ("Init Containers", "spec.initContainers", "status.initContainerStatuses"),
+        ("Containers", "spec.containers", "status.containerStatuses")
+    ] {
+        let containers = array(object, containers);
+        if containers.is_empty() && title == "Init Containers" {
+            continue
+        }
+        writer.line(0, format!("{}:", title));
+        for container in containers {
+            let status = array(object, statuses).iter()
+                .find(|status| string(status, "name") == string(container, "name"));
+            describe_container(writer, container, status);
+        }
+    }
+    let conditions = array(object, "status.conditions");
+    if !conditions.is_empty() {
+        writer.line(0, "Conditions:");
+        let table = printer::Table {
+            headers: vec!["Type".to_owned(), "Status".to_owned()],
+            rows: conditions.iter()
+                .map(|condition| vec![string(condition, "type").to_owned(),
+                    string(condition, "status").to_owned()])
+                .collect()
+        };
+        for line in table.to_string().lines() {
+            writer.line(1, line);
+        }
+    }
+    let volumes = array(object, "spec.volumes");
+    if volumes.is_empty() {
+        writer.field(0, "Volumes", "<none>");
+    } else {
+        writer.line(0, "Volumes:");
+        for volume in volumes {
+            writer.line(1, format!("{}:", string(volume, "name")));
+            let source = volume.as_object().into_iter().flatten()
+                .find(|(key, _)| key.as_str() != "name");
+            if let Some((r#type, source)) = source {
+                writer.field(2, "Type", format!("{} ({})", title(r#type), source_summary(source)));
+            }
+        }
+    }
+    writer.fields(0, "Node-Selectors", pairs(lookup(object, "spec.nodeSelector"), "=", &[]));
+    writer.fields(0, "Tolerations", array(object, "spec.tolerations").iter().map(|toleration| {
+        let mut text = string(toleration, "key").to_owned();
+        match string(toleration, "value") {
+            "" => {}
+            value => text.push_str(&format!("={}", value))
+        }
+        match string(toleration, "effect") {
+            "" => {}
+            effect => text.push_str(&format!(":{}", effect))
+        }
+        if string(toleration, "operator") == "Exists" && text.is_empty() {
+            text.push_str("op=Exists");
+        }
+        if let Some(seconds) = lookup(toleration, "tolerationSeconds").and_then(Value::as_i64) {
+            text.push_str(&format!(" for {}s", seconds));
+        }
+        text
+    }).collect());
+}
+
+fn describe_container(writer: &mut Writer, container: &Value, status: Option<&Value>) {
+    writer.line(1, format!("{}:", string(container, "name")));
+    if let Some(status) = status {
+        writer.field(2, "Container ID", string(status, "containerID"));
+    }
+    writer.field(2, "Image", string(container, "image"));
+    if let Some(status) = status {
+        writer.field(2, "Image ID", string(status, "imageID"));
+    }
+    let ports = array(container, "ports");
+    let port = |field: &str| ports.iter().map(|port| format!("{}/{}",
+        lookup(port, field).and_then(Value::as_i64).unwrap_or_default(),
+        match string(port, "protocol") {
+            "" => "TCP",
+            protocol => protocol
+        })).collect::<Vec<_>>().join(", ");
+    writer.field(2, if ports.len() > 1 { "Ports" } else { "Port" },
+        if ports.is_empty() { "<none>".to_owned() } else { port("containerPort") });
+    writer.field(2, if ports.len() > 1 { "Host Ports" } else { "Host Port" },
+        if ports.is_empty() { "<none>".to_owned() } else { port("hostPort") });
+    for (key, path) in [("Command", "command"), ("Args", "args")] {
+        let values = array(container, path);
+        if !values.is_empty() {
+            writer.line(2, format!("{}:", key));
+            for value in values {
+                writer.line(3, value.as_str().unwrap_or_default());
+            }
+        }
+    }
+    if let Some(status) = status {
+        for (key, path) in [("State", "state"), ("Last State", "lastState")] {
+            let Some((state, details)) = lookup(status, path).and_then(Value::as_object)
+                .and_then(|state| state.iter().next())
+            else {
+                if key == "State" {
+                    writer.field(2, key, "Waiting");
+                }
+                continue
+            };
+            writer.field(2, key, title(state));
+            for (key, path) in [
+                ("Reason", "reason"), ("Message", "message"), ("Exit Code", "exitCode"),
+                ("Signal", "signal"), ("Started", "startedAt"), ("Finished", "finishedAt")
+            ] {
+                match lookup(details, path) {
+                    Some(Value::String(value)) => writer.field(3, key, value.as_str()),
+                    Some(Value::Number(value)) => writer.field(3, key, value.to_string()),
+                    _ => {}
+                }
+            }
+        }
+        writer.field(2, "Ready", if lookup(status, "ready").and_then(Value::as_bool)
+            == Some(true) { "True" } else { "False" });
+        writer.field(2, "Restart Count", lookup(status, "restartCount").and_then(Value::as_i64)
+            .unwrap_or_default().to_string());
+    }
+    for (key, path) in [("Limits", "resources.limits"), ("Requests", "resources.requests")] {
+        let resources = pairs(lookup(container, path), ":", &[]);
+        if !resources.is_empty() {
+            writer.line(2, format!("{}:", key));
+            for resource in resources {
+                let (name, quantity) = resource.split_once(':').unwrap_or_default();
+                writer.field(3, name, quantity);
+            }
+        }
+    }
+    let environment = array(container, "env");
+    if environment.is_empty() {
+        writer.field(2, "Environment", "<none>");
+    } else {
+        writer.line(2, "Environment:");
+        for variable in environment {
+            let value = match lookup(variable, "valueFrom") {
+                Some(from) => source_summary(from),
+                None => string(variable, "value").to_owned()
+            };
+            writer.field(3, string(variable, "name"), value);
+        }
+    }
+    writer.fields(2, "Mounts", array(container, "volumeMounts").iter().map(|mount| {
+        format!("{} from {} ({})", string(mount, "mountPath"), string(mount, "name"),
+            if lookup(mount, "readOnly").and_then(Value::as_bool) == Some(true) { "ro" }
+            else { "rw" })
+    }).collect());
+}
+
+fn workload(writer: &mut Writer, kind: &str, object: &Value) {
+    if let Some(created) = timestamp(object, "metadata.creationTimestamp") {
+        writer.field(0, "CreationTimestamp", created.to_string());
+    }
+    metadata(writer, object);
+    let selector = lookup(object, "spec.selector.matchLabels");
+    writer.field(0, "Selector", pairs(selector, "=", &[]).join(","));
+    let int = |path: &str| lookup(object, path).and_then(Value::as_i64).unwrap_or_default();
+    let desired = lookup(object, "spec.replicas").and_then(Value::as_i64).unwrap_or(1);
+    match kind {
+        "Deployment" => {
+            writer.field(0, "Replicas", format!(
+                "{} desired | {} updated | {} total | {} available | {} unavailable",
+                desired, int("status.updatedReplicas"), int("status.replicas"),
+                int("status.availableReplicas"), int("status.unavailableReplicas")));
+            let strategy = match string(object, "spec.strategy.type") {
+                "" => "RollingUpdate",
+                strategy => strategy
+            };
+            writer.field(0, "StrategyType", strategy);
+            writer.field(0, "MinReadySeconds", int("spec.minReadySeconds").to_string());
+            if strategy == "RollingUpdate" {
+                let value = |path: &str| match lookup(object, path) {
+                    Some(Value::String(value)) => value.clone(),
+                    Some(Value::Number(value)) => value.to_string(),
+                    _ => "25%".to_owned()
+                };
+                writer.field(0, "RollingUpdateStrategy", format!(
+                    "{} max unavailable, {} max surge",
+                    value("spec.strategy.rollingUpdate.maxUnavailable"),
+                    value("spec.strategy.rollingUpdate.maxSurge")));
+            }
+        }
+        "DaemonSet" => {
+            writer.field(0, "Desired Number of Nodes Scheduled",
+                int("status.desiredNumberScheduled").to_string());
+            writer.field(0, "Current Number of Nodes Scheduled",
+                int("status.currentNumberScheduled").to_string());
+            writer.field(0, "Number of Nodes Scheduled with Up-to-date Pods",
+                int("status.updatedNumberScheduled").to_string());
+            writer.field(0, "Number of Nodes Scheduled with Available Pods",
+                int("status.numberAvailable").to_string());
+            writer.field(0, "Number of Nodes Misscheduled",
+                int("status.numberMisscheduled").to_string());
+        }
+        _ => {
+            writer.field(0, "Replicas", format!("{} current / {} desired",
+                int("status.replicas"), desired));
+        }
+    }
+    writer.line(0, "Pod Template:");
+    writer.fields(1, "Labels", pairs(lookup(object, "spec.template.metadata.labels"), "=", &[]));
+    writer.line(1, "Containers:");
+    for container in array(object, "spec.template.spec.containers") {
+        let mut nested = Writer::default();
+        describe_container(&mut nested, container, None);
+        for (level, key, value) in nested.lines {
+            writer.lines.push((level + 1, key, value));
+        }
+    }
+    let conditions = array(object, "status.conditions");
+    if !conditions.is_empty() {
+        writer.line(0, "Conditions:");
+        let table = printer::Table {
+            headers: vec!["Type".to_owned(), "Status".to_owned(), "Reason".to_owned()],
+            rows: conditions.iter().map(|condition| vec![
+                string(condition, "type").to_owned(),
+                string(condition, "status").to_owned(),
+                string(condition, "reason").to_owned()
+            ]).collect()
+        };
+        for line in table.to_string().lines() {
+            writer.line(1, line);
+        }
+    }
+}
+
+fn service(writer: &mut Writer, object: &Value) {
+    metadata(writer, object);
+    writer.field(0, "Selector", pairs(lookup(object, "spec.selector"), "=", &[]).join(","));
+    writer.field(0, "Type", match string(object, "spec.type") {
+        "" => "ClusterIP",
+        r#type => r#type
+    });
+    if !string(object, "spec.ipFamilyPolicy").is_empty() {
+        writer.field(0, "IP Family Policy", string(object, "spec.ipFamilyPolicy"));
+    }
+    writer.field(0, "IP", match string(object, "spec.clusterIP") {
+        "" => "<none>",
+        ip => ip
+    });
+    for port in array(object, "spec.ports") {
+        let protocol = match string(port, "protocol") {
+            "" => "TCP",
+            protocol => protocol
+        };
+        let name = match string(port, "name") {
+            "" => "<unset>",
+            name => name
+        };
+        let number = lookup(port, "port").and_then(Value::as_i64).unwrap_or_default();
+        writer.field(0, "Port", format!("{}  {}/{}", name, number, protocol));
+        let target = match lookup(port, "targetPort") {
+            Some(Value::String(target)) => target.clone(),
+            Some(Value::Number(target)) => target.to_string(),
+            _ => number.to_string()
+        };
+        writer.field(0, "TargetPort", format!("{}/{}", target, protocol));
+        if let Some(node_port) = lookup(port, "nodePort").and_then(Value::as_i64) {
+            writer.field(0, "NodePort", format!("{}  {}/{}", name, node_port, protocol));
+        }
+    }
+    writer.field(0, "Session Affinity", match string(object, "spec.sessionAffinity") {
+        "" => "None",
+        affinity => affinity
+    });
+}
+
+fn config_map(writer: &mut Writer, object: &Value) {
+    metadata(writer, object);
+    writer.line(0, "");
+    writer.line(0, "Data");
+    writer.line(0, "====");
+    for (key, value) in lookup(object, "data").and_then(Value::as_object).into_iter().flatten() {
+        writer.line(0, format!("{}:", key));
+        writer.line(0, "----");
+        writer.line(0, value.as_str().unwrap_or_default());
+        writer.line(0, "");
+    }
+    writer.line(0, "");
+    writer.line(0, "BinaryData");
+    writer.line(0, "====");
+    for (key, value) in lookup(object, "binaryData").and_then(Value::as_object).into_iter()
+        .flatten()
+    {
+        let bytes = value.as_str().unwrap_or_default().len() / 4 * 3;
+        writer.line(0, format!("{}: {} bytes", key, bytes));
+    }
+    writer.line(0, "");
+}
+
+fn secret(writer: &mut Writer, object: &Value) {
+    metadata(writer, object);
+    writer.line(0, "");
+    writer.field(0, "Type", string(object, "type"));
+    writer.line(0, "");
+    writer.line(0, "Data");
+    writer.line(0, "====");
+    for (key, value) in lookup(object, "data").and_then(Value::as_object).into_iter().flatten() {
+        // Values are not shown, only their decoded length.
+        let encoded = value.as_str().unwrap_or_default();
+        let padding = encoded.bytes().rev().take_while(|byte| *byte == b'=').count();
+        writer.field(0, key, format!("{} bytes", encoded.len() / 4 * 3 - padding));
+    }
+}
+
+fn generic(writer: &mut Writer, object: &Value) {
+    metadata(writer, object);
+    writer.field(0, "API Version", string(object, "apiVersion"));
+    writer.field(0, "Kind", string(object, "kind"));
+    for (key, value) in object.as_object().into_iter().flatten() {
+        if !["apiVersion", "kind", "metadata"].contains(&key.as_str()) {
+            field_tree(writer, 0, &title(key), value);
+        }
+    }
+}
+
+/// Writes `value` under `key`, maps and lists nested a level deeper.
+fn field_tree(writer: &mut Writer, level: usize, key: &str, value: &Value) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            writer.line(level, format!("{}:", key));
+            for (key, value) in map {
+                field_tree(writer, level + 1, &title(key), value);
+            }
+        }
+        Value::Array(items) if items.iter().any(|item| item.is_object() || item.is_array()) => {
+            writer.line(level, format!("{}:", key));
+            for item in items {
+                match item {
+                    Value::Object(map) => for (key, value) in map {
+                        field_tree(writer, level + 1, &title(key), value);
+                    },
+                    item => field_tree(writer, level + 1, "", item)
+                }
+            }
+        }
+        Value::Array(items) => {
+            writer.line(level, format!("{}:", key));
+            for item in items {
+                writer.line(level + 1, scalar(item));
+            }
+        }
+        value => writer.field(level, key, scalar(value))
+    }
+}
+
+fn events(writer: &mut Writer, object: &Value, store: &Store, now: Time) {
+    let uid = string(object, "metadata.uid");
+    let name = string(object, "metadata.name");
+    let kind = string(object, "kind");
+    let namespace = lookup(object, "metadata.namespace").and_then(Value::as_str);
+    let mut events: Vec<&Value> = store.list("Event", None).map(|(_, event)| event)
+        .filter(|event| {
+            let involved = lookup(event, "involvedObject").or_else(|| lookup(event, "regarding"));
+            involved.is_some_and(|involved| if uid.is_empty() || string(involved, "uid").is_empty() {
+                string(involved, "name") == name && string(involved, "kind") == kind
+                    && lookup(involved, "namespace").and_then(Value::as_str) == namespace
+            } else {
+                string(involved, "uid") == uid
+            })
+        })
+        .collect();
+    if events.is_empty() {
+        writer.field(0, "Events", "<none>");
+        return
+    }
+    events.sort_by_key(|event| last_seen(event));
+    writer.line(0, "Events:");
+    let table = printer::Table {
+        headers: ["Type", "Reason", "Age", "From", "Message"].map(str::to_owned).to_vec(),
+        rows: events.iter().map(|event| {
+            let from = match string(event, "source.component") {
+                "" => string(event, "reportingController"),
+                component => component
+            };
+            let age = last_seen(event).map_or("<unknown>".to_owned(), |time| {
+                printer::human_duration(now.0.duration_since(time.0).unwrap_or_default())
+            });
+            let message = match string(event, "message") {
+                "" => string(event, "note"),
+                message => message
+            };
+            vec![string(event, "type").to_owned(), string(event, "reason").to_owned(), age,
+                from.to_owned(), message.trim().to_owned()]
+        }).collect()
+    };
+    for line in table.to_string().lines() {
+        writer.line(1, line);
+    }
+}
+
+fn last_seen(event: &Value) -> Option<Time> {
+    timestamp(event, "lastTimestamp")
+        .or_else(|| timestamp(event, "eventTime"))
+        .or_else(|| timestamp(event, "metadata.creationTimestamp"))
+}
+
+fn array<'a>(object: &'a Value, path: &str) -> &'a [Value] {
+    lookup(object, path).and_then(Value::as_array).map_or(&[], Vec::as_slice)
+}
+
+/// `<key><separator><value>` of a map, sorted by key, without `excluded` keys.
+fn pairs(map: Option<&Value>, separator: &str, excluded: &[&str]) -> Vec<String> {
+    map.and_then(Value::as_object).into_iter().flatten()
+        .filter(|(key, _)| !excluded.contains(&key.as_str()))
+        .map(|(key, value)| format!("{}{}{}", key, separator, scalar(value)))
+        .collect()
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Null => "<nil>".to_owned(),
+        value => value.to_string()
+    }
+}
+
+/// A field name as a title, `hostPath` as `HostPath`.
+fn title(field: &str) -> String {
+    let mut chars = field.chars();
+    chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+}
+
+/// A one-line summary of a volume or environment source, its fields as `key=value`.
+fn source_summary(source: &Value) -> String {
+    match source {
+        Value::Object(map) => map.iter().map(|(key, value)| match value {
+            Value::Object(_) => format!("{}{{{}}}", key, source_summary(value)),
+            value => format!("{}={}", key, scalar(value))
+        }).collect::<Vec<_>>().join(", "),
+        value => scalar(value)
+    }
+}
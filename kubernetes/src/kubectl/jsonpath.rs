@@ -0,0 +1,721 @@
+/*!
+The JSONPath dialect of Kubernetes: templates of `kubectl -o jsonpath`, the columns of `-o custom-columns` and the `additionalPrinterColumns` of custom resources.
+
+Templates are text interleaved with actions in braces: paths, quoted strings as `{"\n"}`, and `{range <path>}`...`{end}` blocks, which render what they enclose once for every value the path leads to, paths within starting at that value.
+
+Paths start at the current value, `@`, which is optional, or at the root, `$`, and go through:
+
+- `.field` or `['field']`, with `\.` for a dot within a field name;
+- `[index]`, negative from the end, or `[start:end:step]`, as Python slices;
+- `.*` or `[*]`, every item of an array or value of an object;
+- `..`, the value and everything beneath it, as in `..image`;
+- `['a', 'b']` or `[0, 2]`, each of several;
+- `[?(<filter>)]`, the items of an array for which `@.path <op> <value>` holds, comparing with `==`, `!=`, `<`, `<=`, `>` or `>=` against a path, string, number, boolean or `null`, or for which `@.path` leads somewhere.
+
+Fields missing from an object render as nothing, as with `--allow-missing-template-keys`.
+
+- Reference <https://kubernetes.io/docs/reference/kubectl/jsonpath/>
+- Reference <https://kubernetes.io/docs/reference/kubectl/#custom-columns>
+- Source <https://github.com/kubernetes/client-go/blob/master/util/jsonpath/parser.go>
+*/
+
+use std::{
+    cmp::Ordering,
+    str::FromStr
+};
+
+use serde_json::Value;
+
+use super::Error;
+
+/// A step of a field path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Field(String),
+    /// Negative indices count from the end.
+    Index(i64),
+    /// Items from `start` up to `end` by `step`, negative bounds from the end, as Python slices.
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        /// Not 0; 1 if `None`.
+        step: Option<i64>
+    },
+    /// Every item of an array or value of an object.
+    Wildcard,
+    /// The value and every value beneath it, depth first, as `..` leads to.
+    Descendants,
+    /// What each step leads to, in order, as `['a', 'b']`.
+    Union(Vec<Step>),
+    /// The items of an array for which the filter holds.
+    Filter(Filter)
+}
+
+impl Step {
+    /// The values the step leads to from `value`, paths in filters starting at `root` if absolute.
+    fn apply<'a>(&self, root: &'a Value, value: &'a Value) -> Vec<&'a Value> {
+        match (self, value) {
+            (Self::Field(name), Value::Object(fields)) => fields.get(name).into_iter().collect(),
+            (Self::Index(index), Value::Array(items)) => {
+                let index = if *index < 0 { items.len() as i64 + index } else { *index };
+                usize::try_from(index).ok().and_then(|index| items.get(index))
+                    .into_iter().collect()
+            }
+            (Self::Slice { start, end, step }, Value::Array(items)) =>
+                slice(items.len(), *start, *end, step.unwrap_or(1)).into_iter()
+                    .map(|index| &items[index])
+                    .collect(),
+            (Self::Wildcard, Value::Array(items)) => items.iter().collect(),
+            (Self::Wildcard, Value::Object(fields)) => fields.values().collect(),
+            (Self::Descendants, value) => {
+                let mut values = Vec::new();
+                descendants(value, &mut values);
+                values
+            }
+            (Self::Union(steps), value) => steps.iter().flat_map(|step| step.apply(root, value)).collect(),
+            (Self::Filter(filter), Value::Array(items)) => items.iter()
+                .filter(|item| filter.matches(root, item))
+                .collect(),
+            _ => Vec::new()
+        }
+    }
+}
+
+/// The indices of `[start:end:step]` of an array of `len` items.
+fn slice(len: usize, start: Option<i64>, end: Option<i64>, step: i64) -> Vec<usize> {
+    let len = len as i64;
+    let bound = |index: i64, low: i64, high: i64|
+        (if index < 0 { index + len } else { index }).clamp(low, high);
+    let mut indices = Vec::new();
+    if step > 0 {
+        let mut index = start.map_or(0, |start| bound(start, 0, len));
+        let end = end.map_or(len, |end| bound(end, 0, len));
+        while index < end {
+            indices.push(index as usize);
+            // Steps past either end may overflow, where there is nothing left to take.
+            let Some(next) = index.checked_add(step) else {
+                break
+            };
+            index = next;
+        }
+    } else if step < 0 {
+        let mut index = start.map_or(len - 1, |start| bound(start, -1, len - 1));
+        let end = end.map_or(-1, |end| bound(end, -1, len - 1));
+        while index > end {
+            indices.push(index as usize);
+            let Some(next) = index.checked_add(step) else {
+                break
+            };
+            index = next;
+        }
+    }
+    indices
+}
+
+fn descendants<'a>(value: &'a Value, values: &mut Vec<&'a Value>) {
+    values.push(value);
+    match value {
+        Value::Array(items) => for item in items {
+            descendants(item, values);
+        },
+        Value::Object(fields) => for field in fields.values() {
+            descendants(field, values);
+        },
+        _ => {}
+    }
+}
+
+/// The condition of `[?(...)]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    pub left: Operand,
+    /// Without one, the filter holds if `left` leads somewhere.
+    pub comparison: Option<(Operator, Operand)>
+}
+
+impl Filter {
+    /// Whether the filter holds of `item`: whether any value `left` leads to compares as asked with any `right` leads to.
+    fn matches(&self, root: &Value, item: &Value) -> bool {
+        let left = self.left.values(root, item);
+        match &self.comparison {
+            None => !left.is_empty(),
+            Some((operator, right)) => {
+                let right = right.values(root, item);
+                left.iter().any(|left| right.iter().any(|right| operator.compare(left, right)))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    /// From the item filtered, `@`, or the root, `$`.
+    Path(Path),
+    Literal(Value)
+}
+
+impl Operand {
+    fn values<'a>(&'a self, root: &'a Value, item: &'a Value) -> Vec<&'a Value> {
+        match self {
+            Self::Path(path) => path.evaluate_at(root, item),
+            Self::Literal(literal) => vec![literal]
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual
+}
+
+impl Operator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">="
+        }
+    }
+
+    /// Numbers compare as numbers and strings as strings; other values are only equal or not.
+    fn compare(&self, left: &Value, right: &Value) -> bool {
+        let ordering = match (left, right) {
+            (Value::Number(left), Value::Number(right)) => left.as_f64().partial_cmp(&right.as_f64()),
+            (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+            (left, right) => (left == right).then_some(Ordering::Equal)
+        };
+        match self {
+            Self::Equal => ordering == Some(Ordering::Equal),
+            Self::NotEqual => ordering != Some(Ordering::Equal),
+            Self::Less => ordering == Some(Ordering::Less),
+            Self::LessOrEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Self::Greater => ordering == Some(Ordering::Greater),
+            Self::GreaterOrEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+        }
+    }
+}
+
+/// A field path, as `.spec.containers[*].image`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Path {
+    /// Whether the path starts at the root, `$`, rather than at the current value.
+    pub absolute: bool,
+    pub steps: Vec<Step>
+}
+
+impl Path {
+    /// The values the path leads to from `root`, none if it is missing.
+    pub fn evaluate<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        self.evaluate_at(root, root)
+    }
+
+    /// The values the path leads to from `current`, or from `root` if absolute.
+    pub fn evaluate_at<'a>(&self, root: &'a Value, current: &'a Value) -> Vec<&'a Value> {
+        let start = if self.absolute { root } else { current };
+        self.steps.iter().fold(vec![start], |values, step| {
+            values.into_iter().flat_map(|value| step.apply(root, value)).collect()
+        })
+    }
+}
+
+impl FromStr for Path {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s.trim());
+        let path = parser.path()?;
+        parser.end()?;
+        Ok(path)
+    }
+}
+
+/// Parses paths, quoted strings and filters, left to right.
+struct Parser<'a> {
+    source: &'a str,
+    rest: &'a str
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self { source, rest: source }
+    }
+
+    fn invalid(&self, message: &str) -> Error {
+        Error::Template(format!("{} at {} in \"{}\"", message, self.source.len() - self.rest.len(),
+            self.source))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    /// Consumes `prefix` if the rest starts with it.
+    fn eat(&mut self, prefix: &str) -> bool {
+        match self.rest.strip_prefix(prefix) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false
+        }
+    }
+
+    fn expect(&mut self, prefix: &str) -> Result<(), Error> {
+        if self.eat(prefix) {
+            Ok(())
+        } else {
+            Err(self.invalid(&format!("expected '{}'", prefix)))
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn end(&self) -> Result<(), Error> {
+        match self.peek() {
+            None => Ok(()),
+            Some(character) => Err(self.invalid(&format!("unrecognised character '{}'", character)))
+        }
+    }
+
+    fn path(&mut self) -> Result<Path, Error> {
+        let absolute = self.eat("$");
+        if !absolute {
+            self.eat("@");
+        }
+        let mut steps = Vec::new();
+        loop {
+            if self.eat("..") {
+                steps.push(Step::Descendants);
+                if self.peek() == Some('[') {
+                    continue
+                }
+                if self.eat("*") {
+                    steps.push(Step::Wildcard);
+                } else {
+                    let name = self.name();
+                    if name.is_empty() {
+                        return Err(self.invalid("expected a field after '..'"))
+                    }
+                    steps.push(Step::Field(name));
+                }
+            } else if self.eat(".") {
+                if self.eat("*") {
+                    steps.push(Step::Wildcard);
+                } else {
+                    // A lone `.` is the current value.
+                    let name = self.name();
+                    if !name.is_empty() {
+                        steps.push(Step::Field(name));
+                    }
+                }
+            } else if self.eat("[") {
+                steps.push(self.subscript()?);
+            } else {
+                break
+            }
+        }
+        Ok(Path { absolute, steps })
+    }
+
+    /// A field name after `.`, up to the next step, space or operator.
+    fn name(&mut self) -> String {
+        let mut name = String::new();
+        let mut characters = self.rest.char_indices();
+        let mut end = self.rest.len();
+        while let Some((index, character)) = characters.next() {
+            match character {
+                '\\' => name.extend(characters.next().map(|(_, escaped)| escaped)),
+                character if character.is_whitespace() || ".[]()=!<>,'\"{}".contains(character) => {
+                    end = index;
+                    break
+                }
+                character => name.push(character)
+            }
+        }
+        self.rest = &self.rest[end..];
+        name
+    }
+
+    /// What is within `[...]`, the `[` consumed.
+    fn subscript(&mut self) -> Result<Step, Error> {
+        self.skip_spaces();
+        let step = if self.eat("?") {
+            self.skip_spaces();
+            self.expect("(")?;
+            let filter = self.filter()?;
+            self.skip_spaces();
+            self.expect(")")?;
+            Step::Filter(filter)
+        } else if self.eat("*") {
+            Step::Wildcard
+        } else {
+            let mut steps = vec![self.selector()?];
+            loop {
+                self.skip_spaces();
+                if !self.eat(",") {
+                    break
+                }
+                self.skip_spaces();
+                steps.push(self.selector()?);
+            }
+            if steps.len() == 1 {
+                steps.remove(0)
+            } else {
+                Step::Union(steps)
+            }
+        };
+        self.skip_spaces();
+        self.expect("]")?;
+        Ok(step)
+    }
+
+    /// A quoted field, an index or a slice.
+    fn selector(&mut self) -> Result<Step, Error> {
+        if matches!(self.peek(), Some('\'' | '"')) {
+            return Ok(Step::Field(self.string()?))
+        }
+        let start = self.integer()?;
+        if !self.eat(":") {
+            return start.map(Step::Index).ok_or_else(|| self.invalid("expected an index"))
+        }
+        let end = self.integer()?;
+        let step = if self.eat(":") { self.integer()? } else { None };
+        if step == Some(0) {
+            return Err(self.invalid("the step of a slice cannot be 0"))
+        }
+        Ok(Step::Slice { start, end, step })
+    }
+
+    fn integer(&mut self) -> Result<Option<i64>, Error> {
+        self.skip_spaces();
+        let end = self.rest.char_indices()
+            .find(|(index, character)| !(character.is_ascii_digit() || *index == 0 && *character == '-'))
+            .map_or(self.rest.len(), |(index, _)| index);
+        if end == 0 {
+            return Ok(None)
+        }
+        let integer = self.rest[..end].parse().map_err(|_| self.invalid("invalid integer"))?;
+        self.rest = &self.rest[end..];
+        self.skip_spaces();
+        Ok(Some(integer))
+    }
+
+    /// A string in single or double quotes, with `\n`, `\t`, `\r` and backslashes before other characters unescaped.
+    fn string(&mut self) -> Result<String, Error> {
+        let quote = self.peek().filter(|quote| matches!(quote, '\'' | '"'))
+            .ok_or_else(|| self.invalid("expected a quoted string"))?;
+        let mut string = String::new();
+        let mut characters = self.rest.char_indices().skip(1);
+        while let Some((index, character)) = characters.next() {
+            match character {
+                '\\' => match characters.next() {
+                    Some((_, 'n')) => string.push('\n'),
+                    Some((_, 't')) => string.push('\t'),
+                    Some((_, 'r')) => string.push('\r'),
+                    Some((_, escaped)) => string.push(escaped),
+                    None => break
+                },
+                character if character == quote => {
+                    self.rest = &self.rest[index + 1..];
+                    return Ok(string)
+                }
+                character => string.push(character)
+            }
+        }
+        Err(self.invalid("unterminated string"))
+    }
+
+    fn filter(&mut self) -> Result<Filter, Error> {
+        self.skip_spaces();
+        let left = self.operand()?;
+        self.skip_spaces();
+        let comparison = match self.operator() {
+            Some(operator) => {
+                self.skip_spaces();
+                Some((operator, self.operand()?))
+            }
+            None => None
+        };
+        Ok(Filter { left, comparison })
+    }
+
+    fn operator(&mut self) -> Option<Operator> {
+        // Two characters before one, so that `<=` is not taken for `<`.
+        [
+            ("==", Operator::Equal),
+            ("!=", Operator::NotEqual),
+            ("<=", Operator::LessOrEqual),
+            (">=", Operator::GreaterOrEqual),
+            ("<", Operator::Less),
+            (">", Operator::Greater)
+        ].into_iter().find(|(token, _)| self.eat(token)).map(|(_, operator)| operator)
+    }
+
+    fn operand(&mut self) -> Result<Operand, Error> {
+        match self.peek() {
+            Some('@' | '$') => Ok(Operand::Path(self.path()?)),
+            Some('\'' | '"') => Ok(Operand::Literal(Value::String(self.string()?))),
+            _ => {
+                let end = self.rest.find(|character: char| character.is_whitespace() || character == ')')
+                    .unwrap_or(self.rest.len());
+                let literal = serde_json::from_str(&self.rest[..end])
+                    .map_err(|_| self.invalid("expected a path, string, number, boolean or null"))?;
+                self.rest = &self.rest[end..];
+                Ok(Operand::Literal(literal))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    Path(Path),
+    /// What to render for every value the path leads to.
+    Range(Path, Vec<Node>)
+}
+
+/// A `-o jsonpath` template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    nodes: Vec<Node>
+}
+
+impl Template {
+    /// Renders the template over `root`, joining the values of each path with spaces.
+    pub fn render(&self, root: &Value) -> String {
+        let mut output = String::new();
+        render(&self.nodes, root, root, &mut output);
+        output
+    }
+}
+
+fn render(nodes: &[Node], root: &Value, current: &Value, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Path(path) => output.push_str(&path.evaluate_at(root, current).into_iter()
+                .map(display).collect::<Vec<_>>().join(" ")),
+            Node::Range(path, body) => for value in path.evaluate_at(root, current) {
+                render(body, root, value, output);
+            }
+        }
+    }
+}
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut nodes = Vec::new();
+        // The ranges open, innermost last, each with the nodes before it.
+        let mut ranges: Vec<(Path, Vec<Node>)> = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                nodes.push(Node::Text(unescape(&rest[..start])));
+            }
+            let end = action_end(&rest[start..])
+                .ok_or_else(|| Error::Template(format!("unclosed action in \"{}\"", s)))?;
+            let action = rest[start + 1..start + end].trim();
+            rest = &rest[start + end + 1..];
+            if action == "end" {
+                let (path, before) = ranges.pop().ok_or_else(|| Error::Template(format!(
+                    "not in range, nothing to end in \"{}\"", s)))?;
+                let body = std::mem::replace(&mut nodes, before);
+                nodes.push(Node::Range(path, body));
+            } else if let Some(path) = action.strip_prefix("range")
+                .filter(|path| path.starts_with(char::is_whitespace))
+            {
+                ranges.push((path.parse()?, std::mem::take(&mut nodes)));
+            } else if action.starts_with(['\'', '"']) {
+                let mut parser = Parser::new(action);
+                nodes.push(Node::Text(parser.string()?));
+                parser.end()?;
+            } else {
+                nodes.push(Node::Path(action.parse()?));
+            }
+        }
+        if !ranges.is_empty() {
+            return Err(Error::Template(format!("unclosed range in \"{}\"", s)))
+        }
+        if !rest.is_empty() {
+            nodes.push(Node::Text(unescape(rest)));
+        }
+        Ok(Self { nodes })
+    }
+}
+
+/// The offset of the brace closing the action `text` starts with, braces in quoted strings skipped.
+fn action_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, character) in text.char_indices().skip(1) {
+        match (quote, character) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(open), character) if character == open => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(character),
+            (None, '}') => return Some(index),
+            (None, _) => {}
+        }
+    }
+    None
+}
+
+/// Columns of `-o custom-columns=HEADER:path,...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomColumns {
+    pub columns: Vec<(String, Path)>
+}
+
+impl CustomColumns {
+    pub fn headers(&self) -> Vec<String> {
+        self.columns.iter().map(|(header, _)| header.clone()).collect()
+    }
+
+    /// The cells of `object`, `<none>` for fields it does not have.
+    pub fn row(&self, object: &Value) -> Vec<String> {
+        self.columns.iter().map(|(_, path)| cell(path, object)).collect()
+    }
+}
+
+impl FromStr for CustomColumns {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let columns = s.split(',').map(|column| {
+            let (header, path) = column.split_once(':').ok_or_else(|| Error::Template(
+                format!("unexpected custom-columns spec: {}, expected <header>:<json-path-expr>",
+                    column)))?;
+            Ok((header.to_owned(), column_path(path)?))
+        }).collect::<Result<_, Error>>()?;
+        Ok(Self { columns })
+    }
+}
+
+/// The path of a column, written with or without braces and the leading dot.
+pub fn column_path(path: &str) -> Result<Path, Error> {
+    let path = path.trim().trim_start_matches('{').trim_end_matches('}');
+    if path.starts_with(['.', '[', '$', '@']) {
+        path.parse()
+    } else {
+        format!(".{}", path).parse()
+    }
+}
+
+/// The values `path` leads to in `object`, joined with commas, `<none>` if there are none.
+pub fn cell(path: &Path, object: &Value) -> String {
+    let values = path.evaluate(object);
+    if values.is_empty() {
+        "<none>".to_owned()
+    } else {
+        values.into_iter().map(display).collect::<Vec<_>>().join(",")
+    }
+}
+
+/// Strings as they are, other values as JSON.
+pub fn display(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string()
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", "\n").replace("\\t", "\t")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(template: &str, root: &Value) -> String {
+        template.parse::<Template>().unwrap().render(root)
+    }
+
+    #[test]
+    fn slices() {
+        let root = json!({"items": [0, 1, 2, 3, 4]});
+        assert_eq!(render("{.items[1:4]}", &root), "1 2 3");
+        assert_eq!(render("{.items[-2:]}", &root), "3 4");
+        assert_eq!(render("{.items[::2]}", &root), "0 2 4");
+        assert_eq!(render("{.items[::-2]}", &root), "4 2 0");
+        assert_eq!(render("{.items[3:0:-1]}", &root), "3 2 1");
+        assert_eq!(render("{.items[4:1]}", &root), "");
+        assert_eq!(render("{.items[1::9223372036854775807]}", &root), "1");
+        assert_eq!(render("{.items[::-9223372036854775808]}", &root), "4");
+        assert!("{.items[::0]}".parse::<Template>().is_err());
+    }
+
+    fn pods() -> Value {
+        json!({
+            "kind": "List",
+            "items": [
+                {
+                    "metadata": {"name": "web", "labels": {"app.kubernetes.io/name": "web"}},
+                    "spec": {"containers": [{"image": "nginx"}, {"image": "envoy"}]},
+                    "status": {"phase": "Running", "restarts": 3}
+                },
+                {
+                    "metadata": {"name": "job"},
+                    "spec": {"containers": [{"image": "busybox"}]},
+                    "status": {"phase": "Succeeded", "restarts": 0}
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn follows_paths() {
+        let root = pods();
+        assert_eq!(render("{.items[*].metadata.name}", &root), "web job");
+        assert_eq!(render("{..image}", &root), "nginx envoy busybox");
+        assert_eq!(render("{$.items[-1:].metadata.name}", &root), "job");
+        assert_eq!(render(r"{.items[0].metadata.labels.app\.kubernetes\.io/name}", &root), "web");
+        assert_eq!(render("{.items[0].metadata['name', 'missing']}", &root), "web");
+        assert_eq!(render("{.items[0].spec.containers}", &root),
+            r#"[{"image":"nginx"},{"image":"envoy"}]"#);
+    }
+
+    #[test]
+    fn filters_items() {
+        let root = pods();
+        assert_eq!(render("{.items[?(@.status.phase=='Running')].metadata.name}", &root), "web");
+        assert_eq!(render("{.items[?(@.status.restarts>0)].metadata.name}", &root), "web");
+        assert_eq!(render("{.items[?(@.metadata.labels)].metadata.name}", &root), "web");
+        assert_eq!(render("{.items[?(@.status.phase!=\"Running\")].metadata.name}", &root), "job");
+    }
+
+    #[test]
+    fn renders_ranges() {
+        let root = pods();
+        assert_eq!(render(r#"{range .items[*]}{.metadata.name}:{range .spec.containers[*]} {.image}{end}{"\n"}{end}"#, &root),
+            "web: nginx envoy\njob: busybox\n");
+        assert!("{range .items[*]}{.metadata.name}".parse::<Template>().is_err());
+        assert!("{.metadata.name}{end}".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn prints_custom_columns() {
+        let columns: CustomColumns = "NAME:.metadata.name,IMAGES:spec.containers[*].image,NODE:{.spec.nodeName}"
+            .parse().unwrap();
+        let root = pods();
+        assert_eq!(columns.headers(), ["NAME", "IMAGES", "NODE"]);
+        assert_eq!(columns.row(&root["items"][0]), ["web", "nginx,envoy", "<none>"]);
+        assert!("NAME".parse::<CustomColumns>().is_err());
+    }
+}
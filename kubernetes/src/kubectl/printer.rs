@@ -0,0 +1,881 @@
+/*!
+Printing objects as `kubectl get -o` does: as tables of the columns of their kind, by name, in JSON or YAML, or through a JSONPath template or custom columns.
+
+- Reference <https://kubernetes.io/docs/reference/kubectl/#output-options>
+- Reference <https://kubernetes.io/docs/tasks/extend-kubernetes/custom-resources/custom-resource-definitions/#additional-printer-columns>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/printers/internalversion/printers.go>
+*/
+
+use std::{
+    fmt,
+    str::FromStr,
+    time::Duration
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    api_extensions::custom_resource_definition::Column,
+    core::pod::{
+        Container, ContainerState, ContainerStateRunning, ContainerStateTerminated,
+        ContainerStateWaiting, ContainerStatus, ConditionType, Pod, RestartPolicy, Status
+    },
+    meta::condition::{Condition, ConditionStatus},
+    time::Time
+};
+
+use super::{
+    jsonpath::{cell, column_path, CustomColumns, Template},
+    store::{lookup, string},
+    Error
+};
+
+/// Formats of `-o`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Output {
+    /// The columns of the kind.
+    #[default]
+    Table,
+    /// The columns of the kind and some more.
+    Wide,
+    /// `<type>/<name>`, as `deployment.apps/web`.
+    Name,
+    Json,
+    Yaml,
+    JsonPath(Template),
+    CustomColumns(CustomColumns)
+}
+
+impl FromStr for Output {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, argument) = s.split_once('=').unwrap_or((s, ""));
+        match format {
+            "" => Ok(Self::Table),
+            "wide" => Ok(Self::Wide),
+            "name" => Ok(Self::Name),
+            "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
+            "jsonpath" => Ok(Self::JsonPath(argument.parse()?)),
+            "custom-columns" => Ok(Self::CustomColumns(argument.parse()?)),
+            _ => Err(Error::Usage(format!(
+                "unable to match a printer suitable for the output format \"{}\", allowed formats \
+                are: custom-columns,json,jsonpath,name,wide,yaml", s)))
+        }
+    }
+}
+
+/// Rows of cells under headers, printed with the columns aligned as `kubectl` aligns them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut widths: Vec<usize> = self.headers.iter().map(|header| header.len()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        for row in std::iter::once(&self.headers).chain(&self.rows) {
+            let last = row.len().saturating_sub(1);
+            for (index, (cell, width)) in row.iter().zip(&widths).enumerate() {
+                if index == last {
+                    writeln!(f, "{}", cell)?;
+                } else {
+                    write!(f, "{:width$}   ", cell, width = width)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What to add to the columns of a kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TableOptions<'a> {
+    /// Adds the columns of `-o wide`.
+    pub wide: bool,
+    /// Adds `NAMESPACE` first, as `--all-namespaces` does.
+    pub namespace: bool,
+    /// Adds `LABELS` last, as `--show-labels` does.
+    pub labels: bool,
+    /// Prefixes names with their type, as when several types are listed.
+    pub kind: Option<&'a str>
+}
+
+/// The table of `objects` of `kind`, ages taken at `now`.
+pub fn table(kind: &str, objects: &[&Value], options: TableOptions<'_>, now: Time) -> Table {
+    let mut table = Table::default();
+    if options.namespace {
+        table.headers.push("NAMESPACE".to_owned());
+    }
+    table.headers.push("NAME".to_owned());
+    let (columns, wide) = headers(kind);
+    table.headers.extend(columns.iter().map(|header| header.to_string()));
+    if options.wide {
+        table.headers.extend(wide.iter().map(|header| header.to_string()));
+    }
+    if options.labels {
+        table.headers.push("LABELS".to_owned());
+    }
+    for object in objects {
+        let mut row = Vec::new();
+        if options.namespace {
+            row.push(string(object, "metadata.namespace").to_owned());
+        }
+        let name = string(object, "metadata.name");
+        row.push(match options.kind {
+            Some(kind) => format!("{}/{}", kind, name),
+            None => name.to_owned()
+        });
+        let (cells, wide) = cells(kind, object, now);
+        row.extend(cells);
+        if options.wide {
+            row.extend(wide);
+        }
+        if options.labels {
+            row.push(labels(object));
+        }
+        table.rows.push(row);
+    }
+    table
+}
+
+/**
+The table of custom resources `objects` by the `additionalPrinterColumns` of their version, ages taken at `now`: the name, then the columns of priority 0, or of any priority if wide, or the age if there are none.
+
+Cells are the values the path of their column leads to, joined with commas; dates are shown as ages.
+*/
+pub fn custom_resource_table(columns: &[Column], objects: &[&Value], options: TableOptions<'_>,
+    now: Time) -> Result<Table, Error>
+{
+    let age = Column {
+        json_path: ".metadata.creationTimestamp".to_owned(),
+        name: "Age".to_owned(),
+        r#type: "date".to_owned(),
+        description: None,
+        format: String::new(),
+        priority: None
+    };
+    let columns: Vec<&Column> = if columns.is_empty() {
+        vec![&age]
+    } else {
+        columns.iter()
+            .filter(|column| options.wide || column.priority.unwrap_or_default() == 0)
+            .collect()
+    };
+    let paths = columns.iter().map(|column| column_path(&column.json_path))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let mut table = Table::default();
+    if options.namespace {
+        table.headers.push("NAMESPACE".to_owned());
+    }
+    table.headers.push("NAME".to_owned());
+    table.headers.extend(columns.iter().map(|column| column.name.to_uppercase()));
+    if options.labels {
+        table.headers.push("LABELS".to_owned());
+    }
+    for object in objects {
+        let mut row = Vec::new();
+        if options.namespace {
+            row.push(string(object, "metadata.namespace").to_owned());
+        }
+        let name = string(object, "metadata.name");
+        row.push(match options.kind {
+            Some(kind) => format!("{}/{}", kind, name),
+            None => name.to_owned()
+        });
+        row.extend(columns.iter().zip(&paths).map(|(column, path)| match column.r#type.as_str() {
+            "date" => path.evaluate(object).first()
+                .and_then(|time| time.as_str()?.parse().ok())
+                .map_or("<unknown>".to_owned(), |time| since(time, now)),
+            _ => cell(path, object)
+        }));
+        if options.labels {
+            row.push(labels(object));
+        }
+        table.rows.push(row);
+    }
+    Ok(table)
+}
+
+fn headers(kind: &str) -> (&'static [&'static str], &'static [&'static str]) {
+    const WORKLOAD: &[&str] = &["CONTAINERS", "IMAGES", "SELECTOR"];
+    match kind {
+        "Pod" => (&["READY", "STATUS", "RESTARTS", "AGE"],
+            &["IP", "NODE", "NOMINATED NODE", "READINESS GATES"]),
+        "Service" => (&["TYPE", "CLUSTER-IP", "EXTERNAL-IP", "PORT(S)", "AGE"], &["SELECTOR"]),
+        "Deployment" => (&["READY", "UP-TO-DATE", "AVAILABLE", "AGE"], WORKLOAD),
+        "ReplicaSet" => (&["DESIRED", "CURRENT", "READY", "AGE"], WORKLOAD),
+        "StatefulSet" => (&["READY", "AGE"], &["CONTAINERS", "IMAGES"]),
+        "DaemonSet" => (&["DESIRED", "CURRENT", "READY", "UP-TO-DATE", "AVAILABLE",
+            "NODE SELECTOR", "AGE"], WORKLOAD),
+        "Job" => (&["STATUS", "COMPLETIONS", "DURATION", "AGE"], WORKLOAD),
+        "CronJob" => (&["SCHEDULE", "TIMEZONE", "SUSPEND", "ACTIVE", "LAST SCHEDULE", "AGE"],
+            WORKLOAD),
+        "ConfigMap" => (&["DATA", "AGE"], &[]),
+        "Secret" => (&["TYPE", "DATA", "AGE"], &[]),
+        "ServiceAccount" => (&["SECRETS", "AGE"], &[]),
+        "Namespace" => (&["STATUS", "AGE"], &[]),
+        "Node" => (&["STATUS", "ROLES", "AGE", "VERSION"],
+            &["INTERNAL-IP", "EXTERNAL-IP", "OS-IMAGE", "KERNEL-VERSION", "CONTAINER-RUNTIME"]),
+        "PersistentVolumeClaim" => (&["STATUS", "VOLUME", "CAPACITY", "ACCESS MODES",
+            "STORAGECLASS", "AGE"], &["VOLUMEMODE"]),
+        "PersistentVolume" => (&["CAPACITY", "ACCESS MODES", "RECLAIM POLICY", "STATUS",
+            "CLAIM", "STORAGECLASS", "REASON", "AGE"], &["VOLUMEMODE"]),
+        _ => (&["AGE"], &[])
+    }
+}
+
+fn cells(kind: &str, object: &Value, now: Time) -> (Vec<String>, Vec<String>) {
+    let age = age(object, now);
+    let int = |path: &str| lookup(object, path).and_then(Value::as_i64).unwrap_or_default();
+    let text = |path: &str| none(string(object, path));
+    match kind {
+        "Pod" => {
+            let status = pod(object).display_status();
+            let restarts = match status.last_restart {
+                Some(time) if status.restarts > 0 =>
+                    format!("{} ({} ago)", status.restarts, since(time, now)),
+                _ => status.restarts.to_string()
+            };
+            let gates = lookup(object, "spec.readinessGates").and_then(Value::as_array)
+                .map_or(0, Vec::len);
+            let gates = if gates == 0 {
+                "<none>".to_owned()
+            } else {
+                let ready = lookup(object, "status.conditions").and_then(Value::as_array)
+                    .into_iter().flatten()
+                    .filter(|condition| string(condition, "status") == "True"
+                        && lookup(object, "spec.readinessGates").and_then(Value::as_array)
+                            .into_iter().flatten()
+                            .any(|gate| string(gate, "conditionType") == string(condition, "type")))
+                    .count();
+                format!("{}/{}", ready, gates)
+            };
+            (vec![format!("{}/{}", status.ready, status.total), status.status, restarts, age],
+                vec![text("status.podIP"), text("spec.nodeName"),
+                    text("status.nominatedNodeName"), gates])
+        }
+        "Service" => {
+            let r#type = match string(object, "spec.type") {
+                "" => "ClusterIP",
+                r#type => r#type
+            };
+            let ports: Vec<String> = array(object, "spec.ports").iter().map(|port| {
+                let protocol = match string(port, "protocol") {
+                    "" => "TCP",
+                    protocol => protocol
+                };
+                match lookup(port, "nodePort").and_then(Value::as_i64) {
+                    Some(node_port) => format!("{}:{}/{}", int_of(port, "port"), node_port,
+                        protocol),
+                    None => format!("{}/{}", int_of(port, "port"), protocol)
+                }
+            }).collect();
+            let mut external: Vec<String> = array(object, "spec.externalIPs").iter()
+                .filter_map(|ip| ip.as_str().map(str::to_owned))
+                .collect();
+            if r#type == "LoadBalancer" {
+                external.extend(array(object, "status.loadBalancer.ingress").iter()
+                    .map(|ingress| match string(ingress, "ip") {
+                        "" => string(ingress, "hostname").to_owned(),
+                        ip => ip.to_owned()
+                    }));
+                if external.is_empty() {
+                    external.push("<pending>".to_owned());
+                }
+            } else if r#type == "ExternalName" {
+                external.push(string(object, "spec.externalName").to_owned());
+            }
+            (vec![r#type.to_owned(), text("spec.clusterIP"), none(&external.join(",")),
+                none(&ports.join(",")), age],
+                vec![none(&map_pairs(lookup(object, "spec.selector"), "="))])
+        }
+        "Deployment" => (vec![
+            format!("{}/{}", int("status.readyReplicas"), replicas(object)),
+            int("status.updatedReplicas").to_string(),
+            int("status.availableReplicas").to_string(),
+            age
+        ], workload(object, "spec.template")),
+        "ReplicaSet" => (vec![
+            replicas(object).to_string(),
+            int("status.replicas").to_string(),
+            int("status.readyReplicas").to_string(),
+            age
+        ], workload(object, "spec.template")),
+        "StatefulSet" => {
+            let mut wide = workload(object, "spec.template");
+            wide.pop();
+            (vec![format!("{}/{}", int("status.readyReplicas"), replicas(object)), age], wide)
+        }
+        "DaemonSet" => (vec![
+            int("status.desiredNumberScheduled").to_string(),
+            int("status.currentNumberScheduled").to_string(),
+            int("status.numberReady").to_string(),
+            int("status.updatedNumberScheduled").to_string(),
+            int("status.numberAvailable").to_string(),
+            none(&map_pairs(lookup(object, "spec.template.spec.nodeSelector"), "=")),
+            age
+        ], workload(object, "spec.template")),
+        "Job" => {
+            let conditions = array(object, "status.conditions");
+            let has = |r#type: &str| conditions.iter()
+                .any(|condition| string(condition, "type") == r#type
+                    && string(condition, "status") == "True");
+            let status = if has("Complete") {
+                "Complete"
+            } else if has("Failed") {
+                "Failed"
+            } else if has("FailureTarget") || has("SuccessCriteriaMet") {
+                "Terminating"
+            } else if has("Suspended") {
+                "Suspended"
+            } else {
+                "Running"
+            };
+            let completions = match lookup(object, "spec.completions").and_then(Value::as_i64) {
+                Some(completions) => format!("{}/{}", int("status.succeeded"), completions),
+                None if lookup(object, "spec.parallelism").and_then(Value::as_i64)
+                    .unwrap_or(1) > 1 => format!("{}/1 of {}", int("status.succeeded"),
+                        int("spec.parallelism")),
+                None => format!("{}/1", int("status.succeeded"))
+            };
+            let start = timestamp(object, "status.startTime");
+            let end = timestamp(object, "status.completionTime").unwrap_or(now);
+            let duration = start.map_or(String::new(), |start| since(start, end));
+            (vec![status.to_owned(), completions, duration, age],
+                workload(object, "spec.template"))
+        }
+        "CronJob" => (vec![
+            text("spec.schedule"),
+            text("spec.timeZone"),
+            lookup(object, "spec.suspend").and_then(Value::as_bool).unwrap_or_default()
+                .to_string()
+                .replace("false", "False").replace("true", "True"),
+            array(object, "status.active").len().to_string(),
+            timestamp(object, "status.lastScheduleTime")
+                .map_or("<none>".to_owned(), |time| since(time, now)),
+            age
+        ], workload(object, "spec.jobTemplate.spec.template")),
+        "ConfigMap" => (vec![(object_len(object, "data") + object_len(object, "binaryData"))
+            .to_string(), age], Vec::new()),
+        "Secret" => (vec![text("type"), object_len(object, "data").to_string(), age], Vec::new()),
+        "ServiceAccount" => (vec![array(object, "secrets").len().to_string(), age], Vec::new()),
+        "Namespace" => (vec![
+            match string(object, "status.phase") {
+                "" => "Active".to_owned(),
+                phase => phase.to_owned()
+            },
+            age
+        ], Vec::new()),
+        "Node" => {
+            let mut status = match array(object, "status.conditions").iter()
+                .find(|condition| string(condition, "type") == "Ready")
+                .map(|condition| string(condition, "status"))
+            {
+                Some("True") => "Ready",
+                Some("False") => "NotReady",
+                _ => "Unknown"
+            }.to_owned();
+            if lookup(object, "spec.unschedulable").and_then(Value::as_bool) == Some(true) {
+                status.push_str(",SchedulingDisabled");
+            }
+            let mut roles: Vec<&str> = lookup(object, "metadata.labels").and_then(Value::as_object)
+                .into_iter().flatten()
+                .filter_map(|(key, _)| key.strip_prefix("node-role.kubernetes.io/"))
+                .filter(|role| !role.is_empty())
+                .collect();
+            roles.sort();
+            let address = |r#type: &str| none(array(object, "status.addresses").iter()
+                .find(|address| string(address, "type") == r#type)
+                .map_or("", |address| string(address, "address")));
+            (vec![status, none(&roles.join(",")), age, text("status.nodeInfo.kubeletVersion")],
+                vec![address("InternalIP"), address("ExternalIP"),
+                    text("status.nodeInfo.osImage"), text("status.nodeInfo.kernelVersion"),
+                    text("status.nodeInfo.containerRuntimeVersion")])
+        }
+        "PersistentVolumeClaim" => {
+            let bound = string(object, "status.phase") == "Bound";
+            (vec![
+                text("status.phase"),
+                string(object, "spec.volumeName").to_owned(),
+                if bound { string(object, "status.capacity.storage") } else { "" }.to_owned(),
+                if bound { access_modes(object, "status.accessModes") } else { String::new() },
+                storage_class(object),
+                age
+            ], vec![volume_mode(object)])
+        }
+        "PersistentVolume" => {
+            let claim = lookup(object, "spec.claimRef").map_or(String::new(), |claim| {
+                format!("{}/{}", string(claim, "namespace"), string(claim, "name"))
+            });
+            (vec![
+                string(object, "spec.capacity.storage").to_owned(),
+                access_modes(object, "spec.accessModes"),
+                match string(object, "spec.persistentVolumeReclaimPolicy") {
+                    "" => "Retain",
+                    policy => policy
+                }.to_owned(),
+                string(object, "status.phase").to_owned(),
+                claim,
+                storage_class(object),
+                string(object, "status.reason").to_owned(),
+                age
+            ], vec![volume_mode(object)])
+        }
+        _ => (vec![age], Vec::new())
+    }
+}
+
+fn array<'a>(object: &'a Value, path: &str) -> &'a [Value] {
+    lookup(object, path).and_then(Value::as_array).map_or(&[], Vec::as_slice)
+}
+
+fn object_len(object: &Value, path: &str) -> usize {
+    lookup(object, path).and_then(Value::as_object).map_or(0, |map| map.len())
+}
+
+fn int_of(object: &Value, path: &str) -> i64 {
+    lookup(object, path).and_then(Value::as_i64).unwrap_or_default()
+}
+
+fn replicas(object: &Value) -> i64 {
+    lookup(object, "spec.replicas").and_then(Value::as_i64).unwrap_or(1)
+}
+
+fn none(text: &str) -> String {
+    if text.is_empty() { "<none>" } else { text }.to_owned()
+}
+
+/// `key=value,...` of a map of strings, sorted by key.
+fn map_pairs(map: Option<&Value>, separator: &str) -> String {
+    map.and_then(Value::as_object).into_iter().flatten()
+        .map(|(key, value)| format!("{}{}{}", key, separator, value.as_str().unwrap_or_default()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn labels(object: &Value) -> String {
+    none(&map_pairs(lookup(object, "metadata.labels"), "="))
+}
+
+/// The `CONTAINERS`, `IMAGES` and `SELECTOR` columns of a workload with the pod template at `template`.
+fn workload(object: &Value, template: &str) -> Vec<String> {
+    let containers = array(object, &format!("{}.spec.containers", template));
+    let names: Vec<&str> = containers.iter().map(|container| string(container, "name")).collect();
+    let images: Vec<&str> = containers.iter().map(|container| string(container, "image"))
+        .collect();
+    let selector = lookup(object, "spec.selector").map_or(String::new(), |selector| {
+        let mut requirements = Vec::new();
+        if selector.get("matchLabels").is_some() || selector.get("matchExpressions").is_some() {
+            requirements.push(map_pairs(selector.get("matchLabels"), "="));
+            for expression in array(selector, "matchExpressions") {
+                let values: Vec<&str> = array(expression, "values").iter()
+                    .filter_map(Value::as_str).collect();
+                requirements.push(match string(expression, "operator") {
+                    "In" => format!("{} in ({})", string(expression, "key"), values.join(",")),
+                    "NotIn" => format!("{} notin ({})", string(expression, "key"),
+                        values.join(",")),
+                    "DoesNotExist" => format!("!{}", string(expression, "key")),
+                    _ => string(expression, "key").to_owned()
+                });
+            }
+        } else {
+            requirements.push(map_pairs(Some(selector), "="));
+        }
+        requirements.retain(|requirement| !requirement.is_empty());
+        requirements.join(",")
+    });
+    vec![none(&names.join(",")), none(&images.join(",")), none(&selector)]
+}
+
+fn access_modes(object: &Value, path: &str) -> String {
+    array(object, path).iter().filter_map(Value::as_str).map(|mode| match mode {
+        "ReadWriteOnce" => "RWO",
+        "ReadOnlyMany" => "ROX",
+        "ReadWriteMany" => "RWX",
+        "ReadWriteOncePod" => "RWOP",
+        mode => mode
+    }).collect::<Vec<_>>().join(",")
+}
+
+fn storage_class(object: &Value) -> String {
+    match string(object, "spec.storageClassName") {
+        "" => lookup(object, "metadata.annotations")
+            .and_then(|annotations| annotations.get("volume.beta.kubernetes.io/storage-class"))
+            .and_then(Value::as_str).unwrap_or_default().to_owned(),
+        class => class.to_owned()
+    }
+}
+
+fn volume_mode(object: &Value) -> String {
+    match string(object, "spec.volumeMode") {
+        "" => "Filesystem",
+        mode => mode
+    }.to_owned()
+}
+
+/**
+The typed view of a pod's containers and status, enough for [`Pod::display_status`] to compute what `kubectl get pods` shows.
+*/
+fn pod(object: &Value) -> Pod {
+    let container = |value: &Value| Container {
+        name: string(value, "name").to_owned(),
+        restart_policy: (string(value, "restartPolicy") == "Always")
+            .then_some(RestartPolicy::Always),
+        ..Default::default()
+    };
+    let state = |value: Option<&Value>| value.map(|state| ContainerState {
+        running: lookup(state, "running").map(|running| ContainerStateRunning {
+            started_at: timestamp(running, "startedAt")
+        }),
+        terminated: lookup(state, "terminated").map(|terminated| ContainerStateTerminated {
+            exit_code: lookup(terminated, "exitCode").and_then(Value::as_i64)
+                .and_then(|code| i32::try_from(code).ok()),
+            signal: lookup(terminated, "signal").and_then(Value::as_i64)
+                .and_then(|signal| i32::try_from(signal).ok()),
+            reason: lookup(terminated, "reason").and_then(Value::as_str).map(str::to_owned),
+            finished_at: timestamp(terminated, "finishedAt"),
+            ..Default::default()
+        }),
+        waiting: lookup(state, "waiting").map(|waiting| ContainerStateWaiting {
+            reason: lookup(waiting, "reason").and_then(Value::as_str).map(str::to_owned),
+            message: None
+        })
+    });
+    let status = |value: &Value| ContainerStatus {
+        name: string(value, "name").to_owned(),
+        ready: lookup(value, "ready").and_then(Value::as_bool).unwrap_or_default(),
+        restart_count: i32::try_from(int_of(value, "restartCount")).unwrap_or_default(),
+        started: lookup(value, "started").and_then(Value::as_bool),
+        state: state(value.get("state")),
+        last_state: state(value.get("lastState")),
+        ..Default::default()
+    };
+    let mut pod = Pod::default();
+    pod.metadata.deletion_timestamp = timestamp(object, "metadata.deletionTimestamp");
+    pod.spec.containers = array(object, "spec.containers").iter().map(container).collect();
+    pod.spec.init_containers = array(object, "spec.initContainers").iter().map(container)
+        .collect();
+    pod.status = lookup(object, "status").map(|value| Status {
+        phase: string(value, "phase").to_owned(),
+        reason: string(value, "reason").to_owned(),
+        conditions: array(value, "conditions").iter().filter_map(|condition| {
+            let r#type = match string(condition, "type") {
+                "PodScheduled" => ConditionType::PodScheduled,
+                "Initialized" => ConditionType::Initialized,
+                "ContainersReady" => ConditionType::ContainersReady,
+                "Ready" => ConditionType::Ready,
+                "DisruptionTarget" => ConditionType::DisruptionTarget,
+                _ => return None
+            };
+            let status = match string(condition, "status") {
+                "True" => ConditionStatus::True,
+                "False" => ConditionStatus::False,
+                _ => ConditionStatus::Unknown
+            };
+            Some(Condition::new(r#type, status, string(condition, "reason"),
+                string(condition, "message"), Time::default()))
+        }).collect(),
+        init_container_statuses: array(value, "initContainerStatuses").iter().map(status)
+            .collect(),
+        container_statuses: array(value, "containerStatuses").iter().map(status).collect(),
+        ..Default::default()
+    });
+    pod
+}
+
+/// The time at `path` in `object`, if there is one in RFC 3339.
+pub fn timestamp(object: &Value, path: &str) -> Option<Time> {
+    lookup(object, path).and_then(Value::as_str).and_then(|time| time.parse().ok())
+}
+
+/// The age of `object` at `now`, `<unknown>` without a creation timestamp.
+pub fn age(object: &Value, now: Time) -> String {
+    timestamp(object, "metadata.creationTimestamp")
+        .map_or("<unknown>".to_owned(), |created| since(created, now))
+}
+
+fn since(start: Time, end: Time) -> String {
+    match end.0.duration_since(start.0) {
+        Ok(duration) => human_duration(duration),
+        // Allows for a second of clock skew.
+        Err(error) if error.duration() <= Duration::from_secs(1) => "0s".to_owned(),
+        Err(_) => "<invalid>".to_owned()
+    }
+}
+
+/// A duration as `kubectl` shows ages, precise for recent times and coarse for old ones, as `90s`, `5m10s`, `3h`, `12d` or `2y30d`.
+pub fn human_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds < 60 * 2 {
+        return format!("{}s", seconds)
+    }
+    let minutes = seconds / 60;
+    if minutes < 10 {
+        return match seconds % 60 {
+            0 => format!("{}m", minutes),
+            seconds => format!("{}m{}s", minutes, seconds)
+        }
+    }
+    if minutes < 60 * 3 {
+        return format!("{}m", minutes)
+    }
+    let hours = minutes / 60;
+    if hours < 8 {
+        return match minutes % 60 {
+            0 => format!("{}h", hours),
+            minutes => format!("{}h{}m", hours, minutes)
+        }
+    }
+    if hours < 48 {
+        return format!("{}h", hours)
+    }
+    if hours < 24 * 8 {
+        return match hours % 24 {
+            0 => format!("{}d", hours / 24),
+            hours_left => format!("{}d{}h", hours / 24, hours_left)
+        }
+    }
+    if hours < 24 * 365 * 2 {
+        return format!("{}d", hours / 24)
+    }
+    if hours < 24 * 365 * 8 {
+        return match hours / 24 % 365 {
+            0 => format!("{}y", hours / 24 / 365),
+            days => format!("{}y{}d", hours / 24 / 365, days)
+        }
+    }
+    format!("{}y", hours / 24 / 365)
+}
+
+/// `items` as a `List`, as `-o json` and `-o yaml` print several objects.
+pub fn list(items: Vec<Value>) -> Value {
+    json!({
+        "apiVersion": "v1",
+        "items": items,
+        "kind": "List",
+        "metadata": {
+            "resourceVersion": ""
+        }
+    })
+}
+
+/// `value` in YAML, as `kubectl` writes it: keys sorted, sequences not indented under their keys and multi-line strings as literal blocks.
+pub fn to_yaml(value: &Value) -> String {
+    let mut output = String::new();
+    match value {
+        Value::Object(map) if !map.is_empty() => block(&mut output, value, 0),
+        Value::Array(items) if !items.is_empty() => block(&mut output, value, 0),
+        value => {
+            scalar(&mut output, value, 0);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// Writes a non-empty map or sequence, each entry on its own lines at `indent`.
+fn block(output: &mut String, value: &Value, indent: usize) {
+    let padding = " ".repeat(indent);
+    match value {
+        Value::Object(map) => for (key, value) in map {
+            output.push_str(&padding);
+            scalar(output, &Value::String(key.clone()), indent);
+            output.push(':');
+            match value {
+                Value::Object(map) if !map.is_empty() => {
+                    output.push('\n');
+                    block(output, value, indent + 2);
+                }
+                Value::Array(items) if !items.is_empty() => {
+                    output.push('\n');
+                    block(output, value, indent);
+                }
+                value => {
+                    output.push(' ');
+                    scalar(output, value, indent + 2);
+                    output.push('\n');
+                }
+            }
+        },
+        Value::Array(items) => for item in items {
+            output.push_str(&padding);
+            output.push('-');
+            match item {
+                Value::Object(map) if !map.is_empty() => nested(output, item, indent + 2),
+                Value::Array(items) if !items.is_empty() => nested(output, item, indent + 2),
+                value => {
+                    output.push(' ');
+                    scalar(output, value, indent + 2);
+                    output.push('\n');
+                }
+            }
+        },
+        _ => unreachable!("only maps and sequences are blocks")
+    }
+}
+
+/// Writes a block in a sequence, its first line after the dash.
+fn nested(output: &mut String, value: &Value, indent: usize) {
+    let mut inner = String::new();
+    block(&mut inner, value, indent);
+    output.push(' ');
+    output.push_str(&inner[indent..]);
+}
+
+fn scalar(output: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Null => output.push_str("null"),
+        Value::Bool(value) => output.push_str(&value.to_string()),
+        Value::Number(number) => output.push_str(&number.to_string()),
+        Value::Object(_) => output.push_str("{}"),
+        Value::Array(_) => output.push_str("[]"),
+        Value::String(string) if string.contains('\n') && !string.contains(['\r', '\t'])
+            && !string.starts_with(' ') =>
+        {
+            output.push_str(if string.ends_with('\n') { "|" } else { "|-" });
+            let padding = " ".repeat(indent);
+            for line in string.strip_suffix('\n').unwrap_or(string).split('\n') {
+                output.push('\n');
+                if !line.is_empty() {
+                    output.push_str(&padding);
+                    output.push_str(line);
+                }
+            }
+        }
+        Value::String(string) if string.chars().any(char::is_control) => {
+            output.push_str(&Value::String(string.clone()).to_string());
+        }
+        Value::String(string) if needs_quotes(string) => {
+            output.push('\'');
+            output.push_str(&string.replace('\'', "''"));
+            output.push('\'');
+        }
+        Value::String(string) => output.push_str(string)
+    }
+}
+
+/// Whether a plain `string` would be read back as something else, or not be read at all.
+fn needs_quotes(string: &str) -> bool {
+    const RESERVED: [&str; 12] = [
+        "true", "false", "yes", "no", "on", "off", "y", "n", "null", "~", "<<", "="
+    ];
+    string.is_empty()
+        || RESERVED.iter().any(|reserved| string.eq_ignore_ascii_case(reserved))
+        || string.parse::<f64>().is_ok()
+        || string.starts_with(|c: char| ",[]{}#&*!|>'\"%@` ".contains(c))
+        // These only start something else when followed by a space.
+        || ["-", "?", ":"].iter().any(|indicator| string == *indicator
+            || string.strip_prefix(indicator).is_some_and(|rest| rest.starts_with(' ')))
+        || string.ends_with([' ', ':'])
+        || string.contains(": ")
+        || string.contains(" #")
+        || string.starts_with("0x") || string.starts_with("0o")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_ages() {
+        let age = |seconds| human_duration(Duration::from_secs(seconds));
+        assert_eq!(age(90), "90s");
+        assert_eq!(age(5 * 60 + 10), "5m10s");
+        assert_eq!(age(30 * 60), "30m");
+        assert_eq!(age(3 * 3600 + 20 * 60), "3h20m");
+        assert_eq!(age(30 * 3600), "30h");
+        assert_eq!(age(3 * 86400 + 4 * 3600), "3d4h");
+        assert_eq!(age(40 * 86400), "40d");
+        assert_eq!(age(3 * 365 * 86400 + 86400), "3y1d");
+        assert_eq!(age(10 * 365 * 86400), "10y");
+    }
+
+    #[test]
+    fn prints_tables_of_pods() {
+        let now: Time = "2024-01-01T01:00:00Z".parse().unwrap();
+        let pod = json!({
+            "metadata": {
+                "name": "web-0",
+                "namespace": "apps",
+                "creationTimestamp": "2024-01-01T00:00:00Z",
+                "labels": {"app": "web"}
+            },
+            "spec": {"containers": [{"name": "nginx"}, {"name": "sidecar"}]},
+            "status": {
+                "phase": "Running",
+                "containerStatuses": [
+                    {"name": "nginx", "ready": true, "restartCount": 0,
+                        "state": {"running": {}}},
+                    {"name": "sidecar", "ready": false, "restartCount": 2,
+                        "state": {"running": {}}}
+                ]
+            }
+        });
+        let options = TableOptions { namespace: true, labels: true, ..Default::default() };
+        assert_eq!(table("Pod", &[&pod], options, now).to_string(), "\
+            NAMESPACE   NAME    READY   STATUS    RESTARTS   AGE   LABELS\n\
+            apps        web-0   1/2     Running   2          60m   app=web\n");
+    }
+
+    #[test]
+    fn prints_printer_columns_of_custom_resources() {
+        let now: Time = "2024-01-01T00:05:00Z".parse().unwrap();
+        let column = |name: &str, json_path: &str, r#type: &str, priority| Column {
+            json_path: json_path.to_owned(),
+            name: name.to_owned(),
+            r#type: r#type.to_owned(),
+            description: None,
+            format: String::new(),
+            priority
+        };
+        let columns = [
+            column("Replicas", ".spec.replicas", "integer", None),
+            column("Hosts", ".spec.hosts[*]", "string", Some(1)),
+            column("Created", ".metadata.creationTimestamp", "date", None)
+        ];
+        let object = json!({
+            "metadata": {"name": "shop", "creationTimestamp": "2024-01-01T00:00:00Z"},
+            "spec": {"replicas": 2, "hosts": ["a.example", "b.example"]}
+        });
+        let table = custom_resource_table(&columns, &[&object], TableOptions::default(), now)
+            .unwrap();
+        assert_eq!(table.to_string(), "\
+            NAME   REPLICAS   CREATED\n\
+            shop   2          5m\n");
+        let wide = TableOptions { wide: true, ..Default::default() };
+        let table = custom_resource_table(&columns, &[&object], wide, now).unwrap();
+        assert_eq!(table.rows[0][2], "a.example,b.example");
+        let table = custom_resource_table(&[], &[&object], TableOptions::default(), now).unwrap();
+        assert_eq!(table.headers, ["NAME", "AGE"]);
+    }
+
+    #[test]
+    fn writes_yaml() {
+        let value = json!({
+            "kind": "ConfigMap",
+            "data": {"enabled": "true", "script": "echo hello\nexit 0\n", "empty": ""},
+            "items": [{"name": "a", "port": 80}, "b"]
+        });
+        assert_eq!(to_yaml(&value), "\
+data:
+  empty: ''
+  enabled: 'true'
+  script: |
+    echo hello
+    exit 0
+items:
+- name: a
+  port: 80
+- b
+kind: ConfigMap
+");
+    }
+}
@@ -0,0 +1,99 @@
+/*!
+Resource types as `kubectl` names them on the command line, by plural, singular, short name or kind.
+
+Reference <https://kubernetes.io/docs/reference/kubectl/#resource-types>
+*/
+
+/// A resource type served by the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resource {
+    /// Plural, as in the paths of the API.
+    pub name: &'static str,
+    pub singular: &'static str,
+    pub short_names: &'static [&'static str],
+    /// Empty for the core group.
+    pub group: &'static str,
+    pub version: &'static str,
+    pub kind: &'static str,
+    pub namespaced: bool
+}
+
+impl Resource {
+    const fn new(
+        name: &'static str,
+        singular: &'static str,
+        short_names: &'static [&'static str],
+        group: &'static str,
+        version: &'static str,
+        kind: &'static str,
+        namespaced: bool
+    ) -> Self {
+        Self { name, singular, short_names, group, version, kind, namespaced }
+    }
+
+    /// `v1` or `<group>/<version>`.
+    pub fn api_version(&self) -> String {
+        if self.group.is_empty() {
+            self.version.to_owned()
+        } else {
+            format!("{}/{}", self.group, self.version)
+        }
+    }
+
+    /// The type as `-o name` prefixes names with, `pod` or `deployment.apps`.
+    pub fn qualified_name(&self) -> String {
+        if self.group.is_empty() {
+            self.singular.to_owned()
+        } else {
+            format!("{}.{}", self.singular, self.group)
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        let (name, group) = name.split_once('.').unwrap_or((name, self.group));
+        group == self.group && (name == self.name || name == self.singular
+            || self.short_names.contains(&name) || name.eq_ignore_ascii_case(self.kind))
+    }
+}
+
+pub const RESOURCES: [Resource; 22] = [
+    Resource::new("bindings", "binding", &[], "", "v1", "Binding", true),
+    Resource::new("configmaps", "configmap", &["cm"], "", "v1", "ConfigMap", true),
+    Resource::new("endpoints", "endpoints", &["ep"], "", "v1", "Endpoints", true),
+    Resource::new("events", "event", &["ev"], "", "v1", "Event", true),
+    Resource::new("limitranges", "limitrange", &["limits"], "", "v1", "LimitRange", true),
+    Resource::new("namespaces", "namespace", &["ns"], "", "v1", "Namespace", false),
+    Resource::new("nodes", "node", &["no"], "", "v1", "Node", false),
+    Resource::new("persistentvolumeclaims", "persistentvolumeclaim", &["pvc"], "", "v1",
+        "PersistentVolumeClaim", true),
+    Resource::new("persistentvolumes", "persistentvolume", &["pv"], "", "v1",
+        "PersistentVolume", false),
+    Resource::new("pods", "pod", &["po"], "", "v1", "Pod", true),
+    Resource::new("resourcequotas", "resourcequota", &["quota"], "", "v1", "ResourceQuota",
+        true),
+    Resource::new("secrets", "secret", &[], "", "v1", "Secret", true),
+    Resource::new("serviceaccounts", "serviceaccount", &["sa"], "", "v1", "ServiceAccount",
+        true),
+    Resource::new("services", "service", &["svc"], "", "v1", "Service", true),
+    Resource::new("daemonsets", "daemonset", &["ds"], "apps", "v1", "DaemonSet", true),
+    Resource::new("deployments", "deployment", &["deploy"], "apps", "v1", "Deployment", true),
+    Resource::new("replicasets", "replicaset", &["rs"], "apps", "v1", "ReplicaSet", true),
+    Resource::new("statefulsets", "statefulset", &["sts"], "apps", "v1", "StatefulSet", true),
+    Resource::new("cronjobs", "cronjob", &["cj"], "batch", "v1", "CronJob", true),
+    Resource::new("jobs", "job", &[], "batch", "v1", "Job", true),
+    Resource::new("ingresses", "ingress", &["ing"], "networking.k8s.io", "v1", "Ingress",
+        true),
+    Resource::new("poddisruptionbudgets", "poddisruptionbudget", &["pdb"], "policy", "v1",
+        "PodDisruptionBudget", true)
+];
+
+/// The resource type `name` refers to, optionally qualified by its group as `deployments.apps`.
+pub fn find(name: &str) -> Option<&'static Resource> {
+    RESOURCES.iter().find(|resource| resource.matches(name))
+}
+
+/// The resource type of objects of `kind` in `api_version`.
+pub fn for_kind(api_version: &str, kind: &str) -> Option<&'static Resource> {
+    let group = api_version.rsplit_once('/').map_or("", |(group, _)| group);
+    RESOURCES.iter().find(|resource| resource.kind == kind && resource.group == group)
+}
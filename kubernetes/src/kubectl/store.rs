@@ -0,0 +1,102 @@
+/*!
+A local store of objects `kubectl` runs against instead of an API server, as decoded from manifests or fetched beforehand.
+
+Objects are kept unstructured, as the API serves them in JSON, so any kind can be stored, custom resources included.
+*/
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use super::Error;
+
+/// Identifies an object in a [`Store`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key {
+    pub kind: String,
+    /// `None` for cluster-scoped objects.
+    pub namespace: Option<String>,
+    pub name: String
+}
+
+impl Key {
+    /// The key of `object`, which must have a kind and a name.
+    pub fn of(object: &Value) -> Result<Self, Error> {
+        let kind = object.get("kind").and_then(Value::as_str)
+            .ok_or_else(|| Error::Invalid("object has no kind".to_owned()))?;
+        let name = lookup(object, "metadata.name").and_then(Value::as_str)
+            .ok_or_else(|| Error::Invalid(format!("{} has no name", kind)))?;
+        Ok(Self {
+            kind: kind.to_owned(),
+            namespace: lookup(object, "metadata.namespace").and_then(Value::as_str)
+                .map(str::to_owned),
+            name: name.to_owned()
+        })
+    }
+}
+
+/// Objects by kind, namespace and name.
+#[derive(Debug, Clone, Default)]
+pub struct Store {
+    objects: BTreeMap<Key, Value>
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `object`, returning the one it replaces.
+    pub fn insert(&mut self, object: Value) -> Result<Option<Value>, Error> {
+        Ok(self.objects.insert(Key::of(&object)?, object))
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&Value> {
+        self.objects.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &Key) -> Option<&mut Value> {
+        self.objects.get_mut(key)
+    }
+
+    pub fn remove(&mut self, key: &Key) -> Option<Value> {
+        self.objects.remove(key)
+    }
+
+    /// Objects of `kind`, in `namespace` or in all of them, sorted by namespace then name.
+    pub fn list<'a>(&'a self, kind: &'a str, namespace: Option<&'a str>)
+        -> impl Iterator<Item = (&'a Key, &'a Value)>
+    {
+        self.objects.iter().filter(move |(key, _)| {
+            key.kind == kind
+                && namespace.is_none_or(|namespace| key.namespace.as_deref() == Some(namespace))
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Value)> {
+        self.objects.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+/// The field of `object` at the dotted `path`, as `metadata.name` or `spec.containers.0.image`.
+pub fn lookup<'a>(object: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').filter(|field| !field.is_empty()).try_fold(object, |value, field| {
+        match value {
+            Value::Array(items) => items.get(field.parse::<usize>().ok()?),
+            _ => value.get(field)
+        }
+    })
+}
+
+/// The string at `path` in `object`, empty if there is none.
+pub fn string<'a>(object: &'a Value, path: &str) -> &'a str {
+    lookup(object, path).and_then(Value::as_str).unwrap_or_default()
+}
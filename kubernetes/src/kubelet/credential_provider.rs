@@ -1,27 +1,384 @@
-//! Reference <https://kubernetes.io/docs/reference/config-api/kubelet-credentialprovider.v1beta1/>
+//! - Concepts <https://kubernetes.io/docs/tasks/administer-cluster/kubelet-credential-provider/>
+//! - Reference <https://kubernetes.io/docs/reference/config-api/kubelet-credentialprovider.v1/>
+//! - Source <https://github.com/kubernetes/kubernetes/tree/master/pkg/credentialprovider/plugin>
 
-use std::{time::Duration, collections::HashMap};
+pub mod plugin;
 
-use kfl::Decode;
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{Read, Write},
+    time::Duration
+};
 
-/// <https://kubernetes.io/docs/reference/config-api/kubelet-credentialprovider.v1beta1/#credentialprovider-kubelet-k8s-io-v1beta1-CredentialProviderRequest>
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
+use serde_json::{json, Map, Value};
+
+use crate::{decode::Fields, time::{format_duration, parse_duration}};
+
+/// API versions of the exec protocol, newest first.
+pub const API_VERSIONS: [&str; 3] = [
+    "credentialprovider.kubelet.k8s.io/v1",
+    "credentialprovider.kubelet.k8s.io/v1beta1",
+    "credentialprovider.kubelet.k8s.io/v1alpha1"
+];
+
+/// <https://kubernetes.io/docs/reference/config-api/kubelet-credentialprovider.v1/#credentialprovider-kubelet-k8s-io-v1-CredentialProviderRequest>
 #[derive(Debug, Decode)]
 pub struct CredentialProviderRequest {
-    image: Option<String>
+    /// The container image that is being pulled as part of the credential provider plugin request. Images can optionally contain a tag or digest.
+    #[kfl(property, default)]
+    pub image: Option<String>
 }
 
-/// <https://kubernetes.io/docs/reference/config-api/kubelet-credentialprovider.v1beta1/#credentialprovider-kubelet-k8s-io-v1beta1-CredentialProviderResponse>
-#[derive(Debug, Decode)]
+/// <https://kubernetes.io/docs/reference/config-api/kubelet-credentialprovider.v1/#credentialprovider-kubelet-k8s-io-v1-CredentialProviderResponse>
+#[derive(Debug)]
 pub struct CredentialProviderResponse {
-    cache_key_type: PluginCacheKeyType,
-    cache_duration: Option<Duration>,
-    auth: Option<HashMap<String, AuthConfig>>
+    /// The type of caching key to use based on the image provided in the request.
+    pub cache_key_type: PluginCacheKeyType,
+    /// How long the provided credentials should be cached for. If empty, the kubelet uses the [`default_cache_duration`][crate::config::kubelet::CredentialProvider::default_cache_duration] of the provider, and zero disables caching.
+    pub cache_duration: Option<Duration>,
+    /// Authentication information by image pattern, in the [`match_images`][crate::config::kubelet::CredentialProvider::match_images] format. The kubelet uses every entry whose pattern matches the image being pulled.
+    pub auth: Option<HashMap<String, AuthConfig>>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for CredentialProviderResponse {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let credential_provider_response = Self {
+            cache_key_type: fields.property("cache-key-type")?.unwrap_or_default(),
+            cache_duration: fields.duration("cache-duration")?,
+            auth: Some(fields.nodes("auth")?).filter(|auth: &HashMap<_, _>| !auth.is_empty())
+        };
+        fields.finish()?;
+        Ok(credential_provider_response)
+    }
+}
+
+/// <https://kubernetes.io/docs/reference/config-api/kubelet-credentialprovider.v1/#credentialprovider-kubelet-k8s-io-v1-AuthConfig>
+#[derive(Debug, Decode, Clone, PartialEq, Eq)]
 pub struct AuthConfig {
-    username: String,
-    password: String
+    #[kfl(property, default)]
+    pub username: String,
+    #[kfl(property, default)]
+    pub password: String
 }
 
-type PluginCacheKeyType = String;
+/// <https://kubernetes.io/docs/reference/config-api/kubelet-credentialprovider.v1/#credentialprovider-kubelet-k8s-io-v1-PluginCacheKeyType>
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PluginCacheKeyType {
+    /// Credentials are cached for the image itself, tag or digest included.
+    #[default]
+    Image,
+    /// Credentials are cached for the registry host (and port) of the image.
+    Registry,
+    /// Credentials are cached for every image matched by the provider.
+    Global
+}
+
+impl PluginCacheKeyType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Image => "Image",
+            Self::Registry => "Registry",
+            Self::Global => "Global"
+        }
+    }
+}
+
+/**
+A credential provider, in or out of process.
+
+[`ExecPlugin`][plugin::ExecPlugin] runs a provider binary; implement this trait directly to write a provider in Rust, and [`serve`] it from the binary's `main`.
+*/
+pub trait CredentialProvider {
+    fn provide(&self, request: &CredentialProviderRequest)
+        -> Result<CredentialProviderResponse, Error>;
+}
+
+impl CredentialProviderRequest {
+    /// Serialises the request as it is written to the plugin's stdin.
+    pub fn to_json(&self, api_version: &str) -> String {
+        json!({
+            "apiVersion": api_version,
+            "kind": "CredentialProviderRequest",
+            "image": self.image.as_deref().unwrap_or_default()
+        }).to_string()
+    }
+
+    /// Parses a request and returns it along with the API version it was written in.
+    pub fn from_json(input: &[u8]) -> Result<(Self, String), Error> {
+        let object = object(input, "CredentialProviderRequest")?;
+        let image = match object.get("image") {
+            Some(Value::String(image)) if !image.is_empty() =>
+                Some(image.clone()),
+            _ => None
+        };
+        Ok((Self { image }, api_version(&object)?))
+    }
+}
+
+impl CredentialProviderResponse {
+    /// Serialises the response as a plugin writes it to stdout.
+    pub fn to_json(&self, api_version: &str) -> String {
+        let mut object = Map::new();
+        object.insert("apiVersion".to_owned(), api_version.into());
+        object.insert("kind".to_owned(), "CredentialProviderResponse".into());
+        object.insert("cacheKeyType".to_owned(),
+            self.cache_key_type.as_str().into());
+        if let Some(cache_duration) = &self.cache_duration {
+            object.insert("cacheDuration".to_owned(),
+                format_duration(cache_duration).into());
+        }
+        if let Some(auth) = &self.auth {
+            let auth = auth.iter()
+                .map(|(image, config)| (image.clone(), json!({
+                    "username": config.username,
+                    "password": config.password
+                })))
+                .collect();
+            object.insert("auth".to_owned(), Value::Object(auth));
+        }
+        Value::Object(object).to_string()
+    }
+
+    /// Parses a response and returns it along with the API version it was written in.
+    pub fn from_json(input: &[u8]) -> Result<(Self, String), Error> {
+        let object = object(input, "CredentialProviderResponse")?;
+        let cache_key_type = match object.get("cacheKeyType") {
+            Some(Value::String(value)) => match value.as_str() {
+                "Image" => PluginCacheKeyType::Image,
+                "Registry" => PluginCacheKeyType::Registry,
+                "Global" => PluginCacheKeyType::Global,
+                _ => return Err(Error::InvalidResponse(format!(
+                    "invalid cacheKeyType {:?}", value)))
+            },
+            _ => return Err(Error::InvalidResponse(
+                "cacheKeyType is required".to_owned()))
+        };
+        let cache_duration = match object.get("cacheDuration") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(value)) => Some(parse_duration(value)
+                .ok_or_else(|| Error::InvalidResponse(format!(
+                    "invalid cacheDuration {:?}", value)))?),
+            Some(value) => return Err(Error::InvalidResponse(format!(
+                "invalid cacheDuration {}", value)))
+        };
+        let auth = match object.get("auth") {
+            None | Some(Value::Null) => None,
+            Some(Value::Object(auth)) => Some(auth.iter()
+                .map(|(image, config)| {
+                    let field = |name| config.get(name)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_owned();
+                    (image.clone(), AuthConfig {
+                        username: field("username"),
+                        password: field("password")
+                    })
+                })
+                .collect()),
+            Some(value) => return Err(Error::InvalidResponse(format!(
+                "invalid auth {}", value)))
+        };
+        let response = Self { cache_key_type, cache_duration, auth };
+        Ok((response, api_version(&object)?))
+    }
+}
+
+fn object(input: &[u8], kind: &str) -> Result<Map<String, Value>, Error> {
+    let object = match serde_json::from_slice(input) {
+        Ok(Value::Object(object)) => object,
+        Ok(_) => return Err(Error::InvalidResponse(
+            "expected a JSON object".to_owned())),
+        Err(error) => return Err(Error::InvalidResponse(error.to_string()))
+    };
+    match object.get("kind").and_then(Value::as_str) {
+        Some(found) if found == kind => Ok(object),
+        found => Err(Error::InvalidResponse(format!(
+            "expected kind {}, got {:?}", kind, found.unwrap_or_default())))
+    }
+}
+
+fn api_version(object: &Map<String, Value>) -> Result<String, Error> {
+    match object.get("apiVersion").and_then(Value::as_str) {
+        Some(version) if API_VERSIONS.contains(&version) =>
+            Ok(version.to_owned()),
+        version => Err(Error::UnsupportedVersion(
+            version.unwrap_or_default().to_owned()))
+    }
+}
+
+/**
+Runs `provider` as an exec plugin: reads the request from `input`, writes the response to `output` in the same API version.
+
+```ignore
+fn main() {
+    credential_provider::serve(&MyProvider, std::io::stdin(), std::io::stdout())
+        .unwrap()
+}
+```
+*/
+pub fn serve<P: CredentialProvider + ?Sized>(
+    provider: &P,
+    mut input: impl Read,
+    mut output: impl Write
+) -> Result<(), Error> {
+    let mut request = Vec::new();
+    input.read_to_end(&mut request)
+        .map_err(|error| Error::Io(error.to_string()))?;
+    let (request, api_version) = CredentialProviderRequest::from_json(&request)?;
+    let response = provider.provide(&request)?;
+    output.write_all(response.to_json(&api_version).as_bytes())
+        .map_err(|error| Error::Io(error.to_string()))
+}
+
+/**
+Whether `image` is matched by `pattern`, one of [`match_images`][crate::config::kubelet::CredentialProvider::match_images] or a key of [`auth`][CredentialProviderResponse::auth].
+
+Both are read as URLs without scheme: the hosts must have as many dot-separated parts and each part of `image` must match the glob in `pattern`, the ports must be equal, and the path of `image` must start with the path of `pattern`.
+*/
+pub fn matches(pattern: &str, image: &str) -> bool {
+    let (pattern_host, pattern_port, pattern_path) = split(pattern);
+    let (host, port, path) = split(image);
+    let pattern_parts: Vec<&str> = pattern_host.split('.').collect();
+    let parts: Vec<&str> = host.split('.').collect();
+    pattern_parts.len() == parts.len()
+        && pattern_parts.iter().zip(&parts).all(|(pattern, part)|
+            glob::Pattern::new(pattern)
+                .is_ok_and(|pattern| pattern.matches(part)))
+        && pattern_port == port
+        && path.starts_with(pattern_path)
+}
+
+/// The registry host of `image`, port included, as used by [`PluginCacheKeyType::Registry`].
+pub fn registry(image: &str) -> &str {
+    let image = strip_scheme(image);
+    image.split_once('/').map_or(image, |(host, _)| host)
+}
+
+fn strip_scheme(url: &str) -> &str {
+    url.strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url)
+}
+
+/// `(host, port, path)`
+fn split(url: &str) -> (&str, Option<&str>, &str) {
+    let url = strip_scheme(url);
+    let (authority, path) = url.find('/')
+        .map_or((url, ""), |index| url.split_at(index));
+    match authority.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port), path),
+        None => (authority, None, path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// No configured provider has this name.
+    NotFound(String),
+    /// Reading from or writing to the plugin failed.
+    Io(String),
+    /// The plugin exited unsuccessfully.
+    Exec {
+        plugin: String,
+        status: Option<i32>,
+        stderr: String
+    },
+    /// The request or response is not valid.
+    InvalidResponse(String),
+    /// The API version is not one of [`API_VERSIONS`], or the response does not use the version of the request.
+    UnsupportedVersion(String),
+    /// The provider declined to give credentials.
+    Provider(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(name) =>
+                write!(f, "credential provider {} not found", name),
+            Self::Io(message) => write!(f, "{}", message),
+            Self::Exec { plugin, status, stderr } => write!(f,
+                "error execing credential provider plugin {} (exit status {}): {}",
+                plugin,
+                status.map_or("unknown".to_owned(), |status| status.to_string()),
+                stderr),
+            Self::InvalidResponse(message) =>
+                write!(f, "error decoding credential provider response: {}",
+                    message),
+            Self::UnsupportedVersion(version) =>
+                write!(f, "unsupported credential provider API version {:?}",
+                    version),
+            Self::Provider(message) => write!(f, "{}", message)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gives the same credentials for every image of its registry.
+    struct Registry;
+
+    impl CredentialProvider for Registry {
+        fn provide(&self, request: &CredentialProviderRequest)
+            -> Result<CredentialProviderResponse, Error>
+        {
+            let image = request.image.as_deref().unwrap_or_default();
+            Ok(CredentialProviderResponse {
+                cache_key_type: PluginCacheKeyType::Registry,
+                cache_duration: Some(Duration::from_secs(300)),
+                auth: Some([(registry(image).to_owned(), AuthConfig {
+                    username: "user".to_owned(),
+                    password: "token".to_owned()
+                })].into())
+            })
+        }
+    }
+
+    #[test]
+    fn serves_in_the_version_of_the_request() {
+        let request = CredentialProviderRequest {
+            image: Some("registry.example:5000/app:1".to_owned())
+        }.to_json(API_VERSIONS[1]);
+        let mut output = Vec::new();
+        serve(&Registry, request.as_bytes(), &mut output).unwrap();
+        let (response, api_version) = CredentialProviderResponse::from_json(&output).unwrap();
+        assert_eq!(api_version, API_VERSIONS[1]);
+        assert_eq!(response.cache_key_type, PluginCacheKeyType::Registry);
+        assert_eq!(response.cache_duration, Some(Duration::from_secs(300)));
+        assert_eq!(response.auth.unwrap()["registry.example:5000"].password, "token");
+
+        let request = br#"{"apiVersion": "credentialprovider.kubelet.k8s.io/v2", "kind": "CredentialProviderRequest"}"#;
+        assert_eq!(serve(&Registry, &request[..], Vec::new()),
+            Err(Error::UnsupportedVersion("credentialprovider.kubelet.k8s.io/v2".to_owned())));
+    }
+
+    #[test]
+    fn rejects_invalid_responses() {
+        let response = |json: &str| CredentialProviderResponse::from_json(json.as_bytes());
+        assert!(response(r#"{"apiVersion": "credentialprovider.kubelet.k8s.io/v1", "kind": "CredentialProviderResponse"}"#).is_err());
+        assert!(response(r#"{"apiVersion": "credentialprovider.kubelet.k8s.io/v1", "kind": "CredentialProviderRequest", "cacheKeyType": "Image"}"#).is_err());
+        assert!(response(r#"{"apiVersion": "credentialprovider.kubelet.k8s.io/v1", "kind": "CredentialProviderResponse", "cacheKeyType": "Image", "cacheDuration": "soon"}"#).is_err());
+        let (response, _) = response(r#"{"apiVersion": "credentialprovider.kubelet.k8s.io/v1", "kind": "CredentialProviderResponse", "cacheKeyType": "Global", "cacheDuration": "1h5m"}"#).unwrap();
+        assert_eq!(response.cache_duration, Some(Duration::from_secs(3900)));
+        assert_eq!(response.auth, None);
+    }
+
+    #[test]
+    fn matches_images() {
+        assert!(matches("*.dkr.ecr.*.amazonaws.com", "123.dkr.ecr.us-east-1.amazonaws.com/app:1"));
+        assert!(!matches("*.amazonaws.com", "123.dkr.ecr.us-east-1.amazonaws.com/app"));
+        assert!(matches("registry.example/team", "registry.example/team/app"));
+        assert!(!matches("registry.example/team", "registry.example/other/app"));
+        assert!(matches("registry.example:5000", "registry.example:5000/app"));
+        assert!(!matches("registry.example", "registry.example:5000/app"));
+        assert_eq!(registry("https://registry.example:5000/app:1"), "registry.example:5000");
+        assert_eq!(registry("nginx"), "nginx");
+    }
+}
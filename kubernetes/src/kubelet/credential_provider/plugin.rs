@@ -0,0 +1,239 @@
+//! The kubelet side of credential provider plugins: which plugins an image is handed to, running them, and caching what they return.
+//!
+//! Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/credentialprovider/plugin/plugin.go>
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant}
+};
+
+use crate::{
+    authentication::exec::{Runner, Stdin},
+    config::kubelet::{CredentialProvider as Config, CredentialProviderConfig}
+};
+use super::{
+    matches, registry, AuthConfig, CredentialProvider, CredentialProviderRequest,
+    CredentialProviderResponse, Error, PluginCacheKeyType
+};
+
+/// Cache key of [`PluginCacheKeyType::Global`] responses.
+pub const GLOBAL_CACHE_KEY: &str = "global";
+/// How long a plugin may run before it is killed, as the kubelet allows.
+pub const EXEC_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Runs a provider binary, writing the request to its stdin and reading the response from its stdout.
+#[derive(Debug)]
+pub struct ExecPlugin {
+    pub name: String,
+    pub command: PathBuf,
+    pub api_version: String,
+    pub args: Vec<String>,
+    /// Added to the kubelet's own environment.
+    pub env: Vec<(String, String)>,
+    /// [`EXEC_TIMEOUT`] unless changed.
+    pub timeout: Duration
+}
+
+impl ExecPlugin {
+    /// The plugin for `config`, whose binary is `config.name` in `bin_dir`.
+    pub fn new(config: &Config, bin_dir: &Path) -> Self {
+        Self {
+            name: config.name.clone(),
+            command: bin_dir.join(&config.name),
+            api_version: config.api_version.clone(),
+            args: config.args.clone(),
+            env: config.env.iter()
+                .map(|env| (env.name.clone(), env.value.clone()))
+                .collect(),
+            timeout: EXEC_TIMEOUT
+        }
+    }
+}
+
+impl CredentialProvider for ExecPlugin {
+    fn provide(&self, request: &CredentialProviderRequest)
+        -> Result<CredentialProviderResponse, Error>
+    {
+        let runner = Runner {
+            args: self.args.clone(),
+            env: self.env.clone(),
+            stdin: Stdin::Bytes(request.to_json(&self.api_version).into_bytes()),
+            timeout: Some(self.timeout),
+            ..Runner::new(&self.command)
+        };
+        let output = runner.run().map_err(|error| Error::Io(format!(
+            "credential provider plugin {}: {}", self.name, error)))?;
+        if !output.status.success() {
+            return Err(Error::Exec {
+                plugin: self.name.clone(),
+                status: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned()
+            })
+        }
+        let (response, api_version)
+            = CredentialProviderResponse::from_json(&output.stdout)?;
+        if api_version != self.api_version {
+            return Err(Error::UnsupportedVersion(api_version))
+        }
+        Ok(response)
+    }
+}
+
+struct CacheEntry {
+    expires_at: Instant,
+    auth: HashMap<String, AuthConfig>
+}
+
+/// A configured provider and the responses it gave.
+pub struct Plugin {
+    pub name: String,
+    pub match_images: Vec<String>,
+    pub default_cache_duration: Duration,
+    provider: Box<dyn CredentialProvider + Send + Sync>,
+    cache: Mutex<HashMap<String, CacheEntry>>
+}
+
+impl Plugin {
+    pub fn new(
+        name: String,
+        match_images: Vec<String>,
+        default_cache_duration: Duration,
+        provider: Box<dyn CredentialProvider + Send + Sync>
+    ) -> Self {
+        Self {
+            name,
+            match_images,
+            default_cache_duration,
+            provider,
+            cache: Mutex::new(HashMap::new())
+        }
+    }
+
+    /// Whether the image is matched by one of [`match_images`][Self::match_images].
+    pub fn is_image_allowed(&self, image: &str) -> bool {
+        self.match_images.iter().any(|pattern| matches(pattern, image))
+    }
+
+    /**
+    The credentials for `image`, from the cache if a response keyed by the image, its registry or [`GLOBAL_CACHE_KEY`] has not expired, else from the provider.
+
+    `now` is the instant cache expiry is checked against, [`Instant::now`] outside tests.
+    */
+    pub fn credentials(&self, image: &str, now: Instant)
+        -> Result<HashMap<String, AuthConfig>, Error>
+    {
+        {
+            let mut cache = self.cache.lock().unwrap();
+            cache.retain(|_, entry| entry.expires_at > now);
+            let cached = [image, registry(image), GLOBAL_CACHE_KEY].iter()
+                .find_map(|key| cache.get(*key));
+            if let Some(entry) = cached {
+                return Ok(entry.auth.clone())
+            }
+        }
+        let response = self.provider.provide(&CredentialProviderRequest {
+            image: Some(image.to_owned())
+        })?;
+        let auth = response.auth.unwrap_or_default();
+        let duration = response.cache_duration
+            .unwrap_or(self.default_cache_duration);
+        if !duration.is_zero() {
+            let key = match response.cache_key_type {
+                PluginCacheKeyType::Image => image,
+                PluginCacheKeyType::Registry => registry(image),
+                PluginCacheKeyType::Global => GLOBAL_CACHE_KEY
+            };
+            self.cache.lock().unwrap().insert(key.to_owned(), CacheEntry {
+                expires_at: now + duration,
+                auth: auth.clone()
+            });
+        }
+        Ok(auth)
+    }
+}
+
+/// The providers of a [`CredentialProviderConfig`], in configuration order.
+pub struct Plugins {
+    plugins: Vec<Plugin>
+}
+
+impl Plugins {
+    pub fn new(plugins: Vec<Plugin>) -> Self {
+        Self { plugins }
+    }
+
+    /// Exec plugins for every provider of `config`, their binaries looked up in `bin_dir` (`--image-credential-provider-bin-dir`).
+    pub fn from_config(config: &CredentialProviderConfig, bin_dir: &Path)
+        -> Result<Self, Error>
+    {
+        let plugins = config.providers.iter()
+            .map(|provider| {
+                if !super::API_VERSIONS.contains(&provider.api_version.as_str()) {
+                    return Err(Error::UnsupportedVersion(
+                        provider.api_version.clone()))
+                }
+                Ok(Plugin::new(
+                    provider.name.clone(),
+                    provider.match_images.clone(),
+                    provider.default_cache_duration.unwrap_or_default(),
+                    Box::new(ExecPlugin::new(provider, bin_dir))))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(plugins))
+    }
+
+    pub fn plugins(&self) -> &[Plugin] {
+        &self.plugins
+    }
+
+    /**
+    The credentials to try when pulling `image`: those of every plugin matching the image, keeping the entries of their [`auth`][CredentialProviderResponse::auth] whose pattern matches it, the most specific pattern first.
+
+    A failing plugin is skipped as the kubelet does, and its error returned only if no credentials were found.
+    */
+    pub fn lookup(&self, image: &str) -> Result<Vec<AuthConfig>, Error> {
+        let now = Instant::now();
+        let mut found = Vec::new();
+        let mut failure = None;
+        for plugin in self.plugins.iter()
+            .filter(|plugin| plugin.is_image_allowed(image))
+        {
+            match plugin.credentials(image, now) {
+                Ok(auth) => found.extend(auth.into_iter()
+                    .filter(|(pattern, _)| matches(pattern, image))),
+                Err(error) => failure = Some(error)
+            }
+        }
+        if found.is_empty() {
+            if let Some(error) = failure {
+                return Err(error)
+            }
+        }
+        found.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then(a.cmp(b)));
+        Ok(found.into_iter().map(|(_, config)| config).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugins_time_out() {
+        let plugin = ExecPlugin {
+            name: "sleeper".to_owned(),
+            command: PathBuf::from("sh"),
+            api_version: "credentialprovider.kubelet.k8s.io/v1".to_owned(),
+            args: vec!["-c".to_owned(), "sleep 10".to_owned()],
+            env: Vec::new(),
+            timeout: Duration::from_millis(100)
+        };
+        let error = plugin.provide(&CredentialProviderRequest {
+            image: Some("registry.example/app".to_owned())
+        }).unwrap_err();
+        assert!(matches!(&error, Error::Io(message) if message.contains("timed out")),
+            "{}", error);
+    }
+}
@@ -2,20 +2,37 @@
 
 use std::path::PathBuf;
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
+
+use crate::decode::Fields;
 
 type PathOrUrl = PathBuf;
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Kustomization {
-    resources: Vec<PathOrUrl>,
-    generators: Vec<PathOrUrl>,
-    transformers: Vec<PathOrUrl>,
-    validators: Vec<PathOrUrl>,
-    build_metadata: Vec<BuildMetadata>,
+    pub resources: Vec<PathOrUrl>,
+    pub generators: Vec<PathOrUrl>,
+    pub transformers: Vec<PathOrUrl>,
+    pub validators: Vec<PathOrUrl>,
+    pub build_metadata: Vec<BuildMetadata>,
 }
 
-#[derive(Debug, Decode)]
+impl Decode for Kustomization {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let kustomization = Self {
+            resources: fields.values("resources")?,
+            generators: fields.values("generators")?,
+            transformers: fields.values("transformers")?,
+            validators: fields.values("validators")?,
+            build_metadata: fields.values("build-metadata")?
+        };
+        fields.finish()?;
+        Ok(kustomization)
+    }
+}
+
+#[derive(Debug, Clone, DecodeScalar)]
 pub enum BuildMetadata {
     ManagedByLabel,
     OriginAnnotations,
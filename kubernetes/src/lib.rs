@@ -11,10 +11,12 @@ pub mod authorisation;
 pub mod autoscaling;
 pub mod batch;
 pub mod certificates;
+pub mod client;
 pub mod config;
 pub mod coordination;
 pub mod core;
-// pub mod cri;
+pub mod cri;
+pub mod decode;
 pub mod discovery;
 pub mod events;
 pub mod kind;
@@ -29,13 +31,14 @@ pub mod node_selector;
 pub mod policy;
 pub mod port_status;
 pub mod protocol;
+pub mod proxy;
 pub mod quantity;
 pub mod sigs;
 pub mod storage;
 pub mod time;
-mod cri;
 
-use kfl::{Decode, DecodeScalar, ast::Literal};
+use kfl::DecodeScalar;
+use serde_json::Value;
 
 // use config::{
 //     api_server::AdmissionConfiguration
@@ -74,22 +77,54 @@ use kfl::{Decode, DecodeScalar, ast::Literal};
 //     Storage(Storage)
 // }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IntOrString {
     Int(i32),
     String(String)
 }
 
-impl<S: kfl::traits::ErrorSpan> DecodeScalar<S> for IntOrString {
-    fn decode(value: &kfl::ast::Value<S>, ctx: &mut kfl::decode::Context<S>)
-        -> Result<Self, kfl::errors::DecodeError<S>>
-    {
-        match &value.literal {
-            Literal::Int(_) => Ok(Self::Int(i32::decode(value, ctx))),
-            Literal::String(_) => Ok(Self::String(String::decode(value, ctx))),
-            _ => Err(kfl::errors::DecodeError::scalar_kind(
-                     kfl::decode::Kind::String,  // TODO(rnarkk) or Int
-                     &value.literal))
+impl IntOrString {
+    /// Resolves this value against `total`: integers are taken as they are, percentages such as `"25%"` are scaled and rounded up or down. Returns `None` for any other string.
+    pub fn scaled_value(&self, total: i32, round_up: bool) -> Option<i32> {
+        match self {
+            Self::Int(value) => Some(*value),
+            Self::String(value) => {
+                let percent: i64 = value.strip_suffix('%')?.parse().ok()?;
+                let scaled = percent * i64::from(total);
+                let value = if round_up {
+                    (scaled + 99).div_euclid(100)
+                } else {
+                    scaled.div_euclid(100)
+                };
+                i32::try_from(value).ok()
+            }
+        }
+    }
+
+    /// A number or a string in JSON.
+    pub fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(number) => number.as_i64()
+                .and_then(|number| i32::try_from(number).ok())
+                .map(Self::Int),
+            Value::String(string) => Some(Self::String(string.clone())),
+            _ => None
         }
     }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            Self::Int(value) => (*value).into(),
+            Self::String(value) => value.clone().into()
+        }
+    }
+}
+
+impl DecodeScalar for IntOrString {
+    fn decode(scalar: &kfl::ast::Scalar, ctx: &mut kfl::context::Context)
+        -> Result<Self, kfl::errors::DecodeError>
+    {
+        let value = String::decode(scalar, ctx)?;
+        Ok(value.parse().map_or(Self::String(value), Self::Int))
+    }
 }
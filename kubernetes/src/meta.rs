@@ -1,7 +1,9 @@
 pub mod condition;
+pub mod garbage_collection;
 pub mod label_selector;
 pub mod list_metadata;
 pub mod metadata;
+pub mod patch;
 pub mod status;
 
 pub use condition::Condition;
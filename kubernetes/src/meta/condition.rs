@@ -1,39 +1,217 @@
-use kfl::Decode;
+use std::{
+    fmt,
+    str::FromStr,
+    time::SystemTime
+};
+
+use kfl::{
+    ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar
+};
+use serde_json::{json, Value};
 
 use crate::time::Time;
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone)]
 pub struct Condition<T = ConditionType> {
     /// Status of the condition, one of True, False, Unknown.
-    status: ConditionStatus,
+    pub status: ConditionStatus,
     /// Type of the condition.
-    r#type: T,
+    pub r#type: T,
     /// Last time the condition transit from one status to another.
-    last_transition_time: Time,
-    last_update_time: Time,
+    pub last_transition_time: Time,
+    pub last_update_time: Time,
     /// Human readable message indicating details about last transition.
-    message: String,
-    /// (brief) reason for the condition's last transition.
-    reason: Reason,
+    pub message: String,
+    /// (brief) reason for the condition's last transition, in CamelCase.
+    pub reason: String,
+}
+
+impl<T: DecodeScalar> Decode for Condition<T> {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let status = property(node, "status", ctx)?
+            .ok_or_else(|| DecodeError::missing(ctx.span(&node), "property `status` is required"))?;
+        let r#type = property(node, "type", ctx)?
+            .ok_or_else(|| DecodeError::missing(ctx.span(&node), "property `type` is required"))?;
+        let last_transition_time = property(node, "last-transition-time", ctx)?
+            .unwrap_or(Time(SystemTime::UNIX_EPOCH));
+        Ok(Self {
+            status,
+            r#type,
+            last_transition_time,
+            last_update_time: property(node, "last-update-time", ctx)?
+                .unwrap_or(last_transition_time),
+            message: property(node, "message", ctx)?.unwrap_or_default(),
+            reason: property(node, "reason", ctx)?.unwrap_or_default()
+        })
+    }
+}
+
+fn property<P: DecodeScalar>(node: &Node, name: &str, ctx: &mut Context)
+    -> Result<Option<P>, DecodeError>
+{
+    node.properties.get(name).map(|scalar| P::decode(scalar, ctx)).transpose()
+}
+
+impl<T> Condition<T> {
+    pub fn new(r#type: T, status: ConditionStatus, reason: &str, message: &str, now: Time)
+        -> Self
+    {
+        Self {
+            status,
+            r#type,
+            last_transition_time: now,
+            last_update_time: now,
+            message: message.to_owned(),
+            reason: reason.to_owned()
+        }
+    }
+}
+
+impl<T: FromStr> Condition<T> {
+    /// A condition in JSON, `None` if its type is not one of `T`.
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let string = |key: &str| value.get(key).and_then(Value::as_str).unwrap_or_default();
+        let last_transition_time = string("lastTransitionTime").parse()
+            .unwrap_or(Time(SystemTime::UNIX_EPOCH));
+        Some(Self {
+            status: string("status").parse().unwrap_or_default(),
+            r#type: string("type").parse().ok()?,
+            last_transition_time,
+            last_update_time: string("lastUpdateTime").parse().unwrap_or(last_transition_time),
+            message: string("message").to_owned(),
+            reason: string("reason").to_owned()
+        })
+    }
+}
+
+impl<T: fmt::Display> Condition<T> {
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            "type": self.r#type.to_string(),
+            "status": self.status.to_string(),
+            "lastTransitionTime": self.last_transition_time.to_string()
+        });
+        if self.last_update_time != self.last_transition_time {
+            value["lastUpdateTime"] = self.last_update_time.to_string().into();
+        }
+        if !self.reason.is_empty() {
+            value["reason"] = self.reason.clone().into();
+        }
+        if !self.message.is_empty() {
+            value["message"] = self.message.clone().into();
+        }
+        value
+    }
+}
+
+/// The condition of `type` among `conditions`.
+pub fn find<'a, T: PartialEq>(conditions: &'a [Condition<T>], r#type: &T) -> Option<&'a Condition<T>> {
+    conditions.iter().find(|condition| condition.r#type == *r#type)
+}
+
+/// Whether the condition of `type` is `True`.
+pub fn is_true<T: PartialEq>(conditions: &[Condition<T>], r#type: &T) -> bool {
+    find(conditions, r#type).is_some_and(|condition| condition.status == ConditionStatus::True)
+}
+
+/**
+Sets `condition` in place of that of its type, keeping the last transition time of the old one if its status is the same, and returns whether anything but the update time changed.
+
+Source <https://github.com/kubernetes/apimachinery/blob/master/pkg/api/meta/conditions.go>
+*/
+pub fn set<T: PartialEq>(conditions: &mut Vec<Condition<T>>, condition: Condition<T>) -> bool {
+    let Some(old) = conditions.iter_mut().find(|old| old.r#type == condition.r#type) else {
+        conditions.push(condition);
+        return true
+    };
+    let changed = old.status != condition.status || old.reason != condition.reason
+        || old.message != condition.message;
+    if old.status != condition.status {
+        old.status = condition.status;
+        old.last_transition_time = condition.last_transition_time;
+    }
+    old.reason = condition.reason;
+    old.message = condition.message;
+    old.last_update_time = condition.last_update_time;
+    changed
 }
 
-#[derive(Debug, Decode)]
+/// Removes the condition of `type`, returning whether there was one.
+pub fn remove<T: PartialEq>(conditions: &mut Vec<Condition<T>>, r#type: &T) -> bool {
+    let len = conditions.len();
+    conditions.retain(|condition| condition.r#type != *r#type);
+    conditions.len() != len
+}
+
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ConditionStatus {
     True,
     False,
+    #[default]
     Unknown
 }
 
-#[derive(Debug, Decode)]
-pub enum Reason {
-    ReplicaSetUpdated,
-    MinimumReplicasAvailable,
-    FailedCreate
-}
-
-#[derive(Debug, Decode)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
 pub enum ConditionType {
     Progressing,
     Available,
     ReplicaFailure
 }
+
+impl ConditionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::True => "True",
+            Self::False => "False",
+            Self::Unknown => "Unknown"
+        }
+    }
+}
+
+impl fmt::Display for ConditionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ConditionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "True" => Ok(Self::True),
+            "False" => Ok(Self::False),
+            "Unknown" => Ok(Self::Unknown),
+            _ => Err(format!("unknown condition status {}", s))
+        }
+    }
+}
+
+impl ConditionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Progressing => "Progressing",
+            Self::Available => "Available",
+            Self::ReplicaFailure => "ReplicaFailure"
+        }
+    }
+}
+
+impl fmt::Display for ConditionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ConditionType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Progressing" => Ok(Self::Progressing),
+            "Available" => Ok(Self::Available),
+            "ReplicaFailure" => Ok(Self::ReplicaFailure),
+            _ => Err(format!("unknown condition type {}", s))
+        }
+    }
+}
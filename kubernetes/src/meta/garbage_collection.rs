@@ -0,0 +1,527 @@
+/*!
+The owner graph of objects, and cascading deletion through it, as kube-controller-manager's `garbagecollector` controller carries it out, to predict what `kubectl delete --cascade` removes.
+
+Objects are linked to their owners by [`OwnerReference`]s matched by UID. A reference only resolves to an owner in the same namespace, or to a cluster-scoped one; the others are dangling, as those to absent owners are. Objects all of whose owners are absent are collected.
+
+- Concepts <https://kubernetes.io/docs/concepts/architecture/garbage-collection/>
+- Concepts <https://kubernetes.io/docs/concepts/overview/working-with-objects/owners-dependents/>
+- Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/garbagecollector/garbagecollector.go>
+*/
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    str::FromStr
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::time::Time;
+
+use super::metadata::{Metadata, OwnerReference};
+
+/// Finaliser keeping an owner deleted in the foreground until its blocking dependents are gone.
+pub const FOREGROUND_DELETION: &str = "foregroundDeletion";
+/// Finaliser keeping an owner deleted with orphaning until its dependents no longer refer to it.
+pub const ORPHAN: &str = "orphan";
+
+/// <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/delete-options/#DeleteOptions>
+///
+/// What happens to the dependents of a deleted object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PropagationPolicy {
+    /// The object is kept, with the [`FOREGROUND_DELETION`] finaliser, until its dependents are deleted.
+    Foreground,
+    /// The object is deleted at once and its dependents after it.
+    #[default]
+    Background,
+    /// The dependents are kept without their references to the object.
+    Orphan
+}
+
+impl PropagationPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Foreground => "Foreground",
+            Self::Background => "Background",
+            Self::Orphan => "Orphan"
+        }
+    }
+}
+
+impl fmt::Display for PropagationPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PropagationPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Foreground" => Ok(Self::Foreground),
+            "Background" => Ok(Self::Background),
+            "Orphan" => Ok(Self::Orphan),
+            _ => Err(Error::PropagationPolicy(s.to_owned()))
+        }
+    }
+}
+
+/// An object of any kind in the graph.
+#[derive(Debug, Clone, Default)]
+pub struct Object {
+    pub api_version: String,
+    pub kind: String,
+    pub metadata: Metadata
+}
+
+impl Object {
+    pub fn new(api_version: &str, kind: &str, metadata: Metadata) -> Self {
+        Self { api_version: api_version.to_owned(), kind: kind.to_owned(), metadata }
+    }
+
+    /// The fields of the unstructured `object` the garbage collector looks at.
+    pub fn from_json(object: &Value) -> Self {
+        let string = |value: &Value, key: &str| value.get(key).and_then(Value::as_str)
+            .unwrap_or_default().to_owned();
+        let metadata = object.get("metadata").cloned().unwrap_or_default();
+        let list = |key: &str| metadata.get(key).and_then(Value::as_array).cloned()
+            .unwrap_or_default();
+        Self::new(&string(object, "apiVersion"), &string(object, "kind"), Metadata {
+            name: Some(string(&metadata, "name")),
+            namespace: metadata.get("namespace").and_then(Value::as_str).map(str::to_owned),
+            uid: Some(string(&metadata, "uid")),
+            finalisers: list("finalizers").iter()
+                .filter_map(|finaliser| Some(finaliser.as_str()?.to_owned()))
+                .collect(),
+            owner_references: list("ownerReferences").iter().map(OwnerReference::from_json)
+                .collect(),
+            deletion_timestamp: metadata.get("deletionTimestamp").and_then(Value::as_str)
+                .and_then(|time| time.parse().ok()),
+            ..Metadata::default()
+        })
+    }
+
+    /**
+    Writes what the garbage collector changes, the finalisers, owner references and deletion timestamp, to the metadata of the unstructured `object`. A deletion timestamp `object` already has is kept as it is written.
+    */
+    pub fn write_json(&self, object: &mut Value) {
+        let Some(metadata) = object.get_mut("metadata").and_then(Value::as_object_mut) else {
+            return
+        };
+        sync(metadata, "finalizers", (!self.metadata.finalisers.is_empty())
+            .then(|| json!(self.metadata.finalisers)));
+        sync(metadata, "ownerReferences", (!self.metadata.owner_references.is_empty())
+            .then(|| Value::Array(self.metadata.owner_references.iter().map(OwnerReference::to_json).collect())));
+        match self.metadata.deletion_timestamp {
+            Some(time) => {
+                metadata.entry("deletionTimestamp").or_insert_with(|| Value::String(time.to_string()));
+            }
+            None => {
+                metadata.remove("deletionTimestamp");
+            }
+        }
+    }
+
+    pub fn uid(&self) -> &str {
+        self.metadata.uid.as_deref().unwrap_or_default()
+    }
+
+    pub fn is_deleting(&self) -> bool {
+        self.metadata.deletion_timestamp.is_some()
+    }
+
+    /// Sets the deletion timestamp and the finaliser `policy` calls for, unless the object is already being deleted, as the API server does when it is deleted.
+    pub fn mark_deleted(&mut self, policy: PropagationPolicy, now: Time) {
+        if self.is_deleting() {
+            return
+        }
+        self.metadata.deletion_timestamp = Some(now);
+        let finaliser = match policy {
+            PropagationPolicy::Foreground => FOREGROUND_DELETION,
+            PropagationPolicy::Orphan => ORPHAN,
+            PropagationPolicy::Background => return
+        };
+        if !self.has_finaliser(finaliser) {
+            self.metadata.finalisers.push(finaliser.to_owned());
+        }
+    }
+
+    fn has_finaliser(&self, finaliser: &str) -> bool {
+        self.metadata.finalisers.iter().any(|name| name == finaliser)
+    }
+
+    fn remove_finaliser(&mut self, finaliser: &str) {
+        self.metadata.finalisers.retain(|name| name != finaliser);
+    }
+
+    /// Whether this object is being deleted in the foreground, so its dependents are deleted regardless of their other owners.
+    fn is_waiting_for_dependents(&self) -> bool {
+        self.is_deleting() && self.has_finaliser(FOREGROUND_DELETION)
+    }
+}
+
+/// What a deletion or a collection did, by UID.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deletion {
+    /// Objects removed from the graph, in the order they were.
+    pub removed: Vec<String>,
+    /// Objects kept after dropping their references to deleted owners.
+    pub orphaned: Vec<String>,
+    /// Objects marked for deletion but still kept by finalisers, or by dependents blocking them in cycles.
+    pub terminating: Vec<String>
+}
+
+/// Objects by UID, linked to their owners by owner references.
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    objects: BTreeMap<String, Object>
+}
+
+impl Graph {
+    /// Builds the graph of `objects`, each of which must have a UID unique among them.
+    pub fn new(objects: impl IntoIterator<Item = Object>) -> Result<Self, Error> {
+        let mut graph = Self::default();
+        for object in objects {
+            graph.insert(object)?;
+        }
+        Ok(graph)
+    }
+
+    pub fn insert(&mut self, object: Object) -> Result<(), Error> {
+        let uid = match object.uid() {
+            "" => return Err(Error::MissingUid(object.metadata.name.unwrap_or_default())),
+            uid => uid.to_owned()
+        };
+        if self.objects.contains_key(&uid) {
+            return Err(Error::DuplicateUid(uid))
+        }
+        self.objects.insert(uid, object);
+        Ok(())
+    }
+
+    pub fn get(&self, uid: &str) -> Option<&Object> {
+        self.objects.get(uid)
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = &Object> {
+        self.objects.values()
+    }
+
+    /// The owner `reference` of `dependent` resolves to, if it is present in the graph and may own it.
+    pub fn owner(&self, dependent: &Object, reference: &OwnerReference) -> Option<&Object> {
+        let owner = self.objects.get(&reference.uid)?;
+        match &owner.metadata.namespace {
+            Some(namespace) if dependent.metadata.namespace.as_ref() != Some(namespace) => None,
+            _ => Some(owner)
+        }
+    }
+
+    /// The owners of the object with `uid` present in the graph.
+    pub fn owners(&self, uid: &str) -> Vec<&Object> {
+        let Some(object) = self.objects.get(uid) else {
+            return Vec::new()
+        };
+        object.metadata.owner_references.iter()
+            .filter_map(|reference| self.owner(object, reference))
+            .collect()
+    }
+
+    /// The objects with an owner reference resolving to the object with `uid`.
+    pub fn dependents(&self, uid: &str) -> Vec<&Object> {
+        self.objects.values()
+            .filter(|object| object.metadata.owner_references.iter().any(|reference| {
+                reference.uid == uid && self.owner(object, reference).is_some()
+            }))
+            .collect()
+    }
+
+    /// UIDs of the objects with owner references, none of which resolves, which the garbage collector deletes.
+    pub fn orphans(&self) -> Vec<&str> {
+        self.objects.iter()
+            .filter(|(_, object)| !object.metadata.owner_references.is_empty()
+                && object.metadata.owner_references.iter()
+                    .all(|reference| self.owner(object, reference).is_none()))
+            .map(|(uid, _)| uid.as_str())
+            .collect()
+    }
+
+    /**
+    Cycles of ownership, each as the UIDs of the objects in it, sorted. Objects in a cycle can be deleted in the background, but deleting any of them in the foreground waits forever unless a reference in the cycle does not block owner deletion.
+    */
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        // Tarjan's strongly connected components over the edges from dependents to owners.
+        struct Search<'a> {
+            graph: &'a Graph,
+            index: BTreeMap<&'a str, usize>,
+            low: BTreeMap<&'a str, usize>,
+            stack: Vec<&'a str>,
+            on_stack: BTreeSet<&'a str>,
+            cycles: Vec<Vec<String>>
+        }
+
+        impl<'a> Search<'a> {
+            /// Numbers `uid` and returns it with its owners, none of them followed yet.
+            fn enter(&mut self, uid: &'a str) -> (&'a str, Vec<&'a str>, usize) {
+                let index = self.index.len();
+                self.index.insert(uid, index);
+                self.low.insert(uid, index);
+                self.stack.push(uid);
+                self.on_stack.insert(uid);
+                let owners = self.graph.owners(uid).into_iter().map(Object::uid).collect();
+                (uid, owners, 0)
+            }
+
+            /// Visits what `root` leads to depth first, the objects being visited on a stack of their own so that long chains of owners do not overflow the call stack.
+            fn visit(&mut self, root: &'a str) {
+                let mut visiting = vec![self.enter(root)];
+                while let Some((uid, owners, next)) = visiting.last_mut() {
+                    let uid = *uid;
+                    if let Some(&owner) = owners.get(*next) {
+                        *next += 1;
+                        if !self.index.contains_key(owner) {
+                            let frame = self.enter(owner);
+                            visiting.push(frame);
+                        } else if self.on_stack.contains(owner) {
+                            let low = self.low[uid].min(self.index[owner]);
+                            self.low.insert(uid, low);
+                        }
+                        continue
+                    }
+                    let self_owned = owners.contains(&uid);
+                    visiting.pop();
+                    if let Some(&(dependent, ..)) = visiting.last() {
+                        let low = self.low[dependent].min(self.low[uid]);
+                        self.low.insert(dependent, low);
+                    }
+                    if self.low[uid] == self.index[uid] {
+                        let mut component = Vec::new();
+                        while let Some(member) = self.stack.pop() {
+                            self.on_stack.remove(member);
+                            component.push(member.to_owned());
+                            if member == uid {
+                                break
+                            }
+                        }
+                        if component.len() > 1 || self_owned {
+                            component.sort();
+                            self.cycles.push(component);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut search = Search {
+            graph: self,
+            index: BTreeMap::new(),
+            low: BTreeMap::new(),
+            stack: Vec::new(),
+            on_stack: BTreeSet::new(),
+            cycles: Vec::new()
+        };
+        for uid in self.objects.keys() {
+            if !search.index.contains_key(uid.as_str()) {
+                search.visit(uid);
+            }
+        }
+        search.cycles.sort();
+        search.cycles
+    }
+
+    /**
+    Deletes the object with `uid` as the API server does given `policy`, then [collects][Self::collect] until nothing changes, returning what was removed, orphaned and left terminating.
+
+    An object already being deleted keeps the policy it was deleted with.
+    */
+    pub fn delete(&mut self, uid: &str, policy: PropagationPolicy, now: Time)
+        -> Result<Deletion, Error>
+    {
+        let object = self.objects.get_mut(uid).ok_or_else(|| Error::NotFound(uid.to_owned()))?;
+        object.mark_deleted(policy, now);
+        Ok(self.collect(now))
+    }
+
+    /**
+    Runs the garbage collector until nothing changes:
+
+    - objects being deleted in the foreground lose their [`FOREGROUND_DELETION`] finaliser once no dependent with [`block_owner_deletion`][OwnerReference::block_owner_deletion] is left;
+    - objects being deleted with orphaning have their references dropped by their dependents, then lose their [`ORPHAN`] finaliser;
+    - objects being deleted without finalisers are removed;
+    - objects none of whose owners are left but being deleted in the foreground are deleted, in the foreground themselves if they have dependents; objects with other owners drop their references to the absent and deleting ones instead.
+    */
+    pub fn collect(&mut self, now: Time) -> Deletion {
+        let mut deletion = Deletion::default();
+        let mut orphaned = BTreeSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let uids: Vec<String> = self.objects.keys().cloned().collect();
+            for uid in uids {
+                changed |= self.process(&uid, now, &mut deletion, &mut orphaned);
+            }
+        }
+        deletion.orphaned = orphaned.into_iter()
+            .filter(|uid| self.objects.contains_key(uid))
+            .collect();
+        deletion.terminating = self.objects.iter()
+            .filter(|(_, object)| object.is_deleting())
+            .map(|(uid, _)| uid.clone())
+            .collect();
+        deletion
+    }
+
+    /// Takes one step on the object with `uid`, returning whether anything changed.
+    fn process(
+        &mut self,
+        uid: &str,
+        now: Time,
+        deletion: &mut Deletion,
+        orphaned: &mut BTreeSet<String>
+    ) -> bool {
+        let Some(object) = self.objects.get(uid) else {
+            return false
+        };
+        if object.is_deleting() {
+            if object.has_finaliser(ORPHAN) {
+                for dependent in self.dependents(uid).iter().map(|object| object.uid().to_owned())
+                    .collect::<Vec<_>>()
+                {
+                    let dependent = self.objects.get_mut(&dependent).unwrap();
+                    dependent.metadata.owner_references.retain(|reference| reference.uid != uid);
+                    orphaned.insert(dependent.uid().to_owned());
+                }
+                self.objects.get_mut(uid).unwrap().remove_finaliser(ORPHAN);
+                return true
+            }
+            if object.has_finaliser(FOREGROUND_DELETION) {
+                let blocked = self.dependents(uid).iter().any(|dependent| {
+                    dependent.metadata.owner_references.iter().any(|reference| {
+                        reference.uid == uid && reference.block_owner_deletion == Some(true)
+                    })
+                });
+                if blocked {
+                    return false
+                }
+                self.objects.get_mut(uid).unwrap().remove_finaliser(FOREGROUND_DELETION);
+                return true
+            }
+            if object.metadata.finalisers.is_empty() {
+                self.objects.remove(uid);
+                deletion.removed.push(uid.to_owned());
+                return true
+            }
+            return false
+        }
+        if object.metadata.owner_references.is_empty() {
+            return false
+        }
+        let (mut solid, mut released) = (false, Vec::new());
+        let mut waiting = false;
+        for reference in &object.metadata.owner_references {
+            match self.owner(object, reference) {
+                None => released.push(reference.uid.clone()),
+                Some(owner) if owner.is_waiting_for_dependents() => {
+                    waiting = true;
+                    released.push(reference.uid.clone());
+                }
+                Some(_) => solid = true
+            }
+        }
+        if solid {
+            if released.is_empty() {
+                return false
+            }
+            let object = self.objects.get_mut(uid).unwrap();
+            object.metadata.owner_references
+                .retain(|reference| !released.contains(&reference.uid));
+            orphaned.insert(uid.to_owned());
+            return true
+        }
+        let policy = if waiting && !self.dependents(uid).is_empty() {
+            PropagationPolicy::Foreground
+        } else {
+            PropagationPolicy::Background
+        };
+        self.objects.get_mut(uid).unwrap().mark_deleted(policy, now);
+        true
+    }
+}
+
+fn sync(metadata: &mut Map<String, Value>, field: &str, value: Option<Value>) {
+    match value {
+        Some(value) => metadata.insert(field.to_owned(), value),
+        None => metadata.remove(field)
+    };
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// An object without a UID, by name.
+    MissingUid(String),
+    DuplicateUid(String),
+    NotFound(String),
+    PropagationPolicy(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingUid(name) => write!(f, "object \"{}\" has no uid", name),
+            Self::DuplicateUid(uid) => write!(f, "duplicate uid \"{}\"", uid),
+            Self::NotFound(uid) => write!(f, "no object with uid \"{}\"", uid),
+            Self::PropagationPolicy(policy) =>
+                write!(f, "invalid propagation policy \"{}\"", policy)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A pod with `uid`, owned by the pods with `owners`.
+    fn pod(uid: &str, owners: &[&str]) -> Object {
+        Object::new("v1", "Pod", Metadata {
+            name: Some(uid.to_owned()),
+            namespace: Some("default".to_owned()),
+            uid: Some(uid.to_owned()),
+            owner_references: owners.iter().map(|owner| OwnerReference {
+                api_version: "v1".to_owned(),
+                kind: "Pod".to_owned(),
+                name: owner.to_string(),
+                uid: owner.to_string(),
+                block_owner_deletion: Some(true),
+                controller: None
+            }).collect(),
+            ..Metadata::default()
+        })
+    }
+
+    #[test]
+    fn cycles() {
+        let graph = Graph::new([
+            pod("a", &["b"]),
+            pod("b", &["c"]),
+            pod("c", &["a"]),
+            pod("d", &["a", "d"]),
+            pod("e", &["d", "f"]),
+            pod("f", &[])
+        ]).unwrap();
+        assert_eq!(graph.cycles(), [vec!["a", "b", "c"], vec!["d"]]);
+    }
+
+    #[test]
+    fn long_chains_of_owners() {
+        let uids: Vec<String> = (0..100_000).map(|index| format!("{:06}", index)).collect();
+        let mut objects: Vec<Object> = uids.windows(2)
+            .map(|pair| pod(&pair[0], &[pair[1].as_str()]))
+            .collect();
+        objects.push(pod(&uids[uids.len() - 1], &[uids[0].as_str()]));
+        let graph = Graph::new(objects).unwrap();
+        assert_eq!(graph.cycles(), [uids]);
+    }
+}
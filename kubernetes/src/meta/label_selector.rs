@@ -1,12 +1,19 @@
 //! - Reference <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/label-selector/>
 //! - Concepts <https://kubernetes.io/docs/concepts/overview/working-with-objects/labels/>
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr
+};
 
-use kfl::{Decode, DecodeScalar};
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
+use serde_json::{json, Map, Value};
+
+use crate::decode::Fields;
 
 /// A label selector is a label query over a set of resources. The result of matchLabels and matchExpressions are ANDed. An empty label selector matches all objects. A null label selector matches no objects.
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, Default)]
 pub struct Selector {
     /// List of label selector requirements. The requirements are ANDed.
     match_expressions: Vec<LabelSelectorRequirement>,
@@ -14,8 +21,131 @@ pub struct Selector {
     match_labels: HashMap<String, String>
 }
 
+impl Decode for Selector {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let selector = Self {
+            match_expressions: fields.children("label-selector-requirement")?,
+            match_labels: fields.entries("match-labels")?
+        };
+        fields.finish()?;
+        Ok(selector)
+    }
+}
+
+impl Selector {
+    /// Whether `labels` satisfy every requirement of this selector. An empty selector matches everything.
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.match_labels.iter()
+            .all(|(key, value)| labels.get(key) == Some(value))
+        && self.match_expressions.iter()
+            .all(|requirement| requirement.matches(labels))
+    }
+
+    /// A selector in JSON. Requirements with an unknown operator are dropped.
+    pub fn from_json(value: &Value) -> Self {
+        fn array<'a>(value: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+            value.get(key).and_then(Value::as_array).into_iter().flatten()
+        }
+        Self {
+            match_expressions: array(value, "matchExpressions").filter_map(|requirement| Some(
+                LabelSelectorRequirement {
+                    key: requirement.get("key")?.as_str()?.to_owned(),
+                    operator: requirement.get("operator")?.as_str()?.parse().ok()?,
+                    values: array(requirement, "values")
+                        .filter_map(|value| Some(value.as_str()?.to_owned()))
+                        .collect()
+                }))
+                .collect(),
+            match_labels: value.get("matchLabels").and_then(Value::as_object).into_iter().flatten()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_owned())))
+                .collect()
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        if !self.match_labels.is_empty() {
+            object.insert("matchLabels".to_owned(), json!(self.match_labels));
+        }
+        if !self.match_expressions.is_empty() {
+            object.insert("matchExpressions".to_owned(), self.match_expressions.iter()
+                .map(|requirement| json!({
+                    "key": requirement.key,
+                    "operator": requirement.operator.as_str(),
+                    "values": requirement.values
+                }))
+                .collect());
+        }
+        Value::Object(object)
+    }
+}
+
+impl FromStr for Selector {
+    type Err = Error;
+
+    /**
+    Parses the string form of selectors, as `kubectl get -l` and the `labelSelector` query parameter take: comma-separated requirements among `key`, `!key`, `key=value`, `key==value`, `key!=value`, `key in (a,b)` and `key notin (a,b)`.
+    */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error(s.to_owned());
+        let mut selector = Self::default();
+        let mut rest = s.trim();
+        while !rest.is_empty() {
+            // Commas inside the parentheses of a set do not separate requirements.
+            let end = match rest.find(['(', ',']) {
+                Some(index) if rest.as_bytes()[index] == b'(' => rest[index..].find(')')
+                    .map(|close| index + close + 1)
+                    .ok_or_else(invalid)?,
+                Some(index) => index,
+                None => rest.len()
+            };
+            let requirement = rest[..end].trim();
+            rest = rest[end..].trim_start();
+            if !rest.is_empty() {
+                rest = rest.strip_prefix(',').ok_or_else(invalid)?.trim_start();
+            }
+            let (key, operator, values) = if let Some(key) = requirement.strip_prefix('!') {
+                (key, Operator::DoesNotExist, Vec::new())
+            } else if let Some((key, value)) = requirement.split_once("!=") {
+                (key, Operator::NotIn, vec![value.trim().to_owned()])
+            } else if let Some((key, value)) = requirement.split_once('=') {
+                let value = value.strip_prefix('=').unwrap_or(value).trim();
+                selector.match_labels.insert(key.trim().to_owned(), value.to_owned());
+                continue
+            } else if let Some((key, set)) = requirement.split_once('(') {
+                let key = key.trim_end();
+                let (key, operator) = if let Some(key) = key.strip_suffix(" notin") {
+                    (key, Operator::NotIn)
+                } else if let Some(key) = key.strip_suffix(" in") {
+                    (key, Operator::In)
+                } else {
+                    return Err(invalid())
+                };
+                let values = set.trim_end_matches(')').split(',')
+                    .map(|value| value.trim().to_owned())
+                    .filter(|value| !value.is_empty())
+                    .collect();
+                (key, operator, values)
+            } else {
+                (requirement, Operator::Exists, Vec::new())
+            };
+            let key = key.trim();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(invalid())
+            }
+            selector.match_expressions.push(LabelSelectorRequirement {
+                key: key.to_owned(),
+                operator,
+                values
+            });
+        }
+        Ok(selector)
+    }
+}
+
 /// A label selector requirement is a selector that contains values, a key, and an operator that relates the key and values.
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone)]
 pub struct LabelSelectorRequirement {
     /// key is the label key that the selector applies to.
     key: String,
@@ -25,10 +155,74 @@ pub struct LabelSelectorRequirement {
     values: Vec<String>
 }
 
-#[derive(Debug, DecodeScalar)]
+impl Decode for LabelSelectorRequirement {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let label_selector_requirement = Self {
+            key: fields.property("key")?.unwrap_or_default(),
+            operator: fields.property("operator")?.unwrap_or_default(),
+            values: fields.values("values")?
+        };
+        fields.finish()?;
+        Ok(label_selector_requirement)
+    }
+}
+
+impl LabelSelectorRequirement {
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        match self.operator {
+            Operator::In => labels.get(&self.key)
+                .is_some_and(|value| self.values.contains(value)),
+            Operator::NotIn => labels.get(&self.key)
+                .is_none_or(|value| !self.values.contains(value)),
+            Operator::Exists => labels.contains_key(&self.key),
+            Operator::DoesNotExist => !labels.contains_key(&self.key)
+        }
+    }
+}
+
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Operator {
+    #[default]
     In,
     NotIn,
     Exists,
     DoesNotExist
 }
+
+impl Operator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::In => "In",
+            Self::NotIn => "NotIn",
+            Self::Exists => "Exists",
+            Self::DoesNotExist => "DoesNotExist"
+        }
+    }
+}
+
+impl FromStr for Operator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "In" => Ok(Self::In),
+            "NotIn" => Ok(Self::NotIn),
+            "Exists" => Ok(Self::Exists),
+            "DoesNotExist" => Ok(Self::DoesNotExist),
+            _ => Err(format!("unknown operator {}", s))
+        }
+    }
+}
+
+/// A selector that is not one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(pub String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unable to parse requirement: \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,11 +1,48 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/list-meta/>
 
 use kfl::Decode;
+use serde_json::{Map, Value};
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
 pub struct ListMeta {
-    r#continue: String,
-    remaining_item_count: Option<i64>,
-    resource_version: Option<String>,
-    self_link: Option<String>
+    /// Token to get the next page of a list with, empty on the last page. It expires with the `resourceVersion` it was issued at, after which the server answers `410 Gone`.
+    #[kfl(property, default)]
+    pub r#continue: String,
+    /// Number of items left after this page, if the server could tell.
+    #[kfl(property, default)]
+    pub remaining_item_count: Option<i64>,
+    /// Version of the list, to start watching from.
+    #[kfl(property, default)]
+    pub resource_version: Option<String>,
+    #[kfl(property, default)]
+    pub self_link: Option<String>
+}
+
+impl ListMeta {
+    pub fn from_json(value: &Value) -> Self {
+        let string = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_owned);
+        Self {
+            r#continue: string("continue").unwrap_or_default(),
+            remaining_item_count: value.get("remainingItemCount").and_then(Value::as_i64),
+            resource_version: string("resourceVersion"),
+            self_link: string("selfLink")
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        if !self.r#continue.is_empty() {
+            object.insert("continue".to_owned(), self.r#continue.clone().into());
+        }
+        if let Some(count) = self.remaining_item_count {
+            object.insert("remainingItemCount".to_owned(), count.into());
+        }
+        if let Some(version) = &self.resource_version {
+            object.insert("resourceVersion".to_owned(), version.clone().into());
+        }
+        if let Some(link) = &self.self_link {
+            object.insert("selfLink".to_owned(), link.clone().into());
+        }
+        Value::Object(object)
+    }
 }
@@ -1,15 +1,15 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/object-meta/>
 
 use std::collections::HashMap;
-use kfl::{Decode, DecodeScalar};
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
+use serde_json::{json, Map, Value};
 
-use crate::time::Time;
+use crate::{decode::Fields, time::Time};
 
 /// Metadata that all persisted resources must have, which includes all objects users must create.
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone, Default)]
 pub struct Metadata {
     /// Name must be unique within a namespace. Is required when creating resources, although some resources may allow a client to request the generation of an appropriate name automatically. Name is primarily intended for creation idempotence and configuration definition. Cannot be updated. More info: <http://kubernetes.io/docs/user-guide/identifiers#names>
-    #[kfl(argument)]
     pub name: Option<String>,
     /// Optional prefix, used by the server, to generate a unique name ONLY IF the Name field has not been provided. If this field is used, the name returned to the client will be different than the name passed. This value will also be combined with a unique suffix. The provided value has the same validation rules as the Name field, and may be truncated by the length of the suffix required to make the value unique on the server.
     ///
@@ -29,48 +29,202 @@ pub struct Metadata {
     // System
 
     /// <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/object-meta/#System>
-    finalisers: Vec<String>,
-    managed_fields: Vec<ManagedFieldsEntry>,
-    owner_references: Vec<OwnerReference>,
+    pub finalisers: Vec<String>,
+    pub managed_fields: Vec<ManagedFieldsEntry>,
+    pub owner_references: Vec<OwnerReference>,
     // Read-Only
     /// <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/object-meta/#Read-only>
-    creation_timestamp: Option<Time>,
-    deletion_grace_period_seconds: Option<i64>,
-    deletion_timestamp: Option<Time>,
-    generation: Option<i64>,
-    resource_version: Option<String>,
-    self_link: Option<String>,
-    uid: Option<String>
+    pub creation_timestamp: Option<Time>,
+    pub deletion_grace_period_seconds: Option<i64>,
+    pub deletion_timestamp: Option<Time>,
+    pub generation: Option<i64>,
+    pub resource_version: Option<String>,
+    pub self_link: Option<String>,
+    pub uid: Option<String>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for Metadata {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let metadata = Self {
+            name: fields.argument()?,
+            generate_name: fields.property("generate-name")?,
+            namespace: fields.property("namespace")?,
+            labels: fields.entries("labels")?,
+            annotations: fields.entries("annotations")?,
+            finalisers: fields.values("finalisers")?,
+            managed_fields: fields.children("managed-fields-entry")?,
+            owner_references: fields.children("owner-reference")?,
+            creation_timestamp: fields.property("creation-timestamp")?,
+            deletion_grace_period_seconds: fields.property("deletion-grace-period-seconds")?,
+            deletion_timestamp: fields.property("deletion-timestamp")?,
+            generation: fields.property("generation")?,
+            resource_version: fields.property("resource-version")?,
+            self_link: fields.property("self-link")?,
+            uid: fields.property("uid")?
+        };
+        fields.finish()?;
+        Ok(metadata)
+    }
+}
+
+impl Metadata {
+    /// Whether the object is being deleted, kept until its finalisers are removed.
+    pub fn is_being_deleted(&self) -> bool {
+        self.deletion_timestamp.is_some()
+    }
+
+    pub fn has_finaliser(&self, finaliser: &str) -> bool {
+        self.finalisers.iter().any(|name| name == finaliser)
+    }
+
+    /// Adds `finaliser` unless present, returning whether it was added.
+    pub fn add_finaliser(&mut self, finaliser: &str) -> bool {
+        if self.has_finaliser(finaliser) {
+            return false
+        }
+        self.finalisers.push(finaliser.to_owned());
+        true
+    }
+
+    /// Removes `finaliser`, returning whether it was present.
+    pub fn remove_finaliser(&mut self, finaliser: &str) -> bool {
+        let len = self.finalisers.len();
+        self.finalisers.retain(|name| name != finaliser);
+        self.finalisers.len() != len
+    }
+
+    /// The owner reference marked as the controller of the object.
+    pub fn controller(&self) -> Option<&OwnerReference> {
+        self.owner_references.iter().find(|reference| reference.controller == Some(true))
+    }
+
+    /// The metadata of an object in JSON, but its managed fields. Fields of the wrong type are taken as absent.
+    pub fn from_json(value: &Value) -> Self {
+        let string = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_owned);
+        let time = |key: &str| value.get(key).and_then(Value::as_str)
+            .and_then(|time| time.parse().ok());
+        let map = |key: &str| value.get(key).and_then(Value::as_object).into_iter().flatten()
+            .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_owned())))
+            .collect();
+        let array = |key: &str| value.get(key).and_then(Value::as_array).into_iter().flatten();
+        Self {
+            name: string("name"),
+            generate_name: string("generateName"),
+            namespace: string("namespace"),
+            labels: map("labels"),
+            annotations: map("annotations"),
+            finalisers: array("finalizers")
+                .filter_map(|finaliser| Some(finaliser.as_str()?.to_owned()))
+                .collect(),
+            managed_fields: Vec::new(),
+            owner_references: array("ownerReferences").map(OwnerReference::from_json).collect(),
+            creation_timestamp: time("creationTimestamp"),
+            deletion_grace_period_seconds: value.get("deletionGracePeriodSeconds")
+                .and_then(Value::as_i64),
+            deletion_timestamp: time("deletionTimestamp"),
+            generation: value.get("generation").and_then(Value::as_i64),
+            resource_version: string("resourceVersion"),
+            self_link: string("selfLink"),
+            uid: string("uid")
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        let mut insert = |key: &str, value: Option<Value>| if let Some(value) = value {
+            object.insert(key.to_owned(), value);
+        };
+        insert("name", self.name.clone().map(Value::from));
+        insert("generateName", self.generate_name.clone().map(Value::from));
+        insert("namespace", self.namespace.clone().map(Value::from));
+        insert("labels", (!self.labels.is_empty()).then(|| json!(self.labels)));
+        insert("annotations", (!self.annotations.is_empty()).then(|| json!(self.annotations)));
+        insert("finalizers", (!self.finalisers.is_empty()).then(|| json!(self.finalisers)));
+        insert("ownerReferences", (!self.owner_references.is_empty()).then(||
+            self.owner_references.iter().map(OwnerReference::to_json).collect()));
+        insert("creationTimestamp", self.creation_timestamp.map(|time| time.to_string().into()));
+        insert("deletionGracePeriodSeconds", self.deletion_grace_period_seconds.map(Value::from));
+        insert("deletionTimestamp", self.deletion_timestamp.map(|time| time.to_string().into()));
+        insert("generation", self.generation.map(Value::from));
+        insert("resourceVersion", self.resource_version.clone().map(Value::from));
+        insert("selfLink", self.self_link.clone().map(Value::from));
+        insert("uid", self.uid.clone().map(Value::from));
+        Value::Object(object)
+    }
+}
+
+#[derive(Debug, Decode, Clone, Default)]
 pub struct ManagedFieldsEntry {
-    api_version: Option<String>,
-    fields_type: Option<FieldsType>,
+    #[kfl(property, default)]
+    pub api_version: Option<String>,
+    #[kfl(property, default)]
+    pub fields_type: Option<FieldsType>,
     // fieldsV1: Option<fieldsV1>,
-    manager: Option<String>,
-    operation: Option<ManagedFieldsEntryOperation>,
-    subresource: Option<String>,
-    time: Option<Time>
+    #[kfl(property, default)]
+    pub manager: Option<String>,
+    #[kfl(property, default)]
+    pub operation: Option<ManagedFieldsEntryOperation>,
+    #[kfl(property, default)]
+    pub subresource: Option<String>,
+    #[kfl(property, default)]
+    pub time: Option<Time>
 }
 
-#[derive(Debug, DecodeScalar)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
 pub enum FieldsType {
     FieldsV1
 }
 
-#[derive(Debug, DecodeScalar)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
 pub enum ManagedFieldsEntryOperation {
     Apply,
     Update
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone, Default)]
 pub struct OwnerReference {
-    api_version: String,
-    kind: String,
-    name: String,
-    uid: String,
-    block_owner_deletion: Option<bool>,
-    controller: Option<bool>
+    #[kfl(property, default)]
+    pub api_version: String,
+    #[kfl(property, default)]
+    pub kind: String,
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub uid: String,
+    #[kfl(property, default)]
+    pub block_owner_deletion: Option<bool>,
+    #[kfl(property, default)]
+    pub controller: Option<bool>
+}
+
+impl OwnerReference {
+    pub fn from_json(value: &Value) -> Self {
+        let string = |key: &str| value.get(key).and_then(Value::as_str).unwrap_or_default()
+            .to_owned();
+        Self {
+            api_version: string("apiVersion"),
+            kind: string("kind"),
+            name: string("name"),
+            uid: string("uid"),
+            block_owner_deletion: value.get("blockOwnerDeletion").and_then(Value::as_bool),
+            controller: value.get("controller").and_then(Value::as_bool)
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            "apiVersion": self.api_version,
+            "kind": self.kind,
+            "name": self.name,
+            "uid": self.uid
+        });
+        if let Some(block) = self.block_owner_deletion {
+            value["blockOwnerDeletion"] = Value::Bool(block);
+        }
+        if let Some(controller) = self.controller {
+            value["controller"] = Value::Bool(controller);
+        }
+        value
+    }
 }
@@ -0,0 +1,163 @@
+/*!
+Patches of unstructured objects, as the API server applies them to `application/merge-patch+json` and `application/json-patch+json` requests.
+
+- Reference <https://www.rfc-editor.org/rfc/rfc7386>
+- Reference <https://www.rfc-editor.org/rfc/rfc6902>
+*/
+
+use serde_json::{Map, Value};
+
+/// Applies a JSON merge patch to `target`: nulls delete fields, objects are merged and anything else replaces.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let fields = target.as_object_mut().unwrap();
+    for (key, value) in patch {
+        if value.is_null() {
+            fields.remove(key);
+        } else {
+            merge_patch(fields.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/**
+Applies a JSON patch, RFC 6902, to `target`: `add`, `remove`, `replace`, `move`, `copy` and `test` operations at JSON pointers, `""` being the whole document. Either all operations apply or, if one fails, none.
+*/
+pub fn json_patch(target: &mut Value, operations: &Value) -> Result<(), String> {
+    let operations = operations.as_array().ok_or("a JSON patch must be a list of operations")?;
+    let mut patched = target.clone();
+    for operation in operations {
+        let field = |key: &str| operation.get(key).and_then(Value::as_str)
+            .ok_or_else(|| format!("operation {} has no {}", operation, key));
+        let value = || operation.get("value").cloned()
+            .ok_or_else(|| format!("operation {} has no value", operation));
+        let path = pointer(field("path")?)?;
+        match field("op")? {
+            "add" => add(&mut patched, &path, value()?)?,
+            "remove" => {
+                remove(&mut patched, &path)?;
+            }
+            "replace" => {
+                let value = value()?;
+                *value_at(&mut patched, &path)? = value;
+            }
+            "move" => {
+                let from = pointer(field("from")?)?;
+                if path.len() > from.len() && path.starts_with(&from) {
+                    return Err(format!("cannot move {} into itself", field("from")?))
+                }
+                let moved = remove(&mut patched, &from)?;
+                add(&mut patched, &path, moved)?;
+            }
+            "copy" => {
+                let from = field("from")?;
+                let copied = patched.pointer(from).cloned()
+                    .ok_or_else(|| format!("no value at {}", from))?;
+                add(&mut patched, &path, copied)?;
+            }
+            "test" => if patched.pointer(field("path")?) != Some(&value()?) {
+                return Err(format!("test of {} failed", field("path")?))
+            },
+            op => return Err(format!("unknown operation {:?}", op))
+        }
+    }
+    *target = patched;
+    Ok(())
+}
+
+/// The unescaped tokens of a JSON pointer, none for the whole document.
+fn pointer(path: &str) -> Result<Vec<String>, String> {
+    if path.is_empty() {
+        return Ok(Vec::new())
+    }
+    let tokens = path.strip_prefix('/').ok_or_else(|| format!("invalid JSON pointer {:?}", path))?;
+    Ok(tokens.split('/').map(|token| token.replace("~1", "/").replace("~0", "~")).collect())
+}
+
+/// The value at `tokens`, `target` itself if there are none.
+fn value_at<'a>(target: &'a mut Value, tokens: &[String]) -> Result<&'a mut Value, String> {
+    tokens.iter().try_fold(target, |value, token| match value {
+        Value::Object(fields) => fields.get_mut(token),
+        Value::Array(items) => token.parse::<usize>().ok().and_then(|index| items.get_mut(index)),
+        _ => None
+    }.ok_or_else(|| format!("no value at /{}", tokens.join("/"))))
+}
+
+fn add(target: &mut Value, path: &[String], value: Value) -> Result<(), String> {
+    let Some((last, tokens)) = path.split_last() else {
+        *target = value;
+        return Ok(())
+    };
+    match value_at(target, tokens)? {
+        Value::Object(fields) => {
+            fields.insert(last.clone(), value);
+        }
+        Value::Array(items) if last == "-" => items.push(value),
+        Value::Array(items) => match last.parse::<usize>() {
+            Ok(index) if index <= items.len() => items.insert(index, value),
+            _ => return Err(format!("invalid index {:?}", last))
+        },
+        _ => return Err(format!("no object or list at /{}", tokens.join("/")))
+    }
+    Ok(())
+}
+
+/// Removes the value at `path` and returns it; removing the whole document leaves `null`.
+fn remove(target: &mut Value, path: &[String]) -> Result<Value, String> {
+    let Some((last, tokens)) = path.split_last() else {
+        return Ok(target.take())
+    };
+    match value_at(target, tokens)? {
+        Value::Object(fields) => fields.remove(last),
+        Value::Array(items) => last.parse::<usize>().ok().filter(|index| *index < items.len())
+            .map(|index| items.remove(index)),
+        _ => None
+    }.ok_or_else(|| format!("no value at /{}", path.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn json_patches() {
+        let mut object = json!({"spec": {"replicas": 1, "ports": [80]}});
+        json_patch(&mut object, &json!([
+            {"op": "replace", "path": "/spec/replicas", "value": 3},
+            {"op": "add", "path": "/spec/ports/-", "value": 443},
+            {"op": "move", "from": "/spec/ports", "path": "/ports"},
+            {"op": "test", "path": "/ports/1", "value": 443}
+        ])).unwrap();
+        assert_eq!(object, json!({"spec": {"replicas": 3}, "ports": [80, 443]}));
+        let error = json_patch(&mut object, &json!([
+            {"op": "remove", "path": "/ports"},
+            {"op": "replace", "path": "/status", "value": {}}
+        ])).unwrap_err();
+        assert_eq!(error, "no value at /status");
+        assert_eq!(object, json!({"spec": {"replicas": 3}, "ports": [80, 443]}));
+        assert!(json_patch(&mut object, &json!([{"op": "move", "from": "/spec", "path": "/spec/old"}]))
+            .is_err());
+    }
+
+    #[test]
+    fn json_patches_of_the_whole_document() {
+        let mut object = json!({"kind": "Pod"});
+        json_patch(&mut object, &json!([
+            {"op": "replace", "path": "", "value": {"kind": "Service"}}
+        ])).unwrap();
+        assert_eq!(object, json!({"kind": "Service"}));
+        json_patch(&mut object, &json!([
+            {"op": "remove", "path": ""},
+            {"op": "add", "path": "", "value": [1]}
+        ])).unwrap();
+        assert_eq!(object, json!([1]));
+    }
+}
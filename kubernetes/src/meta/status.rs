@@ -1,27 +1,313 @@
-//! Reference <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/status/>
+//! - Reference <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/status/>
+//! - Source <https://github.com/kubernetes/apimachinery/blob/master/pkg/api/errors/errors.go>
 
-use kfl::Decode;
+use std::fmt;
 
-use crate::meta::list_metadata::ListMeta;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
+use serde_json::{json, Map, Value};
 
-#[derive(Debug, Decode)]
+use crate::{decode::Fields, meta::list_metadata::ListMeta};
+
+/// The request is missing or has invalid credentials.
+pub const UNAUTHORIZED: &str = "Unauthorized";
+pub const FORBIDDEN: &str = "Forbidden";
+pub const NOT_FOUND: &str = "NotFound";
+pub const ALREADY_EXISTS: &str = "AlreadyExists";
+/// The object was modified since it was read, its `resourceVersion` no longer matches.
+pub const CONFLICT: &str = "Conflict";
+/// The resource version asked for is no longer available.
+pub const GONE: &str = "Gone";
+/// A watch or a `continue` token started from a resource version that has been compacted.
+pub const EXPIRED: &str = "Expired";
+pub const INVALID: &str = "Invalid";
+pub const BAD_REQUEST: &str = "BadRequest";
+pub const METHOD_NOT_ALLOWED: &str = "MethodNotAllowed";
+pub const UNSUPPORTED_MEDIA_TYPE: &str = "UnsupportedMediaType";
+pub const TOO_MANY_REQUESTS: &str = "TooManyRequests";
+pub const INTERNAL_ERROR: &str = "InternalError";
+pub const SERVICE_UNAVAILABLE: &str = "ServiceUnavailable";
+
+/// What the API server returns for errors and for deletions that are not of a single object.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Status {
-    code: Option<i32>,
-    details: Option<StatusDetails>,
-    kind: Option<String>,
-    message: Option<String>,
-    metadata: Option<ListMeta>,
-    reason: Option<String>,
-    status: Option<StatusStatus>
+    /// HTTP status code.
+    pub code: Option<i32>,
+    pub details: Option<StatusDetails>,
+    pub message: Option<String>,
+    pub metadata: Option<ListMeta>,
+    /// Why the request failed, in CamelCase as [`NOT_FOUND`]; empty if the server did not say.
+    pub reason: Option<String>,
+    pub status: Option<StatusStatus>
+}
+
+impl Decode for Status {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let status = Self {
+            code: fields.property("code")?,
+            details: fields.child("details")?,
+            message: fields.property("message")?,
+            metadata: fields.child("metadata")?,
+            reason: fields.property("reason")?,
+            status: fields.property("status")?
+        };
+        fields.finish()?;
+        Ok(status)
+    }
 }
 
-#[derive(Debug, Decode)]
+/// <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/status/#StatusDetails>
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
 pub struct StatusDetails {
-    
+    #[kfl(property, default)]
+    pub name: Option<String>,
+    #[kfl(property, default)]
+    pub group: Option<String>,
+    /// The resource, as `pods`, despite its name.
+    #[kfl(property, default)]
+    pub kind: Option<String>,
+    #[kfl(property, default)]
+    pub uid: Option<String>,
+    #[kfl(children)]
+    pub causes: Vec<StatusCause>,
+    /// Seconds to wait before retrying.
+    #[kfl(property, default)]
+    pub retry_after_seconds: Option<i32>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Clone, Default, PartialEq, Eq)]
+pub struct StatusCause {
+    /// Path of the field at fault, as `spec.containers[0].image`.
+    #[kfl(property, default)]
+    pub field: Option<String>,
+    #[kfl(property, default)]
+    pub message: Option<String>,
+    /// What is wrong with the field, as `FieldValueRequired`.
+    #[kfl(property, default)]
+    pub reason: Option<String>
+}
+
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
 pub enum StatusStatus {
     Success,
     Failure
 }
+
+impl Status {
+    pub fn success() -> Self {
+        Self {
+            status: Some(StatusStatus::Success),
+            ..Default::default()
+        }
+    }
+
+    pub fn failure(code: i32, reason: &str, message: String) -> Self {
+        Self {
+            code: Some(code),
+            message: Some(message),
+            reason: Some(reason.to_owned()),
+            status: Some(StatusStatus::Failure),
+            ..Default::default()
+        }
+    }
+
+    /// `404`, as `pods "web" not found`; `group` is empty for the core group.
+    pub fn not_found(group: &str, resource: &str, name: &str) -> Self {
+        Self::failure(404, NOT_FOUND, format!("{} \"{}\" not found", qualified(group, resource), name))
+            .with_details(group, resource, name)
+    }
+
+    /// `409`, as `pods "web" already exists`.
+    pub fn already_exists(group: &str, resource: &str, name: &str) -> Self {
+        Self::failure(409, ALREADY_EXISTS,
+            format!("{} \"{}\" already exists", qualified(group, resource), name))
+            .with_details(group, resource, name)
+    }
+
+    /// `409`, for an update with a stale `resourceVersion`.
+    pub fn conflict(group: &str, resource: &str, name: &str) -> Self {
+        Self::failure(409, CONFLICT, format!(
+            "Operation cannot be fulfilled on {} \"{}\": the object has been modified; please apply \
+            your changes to the latest version and try again", qualified(group, resource), name))
+            .with_details(group, resource, name)
+    }
+
+    /// `410`, for a watch or list from a compacted resource version.
+    pub fn expired(message: String) -> Self {
+        Self::failure(410, EXPIRED, message)
+    }
+
+    /// `422`, as `Pod "web" is invalid: ...`, one cause per field.
+    pub fn invalid(group: &str, kind: &str, name: &str, causes: Vec<StatusCause>) -> Self {
+        let messages: Vec<String> = causes.iter()
+            .map(|cause| format!("{}: {}", cause.field.as_deref().unwrap_or_default(),
+                cause.message.as_deref().unwrap_or_default()))
+            .collect();
+        let message = match messages.as_slice() {
+            [message] => message.clone(),
+            messages => format!("[{}]", messages.join(", "))
+        };
+        let mut status = Self::failure(422, INVALID, format!("{} \"{}\" is invalid: {}",
+            if group.is_empty() { kind.to_owned() } else { format!("{}.{}", kind, group) },
+            name, message))
+            .with_details(group, kind, name);
+        if let Some(details) = status.details.as_mut() {
+            details.causes = causes;
+        }
+        status
+    }
+
+    pub fn bad_request(message: String) -> Self {
+        Self::failure(400, BAD_REQUEST, message)
+    }
+
+    fn with_details(mut self, group: &str, kind: &str, name: &str) -> Self {
+        self.details = Some(StatusDetails {
+            name: Some(name.to_owned()),
+            group: (!group.is_empty()).then(|| group.to_owned()),
+            kind: Some(kind.to_owned()),
+            ..Default::default()
+        });
+        self
+    }
+
+    /// Whether the reason is `reason`, or the code is its code if the server gave no reason.
+    fn is(&self, reason: &str, code: i32) -> bool {
+        match self.reason.as_deref() {
+            Some(own) if !own.is_empty() => own == reason,
+            _ => self.code == Some(code)
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.is(NOT_FOUND, 404)
+    }
+
+    pub fn is_already_exists(&self) -> bool {
+        self.reason.as_deref() == Some(ALREADY_EXISTS)
+    }
+
+    pub fn is_conflict(&self) -> bool {
+        self.is(CONFLICT, 409)
+    }
+
+    /// Whether a watch or list must start over from a new list, for [`GONE`] or [`EXPIRED`].
+    pub fn is_gone(&self) -> bool {
+        self.code == Some(410) || matches!(self.reason.as_deref(), Some(GONE | EXPIRED))
+    }
+
+    pub fn is_unauthorized(&self) -> bool {
+        self.is(UNAUTHORIZED, 401)
+    }
+
+    pub fn from_json(value: &Value) -> Self {
+        let string = |value: &Value, key: &str| value.get(key).and_then(Value::as_str)
+            .map(str::to_owned);
+        Self {
+            code: value.get("code").and_then(Value::as_i64).and_then(|code| i32::try_from(code).ok()),
+            details: value.get("details").filter(|details| details.is_object()).map(|details| StatusDetails {
+                name: string(details, "name"),
+                group: string(details, "group"),
+                kind: string(details, "kind"),
+                uid: string(details, "uid"),
+                causes: details.get("causes").and_then(Value::as_array).into_iter().flatten()
+                    .map(|cause| StatusCause {
+                        field: string(cause, "field"),
+                        message: string(cause, "message"),
+                        reason: string(cause, "reason")
+                    })
+                    .collect(),
+                retry_after_seconds: details.get("retryAfterSeconds").and_then(Value::as_i64)
+                    .and_then(|seconds| i32::try_from(seconds).ok())
+            }),
+            message: string(value, "message"),
+            metadata: value.get("metadata").filter(|metadata| metadata.is_object())
+                .map(ListMeta::from_json),
+            reason: string(value, "reason"),
+            status: match value.get("status").and_then(Value::as_str) {
+                Some("Success") => Some(StatusStatus::Success),
+                Some("Failure") => Some(StatusStatus::Failure),
+                _ => None
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        object.insert("apiVersion".to_owned(), "v1".into());
+        object.insert("kind".to_owned(), "Status".into());
+        object.insert("metadata".to_owned(),
+            self.metadata.as_ref().map_or_else(|| json!({}), ListMeta::to_json));
+        if let Some(status) = self.status {
+            object.insert("status".to_owned(), status.as_str().into());
+        }
+        if let Some(message) = &self.message {
+            object.insert("message".to_owned(), message.clone().into());
+        }
+        if let Some(reason) = &self.reason {
+            object.insert("reason".to_owned(), reason.clone().into());
+        }
+        if let Some(details) = &self.details {
+            let mut fields = Map::new();
+            let mut insert = |key: &str, value: &Option<String>| if let Some(value) = value {
+                fields.insert(key.to_owned(), value.clone().into());
+            };
+            insert("name", &details.name);
+            insert("group", &details.group);
+            insert("kind", &details.kind);
+            insert("uid", &details.uid);
+            if !details.causes.is_empty() {
+                fields.insert("causes".to_owned(), details.causes.iter().map(|cause| {
+                    let mut object = Map::new();
+                    for (key, value) in [("reason", &cause.reason), ("message", &cause.message),
+                        ("field", &cause.field)]
+                    {
+                        if let Some(value) = value {
+                            object.insert(key.to_owned(), value.clone().into());
+                        }
+                    }
+                    Value::Object(object)
+                }).collect());
+            }
+            if let Some(seconds) = details.retry_after_seconds {
+                fields.insert("retryAfterSeconds".to_owned(), seconds.into());
+            }
+            object.insert("details".to_owned(), Value::Object(fields));
+        }
+        if let Some(code) = self.code {
+            object.insert("code".to_owned(), code.into());
+        }
+        Value::Object(object)
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.message, &self.reason, self.code) {
+            (Some(message), _, _) if !message.is_empty() => f.write_str(message),
+            (_, Some(reason), _) if !reason.is_empty() => f.write_str(reason),
+            (_, _, Some(code)) => write!(f, "the server responded with the status code {}", code),
+            _ => f.write_str("unknown error")
+        }
+    }
+}
+
+impl std::error::Error for Status {}
+
+impl StatusStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "Success",
+            Self::Failure => "Failure"
+        }
+    }
+}
+
+/// `pods` or `deployments.apps`.
+fn qualified(group: &str, resource: &str) -> String {
+    if group.is_empty() {
+        resource.to_owned()
+    } else {
+        format!("{}.{}", resource, group)
+    }
+}
@@ -1,3 +1,4 @@
+pub mod cidr;
 pub mod cluster_cidr;
 pub mod gateway;
 pub mod ingress;
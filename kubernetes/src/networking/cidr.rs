@@ -0,0 +1,63 @@
+/*!
+CIDR notation, `<ip>/<prefix length>`, as kubeadm validates it and kube-proxy matches addresses against it.
+
+- Reference <https://www.rfc-editor.org/rfc/rfc4632#section-3.1>
+*/
+
+use std::net::IpAddr;
+
+/// The network address and prefix length of `cidr`, `None` if either is invalid or the prefix is longer than the address.
+pub fn parse(cidr: &str) -> Option<(IpAddr, u8)> {
+    let (ip, prefix) = cidr.split_once('/')?;
+    let ip: IpAddr = ip.parse().ok()?;
+    let prefix: u8 = prefix.parse().ok()?;
+    (prefix <= width(&ip)).then_some((ip, prefix))
+}
+
+/// Whether `ip` is within `cidr`, or is `cidr` if it is a single address.
+pub fn contains(cidr: &str, ip: &IpAddr) -> bool {
+    let Some((network, prefix)) = parse(cidr).or_else(|| cidr.parse().ok()
+        .map(|ip: IpAddr| (ip, width(&ip))))
+    else {
+        return false
+    };
+    let bits = |ip: &IpAddr| match ip {
+        IpAddr::V4(ip) => u128::from(u32::from(*ip)),
+        IpAddr::V6(ip) => u128::from(*ip)
+    };
+    if width(ip) != width(&network) {
+        return false
+    }
+    let shift = u32::from(width(ip) - prefix);
+    shift >= u32::from(width(ip)) || bits(ip) >> shift == bits(&network) >> shift
+}
+
+/// Bits in an address of the family of `ip`.
+fn width(ip: &IpAddr) -> u8 {
+    if ip.is_ipv4() { 32 } else { 128 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses() {
+        assert_eq!(parse("10.96.0.0/12"), Some(("10.96.0.0".parse().unwrap(), 12)));
+        assert_eq!(parse("fd00::/108"), Some(("fd00::".parse().unwrap(), 108)));
+        assert_eq!(parse("10.96.0.0/33"), None);
+        assert_eq!(parse("10.96.0.0"), None);
+    }
+
+    #[test]
+    fn contains_addresses() {
+        let ip = "10.0.1.5".parse().unwrap();
+        assert!(contains("10.0.0.0/16", &ip));
+        assert!(contains("0.0.0.0/0", &ip));
+        assert!(contains("10.0.1.5", &ip));
+        assert!(!contains("10.0.2.0/24", &ip));
+        assert!(!contains("::/0", &ip));
+        assert!(!contains("not a cidr", &ip));
+        assert!(contains("fd00::/64", &"fd00::1".parse().unwrap()));
+    }
+}
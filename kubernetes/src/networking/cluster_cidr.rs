@@ -1,24 +1,41 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/cluster-cidr-v1alpha1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
+    decode::Fields,
     meta::metadata::Metadata,
     node_selector::NodeSelector
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/cluster-cidr-v1alpha1/#ClusterCidr>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct ClusterCidr {
-    metadata: Option<Metadata>,
-    spec: Option<ClusterCidrSpec>
+    pub metadata: Option<Metadata>,
+    pub spec: Option<ClusterCidrSpec>
+}
+
+impl Decode for ClusterCidr {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let cluster_cidr = Self {
+            metadata: fields.child("metadata")?,
+            spec: fields.child("spec")?
+        };
+        fields.finish()?;
+        Ok(cluster_cidr)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/cluster-cidr-v1alpha1/#ClusterCidrSpec>
 #[derive(Debug, Decode)]
 pub struct ClusterCidrSpec {
-    per_node_host_bits: i32,
-    ipv4: Option<String>,
-    ipv6: Option<String>,
-    node_selector: Option<NodeSelector>,
+    #[kfl(property, default)]
+    pub per_node_host_bits: i32,
+    #[kfl(property, default)]
+    pub ipv4: Option<String>,
+    #[kfl(property, default)]
+    pub ipv6: Option<String>,
+    #[kfl(child, default)]
+    pub node_selector: Option<NodeSelector>,
 }
@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod gateway;
 pub mod gateway_class;
 pub mod http_route;
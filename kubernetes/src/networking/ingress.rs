@@ -3,10 +3,11 @@
 - Reference <https://kubernetes.io/docs/reference/kubernetes-api/service-resources/ingress-v1/>
 */
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
     core::typed_local_reference::TypedLocalReference,
+    decode::{decode_as, Fields},
     meta::metadata::Metadata,
     // port_status::PortStatus,
 };
@@ -15,28 +16,45 @@ use crate::{
 /// Ingress is a collection of rules that allow inbound connections to reach the endpoints defined by a backend. An Ingress can be configured to give services externally-reachable urls, load balance traffic, terminate SSL, offer name based virtual hosting etc.
 #[derive(Debug, Decode)]
 pub struct Ingress {
-    metadata: Metadata,
-    spec: Spec,
-    status: Option<Status>
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child)]
+    pub spec: Spec,
+    #[kfl(child, default)]
+    pub status: Option<Status>
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/service-resources/ingress-v1/#IngressSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct Spec {
     /// `default_backend` is the backend that should handle requests that don't match any rule. If [`rules`][Self::rules] are not specified, `default_backend` must be specified. If `default_backend` is not set, the handling of requests that do not match any of the rules will be up to the Ingress controller.
-    default_backend: Option<Backend>,
+    pub default_backend: Option<Backend>,
     /// `ingress_class_name` is the name of an IngressClass cluster resource. Ingress controller implementations use this field to know whether they should be serving this Ingress resource, by a transitive connection (controller -> IngressClass -> Ingress resource). Although the kubernetes.io/ingress.class annotation (simple constant name) was never formally defined, it was widely supported by Ingress controllers to create a direct binding between Ingress controller and Ingress resources. Newly created Ingress resources should prefer using the field. However, even though the annotation is officially deprecated, for backwards compatibility reasons, ingress controllers should still honour that annotation if present.
-    ingress_class_name: Option<String>,
+    pub ingress_class_name: Option<String>,
     /// A list of host rules used to configure the Ingress. If unspecified, or no rule matches, all traffic is sent to the default backend.
-    rules: Vec<Rule>,
+    pub rules: Vec<Rule>,
     /// TLS configuration. Currently the Ingress only supports a single TLS port, 443. If multiple members of this list specify different hosts, they will be multiplexed on the same port according to the hostname specified through the SNI TLS extension, if the ingress controller fulfilling the ingress supports SNI.
-    tls: Vec<IngressTLS>
+    pub tls: Vec<IngressTLS>
+}
+
+impl Decode for Spec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let spec = Self {
+            default_backend: fields.variant()?,
+            ingress_class_name: fields.property("ingress-class-name")?,
+            rules: fields.children("rule")?,
+            tls: fields.children("ingress-tls")?
+        };
+        fields.finish()?;
+        Ok(spec)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/service-resources/ingress-v1/#IngressBackend>
 ///
 /// Backend describes all endpoints for a given service and port.
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub enum Backend {
     /// Resource is an ObjectRef to another Kubernetes resource in the namespace of the Ingress object. If resource is specified, a [`service.name`][Service::name] and [`service.port`][Service::port] must not be specified. This is a mutually exclusive setting with [`service`][Self::service].
     Resource(TypedLocalReference),
@@ -44,25 +62,49 @@ pub enum Backend {
     Service(backend::Service),
 }
 
+impl Decode for Backend {
+    fn decode(node: &kfl::ast::Node, ctx: &mut kfl::context::Context)
+        -> Result<Self, kfl::errors::DecodeError>
+    {
+        match node.node_name.as_ref() {
+            "resource" => decode_as(node, ctx).map(Self::Resource),
+            "service" => decode_as(node, ctx).map(Self::Service),
+            _ => Err(kfl::errors::DecodeError::conversion(ctx.span(&node),
+                "expected one of `resource`, `service`"))
+        }
+    }
+}
+
 pub mod backend {
-    use kfl::Decode;
+    use kfl::{Decode, DecodeScalar};
 
     /// IngressServiceBackend references a Kubernetes Service as a Backend.
     #[derive(Debug, Decode)]
     pub struct Service {
         /// Name is the referenced service. The service must exist in the same namespace as the Ingress object.
-        name: String,
+        #[kfl(property, default)]
+        pub name: String,
         /// Port of the referenced service. A port name or port number is required for a Service.
-        port: Port
+        #[kfl(property)]
+        pub port: Port
     }
 
-    #[derive(Debug, DecodeScalar)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     pub enum Port {
         /// Name is the name of the port on the Service. This is a mutually exclusive setting with [`number`][Self::number].
         Name(String),
         /// Number is the numerical port number (e.g. `80`) on the Service. This is a mutually exclusive setting with [`name`][Self::name].
         Number(u16)
     }
+
+    impl DecodeScalar for Port {
+        fn decode(scalar: &kfl::ast::Scalar, ctx: &mut kfl::context::Context)
+            -> Result<Self, kfl::errors::DecodeError>
+        {
+            let port = String::decode(scalar, ctx)?;
+            Ok(port.parse().map_or(Self::Name(port), Self::Number))
+        }
+    }
 }
 
 /// Rule represents the rules mapping the paths under a specified host to the related backend services. Incoming requests are first evaluated for a host match, then routed to the backend associated with the matching IngressRuleValue.
@@ -79,8 +121,10 @@ pub struct Rule {
     /// 1. If `host` is precise, the request matches this rule if the http host header is equal to `host`.
     ///
     /// 2. If `host` is a wildcard, then the request matches this rule if the http host header is to equal to the suffix (removing the first label) of the wildcard rule.
-    host: Option<String>,
-    http: Option<rule::Http>
+    #[kfl(property, default)]
+    pub host: Option<String>,
+    #[kfl(child, default)]
+    pub http: Option<rule::Http>
 }
 
 pub mod rule {
@@ -90,21 +134,25 @@ pub mod rule {
     #[derive(Debug, Decode)]
     pub struct Http {
         /// A collection of paths that map requests to backends.
-        paths: Vec<Path>,
+        #[kfl(children)]
+        pub paths: Vec<Path>,
     }
 
     /// Path associates a path with a backend. Incoming urls matching the path are forwarded to the backend.
     #[derive(Debug, Decode)]
     pub struct Path {
         /// Backend defines the referenced service endpoint to which the traffic will be forwarded to.
-        backend: Backend,
+        #[kfl(child)]
+        pub backend: super::Backend,
         /// PathType determines the interpretation of the Path matching.
-        r#type: Type,
-        /// `path` is matched against the path of an incoming request. Currently it can contain characters disallowed from the conventional 'path' part of a URL as defined by [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986). Paths must begin with a '/' and must be present when using [`path_type`][Self::path_type] with value `Exact` or `Prefix`.
-        path: Option<String>
+        #[kfl(property)]
+        pub r#type: Type,
+        /// `path` is matched against the path of an incoming request. Currently it can contain characters disallowed from the conventional 'path' part of a URL as defined by [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986). Paths must begin with a '/' and must be present when using [`type`][Self::type] with value `Exact` or `Prefix`.
+        #[kfl(property, default)]
+        pub path: Option<String>
     }
 
-    #[derive(Debug, DecodeScalar)]
+    #[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
     pub enum Type {
         /// Matches the URL path exactly.
         Exact,
@@ -115,10 +163,22 @@ pub mod rule {
     }
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct IngressTLS {
-    hosts: Vec<String>,
-    secret_name: Option<String>
+    pub hosts: Vec<String>,
+    pub secret_name: Option<String>
+}
+
+impl Decode for IngressTLS {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let ingress_tls = Self {
+            hosts: fields.values("hosts")?,
+            secret_name: fields.property("secret-name")?
+        };
+        fields.finish()?;
+        Ok(ingress_tls)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/service-resources/ingress-v1/#IngressStatus>
@@ -127,7 +187,8 @@ pub struct IngressTLS {
 #[derive(Debug, Decode)]
 pub struct Status {
     /// LoadBalancer contains the current status of the load-balancer.
-    load_balancer: Option<status::LoadBalancer>,
+    #[kfl(child, default)]
+    pub load_balancer: Option<status::LoadBalancer>,
 }
 
 pub mod status {
@@ -138,17 +199,21 @@ pub mod status {
     #[derive(Debug, Decode)]
     pub struct LoadBalancer {
         /// Ingress is a list containing ingress points for the load-balancer. Traffic intended for the service should be sent to these ingress points.
-        ingress: Vec<Ingress>,
+        #[kfl(children)]
+        pub ingress: Vec<Ingress>,
     }
 
     /// Ingress represents the status of a load-balancer ingress point: traffic intended for the service should be sent to an ingress point.
     #[derive(Debug, Decode)]
     pub struct Ingress {
         /// Hostname is set for load-balancer ingress points that are DNS based (typically AWS load-balancers)
-        host_names: Option<String>,
+        #[kfl(property, default)]
+        pub host_names: Option<String>,
         /// IP is set for load-balancer ingress points that are IP based (typically GCE or OpenStack load-balancers)
-        ip: Option<String>,
+        #[kfl(property, default)]
+        pub ip: Option<String>,
         /// Ports is a list of records of service ports If used, every port defined in the service should have an entry in it.
-        ports: Vec<Port>
+        #[kfl(children)]
+        pub ports: Vec<Port>
     }
 }
@@ -7,27 +7,36 @@ use crate::meta::metadata::Metadata;
 /// <https://kubernetes.io/docs/reference/kubernetes-api/service-resources/ingress-class-v1/#IngressClass>
 #[derive(Debug, Decode)]
 pub struct IngressClass {
-    metadata: Metadata,
-    spec: Spec
+    #[kfl(child)]
+    pub metadata: Metadata,
+    #[kfl(child)]
+    pub spec: Spec
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/service-resources/ingress-class-v1/#IngressClassSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct Spec {
-    controller: Option<String>,
-    parameters: Option<Parameters>
+    #[kfl(property, default)]
+    pub controller: Option<String>,
+    #[kfl(child, default)]
+    pub parameters: Option<Parameters>
 }
 
 #[derive(Debug, Decode)]
 pub struct Parameters {
-    kind: String,
-    name: String,
-    api_group: Option<String>,
-    namespace: Option<String>,
-    scope: Option<Scope>
+    #[kfl(property, default)]
+    pub kind: String,
+    #[kfl(property, default)]
+    pub name: String,
+    #[kfl(property, default)]
+    pub api_group: Option<String>,
+    #[kfl(property, default)]
+    pub namespace: Option<String>,
+    #[kfl(property, default)]
+    pub scope: Option<Scope>
 }
 
-#[derive(Debug, DecodeScalar, Default)]
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Scope {
     #[default]
     Cluster,
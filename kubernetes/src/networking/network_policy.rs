@@ -1,9 +1,10 @@
 //! - Concepts <https://kubernetes.io/docs/concepts/services-networking/network-policies/>
 //! - Reference <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/network-policy-v1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
+    decode::Fields,
     meta::{
         condition::Condition,
         label_selector::Selector,
@@ -13,55 +14,114 @@ use crate::{
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/network-policy-v1/#NetworkPolicy>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct NetworkPolicy {
-    metadata: Metadata,
-    spec: NetworkPolicySpec,
-    status: Option<NetworkPolicyStatus>
+    pub metadata: Metadata,
+    pub spec: NetworkPolicySpec,
+    pub status: Option<NetworkPolicyStatus>
 }
 
-/// 
-#[derive(Debug, Decode)]
+impl Decode for NetworkPolicy {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let network_policy = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            spec: fields.child("spec")?.ok_or_else(|| fields.missing("spec"))?,
+            status: fields.child("status")?
+        };
+        fields.finish()?;
+        Ok(network_policy)
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct NetworkPolicySpec {
-    pod_selector: Selector,
-    policy_types: Vec<String>,
-    ingress: Vec<NetworkPolicyIngressRule>,
-    egress: Vec<NetworkPolicyEgressRule>
+    pub pod_selector: Selector,
+    pub policy_types: Vec<String>,
+    pub ingress: Vec<NetworkPolicyIngressRule>,
+    pub egress: Vec<NetworkPolicyEgressRule>
+}
+
+impl Decode for NetworkPolicySpec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let network_policy_spec = Self {
+            pod_selector: fields.child("pod-selector")?.unwrap_or_default(),
+            policy_types: fields.values("policy-types")?,
+            ingress: fields.children("network-policy-ingress-rule")?,
+            egress: fields.children("network-policy-egress-rule")?
+        };
+        fields.finish()?;
+        Ok(network_policy_spec)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct NetworkPolicyIngressRule {
-    from: Vec<NetworkPolicyPeer>,
-    ports: Vec<NetworkPolicyPort>
+    #[kfl(children)]
+    pub from: Vec<NetworkPolicyPeer>,
+    #[kfl(children)]
+    pub ports: Vec<NetworkPolicyPort>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct NetworkPolicyPeer {
-    ip_block: IPBlock,
-    namespace_selector: Selector,
-    pod_selector: Selector
+    pub ip_block: IPBlock,
+    pub namespace_selector: Selector,
+    pub pod_selector: Selector
 }
 
-#[derive(Debug, Decode)]
+impl Decode for NetworkPolicyPeer {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let network_policy_peer = Self {
+            ip_block: fields.child("ip-block")?.ok_or_else(|| fields.missing("ip-block"))?,
+            namespace_selector: fields.child("namespace-selector")?.ok_or_else(|| fields.missing("namespace-selector"))?,
+            pod_selector: fields.child("pod-selector")?.ok_or_else(|| fields.missing("pod-selector"))?
+        };
+        fields.finish()?;
+        Ok(network_policy_peer)
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct IPBlock {
-    cidr: String,
-    except: Vec<String>
+    pub cidr: String,
+    pub except: Vec<String>
+}
+
+impl Decode for IPBlock {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let ip_block = Self {
+            cidr: fields.property("cidr")?.unwrap_or_default(),
+            except: fields.values("except")?
+        };
+        fields.finish()?;
+        Ok(ip_block)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct NetworkPolicyPort {
-    port: i32,
-    end_port: i32,
-    protocol: Protocol
+    #[kfl(property, default)]
+    pub port: i32,
+    #[kfl(property, default)]
+    pub end_port: i32,
+    #[kfl(property, default)]
+    pub protocol: Protocol
 }
 
 #[derive(Debug, Decode)]
 pub struct NetworkPolicyEgressRule {
-    to: Vec<NetworkPolicyPeer>,
-    ports: Vec<NetworkPolicyPort>
+    #[kfl(children)]
+    pub to: Vec<NetworkPolicyPeer>,
+    #[kfl(children)]
+    pub ports: Vec<NetworkPolicyPort>
 }
 
 #[derive(Debug, Decode)]
 pub struct NetworkPolicyStatus {
-    conditions: Vec<Condition>
+    #[kfl(children)]
+    pub conditions: Vec<Condition>
 }
@@ -4,10 +4,11 @@ Reference <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources
 
 use std::collections::HashMap;
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
     core::pod::Toleration,
+    decode::Fields,
     meta::metadata::Metadata,
     quantity::Quantity
 };
@@ -15,19 +16,46 @@ use crate::{
 /// <https://kubernetes.io/docs/reference/kubernetes-api/cluster-resources/runtime-class-v1/#RuntimeClass>
 #[derive(Debug, Decode)]
 pub struct RuntimeClass {
-    metadata: Option<Metadata>,
-    handler: String,
-    overhead: Option<Overhead>,
-    scheduling: Option<Scheduling>
+    #[kfl(child, default)]
+    pub metadata: Option<Metadata>,
+    #[kfl(property, default)]
+    pub handler: String,
+    #[kfl(child, default)]
+    pub overhead: Option<Overhead>,
+    #[kfl(child, default)]
+    pub scheduling: Option<Scheduling>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct Overhead {
-    pod_fixed: HashMap<String, Quantity>
+    pub pod_fixed: HashMap<String, Quantity>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for Overhead {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let overhead = Self {
+            pod_fixed: fields.entries("pod-fixed")?
+        };
+        fields.finish()?;
+        Ok(overhead)
+    }
+}
+
+#[derive(Debug)]
 pub struct Scheduling {
-    node_selector: HashMap<String, String>,
-    tolerations: Vec<Toleration>
+    pub node_selector: HashMap<String, String>,
+    pub tolerations: Vec<Toleration>
+}
+
+impl Decode for Scheduling {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let scheduling = Self {
+            node_selector: fields.entries("node-selector")?,
+            tolerations: fields.children("toleration")?
+        };
+        fields.finish()?;
+        Ok(scheduling)
+    }
 }
@@ -1,17 +1,41 @@
+use std::collections::HashMap;
+
 use kfl::Decode;
 
 use crate::core::node_selector_requirement::NodeSelectorRequirement;
 
-#[derive(Debug, Decode)]
+/// Terms of which a node must match any.
+#[derive(Debug, Decode, Clone, Default)]
 pub struct NodeSelector {
     #[kfl(children)]
-    node_selector_terms: Vec<NodeSelectorTerm>,
+    pub node_selector_terms: Vec<NodeSelectorTerm>,
+}
+
+impl NodeSelector {
+    /// Whether a node with `labels`, named `name`, matches any of the terms.
+    pub fn matches(&self, labels: &HashMap<String, String>, name: &str) -> bool {
+        self.node_selector_terms.iter().any(|term| term.matches(labels, name))
+    }
 }
 
-#[derive(Debug, Decode)]
+/// Requirements of which a node must match all.
+#[derive(Debug, Decode, Clone, Default)]
 pub struct NodeSelectorTerm {
+    /// Requirements on the labels of the node.
     #[kfl(children)]
-    match_expressions: Vec<NodeSelectorRequirement>,
+    pub match_expressions: Vec<NodeSelectorRequirement>,
+    /// Requirements on the fields of the node, of which only `metadata.name` is supported.
     #[kfl(children)]
-    match_fields: Vec<NodeSelectorRequirement>
+    pub match_fields: Vec<NodeSelectorRequirement>
+}
+
+impl NodeSelectorTerm {
+    /// Whether a node with `labels`, named `name`, matches every requirement; a term without any matches no node.
+    pub fn matches(&self, labels: &HashMap<String, String>, name: &str) -> bool {
+        (!self.match_expressions.is_empty() || !self.match_fields.is_empty())
+            && self.match_expressions.iter()
+                .all(|requirement| requirement.matches(labels.get(&requirement.key).map(String::as_str)))
+            && self.match_fields.iter()
+                .all(|requirement| requirement.key == "metadata.name" && requirement.matches(Some(name)))
+    }
 }
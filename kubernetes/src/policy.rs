@@ -1 +1,3 @@
+pub mod disruption;
 pub mod pod_disruption_budget;
+pub mod pod_security;
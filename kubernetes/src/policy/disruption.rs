@@ -0,0 +1,322 @@
+//! Disruption budget arithmetic of the disruption controller and the `pods/eviction` subresource, evaluated offline.
+//!
+//! - Concepts <https://kubernetes.io/docs/concepts/workloads/pods/disruptions/>
+//! - Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/controller/disruption/disruption.go>
+//! - Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/registry/core/pod/storage/eviction.go>
+
+use std::fmt;
+
+use crate::{
+    core::pod::Pod,
+    policy::pod_disruption_budget::{
+        PodDisruptionBudget, UnhealthyPodEvictionPolicy
+    },
+    IntOrString
+};
+
+/// The figures the disruption controller writes into [`PodDisruptionBudgetStatus`][crate::policy::pod_disruption_budget::PodDisruptionBudgetStatus].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    /// Total number of pods counted by the budget.
+    pub expected_pods: i32,
+    /// Number of selected pods which are ready and not being deleted.
+    pub current_healthy: i32,
+    /// Minimum number of healthy pods the budget asks for.
+    pub desired_healthy: i32,
+    /// Number of further disruptions currently allowed.
+    pub disruptions_allowed: i32
+}
+
+impl Budget {
+    /**
+    Computes the budget of `pdb` over `pods`, which must already be the pods it selects (see [`selects`]).
+
+    `max_unavailable` and percentage `min_available` are resolved against `expected_scale`, the summed `replicas` of the controllers owning the pods; when it is not known the number of selected pods is used instead. An integer `min_available` always counts the selected pods, like the controller does.
+    */
+    pub fn new(
+        pdb: &PodDisruptionBudget,
+        pods: &[&Pod],
+        expected_scale: Option<i32>
+    ) -> Result<Self, Error> {
+        let pod_count = i32::try_from(pods.len()).unwrap_or(i32::MAX);
+        let scale = expected_scale.unwrap_or(pod_count);
+        let (expected_pods, desired_healthy) = match (
+            &pdb.spec.max_unavailable, &pdb.spec.min_available)
+        {
+            (Some(max_unavailable), _) => {
+                let max_unavailable = max_unavailable.scaled_value(scale, true)
+                    .ok_or_else(|| Error::InvalidValue(max_unavailable.clone()))?;
+                (scale, (scale - max_unavailable).max(0))
+            }
+            (None, Some(IntOrString::Int(min_available))) =>
+                (pod_count, *min_available),
+            (None, Some(min_available)) => {
+                let min_available = min_available.scaled_value(scale, true)
+                    .ok_or_else(|| Error::InvalidValue(min_available.clone()))?;
+                (scale, min_available)
+            }
+            (None, None) => (0, 0)
+        };
+        let disrupted = pdb.status.as_ref()
+            .map(|status| &status.disrupted_pods);
+        let current_healthy = pods.iter()
+            .filter(|pod| pod.metadata.deletion_timestamp.is_none())
+            .filter(|pod| !disrupted.is_some_and(|disrupted|
+                pod.metadata.name.as_ref()
+                    .is_some_and(|name| disrupted.contains_key(name))))
+            .filter(|pod| pod.is_ready())
+            .count();
+        let current_healthy = i32::try_from(current_healthy)
+            .unwrap_or(i32::MAX);
+        let disruptions_allowed = if expected_pods <= 0 {
+            0
+        } else {
+            (current_healthy - desired_healthy).max(0)
+        };
+        Ok(Self {
+            expected_pods,
+            current_healthy,
+            desired_healthy,
+            disruptions_allowed
+        })
+    }
+
+    /// Whether the application already has at least the healthy pods it asks for.
+    pub fn is_healthy(&self) -> bool {
+        self.current_healthy >= self.desired_healthy
+    }
+}
+
+/// Whether `pdb` guards `pod`: same namespace and a selector matching its labels. A missing selector selects nothing.
+pub fn selects(pdb: &PodDisruptionBudget, pod: &Pod) -> bool {
+    pdb.metadata.namespace == pod.metadata.namespace
+        && pdb.spec.selector.as_ref()
+            .is_some_and(|selector| selector.matches(&pod.metadata.labels))
+}
+
+/// How an eviction was let through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Eviction {
+    /// The pod is terminal, pending or already being deleted, so budgets are not consulted.
+    Ignored,
+    /// No budget selects the pod.
+    Unguarded,
+    /// The pod is not ready and [`unhealthy_pod_eviction_policy`][crate::policy::pod_disruption_budget::PodDisruptionBudgetSpec::unhealthy_pod_eviction_policy] allows evicting it without consuming the budget.
+    Unhealthy,
+    /// The eviction consumed one of the disruptions allowed by the named budget.
+    Budgeted(String)
+}
+
+/// Replays eviction requests against a set of budgets in order, as the eviction subresource handles them during a node drain. Every healthy pod evicted consumes one disruption and stops counting as healthy.
+#[derive(Debug)]
+pub struct Evictions<'a> {
+    budgets: Vec<(&'a PodDisruptionBudget, Budget)>
+}
+
+impl<'a> Evictions<'a> {
+    pub fn new(budgets: Vec<(&'a PodDisruptionBudget, Budget)>) -> Self {
+        Self { budgets }
+    }
+
+    /// Computes the budget of each of `pdbs` over the `pods` they select, with no known controller scale.
+    pub fn from_pods(pdbs: &[&'a PodDisruptionBudget], pods: &[&Pod])
+        -> Result<Self, Error>
+    {
+        let budgets = pdbs.iter()
+            .map(|pdb| {
+                let selected: Vec<&Pod> = pods.iter().copied()
+                    .filter(|pod| selects(pdb, pod))
+                    .collect();
+                Budget::new(pdb, &selected, None).map(|budget| (*pdb, budget))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(budgets))
+    }
+
+    /// The current state of the budgets, updated by every eviction let through so far.
+    pub fn budgets(&self) -> &[(&'a PodDisruptionBudget, Budget)] {
+        &self.budgets
+    }
+
+    /// Decides on the eviction of `pod` and, when it is allowed, charges it to the budget guarding it.
+    pub fn evict(&mut self, pod: &Pod) -> Result<Eviction, EvictionError> {
+        let phase = pod.status.as_ref().map(|status| status.phase.as_str());
+        if matches!(phase, Some("Succeeded" | "Failed" | "Pending"))
+            || pod.metadata.deletion_timestamp.is_some()
+        {
+            return Ok(Eviction::Ignored)
+        }
+        let mut guarding = self.budgets.iter_mut()
+            .filter(|(pdb, _)| selects(pdb, pod))
+            .collect::<Vec<_>>();
+        let (pdb, budget) = match guarding.len() {
+            0 => return Ok(Eviction::Unguarded),
+            1 => guarding.remove(0),
+            _ => return Err(EvictionError::MultipleBudgets(
+                guarding.iter().map(|(pdb, _)| name(pdb)).collect()))
+        };
+        let ready = pod.is_ready();
+        if !ready {
+            let policy = pdb.spec.unhealthy_pod_eviction_policy
+                .unwrap_or_default();
+            if policy == UnhealthyPodEvictionPolicy::AlwaysAllow
+                || (budget.is_healthy() && budget.desired_healthy > 0)
+            {
+                return Ok(Eviction::Unhealthy)
+            }
+        }
+        if budget.disruptions_allowed <= 0 {
+            return Err(EvictionError::Disrupted {
+                budget: name(pdb),
+                desired_healthy: budget.desired_healthy,
+                current_healthy: budget.current_healthy
+            })
+        }
+        budget.disruptions_allowed -= 1;
+        if ready {
+            budget.current_healthy -= 1;
+        }
+        Ok(Eviction::Budgeted(name(pdb)))
+    }
+
+    /// Evicts `pods` one after another, reporting the decision for each.
+    pub fn drain(&mut self, pods: &[&Pod])
+        -> Vec<Result<Eviction, EvictionError>>
+    {
+        pods.iter().map(|pod| self.evict(pod)).collect()
+    }
+}
+
+fn name(pdb: &PodDisruptionBudget) -> String {
+    pdb.metadata.name.clone().unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// `min_available` or `max_unavailable` is a string but not a percentage.
+    InvalidValue(IntOrString)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidValue(value) =>
+                write!(f, "invalid value for IntOrString: {:?}", value)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Refusals of the eviction subresource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvictionError {
+    /// More than one budget selects the pod, which the eviction subresource does not support.
+    MultipleBudgets(Vec<String>),
+    /// No disruption is left in the budget; the API server answers `429 Too Many Requests`.
+    Disrupted {
+        budget: String,
+        desired_healthy: i32,
+        current_healthy: i32
+    }
+}
+
+impl fmt::Display for EvictionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MultipleBudgets(_) => write!(f, "This pod has more than one PodDisruptionBudget, which the eviction subresource does not support."),
+            Self::Disrupted { budget, desired_healthy, current_healthy } =>
+                write!(f, "Cannot evict pod as it would violate the pod's disruption budget. The disruption budget {} needs {} healthy pods and has {} currently",
+                    budget, desired_healthy, current_healthy)
+        }
+    }
+}
+
+impl std::error::Error for EvictionError {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{client::Object, decode::decode};
+    use super::*;
+
+    fn pdb(spec: &str) -> PodDisruptionBudget {
+        decode("pdb.kdl", &format!(r#"
+            pod-disruption-budget {{
+                metadata "web" namespace="default"
+                spec {} {{
+                    selector {{
+                        match-labels app="web"
+                    }}
+                }}
+            }}
+        "#, spec)).unwrap()
+    }
+
+    fn pod(name: &str, ready: bool) -> Pod {
+        Pod::from_json(json!({
+            "metadata": { "name": name, "namespace": "default", "labels": { "app": "web" } },
+            "spec": { "containers": [{ "name": "web" }] },
+            "status": {
+                "phase": "Running",
+                "conditions": [{
+                    "type": "Ready",
+                    "status": if ready { "True" } else { "False" },
+                    "lastTransitionTime": "2024-01-01T00:00:00Z"
+                }]
+            }
+        })).unwrap()
+    }
+
+    #[test]
+    fn computes_budgets() {
+        let pods = [pod("a", true), pod("b", true), pod("c", false)];
+        let pods: Vec<&Pod> = pods.iter().collect();
+        assert_eq!(Budget::new(&pdb(r#"min-available=2"#), &pods, None).unwrap(), Budget {
+            expected_pods: 3,
+            current_healthy: 2,
+            desired_healthy: 2,
+            disruptions_allowed: 0
+        });
+        // Percentages round up, against the scale of the controllers when known.
+        let budget = Budget::new(&pdb(r#"max-unavailable="25%""#), &pods, Some(4)).unwrap();
+        assert_eq!((budget.expected_pods, budget.desired_healthy, budget.disruptions_allowed),
+            (4, 3, 0));
+        let budget = Budget::new(&pdb(r#"min-available="50%""#), &pods, None).unwrap();
+        assert_eq!((budget.desired_healthy, budget.disruptions_allowed), (2, 0));
+        assert_eq!(Budget::new(&pdb(r#"min-available="half""#), &pods, None),
+            Err(Error::InvalidValue(IntOrString::String("half".to_owned()))));
+    }
+
+    #[test]
+    fn drains_within_the_budget() {
+        let pdb = pdb("max-unavailable=2");
+        let pods = [pod("a", true), pod("b", true), pod("c", false)];
+        let pods: Vec<&Pod> = pods.iter().collect();
+        let mut evictions = Evictions::from_pods(&[&pdb], &pods).unwrap();
+        let results = evictions.drain(&pods);
+        assert_eq!(results[0], Ok(Eviction::Budgeted("web".to_owned())));
+        assert_eq!(results[1], Err(EvictionError::Disrupted {
+            budget: "web".to_owned(),
+            desired_healthy: 1,
+            current_healthy: 1
+        }));
+        // Unhealthy pods go without consuming the budget while the application is healthy.
+        assert_eq!(results[2], Ok(Eviction::Unhealthy));
+
+        let mut other = pod("d", true);
+        other.metadata.labels.clear();
+        assert_eq!(evictions.evict(&other), Ok(Eviction::Unguarded));
+    }
+
+    #[test]
+    fn evicts_unhealthy_pods_as_the_policy_says() {
+        let pdb = pdb(r#"min-available=1 unhealthy-pod-eviction-policy="always-allow""#);
+        let pods = [pod("a", true), pod("b", false)];
+        let pods: Vec<&Pod> = pods.iter().collect();
+        let mut evictions = Evictions::from_pods(&[&pdb], &pods).unwrap();
+        assert_eq!(evictions.evict(pods[1]), Ok(Eviction::Unhealthy));
+        assert!(evictions.evict(pods[0]).is_err());
+    }
+}
@@ -1,25 +1,104 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/pod-disruption-budget-v1/>
 
-use kfl::Decode;
+use std::collections::HashMap;
 
-use crate::meta::metadata::Metadata;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
+
+use crate::{
+    decode::Fields,
+    meta::{Condition, Metadata, Selector},
+    time::Time,
+    IntOrString
+};
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/pod-disruption-budget-v1/#PodDisruptionBudget>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct PodDisruptionBudget {
-    metadata: Metadata,
-    spec: PodDisruptionBudgetSpec,
-    status: Option<PodDisruptionBudgetStatus>
+    pub metadata: Metadata,
+    pub spec: PodDisruptionBudgetSpec,
+    pub status: Option<PodDisruptionBudgetStatus>
+}
+
+impl Decode for PodDisruptionBudget {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let pod_disruption_budget = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            spec: fields.child("spec")?.ok_or_else(|| fields.missing("spec"))?,
+            status: fields.child("status")?
+        };
+        fields.finish()?;
+        Ok(pod_disruption_budget)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/pod-disruption-budget-v1/#PodDisruptionBudgetSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct PodDisruptionBudgetSpec {
-    
+    /// An eviction is allowed if at most `max_unavailable` pods selected by [`selector`][Self::selector] are unavailable after the eviction, i.e. even in absence of the evicted pod. For example, one can prevent all voluntary evictions by specifying 0. This is a mutually exclusive setting with [`min_available`][Self::min_available].
+    #[kfl(property, default)]
+    pub max_unavailable: Option<IntOrString>,
+    /// An eviction is allowed if at least `min_available` pods selected by [`selector`][Self::selector] will still be available after the eviction, i.e. even in the absence of the evicted pod. So for example you can prevent all voluntary evictions by specifying `"100%"`.
+    #[kfl(property, default)]
+    pub min_available: Option<IntOrString>,
+    /// Label query over pods whose evictions are managed by the disruption budget. A null selector will match no pods, while an empty (`{}`) selector will select all pods within the namespace.
+    #[kfl(child, default)]
+    pub selector: Option<Selector>,
+    /// `unhealthy_pod_eviction_policy` defines the criteria for when unhealthy pods should be considered for eviction. Current implementation considers healthy pods, as pods that have status.conditions item with type=`Ready`, status=`True`.
+    ///
+    /// Additional policies may be added in the future. Clients making eviction decisions should disallow eviction of unhealthy pods if they encounter an unrecognised policy in this field.
+    #[kfl(property, default)]
+    pub unhealthy_pod_eviction_policy: Option<UnhealthyPodEvictionPolicy>
+}
+
+/// <https://kubernetes.io/docs/tasks/run-application/configure-pdb/#unhealthy-pod-eviction-policy>
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnhealthyPodEvictionPolicy {
+    /// Running (`status.phase="Running"`), but not yet healthy pods can be evicted only if the guarded application is not disrupted (`status.current_healthy` is at least equal to `status.desired_healthy`). Healthy pods will be subject to the PDB for eviction.
+    #[default]
+    IfHealthyBudget,
+    /// Running (`status.phase="Running"`), but not yet healthy pods are considered disrupted and can be evicted regardless of whether the criteria in a PDB is met. This means perspective running pods of a disrupted application might not get a chance to become healthy. Healthy pods will be subject to the PDB for eviction.
+    AlwaysAllow
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/policy-resources/pod-disruption-budget-v1/#PodDisruptionBudgetStatus>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct PodDisruptionBudgetStatus {
+    /// Current number of healthy pods.
+    pub current_healthy: i32,
+    /// Minimum desired number of healthy pods.
+    pub desired_healthy: i32,
+    /// Number of pod disruptions that are currently allowed.
+    pub disruptions_allowed: i32,
+    /// Total number of pods counted by this disruption budget.
+    pub expected_pods: i32,
+    /// Conditions contain conditions for PDB. The disruption controller sets the `DisruptionAllowed` condition.
+    pub conditions: Vec<Condition<ConditionType>>,
+    /// `disrupted_pods` contains information about pods whose eviction was processed by the API server eviction subresource handler but has not yet been observed by the PodDisruptionBudget controller. A pod will be in this map from the time when the API server processed the eviction request to the time when the pod is seen by PDB controller as having been marked for deletion (or after a timeout). The key in the map is the name of the pod and the value is the time when the API server processed the eviction request. If the deletion didn't occur and a pod is still there it will be removed from the list automatically by PodDisruptionBudget controller after some time. If everything goes smooth this map should be empty for the most of the time. Large number of entries in the map may indicate problems with pod deletions.
+    pub disrupted_pods: HashMap<String, Time>,
+    /// Most recent generation observed when updating this PDB status. [`disruptions_allowed`][Self::disruptions_allowed] and other status information is valid only if `observed_generation` equals to PDB's object generation.
+    pub observed_generation: Option<i64>
+}
+
+impl Decode for PodDisruptionBudgetStatus {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let pod_disruption_budget_status = Self {
+            current_healthy: fields.property("current-healthy")?.unwrap_or_default(),
+            desired_healthy: fields.property("desired-healthy")?.unwrap_or_default(),
+            disruptions_allowed: fields.property("disruptions-allowed")?.unwrap_or_default(),
+            expected_pods: fields.property("expected-pods")?.unwrap_or_default(),
+            conditions: fields.children("condition")?,
+            disrupted_pods: fields.entries("disrupted-pods")?,
+            observed_generation: fields.property("observed-generation")?
+        };
+        fields.finish()?;
+        Ok(pod_disruption_budget_status)
+    }
+}
 
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionType {
+    /// `True` if [`disruptions_allowed`][PodDisruptionBudgetStatus::disruptions_allowed] is greater than zero, `False` with reason `InsufficientPods` or `SyncFailed` otherwise.
+    DisruptionAllowed
 }
@@ -0,0 +1,706 @@
+/*!
+Checking pods, and the pod templates of workloads, against the Pod Security Standards, as the `PodSecurity` admission plugin enforces a namespace's `pod-security.kubernetes.io/enforce` level at its `enforce-version`.
+
+- Concepts <https://kubernetes.io/docs/concepts/security/pod-security-standards/>
+- Concepts <https://kubernetes.io/docs/concepts/security/pod-security-admission/>
+- Source <https://github.com/kubernetes/pod-security-admission/tree/master/policy>
+*/
+
+use std::{
+    fmt,
+    str::FromStr
+};
+
+use crate::{
+    apps::{DaemonSet, Deployment, ReplicaSet, StatefulSet},
+    batch::{CronJob, Job},
+    core::{
+        pod::{Container, Pod, PodOSName, SELinuxOptions, SecurityContext, Spec},
+        pod_template::PodTemplateSpec
+    },
+    meta::metadata::Metadata
+};
+
+/// Label of a namespace setting the level pods are denied admission by.
+pub const ENFORCE_LABEL: &str = "pod-security.kubernetes.io/enforce";
+/// Label of a namespace setting the version of [`ENFORCE_LABEL`].
+pub const ENFORCE_VERSION_LABEL: &str = "pod-security.kubernetes.io/enforce-version";
+
+const APPARMOR_PREFIX: &str = "container.apparmor.security.beta.kubernetes.io/";
+const SECCOMP_POD_ANNOTATION: &str = "seccomp.security.alpha.kubernetes.io/pod";
+const SECCOMP_CONTAINER_PREFIX: &str = "container.seccomp.security.alpha.kubernetes.io/";
+
+/// Capabilities the baseline level allows to be added.
+const BASELINE_CAPABILITIES: [&str; 13] = [
+    "AUDIT_WRITE", "CHOWN", "DAC_OVERRIDE", "FOWNER", "FSETID", "KILL", "MKNOD",
+    "NET_BIND_SERVICE", "SETFCAP", "SETGID", "SETPCAP", "SETUID", "SYS_CHROOT"
+];
+
+/// Sysctls the baseline level allows, by the minor version that allowed them.
+const SAFE_SYSCTLS: [(&str, u32); 12] = [
+    ("kernel.shm_rmid_forced", 0),
+    ("net.ipv4.ip_local_port_range", 0),
+    ("net.ipv4.ip_unprivileged_port_start", 0),
+    ("net.ipv4.tcp_syncookies", 0),
+    ("net.ipv4.ping_group_range", 0),
+    ("net.ipv4.ip_local_reserved_ports", 27),
+    ("net.ipv4.tcp_keepalive_time", 29),
+    ("net.ipv4.tcp_fin_timeout", 29),
+    ("net.ipv4.tcp_keepalive_intvl", 29),
+    ("net.ipv4.tcp_keepalive_probes", 29),
+    ("net.ipv4.tcp_rmem", 32),
+    ("net.ipv4.tcp_wmem", 32)
+];
+
+/// SELinux types the baseline level allows, by the minor version that allowed them.
+const SELINUX_TYPES: [(&str, u32); 5] = [
+    ("", 0), ("container_t", 0), ("container_init_t", 0), ("container_kvm_t", 0),
+    ("container_engine_t", 31)
+];
+
+/// Profiles of the Pod Security Standards, each allowing less than the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Unrestricted.
+    Privileged,
+    /// Prevents known privilege escalations.
+    Baseline,
+    /// Follows pod hardening best practices.
+    Restricted
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Privileged => "privileged",
+            Self::Baseline => "baseline",
+            Self::Restricted => "restricted"
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Level {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "privileged" => Ok(Self::Privileged),
+            "baseline" => Ok(Self::Baseline),
+            "restricted" => Ok(Self::Restricted),
+            _ => Err(Error::Level(s.to_owned()))
+        }
+    }
+}
+
+/// Version of the standards a level is checked at, `latest` or `v1.<minor>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Version {
+    #[default]
+    Latest,
+    V1(u32)
+}
+
+impl Version {
+    /// Whether the checks of `v1.<minor>` apply at this version.
+    pub fn at_least(&self, minor: u32) -> bool {
+        match self {
+            Self::Latest => true,
+            Self::V1(version) => *version >= minor
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Latest => f.write_str("latest"),
+            Self::V1(minor) => write!(f, "v1.{}", minor)
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "latest" {
+            return Ok(Self::Latest)
+        }
+        s.strip_prefix("v1.")
+            .filter(|minor| !minor.starts_with('+'))
+            .and_then(|minor| minor.parse().ok())
+            .map(Self::V1)
+            .ok_or_else(|| Error::Version(s.to_owned()))
+    }
+}
+
+/// A level at a version, as a namespace enforces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Profile {
+    pub level: Level,
+    pub version: Version
+}
+
+impl Profile {
+    pub fn new(level: Level, version: Version) -> Self {
+        Self { level, version }
+    }
+
+    /// The profile the `pod-security.kubernetes.io/enforce` labels of a namespace set, `privileged` at `latest` when they are not.
+    pub fn from_labels(metadata: &Metadata) -> Result<Self, Error> {
+        let level = match metadata.labels.get(ENFORCE_LABEL) {
+            Some(level) => level.parse()?,
+            None => Level::Privileged
+        };
+        let version = match metadata.labels.get(ENFORCE_VERSION_LABEL) {
+            Some(version) => version.parse()?,
+            None => Version::Latest
+        };
+        Ok(Self::new(level, version))
+    }
+
+    /**
+    Checks the pod `workload` creates against this profile, returning the controls it violates, each with the path of the offending field in `workload`, or none if it is allowed.
+
+    Workloads without a pod template, such as a CronJob without a job spec, are allowed.
+    */
+    pub fn check(&self, workload: &impl Workload) -> Vec<Violation> {
+        let mut checker = Checker { profile: *self, violations: Vec::new() };
+        if let Some((metadata, spec)) = workload.pod_template() {
+            checker.check(workload.template_path(), metadata, spec);
+        }
+        checker.violations
+    }
+}
+
+/**
+Controls of the Pod Security Standards, baseline ones then restricted ones.
+
+Upstream also restricts AppArmor profiles set by the `appArmorProfile` fields and volumes of other types than are modelled here; only the annotations and modelled volume types are checked.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Control {
+    HostProcess,
+    HostNamespaces,
+    PrivilegedContainers,
+    Capabilities,
+    HostPathVolumes,
+    HostPorts,
+    AppArmor,
+    SELinux,
+    ProcMountType,
+    Seccomp,
+    Sysctls,
+    VolumeTypes,
+    PrivilegeEscalation,
+    RunningAsNonRoot,
+    RunningAsNonRootUser,
+    SeccompRestricted,
+    CapabilitiesRestricted
+}
+
+impl Control {
+    /// The lowest level the control is part of.
+    pub fn level(&self) -> Level {
+        if *self >= Self::VolumeTypes { Level::Restricted } else { Level::Baseline }
+    }
+
+    /// The reason `PodSecurity` denies admission with when the control is violated.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::HostProcess => "hostProcess",
+            Self::HostNamespaces => "host namespaces",
+            Self::PrivilegedContainers => "privileged",
+            Self::Capabilities | Self::CapabilitiesRestricted => "unrestricted capabilities",
+            Self::HostPathVolumes => "hostPath volumes",
+            Self::HostPorts => "hostPort",
+            Self::AppArmor => "forbidden AppArmor profile",
+            Self::SELinux => "seLinuxOptions",
+            Self::ProcMountType => "procMount",
+            Self::Seccomp | Self::SeccompRestricted => "seccompProfile",
+            Self::Sysctls => "forbidden sysctls",
+            Self::VolumeTypes => "restricted volume types",
+            Self::PrivilegeEscalation => "allowPrivilegeEscalation != false",
+            Self::RunningAsNonRoot => "runAsNonRoot != true",
+            Self::RunningAsNonRootUser => "runAsUser=0"
+        }
+    }
+}
+
+impl fmt::Display for Control {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.reason())
+    }
+}
+
+/// A control violated by a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub control: Control,
+    /// Path of the offending field in the checked object, as `spec.template.spec.containers[0].securityContext.privileged`.
+    pub path: String,
+    /// The offending value, or what is missing when the field is unset.
+    pub value: String
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}: {})", self.control, self.path, self.value)
+    }
+}
+
+/// Objects that create pods, and so are checked by what they create.
+pub trait Workload {
+    /// Path of the pod template in the object, empty for a pod itself.
+    fn template_path(&self) -> &'static str;
+
+    /// The metadata and spec of the pods created.
+    fn pod_template(&self) -> Option<(Option<&Metadata>, &Spec)>;
+}
+
+impl Workload for Pod {
+    fn template_path(&self) -> &'static str {
+        ""
+    }
+
+    fn pod_template(&self) -> Option<(Option<&Metadata>, &Spec)> {
+        Some((Some(&self.metadata), &self.spec))
+    }
+}
+
+impl Workload for PodTemplateSpec {
+    fn template_path(&self) -> &'static str {
+        "template."
+    }
+
+    fn pod_template(&self) -> Option<(Option<&Metadata>, &Spec)> {
+        Some((self.metadata.as_ref(), &self.spec))
+    }
+}
+
+impl Workload for Deployment {
+    fn template_path(&self) -> &'static str {
+        "spec.template."
+    }
+
+    fn pod_template(&self) -> Option<(Option<&Metadata>, &Spec)> {
+        self.spec.as_ref().and_then(|spec| spec.template.pod_template())
+    }
+}
+
+impl Workload for ReplicaSet {
+    fn template_path(&self) -> &'static str {
+        "spec.template."
+    }
+
+    fn pod_template(&self) -> Option<(Option<&Metadata>, &Spec)> {
+        self.spec.template.as_ref().and_then(Workload::pod_template)
+    }
+}
+
+impl Workload for DaemonSet {
+    fn template_path(&self) -> &'static str {
+        "spec.template."
+    }
+
+    fn pod_template(&self) -> Option<(Option<&Metadata>, &Spec)> {
+        self.spec.template.pod_template()
+    }
+}
+
+impl Workload for StatefulSet {
+    fn template_path(&self) -> &'static str {
+        "spec.template."
+    }
+
+    fn pod_template(&self) -> Option<(Option<&Metadata>, &Spec)> {
+        self.spec.template.pod_template()
+    }
+}
+
+impl Workload for Job {
+    fn template_path(&self) -> &'static str {
+        "spec.template."
+    }
+
+    fn pod_template(&self) -> Option<(Option<&Metadata>, &Spec)> {
+        self.spec.template.pod_template()
+    }
+}
+
+impl Workload for CronJob {
+    fn template_path(&self) -> &'static str {
+        "spec.jobTemplate.spec.template."
+    }
+
+    fn pod_template(&self) -> Option<(Option<&Metadata>, &Spec)> {
+        self.spec.job_template.spec.as_ref().and_then(|spec| spec.template.pod_template())
+    }
+}
+
+struct Checker {
+    profile: Profile,
+    violations: Vec<Violation>
+}
+
+impl Checker {
+    fn violate(&mut self, control: Control, path: String, value: impl Into<String>) {
+        self.violations.push(Violation { control, path, value: value.into() });
+    }
+
+    fn check(&mut self, prefix: &str, metadata: Option<&Metadata>, spec: &Spec) {
+        if self.profile.level == Level::Privileged {
+            return
+        }
+        let containers: Vec<(String, &Container)> = spec.init_containers.iter().enumerate()
+            .map(|(index, container)| (format!("{}spec.initContainers[{}]", prefix, index), container))
+            .chain(spec.containers.iter().enumerate()
+                .map(|(index, container)| (format!("{}spec.containers[{}]", prefix, index), container)))
+            .collect();
+        self.baseline(prefix, metadata, spec, &containers);
+        if self.profile.level == Level::Restricted {
+            self.restricted(prefix, spec, &containers);
+        }
+    }
+
+    fn baseline(
+        &mut self,
+        prefix: &str,
+        metadata: Option<&Metadata>,
+        spec: &Spec,
+        containers: &[(String, &Container)]
+    ) {
+        let version = self.profile.version;
+        let pod_context = spec.security_context.as_ref();
+        let pod_path = format!("{}spec.securityContext", prefix);
+
+        if pod_context.and_then(|context| context.windows_options.as_ref())
+            .and_then(|options| options.host_process) == Some(true)
+        {
+            self.violate(Control::HostProcess,
+                format!("{}.windowsOptions.hostProcess", pod_path), "true");
+        }
+        for (name, enabled) in [
+            ("hostNetwork", spec.host_network), ("hostPID", spec.host_pid),
+            ("hostIPC", spec.host_ipc)
+        ] {
+            if enabled {
+                self.violate(Control::HostNamespaces, format!("{}spec.{}", prefix, name), "true");
+            }
+        }
+        for (index, volume) in spec.volumes.iter().enumerate() {
+            if let Some(host_path) = &volume.host_path {
+                self.violate(Control::HostPathVolumes,
+                    format!("{}spec.volumes[{}].hostPath", prefix, index),
+                    host_path.path.display().to_string());
+            }
+        }
+        if let Some(metadata) = metadata {
+            let mut annotations: Vec<_> = metadata.annotations.iter().collect();
+            annotations.sort();
+            for (key, value) in annotations {
+                let path = format!("{}metadata.annotations[{:?}]", prefix, key);
+                if key.starts_with(APPARMOR_PREFIX) && !(value.is_empty()
+                    || value == "runtime/default" || value.starts_with("localhost/"))
+                {
+                    self.violate(Control::AppArmor, path, value.as_str());
+                } else if !version.at_least(19)
+                    && (key == SECCOMP_POD_ANNOTATION || key.starts_with(SECCOMP_CONTAINER_PREFIX))
+                    && value == "unconfined"
+                {
+                    self.violate(Control::Seccomp, path, value.as_str());
+                }
+            }
+        }
+        if let Some(context) = pod_context {
+            self.se_linux(&pod_path, context.se_linux_options.as_ref());
+            if version.at_least(19) && context.seccomp_profile.as_ref()
+                .is_some_and(|profile| profile.r#type == "Unconfined")
+            {
+                self.violate(Control::Seccomp, format!("{}.seccompProfile.type", pod_path),
+                    "Unconfined");
+            }
+            for (index, sysctl) in context.sysctls.iter().enumerate() {
+                if !SAFE_SYSCTLS.iter()
+                    .any(|(name, minor)| *name == sysctl.name && version.at_least(*minor))
+                {
+                    self.violate(Control::Sysctls,
+                        format!("{}.sysctls[{}].name", pod_path, index), sysctl.name.as_str());
+                }
+            }
+        }
+        for (path, container) in containers {
+            for (index, port) in container.ports.iter().enumerate() {
+                if let Some(host_port) = port.host_port.filter(|port| *port != 0) {
+                    self.violate(Control::HostPorts,
+                        format!("{}.ports[{}].hostPort", path, index), host_port.to_string());
+                }
+            }
+            let Some(context) = &container.security_context else {
+                continue
+            };
+            let path = format!("{}.securityContext", path);
+            if context.windows_options.as_ref()
+                .and_then(|options| options.host_process) == Some(true)
+            {
+                self.violate(Control::HostProcess,
+                    format!("{}.windowsOptions.hostProcess", path), "true");
+            }
+            if context.privileged == Some(true) {
+                self.violate(Control::PrivilegedContainers, format!("{}.privileged", path),
+                    "true");
+            }
+            if let Some(capabilities) = &context.capabilities {
+                let added: Vec<_> = capabilities.add.iter()
+                    .filter(|capability| !BASELINE_CAPABILITIES.contains(&capability.as_str()))
+                    .map(String::as_str)
+                    .collect();
+                if !added.is_empty() {
+                    self.violate(Control::Capabilities, format!("{}.capabilities.add", path),
+                        added.join(", "));
+                }
+            }
+            self.se_linux(&path, context.se_linux_options.as_ref());
+            if let Some(proc_mount) = context.proc_mount.as_ref()
+                .filter(|proc_mount| *proc_mount != "Default")
+            {
+                self.violate(Control::ProcMountType, format!("{}.procMount", path),
+                    proc_mount.as_str());
+            }
+            if version.at_least(19) && context.seccomp_profile.as_ref()
+                .is_some_and(|profile| profile.r#type == "Unconfined")
+            {
+                self.violate(Control::Seccomp, format!("{}.seccompProfile.type", path),
+                    "Unconfined");
+            }
+        }
+    }
+
+    fn se_linux(&mut self, path: &str, options: Option<&SELinuxOptions>) {
+        let Some(options) = options else {
+            return
+        };
+        let version = self.profile.version;
+        let r#type = options.r#type.as_deref().unwrap_or_default();
+        if !SELINUX_TYPES.iter().any(|(name, minor)| *name == r#type && version.at_least(*minor)) {
+            self.violate(Control::SELinux, format!("{}.seLinuxOptions.type", path), r#type);
+        }
+        for (name, value) in [("user", &options.user), ("role", &options.role)] {
+            if let Some(value) = value.as_ref().filter(|value| !value.is_empty()) {
+                self.violate(Control::SELinux, format!("{}.seLinuxOptions.{}", path, name),
+                    value.as_str());
+            }
+        }
+    }
+
+    fn restricted(&mut self, prefix: &str, spec: &Spec, containers: &[(String, &Container)]) {
+        let version = self.profile.version;
+        let pod_context = spec.security_context.as_ref();
+        let pod_path = format!("{}spec.securityContext", prefix);
+        // Since v1.25, controls not applying to Windows are skipped for pods declaring it.
+        let windows = version.at_least(25)
+            && spec.os.as_ref().is_some_and(|os| matches!(os.name, PodOSName::Windows));
+
+        for (index, volume) in spec.volumes.iter().enumerate() {
+            let r#type = if volume.host_path.is_some() {
+                "hostPath"
+            } else if volume.aws_elastic_block_store.is_some() {
+                "awsElasticBlockStore"
+            } else {
+                continue
+            };
+            self.violate(Control::VolumeTypes, format!("{}spec.volumes[{}]", prefix, index), r#type);
+        }
+
+        let pod_non_root = pod_context.and_then(|context| context.run_as_non_root);
+        if pod_non_root == Some(false) {
+            self.violate(Control::RunningAsNonRoot, format!("{}.runAsNonRoot", pod_path), "false");
+        }
+        if version.at_least(23) && pod_context.and_then(|context| context.run_as_user) == Some(0) {
+            self.violate(Control::RunningAsNonRootUser, format!("{}.runAsUser", pod_path), "0");
+        }
+        let pod_seccomp = pod_context.and_then(|context| context.seccomp_profile.as_ref())
+            .map(|profile| profile.r#type.as_str());
+        if version.at_least(19) && !windows {
+            if let Some(r#type) = pod_seccomp.filter(|r#type| !allowed_seccomp(r#type)) {
+                self.violate(Control::SeccompRestricted,
+                    format!("{}.seccompProfile.type", pod_path), r#type);
+            }
+        }
+
+        for (path, container) in containers {
+            let context = container.security_context.as_ref();
+            let path = format!("{}.securityContext", path);
+            let field = |name: &str| format!("{}.{}", path, name);
+            let get = |f: fn(&SecurityContext) -> Option<bool>| context.and_then(f);
+
+            if !windows && get(|context| context.allow_privilege_escalation) != Some(false) {
+                self.violate(Control::PrivilegeEscalation, field("allowPrivilegeEscalation"),
+                    "must be false");
+            }
+            match get(|context| context.run_as_non_root) {
+                Some(false) => self.violate(Control::RunningAsNonRoot, field("runAsNonRoot"),
+                    "false"),
+                None if pod_non_root != Some(true) => self.violate(Control::RunningAsNonRoot,
+                    field("runAsNonRoot"), "must be true at the pod or container level"),
+                _ => {}
+            }
+            if version.at_least(23)
+                && context.and_then(|context| context.run_as_user) == Some(0)
+            {
+                self.violate(Control::RunningAsNonRootUser, field("runAsUser"), "0");
+            }
+            if version.at_least(19) && !windows {
+                match context.and_then(|context| context.seccomp_profile.as_ref()) {
+                    Some(profile) if !allowed_seccomp(&profile.r#type) => self.violate(
+                        Control::SeccompRestricted, field("seccompProfile.type"),
+                        profile.r#type.as_str()),
+                    None if pod_seccomp.is_none() => self.violate(Control::SeccompRestricted,
+                        field("seccompProfile.type"),
+                        "must be RuntimeDefault or Localhost at the pod or container level"),
+                    _ => {}
+                }
+            }
+            if version.at_least(22) && !windows {
+                let capabilities = context.and_then(|context| context.capabilities.as_ref());
+                if !capabilities.is_some_and(|capabilities| {
+                    capabilities.drop.iter().any(|capability| capability == "ALL")
+                }) {
+                    self.violate(Control::CapabilitiesRestricted, field("capabilities.drop"),
+                        "must include ALL");
+                }
+                let added: Vec<_> = capabilities.iter()
+                    .flat_map(|capabilities| &capabilities.add)
+                    .filter(|capability| *capability != "NET_BIND_SERVICE")
+                    .map(String::as_str)
+                    .collect();
+                if !added.is_empty() {
+                    self.violate(Control::CapabilitiesRestricted, field("capabilities.add"),
+                        added.join(", "));
+                }
+            }
+        }
+    }
+}
+
+fn allowed_seccomp(r#type: &str) -> bool {
+    r#type == "RuntimeDefault" || r#type == "Localhost"
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// A level other than `privileged`, `baseline` or `restricted`.
+    Level(String),
+    /// A version other than `latest` or `v1.<minor>`.
+    Version(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Level(level) => write!(f, "invalid pod security level \"{}\"", level),
+            Self::Version(version) => write!(f, "invalid pod security version \"{}\"", version)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use crate::decode::decode;
+    use super::*;
+
+    fn privileged_pod() -> Pod {
+        decode("pod.kdl", r#"
+            pod {
+                metadata "debug" {
+                    annotations "container.apparmor.security.beta.kubernetes.io/shell"="unconfined"
+                }
+                spec host-network=true {
+                    volume "root" {
+                        host-path path="/"
+                    }
+                    container "shell" image="busybox" {
+                        port container-port=22 host-port=2222
+                        security-context privileged=true {
+                            capabilities {
+                                add "NET_ADMIN" "CHOWN"
+                            }
+                        }
+                    }
+                }
+            }
+        "#).unwrap()
+    }
+
+    fn controls(violations: &[Violation]) -> Vec<Control> {
+        violations.iter().map(|violation| violation.control).collect()
+    }
+
+    #[test]
+    fn reads_profiles_from_namespace_labels() {
+        let mut namespace = Metadata::default();
+        namespace.labels.insert(ENFORCE_LABEL.to_owned(), "baseline".to_owned());
+        namespace.labels.insert(ENFORCE_VERSION_LABEL.to_owned(), "v1.28".to_owned());
+        assert_eq!(Profile::from_labels(&namespace).unwrap(),
+            Profile::new(Level::Baseline, Version::V1(28)));
+        assert_eq!(Profile::from_labels(&Metadata::default()).unwrap(),
+            Profile::new(Level::Privileged, Version::Latest));
+        assert!(matches!("v1.+5".parse::<Version>(), Err(Error::Version(_))));
+        assert!(matches!("strict".parse::<Level>(), Err(Error::Level(_))));
+    }
+
+    #[test]
+    fn checks_the_baseline_level() {
+        let pod = privileged_pod();
+        assert!(Profile::new(Level::Privileged, Version::Latest).check(&pod).is_empty());
+        let violations = Profile::new(Level::Baseline, Version::Latest).check(&pod);
+        assert_eq!(controls(&violations), [
+            Control::HostNamespaces, Control::HostPathVolumes, Control::AppArmor,
+            Control::HostPorts, Control::PrivilegedContainers, Control::Capabilities
+        ]);
+        assert_eq!(violations[1].to_string(), "hostPath volumes (spec.volumes[0].hostPath: /)");
+        assert_eq!(violations[5].path, "spec.containers[0].securityContext.capabilities.add");
+        assert_eq!(violations[5].value, "NET_ADMIN");
+    }
+
+    #[test]
+    fn checks_the_restricted_level_at_its_version() {
+        let deployment: Deployment = decode("deployment.kdl", r#"
+            deployment {
+                metadata "web"
+                spec {
+                    selector {
+                        match-labels app="web"
+                    }
+                    template {
+                        spec {
+                            security-context run-as-non-root=true {
+                                seccomp-profile type="RuntimeDefault"
+                            }
+                            container "nginx" image="nginx" {
+                                security-context allow-privilege-escalation=false run-as-user=0
+                            }
+                        }
+                    }
+                }
+            }
+        "#).unwrap();
+        let violations = Profile::new(Level::Restricted, Version::Latest).check(&deployment);
+        assert_eq!(controls(&violations), [
+            Control::RunningAsNonRootUser, Control::CapabilitiesRestricted
+        ]);
+        assert_eq!(violations[0].path,
+            "spec.template.spec.containers[0].securityContext.runAsUser");
+        assert_eq!(violations[1].value, "must include ALL");
+        // Neither runAsUser=0 nor dropping capabilities were restricted before v1.22.
+        assert!(Profile::new(Level::Restricted, Version::V1(21)).check(&deployment).is_empty());
+    }
+}
@@ -1,8 +1,35 @@
-use kfl::Decode;
+use std::str::FromStr;
 
-#[derive(Debug, Decode)]
+use kfl::DecodeScalar;
+
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Protocol {
+    #[default]
     Tcp,
     Udp,
     Sctp
 }
+
+impl Protocol {
+    /// As in the API, `TCP`, `UDP` or `SCTP`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tcp => "TCP",
+            Self::Udp => "UDP",
+            Self::Sctp => "SCTP"
+        }
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "TCP" => Ok(Self::Tcp),
+            "UDP" => Ok(Self::Udp),
+            "SCTP" => Ok(Self::Sctp),
+            _ => Err(format!("unknown protocol {}", s))
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! Dataplane rules kube-proxy would program for a set of Services and EndpointSlices, rendered offline so they can be diffed without a cluster.
+//!
+//! - Reference <https://kubernetes.io/docs/reference/networking/virtual-ips/>
+//! - Source <https://github.com/kubernetes/kubernetes/tree/master/pkg/proxy>
+
+pub mod iptables;
+pub mod ipvs;
+pub mod nftables;
+
+use std::net::IpAddr;
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::kube_proxy::{KubeProxyConfiguration, ProxyMode},
+    core::service::{
+        ClusterIp, IpFamily, Service, SessionAffinity, TrafficPolicy, Type
+    },
+    discovery::endpoint_slice::{AddressType, EndpointSlice},
+    protocol::Protocol
+};
+
+/// Label set on every EndpointSlice naming the Service it belongs to.
+pub const SERVICE_NAME_LABEL: &str = "kubernetes.io/service-name";
+
+/// Default stickiness timeout for `ClientIp` session affinity (3 hours).
+pub const DEFAULT_STICKY_SECONDS: u32 = 10800;
+
+/// Renders the rules for [`mode`][KubeProxyConfiguration::mode] and `family`: `iptables-restore` input, an `nft -f` script or `ipvsadm` commands. `node_ips` are the addresses of the node's interfaces, which node ports are bound to in `ipvs` mode.
+pub fn generate(
+    config: &KubeProxyConfiguration,
+    services: &[Service],
+    endpoint_slices: &[EndpointSlice],
+    family: &IpFamily,
+    node_ips: &[IpAddr]
+) -> String {
+    let ports = service_ports(config, services, endpoint_slices, family);
+    match config.mode {
+        ProxyMode::IpTables => iptables::generate(config, &ports, family),
+        ProxyMode::IPVirtualServer => {
+            let node_ips: Vec<IpAddr> = node_ips.iter().copied()
+                .filter(|ip| in_family(ip, family))
+                .collect();
+            ipvs::generate(config, &ports, &node_ips)
+        }
+        ProxyMode::NfTables => nftables::generate(config, &ports, family)
+    }
+}
+
+/// A single port of a Service joined with its endpoints, the unit kube-proxy programs rules for.
+#[derive(Debug)]
+pub struct ServicePort {
+    pub namespace: String,
+    pub name: String,
+    /// Name of the port within the Service, empty for an unnamed port.
+    pub port_name: String,
+    pub protocol: Protocol,
+    pub port: u16,
+    pub cluster_ips: Vec<IpAddr>,
+    pub node_port: Option<u16>,
+    pub external_ips: Vec<IpAddr>,
+    pub load_balancer_ips: Vec<IpAddr>,
+    pub load_balancer_source_ranges: Vec<String>,
+    pub health_check_node_port: Option<u16>,
+    /// Stickiness timeout when session affinity is `ClientIp`.
+    pub sticky_seconds: Option<u32>,
+    pub internal_policy_local: bool,
+    pub external_policy_local: bool,
+    /// Endpoints sorted by address, the order kube-proxy balances over them.
+    pub endpoints: Vec<Endpoint>
+}
+
+#[derive(Debug)]
+pub struct Endpoint {
+    pub ip: IpAddr,
+    pub port: u16,
+    /// Whether the endpoint runs on the node named by [`hostname_override`][KubeProxyConfiguration::hostname_override].
+    pub is_local: bool,
+    pub ready: bool,
+    pub serving: bool,
+    pub terminating: bool
+}
+
+impl ServicePort {
+    /// `namespace/name:port`, the string kube-proxy prints in comments and hashes into chain names.
+    pub fn service_port_name(&self) -> String {
+        if self.port_name.is_empty() {
+            format!("{}/{}", self.namespace, self.name)
+        } else {
+            format!("{}/{}:{}", self.namespace, self.name, self.port_name)
+        }
+    }
+
+    /// Whether the port is reachable from outside the cluster through a node port, an external IP or a load balancer.
+    pub fn has_external_destinations(&self) -> bool {
+        self.node_port.is_some()
+            || !self.external_ips.is_empty()
+            || !self.load_balancer_ips.is_empty()
+    }
+
+    /// Whether traffic is balanced over endpoints on any node.
+    pub fn uses_cluster_endpoints(&self) -> bool {
+        !self.internal_policy_local
+            || (self.has_external_destinations() && !self.external_policy_local)
+    }
+
+    /// Whether traffic is balanced over endpoints on this node only.
+    pub fn uses_local_endpoints(&self) -> bool {
+        self.internal_policy_local
+            || (self.has_external_destinations() && self.external_policy_local)
+    }
+
+    /// Endpoints for cluster-wide traffic: the ready ones, or the serving terminating ones when none is ready.
+    pub fn cluster_endpoints(&self) -> Vec<&Endpoint> {
+        usable(self.endpoints.iter())
+    }
+
+    /// Endpoints for node-local traffic, chosen like [`cluster_endpoints`][Self::cluster_endpoints].
+    pub fn local_endpoints(&self) -> Vec<&Endpoint> {
+        usable(self.endpoints.iter().filter(|endpoint| endpoint.is_local))
+    }
+
+    /// Endpoints reachable through the cluster IPs.
+    pub fn internal_endpoints(&self) -> Vec<&Endpoint> {
+        if self.internal_policy_local {
+            self.local_endpoints()
+        } else {
+            self.cluster_endpoints()
+        }
+    }
+
+    /// Endpoints reachable through node ports, external IPs and load balancers.
+    pub fn external_endpoints(&self) -> Vec<&Endpoint> {
+        if self.external_policy_local {
+            self.local_endpoints()
+        } else {
+            self.cluster_endpoints()
+        }
+    }
+}
+
+impl Endpoint {
+    /// `ip:port`, with IPv6 addresses bracketed.
+    pub fn address(&self) -> String {
+        match self.ip {
+            IpAddr::V4(ip) => format!("{}:{}", ip, self.port),
+            IpAddr::V6(ip) => format!("[{}]:{}", ip, self.port)
+        }
+    }
+}
+
+fn usable<'a>(endpoints: impl Iterator<Item = &'a Endpoint>) -> Vec<&'a Endpoint> {
+    let (ready, not_ready): (Vec<_>, Vec<_>)
+        = endpoints.partition(|endpoint| endpoint.ready);
+    if !ready.is_empty() {
+        return ready
+    }
+    not_ready.into_iter()
+        .filter(|endpoint| endpoint.serving && endpoint.terminating)
+        .collect()
+}
+
+/// Whether `endpoint` is one of `endpoints`, by identity.
+pub(crate) fn contains(endpoints: &[&Endpoint], endpoint: &Endpoint) -> bool {
+    endpoints.iter().any(|candidate| std::ptr::eq(*candidate, endpoint))
+}
+
+/// Flattens `services` into one [`ServicePort`] per port and `family`, joined with the endpoints of their EndpointSlices. Headless and `ExternalName` Services are skipped, as kube-proxy does not program them.
+pub fn service_ports(
+    config: &KubeProxyConfiguration,
+    services: &[Service],
+    endpoint_slices: &[EndpointSlice],
+    family: &IpFamily
+) -> Vec<ServicePort> {
+    let mut ports = Vec::new();
+    for service in services {
+        let spec = &service.spec;
+        if matches!(spec.r#type, Type::ExternalName) {
+            continue
+        }
+        let declared = if spec.cluster_ips.is_empty() {
+            spec.cluster_ip.as_slice()
+        } else {
+            spec.cluster_ips.as_slice()
+        };
+        let cluster_ips: Vec<IpAddr> = declared.iter()
+            .filter_map(|cluster_ip| match cluster_ip {
+                ClusterIp::Ip(ip) => ip.parse().ok(),
+                ClusterIp::None | ClusterIp::Empty => None
+            })
+            .filter(|ip| in_family(ip, family))
+            .collect();
+        if cluster_ips.is_empty() {
+            continue
+        }
+        let namespace = service.metadata.namespace.as_deref()
+            .unwrap_or("default");
+        let name = service.metadata.name.as_deref().unwrap_or_default();
+        let slices: Vec<&EndpointSlice> = endpoint_slices.iter()
+            .filter(|slice|
+                slice.metadata.namespace.as_deref().unwrap_or("default")
+                    == namespace
+                && slice.metadata.labels.get(SERVICE_NAME_LABEL)
+                    .map(String::as_str) == Some(name)
+                && slice.address_type == match family {
+                    IpFamily::Ipv4 => AddressType::Ipv4,
+                    IpFamily::Ipv6 => AddressType::Ipv6
+                })
+            .collect();
+        let external_ips = parse_ips(&spec.external_ips, family);
+        let load_balancer_ips = match (&spec.r#type, &service.status) {
+            (Type::LoadBalancer, Some(status)) => parse_ips(
+                status.load_balancer.ingress.iter().map(|ingress| &ingress.ip),
+                family),
+            _ => Vec::new()
+        };
+        let sticky_seconds = match spec.session_affinity {
+            SessionAffinity::ClientIp => Some(
+                spec.session_affinity_config.as_ref()
+                    .and_then(|config| config.client_ip.as_ref())
+                    .and_then(|client_ip| client_ip.timeout_seconds)
+                    .map_or(DEFAULT_STICKY_SECONDS, u32::from)),
+            SessionAffinity::None => None
+        };
+        for port in &spec.ports {
+            let port_name = port.name.clone().unwrap_or_default();
+            let mut endpoints = Vec::new();
+            for slice in &slices {
+                let Some(target_port) = slice.ports.iter()
+                    .find(|slice_port|
+                        slice_port.name.as_deref().unwrap_or_default()
+                            == port_name
+                        && slice_port.protocol.unwrap_or(Protocol::Tcp)
+                            == port.protocol)
+                    .and_then(|slice_port| u16::try_from(slice_port.port).ok())
+                else {
+                    continue
+                };
+                for endpoint in &slice.endpoints {
+                    let Some(ip) = endpoint.addresses.first()
+                        .and_then(|address| address.parse().ok())
+                    else {
+                        continue
+                    };
+                    let conditions = endpoint.conditions.as_ref();
+                    let ready = conditions.and_then(|c| c.ready)
+                        .unwrap_or(true);
+                    endpoints.push(Endpoint {
+                        ip,
+                        port: target_port,
+                        is_local: endpoint.node_name.is_some()
+                            && endpoint.node_name.as_ref()
+                                == Some(&config.hostname_override),
+                        ready,
+                        serving: conditions.and_then(|c| c.serving)
+                            .unwrap_or(ready),
+                        terminating: conditions.and_then(|c| c.terminating)
+                            .unwrap_or(false)
+                    });
+                }
+            }
+            endpoints.sort_by_key(Endpoint::address);
+            endpoints.dedup_by_key(|endpoint| endpoint.address());
+            let exposes_node_port = matches!(
+                spec.r#type, Type::NodePort | Type::LoadBalancer);
+            ports.push(ServicePort {
+                namespace: namespace.to_owned(),
+                name: name.to_owned(),
+                port_name,
+                protocol: port.protocol,
+                port: port.port,
+                cluster_ips: cluster_ips.clone(),
+                node_port: port.node_port.filter(|_| exposes_node_port),
+                external_ips: external_ips.clone(),
+                load_balancer_ips: load_balancer_ips.clone(),
+                load_balancer_source_ranges:
+                    spec.load_balancer_source_ranges.clone(),
+                health_check_node_port: spec.health_check_node_port,
+                sticky_seconds,
+                internal_policy_local: matches!(
+                    spec.internal_traffic_policy, TrafficPolicy::Local),
+                external_policy_local: matches!(
+                    spec.external_traffic_policy, TrafficPolicy::Local),
+                endpoints
+            });
+        }
+    }
+    ports.sort_by_key(ServicePort::service_port_name);
+    ports
+}
+
+fn parse_ips<'a>(
+    ips: impl IntoIterator<Item = &'a String>,
+    family: &IpFamily
+) -> Vec<IpAddr> {
+    ips.into_iter()
+        .filter_map(|ip| ip.parse().ok())
+        .filter(|ip| in_family(ip, family))
+        .collect()
+}
+
+fn in_family(ip: &IpAddr, family: &IpFamily) -> bool {
+    match family {
+        IpFamily::Ipv4 => ip.is_ipv4(),
+        IpFamily::Ipv6 => ip.is_ipv6()
+    }
+}
+
+/// The entry of [`cluster_cidr`][KubeProxyConfiguration::cluster_cidr] (comma-separated when dual-stack) belonging to `family`.
+pub(crate) fn cluster_cidr<'a>(
+    config: &'a KubeProxyConfiguration,
+    family: &IpFamily
+) -> Option<&'a str> {
+    config.cluster_cidr.split(',')
+        .map(str::trim)
+        .filter(|cidr| !cidr.is_empty())
+        .find(|cidr| cidr.contains(':') == matches!(family, IpFamily::Ipv6))
+}
+
+/// The packet mark kube-proxy uses to flag connections for SNAT, `0x4000` by default.
+pub(crate) fn masquerade_mark(config: &KubeProxyConfiguration) -> String {
+    format!("{:#x}", config.iptables.masquerade_bit.mark())
+}
+
+pub(crate) fn protocol(protocol: &Protocol) -> &'static str {
+    match protocol {
+        Protocol::Tcp => "tcp",
+        Protocol::Udp => "udp",
+        Protocol::Sctp => "sctp"
+    }
+}
+
+/// Base32 encoded SHA-256 of `input`, from which kube-proxy derives its chain names.
+pub(crate) fn hash(input: &str) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut encoded = String::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in Sha256::digest(input.as_bytes()).iter() {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[(buffer >> bits) as usize & 31] as char);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        encoded.push(ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+    encoded
+}
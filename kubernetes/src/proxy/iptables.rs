@@ -0,0 +1,402 @@
+//! `iptables-restore` input as programmed by kube-proxy in `iptables` mode.
+//!
+//! Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/proxy/iptables/proxier.go>
+
+use crate::{
+    config::kube_proxy::KubeProxyConfiguration,
+    core::service::IpFamily,
+    proxy::{self, Endpoint, ServicePort}
+};
+
+pub const KUBE_SERVICES: &str = "KUBE-SERVICES";
+pub const KUBE_EXTERNAL_SERVICES: &str = "KUBE-EXTERNAL-SERVICES";
+pub const KUBE_NODEPORTS: &str = "KUBE-NODEPORTS";
+pub const KUBE_POSTROUTING: &str = "KUBE-POSTROUTING";
+pub const KUBE_MARK_MASQ: &str = "KUBE-MARK-MASQ";
+pub const KUBE_FORWARD: &str = "KUBE-FORWARD";
+pub const KUBE_PROXY_FIREWALL: &str = "KUBE-PROXY-FIREWALL";
+
+/// `KUBE-SVC-<hash>`, balancing over all endpoints of a port.
+pub fn service_chain(port: &ServicePort) -> String {
+    chain("KUBE-SVC-", port, "")
+}
+
+/// `KUBE-SVL-<hash>`, balancing over the endpoints of a port on this node.
+pub fn local_chain(port: &ServicePort) -> String {
+    chain("KUBE-SVL-", port, "")
+}
+
+/// `KUBE-EXT-<hash>`, entered by traffic to the node port, external IPs and load balancer IPs of a port.
+pub fn external_chain(port: &ServicePort) -> String {
+    chain("KUBE-EXT-", port, "")
+}
+
+/// `KUBE-FW-<hash>`, applying [`load_balancer_source_ranges`][ServicePort::load_balancer_source_ranges].
+pub fn firewall_chain(port: &ServicePort) -> String {
+    chain("KUBE-FW-", port, "")
+}
+
+/// `KUBE-SEP-<hash>`, DNATing to a single endpoint.
+pub fn endpoint_chain(port: &ServicePort, endpoint: &Endpoint) -> String {
+    chain("KUBE-SEP-", port, &endpoint.address())
+}
+
+fn chain(prefix: &str, port: &ServicePort, suffix: &str) -> String {
+    let hash = proxy::hash(&format!("{}{}{}",
+        port.service_port_name(), proxy::protocol(&port.protocol), suffix));
+    format!("{}{}", prefix, &hash[..16])
+}
+
+/// A table in `iptables-restore` format: chain declarations followed by rules.
+#[derive(Debug, Default)]
+struct Table {
+    chains: Vec<String>,
+    rules: Vec<String>
+}
+
+impl Table {
+    fn chain(&mut self, chain: &str) {
+        if !self.chains.iter().any(|declared| declared == chain) {
+            self.chains.push(chain.to_owned());
+        }
+    }
+
+    fn rule(&mut self, chain: &str, rule: String) {
+        self.chain(chain);
+        self.rules.push(format!("-A {} {}", chain, rule));
+    }
+
+    fn render(&self, name: &str, output: &mut String) {
+        output.push_str(&format!("*{}\n", name));
+        for chain in &self.chains {
+            output.push_str(&format!(":{} - [0:0]\n", chain));
+        }
+        for rule in &self.rules {
+            output.push_str(rule);
+            output.push('\n');
+        }
+        output.push_str("COMMIT\n");
+    }
+}
+
+/// Renders the `filter` and `nat` tables for `ports`, ready to be fed to `iptables-restore` (or `ip6tables-restore` for [`IpFamily::Ipv6`]).
+pub fn generate(
+    config: &KubeProxyConfiguration,
+    ports: &[ServicePort],
+    family: &IpFamily
+) -> String {
+    let mark = proxy::masquerade_mark(config);
+    let cluster_cidr = proxy::cluster_cidr(config, family);
+    let mut filter = Table::default();
+    let mut nat = Table::default();
+
+    for chain in [KUBE_SERVICES, KUBE_EXTERNAL_SERVICES, KUBE_FORWARD,
+                  KUBE_NODEPORTS, KUBE_PROXY_FIREWALL]
+    {
+        filter.chain(chain);
+    }
+    for chain in [KUBE_SERVICES, KUBE_NODEPORTS, KUBE_POSTROUTING,
+                  KUBE_MARK_MASQ]
+    {
+        nat.chain(chain);
+    }
+    nat.rule(KUBE_POSTROUTING,
+        format!("-m mark ! --mark {0}/{0} -j RETURN", mark));
+    nat.rule(KUBE_POSTROUTING, format!("-j MARK --xor-mark {}", mark));
+    nat.rule(KUBE_POSTROUTING, "-m comment --comment \"kubernetes service traffic requiring SNAT\" -j MASQUERADE --random-fully".to_owned());
+    nat.rule(KUBE_MARK_MASQ, format!("-j MARK --or-mark {}", mark));
+
+    for port in ports {
+        let name = port.service_port_name();
+        let protocol = proxy::protocol(&port.protocol);
+        let matcher = format!("-m {0} -p {0}", protocol);
+        let cluster_endpoints = port.cluster_endpoints();
+        let local_endpoints = port.local_endpoints();
+        let internal_endpoints = port.internal_endpoints();
+        let external_endpoints = port.external_endpoints();
+        let uses_cluster_chain = port.uses_cluster_endpoints()
+            && !cluster_endpoints.is_empty();
+        let uses_local_chain = port.uses_local_endpoints()
+            && !local_endpoints.is_empty();
+        let internal_chain = if port.internal_policy_local {
+            local_chain(port)
+        } else {
+            service_chain(port)
+        };
+        let external_target = if port.external_policy_local {
+            local_chain(port)
+        } else {
+            service_chain(port)
+        };
+        let ext = external_chain(port);
+        let uses_external_chain = port.has_external_destinations()
+            && !external_endpoints.is_empty();
+
+        // Cluster IPs.
+        for ip in &port.cluster_ips {
+            if internal_endpoints.is_empty() {
+                filter.rule(KUBE_SERVICES, format!(
+                    "-m comment --comment \"{} has no endpoints\" {} -d {} --dport {} -j REJECT",
+                    name, matcher, ip, port.port));
+            } else {
+                nat.rule(KUBE_SERVICES, format!(
+                    "-m comment --comment \"{} cluster IP\" {} -d {} --dport {} -j {}",
+                    name, matcher, ip, port.port, internal_chain));
+            }
+        }
+
+        // External IPs.
+        for ip in &port.external_ips {
+            if uses_external_chain {
+                nat.rule(KUBE_SERVICES, format!(
+                    "-m comment --comment \"{} external IP\" {} -d {} --dport {} -j {}",
+                    name, matcher, ip, port.port, ext));
+            } else {
+                filter.rule(KUBE_EXTERNAL_SERVICES, format!(
+                    "-m comment --comment \"{} has no endpoints\" {} -d {} --dport {} -j REJECT",
+                    name, matcher, ip, port.port));
+            }
+        }
+
+        // Load balancer IPs.
+        let firewall = firewall_chain(port);
+        let uses_firewall = !port.load_balancer_source_ranges.is_empty();
+        for ip in &port.load_balancer_ips {
+            if uses_external_chain {
+                nat.rule(KUBE_SERVICES, format!(
+                    "-m comment --comment \"{} loadbalancer IP\" {} -d {} --dport {} -j {}",
+                    name, matcher, ip, port.port,
+                    if uses_firewall { &firewall } else { &ext }));
+                if uses_firewall {
+                    filter.rule(KUBE_PROXY_FIREWALL, format!(
+                        "-m comment --comment \"{} traffic not accepted by {}\" {} -d {} --dport {} -j DROP",
+                        name, firewall, matcher, ip, port.port));
+                }
+            } else {
+                filter.rule(KUBE_EXTERNAL_SERVICES, format!(
+                    "-m comment --comment \"{} has no endpoints\" {} -d {} --dport {} -j REJECT",
+                    name, matcher, ip, port.port));
+            }
+        }
+
+        // Node port.
+        if let Some(node_port) = port.node_port {
+            if uses_external_chain {
+                nat.rule(KUBE_NODEPORTS, format!(
+                    "-m comment --comment {} {} --dport {} -j {}",
+                    name, matcher, node_port, ext));
+            } else {
+                filter.rule(KUBE_EXTERNAL_SERVICES, format!(
+                    "-m comment --comment \"{} has no endpoints\" -m addrtype --dst-type LOCAL {} --dport {} -j REJECT",
+                    name, matcher, node_port));
+            }
+        }
+        if let Some(health_check_node_port) = port.health_check_node_port {
+            filter.rule(KUBE_NODEPORTS, format!(
+                "-m comment --comment \"{} health check node port\" -m tcp -p tcp --dport {} -j ACCEPT",
+                name, health_check_node_port));
+        }
+
+        // External traffic chain.
+        if uses_external_chain {
+            if port.external_policy_local {
+                if uses_cluster_chain {
+                    if let Some(cidr) = cluster_cidr {
+                        nat.rule(&ext, format!(
+                            "-m comment --comment \"pod traffic for {} external destinations\" -s {} -j {}",
+                            name, cidr, service_chain(port)));
+                    }
+                    nat.rule(&ext, format!(
+                        "-m comment --comment \"masquerade LOCAL traffic for {} external destinations\" -m addrtype --src-type LOCAL -j {}",
+                        name, KUBE_MARK_MASQ));
+                    nat.rule(&ext, format!(
+                        "-m comment --comment \"route LOCAL traffic for {} external destinations\" -m addrtype --src-type LOCAL -j {}",
+                        name, service_chain(port)));
+                }
+            } else {
+                nat.rule(&ext, format!(
+                    "-m comment --comment \"masquerade traffic for {} external destinations\" -j {}",
+                    name, KUBE_MARK_MASQ));
+            }
+            nat.rule(&ext, format!("-j {}", external_target));
+        }
+
+        // Firewall chain.
+        if uses_external_chain && uses_firewall {
+            for range in &port.load_balancer_source_ranges {
+                nat.rule(&firewall, format!(
+                    "-m comment --comment \"{} loadbalancer IP\" -s {} -j {}",
+                    name, range, ext));
+            }
+            nat.rule(&firewall, format!(
+                "-m comment --comment \"other traffic to {} will be dropped by {}\"",
+                name, KUBE_PROXY_FIREWALL));
+        }
+
+        // Balancing chains.
+        if uses_cluster_chain {
+            let chain = service_chain(port);
+            for ip in &port.cluster_ips {
+                let masquerade = if config.iptables.masquerade_all {
+                    String::new()
+                } else if let Some(cidr) = cluster_cidr {
+                    format!(" ! -s {}", cidr)
+                } else {
+                    continue
+                };
+                nat.rule(&chain, format!(
+                    "-m comment --comment \"{} cluster IP\" {} -d {} --dport {}{} -j {}",
+                    name, matcher, ip, port.port, masquerade, KUBE_MARK_MASQ));
+            }
+            balance(&mut nat, &chain, port, &cluster_endpoints);
+        }
+        if uses_local_chain {
+            balance(&mut nat, &local_chain(port), port, &local_endpoints);
+        }
+
+        // Endpoint chains.
+        let balanced = |endpoint: &Endpoint|
+            (uses_cluster_chain && proxy::contains(&cluster_endpoints, endpoint))
+            || (uses_local_chain && proxy::contains(&local_endpoints, endpoint));
+        for endpoint in port.endpoints.iter().filter(|e| balanced(e)) {
+            let chain = endpoint_chain(port, endpoint);
+            nat.rule(&chain, format!("-m comment --comment {} -s {} -j {}",
+                name, endpoint.ip, KUBE_MARK_MASQ));
+            let affinity = match port.sticky_seconds {
+                Some(_) => format!(" -m recent --name {} --set", chain),
+                None => String::new()
+            };
+            nat.rule(&chain, format!(
+                "-m comment --comment {} {}{} -j DNAT --to-destination {}",
+                name, matcher, affinity, endpoint.address()));
+        }
+    }
+
+    nat.rule(KUBE_SERVICES, format!("-m comment --comment \"kubernetes service nodeports; NOTE: this must be the last rule in this chain\" -m addrtype --dst-type LOCAL -j {}", KUBE_NODEPORTS));
+    filter.rule(KUBE_FORWARD, "-m conntrack --ctstate INVALID -j DROP".to_owned());
+    filter.rule(KUBE_FORWARD, format!(
+        "-m comment --comment \"kubernetes forwarding rules\" -m mark --mark {0}/{0} -j ACCEPT",
+        mark));
+    filter.rule(KUBE_FORWARD, "-m comment --comment \"kubernetes forwarding conntrack rule\" -m conntrack --ctstate RELATED,ESTABLISHED -j ACCEPT".to_owned());
+
+    let mut output = String::new();
+    filter.render("filter", &mut output);
+    nat.render("nat", &mut output);
+    output
+}
+
+/// Spreads traffic entering `chain` evenly over `endpoints` with the `statistic` module, after the `recent` matches that keep sticky clients on their endpoint.
+fn balance(
+    nat: &mut Table,
+    chain: &str,
+    port: &ServicePort,
+    endpoints: &[&Endpoint]
+) {
+    let name = port.service_port_name();
+    if let Some(seconds) = port.sticky_seconds {
+        for endpoint in endpoints {
+            let target = endpoint_chain(port, endpoint);
+            nat.rule(chain, format!(
+                "-m comment --comment \"{} -> {}\" -m recent --name {} --rcheck --seconds {} --reap -j {}",
+                name, endpoint.address(), target, seconds, target));
+        }
+    }
+    let count = endpoints.len();
+    for (index, endpoint) in endpoints.iter().enumerate() {
+        let probability = if index + 1 < count {
+            format!(" -m statistic --mode random --probability {:.10}",
+                1.0 / (count - index) as f64)
+        } else {
+            String::new()
+        };
+        nat.rule(chain, format!("-m comment --comment \"{} -> {}\"{} -j {}",
+            name, endpoint.address(), probability,
+            endpoint_chain(port, endpoint)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Protocol;
+
+    fn endpoint(ip: &str, is_local: bool) -> Endpoint {
+        Endpoint { ip: ip.parse().unwrap(), port: 8080, is_local, ready: true, serving: true, terminating: false }
+    }
+
+    fn service_port(endpoints: Vec<Endpoint>) -> ServicePort {
+        ServicePort {
+            namespace: "default".to_owned(),
+            name: "web".to_owned(),
+            port_name: "http".to_owned(),
+            protocol: Protocol::Tcp,
+            port: 80,
+            cluster_ips: vec!["10.96.0.10".parse().unwrap()],
+            node_port: None,
+            external_ips: Vec::new(),
+            load_balancer_ips: Vec::new(),
+            load_balancer_source_ranges: Vec::new(),
+            health_check_node_port: None,
+            sticky_seconds: None,
+            internal_policy_local: false,
+            external_policy_local: false,
+            endpoints
+        }
+    }
+
+    #[test]
+    fn balances_cluster_ips_over_endpoints() {
+        let config = KubeProxyConfiguration {
+            cluster_cidr: "10.244.0.0/16".to_owned(),
+            ..Default::default()
+        };
+        let port = service_port(vec![
+            endpoint("10.244.1.2", false),
+            endpoint("10.244.2.2", false)
+        ]);
+        let service = service_chain(&port);
+        let first = endpoint_chain(&port, &port.endpoints[0]);
+        let second = endpoint_chain(&port, &port.endpoints[1]);
+        let rules = generate(&config, &[port], &IpFamily::Ipv4);
+        assert!(rules.starts_with("*filter\n"));
+        assert!(rules.contains(&format!("-A KUBE-SERVICES -m comment --comment \"default/web:http cluster IP\" -m tcp -p tcp -d 10.96.0.10 --dport 80 -j {}\n", service)));
+        assert!(rules.contains(&format!("-A {} -m comment --comment \"default/web:http cluster IP\" -m tcp -p tcp -d 10.96.0.10 --dport 80 ! -s 10.244.0.0/16 -j KUBE-MARK-MASQ\n", service)));
+        assert!(rules.contains(&format!("-A {} -m comment --comment \"default/web:http -> 10.244.1.2:8080\" -m statistic --mode random --probability 0.5000000000 -j {}\n", service, first)));
+        assert!(rules.contains(&format!("-A {} -m comment --comment \"default/web:http -> 10.244.2.2:8080\" -j {}\n", service, second)));
+        assert!(rules.contains(&format!("-A {} -m comment --comment default/web:http -m tcp -p tcp -j DNAT --to-destination 10.244.2.2:8080\n", second)));
+        assert!(rules.ends_with("COMMIT\n"));
+    }
+
+    #[test]
+    fn rejects_traffic_to_ports_without_endpoints() {
+        let config = KubeProxyConfiguration::default();
+        let mut port = service_port(Vec::new());
+        port.node_port = Some(30080);
+        let service = service_chain(&port);
+        let rules = generate(&config, &[port], &IpFamily::Ipv4);
+        assert!(rules.contains("-A KUBE-SERVICES -m comment --comment \"default/web:http has no endpoints\" -m tcp -p tcp -d 10.96.0.10 --dport 80 -j REJECT\n"));
+        assert!(rules.contains("-A KUBE-EXTERNAL-SERVICES -m comment --comment \"default/web:http has no endpoints\" -m addrtype --dst-type LOCAL -m tcp -p tcp --dport 30080 -j REJECT\n"));
+        assert!(!rules.contains(&service));
+    }
+
+    #[test]
+    fn sends_local_external_traffic_to_local_endpoints() {
+        let config = KubeProxyConfiguration::default();
+        let mut port = service_port(vec![
+            endpoint("10.244.1.2", true),
+            endpoint("10.244.2.2", false)
+        ]);
+        port.node_port = Some(30080);
+        port.external_policy_local = true;
+        port.sticky_seconds = Some(60);
+        let external = external_chain(&port);
+        let local = local_chain(&port);
+        let local_endpoint = endpoint_chain(&port, &port.endpoints[0]);
+        let rules = generate(&config, &[port], &IpFamily::Ipv4);
+        assert!(rules.contains(&format!("-A KUBE-NODEPORTS -m comment --comment default/web:http -m tcp -p tcp --dport 30080 -j {}\n", external)));
+        assert!(rules.contains(&format!("-A {} -j {}\n", external, local)));
+        assert!(rules.contains(&format!("-A {0} -m comment --comment \"default/web:http -> 10.244.1.2:8080\" -m recent --name {1} --rcheck --seconds 60 --reap -j {1}\n", local, local_endpoint)));
+        assert!(!rules.contains(&format!("-A {} -m comment --comment \"default/web:http -> 10.244.2.2:8080\"", local)));
+        assert!(rules.contains(&format!("-A {0} -m comment --comment default/web:http -m tcp -p tcp -m recent --name {0} --set -j DNAT --to-destination 10.244.1.2:8080\n", local_endpoint)));
+    }
+}
@@ -0,0 +1,153 @@
+//! `ipvsadm` commands equivalent to the virtual servers kube-proxy programs in `ipvs` mode.
+//!
+//! Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/proxy/ipvs/proxier.go>
+
+use std::net::IpAddr;
+
+use crate::{
+    config::kube_proxy::KubeProxyConfiguration,
+    networking::cidr,
+    protocol::Protocol,
+    proxy::{Endpoint, ServicePort}
+};
+
+/// Dummy interface every virtual server address is bound to.
+pub const DUMMY_INTERFACE: &str = "kube-ipvs0";
+
+/// Scheduler used when [`scheduler`][crate::config::kube_proxy::IPVS::scheduler] is empty.
+pub const DEFAULT_SCHEDULER: &str = "rr";
+
+/**
+Renders one `ipvsadm -A` per virtual server followed by one `ipvsadm -a` per real server, of weight 1 each: the endpoints of a port are those it balances over, the serving terminating ones included when they are all it has left.
+
+Node ports are bound to the addresses of the node, `node_ips`, within the CIDRs of [`node_port_addresses`][KubeProxyConfiguration::node_port_addresses], or to all of them if it is empty.
+*/
+pub fn generate(config: &KubeProxyConfiguration, ports: &[ServicePort], node_ips: &[IpAddr])
+    -> String
+{
+    let scheduler = if config.ipvs.scheduler.is_empty() {
+        DEFAULT_SCHEDULER
+    } else {
+        config.ipvs.scheduler.as_str()
+    };
+    let node_ips: Vec<IpAddr> = node_ips.iter().copied()
+        .filter(|ip| config.node_port_addresses.is_empty()
+            || config.node_port_addresses.iter().any(|network| cidr::contains(network, ip)))
+        .collect();
+    let mut commands = Vec::new();
+    for port in ports {
+        let internal_endpoints = port.internal_endpoints();
+        let external_endpoints = port.external_endpoints();
+        let mut servers: Vec<(IpAddr, u16, &[&Endpoint])> = Vec::new();
+        for ip in &port.cluster_ips {
+            servers.push((*ip, port.port, &internal_endpoints));
+        }
+        for ip in port.external_ips.iter().chain(&port.load_balancer_ips) {
+            servers.push((*ip, port.port, &external_endpoints));
+        }
+        if let Some(node_port) = port.node_port {
+            for ip in &node_ips {
+                servers.push((*ip, node_port, &external_endpoints));
+            }
+        }
+        for (ip, service_port, endpoints) in servers {
+            let service = format!("{} {}", flag(&port.protocol),
+                address(&ip, service_port));
+            let persistence = match port.sticky_seconds {
+                Some(seconds) => format!(" -p {}", seconds),
+                None => String::new()
+            };
+            commands.push(format!("ipvsadm -A {} -s {}{}",
+                service, scheduler, persistence));
+            for endpoint in endpoints {
+                commands.push(format!("ipvsadm -a {} -r {} -m -w 1",
+                    service, endpoint.address()));
+            }
+        }
+    }
+    commands.push(String::new());
+    commands.join("\n")
+}
+
+fn flag(protocol: &Protocol) -> &'static str {
+    match protocol {
+        Protocol::Tcp => "-t",
+        Protocol::Udp => "-u",
+        Protocol::Sctp => "--sctp-service"
+    }
+}
+
+fn address(ip: &IpAddr, port: u16) -> String {
+    match ip {
+        IpAddr::V4(ip) => format!("{}:{}", ip, port),
+        IpAddr::V6(ip) => format!("[{}]:{}", ip, port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(ip: &str, ready: bool, serving: bool, terminating: bool) -> Endpoint {
+        Endpoint { ip: ip.parse().unwrap(), port: 8080, is_local: false, ready, serving, terminating }
+    }
+
+    fn service_port(endpoints: Vec<Endpoint>) -> ServicePort {
+        ServicePort {
+            namespace: "default".to_owned(),
+            name: "web".to_owned(),
+            port_name: String::new(),
+            protocol: Protocol::Tcp,
+            port: 80,
+            cluster_ips: vec!["10.96.0.10".parse().unwrap()],
+            node_port: Some(30080),
+            external_ips: Vec::new(),
+            load_balancer_ips: Vec::new(),
+            load_balancer_source_ranges: Vec::new(),
+            health_check_node_port: None,
+            sticky_seconds: None,
+            internal_policy_local: false,
+            external_policy_local: false,
+            endpoints
+        }
+    }
+
+    #[test]
+    fn terminating_fallback_endpoints_get_weight_one() {
+        let config = KubeProxyConfiguration::default();
+        let port = service_port(vec![
+            endpoint("10.0.0.1", false, true, true),
+            endpoint("10.0.0.2", false, false, true)
+        ]);
+        let rules = generate(&config, &[port], &[]);
+        assert_eq!(rules, "ipvsadm -A -t 10.96.0.10:80 -s rr\n\
+            ipvsadm -a -t 10.96.0.10:80 -r 10.0.0.1:8080 -m -w 1\n");
+    }
+
+    #[test]
+    fn ready_endpoints_shadow_terminating_ones() {
+        let config = KubeProxyConfiguration::default();
+        let port = service_port(vec![
+            endpoint("10.0.0.1", false, true, true),
+            endpoint("10.0.0.2", true, true, false)
+        ]);
+        let rules = generate(&config, &[port], &[]);
+        assert!(rules.contains("-r 10.0.0.2:8080 -m -w 1"));
+        assert!(!rules.contains("10.0.0.1:8080"));
+    }
+
+    #[test]
+    fn node_ports_bind_all_node_addresses_by_default() {
+        let mut config = KubeProxyConfiguration::default();
+        let node_ips: Vec<IpAddr> = vec!["192.168.1.5".parse().unwrap(), "172.16.0.5".parse().unwrap()];
+        let port = || service_port(vec![endpoint("10.0.0.1", true, true, false)]);
+        let rules = generate(&config, &[port()], &node_ips);
+        assert!(rules.contains("ipvsadm -A -t 192.168.1.5:30080 -s rr"));
+        assert!(rules.contains("ipvsadm -A -t 172.16.0.5:30080 -s rr"));
+
+        config.node_port_addresses = vec!["192.168.0.0/16".to_owned()];
+        let rules = generate(&config, &[port()], &node_ips);
+        assert!(rules.contains("ipvsadm -A -t 192.168.1.5:30080 -s rr"));
+        assert!(!rules.contains("172.16.0.5"));
+    }
+}
@@ -0,0 +1,327 @@
+//! `nft -f` script as programmed by kube-proxy in `nftables` mode.
+//!
+//! Source <https://github.com/kubernetes/kubernetes/blob/master/pkg/proxy/nftables/proxier.go>
+
+use crate::{
+    config::kube_proxy::KubeProxyConfiguration,
+    core::service::IpFamily,
+    proxy::{self, Endpoint, ServicePort}
+};
+
+pub const TABLE: &str = "kube-proxy";
+pub const SERVICE_IPS_MAP: &str = "service-ips";
+pub const SERVICE_NODEPORTS_MAP: &str = "service-nodeports";
+pub const NO_ENDPOINT_SERVICES_MAP: &str = "no-endpoint-services";
+pub const NO_ENDPOINT_NODEPORTS_MAP: &str = "no-endpoint-nodeports";
+
+/// Longest chain name kube-proxy generates before truncating it; nftables itself allows 256 bytes including the prefix.
+const CHAIN_NAME_MAX_LENGTH: usize = 200;
+
+/// `service-<hash>-<namespace>/<name>/<protocol>/<port>`, balancing over all endpoints of a port.
+pub fn service_chain(port: &ServicePort) -> String {
+    format!("service-{}", chain_base(port))
+}
+
+/// `local-<hash>-…`, balancing over the endpoints of a port on this node.
+pub fn local_chain(port: &ServicePort) -> String {
+    format!("local-{}", chain_base(port))
+}
+
+/// `external-<hash>-…`, entered by traffic to the node port, external IPs and load balancer IPs of a port.
+pub fn external_chain(port: &ServicePort) -> String {
+    format!("external-{}", chain_base(port))
+}
+
+/// `endpoint-<hash>-…__<ip>/<port>`, DNATing to a single endpoint.
+pub fn endpoint_chain(port: &ServicePort, endpoint: &Endpoint) -> String {
+    format!("endpoint-{}", hash_and_truncate(&format!("{}__{}/{}",
+        port_path(port), endpoint.ip, endpoint.port)))
+}
+
+fn port_path(port: &ServicePort) -> String {
+    format!("{}/{}/{}/{}", port.namespace, port.name,
+        proxy::protocol(&port.protocol), port.port_name)
+}
+
+fn chain_base(port: &ServicePort) -> String {
+    hash_and_truncate(&port_path(port))
+}
+
+fn hash_and_truncate(name: &str) -> String {
+    let mut name = format!("{}-{}", &proxy::hash(name)[..8], name);
+    if name.len() > CHAIN_NAME_MAX_LENGTH {
+        name.truncate(CHAIN_NAME_MAX_LENGTH - 3);
+        name.push_str("...");
+    }
+    name
+}
+
+/// Renders the `kube-proxy` table for `ports` as a script for `nft -f`.
+pub fn generate(
+    config: &KubeProxyConfiguration,
+    ports: &[ServicePort],
+    family: &IpFamily
+) -> String {
+    let (family_name, address_type, ip) = match family {
+        IpFamily::Ipv4 => ("ip", "ipv4_addr", "ip"),
+        IpFamily::Ipv6 => ("ip6", "ipv6_addr", "ip6")
+    };
+    let mark = proxy::masquerade_mark(config);
+    let cluster_cidr = proxy::cluster_cidr(config, family);
+    let table = format!("{} {}", family_name, TABLE);
+    let mut script = Vec::new();
+    let mut elements = Vec::new();
+    let mut add = |line: String| script.push(line);
+
+    add(format!("add table {} {{ comment \"rules for kube-proxy\" ; }}", table));
+    add(format!("add chain {} mark-for-masquerade", table));
+    add(format!("add rule {} mark-for-masquerade mark set mark or {}", table, mark));
+    add(format!("add chain {} masquerading {{ type nat hook postrouting priority srcnat ; }}", table));
+    add(format!("add rule {} masquerading mark and {} == 0 return", table, mark));
+    add(format!("add rule {} masquerading mark set mark xor {}", table, mark));
+    add(format!("add rule {} masquerading masquerade fully-random", table));
+    add(format!("add chain {} services", table));
+    add(format!("add chain {} nat-prerouting {{ type nat hook prerouting priority dstnat ; }}", table));
+    add(format!("add rule {} nat-prerouting jump services", table));
+    add(format!("add chain {} nat-output {{ type nat hook output priority -100 ; }}", table));
+    add(format!("add rule {} nat-output jump services", table));
+    add(format!("add chain {} filter-forward {{ type filter hook forward priority -101 ; }}", table));
+    add(format!("add rule {} filter-forward ct state new jump service-endpoints-check", table));
+    add(format!("add chain {} service-endpoints-check", table));
+    add(format!("add map {} {} {{ type {} . inet_proto . inet_service : verdict ; comment \"ClusterIP, ExternalIP and LoadBalancer IP traffic\" ; }}",
+        table, SERVICE_IPS_MAP, address_type));
+    add(format!("add map {} {} {{ type inet_proto . inet_service : verdict ; comment \"NodePort traffic\" ; }}",
+        table, SERVICE_NODEPORTS_MAP));
+    add(format!("add map {} {} {{ type {} . inet_proto . inet_service : verdict ; comment \"vmap to drop or reject packets to services with no endpoints\" ; }}",
+        table, NO_ENDPOINT_SERVICES_MAP, address_type));
+    add(format!("add map {} {} {{ type inet_proto . inet_service : verdict ; comment \"vmap to drop or reject packets to service nodeports with no endpoints\" ; }}",
+        table, NO_ENDPOINT_NODEPORTS_MAP));
+    add(format!("add rule {} services {} daddr . meta l4proto . th dport vmap @{}",
+        table, ip, SERVICE_IPS_MAP));
+    add(format!("add rule {} services fib daddr type local meta l4proto . th dport vmap @{}",
+        table, SERVICE_NODEPORTS_MAP));
+    add(format!("add rule {} service-endpoints-check {} daddr . meta l4proto . th dport vmap @{}",
+        table, ip, NO_ENDPOINT_SERVICES_MAP));
+    add(format!("add rule {} service-endpoints-check fib daddr type local meta l4proto . th dport vmap @{}",
+        table, NO_ENDPOINT_NODEPORTS_MAP));
+
+    for port in ports {
+        let protocol = proxy::protocol(&port.protocol);
+        let cluster_endpoints = port.cluster_endpoints();
+        let local_endpoints = port.local_endpoints();
+        let uses_cluster_chain = port.uses_cluster_endpoints()
+            && !cluster_endpoints.is_empty();
+        let uses_local_chain = port.uses_local_endpoints()
+            && !local_endpoints.is_empty();
+        let internal_chain = if port.internal_policy_local {
+            local_chain(port)
+        } else {
+            service_chain(port)
+        };
+        let external_target = if port.external_policy_local {
+            local_chain(port)
+        } else {
+            service_chain(port)
+        };
+        let ext = external_chain(port);
+        let has_internal_endpoints = !port.internal_endpoints().is_empty();
+        let uses_external_chain = port.has_external_destinations()
+            && !port.external_endpoints().is_empty();
+
+        for cluster_ip in &port.cluster_ips {
+            elements.push(if has_internal_endpoints {
+                format!("add element {} {} {{ {} . {} . {} : goto {} }}",
+                    table, SERVICE_IPS_MAP, cluster_ip, protocol, port.port,
+                    internal_chain)
+            } else {
+                format!("add element {} {} {{ {} . {} . {} comment \"{}\" : reject }}",
+                    table, NO_ENDPOINT_SERVICES_MAP, cluster_ip, protocol,
+                    port.port, port.service_port_name())
+            });
+        }
+        for external_ip in port.external_ips.iter()
+            .chain(&port.load_balancer_ips)
+        {
+            elements.push(if uses_external_chain {
+                format!("add element {} {} {{ {} . {} . {} : goto {} }}",
+                    table, SERVICE_IPS_MAP, external_ip, protocol, port.port,
+                    ext)
+            } else {
+                format!("add element {} {} {{ {} . {} . {} comment \"{}\" : drop }}",
+                    table, NO_ENDPOINT_SERVICES_MAP, external_ip, protocol,
+                    port.port, port.service_port_name())
+            });
+        }
+        if let Some(node_port) = port.node_port {
+            elements.push(if uses_external_chain {
+                format!("add element {} {} {{ {} . {} : goto {} }}",
+                    table, SERVICE_NODEPORTS_MAP, protocol, node_port, ext)
+            } else {
+                format!("add element {} {} {{ {} . {} comment \"{}\" : drop }}",
+                    table, NO_ENDPOINT_NODEPORTS_MAP, protocol, node_port,
+                    port.service_port_name())
+            });
+        }
+
+        if uses_external_chain {
+            add(format!("add chain {} {}", table, ext));
+            if port.external_policy_local {
+                if uses_cluster_chain {
+                    if let Some(cidr) = cluster_cidr {
+                        add(format!("add rule {} {} {} saddr {} goto {} comment \"short-circuit pod traffic\"",
+                            table, ext, ip, cidr, service_chain(port)));
+                    }
+                    add(format!("add rule {} {} fib saddr type local jump mark-for-masquerade comment \"masquerade local traffic\"",
+                        table, ext));
+                    add(format!("add rule {} {} fib saddr type local goto {} comment \"short-circuit local traffic\"",
+                        table, ext, service_chain(port)));
+                }
+            } else {
+                add(format!("add rule {} {} jump mark-for-masquerade", table, ext));
+            }
+            add(format!("add rule {} {} goto {}", table, ext, external_target));
+        }
+
+        if uses_cluster_chain {
+            let chain = service_chain(port);
+            add(format!("add chain {} {}", table, chain));
+            for cluster_ip in &port.cluster_ips {
+                if config.iptables.masquerade_all {
+                    add(format!("add rule {} {} {} daddr {} {} dport {} jump mark-for-masquerade",
+                        table, chain, ip, cluster_ip, protocol, port.port));
+                } else if let Some(cidr) = cluster_cidr {
+                    add(format!("add rule {} {} {} daddr {} {} dport {} {} saddr != {} jump mark-for-masquerade",
+                        table, chain, ip, cluster_ip, protocol, port.port, ip,
+                        cidr));
+                }
+            }
+            balance(&mut add, &table, ip, &chain, port, &cluster_endpoints);
+        }
+        if uses_local_chain {
+            let chain = local_chain(port);
+            add(format!("add chain {} {}", table, chain));
+            balance(&mut add, &table, ip, &chain, port, &local_endpoints);
+        }
+
+        let balanced = |endpoint: &Endpoint|
+            (uses_cluster_chain && proxy::contains(&cluster_endpoints, endpoint))
+            || (uses_local_chain && proxy::contains(&local_endpoints, endpoint));
+        for endpoint in port.endpoints.iter().filter(|e| balanced(e)) {
+            let chain = endpoint_chain(port, endpoint);
+            add(format!("add chain {} {}", table, chain));
+            add(format!("add rule {} {} {} saddr {} jump mark-for-masquerade",
+                table, chain, ip, endpoint.ip));
+            let affinity = match port.sticky_seconds {
+                Some(_) => format!(" update @affinity-{} {{ {} saddr : {} daddr }}",
+                    chain, ip, ip),
+                None => String::new()
+            };
+            add(format!("add rule {} {}{} meta l4proto {} dnat to {}",
+                table, chain, affinity, protocol, endpoint.address()));
+        }
+    }
+
+    script.extend(elements);
+    script.push(String::new());
+    script.join("\n")
+}
+
+/// Spreads traffic entering `chain` over `endpoints` with `numgen`, after the affinity sets that keep sticky clients on their endpoint.
+fn balance(
+    add: &mut impl FnMut(String),
+    table: &str,
+    ip: &str,
+    chain: &str,
+    port: &ServicePort,
+    endpoints: &[&Endpoint]
+) {
+    if let Some(seconds) = port.sticky_seconds {
+        for endpoint in endpoints {
+            let target = endpoint_chain(port, endpoint);
+            add(format!("add set {} affinity-{} {{ type {}_addr ; flags dynamic,timeout ; timeout {}s ; }}",
+                table, target, if ip == "ip" { "ipv4" } else { "ipv6" },
+                seconds));
+            add(format!("add rule {} {} {} saddr @affinity-{} goto {}",
+                table, chain, ip, target, target));
+        }
+    }
+    let targets: Vec<String> = endpoints.iter().enumerate()
+        .map(|(index, endpoint)|
+            format!("{} : goto {}", index, endpoint_chain(port, endpoint)))
+        .collect();
+    add(format!("add rule {} {} numgen random mod {} vmap {{ {} }}",
+        table, chain, endpoints.len(), targets.join(" , ")));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Protocol;
+
+    fn endpoint(ip: &str) -> Endpoint {
+        Endpoint { ip: ip.parse().unwrap(), port: 8080, is_local: false, ready: true, serving: true, terminating: false }
+    }
+
+    fn service_port(cluster_ip: &str, endpoints: Vec<Endpoint>) -> ServicePort {
+        ServicePort {
+            namespace: "default".to_owned(),
+            name: "web".to_owned(),
+            port_name: String::new(),
+            protocol: Protocol::Tcp,
+            port: 80,
+            cluster_ips: vec![cluster_ip.parse().unwrap()],
+            node_port: Some(30080),
+            external_ips: Vec::new(),
+            load_balancer_ips: Vec::new(),
+            load_balancer_source_ranges: Vec::new(),
+            health_check_node_port: None,
+            sticky_seconds: None,
+            internal_policy_local: false,
+            external_policy_local: false,
+            endpoints
+        }
+    }
+
+    #[test]
+    fn maps_service_ports_to_their_chains() {
+        let config = KubeProxyConfiguration::default();
+        let port = service_port("10.96.0.10",
+            vec![endpoint("10.244.1.2"), endpoint("10.244.2.2")]);
+        let service = service_chain(&port);
+        let external = external_chain(&port);
+        let first = endpoint_chain(&port, &port.endpoints[0]);
+        let second = endpoint_chain(&port, &port.endpoints[1]);
+        let script = generate(&config, &[port], &IpFamily::Ipv4);
+        assert!(script.starts_with("add table ip kube-proxy { comment \"rules for kube-proxy\" ; }\n"));
+        assert!(script.contains(&format!("add element ip kube-proxy service-ips {{ 10.96.0.10 . tcp . 80 : goto {} }}\n", service)));
+        assert!(script.contains(&format!("add element ip kube-proxy service-nodeports {{ tcp . 30080 : goto {} }}\n", external)));
+        assert!(script.contains(&format!("add rule ip kube-proxy {} jump mark-for-masquerade\n", external)));
+        assert!(script.contains(&format!("add rule ip kube-proxy {} numgen random mod 2 vmap {{ 0 : goto {} , 1 : goto {} }}\n", service, first, second)));
+        assert!(script.contains(&format!("add rule ip kube-proxy {} meta l4proto tcp dnat to 10.244.2.2:8080\n", second)));
+    }
+
+    #[test]
+    fn rejects_and_drops_traffic_without_endpoints() {
+        let config = KubeProxyConfiguration::default();
+        let port = service_port("fd00::10", Vec::new());
+        let service = service_chain(&port);
+        let script = generate(&config, &[port], &IpFamily::Ipv6);
+        assert!(script.starts_with("add table ip6 kube-proxy"));
+        assert!(script.contains("add element ip6 kube-proxy no-endpoint-services { fd00::10 . tcp . 80 comment \"default/web\" : reject }\n"));
+        assert!(script.contains("add element ip6 kube-proxy no-endpoint-nodeports { tcp . 30080 comment \"default/web\" : drop }\n"));
+        assert!(!script.contains(&service));
+    }
+
+    #[test]
+    fn keeps_sticky_clients_on_their_endpoint() {
+        let config = KubeProxyConfiguration::default();
+        let mut port = service_port("10.96.0.10", vec![endpoint("10.244.1.2")]);
+        port.sticky_seconds = Some(60);
+        let service = service_chain(&port);
+        let target = endpoint_chain(&port, &port.endpoints[0]);
+        let script = generate(&config, &[port], &IpFamily::Ipv4);
+        assert!(script.contains(&format!("add set ip kube-proxy affinity-{} {{ type ipv4_addr ; flags dynamic,timeout ; timeout 60s ; }}\n", target)));
+        assert!(script.contains(&format!("add rule ip kube-proxy {0} ip saddr @affinity-{1} goto {1}\n", service, target)));
+        assert!(script.contains(&format!("add rule ip kube-proxy {0} update @affinity-{0} {{ ip saddr : ip daddr }} meta l4proto tcp dnat to 10.244.1.2:8080\n", target)));
+    }
+}
@@ -2,4 +2,244 @@
 
 // use kfl::Decode;
 
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Sub, SubAssign},
+    str::FromStr
+};
+
 pub type Quantity = String;
+
+/// Amounts by resource name, as requests, limits or usage.
+pub type Amounts = HashMap<String, Amount>;
+
+const NANOS: i128 = 1_000_000_000;
+
+/// Decimal suffixes by their power of 1000, from nano (-3) to exa (6).
+const DECIMAL: [(&str, i32); 10] = [
+    ("n", -3), ("u", -2), ("m", -1), ("", 0), ("k", 1), ("M", 2), ("G", 3), ("T", 4), ("P", 5),
+    ("E", 6)
+];
+/// Binary suffixes by their power of 1024.
+const BINARY: [(&str, u32); 6] = [("Ki", 1), ("Mi", 2), ("Gi", 3), ("Ti", 4), ("Pi", 5), ("Ei", 6)];
+
+/**
+The value of a [`Quantity`], exact down to nano units, which it can be compared, added and subtracted as.
+
+It is written back with the largest suffix that keeps it exact, binary ones if it was parsed with one, as `1536Mi`, `500m` or `2G`.
+*/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Amount {
+    nanos: i128,
+    format: Format
+}
+
+/// Suffixes a [`Quantity`] is written with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// `m`, `k`, `M` and so on, or an exponent as `1e3`.
+    #[default]
+    Decimal,
+    /// `Ki`, `Mi` and so on.
+    Binary
+}
+
+impl Amount {
+    pub const ZERO: Self = Self { nanos: 0, format: Format::Decimal };
+
+    pub fn from_nanos(nanos: i128, format: Format) -> Self {
+        Self { nanos, format }
+    }
+
+    pub fn from_milli(milli: i64) -> Self {
+        Self::from_nanos(i128::from(milli) * 1_000_000, Format::Decimal)
+    }
+
+    pub fn from_value(value: i64, format: Format) -> Self {
+        Self::from_nanos(i128::from(value) * NANOS, format)
+    }
+
+    pub fn nanos(&self) -> i128 {
+        self.nanos
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// The value in milli units, rounded up, as CPU is counted.
+    pub fn milli_value(&self) -> i128 {
+        div_ceil(self.nanos, 1_000_000)
+    }
+
+    /// The value in units, rounded up, as memory is counted.
+    pub fn value(&self) -> i128 {
+        div_ceil(self.nanos, NANOS)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.nanos == 0
+    }
+
+    /// The value as a float, for ratios.
+    pub fn as_f64(&self) -> f64 {
+        self.nanos as f64 / NANOS as f64
+    }
+}
+
+fn div_ceil(value: i128, divisor: i128) -> i128 {
+    let quotient = value / divisor;
+    if value % divisor > 0 { quotient + 1 } else { quotient }
+}
+
+impl PartialEq for Amount {
+    fn eq(&self, other: &Self) -> bool {
+        self.nanos == other.nanos
+    }
+}
+
+impl Eq for Amount {}
+
+impl PartialOrd for Amount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Amount {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.nanos.cmp(&other.nanos)
+    }
+}
+
+impl Add for Amount {
+    type Output = Self;
+
+    /// Keeps the format of the left hand side, unless it is zero.
+    fn add(self, other: Self) -> Self {
+        let format = if self.is_zero() { other.format } else { self.format };
+        Self::from_nanos(self.nanos.saturating_add(other.nanos), format)
+    }
+}
+
+impl AddAssign for Amount {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Amount {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::from_nanos(self.nanos.saturating_sub(other.nanos), self.format)
+    }
+}
+
+impl SubAssign for Amount {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = Error;
+
+    /// Parses `<sign><digits>.<digits><suffix>`, where the suffix is a decimal or binary one or an exponent, rounding up below nano units.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error(s.to_owned());
+        let (negative, unsigned) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s)
+        };
+        let end = unsigned.find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(unsigned.len());
+        let (number, suffix) = unsigned.split_at(end);
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if whole.is_empty() && fraction.is_empty() || fraction.contains('.') {
+            return Err(invalid())
+        }
+        // The number as an integer of `digits` and a power of ten.
+        let digits: i128 = match format!("{}{}", whole, fraction).trim_start_matches('0') {
+            "" => 0,
+            digits => digits.parse().map_err(|_| invalid())?
+        };
+        let mut exponent = -(fraction.len() as i32);
+        let mut binary = 0;
+        let format = if let Some(&(_, power)) = BINARY.iter().find(|(name, _)| *name == suffix) {
+            binary = power;
+            Format::Binary
+        } else if let Some(&(_, power)) = DECIMAL.iter().find(|(name, _)| *name == suffix) {
+            exponent += power * 3;
+            Format::Decimal
+        } else if let Some(power) = suffix.strip_prefix(['e', 'E']) {
+            exponent += power.parse::<i32>().map_err(|_| invalid())?;
+            Format::Decimal
+        } else {
+            return Err(invalid())
+        };
+        // Nano units are 10^-9.
+        exponent += 9;
+        let mut nanos = digits.checked_mul(1 << (10 * binary)).ok_or_else(invalid)?;
+        if exponent >= 0 {
+            let scale = 10_i128.checked_pow(exponent as u32).ok_or_else(invalid)?;
+            nanos = nanos.checked_mul(scale).ok_or_else(invalid)?;
+        } else {
+            nanos = match 10_i128.checked_pow(exponent.unsigned_abs()) {
+                Some(scale) => div_ceil(nanos, scale),
+                None => i128::from(nanos > 0)
+            };
+        }
+        Ok(Self::from_nanos(if negative { -nanos } else { nanos }, format))
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.nanos == 0 {
+            return f.write_str("0")
+        }
+        if self.format == Format::Binary && self.nanos % NANOS == 0 {
+            let value = self.nanos / NANOS;
+            if let Some(&(suffix, power)) = BINARY.iter().rev()
+                .find(|(_, power)| value % (1 << (10 * power)) == 0)
+            {
+                return write!(f, "{}{}", value >> (10 * power), suffix)
+            }
+            if value.abs() < 1024 {
+                return write!(f, "{}", value)
+            }
+        }
+        let (suffix, power) = DECIMAL.iter().rev()
+            .find(|(_, power)| self.nanos % 1000_i128.pow((power + 3) as u32) == 0)
+            .unwrap_or(&DECIMAL[0]);
+        write!(f, "{}{}", self.nanos / 1000_i128.pow((power + 3) as u32), suffix)
+    }
+}
+
+/// Parses the quantities of a resource list, such as requests or limits.
+pub fn parse_all(list: &HashMap<String, Quantity>) -> Result<Amounts, Error> {
+    list.iter().map(|(name, quantity)| Ok((name.clone(), quantity.parse()?))).collect()
+}
+
+/// A quantity that is not one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(pub String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid quantity \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for Error {}
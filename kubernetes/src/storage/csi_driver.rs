@@ -1,21 +1,35 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/csi-driver-v1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
-use crate::meta::metadata::Metadata;
+use crate::{decode::Fields, meta::metadata::Metadata};
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/csi-driver-v1/#CsiDriver>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct CsiDriver {
-    metadata: Metadata,
-    spec: CsiDriverSpec
+    pub metadata: Metadata,
+    pub spec: CsiDriverSpec
+}
+
+impl Decode for CsiDriver {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let csi_driver = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            spec: fields.child("spec")?.ok_or_else(|| fields.missing("spec"))?
+        };
+        fields.finish()?;
+        Ok(csi_driver)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/csi-driver-v1/#CsiDriverSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct CsiDriverSpec {
-    attach_required: Option<bool>,
-    fs_group_policy: Option<FSGroupPolicy>
+    #[kfl(property, default)]
+    pub attach_required: Option<bool>,
+    #[kfl(child, default)]
+    pub fs_group_policy: Option<FSGroupPolicy>
 }
 
 #[derive(Debug, Decode)]
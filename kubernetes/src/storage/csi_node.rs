@@ -2,32 +2,60 @@
 Reference <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/csi-node-v1/>
 */
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
-use crate::meta::metadata::Metadata;
+use crate::{decode::Fields, meta::metadata::Metadata};
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/csi-node-v1/#CsiNode>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct CsiNode {
-    metadata: Metadata,
-    spec: CsiNodeSpec
+    pub metadata: Metadata,
+    pub spec: CsiNodeSpec
+}
+
+impl Decode for CsiNode {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let csi_node = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            spec: fields.child("spec")?.ok_or_else(|| fields.missing("spec"))?
+        };
+        fields.finish()?;
+        Ok(csi_node)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/csi-node-v1/#CsiNodeSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Decode, Default)]
 pub struct CsiNodeSpec {
-    drivers: Vec<CsiNodeDriver>
+    #[kfl(children)]
+    pub drivers: Vec<CsiNodeDriver>
 }
 
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct CsiNodeDriver {
-    name: String,
-    node_id: String,
-    allocatable: Option<VolumeNodeResources>,
-    topology_keys: Vec<String>
+    pub name: String,
+    pub node_id: String,
+    pub allocatable: Option<VolumeNodeResources>,
+    pub topology_keys: Vec<String>
+}
+
+impl Decode for CsiNodeDriver {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let csi_node_driver = Self {
+            name: fields.property("name")?.unwrap_or_default(),
+            node_id: fields.property("node-id")?.unwrap_or_default(),
+            allocatable: fields.child("allocatable")?,
+            topology_keys: fields.values("topology-keys")?
+        };
+        fields.finish()?;
+        Ok(csi_node_driver)
+    }
 }
 
 #[derive(Debug, Decode)]
 pub struct VolumeNodeResources {
-    count: Option<i32>
+    #[kfl(property, default)]
+    pub count: Option<i32>
 }
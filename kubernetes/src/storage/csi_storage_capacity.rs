@@ -1,8 +1,9 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/csi-storage-capacity-v1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
+    decode::Fields,
     meta::{
         label_selector::Selector,
         metadata::Metadata,
@@ -11,11 +12,26 @@ use crate::{
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/csi-storage-capacity-v1/#CsiStorageCapacity>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct CsiStorageCapacity {
-    metadata: Metadata,
-    storage_class_name: String,
-    capacity: Option<Quantity>,
-    maximum_volume_size: Option<Quantity>,
-    node_topology: Option<Selector>
+    pub metadata: Metadata,
+    pub storage_class_name: String,
+    pub capacity: Option<Quantity>,
+    pub maximum_volume_size: Option<Quantity>,
+    pub node_topology: Option<Selector>
+}
+
+impl Decode for CsiStorageCapacity {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let csi_storage_capacity = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            storage_class_name: fields.property("storage-class-name")?.unwrap_or_default(),
+            capacity: fields.property("capacity")?,
+            maximum_volume_size: fields.property("maximum-volume-size")?,
+            node_topology: fields.child("node-topology")?
+        };
+        fields.finish()?;
+        Ok(csi_storage_capacity)
+    }
 }
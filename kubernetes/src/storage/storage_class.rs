@@ -3,39 +3,102 @@
 
 use std::collections::HashMap;
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode, DecodeScalar};
 
 use crate::{
     core::persistent_volume::{MountOption, ReclaimPolicy},
+    decode::Fields,
     meta::metadata::Metadata
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/storage-class-v1/#StorageClass>
-#[derive(Debug, Decode)]
+#[derive(Debug, Clone)]
 pub struct StorageClass {
-    metadata: Option<Metadata>,
-    provisioner: String,
-    allow_volume_expansion: Option<bool>,
-    allowed_topologies: Vec<TopologySelectorTerm>,
-    mount_options: Vec<MountOption>,
-    parameters: HashMap<String, String>,
-    reclaim_policy: Option<ReclaimPolicy>,
-    volume_binding_mode: Option<VolumeBindingMode>
+    pub metadata: Option<Metadata>,
+    /// The provisioner of the volumes of the class, empty or `kubernetes.io/no-provisioner` if they are created by hand.
+    pub provisioner: String,
+    pub allow_volume_expansion: Option<bool>,
+    /// Where volumes may be provisioned; anywhere if empty.
+    pub allowed_topologies: Vec<TopologySelectorTerm>,
+    pub mount_options: Vec<MountOption>,
+    pub parameters: HashMap<String, String>,
+    /// `Delete` if `None`.
+    pub reclaim_policy: Option<ReclaimPolicy>,
+    /// `Immediate` if `None`.
+    pub volume_binding_mode: Option<VolumeBindingMode>
 }
 
-#[derive(Debug, Decode)]
+impl Decode for StorageClass {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let storage_class = Self {
+            metadata: fields.child("metadata")?,
+            provisioner: fields.property("provisioner")?.unwrap_or_default(),
+            allow_volume_expansion: fields.property("allow-volume-expansion")?,
+            allowed_topologies: fields.children("topology-selector-term")?,
+            mount_options: fields.values("mount-options")?,
+            parameters: fields.entries("parameters")?,
+            reclaim_policy: fields.property("reclaim-policy")?,
+            volume_binding_mode: fields.property("volume-binding-mode")?
+        };
+        fields.finish()?;
+        Ok(storage_class)
+    }
+}
+
+/// Labels of nodes, all of which must match.
+#[derive(Debug, Decode, Clone, Default)]
 pub struct TopologySelectorTerm {
-    match_label_expressions: Vec<TopologySelectorLabelRequirement>
+    #[kfl(children)]
+    pub match_label_expressions: Vec<TopologySelectorLabelRequirement>
 }
 
-#[derive(Debug, Decode)]
+impl TopologySelectorTerm {
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.match_label_expressions.iter().all(|requirement| requirement.matches(labels))
+    }
+}
+
+/// The label `key` with any of `values`.
+#[derive(Debug, Clone)]
 pub struct TopologySelectorLabelRequirement {
-    key: String,
-    values: Vec<String>
+    pub key: String,
+    pub values: Vec<String>
+}
+
+impl Decode for TopologySelectorLabelRequirement {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let topology_selector_label_requirement = Self {
+            key: fields.property("key")?.unwrap_or_default(),
+            values: fields.values("values")?
+        };
+        fields.finish()?;
+        Ok(topology_selector_label_requirement)
+    }
+}
+
+impl TopologySelectorLabelRequirement {
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        labels.get(&self.key).is_some_and(|value| self.values.contains(value))
+    }
 }
 
-#[derive(Debug, Decode, Default)]
+/// <https://kubernetes.io/docs/concepts/storage/storage-classes/#volume-binding-mode>
+#[derive(Debug, DecodeScalar, Clone, Copy, PartialEq, Eq, Default)]
 pub enum VolumeBindingMode {
+    /// Bind and provision as soon as the claim is created.
     #[default]
-    VolumeBindingImmediate
+    Immediate,
+    /// Bind and provision once a pod using the claim is scheduled, on its node.
+    WaitForFirstConsumer
+}
+
+impl VolumeBindingMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Immediate => "Immediate",
+            Self::WaitForFirstConsumer => "WaitForFirstConsumer"
+        }
+    }
 }
@@ -1,32 +1,71 @@
 //! Reference <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/volume-attachment-v1/>
 
-use kfl::Decode;
+use kfl::{ast::Node, context::Context, errors::DecodeError, Decode};
 
 use crate::{
-    core::persistent_volume::PersistentVolumeSpec,
+    core::persistent_volume::Spec as PersistentVolumeSpec,
+    decode::Fields,
     meta::metadata::Metadata,
 };
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/volume-attachment-v1/#VolumeAttachment>
-#[derive(Debug, Decode)]
+#[derive(Debug)]
 pub struct VolumeAttachment {
-    metadata: Metadata,
-    spec: VolumeAttachmentSpec,
-    status: Option<VolumeAttachmentStatus>
+    pub metadata: Metadata,
+    pub spec: VolumeAttachmentSpec,
+    pub status: Option<VolumeAttachmentStatus>
+}
+
+impl Decode for VolumeAttachment {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let volume_attachment = Self {
+            metadata: fields.child("metadata")?.ok_or_else(|| fields.missing("metadata"))?,
+            spec: fields.child("spec")?.ok_or_else(|| fields.missing("spec"))?,
+            status: fields.child("status")?
+        };
+        fields.finish()?;
+        Ok(volume_attachment)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/volume-attachment-v1/#VolumeAttachmentSpec>
-#[derive(Debug, Decode)]
+#[derive(Debug, Default)]
 pub struct VolumeAttachmentSpec {
-    attacher: String,
-    node_name: String,
-    source: VolumeAttachmentSource
+    pub attacher: String,
+    pub node_name: String,
+    pub source: VolumeAttachmentSource
 }
 
-#[derive(Debug, Decode)]
+impl Decode for VolumeAttachmentSpec {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let volume_attachment_spec = Self {
+            attacher: fields.property("attacher")?.unwrap_or_default(),
+            node_name: fields.property("node-name")?.unwrap_or_default(),
+            source: fields.child("source")?.unwrap_or_default()
+        };
+        fields.finish()?;
+        Ok(volume_attachment_spec)
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct VolumeAttachmentSource {
-    inline_volume_spec: Option<PersistentVolumeSpec>,
-    persistent_volume_name: Option<String>
+    pub inline_volume_spec: Option<PersistentVolumeSpec>,
+    pub persistent_volume_name: Option<String>
+}
+
+impl Decode for VolumeAttachmentSource {
+    fn decode(node: &Node, ctx: &mut Context) -> Result<Self, DecodeError> {
+        let mut fields = Fields::new(node, ctx);
+        let volume_attachment_source = Self {
+            inline_volume_spec: fields.child("inline-volume-spec")?,
+            persistent_volume_name: fields.property("persistent-volume-name")?
+        };
+        fields.finish()?;
+        Ok(volume_attachment_source)
+    }
 }
 
 /// <https://kubernetes.io/docs/reference/kubernetes-api/config-and-storage-resources/volume-attachment-v1/#VolumeAttachmentStatus>
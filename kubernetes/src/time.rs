@@ -1,11 +1,307 @@
-use kfl::Decode;
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH}
+};
 
-#[derive(Debug, Decode)]
-pub struct Time {
-    
+use kfl::DecodeScalar;
+
+/// <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/time/>, serialised in RFC 3339 with second precision, for example `2006-01-02T15:04:05Z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time(pub SystemTime);
+
+/// <https://kubernetes.io/docs/reference/kubernetes-api/common-definitions/micro-time/>, serialised in RFC 3339 with microsecond precision, for example `2006-01-02T15:04:05.000000Z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MicroTime(pub SystemTime);
+
+impl Time {
+    pub fn now() -> Self {
+        Self(SystemTime::now())
+    }
+}
+
+impl MicroTime {
+    pub fn now() -> Self {
+        Self(SystemTime::now())
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self(UNIX_EPOCH)
+    }
+}
+
+impl Default for MicroTime {
+    fn default() -> Self {
+        Self(UNIX_EPOCH)
+    }
+}
+
+impl From<SystemTime> for Time {
+    fn from(time: SystemTime) -> Self {
+        Self(time)
+    }
 }
 
-#[derive(Debug, Decode)]
-pub struct MicroTime {
-    
+impl From<SystemTime> for MicroTime {
+    fn from(time: SystemTime) -> Self {
+        Self(time)
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_rfc3339(f, self.0, false)
+    }
+}
+
+impl fmt::Display for MicroTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_rfc3339(f, self.0, true)
+    }
+}
+
+impl FromStr for Time {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_rfc3339(s).map(Self)
+    }
+}
+
+impl FromStr for MicroTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_rfc3339(s).map(Self)
+    }
+}
+
+impl DecodeScalar for Time {
+    fn decode(scalar: &kfl::ast::Scalar, ctx: &mut kfl::context::Context)
+        -> Result<Self, kfl::errors::DecodeError>
+    {
+        let time = String::decode(scalar, ctx)?;
+        time.parse().map_err(|message: String|
+            kfl::errors::DecodeError::conversion(ctx.span(&scalar), message))
+    }
+}
+
+impl DecodeScalar for MicroTime {
+    fn decode(scalar: &kfl::ast::Scalar, ctx: &mut kfl::context::Context)
+        -> Result<Self, kfl::errors::DecodeError>
+    {
+        let time = String::decode(scalar, ctx)?;
+        time.parse().map_err(|message: String|
+            kfl::errors::DecodeError::conversion(ctx.span(&scalar), message))
+    }
+}
+
+fn write_rfc3339(f: &mut fmt::Formatter<'_>, time: SystemTime, micros: bool)
+    -> fmt::Result
+{
+    let (seconds, nanos) = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+        Err(error) => {
+            let before = error.duration();
+            match before.subsec_nanos() {
+                0 => (-(before.as_secs() as i64), 0),
+                nanos => (-(before.as_secs() as i64) - 1, 1_000_000_000 - nanos)
+            }
+        }
+    };
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+    let seconds = seconds.rem_euclid(86400);
+    write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year, month, day, seconds / 3600, seconds / 60 % 60, seconds % 60)?;
+    if micros {
+        write!(f, ".{:06}", nanos / 1000)?;
+    }
+    write!(f, "Z")
+}
+
+/// Parses `YYYY-MM-DDTHH:MM:SS[.fraction](Z|±HH:MM)`.
+fn parse_rfc3339(input: &str) -> Result<SystemTime, String> {
+    let error = || format!("{:?} is not an RFC 3339 time", input);
+    let number = |s: &str| -> Result<i64, String> {
+        if s.is_empty() || !s.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(error())
+        }
+        s.parse().map_err(|_| error())
+    };
+    // Other characters are not valid anywhere, and slicing at a byte offset within one panics.
+    if !input.is_ascii() {
+        return Err(error())
+    }
+    let bytes = input.as_bytes();
+    if bytes.len() < 20 || bytes[4] != b'-' || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':' || bytes[16] != b':'
+    {
+        return Err(error())
+    }
+    let year = number(&input[0..4])?;
+    let month = number(&input[5..7])?;
+    let day = number(&input[8..10])?;
+    let hour = number(&input[11..13])?;
+    let minute = number(&input[14..16])?;
+    let second = number(&input[17..19])?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23
+        || minute > 59 || second > 60
+    {
+        return Err(error())
+    }
+    let mut rest = &input[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let end = fraction.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(fraction.len());
+        let digits = &fraction[..end];
+        if digits.is_empty() {
+            return Err(error())
+        }
+        nanos = digits.bytes().chain(std::iter::repeat(b'0')).take(9)
+            .fold(0, |nanos, digit| nanos * 10 + u32::from(digit - b'0'));
+        rest = &fraction[end..];
+    }
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return Err(error())
+            };
+            sign * (number(&rest[1..3])? * 3600 + number(&rest[4..6])? * 60)
+        }
+        _ => return Err(error())
+    };
+    let seconds = days_from_civil(year, month, day) * 86400
+        + hour * 3600 + minute * 60 + second - offset;
+    let nanos = Duration::from_nanos(u64::from(nanos));
+    Ok(if seconds >= 0 {
+        UNIX_EPOCH + Duration::from_secs(seconds as u64) + nanos
+    } else {
+        UNIX_EPOCH - Duration::from_secs(seconds.unsigned_abs()) + nanos
+    })
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100
+        + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+        - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era
+        - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// Parses a duration the way Go's `time.ParseDuration` does, as found in `metav1.Duration` fields, for example `1h30m` or `1.5s`. Negative durations are rejected.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    if input == "0" {
+        return Some(Duration::ZERO)
+    }
+    let mut rest = input.strip_prefix('+').unwrap_or(input);
+    if rest.is_empty() {
+        return None
+    }
+    let mut total = Duration::ZERO;
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(number_end);
+        let unit_end = tail.find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_end);
+        let nanos: f64 = match unit {
+            "ns" => 1.0,
+            "us" | "µs" | "μs" => 1e3,
+            "ms" => 1e6,
+            "s" => 1e9,
+            "m" => 60e9,
+            "h" => 3600e9,
+            _ => return None
+        };
+        if number.is_empty() || number == "." {
+            return None
+        }
+        let value: f64 = number.parse().ok()?;
+        total += Duration::from_nanos((value * nanos).round() as u64);
+        rest = tail;
+    }
+    Some(total)
+}
+
+/// Formats `duration` the way Go's `time.Duration.String` does, for example `1h0m0s` or `500ms`.
+pub fn format_duration(duration: &Duration) -> String {
+    let nanos = duration.as_nanos();
+    if nanos == 0 {
+        return "0s".to_owned()
+    }
+    if nanos < 1_000_000_000 {
+        let (unit, scale) = match nanos {
+            0..1_000 => ("ns", 1),
+            1_000..1_000_000 => ("µs", 1_000),
+            _ => ("ms", 1_000_000)
+        };
+        return format!("{}{}", fraction(nanos, scale), unit)
+    }
+    let hours = nanos / 3_600_000_000_000;
+    let minutes = nanos / 60_000_000_000 % 60;
+    let seconds = fraction(nanos % 60_000_000_000, 1_000_000_000);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, _) => format!("{}m{}s", minutes, seconds),
+        _ => format!("{}h{}m{}s", hours, minutes, seconds)
+    }
+}
+
+/// `value / scale` with the trailing zeros of its decimals trimmed.
+fn fraction(value: u128, scale: u128) -> String {
+    let whole = value / scale;
+    let decimals = value % scale;
+    if decimals == 0 {
+        return whole.to_string()
+    }
+    let width = scale.ilog10() as usize;
+    let decimals = format!("{:0width$}", decimals, width = width);
+    format!("{}.{}", whole, decimals.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc3339_round_trip() {
+        let time: Time = "2024-02-29T13:45:00Z".parse().unwrap();
+        assert_eq!(time.to_string(), "2024-02-29T13:45:00Z");
+        let time: Time = "2024-02-29T15:45:00.5+02:00".parse().unwrap();
+        assert_eq!(time.to_string(), "2024-02-29T13:45:00Z");
+    }
+
+    #[test]
+    fn rejects_non_ascii_input() {
+        for input in ["2024-02-29T13:45:0é", "2024-02-29T13:45:00+0é:00", "2024-02-29T13:45:00.5€", "é024-02-29T13:45:00Z"] {
+            assert!(input.parse::<Time>().is_err(), "{}", input);
+        }
+    }
 }